@@ -0,0 +1,152 @@
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::lsp::{LanguageFilter, Settings};
+
+/// Startup configuration read from a JSON file. Command-line options take
+/// precedence over every value set here.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Workspace directory, relative to the config file
+    pub workspace: Option<PathBuf>,
+    /// Language servers; the first is the primary one
+    pub servers: Vec<ServerConfig>,
+    /// Gitignore-style patterns the watcher ignores
+    pub ignore: Vec<String>,
+    /// Level of the proxy log, like `info` or `debug`
+    pub log_level: Option<log::LevelFilter>,
+    /// Settings answered to the servers' `workspace/configuration` requests
+    pub settings: Option<Settings>,
+    /// Language ids sent in didOpen, keyed by file extension
+    pub language_ids: BTreeMap<String, String>,
+    pub timeouts: TimeoutConfig,
+    pub tools: ToolDefaults,
+    /// Extra tools that each send one LSP request
+    pub custom_tools: Vec<CustomToolConfig>,
+}
+
+/// A language server and how it is started
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// The server's command; for a server connected to, the name it is known by
+    pub command: String,
+    /// Address of a running server to connect to instead of starting it, as
+    /// `host:port` or `unix:PATH`
+    pub connect: Option<String>,
+    pub args: Vec<String>,
+    /// Extra environment variables for the server process
+    pub env: BTreeMap<String, String>,
+    /// Directory the server runs in, relative to the config file
+    pub cwd: Option<PathBuf>,
+    /// Language ids or file extensions the server handles, replacing the
+    /// defaults for known servers
+    pub languages: Option<Vec<String>>,
+    /// Sent in the initialize request in place of the default options
+    pub initialization_options: Option<Value>,
+}
+
+/// Request and startup timeouts, in seconds
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeoutConfig {
+    /// How long to wait for an answer from the server (0 waits forever)
+    pub lsp: Option<u64>,
+    /// How long tool calls wait for the server to finish indexing
+    pub ready: Option<u64>,
+    /// Timeouts for single LSP methods, like `workspace/symbol`
+    pub methods: BTreeMap<String, u64>,
+}
+
+/// Defaults for the output of every tool
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolDefaults {
+    /// `markdown`, `plain` or `compact`
+    pub output_style: Option<String>,
+    pub max_response_tokens: Option<usize>,
+    pub chars_per_token: Option<f64>,
+    pub absolute_paths: Option<bool>,
+}
+
+/// A tool that sends one LSP request, typically a server-specific extension
+/// like `rust-analyzer/expandMacro`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CustomToolConfig {
+    pub name: String,
+    pub description: String,
+    /// The LSP request the tool sends
+    pub method: String,
+    /// Entry of the `experimental` server capabilities the tool needs, as a
+    /// dotted path; the tool is only offered once a server announces it
+    pub requires: Option<String>,
+    /// Parameters sent when the call gives none
+    pub params: Option<Value>,
+}
+
+impl ConfigFile {
+    /// Reads a config file; relative paths in it are taken from its directory
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let mut config: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+
+        if let Some(dir) = path.parent() {
+            config.workspace = config.workspace.map(|workspace| dir.join(workspace));
+            for server in &mut config.servers {
+                server.cwd = server.cwd.take().map(|cwd| dir.join(cwd));
+            }
+        }
+        for tool in &config.custom_tools {
+            if tool.name.trim().is_empty() || tool.method.trim().is_empty() {
+                return Err(anyhow!(
+                    "A custom tool in {} has no name or method",
+                    path.display()
+                ));
+            }
+        }
+        for server in &mut config.servers {
+            if server.command.trim().is_empty() {
+                // A server connected to may go by its address
+                server.command = server
+                    .connect
+                    .clone()
+                    .ok_or_else(|| anyhow!("A server in {} has no command", path.display()))?;
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+impl ServerConfig {
+    /// Describes a server started without extra options
+    pub fn new(command: &str, args: &[String]) -> Self {
+        Self {
+            command: command.to_string(),
+            args: args.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    /// Gets the environment variables as pairs for the server process
+    pub fn env_pairs(&self) -> Vec<(String, String)> {
+        self.env
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Gets the files the server handles, if the config lists them
+    pub fn language_filter(&self) -> Option<LanguageFilter> {
+        self.languages
+            .as_ref()
+            .map(|languages| LanguageFilter::new(languages))
+    }
+}
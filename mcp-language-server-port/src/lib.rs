@@ -1,5 +1,8 @@
+pub mod config;
 pub mod logging;
 pub mod lsp;
 pub mod mcp;
+pub mod session;
 pub mod tools;
-pub mod watcher;
\ No newline at end of file
+pub mod watcher;
+pub mod workspace;
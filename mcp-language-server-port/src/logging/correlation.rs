@@ -0,0 +1,41 @@
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Identifies one MCP tool call in log lines, LSP request logs and error
+/// responses, so a failing agent action can be traced through the logs
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct CorrelationId(String);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+tokio::task_local! {
+    /// Correlation id of the tool call being handled
+    static CURRENT: CorrelationId;
+}
+
+impl CorrelationId {
+    /// Creates a fresh id for a call to `tool`, e.g. `hover-12`
+    pub fn next(tool: &str) -> Self {
+        let number = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        Self(format!("{}-{}", tool, number))
+    }
+}
+
+impl Display for CorrelationId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Gets the correlation id of the tool call being handled, if any
+pub fn current() -> Option<CorrelationId> {
+    CURRENT.try_with(Clone::clone).ok()
+}
+
+/// Runs a tool call with its correlation id. The id is only visible to the
+/// task running `f`, not to tasks it spawns.
+pub async fn with_correlation_id<F: Future>(id: CorrelationId, f: F) -> F::Output {
+    CURRENT.scope(id, f).await
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Environment variable overriding how many records a call site may write per window
+pub const RATE_LIMIT_VAR: &str = "LOG_RATE_LIMIT";
+
+/// Environment variable overriding the rate limit window, in milliseconds
+pub const RATE_WINDOW_VAR: &str = "LOG_RATE_WINDOW_MS";
+
+/// Limits on how much a single call site may log
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloodConfig {
+    /// Collapse runs of identical records, writing only the 2nd, 4th, 16th, ... repeat
+    pub suppress_repeats: bool,
+    /// Records a call site may write per window; 0 disables the limit
+    pub max_per_callsite: u32,
+    /// Length of the rate limit window
+    pub window: Duration,
+}
+
+impl Default for FloodConfig {
+    fn default() -> Self {
+        Self {
+            suppress_repeats: true,
+            max_per_callsite: 50,
+            window: Duration::from_secs(1),
+        }
+    }
+}
+
+impl FloodConfig {
+    /// Reads overrides of the defaults from `LOG_RATE_LIMIT` and `LOG_RATE_WINDOW_MS`,
+    /// ignoring values that don't parse
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(limit) = env::var(RATE_LIMIT_VAR).ok().and_then(|v| v.parse().ok()) {
+            config.max_per_callsite = limit;
+        }
+        if let Some(millis) = env::var(RATE_WINDOW_VAR).ok().and_then(|v| v.parse().ok()) {
+            config.window = Duration::from_millis(millis);
+        }
+        config
+    }
+}
+
+/// What to do with a record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// Write the record
+    Write,
+    /// Write the record with how many times it has been repeated in a row
+    WriteRepeated(u64),
+    /// Drop the record
+    Drop,
+}
+
+/// The decision for a record, with how many records from its call site were
+/// dropped by the rate limit since it last wrote one. Those are reported in a
+/// summary line ahead of the record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Admission {
+    pub decision: Decision,
+    pub suppressed: u64,
+}
+
+/// Decides which log records are written so a watcher storm or retry loop
+/// can't flood the log.
+///
+/// Runs of identical records collapse into occasional repeat counts, and each
+/// call site may write a limited number of records per window. Records over
+/// the limit are dropped and summarized once the call site writes again.
+pub struct FloodControl {
+    config: FloodConfig,
+    state: Mutex<FloodState>,
+}
+
+struct FloodState {
+    last_record: Option<u64>,
+    // The occurrence of the last record being processed, and the next one to write
+    repeats: u64,
+    next_repeat_to_write: u64,
+    callsites: HashMap<u64, CallsiteWindow>,
+}
+
+struct CallsiteWindow {
+    start: Instant,
+    written: u32,
+    suppressed: u64,
+}
+
+impl FloodControl {
+    pub fn new(config: FloodConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(FloodState {
+                last_record: None,
+                repeats: 1,
+                next_repeat_to_write: 2,
+                callsites: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Decides whether to write a record, given a hash of its call site and a
+    /// hash of the record itself (call site, thread and message)
+    pub fn admit(&self, callsite: u64, record: u64, now: Instant) -> Admission {
+        let mut state = self.state.lock().unwrap();
+
+        let mut decision = Decision::Write;
+        if self.config.suppress_repeats {
+            if state.last_record == Some(record) {
+                state.repeats += 1;
+                if state.repeats == state.next_repeat_to_write {
+                    state.next_repeat_to_write = state.repeats * state.repeats;
+                    decision = Decision::WriteRepeated(state.repeats);
+                } else {
+                    decision = Decision::Drop;
+                }
+            } else {
+                state.last_record = Some(record);
+                state.repeats = 1;
+                state.next_repeat_to_write = 2;
+            }
+        }
+
+        // Collapsed repeats don't count against the call site's limit
+        if decision == Decision::Drop || self.config.max_per_callsite == 0 {
+            return Admission {
+                decision,
+                suppressed: 0,
+            };
+        }
+
+        let window = state
+            .callsites
+            .entry(callsite)
+            .or_insert_with(|| CallsiteWindow {
+                start: now,
+                written: 0,
+                suppressed: 0,
+            });
+
+        let mut suppressed = 0;
+        if now.duration_since(window.start) >= self.config.window {
+            suppressed = window.suppressed;
+            *window = CallsiteWindow {
+                start: now,
+                written: 0,
+                suppressed: 0,
+            };
+        }
+
+        if window.written < self.config.max_per_callsite {
+            window.written += 1;
+        } else {
+            window.suppressed += 1;
+            decision = Decision::Drop;
+        }
+
+        Admission {
+            decision,
+            suppressed,
+        }
+    }
+}
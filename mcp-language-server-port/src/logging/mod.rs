@@ -1,11 +1,10 @@
 use log::LevelFilter::Debug;
-use log::{Level, LevelFilter, Record, info, log_enabled};
+use log::{Level, LevelFilter, Record, info};
 use std::fmt::{Arguments, Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::io::Write;
 use std::process;
 use std::sync::Once;
-use std::sync::atomic::{AtomicU64, Ordering};
 
 use ansi_colors::ColouredStr;
 use chrono::{DateTime, Utc};
@@ -15,8 +14,15 @@ use nohash_hasher::NoHashHasher;
 use serde::Serialize;
 use smallstr::SmallString;
 use std::fmt::Write as FmtWrite;
-use std::time::SystemTime;
+use std::time::{Instant, SystemTime};
 
+pub mod correlation;
+pub mod flood;
+
+use self::correlation::CorrelationId;
+use self::flood::{Decision, FloodConfig, FloodControl};
+
+#[allow(dead_code)]
 pub fn debug() {
     CoreLogger::init();
 }
@@ -60,47 +66,53 @@ impl CoreLogger {
         CoreLogger::init_with_filter(Debug);
     }
     pub fn init_with_filter(level: LevelFilter) {
+        CoreLogger::init_with_flood_control(level, FloodConfig::from_env());
+    }
+    pub fn init_with_flood_control(level: LevelFilter, flood_config: FloodConfig) {
         START.call_once(|| {
             // Filtering here doesn't improve performance while filtering in the process.toml file does.
             let mut builder = env_logger::builder();
 
-            let last_log_hash = AtomicU64::new(0);
-            // this is one past the actual number of trace logs that have repeated,
-            // so when a repeat happens, we'll be processing the second occurrence
-            let repeat_count = AtomicU64::new(2);
-            let next_repeat_count_to_print_at = AtomicU64::new(2);
+            let flood_control = FloodControl::new(flood_config);
 
             builder
                 .filter_level(level)
                 .format(move |buf, record| {
                     let record = CoreLoggerRecord::from_record(record);
-                    if log_enabled!(Level::Trace) {
-                        let mut hasher = ahash::AHasher::default();
-                        record.module_path.hash(&mut hasher);
-                        record.file.hash(&mut hasher);
-                        record.line.hash(&mut hasher);
-                        record.tid.hash(&mut hasher);
-                        let mut repeat_message_check: SmallString<[u8; 256]> = SmallString::new();
-                        write!(&mut repeat_message_check, "{}", record.message)
-                            .expect("stringify log message");
-                        repeat_message_check.hash(&mut hasher);
-                        let log_hash = hasher.finish();
-                        let last_log_hash = last_log_hash.swap(log_hash, Ordering::AcqRel);
-                        if log_hash == last_log_hash {
-                            let reps = repeat_count.fetch_add(1, Ordering::AcqRel);
-                            if reps == next_repeat_count_to_print_at.load(Ordering::Acquire) {
-                                next_repeat_count_to_print_at.store(reps * reps, Ordering::Release);
-                                writeln!(buf, "{} ({})", record, reps)
-                            } else {
-                                Ok(())
+
+                    let mut hasher = ahash::AHasher::default();
+                    record.module_path.hash(&mut hasher);
+                    record.file.hash(&mut hasher);
+                    record.line.hash(&mut hasher);
+                    let callsite_hash = hasher.finish();
+
+                    record.tid.hash(&mut hasher);
+                    let mut repeat_message_check: SmallString<[u8; 256]> = SmallString::new();
+                    write!(&mut repeat_message_check, "{}", record.message)
+                        .expect("stringify log message");
+                    repeat_message_check.hash(&mut hasher);
+                    let log_hash = hasher.finish();
+
+                    let admission = flood_control.admit(callsite_hash, log_hash, Instant::now());
+                    if admission.suppressed > 0 {
+                        writeln!(
+                            buf,
+                            "{}",
+                            CoreLoggerRecord {
+                                level: Level::Warn,
+                                message: &format_args!(
+                                    "Suppressed {} messages from this call site",
+                                    admission.suppressed
+                                ),
+                                correlation_id: record.correlation_id.clone(),
+                                ..record
                             }
-                        } else {
-                            repeat_count.store(2, Ordering::Release);
-                            next_repeat_count_to_print_at.store(2, Ordering::Release);
-                            writeln!(buf, "{}", record)
-                        }
-                    } else {
-                        writeln!(buf, "{}", record)
+                        )?;
+                    }
+                    match admission.decision {
+                        Decision::Write => writeln!(buf, "{}", record),
+                        Decision::WriteRepeated(reps) => writeln!(buf, "{} ({})", record, reps),
+                        Decision::Drop => Ok(()),
                     }
                 })
                 .target(Target::Stdout);
@@ -130,6 +142,8 @@ pub struct CoreLoggerRecord<'a> {
     file: &'a str,
     line: u32,
     time: SystemTime,
+    // Set while handling an MCP tool call
+    correlation_id: Option<CorrelationId>,
     message: &'a Arguments<'a>,
 }
 
@@ -154,6 +168,7 @@ impl<'a> CoreLoggerRecord<'a> {
             file: record.file().unwrap_or("None"),
             line: record.line().unwrap_or(0),
             time: SystemTime::now(),
+            correlation_id: correlation::current(),
             message: record.args(),
         }
     }
@@ -218,8 +233,11 @@ impl Display for CoreLoggerRecord<'_> {
             "{level_str:5}  {time_stamp}  [{tid:0>3}]  [{filename}:{line:<4}]{padding}" // TODO "  "
         )?;
 
-        // Write message
+        // Write message, tagged with the tool call it belongs to
         let mut message: SmallString<[u8; 256]> = SmallString::new();
+        if let Some(correlation_id) = &self.correlation_id {
+            write!(&mut message, "[{}] ", correlation_id)?;
+        }
         write!(&mut message, "{}", self.message)?;
 
         let mut remaining_message = message.as_str();
@@ -1,43 +1,141 @@
 use anyhow::{Context, Result, anyhow};
-use log::{debug, error, info};
+use globset::Glob;
+use log::{debug, error, info, warn};
 use lsp_types::{
-    ClientCapabilities, CodeActionKind, InitializeParams, InitializeResult, InitializedParams,
-    TextDocumentIdentifier, TextDocumentItem, Url, VersionedTextDocumentIdentifier,
-    WorkspaceFolder,
+    ClientCapabilities, CodeActionKind, DocumentFilter, InitializeParams, InitializeResult,
+    InitializedParams, NumberOrString, PositionEncodingKind, ProgressToken, ServerCapabilities,
+    TextDocumentIdentifier, TextDocumentItem, TextDocumentSyncCapability, TextDocumentSyncKind,
+    Url, VersionedTextDocumentIdentifier, WorkDoneProgress, WorkspaceFolder,
 };
 use serde::{Serialize, de::DeserializeOwned};
-use serde_json::{Value, json};
+use serde_json::Value;
 use std::{
     collections::HashMap,
-    path::Path,
+    path::{Path, PathBuf},
     process::{Child, Command, Stdio},
     sync::{
-        Arc, RwLock,
-        atomic::{AtomicI32, Ordering},
+        Arc, Mutex, RwLock,
+        atomic::{AtomicI32, AtomicU64, AtomicUsize, Ordering},
     },
+    time::{Duration, Instant},
 };
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt, BufReader as TokioBufReader, BufWriter as TokioBufWriter},
-    sync::{mpsc, oneshot},
+    io::{
+        AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader as TokioBufReader,
+        BufWriter as TokioBufWriter,
+    },
+    sync::{Notify, broadcast, mpsc, oneshot, watch},
 };
 
 use super::{
+    events::{EVENT_CHANNEL_CAPACITY, ProxyEvent},
+    position::PositionEncoding,
+    presets,
     protocol::{Message, MessageID},
+    retry::{self, RetryPolicy},
+    settings::Settings,
+    sync,
+    timeout::{RequestTimeout, TimeoutPolicy},
     transport::write_message,
+    versions::Workaround,
 };
+use crate::logging::correlation::{self, CorrelationId};
+use crate::tools::workspace_edit;
+use crate::workspace::paths::normalize_path;
 
 // Use Url as DocumentUri for compatibility with lsp-types
 type DocumentUri = Url;
 
+/// How long to wait for the server to answer the shutdown request
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Error returned to requests that are still pending when the message loop exits
+const SHUTTING_DOWN: &str = "LSP server shutting down";
+
+/// Semantic token types announced to servers; servers name their own in a legend
+const SEMANTIC_TOKEN_TYPES: &[&str] = &[
+    "namespace",
+    "type",
+    "class",
+    "enum",
+    "interface",
+    "struct",
+    "typeParameter",
+    "parameter",
+    "variable",
+    "property",
+    "enumMember",
+    "event",
+    "function",
+    "method",
+    "macro",
+    "keyword",
+    "modifier",
+    "comment",
+    "string",
+    "number",
+    "regexp",
+    "operator",
+    "decorator",
+];
+
+/// Semantic token modifiers announced to servers
+const SEMANTIC_TOKEN_MODIFIERS: &[&str] = &[
+    "declaration",
+    "definition",
+    "readonly",
+    "static",
+    "deprecated",
+    "abstract",
+    "async",
+    "modification",
+    "documentation",
+    "defaultLibrary",
+];
+
 // Type aliases for handler functions
 type NotificationHandler = Box<dyn Fn(Value) -> Result<()> + Send + Sync>;
 type RequestHandler = Box<dyn Fn(Value) -> Result<Value> + Send + Sync>;
+type AsyncRequestHandler = Box<
+    dyn Fn(Value) -> std::pin::Pin<Box<dyn Future<Output = Result<Value>> + Send>> + Send + Sync,
+>;
 
 /// Represents an open file managed by the LSP server
 #[derive(Debug, Clone)]
 struct OpenFileInfo {
     version: i32,
-    _uri: DocumentUri,
+    uri: DocumentUri,
+    // True when the server holds in-memory content that differs from disk
+    dirty: bool,
+    // Content the server last saw, to send only what changed
+    text: String,
+}
+
+/// Diagnostics the server last published for a document
+#[derive(Debug, Clone)]
+struct DocumentDiagnostics {
+    // Document version the diagnostics were computed for, if the server said
+    version: Option<i32>,
+    diagnostics: Vec<lsp_types::Diagnostic>,
+    // Increases with every publication, so waiters can tell a new one arrived
+    publication: u64,
+}
+
+/// Pull diagnostics options a server announced
+#[derive(Debug, Clone, PartialEq)]
+pub struct PullDiagnostics {
+    /// Identifier the server asked to have sent along with pull requests
+    pub identifier: Option<String>,
+    /// Whether the server also reports diagnostics for the whole workspace
+    pub workspace: bool,
+}
+
+/// Snapshot of a document the LSP server currently has open
+#[derive(Debug, Clone)]
+pub struct OpenDocument {
+    pub uri: DocumentUri,
+    pub version: i32,
+    pub dirty: bool,
 }
 
 #[derive(Debug)]
@@ -47,86 +145,474 @@ enum ClientMessage {
         method: String,
         params: Value,
         response_tx: oneshot::Sender<Result<Value>>,
+        // Tool call the request was made for, to tag its transport logs
+        correlation_id: Option<CorrelationId>,
     },
     Notification {
         method: String,
         params: Value,
     },
+    /// The answer to a request the server made, once an async handler has it
+    Response {
+        id: MessageID,
+        result: Result<Value>,
+    },
     Shutdown,
 }
 
+/// Progress of the LSP initialize handshake
+#[derive(Debug, Clone, PartialEq)]
+pub enum InitState {
+    Starting,
+    Ready,
+    Failed(String),
+    /// Shut down while idle; restarted by the next tool call
+    Stopped,
+}
+
 /// Client for interacting with an LSP server
 pub struct Client {
-    // Child process management
-    _child: Child,
+    // Child process management; replaced when the server is restarted. There
+    // is no child for servers the client connects to over a socket.
+    child: Mutex<Option<Child>>,
+    // When the current process was started or the socket connected
+    started_at: Mutex<Instant>,
+    name: String,
+    command: String,
+    args: Vec<String>,
+    process_options: ProcessOptions,
+    address: Option<ServerAddress>,
 
     // Message routing
     next_id: AtomicI32,
-    message_tx: mpsc::Sender<ClientMessage>,
+    next_progress_token: AtomicI32,
+    pending_requests: AtomicUsize,
+    message_tx: RwLock<mpsc::Sender<ClientMessage>>,
 
     // State tracking
     open_files: RwLock<HashMap<String, OpenFileInfo>>,
-    diagnostics: RwLock<HashMap<DocumentUri, Vec<lsp_types::Diagnostic>>>,
+    diagnostics: RwLock<HashMap<DocumentUri, DocumentDiagnostics>>,
+    // Result ids of pulled diagnostic reports, sent back so unchanged reports can be skipped
+    diagnostic_result_ids: RwLock<HashMap<DocumentUri, String>>,
+    diagnostics_published: Notify,
+    publications: AtomicU64,
+    partial_results: RwLock<HashMap<String, Vec<Value>>>,
+    server_capabilities: RwLock<Option<ServerCapabilities>>,
+    server_info: RwLock<Option<lsp_types::ServerInfo>>,
+    language_overrides: RwLock<HashMap<String, String>>,
+    trace: RwLock<lsp_types::TraceValue>,
+    initialization_options: RwLock<Option<Value>>,
+    workarounds: RwLock<Vec<Workaround>>,
+    retry_policy: RwLock<RetryPolicy>,
+    timeout_policy: RwLock<TimeoutPolicy>,
+    settings: RwLock<Settings>,
+    workspace_root: RwLock<Option<PathBuf>>,
+    // Folders added to the workspace after the root, sent again on restart
+    workspace_folders: RwLock<Vec<PathBuf>>,
+    registrations: RwLock<HashMap<String, lsp_types::Registration>>,
+    // The last request that failed, and when
+    last_error: RwLock<Option<(Instant, String)>>,
+    init_state: watch::Sender<InitState>,
+    // Titles of work done progress the server has begun and not yet ended, by token
+    work_done: watch::Sender<HashMap<String, String>>,
+
+    // Reports of the edits applied for workspace/applyEdit, for the tool that caused them
+    applied_edits: Mutex<Vec<String>>,
+
+    // didChange notifications that didn't fit in the message channel, newest per document
+    pending_changes: Mutex<HashMap<String, (i32, Value)>>,
+    changes_ready: Notify,
+    coalesced_changes: AtomicU64,
+
+    // Typed events for applications embedding the proxy
+    events: broadcast::Sender<ProxyEvent>,
+
+    // Secondary servers (e.g. linters) that mirror this server's documents
+    attached: RwLock<Vec<Arc<Client>>>,
 
     // Handlers for server requests and notifications
     notification_handlers: RwLock<HashMap<String, NotificationHandler>>,
     request_handlers: RwLock<HashMap<String, RequestHandler>>,
+    async_request_handlers: RwLock<HashMap<String, AsyncRequestHandler>>,
+}
+
+/// The pipes or socket to a running language server, and its process if
+/// the client started it
+struct ServerConnection {
+    child: Option<Child>,
+    writer: TokioBufWriter<Box<dyn AsyncWrite + Send + Unpin>>,
+    reader: TokioBufReader<Box<dyn AsyncRead + Send + Unpin>>,
+    stderr: Option<tokio::process::ChildStderr>,
+}
+
+/// Address of a language server that was started separately
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerAddress {
+    /// A TCP `host:port`
+    Tcp(String),
+    /// A Unix domain socket
+    Unix(PathBuf),
+}
+
+impl ServerAddress {
+    /// Parses `host:port`, or a socket path given as `unix:PATH` or as an
+    /// absolute path
+    pub fn parse(value: &str) -> Result<Self> {
+        if let Some(path) = value.strip_prefix("unix:") {
+            return Ok(Self::Unix(PathBuf::from(path)));
+        }
+        if value.starts_with('/') {
+            return Ok(Self::Unix(PathBuf::from(value)));
+        }
+        match value.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {
+                Ok(Self::Tcp(value.to_string()))
+            }
+            _ => Err(anyhow!(
+                "Invalid server address '{}': expected HOST:PORT or unix:PATH",
+                value
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ServerAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp(address) => write!(f, "{}", address),
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Environment and working directory of a language server process
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessOptions {
+    /// Variables set on top of the proxy's environment
+    pub env: Vec<(String, String)>,
+    /// Directory the server runs in instead of the proxy's
+    pub cwd: Option<PathBuf>,
+}
+
+/// Starts a language server process with piped stdio
+fn spawn_server(
+    command: &str,
+    args: &[String],
+    options: &ProcessOptions,
+) -> Result<ServerConnection> {
+    let mut server = Command::new(command);
+    server
+        .args(args)
+        .envs(options.env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(cwd) = &options.cwd {
+        server.current_dir(cwd);
+    }
+    let mut child = server
+        .spawn()
+        .context(format!("Failed to start LSP server: {}", command))?;
+
+    // Get pipes to the child process
+    let stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("Failed to open stdin pipe"))?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow!("Failed to open stdout pipe"))?;
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| anyhow!("Failed to open stderr pipe"))?;
+
+    // Convert to async IO
+    let stdin =
+        tokio::process::ChildStdin::from_std(stdin).context("Failed to convert stdin to async")?;
+    let stdout = tokio::process::ChildStdout::from_std(stdout)
+        .context("Failed to convert stdout to async")?;
+    let stderr = tokio::process::ChildStderr::from_std(stderr)
+        .context("Failed to convert stderr to async")?;
+
+    // Create buffered readers and writers
+    Ok(ServerConnection {
+        child: Some(child),
+        writer: TokioBufWriter::new(Box::new(stdin)),
+        reader: TokioBufReader::new(Box::new(stdout)),
+        stderr: Some(stderr),
+    })
+}
+
+/// Connects to a language server listening on a socket
+async fn connect_server(address: &ServerAddress) -> Result<ServerConnection> {
+    let (reader, writer): (
+        Box<dyn AsyncRead + Send + Unpin>,
+        Box<dyn AsyncWrite + Send + Unpin>,
+    ) = match address {
+        ServerAddress::Tcp(host) => {
+            let stream = tokio::net::TcpStream::connect(host)
+                .await
+                .context(format!("Failed to connect to LSP server at {}", address))?;
+            let (reader, writer) = stream.into_split();
+            (Box::new(reader), Box::new(writer))
+        }
+        #[cfg(unix)]
+        ServerAddress::Unix(path) => {
+            let stream = tokio::net::UnixStream::connect(path)
+                .await
+                .context(format!("Failed to connect to LSP server at {}", address))?;
+            let (reader, writer) = stream.into_split();
+            (Box::new(reader), Box::new(writer))
+        }
+        #[cfg(not(unix))]
+        ServerAddress::Unix(_) => {
+            return Err(anyhow!(
+                "Unix domain sockets are not supported on this platform"
+            ));
+        }
+    };
+
+    Ok(ServerConnection {
+        child: None,
+        writer: TokioBufWriter::new(writer),
+        reader: TokioBufReader::new(reader),
+        stderr: None,
+    })
 }
 
 impl Client {
     /// Creates a new LSP client and starts the LSP server process
     pub async fn new(command: &str, args: &[String]) -> Result<Arc<Self>> {
-        let mut child = Command::new(command)
-            .args(args)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .context(format!("Failed to start LSP server: {}", command))?;
-
-        // Get pipes to the child process
-        let stdin = child
-            .stdin
-            .take()
-            .ok_or_else(|| anyhow!("Failed to open stdin pipe"))?;
-        let stdout = child
-            .stdout
-            .take()
-            .ok_or_else(|| anyhow!("Failed to open stdout pipe"))?;
-        let stderr = child
-            .stderr
-            .take()
-            .ok_or_else(|| anyhow!("Failed to open stderr pipe"))?;
-
-        // Convert to async IO
-        let stdin = tokio::process::ChildStdin::from_std(stdin)
-            .context("Failed to convert stdin to async")?;
-        let stdout = tokio::process::ChildStdout::from_std(stdout)
-            .context("Failed to convert stdout to async")?;
-        let stderr = tokio::process::ChildStderr::from_std(stderr)
-            .context("Failed to convert stderr to async")?;
-
-        // Create buffered readers and writers
-        let stdin_writer = TokioBufWriter::new(stdin);
-        let stdout_reader = TokioBufReader::new(stdout);
+        Self::with_options(command, args, &ProcessOptions::default()).await
+    }
 
+    /// Creates a new LSP client whose server process gets extra environment
+    /// variables or another working directory, also when it is restarted
+    pub async fn with_options(
+        command: &str,
+        args: &[String],
+        options: &ProcessOptions,
+    ) -> Result<Arc<Self>> {
+        let connection = spawn_server(command, args, options)?;
+        Ok(Self::from_connection(
+            command, args, options, None, connection,
+        ))
+    }
+
+    /// Creates a new LSP client for a server that is already running and
+    /// listens on `address`. `name` is the server's command, used to pick
+    /// presets such as its initialization options.
+    pub async fn connect(name: &str, address: &ServerAddress) -> Result<Arc<Self>> {
+        let connection = connect_server(address).await?;
+        info!("[LSP] Connected to {} at {}", name, address);
+        Ok(Self::from_connection(
+            name,
+            &[],
+            &ProcessOptions::default(),
+            Some(address.clone()),
+            connection,
+        ))
+    }
+
+    /// Creates the client for a server it has a connection to
+    fn from_connection(
+        command: &str,
+        args: &[String],
+        options: &ProcessOptions,
+        address: Option<ServerAddress>,
+        connection: ServerConnection,
+    ) -> Arc<Self> {
         // Create message channel
         let (tx, rx) = mpsc::channel::<ClientMessage>(100);
-        let (msg_tx, msg_rx) = mpsc::channel::<Message>(100);
 
         // Create the client instance
         let client = Arc::new(Self {
-            _child: child,
+            child: Mutex::new(connection.child),
+            started_at: Mutex::new(Instant::now()),
+            name: Path::new(command)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| command.to_string()),
+            command: command.to_string(),
+            args: args.to_vec(),
+            process_options: options.clone(),
+            address,
             next_id: AtomicI32::new(1),
-            message_tx: tx,
+            next_progress_token: AtomicI32::new(1),
+            pending_requests: AtomicUsize::new(0),
+            message_tx: RwLock::new(tx),
             open_files: RwLock::new(HashMap::new()),
             diagnostics: RwLock::new(HashMap::new()),
+            diagnostic_result_ids: RwLock::new(HashMap::new()),
+            diagnostics_published: Notify::new(),
+            publications: AtomicU64::new(0),
+            partial_results: RwLock::new(HashMap::new()),
+            server_capabilities: RwLock::new(None),
+            server_info: RwLock::new(None),
+            language_overrides: RwLock::new(HashMap::new()),
+            trace: RwLock::new(lsp_types::TraceValue::Off),
+            initialization_options: RwLock::new(presets::initialization_options(command)),
+            workarounds: RwLock::new(Vec::new()),
+            retry_policy: RwLock::new(RetryPolicy::default()),
+            timeout_policy: RwLock::new(TimeoutPolicy::default()),
+            settings: RwLock::new(Settings::default()),
+            workspace_root: RwLock::new(None),
+            workspace_folders: RwLock::new(Vec::new()),
+            registrations: RwLock::new(HashMap::new()),
+            last_error: RwLock::new(None),
+            init_state: watch::Sender::new(InitState::Starting),
+            work_done: watch::Sender::new(HashMap::new()),
+            applied_edits: Mutex::new(Vec::new()),
+            pending_changes: Mutex::new(HashMap::new()),
+            changes_ready: Notify::new(),
+            coalesced_changes: AtomicU64::new(0),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            attached: RwLock::new(Vec::new()),
             notification_handlers: RwLock::new(HashMap::new()),
             request_handlers: RwLock::new(HashMap::new()),
+            async_request_handlers: RwLock::new(HashMap::new()),
+        });
+
+        Client::start_io(
+            &client,
+            rx,
+            connection.writer,
+            connection.reader,
+            connection.stderr,
+        );
+
+        // Register default notification handlers
+        let client_ref = Arc::clone(&client);
+        let diagnostics_client = Arc::clone(&client);
+        client_ref.register_notification_handler(
+            "textDocument/publishDiagnostics",
+            move |params| {
+                diagnostics_client.store_diagnostics(serde_json::from_value(params)?);
+                Ok(())
+            },
+        );
+
+        let progress_client = Arc::clone(&client);
+        client_ref.register_notification_handler("$/progress", move |params| {
+            progress_client.handle_progress(params)
+        });
+
+        // Forward server traces requested via --lsp-trace into the proxy log
+        client_ref.register_notification_handler("$/logTrace", move |params| {
+            let params: lsp_types::LogTraceParams = serde_json::from_value(params)?;
+            match params.verbose {
+                Some(verbose) => info!("[LSP] Trace: {}\n{}", params.message, verbose),
+                None => info!("[LSP] Trace: {}", params.message),
+            }
+            Ok(())
+        });
+
+        // Servers create tokens before reporting progress they started themselves
+        client_ref.register_request_handler("window/workDoneProgress/create", |_| Ok(Value::Null));
+
+        // Answer configuration requests from the settings store
+        let configuration_client = Arc::clone(&client);
+        client_ref.register_request_handler("workspace/configuration", move |params| {
+            let params: lsp_types::ConfigurationParams = serde_json::from_value(params)?;
+            Ok(Value::Array(
+                configuration_client.configuration(&params.items),
+            ))
+        });
+
+        // Track capabilities the server registers dynamically
+        let registration_client = Arc::clone(&client);
+        client_ref.register_request_handler("client/registerCapability", move |params| {
+            let params: lsp_types::RegistrationParams = serde_json::from_value(params)?;
+            let mut registrations = registration_client.registrations.write().unwrap();
+            for registration in params.registrations {
+                debug!(
+                    "[LSP] Server registered capability: {}",
+                    registration.method
+                );
+                registrations.insert(registration.id.clone(), registration);
+            }
+            Ok(Value::Null)
+        });
+
+        let unregistration_client = Arc::clone(&client);
+        client_ref.register_request_handler("client/unregisterCapability", move |params| {
+            let params: lsp_types::UnregistrationParams = serde_json::from_value(params)?;
+            let mut registrations = unregistration_client.registrations.write().unwrap();
+            for unregistration in params.unregisterations {
+                debug!(
+                    "[LSP] Server unregistered capability: {}",
+                    unregistration.method
+                );
+                registrations.remove(&unregistration.id);
+            }
+            Ok(Value::Null)
+        });
+
+        // Apply edits the server asks for, such as those of a command being
+        // executed, and only report them applied once they're written. The
+        // tool that ran the command picks up the reports.
+        let edit_client = Arc::downgrade(&client);
+        client_ref.register_async_request_handler("workspace/applyEdit", move |params| {
+            let edit_client = edit_client.clone();
+            async move {
+                let params: lsp_types::ApplyWorkspaceEditParams = serde_json::from_value(params)?;
+                debug!(
+                    "[LSP] Server requested edit: {}",
+                    params.label.as_deref().unwrap_or("(unlabeled)")
+                );
+                let client = edit_client
+                    .upgrade()
+                    .ok_or_else(|| anyhow!(SHUTTING_DOWN))?;
+                let response = match workspace_edit::apply(
+                    &client,
+                    params.edit,
+                    &workspace_edit::ApplyOptions::default(),
+                )
+                .await
+                {
+                    Ok(report) => {
+                        client
+                            .applied_edits
+                            .lock()
+                            .unwrap()
+                            .push(report.to_string());
+                        lsp_types::ApplyWorkspaceEditResponse {
+                            applied: true,
+                            failure_reason: None,
+                            failed_change: None,
+                        }
+                    }
+                    Err(e) => {
+                        warn!("[LSP] Failed to apply edit requested by server: {}", e);
+                        lsp_types::ApplyWorkspaceEditResponse {
+                            applied: false,
+                            failure_reason: Some(e.to_string()),
+                            failed_change: None,
+                        }
+                    }
+                };
+                Ok(serde_json::to_value(response)?)
+            }
         });
 
-        // Handle stderr in a separate task
+        client
+    }
+
+    /// Starts the tasks that move messages between the client and a server
+    fn start_io(
+        client: &Arc<Self>,
+        rx: mpsc::Receiver<ClientMessage>,
+        stdin_writer: TokioBufWriter<Box<dyn AsyncWrite + Send + Unpin>>,
+        stdout_reader: TokioBufReader<Box<dyn AsyncRead + Send + Unpin>>,
+        stderr: Option<tokio::process::ChildStderr>,
+    ) {
+        // Handle stderr of a server process in a separate task
         tokio::spawn(async move {
+            let Some(stderr) = stderr else {
+                return;
+            };
             let mut reader = tokio::io::BufReader::new(stderr);
             let mut buffer = Vec::new();
             let mut line = [0u8; 1024];
@@ -159,14 +645,14 @@ impl Client {
         });
 
         // Spawn a task to handle reading messages from the LSP server
-        let msg_tx_clone = msg_tx.clone();
+        let (msg_tx, msg_rx) = mpsc::channel::<Message>(100);
         tokio::spawn(async move {
             let mut reader = stdout_reader;
 
             loop {
                 match super::transport::read_message(&mut reader).await {
                     Ok(msg) => {
-                        if let Err(e) = msg_tx_clone.send(msg).await {
+                        if let Err(e) = msg_tx.send(msg).await {
                             error!("[TRANSPORT] Failed to forward server message: {}", e);
                             break;
                         }
@@ -182,49 +668,177 @@ impl Client {
         });
 
         // Spawn a task to handle the message loop
-        let client_ref = Arc::clone(&client);
+        let client_ref = Arc::clone(client);
         tokio::spawn(async move {
             if let Err(e) = Client::message_loop(client_ref, rx, msg_rx, stdin_writer).await {
                 error!("[LSP] Message loop error: {}", e);
             }
         });
+    }
 
-        // Register default notification handlers
-        let client_ref = Arc::clone(&client);
-        let diagnostics_client = Arc::clone(&client);
-        client_ref.register_notification_handler(
-            "textDocument/publishDiagnostics",
-            move |params| {
-                let diagnostics_params: lsp_types::PublishDiagnosticsParams =
-                    serde_json::from_value(params)?;
-                let mut diagnostics = diagnostics_client.diagnostics.write().unwrap();
-                diagnostics.insert(diagnostics_params.uri, diagnostics_params.diagnostics);
-                Ok(())
+    /// Initializes the LSP client with the given workspace directory
+    pub async fn initialize(&self, workspace_dir: &Path) -> Result<InitializeResult> {
+        // Attached servers are brought up alongside this one
+        let attached = self.attached_servers();
+        let results = futures::future::join_all(
+            attached
+                .iter()
+                .map(|server| Box::pin(server.initialize(workspace_dir))),
+        )
+        .await;
+        for (server, result) in attached.iter().zip(results) {
+            if let Err(e) = result {
+                error!("[LSP] Failed to initialize {}: {}", server.name(), e);
+            }
+        }
+
+        let result = self.send_initialize(workspace_dir).await;
+
+        self.init_state.send_replace(match &result {
+            Ok(_) => InitState::Ready,
+            Err(e) => InitState::Failed(e.to_string()),
+        });
+        self.emit(match &result {
+            Ok(_) => ProxyEvent::ServerInitialized {
+                server: self.name.clone(),
             },
-        );
+            Err(e) => ProxyEvent::ServerFailed {
+                server: self.name.clone(),
+                error: e.to_string(),
+            },
+        });
 
-        Ok(client)
+        result
     }
 
-    /// Initializes the LSP client with the given workspace directory
-    pub async fn initialize(&self, workspace_dir: &Path) -> Result<InitializeResult> {
+    /// Waits until the initialize handshake has finished, failing if it did not succeed
+    pub async fn wait_for_initialization(&self) -> Result<()> {
+        let mut state = self.init_state.subscribe();
+
+        if *state.borrow() == InitState::Starting {
+            info!("[LSP] Waiting for LSP server initialization");
+        }
+
+        let state = state
+            .wait_for(|s| *s != InitState::Starting)
+            .await
+            .map_err(|_| anyhow!("LSP client was dropped during initialization"))?;
+
+        match &*state {
+            InitState::Failed(e) => Err(anyhow!("LSP server failed to initialize: {}", e)),
+            InitState::Stopped => Err(anyhow!("LSP server is stopped")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Waits for the server to initialize and finish the work it reports, such
+    /// as indexing. Returns whether that work finished within the timeout;
+    /// queries issued before then may come back empty.
+    pub async fn wait_until_ready(&self, timeout: Duration) -> Result<bool> {
+        self.wait_for_initialization().await?;
+        Ok(self.wait_for_work_done(timeout).await)
+    }
+
+    /// Waits until no work done progress is in flight, or the timeout passes.
+    /// Returns whether the server went quiet.
+    pub async fn wait_for_work_done(&self, timeout: Duration) -> bool {
+        let pending = self.pending_work();
+        if !pending.is_empty() {
+            info!(
+                "[LSP] Waiting for {} to finish: {}",
+                self.name,
+                pending.join(", ")
+            );
+        }
+
+        let mut work_done = self.work_done.subscribe();
+
+        // The sender lives as long as the client, so this only ends by finishing or timing out
+        tokio::time::timeout(timeout, work_done.wait_for(HashMap::is_empty))
+            .await
+            .is_ok_and(|result| result.is_ok())
+    }
+
+    /// Gets the titles of work done progress the server has begun and not yet ended
+    pub fn pending_work(&self) -> Vec<String> {
+        let mut titles: Vec<String> = self.work_done.borrow().values().cloned().collect();
+        titles.sort();
+        titles
+    }
+
+    /// Gets the process id of the server the client started, if it is running
+    pub fn pid(&self) -> Option<u32> {
+        let mut child = self.child.lock().unwrap();
+        let child = child.as_mut()?;
+        matches!(child.try_wait(), Ok(None)).then(|| child.id())
+    }
+
+    /// Gets how long the server has been running since it was last started
+    pub fn uptime(&self) -> Duration {
+        self.started_at.lock().unwrap().elapsed()
+    }
+
+    /// Gets the number of requests waiting for an answer from the server
+    pub fn pending_requests(&self) -> usize {
+        self.pending_requests.load(Ordering::Relaxed)
+    }
+
+    /// Gets the last request that failed and how long ago
+    pub fn last_error(&self) -> Option<(Duration, String)> {
+        let last_error = self.last_error.read().unwrap();
+        let (at, message) = last_error.as_ref()?;
+        Some((at.elapsed(), message.clone()))
+    }
+
+    /// Gets the number of diagnostics cached for all documents
+    pub fn diagnostic_count(&self) -> usize {
+        let diagnostics = self.diagnostics.read().unwrap();
+        diagnostics.values().map(|d| d.diagnostics.len()).sum()
+    }
+
+    /// Gets the address of a server the client connected to
+    pub fn address(&self) -> Option<&ServerAddress> {
+        self.address.as_ref()
+    }
+
+    /// Gets the current state of the initialize handshake
+    pub fn init_state(&self) -> InitState {
+        self.init_state.borrow().clone()
+    }
+
+    /// Sends the initialize request and initialized notification
+    async fn send_initialize(&self, workspace_dir: &Path) -> Result<InitializeResult> {
+        // Tools normalize the paths they work with, so the root and the URIs
+        // the server sees must be normalized the same way
+        let workspace_dir =
+            &normalize_path(workspace_dir).unwrap_or_else(|_| workspace_dir.to_path_buf());
+        *self.workspace_root.write().unwrap() = Some(workspace_dir.clone());
+
         let params = InitializeParams {
             process_id: Some(std::process::id()),
             root_uri: Some(to_uri(workspace_dir)),
-            initialization_options: Some(json!({
-                "codelenses": {
-                    "generate": true,
-                    "regenerate_cgo": true,
-                    "test": true,
-                    "tidy": true,
-                    "upgrade_dependency": true,
-                    "vendor": true,
-                    "vulncheck": false,
-                }
-            })),
+            initialization_options: self.initialization_options.read().unwrap().clone(),
 
             capabilities: ClientCapabilities {
+                // UTF-8 first, so servers that can count bytes do
+                general: Some(lsp_types::GeneralClientCapabilities {
+                    position_encodings: Some(vec![
+                        PositionEncodingKind::UTF8,
+                        PositionEncodingKind::UTF16,
+                    ]),
+                    ..Default::default()
+                }),
                 workspace: Some(lsp_types::WorkspaceClientCapabilities {
+                    apply_edit: Some(true),
+                    workspace_edit: Some(lsp_types::WorkspaceEditClientCapabilities {
+                        document_changes: Some(true),
+                        resource_operations: Some(vec![
+                            lsp_types::ResourceOperationKind::Create,
+                            lsp_types::ResourceOperationKind::Rename,
+                            lsp_types::ResourceOperationKind::Delete,
+                        ]),
+                        ..Default::default()
+                    }),
                     configuration: Some(true),
                     did_change_configuration: Some(
                         lsp_types::DidChangeConfigurationClientCapabilities {
@@ -238,6 +852,18 @@ impl Client {
                         },
                     ),
                     workspace_folders: Some(true),
+                    file_operations: Some(lsp_types::WorkspaceFileOperationsClientCapabilities {
+                        will_create: Some(true),
+                        did_create: Some(true),
+                        did_rename: Some(true),
+                        did_delete: Some(true),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                // Lets servers report indexing, which tool calls wait for
+                window: Some(lsp_types::WindowClientCapabilities {
+                    work_done_progress: Some(true),
                     ..Default::default()
                 }),
                 text_document: Some(lsp_types::TextDocumentClientCapabilities {
@@ -246,6 +872,10 @@ impl Client {
                         did_save: Some(true),
                         ..Default::default()
                     }),
+                    diagnostic: Some(lsp_types::DiagnosticClientCapabilities {
+                        dynamic_registration: Some(true),
+                        related_document_support: Some(false),
+                    }),
                     completion: Some(lsp_types::CompletionClientCapabilities {
                         dynamic_registration: Some(true),
                         completion_item: Some(lsp_types::CompletionItemCapability {
@@ -258,6 +888,53 @@ impl Client {
                         dynamic_registration: Some(true),
                         ..Default::default()
                     }),
+                    // Links carry the whole definition as well as the name
+                    definition: Some(lsp_types::GotoCapability {
+                        dynamic_registration: Some(true),
+                        link_support: Some(true),
+                    }),
+                    call_hierarchy: Some(lsp_types::CallHierarchyClientCapabilities {
+                        dynamic_registration: Some(true),
+                    }),
+                    type_hierarchy: Some(lsp_types::TypeHierarchyClientCapabilities {
+                        dynamic_registration: Some(true),
+                    }),
+                    document_symbol: Some(lsp_types::DocumentSymbolClientCapabilities {
+                        hierarchical_document_symbol_support: Some(
+                            !self.has_workaround(Workaround::FlatDocumentSymbols),
+                        ),
+                        ..Default::default()
+                    }),
+                    document_highlight: Some(lsp_types::DocumentHighlightClientCapabilities {
+                        dynamic_registration: Some(true),
+                    }),
+                    folding_range: Some(lsp_types::FoldingRangeClientCapabilities {
+                        dynamic_registration: Some(true),
+                        ..Default::default()
+                    }),
+                    selection_range: Some(lsp_types::SelectionRangeClientCapabilities {
+                        dynamic_registration: Some(true),
+                    }),
+                    semantic_tokens: Some(lsp_types::SemanticTokensClientCapabilities {
+                        dynamic_registration: Some(true),
+                        requests: lsp_types::SemanticTokensClientCapabilitiesRequests {
+                            range: Some(true),
+                            full: Some(lsp_types::SemanticTokensFullOptions::Bool(true)),
+                        },
+                        token_types: SEMANTIC_TOKEN_TYPES
+                            .iter()
+                            .map(|name| lsp_types::SemanticTokenType::new(name))
+                            .collect(),
+                        token_modifiers: SEMANTIC_TOKEN_MODIFIERS
+                            .iter()
+                            .map(|name| lsp_types::SemanticTokenModifier::new(name))
+                            .collect(),
+                        formats: vec![lsp_types::TokenFormat::RELATIVE],
+                        ..Default::default()
+                    }),
+                    code_lens: Some(lsp_types::CodeLensClientCapabilities {
+                        dynamic_registration: Some(true),
+                    }),
                     code_action: Some(lsp_types::CodeActionClientCapabilities {
                         dynamic_registration: Some(true),
                         code_action_literal_support: Some(lsp_types::CodeActionLiteralSupport {
@@ -269,20 +946,26 @@ impl Client {
                                 ],
                             },
                         }),
+                        is_preferred_support: Some(true),
+                        disabled_support: Some(true),
+                        data_support: Some(true),
+                        // Edits are computed lazily by some servers and fetched with codeAction/resolve
+                        resolve_support: Some(lsp_types::CodeActionCapabilityResolveSupport {
+                            properties: vec!["edit".to_string()],
+                        }),
                         ..Default::default()
                     }),
                     ..Default::default()
                 }),
                 ..Default::default()
             },
-            trace: Some(lsp_types::TraceValue::Off),
-            workspace_folders: Some(vec![WorkspaceFolder {
-                uri: to_uri(workspace_dir),
-                name: workspace_dir
-                    .file_name()
-                    .map(|name| name.to_string_lossy().to_string())
-                    .unwrap_or_else(|| "workspace".to_string()),
-            }]),
+            trace: Some(*self.trace.read().unwrap()),
+            workspace_folders: Some(
+                std::iter::once(workspace_dir.to_path_buf())
+                    .chain(self.workspace_folders.read().unwrap().iter().cloned())
+                    .map(|folder| workspace_folder(&folder))
+                    .collect(),
+            ),
             client_info: Some(lsp_types::ClientInfo {
                 name: "mcp-language-server-rust".to_string(),
                 version: Some(env!("CARGO_PKG_VERSION").to_string()),
@@ -292,6 +975,16 @@ impl Client {
 
         let result: InitializeResult = self.call("initialize", params).await?;
 
+        if let Some(experimental) = &result.capabilities.experimental {
+            info!("[LSP] Server experimental capabilities: {}", experimental);
+        }
+        debug!(
+            "[LSP] Server position encoding: {:?}",
+            PositionEncoding::from_kind(result.capabilities.position_encoding.as_ref())
+        );
+        *self.server_capabilities.write().unwrap() = Some(result.capabilities.clone());
+        *self.server_info.write().unwrap() = result.server_info.clone();
+
         // Send initialized notification
         self.notify("initialized", InitializedParams {}).await?;
 
@@ -303,24 +996,263 @@ impl Client {
 
     /// Cleanly shuts down the LSP server
     pub async fn shutdown(&self) -> Result<()> {
+        self.emit(ProxyEvent::ShuttingDown {
+            server: self.name.clone(),
+        });
+
         // First close all open files
         self.close_all_files().await?;
 
-        // Send shutdown request
-        let _: Value = self.call("shutdown", Value::Null).await?;
+        // Send shutdown request; an unresponsive server must not block the exit
+        match tokio::time::timeout(
+            SHUTDOWN_TIMEOUT,
+            self.call::<_, Value>("shutdown", Value::Null),
+        )
+        .await
+        {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => warn!("[LSP] Shutdown request failed: {}", e),
+            Err(_) => warn!("[LSP] Server did not answer the shutdown request in time"),
+        }
 
         // Send exit notification
         self.notify("exit", Value::Null).await?;
 
         // Signal the message loop to shut down
-        let _ = self.message_tx.send(ClientMessage::Shutdown).await;
+        let _ = self.sender().send(ClientMessage::Shutdown).await;
+
+        for server in self.attached_servers() {
+            if let Err(e) = Box::pin(server.shutdown()).await {
+                error!("[LSP] Error shutting down {}: {}", server.name(), e);
+            }
+        }
 
         info!("[LSP] LSP server shut down");
         Ok(())
     }
 
-    /// Opens a file in the LSP server
+    /// Shuts the server process down to free its memory while the proxy is
+    /// idle; [`Client::restart`] brings it back
+    pub async fn stop(&self) -> Result<()> {
+        self.shutdown().await?;
+        self.mark_stopped();
+        Ok(())
+    }
+
+    /// Reaps the exited server processes and forgets their state
+    fn mark_stopped(&self) {
+        if let Some(child) = self.child.lock().unwrap().as_mut() {
+            // The server was asked to exit; make sure it is gone
+            if let Ok(None) = child.try_wait() {
+                let _ = child.kill();
+            }
+            let _ = child.wait();
+        }
+
+        self.drop_caches();
+        self.open_files.write().unwrap().clear();
+        self.registrations.write().unwrap().clear();
+        self.work_done.send_replace(HashMap::new());
+        self.init_state.send_replace(InitState::Stopped);
+
+        for server in self.attached_servers() {
+            server.mark_stopped();
+        }
+        info!("[LSP] Stopped {}", self.name);
+    }
+
+    /// Starts a stopped server again and repeats the initialize handshake
+    pub async fn restart(self: &Arc<Self>) -> Result<()> {
+        let workspace_dir = self
+            .workspace_root()
+            .ok_or_else(|| anyhow!("Cannot restart a server that was never initialized"))?;
+
+        self.respawn().await?;
+        self.initialize(&workspace_dir).await?;
+        Ok(())
+    }
+
+    /// Replaces a running, hung or failed server with a fresh process: shuts it
+    /// down (forcibly if it doesn't cooperate), initializes a new one and
+    /// reopens the files that were open. Open files are reopened from disk.
+    pub async fn relaunch(self: &Arc<Self>) -> Result<()> {
+        let reopen: Vec<PathBuf> = self
+            .open_documents()
+            .into_iter()
+            .filter_map(|document| document.uri.to_file_path().ok())
+            .collect();
+
+        if self.init_state() != InitState::Stopped {
+            // A wedged server is killed by mark_stopped either way
+            if let Err(e) = self.shutdown().await {
+                warn!("[LSP] {} did not shut down cleanly: {}", self.name, e);
+            }
+            self.mark_stopped();
+        }
+        self.restart().await?;
+
+        for path in &reopen {
+            if let Err(e) = self.open_file(path).await {
+                warn!("[LSP] Failed to reopen {}: {}", path.display(), e);
+            }
+        }
+        info!(
+            "[LSP] Relaunched {} and reopened {} files",
+            self.name,
+            reopen.len()
+        );
+        self.emit(ProxyEvent::ServerRestarted {
+            server: self.name.clone(),
+            reopened: reopen.len(),
+        });
+        Ok(())
+    }
+
+    /// Starts a new server process behind this client and its attached
+    /// servers, or reconnects to a server listening on a socket
+    async fn respawn(self: &Arc<Self>) -> Result<()> {
+        info!("[LSP] Restarting {}", self.name);
+
+        let connection = match &self.address {
+            Some(address) => connect_server(address).await?,
+            None => spawn_server(&self.command, &self.args, &self.process_options)?,
+        };
+        let (tx, rx) = mpsc::channel::<ClientMessage>(100);
+        *self.child.lock().unwrap() = connection.child;
+        *self.started_at.lock().unwrap() = Instant::now();
+        *self.message_tx.write().unwrap() = tx;
+        self.pending_changes.lock().unwrap().clear();
+        self.work_done.send_replace(HashMap::new());
+        self.init_state.send_replace(InitState::Starting);
+        Client::start_io(
+            self,
+            rx,
+            connection.writer,
+            connection.reader,
+            connection.stderr,
+        );
+
+        for server in self.attached_servers() {
+            if let Err(e) = Box::pin(server.respawn()).await {
+                error!("[LSP] Failed to restart {}: {}", server.name(), e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops cached diagnostics and partial results, here and on attached servers
+    pub fn drop_caches(&self) {
+        self.diagnostics.write().unwrap().clear();
+        self.diagnostic_result_ids.write().unwrap().clear();
+        self.partial_results.write().unwrap().clear();
+
+        for server in self.attached_servers() {
+            server.drop_caches();
+        }
+    }
+
+    /// Gets the channel to the current server process
+    fn sender(&self) -> mpsc::Sender<ClientMessage> {
+        self.message_tx.read().unwrap().clone()
+    }
+
+    /// Subscribes to the proxy's event stream
+    #[allow(dead_code)]
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ProxyEvent> {
+        self.events.subscribe()
+    }
+
+    /// Publishes an event to all subscribers; events are dropped when nobody is listening
+    pub fn emit(&self, event: ProxyEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Gets the name of the server, derived from its command
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Attaches a secondary server that receives the same document notifications
+    /// as this one and contributes its own diagnostics
+    pub fn attach(&self, server: Arc<Client>) {
+        info!("[LSP] Attaching secondary server: {}", server.name());
+        self.attached.write().unwrap().push(server);
+    }
+
+    /// Gets the attached secondary servers
+    pub fn attached_servers(&self) -> Vec<Arc<Client>> {
+        self.attached.read().unwrap().clone()
+    }
+
+    /// Sends a request about a file to this server and every attached server
+    /// that has the file open, returning each answer with the server's name
+    pub async fn call_for_file<P, R>(
+        &self,
+        file_path: &Path,
+        method: &str,
+        params: P,
+    ) -> Vec<(String, Result<R>)>
+    where
+        P: Serialize + Clone + Send + Sync,
+        R: DeserializeOwned + Send + Sync,
+    {
+        let attached = self.attached_servers();
+        let servers: Vec<&Client> = std::iter::once(self)
+            .chain(attached.iter().map(|server| server.as_ref()))
+            .filter(|server| server.is_file_open(file_path))
+            .collect();
+
+        let results = futures::future::join_all(
+            servers
+                .iter()
+                .map(|server| server.call::<P, R>(method, params.clone())),
+        )
+        .await;
+
+        servers
+            .iter()
+            .map(|server| server.name.clone())
+            .zip(results)
+            .collect()
+    }
+
+    /// Opens a file in the LSP server and every attached server that handles it.
+    /// Only fails if no server could open the file.
     pub async fn open_file(&self, file_path: &Path) -> Result<()> {
+        let result = self.open_file_here(file_path).await;
+
+        let mut opened_elsewhere = false;
+        for server in self.attached_servers() {
+            match Box::pin(server.open_file(file_path)).await {
+                Ok(()) => opened_elsewhere = true,
+                Err(e) => debug!(
+                    "[LSP] {} did not open {}: {}",
+                    server.name(),
+                    file_path.display(),
+                    e
+                ),
+            }
+        }
+
+        // An attached server can handle files this one rejects, like a proto
+        // file referenced from Go code
+        match result {
+            Err(e) if !opened_elsewhere => Err(e),
+            Err(e) => {
+                debug!(
+                    "[LSP] {} did not open {}: {}",
+                    self.name,
+                    file_path.display(),
+                    e
+                );
+                Ok(())
+            }
+            Ok(()) => Ok(()),
+        }
+    }
+
+    /// Opens a file in this server only
+    async fn open_file_here(&self, file_path: &Path) -> Result<()> {
         let uri = to_uri(file_path);
         let uri_str = uri.to_string();
 
@@ -332,6 +1264,25 @@ impl Client {
             }
         }
 
+        // Check the file against the documents the server said it handles
+        let mut language_id = self.language_id(file_path);
+        if let Some(selector) = self.did_open_selector() {
+            match select_language_id(&selector, file_path, &language_id) {
+                Some(selected) => language_id = selected,
+                None => {
+                    warn!(
+                        "[LSP] No registered document selector matches {}",
+                        file_path.display()
+                    );
+                    return Err(anyhow!(
+                        "Unsupported file type: the language server does not handle {} files ({})",
+                        language_id,
+                        file_path.display()
+                    ));
+                }
+            }
+        }
+
         // Read the file content
         let content = tokio::fs::read_to_string(file_path)
             .await
@@ -341,9 +1292,9 @@ impl Client {
         let params = lsp_types::DidOpenTextDocumentParams {
             text_document: TextDocumentItem {
                 uri: uri.clone(),
-                language_id: detect_language_id(file_path),
+                language_id,
                 version: 1,
-                text: content,
+                text: content.clone(),
             },
         };
 
@@ -356,57 +1307,141 @@ impl Client {
                 uri_str,
                 OpenFileInfo {
                     version: 1,
-                    _uri: uri,
+                    uri: uri.clone(),
+                    dirty: false,
+                    text: content,
                 },
             );
         }
 
         debug!("[LSP] Opened file: {}", file_path.display());
+        self.emit(ProxyEvent::DocumentOpened { uri });
+
         Ok(())
     }
 
-    /// Notifies the LSP server of changes to a file
+    /// Notifies the LSP server of changes to a file, syncing it with the content on disk
     pub async fn notify_change(&self, file_path: &Path) -> Result<()> {
-        let uri = to_uri(file_path);
-        let uri_str = uri.to_string();
-
-        // Check if the file is open
-        let version = {
-            let mut open_files = self.open_files.write().unwrap();
-            let file_info = open_files.get_mut(&uri_str).ok_or_else(|| {
-                anyhow!(
-                    "Cannot notify change for unopened file: {}",
-                    file_path.display()
-                )
-            })?;
-
-            // Increment version
-            file_info.version += 1;
-            file_info.version
-        };
-
         // Read the file content
         let content = tokio::fs::read_to_string(file_path)
             .await
             .context(format!("Failed to read file: {}", file_path.display()))?;
 
-        // Send didChange notification
+        self.send_change(file_path, content, false).await?;
+
+        debug!("[LSP] Notified change for file: {}", file_path.display());
+
+        for server in self.attached_servers() {
+            if server.is_file_open(file_path)
+                && let Err(e) = Box::pin(server.notify_change(file_path)).await
+            {
+                error!("[LSP] Error notifying {} of change: {}", server.name(), e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tells the servers about files created, changed or deleted on disk, so
+    /// their project model follows changes outside open documents
+    pub async fn notify_watched_files(&self, changes: Vec<lsp_types::FileEvent>) -> Result<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        debug!("[LSP] Notifying {} watched file changes", changes.len());
+        let params = lsp_types::DidChangeWatchedFilesParams { changes };
+        self.notify("workspace/didChangeWatchedFiles", &params)
+            .await?;
+
+        for server in self.attached_servers() {
+            if let Err(e) = server
+                .notify("workspace/didChangeWatchedFiles", &params)
+                .await
+            {
+                error!(
+                    "[LSP] Error notifying {} of file changes: {}",
+                    server.name(),
+                    e
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the server's view of a file with in-memory content that is not on disk.
+    /// The file stays dirty until it is synced from disk again.
+    pub async fn set_overlay(&self, file_path: &Path, content: String) -> Result<()> {
+        self.open_file(file_path).await?;
+        self.send_change(file_path, content.clone(), true).await?;
+
+        debug!("[LSP] Set overlay for file: {}", file_path.display());
+
+        for server in self.attached_servers() {
+            if let Err(e) = Box::pin(server.set_overlay(file_path, content.clone())).await {
+                error!("[LSP] Error setting overlay on {}: {}", server.name(), e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Drops an in-memory overlay and resyncs the server with the file on disk
+    pub async fn discard_overlay(&self, file_path: &Path) -> Result<()> {
+        self.notify_change(file_path).await
+    }
+
+    /// Sends the new text of an open file as a didChange notification, as a
+    /// single ranged edit when the server accepts incremental changes
+    async fn send_change(&self, file_path: &Path, content: String, dirty: bool) -> Result<()> {
+        let uri = to_uri(file_path);
+        let uri_str = uri.to_string();
+        let incremental = self.uses_incremental_sync();
+        let encoding = self.position_encoding();
+
+        // Check if the file is open
+        let mut open_files = self.open_files.write().unwrap();
+        let file_info = open_files.get_mut(&uri_str).ok_or_else(|| {
+            anyhow!(
+                "Cannot notify change for unopened file: {}",
+                file_path.display()
+            )
+        })?;
+
+        let change = incremental
+            .then(|| sync::content_change(&file_info.text, &content, encoding))
+            .flatten()
+            .unwrap_or_else(|| lsp_types::TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: content.clone(),
+            });
+
+        // Increment version
+        file_info.version += 1;
+        file_info.dirty = dirty;
+        file_info.text = content;
+
+        // A server stopped while idle resyncs when it restarts
+        if *self.init_state.borrow() == InitState::Stopped {
+            debug!("[LSP] Dropping didChange for stopped server {}", self.name);
+            return Ok(());
+        }
+
+        // Send didChange notification, queued before the file is unlocked so the
+        // changes to it go out in version order
         let params = lsp_types::DidChangeTextDocumentParams {
             text_document: VersionedTextDocumentIdentifier {
-                uri: uri.clone(),
-                version,
+                uri,
+                version: file_info.version,
             },
-            content_changes: vec![lsp_types::TextDocumentContentChangeEvent {
-                range: None,
-                range_length: None,
-                text: content,
-            }],
+            content_changes: vec![change],
         };
-
-        self.notify("textDocument/didChange", params).await?;
-
-        debug!("[LSP] Notified change for file: {}", file_path.display());
-        Ok(())
+        self.queue_change(
+            serde_json::to_value(params)?,
+            (file_info.version, &file_info.text),
+        )
     }
 
     /// Closes a file in the LSP server
@@ -424,7 +1459,7 @@ impl Client {
 
         // Send didClose notification
         let params = lsp_types::DidCloseTextDocumentParams {
-            text_document: TextDocumentIdentifier { uri },
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
         };
 
         self.notify("textDocument/didClose", params).await?;
@@ -435,7 +1470,19 @@ impl Client {
             open_files.remove(&uri_str);
         }
 
+        // Diagnostics of a closed document go stale; not every server clears them
+        self.diagnostics.write().unwrap().remove(&uri);
+        self.diagnostic_result_ids.write().unwrap().remove(&uri);
+
         debug!("[LSP] Closed file: {}", file_path.display());
+        self.emit(ProxyEvent::DocumentClosed { uri });
+
+        for server in self.attached_servers() {
+            if let Err(e) = Box::pin(server.close_file(file_path)).await {
+                error!("[LSP] Error closing file on {}: {}", server.name(), e);
+            }
+        }
+
         Ok(())
     }
 
@@ -470,10 +1517,590 @@ impl Client {
         open_files.contains_key(&uri_str)
     }
 
+    /// Checks if the server holds in-memory content for a file that differs from disk
+    pub fn is_file_dirty(&self, file_path: &Path) -> bool {
+        let uri_str = to_uri(file_path).to_string();
+
+        let open_files = self.open_files.read().unwrap();
+        open_files.get(&uri_str).is_some_and(|info| info.dirty)
+    }
+
+    /// Gets the in-memory content the server holds for a file in place of the
+    /// content on disk, if it holds any
+    pub fn overlay(&self, file_path: &Path) -> Option<String> {
+        let uri_str = to_uri(file_path).to_string();
+
+        let open_files = self.open_files.read().unwrap();
+        open_files
+            .get(&uri_str)
+            .filter(|info| info.dirty)
+            .map(|info| info.text.clone())
+    }
+
+    /// Lists the documents currently open in the LSP server
+    pub fn open_documents(&self) -> Vec<OpenDocument> {
+        let open_files = self.open_files.read().unwrap();
+        open_files
+            .values()
+            .map(|info| OpenDocument {
+                uri: info.uri.clone(),
+                version: info.version,
+                dirty: info.dirty,
+            })
+            .collect()
+    }
+
+    /// Gets the workspace root the client was initialized with
+    pub fn workspace_root(&self) -> Option<PathBuf> {
+        self.workspace_root.read().unwrap().clone()
+    }
+
+    /// Gets the folders of the workspace, the root first
+    pub fn workspace_folders(&self) -> Vec<PathBuf> {
+        self.workspace_root()
+            .into_iter()
+            .chain(self.workspace_folders.read().unwrap().iter().cloned())
+            .collect()
+    }
+
+    /// Adds a folder to the workspace of this server and its attached
+    /// servers. Returns false if it already is a workspace folder.
+    pub async fn add_workspace_folder(&self, folder: &Path) -> Result<bool> {
+        if self.workspace_folders().iter().any(|f| f == folder) {
+            return Ok(false);
+        }
+        self.workspace_folders
+            .write()
+            .unwrap()
+            .push(folder.to_path_buf());
+
+        self.change_workspace_folders(vec![workspace_folder(folder)], Vec::new())
+            .await?;
+        info!("[LSP] Added workspace folder {}", folder.display());
+
+        for server in self.attached_servers() {
+            if let Err(e) = Box::pin(server.add_workspace_folder(folder)).await {
+                error!(
+                    "[LSP] Error adding workspace folder to {}: {}",
+                    server.name(),
+                    e
+                );
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Removes a folder added with [`Client::add_workspace_folder`] from the
+    /// workspace of this server and its attached servers. Returns false if it
+    /// isn't a workspace folder.
+    pub async fn remove_workspace_folder(&self, folder: &Path) -> Result<bool> {
+        if self.workspace_root().as_deref() == Some(folder) {
+            return Err(anyhow!(
+                "{} is the workspace root and can't be removed",
+                folder.display()
+            ));
+        }
+        {
+            let mut folders = self.workspace_folders.write().unwrap();
+            let Some(index) = folders.iter().position(|f| f == folder) else {
+                return Ok(false);
+            };
+            folders.remove(index);
+        }
+
+        self.change_workspace_folders(Vec::new(), vec![workspace_folder(folder)])
+            .await?;
+        info!("[LSP] Removed workspace folder {}", folder.display());
+
+        for server in self.attached_servers() {
+            if let Err(e) = Box::pin(server.remove_workspace_folder(folder)).await {
+                error!(
+                    "[LSP] Error removing workspace folder from {}: {}",
+                    server.name(),
+                    e
+                );
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Sends a didChangeWorkspaceFolders notification if the server asked for
+    /// them, statically or with a dynamic registration
+    async fn change_workspace_folders(
+        &self,
+        added: Vec<WorkspaceFolder>,
+        removed: Vec<WorkspaceFolder>,
+    ) -> Result<()> {
+        let announced = self
+            .server_capabilities
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|capabilities| capabilities.workspace.as_ref())
+            .and_then(|workspace| workspace.workspace_folders.as_ref())
+            .and_then(|folders| folders.change_notifications.as_ref())
+            .is_some_and(|notifications| match notifications {
+                lsp_types::OneOf::Left(enabled) => *enabled,
+                lsp_types::OneOf::Right(_) => true,
+            });
+        let registered = self
+            .registrations
+            .read()
+            .unwrap()
+            .values()
+            .any(|r| r.method == "workspace/didChangeWorkspaceFolders");
+        if !announced && !registered {
+            debug!(
+                "[LSP] {} doesn't follow workspace folder changes",
+                self.name()
+            );
+            return Ok(());
+        }
+
+        let params = lsp_types::DidChangeWorkspaceFoldersParams {
+            event: lsp_types::WorkspaceFoldersChangeEvent { added, removed },
+        };
+        self.notify("workspace/didChangeWorkspaceFolders", &params)
+            .await
+    }
+
+    /// Sets the trace level requested from the server during initialization
+    pub fn set_trace(&self, trace: lsp_types::TraceValue) {
+        *self.trace.write().unwrap() = trace;
+    }
+
+    /// Sets the initialization options sent to the server in place of the
+    /// preset for its command
+    pub fn set_initialization_options(&self, options: Value) {
+        *self.initialization_options.write().unwrap() = Some(options);
+    }
+
+    /// Gets the initialization options sent to the server, if any
+    #[allow(dead_code)]
+    pub fn initialization_options(&self) -> Option<Value> {
+        self.initialization_options.read().unwrap().clone()
+    }
+
+    /// Changes client behavior to avoid a known problem in the server version.
+    /// Must be called before initialization to affect the advertised capabilities.
+    pub fn apply_workaround(&self, workaround: Workaround) {
+        let mut workarounds = self.workarounds.write().unwrap();
+        if !workarounds.contains(&workaround) {
+            workarounds.push(workaround);
+        }
+    }
+
+    /// Checks whether a workaround is in effect
+    pub fn has_workaround(&self, workaround: Workaround) -> bool {
+        self.workarounds.read().unwrap().contains(&workaround)
+    }
+
+    /// Sets how requests failing with transient server errors are retried
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        *self.retry_policy.write().unwrap() = policy;
+    }
+
+    /// Sets the settings handed to the server in answer to `workspace/configuration`
+    pub fn set_settings(&self, settings: Settings) {
+        *self.settings.write().unwrap() = settings;
+    }
+
+    /// Resolves the settings for each item of a `workspace/configuration` request
+    pub fn configuration(&self, items: &[lsp_types::ConfigurationItem]) -> Vec<Value> {
+        let settings = self.settings.read().unwrap();
+        let root = self.workspace_root();
+        items
+            .iter()
+            .map(|item| {
+                let scope = item
+                    .scope_uri
+                    .as_ref()
+                    .and_then(|uri| uri.to_file_path().ok());
+                settings.resolve(item.section.as_deref(), scope.as_deref(), root.as_deref())
+            })
+            .collect()
+    }
+
+    /// Sets how long requests wait for an answer before they are cancelled
+    pub fn set_timeout_policy(&self, policy: TimeoutPolicy) {
+        *self.timeout_policy.write().unwrap() = policy;
+    }
+
+    /// Overrides the language id sent in didOpen for files with the given extension
+    pub fn set_language_id(&self, extension: &str, language_id: &str) {
+        let mut overrides = self.language_overrides.write().unwrap();
+        overrides.insert(
+            extension.trim_start_matches('.').to_string(),
+            language_id.to_string(),
+        );
+    }
+
+    /// Gets the language id for a file, honoring any configured extension overrides
+    pub fn language_id(&self, file_path: &Path) -> String {
+        let overrides = self.language_overrides.read().unwrap();
+        file_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(|e| overrides.get(e))
+            .cloned()
+            .unwrap_or_else(|| detect_language_id(file_path))
+    }
+
+    /// Gets the combined document selector of the server's dynamic didOpen
+    /// registrations, if it registered any
+    fn did_open_selector(&self) -> Option<Vec<DocumentFilter>> {
+        let registrations = self.registrations.read().unwrap();
+        let mut selector: Option<Vec<DocumentFilter>> = None;
+
+        for registration in registrations
+            .values()
+            .filter(|r| r.method == "textDocument/didOpen")
+        {
+            let options = registration
+                .register_options
+                .clone()
+                .and_then(|o| {
+                    serde_json::from_value::<lsp_types::TextDocumentRegistrationOptions>(o).ok()
+                })
+                .and_then(|o| o.document_selector);
+
+            if let Some(filters) = options {
+                selector.get_or_insert_with(Vec::new).extend(filters);
+            }
+        }
+
+        selector
+    }
+
+    /// Checks whether the server accepts ranged didChange notifications, as
+    /// announced in its capabilities or a dynamic didChange registration
+    fn uses_incremental_sync(&self) -> bool {
+        let static_kind = self
+            .server_capabilities
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|capabilities| capabilities.text_document_sync.clone())
+            .and_then(|sync| match sync {
+                TextDocumentSyncCapability::Kind(kind) => Some(kind),
+                TextDocumentSyncCapability::Options(options) => options.change,
+            });
+        if let Some(kind) = static_kind {
+            return kind == TextDocumentSyncKind::INCREMENTAL;
+        }
+
+        let registrations = self.registrations.read().unwrap();
+        registrations
+            .values()
+            .filter(|r| r.method == "textDocument/didChange")
+            .filter_map(|r| r.register_options.as_ref()?.get("syncKind")?.as_i64())
+            .any(|kind| kind == 2)
+    }
+
+    /// Gets the encoding the columns of positions exchanged with the server
+    /// are counted in
+    pub fn position_encoding(&self) -> PositionEncoding {
+        let capabilities = self.server_capabilities.read().unwrap();
+        PositionEncoding::from_kind(
+            capabilities
+                .as_ref()
+                .and_then(|capabilities| capabilities.position_encoding.as_ref()),
+        )
+    }
+
+    /// Gets the capabilities the server reported during initialization
+    pub fn server_capabilities(&self) -> Option<ServerCapabilities> {
+        self.server_capabilities.read().unwrap().clone()
+    }
+
+    /// Gets the name and version the server reported during initialization
+    pub fn server_info(&self) -> Option<lsp_types::ServerInfo> {
+        self.server_info.read().unwrap().clone()
+    }
+
+    /// Gets the methods the server registered dynamically, sorted
+    pub fn registered_methods(&self) -> Vec<String> {
+        let registrations = self.registrations.read().unwrap();
+        let mut methods: Vec<String> = registrations.values().map(|r| r.method.clone()).collect();
+        methods.sort();
+        methods.dedup();
+        methods
+    }
+
+    /// Gets a named entry from the `experimental` section of the server capabilities
+    pub fn experimental_capability(&self, name: &str) -> Option<Value> {
+        let capabilities = self.server_capabilities.read().unwrap();
+        capabilities
+            .as_ref()?
+            .experimental
+            .as_ref()?
+            .get(name)
+            .cloned()
+    }
+
+    /// Caches diagnostics the server published for a document. Servers may
+    /// deliver them out of order, so diagnostics for an older version of the
+    /// document than the cached ones are ignored.
+    pub fn store_diagnostics(&self, params: lsp_types::PublishDiagnosticsParams) {
+        let count = params.diagnostics.len();
+        {
+            let mut diagnostics = self.diagnostics.write().unwrap();
+            if let Some(cached_version) = diagnostics.get(&params.uri).and_then(|d| d.version)
+                && let Some(version) = params.version
+                && version < cached_version
+            {
+                debug!(
+                    "[LSP] Ignoring diagnostics for version {} of {}, have version {}",
+                    version, params.uri, cached_version
+                );
+                return;
+            }
+            diagnostics.insert(
+                params.uri.clone(),
+                DocumentDiagnostics {
+                    version: params.version,
+                    diagnostics: params.diagnostics,
+                    publication: self.publications.fetch_add(1, Ordering::SeqCst) + 1,
+                },
+            );
+        }
+        self.diagnostics_published.notify_waiters();
+        self.emit(ProxyEvent::DiagnosticsUpdated {
+            uri: params.uri,
+            count,
+        });
+    }
+
     /// Gets diagnostics for a file
     pub fn get_diagnostics(&self, uri: &DocumentUri) -> Vec<lsp_types::Diagnostic> {
         let diagnostics = self.diagnostics.read().unwrap();
-        diagnostics.get(uri).cloned().unwrap_or_default()
+        diagnostics
+            .get(uri)
+            .map(|d| d.diagnostics.clone())
+            .unwrap_or_default()
+    }
+
+    /// Lists the files this server has reported diagnostics for
+    pub fn diagnostic_uris(&self) -> Vec<DocumentUri> {
+        let diagnostics = self.diagnostics.read().unwrap();
+        let mut uris: Vec<DocumentUri> = diagnostics
+            .iter()
+            .filter(|(_, document)| !document.diagnostics.is_empty())
+            .map(|(uri, _)| uri.clone())
+            .collect();
+        uris.sort();
+        uris
+    }
+
+    /// Gets a marker that changes whenever diagnostics are published for a file,
+    /// to pass to `wait_for_diagnostics`
+    pub fn diagnostics_publication(&self, uri: &DocumentUri) -> Option<u64> {
+        Some(self.diagnostics.read().unwrap().get(uri)?.publication)
+    }
+
+    /// Waits until diagnostics for a file are published after the `since`
+    /// marker was taken, or the timeout passes. Returns whether they were.
+    pub async fn wait_for_diagnostics(
+        &self,
+        uri: &DocumentUri,
+        since: Option<u64>,
+        timeout: Duration,
+    ) -> bool {
+        let published = async {
+            loop {
+                // Created before checking, so a publication in between still wakes it
+                let notified = self.diagnostics_published.notified();
+                if self.diagnostics_publication(uri) != since {
+                    return;
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(timeout, published).await.is_ok()
+    }
+
+    /// Gets the document version the cached diagnostics for a file were computed
+    /// for, if the server reported one
+    pub fn diagnostics_version(&self, uri: &DocumentUri) -> Option<i32> {
+        self.diagnostics.read().unwrap().get(uri)?.version
+    }
+
+    /// Gets the pull diagnostics support the server announced, statically or
+    /// through a dynamic registration
+    pub fn pull_diagnostics_support(&self) -> Option<PullDiagnostics> {
+        // Static and registration options share the fields read here
+        let static_options = self
+            .server_capabilities
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|capabilities| capabilities.diagnostic_provider.clone())
+            .and_then(|provider| serde_json::to_value(provider).ok());
+        let options = static_options.or_else(|| {
+            let registrations = self.registrations.read().unwrap();
+            registrations
+                .values()
+                .find(|r| r.method == "textDocument/diagnostic")
+                .map(|r| r.register_options.clone().unwrap_or_default())
+        })?;
+
+        Some(PullDiagnostics {
+            identifier: options
+                .get("identifier")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            workspace: options
+                .get("workspaceDiagnostics")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        })
+    }
+
+    /// Pulls diagnostics for a file with `textDocument/diagnostic` from this
+    /// server and every attached server that supports it, updating the cache
+    /// that pushed diagnostics go to
+    pub async fn pull_diagnostics(&self, uri: &DocumentUri) -> Result<()> {
+        let result = self.pull_document_diagnostics(uri).await;
+
+        for server in self.attached_servers() {
+            if let Err(e) = Box::pin(server.pull_diagnostics(uri)).await {
+                error!(
+                    "[LSP] Error pulling diagnostics from {}: {}",
+                    server.name(),
+                    e
+                );
+            }
+        }
+
+        result
+    }
+
+    async fn pull_document_diagnostics(&self, uri: &DocumentUri) -> Result<()> {
+        let Some(support) = self.pull_diagnostics_support() else {
+            return Ok(());
+        };
+
+        let params = lsp_types::DocumentDiagnosticParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            identifier: support.identifier,
+            previous_result_id: self.diagnostic_result_ids.read().unwrap().get(uri).cloned(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let result: lsp_types::DocumentDiagnosticReportResult =
+            self.call("textDocument/diagnostic", params).await?;
+
+        // An unchanged report means the cached diagnostics still apply
+        if let lsp_types::DocumentDiagnosticReportResult::Report(
+            lsp_types::DocumentDiagnosticReport::Full(report),
+        ) = result
+        {
+            let version = self
+                .open_files
+                .read()
+                .unwrap()
+                .get(uri.as_str())
+                .map(|info| info.version);
+            self.store_pulled_report(uri, version, report.full_document_diagnostic_report);
+        }
+        Ok(())
+    }
+
+    /// Pulls diagnostics for the whole workspace with `workspace/diagnostic`,
+    /// updating the cache. Returns the files reported on, or `None` if the
+    /// server doesn't offer workspace diagnostics.
+    pub async fn pull_workspace_diagnostics(&self) -> Result<Option<Vec<DocumentUri>>> {
+        let Some(support) = self.pull_diagnostics_support().filter(|s| s.workspace) else {
+            return Ok(None);
+        };
+
+        let previous_result_ids = self
+            .diagnostic_result_ids
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(uri, value)| lsp_types::PreviousResultId {
+                uri: uri.clone(),
+                value: value.clone(),
+            })
+            .collect();
+        let params = lsp_types::WorkspaceDiagnosticParams {
+            identifier: support.identifier,
+            previous_result_ids,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let result: lsp_types::WorkspaceDiagnosticReportResult =
+            self.call("workspace/diagnostic", params).await?;
+
+        let items = match result {
+            lsp_types::WorkspaceDiagnosticReportResult::Report(report) => report.items,
+            lsp_types::WorkspaceDiagnosticReportResult::Partial(partial) => partial.items,
+        };
+
+        let mut uris = Vec::new();
+        for item in items {
+            match item {
+                lsp_types::WorkspaceDocumentDiagnosticReport::Full(report) => {
+                    let version = report.version.map(|v| v as i32);
+                    self.store_pulled_report(
+                        &report.uri,
+                        version,
+                        report.full_document_diagnostic_report,
+                    );
+                    uris.push(report.uri);
+                }
+                lsp_types::WorkspaceDocumentDiagnosticReport::Unchanged(report) => {
+                    uris.push(report.uri);
+                }
+            }
+        }
+        Ok(Some(uris))
+    }
+
+    fn store_pulled_report(
+        &self,
+        uri: &DocumentUri,
+        version: Option<i32>,
+        report: lsp_types::FullDocumentDiagnosticReport,
+    ) {
+        match report.result_id {
+            Some(result_id) => {
+                self.diagnostic_result_ids
+                    .write()
+                    .unwrap()
+                    .insert(uri.clone(), result_id);
+            }
+            None => {
+                self.diagnostic_result_ids.write().unwrap().remove(uri);
+            }
+        }
+        self.store_diagnostics(lsp_types::PublishDiagnosticsParams {
+            uri: uri.clone(),
+            diagnostics: report.items,
+            version,
+        });
+    }
+
+    /// Gets diagnostics for a file from this server and every attached server.
+    /// Diagnostics without a source are tagged with the name of the server that sent them,
+    /// and diagnostics reported by several servers are only returned once.
+    pub fn get_all_diagnostics(&self, uri: &DocumentUri) -> Vec<lsp_types::Diagnostic> {
+        let mut all = tag_diagnostics(self.get_diagnostics(uri), self.name());
+        for server in self.attached_servers() {
+            all.extend(tag_diagnostics(
+                server.get_all_diagnostics(uri),
+                server.name(),
+            ));
+        }
+        dedupe_diagnostics(all)
+    }
+
+    /// Takes the reports of the edits applied for workspace/applyEdit so far
+    pub fn take_applied_edits(&self) -> Vec<String> {
+        std::mem::take(&mut *self.applied_edits.lock().unwrap())
     }
 
     /// Registers a handler for server notifications
@@ -494,41 +2121,164 @@ impl Client {
         handlers.insert(method.to_string(), Box::new(handler));
     }
 
-    /// Calls an LSP method and returns the result
+    /// Registers a handler for server requests that takes a while to answer,
+    /// such as applying an edit. It runs in its own task, so the message loop
+    /// keeps going meanwhile.
+    pub fn register_async_request_handler<F, Fut>(&self, method: &str, handler: F)
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value>> + Send + 'static,
+    {
+        let mut handlers = self.async_request_handlers.write().unwrap();
+        handlers.insert(
+            method.to_string(),
+            Box::new(move |params| Box::pin(handler(params))),
+        );
+    }
+
+    /// Calls an LSP method and returns the result. Requests failing with
+    /// ContentModified or ServerCancelled are retried per the retry policy.
     pub async fn call<P, R>(&self, method: &str, params: P) -> Result<R>
     where
         P: Serialize + Send + Sync,
         R: DeserializeOwned + Send + Sync,
     {
-        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
-        let id = MessageID::Number(id);
-
         let params_value = serde_json::to_value(params)?;
+        let policy = *self.retry_policy.read().unwrap();
+
+        let mut retry = 0;
+        let value = loop {
+            match self.request(method, params_value.clone()).await {
+                Err(e) if retry < policy.max_retries && retry::is_transient(&e) => {
+                    retry += 1;
+                    let backoff = policy.backoff(retry);
+                    debug!(
+                        "[LSP] Retrying {} in {:?} ({}/{}): {}",
+                        method, backoff, retry, policy.max_retries, e
+                    );
+                    tokio::time::sleep(backoff).await;
+                }
+                result => break result?,
+            }
+        };
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Sends a request once and waits for the raw result
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let result = {
+            let _pending = PendingRequest::new(&self.pending_requests);
+            self.send_request(method, params).await
+        };
+
+        if let Err(e) = &result {
+            *self.last_error.write().unwrap() =
+                Some((Instant::now(), format!("{} failed: {}", method, e)));
+        }
+        result
+    }
+
+    /// Sends a request and waits for its answer, unless it times out
+    async fn send_request(&self, method: &str, params: Value) -> Result<Value> {
+        let number = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let id = MessageID::Number(number);
 
         // Create a channel for the response
         let (tx, rx) = oneshot::channel();
 
         // Send the request
-        self.message_tx
+        debug!("[LSP] Request {}: {} to {}", id, method, self.name);
+        self.sender()
             .send(ClientMessage::Request {
                 id: id.clone(),
                 method: method.to_string(),
-                params: params_value,
+                params,
                 response_tx: tx,
+                correlation_id: correlation::current(),
             })
-            .await?;
+            .await
+            .map_err(|_| anyhow!(SHUTTING_DOWN))?;
+
+        // Wait for the response; the sender is only dropped if the message loop died
+        let timeout = self.timeout_policy.read().unwrap().for_method(method);
+        let response = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, rx).await {
+                Ok(response) => response,
+                Err(_) => {
+                    warn!(
+                        "[LSP] Request {} ({}) timed out after {:?}",
+                        id, method, timeout
+                    );
+                    // The server answers a cancelled request with an error, which nobody waits for
+                    let cancel = lsp_types::CancelParams {
+                        id: NumberOrString::Number(number),
+                    };
+                    if let Err(e) = self.notify("$/cancelRequest", cancel).await {
+                        debug!("[LSP] Failed to cancel request {}: {}", id, e);
+                    }
+                    return Err(anyhow::Error::new(RequestTimeout {
+                        method: method.to_string(),
+                        timeout,
+                    }));
+                }
+            },
+            None => rx.await,
+        };
+        let result = response.map_err(|_| anyhow!(SHUTTING_DOWN))?;
+        if let Err(e) = &result {
+            debug!("[LSP] Request {} failed: {}", id, e);
+        }
+        result
+    }
 
-        // Wait for the response
-        let result = rx.await?;
+    /// Creates a fresh token for work done progress or partial result streaming
+    pub fn new_progress_token(&self) -> ProgressToken {
+        let id = self.next_progress_token.fetch_add(1, Ordering::SeqCst);
+        NumberOrString::String(format!("mcp-language-server-{}", id))
+    }
 
-        // Convert the result
-        match result {
-            Ok(value) => {
-                let result = serde_json::from_value(value)?;
-                Ok(result)
-            }
-            Err(e) => Err(e),
+    /// Calls an LSP method whose result is a list, collecting any partial results
+    /// streamed via `$/progress` for `partial_result_token` ahead of the final response
+    pub async fn call_with_partial_results<P, T>(
+        &self,
+        method: &str,
+        params: P,
+        partial_result_token: &ProgressToken,
+    ) -> Result<Vec<T>>
+    where
+        P: Serialize + Send + Sync,
+        T: DeserializeOwned + Send + Sync,
+    {
+        let key = token_key(partial_result_token);
+        self.partial_results
+            .write()
+            .unwrap()
+            .insert(key.clone(), Vec::new());
+
+        let result: Result<Option<Vec<Value>>> = self.call(method, params).await;
+
+        let mut items = self
+            .partial_results
+            .write()
+            .unwrap()
+            .remove(&key)
+            .unwrap_or_default();
+
+        if !items.is_empty() {
+            debug!(
+                "[LSP] Received {} partial results for {}",
+                items.len(),
+                method
+            );
         }
+
+        items.extend(result?.unwrap_or_default());
+
+        items
+            .into_iter()
+            .map(|item| serde_json::from_value(item).map_err(Into::into))
+            .collect()
     }
 
     /// Sends a notification to the LSP server
@@ -536,21 +2286,242 @@ impl Client {
     where
         P: Serialize + Send + Sync,
     {
+        // A server stopped while idle resyncs when it restarts
+        if *self.init_state.borrow() == InitState::Stopped {
+            debug!("[LSP] Dropping {} for stopped server {}", method, self.name);
+            return Ok(());
+        }
+
         let params_value = serde_json::to_value(params)?;
 
+        if method == "textDocument/didChange" {
+            let (version, text) = change_target(&params_value)
+                .and_then(|(uri, _)| {
+                    let open_files = self.open_files.read().unwrap();
+                    open_files
+                        .get(&uri)
+                        .map(|file_info| (file_info.version, file_info.text.clone()))
+                })
+                .unwrap_or_default();
+            return self.queue_change(params_value, (version, &text));
+        }
+
         // Send the notification
-        self.message_tx
+        self.sender()
             .send(ClientMessage::Notification {
                 method: method.to_string(),
                 params: params_value,
             })
-            .await?;
+            .await
+            .map_err(|_| anyhow!(SHUTTING_DOWN))?;
 
         Ok(())
     }
 
+    /// Gets the number of didChange notifications that were superseded by a newer
+    /// change before they could be sent
+    #[allow(dead_code)]
+    pub fn coalesced_changes(&self) -> u64 {
+        self.coalesced_changes.load(Ordering::Relaxed)
+    }
+
     // Private methods
 
+    /// Queues a didChange notification without waiting for room in the message channel.
+    ///
+    /// When the channel is full the change is parked in `pending_changes`. A newer change
+    /// for the same document replaces it if it carries the full document content, and is
+    /// appended to it otherwise, since ranged changes build on the ones before them. A
+    /// ranged change older than the parked one no longer fits after it, so the parked
+    /// change is replaced by the document's `current` version and content.
+    fn queue_change(&self, params: Value, current: (i32, &str)) -> Result<()> {
+        let Some((uri, version)) = change_target(&params) else {
+            return Err(anyhow!(
+                "didChange notification without a versioned document"
+            ));
+        };
+
+        let mut pending = self.pending_changes.lock().unwrap();
+
+        // Keep changes for a document in order once one of them is parked
+        if let Some((pending_version, pending_params)) = pending.get_mut(&uri) {
+            if version > *pending_version {
+                *pending_version = version;
+                if replaces_document(&params) {
+                    *pending_params = params;
+                } else {
+                    merge_change(pending_params, params);
+                }
+            } else if !replaces_document(&params) {
+                debug!(
+                    "[LSP] Ranged didChange {} for {} arrived after {}, resending the full text",
+                    version, uri, pending_version
+                );
+                *pending_version = current.0.max(*pending_version);
+                *pending_params = full_change(&uri, *pending_version, current.1);
+            }
+            self.record_coalesced(&uri);
+            return Ok(());
+        }
+
+        match self.sender().try_send(ClientMessage::Notification {
+            method: "textDocument/didChange".to_string(),
+            params,
+        }) {
+            Ok(()) => Ok(()),
+            Err(mpsc::error::TrySendError::Full(ClientMessage::Notification {
+                params, ..
+            })) => {
+                debug!(
+                    "[LSP] Message channel full, deferring didChange for {}",
+                    uri
+                );
+                pending.insert(uri, (version, params));
+                self.changes_ready.notify_one();
+                Ok(())
+            }
+            Err(_) => Err(anyhow!(SHUTTING_DOWN)),
+        }
+    }
+
+    fn record_coalesced(&self, uri: &str) {
+        let total = self.coalesced_changes.fetch_add(1, Ordering::Relaxed) + 1;
+        debug!(
+            "[LSP] Coalesced didChange for {} ({} coalesced in total)",
+            uri, total
+        );
+    }
+
+    /// Decides how a queued notification relates to parked didChange notifications.
+    ///
+    /// Returns whether the notification should still be sent, plus a parked change that
+    /// has to be sent before it.
+    fn reconcile_pending_change(&self, method: &str, params: &Value) -> (bool, Option<Value>) {
+        let Some(uri) = params.pointer("/textDocument/uri").and_then(Value::as_str) else {
+            return (true, None);
+        };
+
+        let mut pending = self.pending_changes.lock().unwrap();
+        if method != "textDocument/didChange" {
+            // didSave/didClose must see the latest content first
+            return (true, pending.remove(uri).map(|(_, params)| params));
+        }
+
+        let version = change_target(params).map(|(_, v)| v).unwrap_or_default();
+        match pending.get(uri) {
+            Some((pending_version, pending_params))
+                if *pending_version > version && replaces_document(pending_params) =>
+            {
+                // A newer full change is parked; this one is stale
+                self.record_coalesced(uri);
+                (false, None)
+            }
+            Some((pending_version, _)) if *pending_version > version => {
+                // The parked change builds on this one and goes out once the
+                // channel is drained
+                (true, None)
+            }
+            Some(_) => {
+                pending.remove(uri);
+                self.record_coalesced(uri);
+                (true, None)
+            }
+            None => (true, None),
+        }
+    }
+
+    /// Takes every parked didChange notification
+    fn take_pending_changes(&self) -> Vec<Value> {
+        let mut pending = self.pending_changes.lock().unwrap();
+        pending.drain().map(|(_, (_, params))| params).collect()
+    }
+
+    /// Handles `$/progress` notifications, buffering partial results for pending
+    /// requests and tracking work done progress
+    fn handle_progress(&self, params: Value) -> Result<()> {
+        let token: ProgressToken = serde_json::from_value(
+            params
+                .get("token")
+                .cloned()
+                .ok_or_else(|| anyhow!("Progress notification without token"))?,
+        )?;
+        let value = params.get("value").cloned().unwrap_or(Value::Null);
+        let key = token_key(&token);
+
+        {
+            let mut partial_results = self.partial_results.write().unwrap();
+            if let Some(items) = partial_results.get_mut(&key) {
+                match value {
+                    Value::Array(values) => items.extend(values),
+                    Value::Null => {}
+                    value => items.push(value),
+                }
+                return Ok(());
+            }
+        }
+
+        let event = match serde_json::from_value::<WorkDoneProgress>(value) {
+            Ok(WorkDoneProgress::Begin(begin)) => {
+                debug!("[LSP] Progress {} started: {}", key, begin.title);
+                self.work_done.send_modify(|work_done| {
+                    work_done.insert(key.clone(), begin.title.clone());
+                });
+                ProxyEvent::Progress {
+                    token: key,
+                    title: begin.title,
+                    message: begin.message,
+                    percentage: begin.percentage,
+                    done: false,
+                }
+            }
+            Ok(WorkDoneProgress::Report(report)) => {
+                debug!(
+                    "[LSP] Progress {}: {}{}",
+                    key,
+                    report.message.as_deref().unwrap_or_default(),
+                    report
+                        .percentage
+                        .map(|p| format!(" ({}%)", p))
+                        .unwrap_or_default()
+                );
+                let title = self.work_done.borrow().get(&key).cloned();
+                ProxyEvent::Progress {
+                    token: key,
+                    title: title.unwrap_or_default(),
+                    message: report.message,
+                    percentage: report.percentage,
+                    done: false,
+                }
+            }
+            Ok(WorkDoneProgress::End(end)) => {
+                debug!(
+                    "[LSP] Progress {} finished: {}",
+                    key,
+                    end.message.as_deref().unwrap_or_default()
+                );
+                let mut title = None;
+                self.work_done.send_if_modified(|work_done| {
+                    title = work_done.remove(&key);
+                    title.is_some()
+                });
+                ProxyEvent::Progress {
+                    token: key,
+                    title: title.unwrap_or_default(),
+                    message: end.message,
+                    percentage: None,
+                    done: true,
+                }
+            }
+            Err(_) => {
+                debug!("[LSP] Progress for unknown token: {}", key);
+                return Ok(());
+            }
+        };
+        self.emit(event);
+
+        Ok(())
+    }
+
     /// Handles messages from the LSP server
     async fn message_loop<W>(
         client: Arc<Client>,
@@ -565,12 +2536,12 @@ impl Client {
         let mut response_channels: HashMap<String, oneshot::Sender<Result<Value>>> = HashMap::new();
 
         // Process messages from both channels: the client and the server
-        loop {
+        let result = loop {
             tokio::select! {
                 // Handle messages from the client
                 Some(client_msg) = rx.recv() => {
                     match client_msg {
-                        ClientMessage::Request { id, method, params, response_tx } => {
+                        ClientMessage::Request { id, method, params, response_tx, correlation_id } => {
                             // Create an LSP request message
                             let msg = Message {
                                 jsonrpc: "2.0".to_string(),
@@ -584,114 +2555,202 @@ impl Client {
                             // Store the response channel
                             response_channels.insert(id.to_string(), response_tx);
 
-                            // Send the message to the server
-                            write_message(&mut writer, &msg).await?;
+                            // Send the message to the server, logged under the tool call that made it
+                            let written = match correlation_id {
+                                Some(correlation_id) => {
+                                    correlation::with_correlation_id(correlation_id, write_message(&mut writer, &msg)).await
+                                }
+                                None => write_message(&mut writer, &msg).await,
+                            };
+                            if let Err(e) = written {
+                                break Err(e);
+                            }
                         }
                         ClientMessage::Notification { method, params } => {
-                            // Create an LSP notification message
-                            let msg = Message {
-                                jsonrpc: "2.0".to_string(),
-                                id: None,
-                                method: Some(method),
-                                params: Some(params),
-                                result: None,
-                                error: None,
-                            };
+                            let (send, parked) = client.reconcile_pending_change(&method, &params);
+
+                            // A parked change for the same document goes out first
+                            if let Some(parked) = parked {
+                                let msg = notification_message("textDocument/didChange".to_string(), parked);
+                                if let Err(e) = write_message(&mut writer, &msg).await {
+                                    break Err(e);
+                                }
+                            }
 
-                            // Send the message to the server
-                            write_message(&mut writer, &msg).await?;
+                            if send {
+                                // Send the message to the server
+                                let msg = notification_message(method, params);
+                                if let Err(e) = write_message(&mut writer, &msg).await {
+                                    break Err(e);
+                                }
+                            }
+                        }
+                        ClientMessage::Response { id, result } => {
+                            if let Err(e) = write_message(&mut writer, &response_message(id, result)).await {
+                                break Err(e);
+                            }
                         }
                         ClientMessage::Shutdown => {
                             // Clean shutdown
+                            break Ok(());
+                        }
+                    }
+                }
+
+                // Send didChange notifications that were parked while the channel was full,
+                // once the channel is drained, so every change queued before them has been
+                // written. Server messages keep being read meanwhile; a server blocked on
+                // its output would stop reading ours.
+                _ = client.changes_ready.notified(), if rx.is_empty() => {
+                    let mut failed = None;
+                    for params in client.take_pending_changes() {
+                        let msg = notification_message("textDocument/didChange".to_string(), params);
+                        if let Err(e) = write_message(&mut writer, &msg).await {
+                            failed = Some(e);
                             break;
                         }
                     }
+                    if let Some(e) = failed {
+                        break Err(e);
+                    }
                 }
 
                 // Handle messages from the server
                 Some(server_msg) = msg_rx.recv() => {
-                    if let Some(id) = &server_msg.id {
-                        // This is a response to one of our requests
-                        if let Some(tx) = response_channels.remove(&id.to_string()) {
-                            if let Some(error) = server_msg.error {
-                                // Send the error to the waiting task
-                                let _ = tx.send(Err(anyhow!("LSP error: {} (code: {})", error.message, error.code)));
-                            } else if let Some(result) = server_msg.result {
-                                // Send the result to the waiting task
-                                let _ = tx.send(Ok(result));
-                            } else {
-                                // No result or error
-                                let _ = tx.send(Err(anyhow!("LSP response has neither result nor error")));
+                    match (server_msg.id, server_msg.method) {
+                        // A request from the server: it carries a method as well as an id
+                        (Some(id), Some(method)) => {
+                            let params = server_msg.params.unwrap_or(Value::Null);
+
+                            // Handlers that take a while answer from their own task
+                            let pending = client
+                                .async_request_handlers
+                                .read()
+                                .unwrap()
+                                .get(&method)
+                                .map(|handler| handler(params.clone()));
+                            if let Some(pending) = pending {
+                                let tx = client.sender();
+                                tokio::spawn(async move {
+                                    let result = pending.await;
+                                    let _ = tx.send(ClientMessage::Response { id, result }).await;
+                                });
+                                continue;
                             }
-                        }
-                    } else if let Some(method) = &server_msg.method {
-                        // This is a server-to-client request or notification
-                        if server_msg.id.is_some() {
-                            // This is a request
-                            let method_name = method.clone();
-                            let id = server_msg.id.clone().unwrap();
-                            let params = server_msg.params.clone().unwrap_or(Value::Null);
 
-                            // Look up handler
-                            let handler_result = {
+                            let result = {
                                 let handlers = client.request_handlers.read().unwrap();
-                                if let Some(handler) = handlers.get(&method_name) {
-                                    handler(params)
-                                } else {
-                                    Err(anyhow!("No handler for request method: {}", method_name))
+                                match handlers.get(&method) {
+                                    Some(handler) => handler(params),
+                                    None => Err(anyhow!("No handler for request method: {}", method)),
                                 }
                             };
 
-                            // Create response message
-                            let response = match handler_result {
-                                Ok(result) => Message {
-                                    jsonrpc: "2.0".to_string(),
-                                    id: Some(id),
-                                    method: None,
-                                    params: None,
-                                    result: Some(result),
-                                    error: None,
-                                },
-                                Err(e) => Message {
-                                    jsonrpc: "2.0".to_string(),
-                                    id: Some(id),
-                                    method: None,
-                                    params: None,
-                                    result: None,
-                                    error: Some(super::protocol::ResponseError {
-                                        code: -32603, // Internal error
-                                        message: e.to_string(),
-                                    }),
-                                },
-                            };
-
                             // Send response back to server
-                            write_message(&mut writer, &response).await?;
-                        } else {
-                            // This is a notification
-                            let method_name = method.clone();
-                            let params = server_msg.params.clone().unwrap_or(Value::Null);
+                            if let Err(e) = write_message(&mut writer, &response_message(id, result)).await {
+                                break Err(e);
+                            }
+                        }
+
+                        // A response to one of our requests
+                        (Some(id), None) => {
+                            if let Some(tx) = response_channels.remove(&id.to_string()) {
+                                if let Some(error) = server_msg.error {
+                                    // Send the error to the waiting task
+                                    let _ = tx.send(Err(anyhow::Error::new(error)));
+                                } else if let Some(result) = server_msg.result {
+                                    // Send the result to the waiting task
+                                    let _ = tx.send(Ok(result));
+                                } else {
+                                    // No result or error
+                                    let _ = tx.send(Err(anyhow!("LSP response has neither result nor error")));
+                                }
+                            }
+                        }
+
+                        // A notification
+                        (None, Some(method)) => {
+                            let params = server_msg.params.unwrap_or(Value::Null);
 
                             // Look up handler
                             let handlers = client.notification_handlers.read().unwrap();
-                            if let Some(handler) = handlers.get(&method_name) {
+                            if let Some(handler) = handlers.get(&method) {
                                 if let Err(e) = handler(params) {
-                                    error!("[LSP] Error handling notification {}: {}", method_name, e);
+                                    error!("[LSP] Error handling notification {}: {}", method, e);
                                 }
                             } else {
-                                debug!("[LSP] No handler for notification: {}", method_name);
+                                debug!("[LSP] No handler for notification: {}", method);
                             }
                         }
+
+                        (None, None) => debug!("[LSP] Ignoring message without id or method"),
                     }
                 }
 
                 // If both channels are closed, break the loop
-                else => break,
+                else => break Ok(()),
             }
+        };
+
+        // Stop accepting new messages, then flush notifications that were queued
+        // before the loop exited and fail requests that can no longer be answered
+        rx.close();
+        while let Ok(client_msg) = rx.try_recv() {
+            match client_msg {
+                ClientMessage::Notification { method, params } if result.is_ok() => {
+                    let msg = notification_message(method, params);
+                    if let Err(e) = write_message(&mut writer, &msg).await {
+                        warn!("[LSP] Failed to flush notification: {}", e);
+                    }
+                }
+                ClientMessage::Request { response_tx, .. } => {
+                    let _ = response_tx.send(Err(anyhow!(SHUTTING_DOWN)));
+                }
+                _ => {}
+            }
+        }
+        let _ = writer.flush().await;
+
+        if !response_channels.is_empty() {
+            debug!(
+                "[LSP] Failing {} pending requests on shutdown",
+                response_channels.len()
+            );
+        }
+        for (_, tx) in response_channels.drain() {
+            let _ = tx.send(Err(anyhow!(SHUTTING_DOWN)));
         }
 
         info!("[LSP] Message loop terminated");
-        Ok(())
+        result
+    }
+}
+
+/// Counts a request as pending until dropped, also when the caller stops
+/// waiting for it
+struct PendingRequest<'a>(&'a AtomicUsize);
+
+impl<'a> PendingRequest<'a> {
+    fn new(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter)
+    }
+}
+
+impl Drop for PendingRequest<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Describes a folder of the workspace, named after its last component
+fn workspace_folder(folder: &Path) -> WorkspaceFolder {
+    WorkspaceFolder {
+        uri: to_uri(folder),
+        name: folder
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "workspace".to_string()),
     }
 }
 
@@ -701,6 +2760,138 @@ fn to_uri(path: &Path) -> DocumentUri {
         .unwrap_or_else(|_| panic!("Failed to convert path to URI: {}", path.display()))
 }
 
+/// Builds the answer to a request from the server
+fn response_message(id: MessageID, result: Result<Value>) -> Message {
+    match result {
+        Ok(result) => Message {
+            jsonrpc: "2.0".to_string(),
+            id: Some(id),
+            method: None,
+            params: None,
+            result: Some(result),
+            error: None,
+        },
+        Err(e) => Message {
+            jsonrpc: "2.0".to_string(),
+            id: Some(id),
+            method: None,
+            params: None,
+            result: None,
+            error: Some(super::protocol::ResponseError {
+                code: -32603, // Internal error
+                message: e.to_string(),
+            }),
+        },
+    }
+}
+
+/// Builds an outgoing notification message
+fn notification_message(method: String, params: Value) -> Message {
+    Message {
+        jsonrpc: "2.0".to_string(),
+        id: None,
+        method: Some(method),
+        params: Some(params),
+        result: None,
+        error: None,
+    }
+}
+
+/// Extracts the document URI and version a didChange notification applies to
+fn change_target(params: &Value) -> Option<(String, i32)> {
+    let document = params.get("textDocument")?;
+    let uri = document.get("uri")?.as_str()?.to_string();
+    let version = document.get("version")?.as_i64()? as i32;
+    Some((uri, version))
+}
+
+/// Checks whether a didChange notification replaces the whole document, so
+/// the changes before it no longer matter
+fn replaces_document(params: &Value) -> bool {
+    params
+        .get("contentChanges")
+        .and_then(Value::as_array)
+        .is_some_and(|changes| changes.iter().any(|c| c.get("range").is_none()))
+}
+
+/// Builds a didChange notification carrying the full document content
+fn full_change(uri: &str, version: i32, text: &str) -> Value {
+    serde_json::json!({
+        "textDocument": { "uri": uri, "version": version },
+        "contentChanges": [{ "text": text }],
+    })
+}
+
+/// Appends the content changes of a newer didChange notification to a parked
+/// one and takes over its version
+fn merge_change(parked: &mut Value, newer: Value) {
+    if let Some(changes) = parked
+        .get_mut("contentChanges")
+        .and_then(Value::as_array_mut)
+        && let Some(newer_changes) = newer.get("contentChanges").and_then(Value::as_array)
+    {
+        changes.extend(newer_changes.iter().cloned());
+    }
+    if let Some(version) = newer.pointer("/textDocument/version") {
+        parked["textDocument"]["version"] = version.clone();
+    }
+}
+
+/// Converts a progress token into a map key
+fn token_key(token: &ProgressToken) -> String {
+    match token {
+        NumberOrString::Number(n) => n.to_string(),
+        NumberOrString::String(s) => s.clone(),
+    }
+}
+
+/// Chooses the language id to send in didOpen given a server's document selector.
+///
+/// Returns `detected` when a filter accepts it, otherwise the language of a
+/// filter whose pattern matches the file, or `None` if no filter matches.
+pub fn select_language_id(
+    selector: &[DocumentFilter],
+    path: &Path,
+    detected: &str,
+) -> Option<String> {
+    let mut candidate = None;
+
+    for filter in selector {
+        if filter
+            .scheme
+            .as_deref()
+            .is_some_and(|scheme| scheme != "file")
+        {
+            continue;
+        }
+
+        let pattern_matches = match &filter.pattern {
+            Some(pattern) => match Glob::new(pattern) {
+                Ok(glob) => glob.compile_matcher().is_match(path),
+                Err(e) => {
+                    debug!("[LSP] Invalid document selector pattern {}: {}", pattern, e);
+                    false
+                }
+            },
+            None => true,
+        };
+        if !pattern_matches {
+            continue;
+        }
+
+        match &filter.language {
+            None => return Some(detected.to_string()),
+            Some(language) if language == detected => return Some(detected.to_string()),
+            Some(language) if filter.pattern.is_some() => {
+                candidate.get_or_insert_with(|| language.clone());
+            }
+            Some(_) => {}
+        }
+    }
+
+    candidate
+}
+
 /// Detects the language ID for a file based on its extension
 fn detect_language_id(path: &Path) -> String {
     match path.extension().and_then(|e| e.to_str()) {
@@ -720,3 +2911,53 @@ fn detect_language_id(path: &Path) -> String {
     }
     .to_string()
 }
+
+/// Fills in a missing diagnostic source with the name of the server that reported it
+fn tag_diagnostics(
+    mut diagnostics: Vec<lsp_types::Diagnostic>,
+    server: &str,
+) -> Vec<lsp_types::Diagnostic> {
+    for diagnostic in &mut diagnostics {
+        if diagnostic.source.is_none() {
+            diagnostic.source = Some(server.to_string());
+        }
+    }
+    diagnostics
+}
+
+/// Removes diagnostics that share a range and message with an earlier one, merging
+/// their sources so the remaining diagnostic lists every server that reported it
+pub fn dedupe_diagnostics(diagnostics: Vec<lsp_types::Diagnostic>) -> Vec<lsp_types::Diagnostic> {
+    let mut merged: Vec<lsp_types::Diagnostic> = Vec::with_capacity(diagnostics.len());
+
+    for diagnostic in diagnostics {
+        let existing = merged
+            .iter_mut()
+            .find(|d| d.range == diagnostic.range && d.message.trim() == diagnostic.message.trim());
+
+        match existing {
+            Some(existing) => {
+                if let Some(source) = diagnostic.source {
+                    match &mut existing.source {
+                        Some(sources) => {
+                            if !sources.split(", ").any(|s| s == source) {
+                                sources.push_str(", ");
+                                sources.push_str(&source);
+                            }
+                        }
+                        None => existing.source = Some(source),
+                    }
+                }
+                // Keep the most severe level reported by any server
+                if let Some(severity) = diagnostic.severity
+                    && existing.severity.is_none_or(|s| severity < s)
+                {
+                    existing.severity = Some(severity);
+                }
+            }
+            None => merged.push(diagnostic),
+        }
+    }
+
+    merged
+}
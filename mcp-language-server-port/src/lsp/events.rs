@@ -0,0 +1,46 @@
+use lsp_types::{FileEvent, Url};
+use serde::Serialize;
+
+/// Capacity of the event channel; slow subscribers miss the oldest events
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// Events emitted by the proxy for applications embedding the crate
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProxyEvent {
+    /// The language server finished its initialization handshake
+    ServerInitialized { server: String },
+    /// The language server failed to initialize
+    ServerFailed { server: String, error: String },
+    /// The language server was replaced by a fresh process, and the documents
+    /// open in the old one were reopened
+    ServerRestarted { server: String, reopened: usize },
+    /// The server published a new set of diagnostics for a document
+    DiagnosticsUpdated { uri: Url, count: usize },
+    /// A document was opened in the language server
+    DocumentOpened { uri: Url },
+    /// A document was closed in the language server
+    DocumentClosed { uri: Url },
+    /// Edits were written to a file on disk
+    EditApplied { uri: Url, edits: usize },
+    /// The server reported work done progress, such as indexing. `title` is
+    /// empty for reports on work that began before the client was listening.
+    Progress {
+        token: String,
+        title: String,
+        message: Option<String>,
+        percentage: Option<u32>,
+        done: bool,
+    },
+    /// The file watcher saw files in the workspace change, appear or
+    /// disappear, leaving out ignored files
+    FilesChanged { changes: Vec<FileEvent> },
+    /// The file watcher stopped watching the workspace
+    WatcherStopped,
+    /// The file watcher holds back changes until it is resumed
+    WatcherPaused,
+    /// The file watcher passes on changes again, starting with those held back
+    WatcherResumed,
+    /// The language server is shutting down
+    ShuttingDown { server: String },
+}
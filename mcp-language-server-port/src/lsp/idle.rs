@@ -0,0 +1,115 @@
+use log::{error, info};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::client::{Client, InitState};
+
+/// What to release after a period without tool calls
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IdlePolicy {
+    /// How long the proxy has to go without tool calls before shedding resources
+    pub timeout: Duration,
+    /// Also stop the language server process, restarting it on the next call
+    pub stop_server: bool,
+}
+
+/// Releases language server resources while the proxy is idle.
+///
+/// Every tool call counts as activity. Once the idle timeout passes, open
+/// documents are closed and cached diagnostics dropped; with `stop_server`
+/// the server process is shut down too and restarted by the next tool call.
+pub struct IdleMonitor {
+    client: Arc<Client>,
+    policy: IdlePolicy,
+    last_activity: Mutex<Instant>,
+    // Held while restarting so concurrent tool calls start the server once
+    restart_lock: tokio::sync::Mutex<()>,
+}
+
+impl IdleMonitor {
+    /// Creates a monitor for a client and starts watching for idle periods
+    pub fn start(client: Arc<Client>, policy: IdlePolicy) -> Arc<Self> {
+        let monitor = Arc::new(Self {
+            client,
+            policy,
+            last_activity: Mutex::new(Instant::now()),
+            restart_lock: tokio::sync::Mutex::new(()),
+        });
+
+        let idle_monitor = Arc::clone(&monitor);
+        tokio::spawn(async move {
+            idle_monitor.run().await;
+        });
+
+        monitor
+    }
+
+    /// Records activity, postponing the next idle shed
+    pub fn touch(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    /// Records activity and restarts the server if it was stopped while idle
+    pub async fn wake(&self) -> anyhow::Result<()> {
+        self.touch();
+
+        let _guard = self.restart_lock.lock().await;
+        if self.client.init_state() == InitState::Stopped {
+            info!("[LSP] Restarting language server after idle period");
+            self.client.restart().await?;
+        }
+        Ok(())
+    }
+
+    /// Time left until the proxy counts as idle
+    fn remaining(&self) -> Duration {
+        self.policy
+            .timeout
+            .saturating_sub(self.last_activity.lock().unwrap().elapsed())
+    }
+
+    async fn run(&self) {
+        let mut shed = false;
+
+        loop {
+            let remaining = self.remaining();
+            if !remaining.is_zero() {
+                // Activity since the last shed means resources are in use again
+                shed = false;
+                tokio::time::sleep(remaining).await;
+                continue;
+            }
+
+            if !shed {
+                self.shed().await;
+                shed = true;
+            }
+            tokio::time::sleep(self.policy.timeout).await;
+        }
+    }
+
+    /// Closes documents, drops caches and optionally stops the server
+    async fn shed(&self) {
+        let _guard = self.restart_lock.lock().await;
+        if self.client.init_state() != InitState::Ready {
+            return;
+        }
+
+        info!(
+            "[LSP] No tool calls for {}s, releasing language server resources",
+            self.policy.timeout.as_secs()
+        );
+
+        if self.policy.stop_server {
+            if let Err(e) = self.client.stop().await {
+                error!("[LSP] Failed to stop idle language server: {}", e);
+            }
+            return;
+        }
+
+        if let Err(e) = self.client.close_all_files().await {
+            error!("[LSP] Failed to close idle documents: {}", e);
+        }
+        self.client.drop_caches();
+    }
+}
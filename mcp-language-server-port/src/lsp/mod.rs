@@ -1,5 +1,22 @@
 pub mod client;
+pub mod events;
+pub mod idle;
+pub mod position;
+pub mod presets;
 pub mod protocol;
+pub mod retry;
+pub mod router;
+pub mod settings;
+pub mod sync;
+pub mod timeout;
 pub mod transport;
+pub mod versions;
 
-pub use client::Client;
+pub use client::{Client, InitState, ProcessOptions, ServerAddress};
+pub use events::ProxyEvent;
+pub use idle::{IdleMonitor, IdlePolicy};
+pub use position::PositionEncoding;
+pub use retry::RetryPolicy;
+pub use router::{LanguageFilter, Router};
+pub use settings::Settings;
+pub use timeout::TimeoutPolicy;
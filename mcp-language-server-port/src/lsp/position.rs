@@ -0,0 +1,84 @@
+use lsp_types::{Position, PositionEncodingKind};
+
+/// How the columns of LSP positions are counted, as negotiated with the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PositionEncoding {
+    /// Bytes of UTF-8, which servers such as clangd can use
+    Utf8,
+    /// UTF-16 code units, the protocol's default
+    #[default]
+    Utf16,
+}
+
+impl PositionEncoding {
+    /// Gets the encoding a server chose in its capabilities. Servers that
+    /// don't choose use UTF-16.
+    pub fn from_kind(kind: Option<&PositionEncodingKind>) -> Self {
+        match kind.map(PositionEncodingKind::as_str) {
+            Some("utf-8") => PositionEncoding::Utf8,
+            _ => PositionEncoding::Utf16,
+        }
+    }
+
+    fn units(self, c: char) -> u32 {
+        match self {
+            PositionEncoding::Utf8 => c.len_utf8() as u32,
+            PositionEncoding::Utf16 => c.len_utf16() as u32,
+        }
+    }
+}
+
+/// Converts an LSP column into a byte index into `line`. Columns past the
+/// end of the line or inside a character are clamped to the nearest
+/// character boundary before them.
+pub fn byte_column(line: &str, character: u32, encoding: PositionEncoding) -> usize {
+    let mut units = 0;
+    for (index, c) in line.char_indices() {
+        units += encoding.units(c);
+        if units > character {
+            return index;
+        }
+    }
+    line.len()
+}
+
+/// Converts a byte index into `line` into an LSP column
+pub fn column(line: &str, index: usize, encoding: PositionEncoding) -> u32 {
+    line[..index.min(line.len())]
+        .chars()
+        .map(|c| encoding.units(c))
+        .sum()
+}
+
+/// Converts an LSP column into a count of characters, for aligning a marker
+/// under the line
+pub fn char_column(line: &str, character: u32, encoding: PositionEncoding) -> usize {
+    line[..byte_column(line, character, encoding)]
+        .chars()
+        .count()
+}
+
+/// Gets the byte offset of a position in `content`, or `None` if the line
+/// doesn't exist. The column is clamped to the end of its line, not
+/// counting the line break.
+pub fn offset_at(content: &str, position: Position, encoding: PositionEncoding) -> Option<usize> {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        line_start += content[line_start..].find('\n')? + 1;
+    }
+
+    let rest = &content[line_start..];
+    let line = rest.find('\n').map_or(rest, |end| &rest[..end]);
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    Some(line_start + byte_column(line, position.character, encoding))
+}
+
+/// Gets the position of a byte offset in `content`
+pub fn position_at(content: &str, offset: usize, encoding: PositionEncoding) -> Position {
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: column(&before[line_start..], offset - line_start, encoding),
+    }
+}
@@ -0,0 +1,47 @@
+use serde_json::{Value, json};
+use std::path::Path;
+
+/// Returns the initialization options sent to a known language server unless
+/// others are configured; unknown servers get none
+pub fn initialization_options(lsp_command: &str) -> Option<Value> {
+    let name = Path::new(lsp_command)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(lsp_command);
+
+    match name {
+        "gopls" => Some(json!({
+            "codelenses": {
+                "generate": true,
+                "regenerate_cgo": true,
+                "test": true,
+                "tidy": true,
+                "upgrade_dependency": true,
+                "vendor": true,
+                "vulncheck": false,
+            }
+        })),
+        "rust-analyzer" => Some(json!({
+            "cargo": { "buildScripts": { "enable": true } },
+            "procMacro": { "enable": true },
+        })),
+        "pyright-langserver" | "pyright" => Some(json!({
+            "python": {
+                "analysis": {
+                    "autoSearchPaths": true,
+                    "useLibraryCodeForTypes": true,
+                }
+            }
+        })),
+        "typescript-language-server" | "tsserver" => Some(json!({
+            "hostInfo": "mcp-language-server",
+            "preferences": {
+                "includeCompletionsForModuleExports": true,
+            }
+        })),
+        "clangd" => Some(json!({
+            "clangdFileStatus": true,
+        })),
+        _ => None,
+    }
+}
@@ -41,6 +41,12 @@ impl fmt::Display for MessageID {
     }
 }
 
+/// Error code for a request whose result was invalidated by a document change
+pub const CONTENT_MODIFIED: i32 = -32801;
+
+/// Error code for a request the server cancelled itself, e.g. to restart work
+pub const SERVER_CANCELLED: i32 = -32802;
+
 /// ResponseError represents a JSON-RPC 2.0 error
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResponseError {
@@ -48,6 +54,22 @@ pub struct ResponseError {
     pub message: String,
 }
 
+impl ResponseError {
+    /// Checks whether the request may succeed if sent again, as the LSP spec
+    /// expects for results invalidated by edits
+    pub fn is_transient(&self) -> bool {
+        matches!(self.code, CONTENT_MODIFIED | SERVER_CANCELLED)
+    }
+}
+
+impl fmt::Display for ResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LSP error: {} (code: {})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for ResponseError {}
+
 /// Message represents a JSON-RPC 2.0 message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
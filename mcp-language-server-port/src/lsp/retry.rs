@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use super::protocol::ResponseError;
+
+/// How requests failing with transient errors are retried.
+///
+/// Servers answer ContentModified or ServerCancelled when an edit lands while
+/// they compute a result, which is routine during rapid edit sequences. Such
+/// requests are sent again after a backoff that doubles on every attempt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 surfaces transient errors right away
+    pub max_retries: u32,
+    /// Delay before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between retries
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries
+    #[allow(dead_code)]
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Gets the delay before retry number `retry`, counting from 1
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Checks whether an error from a request is a transient server error worth retrying
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<ResponseError>()
+        .is_some_and(ResponseError::is_transient)
+}
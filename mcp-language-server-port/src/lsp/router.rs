@@ -0,0 +1,304 @@
+use anyhow::{Context, Result, anyhow};
+use log::{debug, error, info};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use super::{Client, ProcessOptions};
+
+/// The files a language server handles, as file extensions or language ids.
+/// An empty filter matches no file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LanguageFilter {
+    patterns: Vec<String>,
+}
+
+impl LanguageFilter {
+    /// Creates a filter from extensions and language ids, like `rs` or `rust`
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        Self {
+            patterns: patterns
+                .iter()
+                .map(|pattern| pattern.as_ref().trim().trim_start_matches('.').to_string())
+                .filter(|pattern| !pattern.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Parses a comma-separated list of extensions and language ids
+    pub fn parse(value: &str) -> Self {
+        Self::new(&value.split(',').collect::<Vec<_>>())
+    }
+
+    /// Gets the files a known language server handles; unknown servers get
+    /// an empty filter
+    pub fn for_command(command: &str) -> Self {
+        let name = Path::new(command)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(command);
+
+        match name {
+            "rust-analyzer" => Self::new(&["rust"]),
+            "gopls" => Self::new(&["go", "mod", "work"]),
+            "pyright-langserver" | "pyright" | "pylsp" | "ruff-lsp" => Self::new(&["python"]),
+            "typescript-language-server" | "tsserver" => {
+                Self::new(&["typescript", "javascript", "tsx", "jsx", "mjs", "cjs"])
+            }
+            "clangd" => Self::new(&["c", "cpp"]),
+            _ => Self::default(),
+        }
+    }
+
+    /// Checks whether a file with the given language id is handled
+    pub fn matches(&self, file_path: &Path, language_id: &str) -> bool {
+        let extension = file_path.extension().and_then(|e| e.to_str());
+        self.patterns
+            .iter()
+            .any(|pattern| pattern == language_id || Some(pattern.as_str()) == extension)
+    }
+}
+
+/// How a server that starts on first use is launched
+struct Launch {
+    command: String,
+    args: Vec<String>,
+    options: ProcessOptions,
+}
+
+/// A server and the files it handles
+struct Route {
+    filter: LanguageFilter,
+    client: RwLock<Option<Arc<Client>>>,
+    // Set for servers started on first use, which may also be stopped
+    launch: Option<Launch>,
+    last_used: Mutex<Instant>,
+    // Held while starting or stopping so concurrent calls start the server once
+    start_lock: tokio::sync::Mutex<()>,
+}
+
+impl Route {
+    fn new(filter: LanguageFilter, client: Option<Arc<Client>>, launch: Option<Launch>) -> Self {
+        Self {
+            filter,
+            client: RwLock::new(client),
+            launch,
+            last_used: Mutex::new(Instant::now()),
+            start_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn running(&self) -> Option<Arc<Client>> {
+        self.client.read().unwrap().clone()
+    }
+}
+
+/// Sets up a newly started server like the others
+type Setup = Arc<dyn Fn(&Client) + Send + Sync>;
+
+/// Language servers for the languages of a polyglot workspace, each handling
+/// the files its filter matches. The first server is the primary one: it
+/// answers requests that aren't about a file, and handles the files no filter
+/// matches.
+///
+/// Servers added with [`Router::with_lazy_server`] are only started once a
+/// file of their language is first needed.
+#[derive(Clone)]
+pub struct Router {
+    primary: Arc<Client>,
+    routes: Vec<Arc<Route>>,
+    // Shared by clones, as it may only become known once serving
+    workspace: Arc<RwLock<Option<PathBuf>>>,
+    setup: Option<Setup>,
+}
+
+impl Router {
+    /// Creates a router with one server handling every file
+    pub fn new(primary: Arc<Client>) -> Self {
+        Self {
+            routes: vec![Arc::new(Route::new(
+                LanguageFilter::default(),
+                Some(Arc::clone(&primary)),
+                None,
+            ))],
+            primary,
+            workspace: Arc::new(RwLock::new(None)),
+            setup: None,
+        }
+    }
+
+    /// Adds a server for the files `filter` matches; earlier servers take
+    /// precedence
+    pub fn with_server(mut self, filter: LanguageFilter, client: Arc<Client>) -> Self {
+        self.routes
+            .push(Arc::new(Route::new(filter, Some(client), None)));
+        self
+    }
+
+    /// Adds a server for the files `filter` matches that is started when one
+    /// of them is first needed
+    pub fn with_lazy_server(
+        mut self,
+        filter: LanguageFilter,
+        command: &str,
+        args: &[String],
+        options: &ProcessOptions,
+    ) -> Self {
+        let launch = Launch {
+            command: command.to_string(),
+            args: args.to_vec(),
+            options: options.clone(),
+        };
+        self.routes
+            .push(Arc::new(Route::new(filter, None, Some(launch))));
+        self
+    }
+
+    /// Sets the workspace lazily started servers are initialized with, if
+    /// known yet, and how they are set up before that
+    pub fn with_launcher(
+        mut self,
+        workspace: Option<PathBuf>,
+        setup: impl Fn(&Client) + Send + Sync + 'static,
+    ) -> Self {
+        *self.workspace.write().unwrap() = workspace;
+        self.setup = Some(Arc::new(setup));
+        self
+    }
+
+    /// Sets the files the primary server handles ahead of later servers
+    pub fn with_primary_filter(mut self, filter: LanguageFilter) -> Self {
+        let client = self.routes[0].running();
+        self.routes[0] = Arc::new(Route::new(filter, client, None));
+        self
+    }
+
+    /// Sets the workspace lazily started servers are initialized with, once
+    /// it is known
+    pub fn set_workspace(&self, workspace: PathBuf) {
+        *self.workspace.write().unwrap() = Some(workspace);
+    }
+
+    /// Gets the workspace lazily started servers are initialized with
+    pub fn workspace(&self) -> Option<PathBuf> {
+        self.workspace.read().unwrap().clone()
+    }
+
+    /// Gets the primary server
+    pub fn primary(&self) -> &Arc<Client> {
+        &self.primary
+    }
+
+    /// Gets every running server, the primary first
+    pub fn clients(&self) -> Vec<Arc<Client>> {
+        self.routes
+            .iter()
+            .filter_map(|route| route.running())
+            .collect()
+    }
+
+    /// Checks whether every server is running, none waiting to be started
+    pub fn all_running(&self) -> bool {
+        self.routes.iter().all(|route| route.running().is_some())
+    }
+
+    /// Gets the server that handles a file, starting it if it isn't running
+    pub async fn client_for(&self, file_path: &Path) -> Result<Arc<Client>> {
+        // Every server is set up with the same language id overrides
+        let language_id = self.primary.language_id(file_path);
+        let route = self
+            .routes
+            .iter()
+            .find(|route| route.filter.matches(file_path, &language_id))
+            .unwrap_or(&self.routes[0]);
+        *route.last_used.lock().unwrap() = Instant::now();
+
+        let client = match route.running() {
+            Some(client) => client,
+            None => self.start(route).await?,
+        };
+        debug!("[LSP] Routing {} to {}", file_path.display(), client.name());
+        Ok(client)
+    }
+
+    /// Starts and initializes the server of a route
+    async fn start(&self, route: &Route) -> Result<Arc<Client>> {
+        let _guard = route.start_lock.lock().await;
+        if let Some(client) = route.running() {
+            return Ok(client);
+        }
+
+        let launch = route
+            .launch
+            .as_ref()
+            .ok_or_else(|| anyhow!("The language server is not running"))?;
+        let workspace = self
+            .workspace()
+            .ok_or_else(|| anyhow!("No workspace to start {} in", launch.command))?;
+
+        info!("[LSP] Starting {} on first use", launch.command);
+        let client = Client::with_options(&launch.command, &launch.args, &launch.options)
+            .await
+            .context(format!("Failed to create LSP client: {}", launch.command))?;
+        if let Some(setup) = &self.setup {
+            setup(&client);
+        }
+        // Folders added to the workspace since it started apply to it too
+        for folder in self.primary.workspace_folders() {
+            if folder == workspace {
+                continue;
+            }
+            client.add_workspace_folder(&folder).await?;
+        }
+        client.initialize(&workspace).await?;
+
+        *route.client.write().unwrap() = Some(Arc::clone(&client));
+        Ok(client)
+    }
+
+    /// Shuts down lazily started servers once no call has needed them for
+    /// `idle`; they start again when next needed
+    pub fn stop_idle_servers(&self, idle: Duration) {
+        let routes: Vec<Arc<Route>> = self
+            .routes
+            .iter()
+            .filter(|route| route.launch.is_some())
+            .cloned()
+            .collect();
+        if routes.is_empty() {
+            return;
+        }
+
+        tokio::spawn(async move {
+            loop {
+                let mut next_check = idle;
+                for route in &routes {
+                    let remaining = idle.saturating_sub(route.last_used.lock().unwrap().elapsed());
+                    if !remaining.is_zero() {
+                        next_check = next_check.min(remaining);
+                    } else if route.running().is_some() {
+                        stop_route(route, idle).await;
+                    }
+                }
+                tokio::time::sleep(next_check).await;
+            }
+        });
+    }
+}
+
+/// Shuts down the server of a route that went unused
+async fn stop_route(route: &Route, idle: Duration) {
+    let _guard = route.start_lock.lock().await;
+    let Some(client) = route.client.write().unwrap().take() else {
+        return;
+    };
+
+    info!(
+        "[LSP] No calls for {} in {}s, shutting it down",
+        client.name(),
+        idle.as_secs()
+    );
+    if let Err(e) = client.shutdown().await {
+        error!("[LSP] Failed to shut down {}: {}", client.name(), e);
+    }
+}
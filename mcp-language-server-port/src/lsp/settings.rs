@@ -0,0 +1,111 @@
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Settings the client hands out in answer to `workspace/configuration`.
+///
+/// Loaded from a JSON file of the form
+///
+/// ```json
+/// {
+///   "settings": { "gopls": { "staticcheck": true } },
+///   "scopes": { "services/api": { "gopls": { "buildFlags": ["-tags=integration"] } } }
+/// }
+/// ```
+///
+/// Scopes are directories relative to the workspace root whose settings
+/// override the global ones for files below them. Sections are looked up by
+/// dotted path, so `gopls.staticcheck` and `gopls` both resolve.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    settings: Map<String, Value>,
+    #[serde(default)]
+    scopes: HashMap<PathBuf, Map<String, Value>>,
+}
+
+impl Settings {
+    /// Reads settings from a JSON file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read settings file {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid settings file {}", path.display()))
+    }
+
+    /// Sets a global setting by dotted path, creating the objects on the way
+    pub fn set(&mut self, key: &str, value: Value) -> Result<()> {
+        let mut parts = key.split('.').peekable();
+        let mut object = &mut self.settings;
+        while let Some(part) = parts.next() {
+            if part.is_empty() {
+                return Err(anyhow!("Invalid setting name '{}'", key));
+            }
+            if parts.peek().is_none() {
+                object.insert(part.to_string(), value);
+                return Ok(());
+            }
+            let entry = object
+                .entry(part.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !entry.is_object() {
+                *entry = Value::Object(Map::new());
+            }
+            object = entry.as_object_mut().unwrap();
+        }
+        Err(anyhow!("Invalid setting name '{}'", key))
+    }
+
+    /// Resolves a configuration item: the settings for `scope` (a file or
+    /// directory in the workspace at `root`), narrowed to `section`. Unknown
+    /// sections resolve to null, as the protocol asks.
+    pub fn resolve(
+        &self,
+        section: Option<&str>,
+        scope: Option<&Path>,
+        root: Option<&Path>,
+    ) -> Value {
+        let mut settings = Value::Object(self.settings.clone());
+
+        // Broader scopes apply first so the innermost directory wins
+        if let (Some(scope), Some(root)) = (scope, root) {
+            let mut matching: Vec<(&PathBuf, &Map<String, Value>)> = self
+                .scopes
+                .iter()
+                .filter(|(directory, _)| scope.starts_with(root.join(directory)))
+                .collect();
+            matching.sort_by_key(|(directory, _)| directory.components().count());
+            for (_, overrides) in matching {
+                merge(&mut settings, Value::Object(overrides.clone()));
+            }
+        }
+
+        let Some(section) = section.filter(|section| !section.is_empty()) else {
+            return settings;
+        };
+        section
+            .split('.')
+            .try_fold(&settings, |value, part| value.get(part))
+            .cloned()
+            .unwrap_or(Value::Null)
+    }
+}
+
+/// Merges `overrides` into `base`, recursing into objects present in both
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
@@ -0,0 +1,65 @@
+use lsp_types::{Range, TextDocumentContentChangeEvent};
+
+use super::position::{PositionEncoding, position_at};
+
+/// Computes the change that turns `old` into `new` as a single edit of the
+/// range where they differ, or `None` if they are equal.
+///
+/// Large files usually change in one place between syncs, so this sends a
+/// few lines instead of the whole document. Columns are counted in the
+/// encoding negotiated with the server.
+pub fn content_change(
+    old: &str,
+    new: &str,
+    encoding: PositionEncoding,
+) -> Option<TextDocumentContentChangeEvent> {
+    if old == new {
+        return None;
+    }
+
+    // Longest common prefix; equal characters keep both offsets on a boundary
+    let mut prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .find(|((_, old_char), new_char)| old_char != new_char)
+        .map(|((index, _), _)| index)
+        .unwrap_or_else(|| old.len().min(new.len()));
+
+    // Longest common suffix that doesn't overlap the prefix
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let mut suffix = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(old_char, new_char)| old_char == new_char)
+        .map(|(old_char, _)| old_char.len_utf8())
+        .scan(0, |length, char_length| {
+            *length += char_length;
+            Some(*length)
+        })
+        .take_while(|length| *length <= max_suffix)
+        .last()
+        .unwrap_or(0);
+
+    // Don't split a CRLF line break, which counts as one
+    if old[..prefix].ends_with('\r') {
+        prefix -= 1;
+    }
+    if suffix > 0
+        && old[old.len() - suffix..].starts_with('\n')
+        && old[..old.len() - suffix].ends_with('\r')
+    {
+        suffix -= 1;
+    }
+
+    let old_end = old.len() - suffix;
+    let new_end = new.len() - suffix;
+    Some(TextDocumentContentChangeEvent {
+        range: Some(Range {
+            start: position_at(old, prefix, encoding),
+            end: position_at(old, old_end, encoding),
+        }),
+        range_length: None,
+        text: new[prefix..new_end].to_string(),
+    })
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// Timeout for requests without a method-specific one
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long requests wait for the server to answer before they are cancelled.
+///
+/// A zero timeout waits forever, which is what every request did before
+/// timeouts were introduced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeoutPolicy {
+    /// Timeout for methods without an override
+    pub default: Duration,
+    /// Timeouts for specific methods, e.g. a longer one for `workspace/symbol`
+    pub methods: HashMap<String, Duration>,
+}
+
+impl Default for TimeoutPolicy {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_TIMEOUT,
+            methods: HashMap::new(),
+        }
+    }
+}
+
+impl TimeoutPolicy {
+    /// Sets the timeout for one method
+    pub fn with_method(mut self, method: &str, timeout: Duration) -> Self {
+        self.methods.insert(method.to_string(), timeout);
+        self
+    }
+
+    /// Gets the timeout for a method, or `None` if its requests never time out
+    pub fn for_method(&self, method: &str) -> Option<Duration> {
+        let timeout = self.methods.get(method).copied().unwrap_or(self.default);
+        (!timeout.is_zero()).then_some(timeout)
+    }
+}
+
+/// Error returned when the server doesn't answer a request in time. The
+/// request is cancelled with `$/cancelRequest` before this is returned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestTimeout {
+    pub method: String,
+    pub timeout: Duration,
+}
+
+impl Display for RequestTimeout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "LSP request {} timed out after {:?}; the language server may be hung or busy",
+            self.method, self.timeout
+        )
+    }
+}
+
+impl std::error::Error for RequestTimeout {}
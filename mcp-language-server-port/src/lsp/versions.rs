@@ -0,0 +1,175 @@
+use anyhow::{Context, Result, anyhow};
+use log::{debug, warn};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use tokio::process::Command;
+
+/// A language server version as `major.minor.patch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ServerVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl ServerVersion {
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl fmt::Display for ServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for ServerVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        parse_version(s)
+            .ok_or_else(|| anyhow!("Invalid version '{}': expected MAJOR.MINOR[.PATCH]", s))
+    }
+}
+
+/// Client behavior changed to avoid a problem in a server version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Workaround {
+    /// Ask for flat document symbols instead of a hierarchy
+    FlatDocumentSymbols,
+}
+
+/// A problem affecting server releases before a fixed version
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KnownIssue {
+    pub fixed_in: ServerVersion,
+    pub description: &'static str,
+    pub workaround: Option<Workaround>,
+}
+
+/// What the proxy expects of a known language server's version
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VersionRequirements {
+    /// Oldest release the proxy is known to work with
+    pub minimum: Option<ServerVersion>,
+    pub issues: &'static [KnownIssue],
+}
+
+/// Returns the version requirements for a known language server
+pub fn version_requirements(lsp_command: &str) -> VersionRequirements {
+    let name = Path::new(lsp_command)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(lsp_command);
+
+    match name {
+        "clangd" => VersionRequirements {
+            minimum: Some(ServerVersion::new(9, 0, 0)),
+            issues: &[KnownIssue {
+                fixed_in: ServerVersion::new(10, 0, 0),
+                description: "does not support hierarchical document symbols",
+                workaround: Some(Workaround::FlatDocumentSymbols),
+            }],
+        },
+        "gopls" => VersionRequirements {
+            minimum: Some(ServerVersion::new(0, 9, 0)),
+            issues: &[],
+        },
+        _ => VersionRequirements {
+            minimum: None,
+            issues: &[],
+        },
+    }
+}
+
+/// Finds the first `MAJOR.MINOR[.PATCH]` version in `--version` output, such as
+/// `clangd version 17.0.6` or `golang.org/x/tools/gopls v0.15.3`
+pub fn parse_version(output: &str) -> Option<ServerVersion> {
+    output
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')' || c == ',')
+        .find_map(|word| {
+            let word = word.trim_start_matches('v');
+            // Drop suffixes like "-standalone" or "+git"
+            let numbers = word.split(['-', '+']).next()?;
+            let mut parts = numbers.split('.');
+            let major = parts.next()?.parse().ok()?;
+            let minor = parts.next()?.parse().ok()?;
+            let patch = match parts.next() {
+                Some(patch) => patch.parse().ok()?,
+                None => 0,
+            };
+            Some(ServerVersion::new(major, minor, patch))
+        })
+}
+
+/// Runs `<command> --version` and parses the version it prints
+pub async fn detect_version(lsp_command: &str) -> Result<ServerVersion> {
+    let output = Command::new(lsp_command)
+        .arg("--version")
+        .output()
+        .await
+        .context(format!("Failed to run {} --version", lsp_command))?;
+
+    // Some servers print their version to stderr
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    debug!("[LSP] {} --version: {}", lsp_command, text.trim());
+
+    parse_version(&text)
+        .ok_or_else(|| anyhow!("No version found in {} --version output", lsp_command))
+}
+
+/// Result of checking a server version against its requirements
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VersionCheck {
+    pub warnings: Vec<String>,
+    pub workarounds: Vec<Workaround>,
+}
+
+/// Checks a server version against the known requirements for `lsp_command`,
+/// collecting warnings and the workarounds to apply
+pub fn check_version(lsp_command: &str, version: ServerVersion) -> VersionCheck {
+    let requirements = version_requirements(lsp_command);
+    let mut check = VersionCheck::default();
+
+    if let Some(minimum) = requirements.minimum
+        && version < minimum
+    {
+        check.warnings.push(format!(
+            "{} {} is older than {}, the oldest version known to work; consider updating it",
+            lsp_command, version, minimum
+        ));
+    }
+
+    for issue in requirements.issues {
+        if version >= issue.fixed_in {
+            continue;
+        }
+
+        let mut warning = format!(
+            "{} {} {} (fixed in {})",
+            lsp_command, version, issue.description, issue.fixed_in
+        );
+        if let Some(workaround) = issue.workaround {
+            warning.push_str("; working around it");
+            if !check.workarounds.contains(&workaround) {
+                check.workarounds.push(workaround);
+            }
+        }
+        check.warnings.push(warning);
+    }
+
+    for warning in &check.warnings {
+        warn!("[LSP] {}", warning);
+    }
+    check
+}
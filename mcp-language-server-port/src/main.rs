@@ -1,19 +1,25 @@
 use anyhow::{Context, Result, anyhow};
-use clap::Parser;
-use std::path::PathBuf;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser};
+use std::path::{Path, PathBuf};
 use std::sync::{
     Arc,
     atomic::{AtomicBool, Ordering},
 };
+use std::time::Duration;
 use tokio::signal::ctrl_c;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 
+mod config;
 mod logging;
 mod lsp;
 mod mcp;
+mod session;
 mod tools;
 mod watcher;
+mod workspace;
 
+use crate::mcp::ready::ReadySignal;
 use crate::watcher::{FileSystemWatcher, WorkspaceWatcher};
 use log::info;
 
@@ -24,37 +30,516 @@ use log::info;
     about = "MCP Language Server: A proxy server for language servers"
 )]
 struct Config {
-    /// Path to workspace directory
+    /// JSON file with defaults for these options and the language servers; options given here override it
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Serve MCP over SSE on this address (e.g. 127.0.0.1:8080) for any number of clients instead of over stdin/stdout
+    #[arg(long, value_name = "ADDRESS")]
+    listen: Option<std::net::SocketAddr>,
+
+    /// Path to workspace directory; defaults to the first root the MCP client lists
     #[arg(long)]
-    workspace: PathBuf,
+    workspace: Option<PathBuf>,
+
+    /// LSP command to run; repeat for other languages, with their args (e.g. --lsp rust-analyzer --lsp "gopls serve"). Tool calls about a file go to the server for its language, others to the first
+    #[arg(long, required_unless_present_any = ["config", "lsp_connect"])]
+    lsp: Vec<String>,
+
+    /// Files a server handles, by language id or extension, replacing the defaults for known servers (repeatable, e.g. gopls=go,mod)
+    #[arg(long = "lsp-languages", value_name = "SERVER=LANGUAGES", value_parser = parse_lsp_languages)]
+    lsp_languages: Vec<(String, lsp::LanguageFilter)>,
+
+    /// Connect to a language server already listening on HOST:PORT or unix:PATH instead of starting the first --lsp, which then only names it
+    #[arg(long, value_name = "ADDRESS", value_parser = parse_server_address)]
+    lsp_connect: Option<lsp::ServerAddress>,
+
+    /// Initialization options for a server as JSON, replacing the preset for known servers (repeatable, e.g. 'gopls={"staticcheck":true}')
+    #[arg(long = "lsp-init-options", value_name = "SERVER=JSON", value_parser = parse_init_options)]
+    lsp_init_options: Vec<(String, serde_json::Value)>,
 
-    /// LSP command to run
+    /// Environment variable set for the language servers (repeatable, e.g. GOFLAGS=-mod=mod)
+    #[arg(long = "lsp-env", value_name = "KEY=VALUE", value_parser = parse_env_var)]
+    lsp_env: Vec<(String, String)>,
+
+    /// Directory the language servers run in instead of the proxy's working directory
+    #[arg(long, value_name = "DIR")]
+    lsp_cwd: Option<PathBuf>,
+
+    /// Start the servers after the first --lsp only when a file of their language is first needed
     #[arg(long)]
-    lsp: String,
+    lazy_lsp: bool,
+
+    /// With --lazy-lsp, shut a server down after this many seconds without calls about its files; it starts again when needed
+    #[arg(long, value_name = "SECONDS", requires = "lazy_lsp")]
+    lsp_idle_shutdown: Option<u64>,
+
+    /// Level of the proxy log: off, error, warn, info, debug or trace (default debug)
+    #[arg(long, value_name = "LEVEL")]
+    log_level: Option<log::LevelFilter>,
+
+    /// Extra gitignore-style pattern for the watcher to ignore (repeatable; "!node_modules/" re-includes a default)
+    #[arg(long = "ignore", value_name = "PATTERN")]
+    ignore_patterns: Vec<String>,
+
+    /// Search parent directories for the project root when the workspace lacks project files
+    #[arg(long)]
+    find_project_root: bool,
+
+    /// Show absolute paths in tool output instead of workspace-relative ones
+    #[arg(long)]
+    absolute_paths: bool,
+
+    /// Whether paths through symlinks keep their symlinked form or resolve to their target
+    #[arg(long, value_name = "resolve|preserve", default_value = "resolve", value_parser = parse_symlink_policy)]
+    symlinks: workspace::paths::SymlinkPolicy,
+
+    /// Approximate token limit for tool responses; longer output is trimmed
+    #[arg(long, value_name = "TOKENS")]
+    max_response_tokens: Option<usize>,
+
+    /// Characters per token used to estimate response size against --max-response-tokens
+    #[arg(long, value_name = "CHARS", default_value_t = tools::budget::DEFAULT_CHARS_PER_TOKEN)]
+    chars_per_token: f64,
+
+    /// Default layout of tool output; tools can override it per request
+    #[arg(long, value_name = "markdown|plain|compact", default_value = "markdown", value_parser = parse_output_style)]
+    output_style: tools::render::OutputStyle,
+
+    /// Close documents and drop caches after this many seconds without tool calls
+    #[arg(long, value_name = "SECONDS")]
+    idle_timeout: Option<u64>,
+
+    /// With --idle-timeout, also stop the language server while idle; it restarts on the next tool call
+    #[arg(long, requires = "idle_timeout")]
+    stop_server_when_idle: bool,
+
+    /// Check the language server's version against known minimums and problem releases
+    #[arg(long)]
+    check_server_version: bool,
+
+    /// Refuse to start unless the language server is at least this version (e.g. 0.15.0)
+    #[arg(long, value_name = "VERSION", value_parser = parse_server_version)]
+    min_server_version: Option<lsp::versions::ServerVersion>,
+
+    /// Override the language id for a file extension (repeatable, e.g. svelte=svelte)
+    #[arg(long = "language-id", value_name = "EXT=LANGUAGE", value_parser = parse_language_override)]
+    language_ids: Vec<(String, String)>,
+
+    /// Trace level requested from the LSP server; traces are written to the proxy log
+    #[arg(long, value_name = "off|messages|verbose", default_value = "off", value_parser = parse_trace)]
+    lsp_trace: lsp_types::TraceValue,
+
+    /// Times to retry requests the server rejects as content modified or cancelled
+    #[arg(long, value_name = "COUNT", default_value_t = lsp::RetryPolicy::default().max_retries)]
+    max_retries: u32,
+
+    /// Seconds tool calls wait for the language server to finish indexing before answering anyway
+    #[arg(long, value_name = "SECONDS", default_value_t = mcp::DEFAULT_READY_TIMEOUT.as_secs())]
+    ready_timeout: u64,
+
+    /// Seconds to wait for the LSP server to answer a request before cancelling it (0 waits forever)
+    #[arg(long, value_name = "SECONDS", default_value_t = lsp::timeout::DEFAULT_TIMEOUT.as_secs())]
+    lsp_timeout: u64,
+
+    /// Timeout for one LSP method, overriding --lsp-timeout (repeatable, e.g. workspace/symbol=120)
+    #[arg(long = "lsp-method-timeout", value_name = "METHOD=SECONDS", value_parser = parse_method_timeout)]
+    lsp_method_timeouts: Vec<(String, Duration)>,
+
+    /// JSON file of settings answered to the server's workspace/configuration requests
+    #[arg(long, value_name = "FILE")]
+    lsp_settings: Option<PathBuf>,
+
+    /// Setting answered to workspace/configuration, overriding --lsp-settings (repeatable, e.g. gopls.staticcheck=true)
+    #[arg(long = "lsp-setting", value_name = "SECTION=JSON", value_parser = parse_setting)]
+    lsp_setting_overrides: Vec<(String, serde_json::Value)>,
+
+    /// Secondary LSP command (e.g. a linter) that sees the same files (repeatable, e.g. "ruff server")
+    #[arg(long = "secondary-lsp", value_name = "COMMAND")]
+    secondary_lsps: Vec<String>,
 
     /// Additional args to pass to LSP command
     #[arg(last = true)]
     lsp_args: Vec<String>,
 }
 
+/// Environment variable with initialization options for the primary server, as JSON
+const INIT_OPTIONS_VAR: &str = "LSP_INITIALIZATION_OPTIONS";
+
+/// Parses an `EXT=LANGUAGE` language id override
+fn parse_language_override(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((extension, language)) if !extension.is_empty() && !language.is_empty() => {
+            Ok((extension.to_string(), language.to_string()))
+        }
+        _ => Err(format!("expected EXT=LANGUAGE, got '{}'", value)),
+    }
+}
+
+/// Parses a `SERVER=LANGUAGES` language filter
+fn parse_lsp_languages(value: &str) -> Result<(String, lsp::LanguageFilter), String> {
+    match value.split_once('=') {
+        Some((server, languages)) if !server.is_empty() && !languages.is_empty() => {
+            Ok((server.to_string(), lsp::LanguageFilter::parse(languages)))
+        }
+        _ => Err(format!("expected SERVER=LANGUAGES, got '{}'", value)),
+    }
+}
+
+/// Parses the address of a running language server
+fn parse_server_address(value: &str) -> Result<lsp::ServerAddress, String> {
+    lsp::ServerAddress::parse(value).map_err(|e| e.to_string())
+}
+
+/// Parses a `KEY=VALUE` environment variable
+fn parse_env_var(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", value)),
+    }
+}
+
+/// Parses `SERVER=JSON` initialization options
+fn parse_init_options(value: &str) -> Result<(String, serde_json::Value), String> {
+    match value.split_once('=') {
+        Some((server, options)) if !server.is_empty() => match serde_json::from_str(options) {
+            Ok(options) => Ok((server.to_string(), options)),
+            Err(e) => Err(format!("invalid JSON for {}: {}", server, e)),
+        },
+        _ => Err(format!("expected SERVER=JSON, got '{}'", value)),
+    }
+}
+
+/// Parses a `METHOD=SECONDS` request timeout override
+fn parse_method_timeout(value: &str) -> Result<(String, Duration), String> {
+    match value.split_once('=') {
+        Some((method, seconds)) if !method.is_empty() => match seconds.parse() {
+            Ok(seconds) => Ok((method.to_string(), Duration::from_secs(seconds))),
+            Err(_) => Err(format!("expected a number of seconds, got '{}'", seconds)),
+        },
+        _ => Err(format!("expected METHOD=SECONDS, got '{}'", value)),
+    }
+}
+
+/// Parses a `SECTION=JSON` setting; values that aren't JSON are taken as strings
+fn parse_setting(value: &str) -> Result<(String, serde_json::Value), String> {
+    match value.split_once('=') {
+        Some((section, setting)) if !section.is_empty() => {
+            let setting =
+                serde_json::from_str(setting).unwrap_or_else(|_| serde_json::Value::from(setting));
+            Ok((section.to_string(), setting))
+        }
+        _ => Err(format!("expected SECTION=JSON, got '{}'", value)),
+    }
+}
+
+/// Parses an LSP trace level
+fn parse_trace(value: &str) -> Result<lsp_types::TraceValue, String> {
+    match value {
+        "off" => Ok(lsp_types::TraceValue::Off),
+        "messages" => Ok(lsp_types::TraceValue::Messages),
+        "verbose" => Ok(lsp_types::TraceValue::Verbose),
+        _ => Err(format!(
+            "expected off, messages or verbose, got '{}'",
+            value
+        )),
+    }
+}
+
+/// Splits a further `--lsp` or a `--secondary-lsp` value into the command and its args
+fn split_command(value: &str) -> Result<(String, Vec<String>)> {
+    let mut parts = value.split_whitespace();
+    let command = parts
+        .next()
+        .ok_or_else(|| anyhow!("Empty language server command"))?;
+    Ok((command.to_string(), parts.map(str::to_string).collect()))
+}
+
+/// Gets the name a server is known by, the file name of its command
+fn server_name(command: &str) -> &str {
+    Path::new(command)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(command)
+}
+
+/// Gets the files a server handles from --lsp-languages, the config file, or
+/// the defaults for its command
+fn language_filter(config: &Config, server: &config::ServerConfig) -> lsp::LanguageFilter {
+    let command = server.command.as_str();
+    config
+        .lsp_languages
+        .iter()
+        .find(|(name, _)| name == server_name(command) || name == command)
+        .map(|(_, filter)| filter.clone())
+        .or_else(|| server.language_filter())
+        .unwrap_or_else(|| lsp::LanguageFilter::for_command(command))
+}
+
+/// Gets the environment and working directory of a server, with --lsp-env and
+/// --lsp-cwd taking precedence over the config file
+fn process_options(config: &Config, server: &config::ServerConfig) -> lsp::ProcessOptions {
+    let mut env = server.env_pairs();
+    env.extend(config.lsp_env.iter().cloned());
+    lsp::ProcessOptions {
+        env,
+        cwd: config.lsp_cwd.clone().or_else(|| server.cwd.clone()),
+    }
+}
+
+/// Fills in the options not given on the command line from the config file,
+/// and gets the language servers to start, the primary first
+fn apply_config_file(
+    config: &mut Config,
+    matches: &ArgMatches,
+    file: &config::ConfigFile,
+) -> Result<Vec<config::ServerConfig>> {
+    let from_command_line = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    if config.workspace.is_none() {
+        config.workspace = file.workspace.clone();
+    }
+    if config.log_level.is_none() {
+        config.log_level = file.log_level;
+    }
+    if config.ignore_patterns.is_empty() {
+        config.ignore_patterns = file.ignore.clone();
+    }
+    if !from_command_line("lsp_timeout")
+        && let Some(seconds) = file.timeouts.lsp
+    {
+        config.lsp_timeout = seconds;
+    }
+    if !from_command_line("ready_timeout")
+        && let Some(seconds) = file.timeouts.ready
+    {
+        config.ready_timeout = seconds;
+    }
+    // Method timeouts from the command line are applied last and win
+    let mut method_timeouts: Vec<(String, Duration)> = file
+        .timeouts
+        .methods
+        .iter()
+        .map(|(method, seconds)| (method.clone(), Duration::from_secs(*seconds)))
+        .collect();
+    method_timeouts.append(&mut config.lsp_method_timeouts);
+    config.lsp_method_timeouts = method_timeouts;
+    // Likewise for language ids given with --language-id
+    let mut language_ids: Vec<(String, String)> = file
+        .language_ids
+        .iter()
+        .map(|(extension, language)| (extension.clone(), language.clone()))
+        .collect();
+    language_ids.append(&mut config.language_ids);
+    config.language_ids = language_ids;
+
+    if !from_command_line("output_style")
+        && let Some(style) = &file.tools.output_style
+    {
+        config.output_style = tools::render::OutputStyle::parse(style)?;
+    }
+    if config.max_response_tokens.is_none() {
+        config.max_response_tokens = file.tools.max_response_tokens;
+    }
+    if !from_command_line("chars_per_token")
+        && let Some(chars) = file.tools.chars_per_token
+    {
+        config.chars_per_token = chars;
+    }
+    config.absolute_paths |= file.tools.absolute_paths.unwrap_or(false);
+
+    let mut servers = if config.lsp.is_empty() {
+        file.servers.clone()
+    } else {
+        // The first server takes the trailing args; the others are given with theirs
+        let mut servers = vec![config::ServerConfig::new(&config.lsp[0], &config.lsp_args)];
+        for server in &config.lsp[1..] {
+            let (command, args) = split_command(server)?;
+            servers.push(config::ServerConfig::new(&command, &args));
+        }
+        servers
+    };
+
+    if let Some(address) = &config.lsp_connect {
+        if servers.is_empty() {
+            servers.push(config::ServerConfig::new(&address.to_string(), &[]));
+        }
+        servers[0].connect = Some(address.to_string());
+    }
+    if servers.is_empty() {
+        return Err(anyhow!(
+            "No language server to start; pass --lsp or list servers in the config file"
+        ));
+    }
+    Ok(servers)
+}
+
+/// Starts a language server, or connects to it if it is already running
+async fn start_server(config: &Config, server: &config::ServerConfig) -> Result<Arc<lsp::Client>> {
+    match &server.connect {
+        Some(address) => {
+            info!("Connecting to LSP server {} at {}", server.command, address);
+            lsp::Client::connect(&server.command, &lsp::ServerAddress::parse(address)?).await
+        }
+        None => {
+            info!(
+                "Starting LSP client: {} {}",
+                server.command,
+                server.args.join(" ")
+            );
+            lsp::Client::with_options(
+                &server.command,
+                &server.args,
+                &process_options(config, server),
+            )
+            .await
+            .context(format!("Failed to create LSP client: {}", server.command))
+        }
+    }
+}
+
+/// Gets the initialization options replacing the presets, by server name: from
+/// --lsp-init-options, then LSP_INITIALIZATION_OPTIONS for the primary server,
+/// then the config file
+fn initialization_options(
+    config: &Config,
+    servers: &[config::ServerConfig],
+) -> Result<Vec<(String, serde_json::Value)>> {
+    let mut options: Vec<(String, serde_json::Value)> = config
+        .lsp_init_options
+        .iter()
+        .map(|(server, options)| (server_name(server).to_string(), options.clone()))
+        .collect();
+
+    if let Ok(value) = std::env::var(INIT_OPTIONS_VAR) {
+        let primary_options = serde_json::from_str(&value)
+            .with_context(|| format!("{} is not valid JSON", INIT_OPTIONS_VAR))?;
+        options.push((
+            server_name(&servers[0].command).to_string(),
+            primary_options,
+        ));
+    }
+
+    options.extend(servers.iter().filter_map(|server| {
+        let server_options = server.initialization_options.clone()?;
+        Some((server_name(&server.command).to_string(), server_options))
+    }));
+    Ok(options)
+}
+
+/// Builds the setup shared by every language server, including ones started later
+fn client_setup(
+    config: &Config,
+    servers: &[config::ServerConfig],
+    retry_policy: lsp::RetryPolicy,
+    timeout_policy: &lsp::TimeoutPolicy,
+    settings: &lsp::Settings,
+) -> Result<impl Fn(&lsp::Client) + Clone + Send + Sync + 'static> {
+    let trace = config.lsp_trace;
+    let timeout_policy = timeout_policy.clone();
+    let settings = settings.clone();
+    let language_ids = config.language_ids.clone();
+    let init_options = initialization_options(config, servers)?;
+
+    Ok(move |client: &lsp::Client| {
+        client.set_trace(trace);
+        client.set_retry_policy(retry_policy);
+        client.set_timeout_policy(timeout_policy.clone());
+        client.set_settings(settings.clone());
+        for (extension, language_id) in &language_ids {
+            client.set_language_id(extension, language_id);
+        }
+        if let Some((_, options)) = init_options.iter().find(|(name, _)| name == client.name()) {
+            client.set_initialization_options(options.clone());
+        }
+    })
+}
+
+/// Parses a language server version
+fn parse_server_version(value: &str) -> Result<lsp::versions::ServerVersion, String> {
+    value.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Parses a symlink policy
+fn parse_symlink_policy(value: &str) -> Result<workspace::paths::SymlinkPolicy, String> {
+    workspace::paths::SymlinkPolicy::parse(value).map_err(|e| e.to_string())
+}
+
+/// Parses a tool output style
+fn parse_output_style(value: &str) -> Result<tools::render::OutputStyle, String> {
+    tools::render::OutputStyle::parse(value).map_err(|e| e.to_string())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Parse command-line arguments, falling back to the config file
+    let matches = Config::command().get_matches();
+    let mut config = Config::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let config_file = match &config.config {
+        Some(path) => config::ConfigFile::load(path)?,
+        None => config::ConfigFile::default(),
+    };
+    let servers = apply_config_file(&mut config, &matches, &config_file)?;
+
     // Initialize logging
-    logging::debug();
+    logging::CoreLogger::init_with_filter(config.log_level.unwrap_or(log::LevelFilter::Debug));
 
     info!("MCP Language Server starting");
 
-    // Parse command-line arguments
-    let config = Config::parse();
-
     // Validate workspace path
-    if !config.workspace.exists() {
+    if let Some(workspace_root) = &config.workspace
+        && !workspace_root.exists()
+    {
         log::error!(
             "Workspace directory does not exist: {}",
-            config.workspace.display()
+            workspace_root.display()
         );
         return Err(anyhow!("Workspace directory does not exist"));
     }
+    if let Some(cwd) = &config.lsp_cwd
+        && !cwd.is_dir()
+    {
+        return Err(anyhow!(
+            "Language server directory does not exist: {}",
+            cwd.display()
+        ));
+    }
+
+    // The client, watcher and gitignore filter share one root, normalized the
+    // same way as the paths tools work with
+    workspace::paths::set_symlink_policy(config.symlinks);
+
+    // The first server is the primary one
+    let primary = &servers[0];
+    let lsp_command = primary.command.clone();
+
+    // Make sure the workspace is a project root the language server will
+    // recognize. Without one, the MCP client's first root is used once it
+    // lists its roots.
+    let mut workspace_warning = None;
+    let workspace_root = match &config.workspace {
+        Some(workspace_root) => {
+            let workspace_root = workspace::paths::normalize_path(workspace_root)?;
+            let workspace_check =
+                workspace::check_workspace(&workspace_root, &lsp_command, config.find_project_root);
+            workspace_warning = workspace_check.warning;
+            Some(workspace_check.root)
+        }
+        None => {
+            info!("No workspace given, waiting for the MCP client's roots");
+            None
+        }
+    };
+    let roots = mcp::roots::WorkspaceRoots::new(workspace_root.clone());
+
+    tools::utils::set_absolute_paths(config.absolute_paths);
+    tools::render::set_default_style(config.output_style);
+    tools::budget::set_response_budget(config.max_response_tokens.map(|max_tokens| {
+        tools::budget::ResponseBudget {
+            max_tokens,
+            chars_per_token: config.chars_per_token,
+        }
+    }));
 
     // Create a shutdown channel
     let shutdown_flag = Arc::new(AtomicBool::new(false));
@@ -72,54 +557,246 @@ async fn main() -> Result<()> {
     });
 
     // Create LSP client
-    info!(
-        "Starting LSP client: {} {}",
-        &config.lsp,
-        config.lsp_args.join(" ")
+    let lsp_client = start_server(&config, primary).await?;
+
+    let retry_policy = lsp::RetryPolicy {
+        max_retries: config.max_retries,
+        ..Default::default()
+    };
+    let timeout_policy = config.lsp_method_timeouts.iter().fold(
+        lsp::TimeoutPolicy {
+            default: Duration::from_secs(config.lsp_timeout),
+            ..Default::default()
+        },
+        |policy, (method, timeout)| policy.with_method(method, *timeout),
     );
+    let mut settings = match &config.lsp_settings {
+        Some(path) => lsp::Settings::load(path)?,
+        None => config_file.settings.clone().unwrap_or_default(),
+    };
+    for (section, value) in &config.lsp_setting_overrides {
+        settings.set(section, value.clone())?;
+    }
+    let setup = client_setup(&config, &servers, retry_policy, &timeout_policy, &settings)?;
+    setup(&lsp_client);
+
+    // Warn about outdated or known-bad server versions and work around their
+    // problems; the version of a server connected to can't be asked for
+    if primary.connect.is_some() {
+        if config.check_server_version || config.min_server_version.is_some() {
+            log::warn!("Skipping server version check for a server connected to");
+        }
+    } else if config.check_server_version || config.min_server_version.is_some() {
+        match lsp::versions::detect_version(&lsp_command).await {
+            Ok(version) => {
+                info!("Language server version: {}", version);
+                if let Some(minimum) = config.min_server_version
+                    && version < minimum
+                {
+                    return Err(anyhow!(
+                        "{} {} is older than the required version {}",
+                        &lsp_command,
+                        version,
+                        minimum
+                    ));
+                }
+                if config.check_server_version {
+                    let check = lsp::versions::check_version(&lsp_command, version);
+                    for workaround in check.workarounds {
+                        lsp_client.apply_workaround(workaround);
+                    }
+                }
+            }
+            Err(e) if config.min_server_version.is_some() => {
+                return Err(e.context("Failed to check the language server version"));
+            }
+            Err(e) => log::warn!("Skipping server version check: {}", e),
+        }
+    }
 
-    let lsp_client = lsp::Client::new(&config.lsp, &config.lsp_args)
-        .await
-        .context("Failed to create LSP client")?;
+    for secondary in &config.secondary_lsps {
+        let (command, args) = split_command(secondary)?;
 
-    // Initialize the LSP client
-    info!("Initializing LSP client");
+        info!("Starting secondary LSP client: {}", secondary);
+        let options = process_options(&config, &config::ServerConfig::new(&command, &args));
+        let secondary_client = lsp::Client::with_options(&command, &args, &options)
+            .await
+            .context(format!(
+                "Failed to create secondary LSP client: {}",
+                command
+            ))?;
+        setup(&secondary_client);
+        lsp_client.attach(secondary_client);
+    }
 
-    lsp_client
-        .initialize(&config.workspace)
-        .await
-        .context("Failed to initialize LSP client")?;
+    // Servers for other languages each get the files their filter matches
+    let mut router = lsp::Router::new(Arc::clone(&lsp_client))
+        .with_primary_filter(language_filter(&config, primary))
+        .with_launcher(workspace_root.clone(), setup.clone());
+    let mut language_clients = Vec::new();
+    for server in &servers[1..] {
+        let filter = language_filter(&config, server);
 
-    // Create file watcher
-    let workspace_watcher =
-        FileSystemWatcher::new(Arc::clone(&lsp_client), config.workspace.clone());
+        // Servers connected to are already running
+        if config.lazy_lsp && server.connect.is_none() {
+            router = router.with_lazy_server(
+                filter,
+                &server.command,
+                &server.args,
+                &process_options(&config, server),
+            );
+            continue;
+        }
+
+        let client = start_server(&config, server).await?;
+        setup(&client);
+        router = router.with_server(filter, Arc::clone(&client));
+        language_clients.push(client);
+    }
+    if let Some(seconds) = config.lsp_idle_shutdown {
+        router.stop_idle_servers(Duration::from_secs(seconds));
+    }
 
-    // Start watching the workspace
-    workspace_watcher
-        .watch_workspace(config.workspace.clone())
-        .await
-        .context("Failed to start workspace watcher")?;
+    // Restore state from the previous session in this workspace; sessions
+    // are only kept for a workspace given up front
+    let session = workspace_root
+        .as_deref()
+        .and_then(session::Session::for_workspace);
 
-    // Create MCP server handler
-    let server_handler =
-        mcp::McpLanguageServer::new(Arc::clone(&lsp_client), config.workspace.clone());
+    // The file watcher keeps these workspace content hashes current
+    let file_hashes = workspace::hashes::FileHashes::new();
 
-    // Create the MCP server with stdin/stdout transport
-    let transport = (tokio::io::stdin(), tokio::io::stdout());
+    // Initialize the LSP client in the background so the MCP handshake isn't
+    // held up by slow servers; tool calls wait until initialization finishes
+    info!("Initializing LSP client");
 
-    // Start the MCP server
-    let server_handle = tokio::spawn(async move {
-        match rmcp::serve_server(server_handler, transport).await {
-            Ok(server) => {
-                info!("MCP server running");
-                let _ = server.waiting().await;
+    for client in &language_clients {
+        let client = Arc::clone(client);
+        let roots = Arc::clone(&roots);
+        tokio::spawn(async move {
+            let Ok(workspace) = roots.wait_for_workspace().await else {
+                return;
+            };
+            if let Err(e) = client.initialize(&workspace).await {
+                log::error!("Failed to initialize {}: {}", client.name(), e);
             }
+        });
+    }
+
+    let init_client = Arc::clone(&lsp_client);
+    let init_router = router.clone();
+    let init_roots = Arc::clone(&roots);
+    let init_session = session.clone();
+    let init_hashes = Arc::clone(&file_hashes);
+    let ignore_patterns = config.ignore_patterns.clone();
+    let command_name = lsp_client.name().to_string();
+    let transport_name = if config.listen.is_some() {
+        "sse"
+    } else {
+        "stdio"
+    };
+    let (ready_tx, ready_rx) = watch::channel::<Option<ReadySignal>>(None);
+    tokio::spawn(async move {
+        let Ok(workspace) = init_roots.wait_for_workspace().await else {
+            return;
+        };
+        init_router.set_workspace(workspace.clone());
+
+        let result = match init_client.initialize(&workspace).await {
+            Ok(result) => result,
             Err(e) => {
-                log::error!("Failed to start MCP server: {}", e);
+                log::error!("Failed to initialize LSP client: {}", e);
+                return;
+            }
+        };
+
+        // Announce readiness for scripts that wait on the proxy
+        let signal = ReadySignal::new(
+            transport_name,
+            &workspace,
+            &command_name,
+            &result,
+            mcp::McpLanguageServer::tool_names(),
+        );
+        eprintln!("{}", signal.stderr_line());
+        info!("LSP server ready: {}", signal.to_json());
+        let _ = ready_tx.send(Some(signal));
+
+        if let Some(session) = init_session {
+            session.restore(&init_client).await;
+        }
+
+        // Start watching the workspace, and the folders added to it meanwhile
+        let watcher = FileSystemWatcher::new(Arc::clone(&init_client), workspace.clone())
+            .with_router(init_router)
+            .with_file_hashes(init_hashes)
+            .with_ignore_patterns(ignore_patterns);
+        init_roots.set_watcher(watcher.clone());
+        if let Err(e) = watcher.watch_workspace(workspace).await {
+            log::error!("Failed to start workspace watcher: {}", e);
+        }
+        for folder in init_client.workspace_folders().iter().skip(1) {
+            if let Err(e) = watcher.add_folder(folder).await {
+                log::error!("Failed to watch {}: {}", folder.display(), e);
             }
         }
     });
 
+    // Release language server resources when no tool calls arrive for a while
+    let idle_monitor = config.idle_timeout.map(|seconds| {
+        lsp::IdleMonitor::start(
+            Arc::clone(&lsp_client),
+            lsp::IdlePolicy {
+                timeout: Duration::from_secs(seconds),
+                stop_server: config.stop_server_when_idle,
+            },
+        )
+    });
+
+    // Create MCP server handler
+    let server_handler =
+        mcp::McpLanguageServer::new(Arc::clone(&lsp_client), workspace_root.unwrap_or_default())
+            .with_router(router.clone())
+            .with_roots(Arc::clone(&roots))
+            .with_workspace_warning(workspace_warning)
+            .with_session(session.clone())
+            .with_file_hashes(file_hashes)
+            .with_idle_monitor(idle_monitor)
+            .with_ready_timeout(Duration::from_secs(config.ready_timeout))
+            .with_ready_signal(ready_rx)
+            .with_custom_tools(config_file.custom_tools.clone());
+
+    // Serve MCP over SSE, giving every client its own handler on the shared
+    // language servers, or over stdin/stdout to a single client
+    let mut sse_server = None;
+    let server_handle = match config.listen {
+        Some(address) => {
+            let leases = mcp::clients::DocumentLeases::new();
+            let cancel = rmcp::transport::sse_server::SseServer::serve(address)
+                .await
+                .with_context(|| format!("Failed to listen on {}", address))?
+                .with_service(move || server_handler.for_new_client(&leases));
+            info!("MCP server listening on http://{}/sse", address);
+
+            sse_server = Some(cancel.clone());
+            tokio::spawn(async move { cancel.cancelled().await })
+        }
+        None => {
+            let transport = (tokio::io::stdin(), tokio::io::stdout());
+            tokio::spawn(async move {
+                match rmcp::serve_server(server_handler, transport).await {
+                    Ok(server) => {
+                        info!("MCP server running");
+                        let _ = server.waiting().await;
+                    }
+                    Err(e) => {
+                        log::error!("Failed to start MCP server: {}", e);
+                    }
+                }
+            })
+        }
+    };
+
     info!("MCP server initialized and ready");
 
     // Wait for shutdown signal or server completion
@@ -133,11 +810,28 @@ async fn main() -> Result<()> {
     }
 
     // Clean shutdown
+    if let Some(sse_server) = sse_server {
+        sse_server.cancel();
+    }
     info!("Shutting down workspace watcher");
-    let _ = workspace_watcher.stop().await;
+    if let Some(watcher) = roots.watcher() {
+        let _ = watcher.stop().await;
+    }
+
+    if let Some(session) = &session {
+        info!("Saving session state");
+        session.capture(&lsp_client);
+        if let Err(e) = session.save() {
+            log::warn!("Failed to save session state: {}", e);
+        }
+    }
 
     info!("Shutting down LSP client");
     let _ = lsp_client.shutdown().await;
+    // Lazily started servers are only known to the router
+    for client in router.clients().iter().skip(1) {
+        let _ = client.shutdown().await;
+    }
 
     info!("Server shutdown complete");
     Ok(())
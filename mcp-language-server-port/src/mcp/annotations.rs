@@ -0,0 +1,45 @@
+use rmcp::model::ToolAnnotations;
+
+/// Tools that change files or run code, so hosts should confirm them. Dry runs
+/// change nothing, but the hint can't depend on the arguments.
+const DESTRUCTIVE_TOOLS: &[&str] = &[
+    "apply_code_action",
+    "edit_and_check",
+    "edit_file",
+    "execute_command",
+    "fix_diagnostics",
+    "lsp_request",
+    "organize_imports",
+    "rename_symbol",
+    "run_code_lens",
+];
+
+/// Tools that change the session or add files without overwriting anything,
+/// each with whether calling it again has no further effect
+const ADDITIVE_TOOLS: &[(&str, bool)] = &[
+    ("add_workspace_folder", true),
+    ("remove_workspace_folder", true),
+    ("restart_language_server", false),
+    ("test_anchor", true),
+];
+
+/// Gets the behavior hints of a tool. Tools not listed as changing anything
+/// only read the workspace and the servers' state.
+pub fn tool_annotations(tool: &str) -> ToolAnnotations {
+    let (read_only, destructive, idempotent) = if DESTRUCTIVE_TOOLS.contains(&tool) {
+        (false, true, false)
+    } else if let Some((_, idempotent)) = ADDITIVE_TOOLS.iter().find(|(name, _)| *name == tool) {
+        (false, false, *idempotent)
+    } else {
+        (true, false, true)
+    };
+
+    ToolAnnotations {
+        title: None,
+        read_only_hint: Some(read_only),
+        destructive_hint: Some(destructive),
+        idempotent_hint: Some(idempotent),
+        // Tools only reach the workspace and its language servers
+        open_world_hint: Some(false),
+    }
+}
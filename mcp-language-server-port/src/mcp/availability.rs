@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use crate::lsp::{Client, Router};
+use crate::tools::capabilities;
+
+/// The LSP request each tool can't do without
+const TOOL_METHODS: &[(&str, &str)] = &[
+    ("apply_code_action", "textDocument/codeAction"),
+    ("code_actions", "textDocument/codeAction"),
+    ("code_lenses", "textDocument/codeLens"),
+    ("color_presentations", "textDocument/documentColor"),
+    ("completion", "textDocument/completion"),
+    ("definition", "textDocument/definition"),
+    ("document_colors", "textDocument/documentColor"),
+    ("document_symbols", "textDocument/documentSymbol"),
+    ("document_highlights", "textDocument/documentHighlight"),
+    ("execute_command", "workspace/executeCommand"),
+    ("find_impls_of_trait", "textDocument/implementation"),
+    ("fix_diagnostics", "textDocument/codeAction"),
+    ("folding_ranges", "textDocument/foldingRange"),
+    ("hover", "textDocument/hover"),
+    ("incoming_calls", "textDocument/prepareCallHierarchy"),
+    ("inline_values", "textDocument/inlineValue"),
+    ("moniker", "textDocument/moniker"),
+    ("outgoing_calls", "textDocument/prepareCallHierarchy"),
+    ("references", "textDocument/references"),
+    ("related_files", "textDocument/documentLink"),
+    ("organize_imports", "textDocument/codeAction"),
+    ("rename_symbol", "textDocument/rename"),
+    ("run_code_lens", "textDocument/codeLens"),
+    ("selection_ranges", "textDocument/selectionRange"),
+    ("semantic_tokens", "textDocument/semanticTokens"),
+    ("type_hierarchy", "textDocument/prepareTypeHierarchy"),
+];
+
+/// Lists the tools no running server can answer, each with the request it
+/// lacks. Nothing is left out while a server that hasn't started or
+/// initialized might support them.
+pub fn unsupported_tools(router: &Router) -> Vec<(&'static str, &'static str)> {
+    if !router.all_running() {
+        return Vec::new();
+    }
+
+    let mut servers: Vec<Arc<Client>> = Vec::new();
+    for client in router.clients() {
+        servers.extend(client.attached_servers());
+        servers.push(client);
+    }
+
+    TOOL_METHODS
+        .iter()
+        .filter(|(_, method)| {
+            servers
+                .iter()
+                .all(|server| capabilities::supports(server, method) == Some(false))
+        })
+        .copied()
+        .collect()
+}
@@ -0,0 +1,125 @@
+use log::{debug, error, info};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::lsp::Router;
+use crate::workspace::paths::normalize_path;
+
+/// The files each connected MCP client works with. The language servers keep
+/// one open document per file for all clients, so a file is only closed once
+/// the last client using it disconnects.
+#[derive(Debug, Default)]
+pub struct DocumentLeases {
+    holders: Mutex<HashMap<PathBuf, HashSet<u64>>>,
+    next_client: AtomicU64,
+}
+
+impl DocumentLeases {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Registers a newly connected client, whose files are released when the
+    /// returned session is dropped
+    pub fn connect(self: &Arc<Self>, router: Router) -> Arc<ClientSession> {
+        let id = self.next_client.fetch_add(1, Ordering::Relaxed) + 1;
+        info!("[MCP] Client {} connected", id);
+        Arc::new(ClientSession {
+            id,
+            leases: Arc::clone(self),
+            router,
+        })
+    }
+
+    /// Gets how many clients use a file
+    pub fn holders(&self, file_path: &Path) -> usize {
+        let file_path = normalize_path(file_path).unwrap_or_else(|_| file_path.to_path_buf());
+        self.holders
+            .lock()
+            .unwrap()
+            .get(&file_path)
+            .map_or(0, HashSet::len)
+    }
+
+    fn lease(&self, client: u64, file_path: &Path) {
+        let file_path = normalize_path(file_path).unwrap_or_else(|_| file_path.to_path_buf());
+        self.holders
+            .lock()
+            .unwrap()
+            .entry(file_path)
+            .or_default()
+            .insert(client);
+    }
+
+    /// Forgets the files of a client, returning those no other client uses
+    fn release(&self, client: u64) -> Vec<PathBuf> {
+        let mut holders = self.holders.lock().unwrap();
+        let mut released = Vec::new();
+        holders.retain(|file_path, clients| {
+            if clients.remove(&client) && clients.is_empty() {
+                released.push(file_path.clone());
+            }
+            !clients.is_empty()
+        });
+        released
+    }
+}
+
+/// One connected MCP client
+pub struct ClientSession {
+    id: u64,
+    leases: Arc<DocumentLeases>,
+    router: Router,
+}
+
+impl ClientSession {
+    /// Gets the number the client is known by in the log
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Records that the client works with a file
+    pub fn use_file(&self, file_path: &Path) {
+        self.leases.lease(self.id, file_path);
+    }
+}
+
+impl Drop for ClientSession {
+    fn drop(&mut self) {
+        let released = self.leases.release(self.id);
+        info!(
+            "[MCP] Client {} disconnected, {} files no longer in use",
+            self.id,
+            released.len()
+        );
+        if released.is_empty() {
+            return;
+        }
+
+        // Closing needs the runtime, which is gone once the proxy shuts down
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let leases = Arc::clone(&self.leases);
+        let clients = self.router.clients();
+        runtime.spawn(async move {
+            for file_path in released {
+                // Another client may have started using it meanwhile
+                if leases.holders(&file_path) > 0 {
+                    continue;
+                }
+                for client in &clients {
+                    if !client.is_file_open(&file_path) {
+                        continue;
+                    }
+                    debug!("[MCP] Closing {} no client uses", file_path.display());
+                    if let Err(e) = client.close_file(&file_path).await {
+                        error!("[MCP] Failed to close {}: {}", file_path.display(), e);
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,117 @@
+use anyhow::{Result, anyhow};
+use rmcp::model::{JsonObject, Tool};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config::CustomToolConfig;
+use crate::lsp::{Client, Router};
+use crate::tools;
+
+use super::annotations;
+
+/// Checks whether a server announces an entry of its `experimental`
+/// capabilities, given as a dotted path like `serverStatusNotification` or
+/// `commands.expandMacro`
+pub fn has_experimental(client: &Client, path: &str) -> bool {
+    let mut parts = path.split('.');
+    let Some(mut value) = parts
+        .next()
+        .and_then(|name| client.experimental_capability(name))
+    else {
+        return false;
+    };
+    for part in parts {
+        match value.get(part) {
+            Some(next) => value = next.clone(),
+            None => return false,
+        }
+    }
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+/// Gets the running servers that can answer a custom tool, the primary first
+pub fn servers_for(tool: &CustomToolConfig, router: &Router) -> Vec<Arc<Client>> {
+    let mut servers = Vec::new();
+    for client in router.clients() {
+        servers.push(Arc::clone(&client));
+        servers.extend(client.attached_servers());
+    }
+    match &tool.requires {
+        Some(requires) => servers
+            .into_iter()
+            .filter(|server| has_experimental(server, requires))
+            .collect(),
+        None => servers,
+    }
+}
+
+/// Lists the custom tools some running server can answer
+pub fn available<'a>(tools: &'a [CustomToolConfig], router: &Router) -> Vec<&'a CustomToolConfig> {
+    tools
+        .iter()
+        .filter(|tool| !servers_for(tool, router).is_empty())
+        .collect()
+}
+
+/// Describes a custom tool to the MCP client. Like `lsp_request`, it may do
+/// anything the server does for the request.
+pub fn tool(config: &CustomToolConfig) -> Tool {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "file_path": {
+                "type": "string",
+                "description": "Optional file the request is about; it is opened first and picks the language server"
+            },
+            "params": {
+                "description": "Parameters of the request, replacing the configured ones"
+            }
+        }
+    });
+    let schema: JsonObject = match schema {
+        Value::Object(schema) => schema,
+        _ => JsonObject::new(),
+    };
+
+    let mut tool = Tool::new(
+        config.name.clone(),
+        config.description.clone(),
+        Arc::new(schema),
+    );
+    tool.annotations = Some(annotations::tool_annotations("lsp_request"));
+    tool
+}
+
+/// Gets the file a custom tool call is about, if it names one
+pub fn file_path(arguments: Option<&JsonObject>) -> Option<PathBuf> {
+    arguments?
+        .get("file_path")?
+        .as_str()
+        .map(|path| Path::new(path).to_path_buf())
+}
+
+/// Sends a custom tool's request to a server, with the parameters the call
+/// gives or else the configured ones
+pub async fn call(
+    config: &CustomToolConfig,
+    client: &Client,
+    arguments: Option<&JsonObject>,
+) -> Result<String> {
+    if let Some(requires) = &config.requires
+        && !has_experimental(client, requires)
+    {
+        return Err(anyhow!(
+            "{} does not offer the experimental capability {}",
+            client.name(),
+            requires
+        ));
+    }
+
+    let params = arguments
+        .and_then(|arguments| arguments.get("params"))
+        .cloned()
+        .or_else(|| config.params.clone());
+    let file_path = file_path(arguments);
+    tools::send_lsp_request(client, &config.method, params, file_path.as_deref()).await
+}
@@ -1,12 +1,77 @@
 use anyhow::Result;
-use rmcp::model::ServerInfo;
+use rmcp::handler::server::tool::ToolCallContext;
+use rmcp::model::{
+    CallToolRequestParam, CallToolResult, Content, GetPromptRequestParam, GetPromptResult,
+    ListPromptsResult, ListResourcesResult, ListToolsResult, PaginatedRequestParam,
+    ReadResourceRequestParam, ReadResourceResult, ResourceContents, ServerCapabilities, ServerInfo,
+    SubscribeRequestParam, UnsubscribeRequestParam,
+};
+use rmcp::service::{Peer, RequestContext, RoleServer};
 use rmcp::{ServerHandler, tool};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
 
+use crate::config::CustomToolConfig;
+use crate::logging::correlation::{self, CorrelationId};
 use crate::lsp;
+use crate::mcp::clients::{ClientSession, DocumentLeases};
+use crate::mcp::progress::ProgressForwarder;
+use crate::mcp::ready::ReadySignal;
+use crate::mcp::roots::WorkspaceRoots;
+use crate::mcp::subscriptions::ResourceSubscriptions;
+use crate::session::Session;
 use crate::tools;
+use crate::tools::render::{self, OutputFormat, OutputStyle};
+use crate::tools::type_hierarchy::{self, TypeHierarchyDirection};
+use crate::tools::workspace_edit::ApplyOptions;
+use crate::watcher::FileSystemWatcher;
+use crate::watcher::gitignore::GitignoreFilter;
+use crate::workspace::hashes::FileHashes;
+use crate::workspace::paths::normalize_path;
+
+pub mod annotations;
+pub mod availability;
+pub mod clients;
+pub mod custom_tools;
+pub mod progress;
+pub mod prompts;
+pub mod ready;
+pub mod resources;
+pub mod roots;
+pub mod subscriptions;
+
+/// How long tool calls wait for the language server to finish indexing by default
+pub const DEFAULT_READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Appends the correlation id to error messages tools return, so a failed call
+/// can be found in the logs
+pub fn tag_errors(result: &mut CallToolResult, correlation_id: &CorrelationId) {
+    for content in &mut result.content {
+        if let Some(text) = content.as_text()
+            && text.text.starts_with("Error")
+        {
+            *content = Content::text(format!(
+                "{} (correlation id: {})",
+                text.text, correlation_id
+            ));
+        }
+    }
+}
+
+/// Notes on a tool result that the language server was still busy with the
+/// given work, such as indexing, so the result may be incomplete
+pub fn annotate_pending_work(result: &mut CallToolResult, pending: &[String]) {
+    if pending.is_empty() {
+        return;
+    }
+    result.content.push(Content::text(format!(
+        "Note: the language server is still busy ({}); results may be incomplete",
+        pending.join(", ")
+    )));
+}
 
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
 pub struct EditFileRequest {
@@ -14,18 +79,198 @@ pub struct EditFileRequest {
     pub file_path: String,
     #[schemars(description = "List of text edits to apply")]
     pub edits: Vec<tools::edit::TextEditParams>,
+    #[schemars(
+        description = "Content hash from file_hashes; the edit is refused if the file has changed since"
+    )]
+    pub expected_hash: Option<String>,
+    #[schemars(
+        description = "Return the change as a unified diff without writing the file or notifying the language server"
+    )]
+    pub dry_run: Option<bool>,
+    #[schemars(
+        description = "Output format: text (default), or json for typed results programs can parse"
+    )]
+    pub output_format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct EditAndCheckRequest {
+    #[schemars(description = "Path to the file to edit")]
+    pub file_path: String,
+    #[schemars(description = "List of text edits to apply")]
+    pub edits: Vec<tools::edit::TextEditParams>,
+    #[schemars(
+        description = "Content hash from file_hashes; the edit is refused if the file has changed since"
+    )]
+    pub expected_hash: Option<String>,
+    #[schemars(
+        description = "Wait up to this many milliseconds for diagnostics on the edited file (default 5000)"
+    )]
+    pub wait_ms: Option<u64>,
+    #[schemars(description = "Number of context lines to show around diagnostics")]
+    pub context_lines: Option<u32>,
+    #[schemars(
+        description = "Check the edited content without writing it: the server sees it only for this call"
+    )]
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct FileHashesRequest {
+    #[schemars(description = "Paths of the files to hash")]
+    pub file_paths: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct DefinitionRequest {
     #[schemars(description = "The symbol name to find definition for")]
     pub symbol_name: String,
+    #[schemars(
+        description = "Number of lines to show before and after the definition, numbered (default 0)"
+    )]
+    pub context_lines: Option<u32>,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+    #[schemars(
+        description = "Output format: text (default), or json for typed results programs can parse"
+    )]
+    pub output_format: Option<String>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct ReferencesRequest {
-    #[schemars(description = "The symbol name to find references for")]
+    #[schemars(
+        description = "The symbol to find references for: a name such as Config or Config::load, or a path:line:column location"
+    )]
+    pub symbol_name: String,
+    #[schemars(
+        description = "Number of lines to show before and after each reference, numbered (default 0)"
+    )]
+    pub context_lines: Option<u32>,
+    #[schemars(description = "Maximum number of references to return (default 200)")]
+    pub max_results: Option<usize>,
+    #[schemars(
+        description = "Number of references to skip, to get the next page of results (default 0)"
+    )]
+    pub offset: Option<usize>,
+    #[schemars(description = "Maximum number of references to return from any one file")]
+    pub max_per_file: Option<usize>,
+    #[schemars(
+        description = "Only report references within this scope around the symbol: workspace (default), file, function or module"
+    )]
+    pub scope: Option<String>,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+    #[schemars(
+        description = "Output format: text (default), or json for typed results programs can parse"
+    )]
+    pub output_format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CallHierarchyRequest {
+    #[schemars(
+        description = "The function to list calls for: a name such as parse or Config::load, or a path:line:column location"
+    )]
+    pub symbol_name: String,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+    #[schemars(
+        description = "Output format: text (default), or json for typed results programs can parse"
+    )]
+    pub output_format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct WorkspaceDiagnosticsRequest {
+    #[schemars(
+        description = "Output format: text (default), or json for typed results programs can parse"
+    )]
+    pub output_format: Option<String>,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct TypeHierarchyRequest {
+    #[schemars(
+        description = "The type to list the hierarchy of: a name such as Shape or io::Read, or a path:line:column location"
+    )]
     pub symbol_name: String,
+    #[schemars(description = "Which side to list: supertypes, subtypes or both (default)")]
+    pub direction: Option<String>,
+    #[schemars(description = "Levels of the hierarchy to list (default 3, at most 10)")]
+    pub depth: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ProjectModelRequest {
+    #[schemars(
+        description = "Optional file to also list the targets and tests that can run it (rust-analyzer only)"
+    )]
+    pub file_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct StatusRequest {
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ServerCapabilitiesRequest {
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RestartRequest {
+    #[schemars(
+        description = "Optional file whose language server to restart; every running server is restarted without it"
+    )]
+    pub file_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ImplsRequest {
+    #[schemars(description = "Name of the trait or interface to find implementations of")]
+    pub trait_name: String,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RelatedFilesRequest {
+    #[schemars(description = "Path to the file to find related files for")]
+    pub file_path: String,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct DocumentSymbolsRequest {
+    #[schemars(description = "Path to the file to outline")]
+    pub file_path: String,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -36,6 +281,22 @@ pub struct DiagnosticsRequest {
     pub context_lines: Option<u32>,
     #[schemars(description = "Show line numbers in the output")]
     pub show_line_numbers: Option<bool>,
+    #[schemars(
+        description = "Also show the signature and doc comment of the function enclosing each diagnostic"
+    )]
+    pub include_enclosing: Option<bool>,
+    #[schemars(
+        description = "Wait up to this many milliseconds for the language server to analyze a file it has not reported on yet (default 0)"
+    )]
+    pub wait_ms: Option<u64>,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+    #[schemars(
+        description = "Output format: text (default), or json for typed results programs can parse"
+    )]
+    pub output_format: Option<String>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -46,6 +307,192 @@ pub struct HoverRequest {
     pub line: u32,
     #[schemars(description = "Column number (0-based)")]
     pub column: u32,
+    #[schemars(
+        description = "Maximum length of the hover text in characters (default 2000); longer hovers keep the signature and first doc paragraph"
+    )]
+    pub max_length: Option<u32>,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CompletionRequest {
+    #[schemars(description = "Path to the file")]
+    pub file_path: String,
+    #[schemars(description = "Line number (1-based)")]
+    pub line: u32,
+    #[schemars(description = "Column number (1-based)")]
+    pub column: u32,
+    #[schemars(description = "Maximum number of completions to return (default 20)")]
+    pub max_items: Option<u32>,
+    #[schemars(description = "Only return completions starting with this prefix")]
+    pub prefix: Option<String>,
+    #[schemars(
+        description = "Resolve items missing documentation with completionItem/resolve (default true)"
+    )]
+    pub resolve: Option<bool>,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CodeActionsRequest {
+    #[schemars(description = "Path to the file")]
+    pub file_path: String,
+    #[schemars(description = "Line number (0-based)")]
+    pub line: u32,
+    #[schemars(description = "Column number (0-based)")]
+    pub column: u32,
+    #[schemars(description = "Last line of the range (defaults to the start line)")]
+    pub end_line: Option<u32>,
+    #[schemars(description = "Column the range ends at (defaults to the start column)")]
+    pub end_column: Option<u32>,
+    #[schemars(
+        description = "Only list actions of this kind, e.g. quickfix, refactor.extract or source.organizeImports"
+    )]
+    pub only: Option<String>,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ApplyCodeActionRequest {
+    #[schemars(description = "Path to the file")]
+    pub file_path: String,
+    #[schemars(description = "Line number (0-based)")]
+    pub line: u32,
+    #[schemars(description = "Column number (0-based)")]
+    pub column: u32,
+    #[schemars(description = "Last line of the range (defaults to the start line)")]
+    pub end_line: Option<u32>,
+    #[schemars(description = "Column the range ends at (defaults to the start column)")]
+    pub end_column: Option<u32>,
+    #[schemars(description = "Kind filter used when listing the actions")]
+    pub only: Option<String>,
+    #[schemars(description = "Number of the action in the code_actions listing")]
+    pub index: usize,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct TestAnchorRequest {
+    #[schemars(
+        description = "The function to write a test for: a name such as parse or Config::load, or a path:line:column location"
+    )]
+    pub symbol_name: String,
+    #[schemars(description = "Create the test file with a stub if there is none (default true)")]
+    pub create: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct MonikerRequest {
+    #[schemars(description = "Path to the file")]
+    pub file_path: String,
+    #[schemars(description = "Line number (1-based)")]
+    pub line: u32,
+    #[schemars(description = "Column number (1-based)")]
+    pub column: u32,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct DocumentColorsRequest {
+    #[schemars(description = "Path to the stylesheet or other file to list colors for")]
+    pub file_path: String,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ColorPresentationsRequest {
+    #[schemars(description = "Path to the file")]
+    pub file_path: String,
+    #[schemars(description = "Line number (1-based)")]
+    pub line: u32,
+    #[schemars(description = "Column number (1-based)")]
+    pub column: u32,
+    #[schemars(
+        description = "Replacement color as #rgb, #rrggbb or #rrggbbaa; defaults to the color already at the position"
+    )]
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct DocumentHighlightsRequest {
+    #[schemars(description = "Path to the file")]
+    pub file_path: String,
+    #[schemars(description = "Line number (0-based)")]
+    pub line: u32,
+    #[schemars(description = "Column number (0-based)")]
+    pub column: u32,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct FoldingRangesRequest {
+    #[schemars(description = "Path to the file")]
+    pub file_path: String,
+    #[schemars(description = "Leave out ranges spanning fewer lines than this (default 1)")]
+    pub min_lines: Option<u32>,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SelectionRangesRequest {
+    #[schemars(description = "Path to the file")]
+    pub file_path: String,
+    #[schemars(description = "Line number (0-based)")]
+    pub line: u32,
+    #[schemars(description = "Column number (0-based)")]
+    pub column: u32,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SemanticTokensRequest {
+    #[schemars(description = "Path to the file")]
+    pub file_path: String,
+    #[schemars(description = "First line to report (defaults to the start of the file)")]
+    pub start_line: Option<u32>,
+    #[schemars(description = "Last line to report (defaults to the end of the file)")]
+    pub end_line: Option<u32>,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct InlineValuesRequest {
+    #[schemars(description = "Path to the file")]
+    pub file_path: String,
+    #[schemars(description = "First line to report (defaults to the start of the file)")]
+    pub start_line: Option<u32>,
+    #[schemars(description = "Last line to report (defaults to the end of the file)")]
+    pub end_line: Option<u32>,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -58,13 +505,119 @@ pub struct RenameRequest {
     pub column: u32,
     #[schemars(description = "New name for the symbol")]
     pub new_name: String,
+    #[schemars(
+        description = "Apply the rename even if the new name collides with an existing symbol"
+    )]
+    pub allow_conflicts: Option<bool>,
+    #[schemars(description = "Return the rename as unified diffs without applying it")]
+    pub dry_run: Option<bool>,
+    #[schemars(description = "Include a unified diff of each changed file in the result")]
+    pub show_diff: Option<bool>,
+    #[schemars(
+        description = "Output format: text (default), or json for typed results programs can parse"
+    )]
+    pub output_format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct OrganizeImportsRequest {
+    #[schemars(
+        description = "Path to the file, or to the directory for the directory and workspace scopes"
+    )]
+    pub file_path: String,
+    #[schemars(
+        description = "Which files to organize: file (default), directory for every file in its directory, or workspace. Given a file, only files of its language are organized"
+    )]
+    pub scope: Option<String>,
+    #[schemars(description = "Return the changes as a unified diff without applying them")]
+    pub dry_run: Option<bool>,
+    #[schemars(description = "Include a unified diff of the change in the result")]
+    pub show_diff: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct FixDiagnosticsRequest {
+    #[schemars(description = "Path to the file (defaults to every file with diagnostics)")]
+    pub file_path: Option<String>,
+    #[schemars(
+        description = "Which files to fix around file_path: file (default), directory for every file in its directory, or workspace"
+    )]
+    pub scope: Option<String>,
+    #[schemars(description = "Most fixes to apply (defaults to 20)")]
+    pub max_fixes: Option<usize>,
+    #[schemars(description = "Return the fixes as unified diffs without applying them")]
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CodeLensesRequest {
+    #[schemars(description = "Path to the file")]
+    pub file_path: String,
+    #[schemars(
+        description = "Output style: markdown (default), plain, or compact with one line per item"
+    )]
+    pub output_style: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RunCodeLensRequest {
+    #[schemars(description = "Path to the file")]
+    pub file_path: String,
+    #[schemars(description = "Number of the lens in the code_lenses listing")]
+    pub index: usize,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ExecuteCommandRequest {
+    #[schemars(description = "Name of the command, as listed by server_capabilities")]
+    pub command: String,
+    #[schemars(description = "JSON arguments of the command")]
+    pub arguments: Option<Vec<serde_json::Value>>,
+    #[schemars(
+        description = "File whose language server runs the command; the primary one if not given"
+    )]
+    pub file_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct LspRequestRequest {
+    #[schemars(
+        description = "LSP method to call, e.g. rust-analyzer/expandMacro or workspace/executeCommand"
+    )]
+    pub method: String,
+    #[schemars(description = "JSON params of the request")]
+    pub params: Option<serde_json::Value>,
+    #[schemars(
+        description = "File the request is about; it is opened first and decides which language server gets the request"
+    )]
+    pub file_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct WorkspaceFolderRequest {
+    #[schemars(description = "Path to the folder, absolute or relative to the workspace root")]
+    pub folder: String,
 }
 
 /// MCP Server implementation with LSP backend
 #[derive(Clone)]
 pub struct McpLanguageServer {
     lsp_client: Arc<lsp::Client>,
+    router: lsp::Router,
     workspace_dir: std::path::PathBuf,
+    workspace_warning: Option<String>,
+    session: Option<Arc<Session>>,
+    file_hashes: Arc<FileHashes>,
+    idle_monitor: Option<Arc<lsp::IdleMonitor>>,
+    roots: Option<Arc<WorkspaceRoots>>,
+    ready_timeout: Duration,
+    // Carries the ready signal once the language server is up
+    ready_signal: Option<watch::Receiver<Option<ReadySignal>>>,
+    // Set when several MCP clients share the language servers
+    client_session: Option<Arc<ClientSession>>,
+    peer: Option<Peer<RoleServer>>,
+    subscriptions: Arc<ResourceSubscriptions>,
+    custom_tools: Arc<Vec<CustomToolConfig>>,
 }
 
 impl std::fmt::Debug for McpLanguageServer {
@@ -72,6 +625,7 @@ impl std::fmt::Debug for McpLanguageServer {
         f.debug_struct("McpLanguageServer")
             .field("lsp_client", &"<LSP Client>")
             .field("workspace_dir", &self.workspace_dir)
+            .field("workspace_warning", &self.workspace_warning)
             .finish()
     }
 }
@@ -79,86 +633,1935 @@ impl std::fmt::Debug for McpLanguageServer {
 impl McpLanguageServer {
     pub fn new(lsp_client: Arc<lsp::Client>, workspace_dir: std::path::PathBuf) -> Self {
         Self {
+            router: lsp::Router::new(Arc::clone(&lsp_client)),
             lsp_client,
             workspace_dir,
+            workspace_warning: None,
+            session: None,
+            file_hashes: FileHashes::new(),
+            idle_monitor: None,
+            roots: None,
+            ready_timeout: DEFAULT_READY_TIMEOUT,
+            ready_signal: None,
+            client_session: None,
+            peer: None,
+            subscriptions: ResourceSubscriptions::new(),
+            custom_tools: Arc::new(Vec::new()),
         }
     }
-}
 
-// Create a toolbox for our tools
-#[tool(tool_box)]
-impl McpLanguageServer {
-    #[tool(description = "Edit a file by applying text edits")]
-    async fn edit_file(&self, #[tool(aggr)] request: EditFileRequest) -> String {
-        let path = Path::new(&request.file_path).to_path_buf();
-        match tools::apply_text_edits(&self.lsp_client, path, request.edits).await {
-            Ok(result) => result,
-            Err(e) => format!("Error editing file: {}", e),
+    /// Routes tool calls about a file to the language server for its language;
+    /// the router's primary server answers the others
+    pub fn with_router(mut self, router: lsp::Router) -> Self {
+        self.lsp_client = Arc::clone(router.primary());
+        self.router = router;
+        self
+    }
+
+    /// Attaches a warning about the workspace that is reported to the MCP client
+    pub fn with_workspace_warning(mut self, warning: Option<String>) -> Self {
+        self.workspace_warning = warning;
+        self
+    }
+
+    /// Attaches the persistent session that tool usage is recorded in
+    pub fn with_session(mut self, session: Option<Arc<Session>>) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Shares content hashes with the file watcher that keeps them current
+    pub fn with_file_hashes(mut self, file_hashes: Arc<FileHashes>) -> Self {
+        self.file_hashes = file_hashes;
+        self
+    }
+
+    /// Attaches the monitor that releases language server resources while idle
+    pub fn with_idle_monitor(mut self, idle_monitor: Option<Arc<lsp::IdleMonitor>>) -> Self {
+        self.idle_monitor = idle_monitor;
+        self
+    }
+
+    /// Follows the roots the MCP client lists, which set the workspace if none
+    /// was given and add workspace folders
+    pub fn with_roots(mut self, roots: Arc<WorkspaceRoots>) -> Self {
+        self.roots = Some(roots);
+        self
+    }
+
+    /// Sets how long tool calls wait for the language server to finish indexing
+    /// before answering with what it has
+    pub fn with_ready_timeout(mut self, ready_timeout: Duration) -> Self {
+        self.ready_timeout = ready_timeout;
+        self
+    }
+
+    /// Adds the tools declared in the config file. Ones named like a built-in
+    /// tool are left out.
+    pub fn with_custom_tools(mut self, custom_tools: Vec<CustomToolConfig>) -> Self {
+        let builtin = Self::tool_names();
+        self.custom_tools = Arc::new(
+            custom_tools
+                .into_iter()
+                .filter(|tool| {
+                    let clashes = builtin.contains(&tool.name);
+                    if clashes {
+                        log::warn!(
+                            "[MCP] Ignoring custom tool {}: a tool has that name",
+                            tool.name
+                        );
+                    }
+                    !clashes
+                })
+                .collect(),
+        );
+        self
+    }
+
+    /// Sends the ready signal to each MCP client as a logging notification,
+    /// once it is set
+    pub fn with_ready_signal(mut self, ready_signal: watch::Receiver<Option<ReadySignal>>) -> Self {
+        self.ready_signal = Some(ready_signal);
+        self
+    }
+
+    /// Creates the handler for a newly connected MCP client that shares the
+    /// language servers with others; the files it used are closed once no
+    /// other client uses them after it disconnects
+    pub fn for_new_client(&self, leases: &Arc<DocumentLeases>) -> Self {
+        Self {
+            client_session: Some(leases.connect(self.router.clone())),
+            subscriptions: ResourceSubscriptions::new(),
+            ..self.clone()
         }
     }
 
-    #[tool(description = "Find the definition of a symbol")]
-    async fn definition(&self, #[tool(aggr)] request: DefinitionRequest) -> String {
-        match tools::find_definition(&self.lsp_client, &request.symbol_name).await {
-            Ok(result) => result,
-            Err(e) => format!("Error finding definition: {}", e),
+    /// Gets the watcher of the workspace, which only starts once the
+    /// workspace is known
+    fn watcher(&self) -> Option<&FileSystemWatcher> {
+        self.roots.as_ref()?.watcher()
+    }
+
+    /// Builds the check for paths the file watcher ignores, loading the
+    /// workspace's gitignore rules itself while the watcher hasn't started
+    fn ignore_check(&self) -> impl Fn(&Path) -> bool + '_ {
+        let fallback = match self.watcher() {
+            Some(_) => None,
+            None => self.lsp_client.workspace_root().map(GitignoreFilter::new),
+        };
+        move |path| match (self.watcher(), &fallback) {
+            (Some(watcher), _) => watcher.is_ignored(path),
+            (None, Some(filter)) => filter.is_ignored(path),
+            (None, None) => false,
         }
     }
 
-    #[tool(description = "Find all references to a symbol")]
-    async fn references(&self, #[tool(aggr)] request: ReferencesRequest) -> String {
-        match tools::find_references(&self.lsp_client, &request.symbol_name).await {
-            Ok(result) => result,
-            Err(e) => format!("Error finding references: {}", e),
+    /// Gets the running servers and the servers attached to them, which
+    /// each publish diagnostics of their own
+    fn diagnostics_servers(&self) -> Vec<Arc<lsp::Client>> {
+        self.router
+            .clients()
+            .into_iter()
+            .flat_map(|client| {
+                let attached = client.attached_servers();
+                std::iter::once(client).chain(attached)
+            })
+            .collect()
+    }
+
+    /// Gets the diagnostics every running server reported for a file
+    fn file_diagnostics(&self, path: &Path) -> Vec<lsp_types::Diagnostic> {
+        let uri = tools::utils::to_uri(path);
+        self.router
+            .clients()
+            .iter()
+            .flat_map(|client| client.get_all_diagnostics(&uri))
+            .collect()
+    }
+
+    /// Asks the client for its roots and applies them to the workspace
+    fn update_roots(&self) {
+        let (Some(roots), Some(peer)) = (self.roots.clone(), self.peer.clone()) else {
+            return;
+        };
+        let router = self.router.clone();
+        let client = self
+            .client_session
+            .as_ref()
+            .map_or(0, |session| session.id());
+
+        // The client answers on the connection this notification came from
+        tokio::spawn(async move {
+            match peer.list_roots().await {
+                Ok(result) => {
+                    let uris: Vec<String> = result.roots.into_iter().map(|root| root.uri).collect();
+                    roots.update(client, &uris, &router).await;
+                }
+                Err(e) if roots.workspace().is_none() => log::error!(
+                    "[MCP] No workspace: the client doesn't list roots ({}); pass --workspace",
+                    e
+                ),
+                Err(e) => log::debug!("[MCP] Client doesn't list roots: {}", e),
+            }
+        });
+    }
+
+    /// Tells the client the language server is ready, right away if it
+    /// already was when the client connected
+    fn announce_ready(&self) {
+        let (Some(mut ready_signal), Some(peer)) = (self.ready_signal.clone(), self.peer.clone())
+        else {
+            return;
+        };
+        tokio::spawn(async move {
+            let message = match ready_signal.wait_for(Option::is_some).await {
+                Ok(signal) => signal.as_ref().map(ReadySignal::logging_message),
+                Err(_) => None,
+            };
+            if let Some(message) = message
+                && let Err(e) = peer.notify_logging_message(message).await
+            {
+                log::warn!("[MCP] Failed to send ready notification: {}", e);
+            }
+        });
+    }
+
+    /// Tells the client to list the tools again once the language servers are
+    /// ready, if some turn out to be unsupported. Clients list them right
+    /// after connecting, before the servers have said what they support.
+    fn announce_unsupported_tools(&self) {
+        let Some(peer) = self.peer.clone() else {
+            return;
+        };
+        let server = self.clone();
+        tokio::spawn(async move {
+            if server.wait_for_lsp().await.is_err()
+                || availability::unsupported_tools(&server.router).is_empty()
+            {
+                return;
+            }
+            if let Err(e) = peer.notify_tool_list_changed().await {
+                log::warn!("[MCP] Failed to announce the tool list change: {}", e);
+            }
+        });
+    }
+
+    /// Sends resource notifications from the events of the running servers,
+    /// once they have started
+    fn forward_resource_notifications(&self) {
+        let Some(peer) = self.peer.clone() else {
+            return;
+        };
+        let server = self.clone();
+        tokio::spawn(async move {
+            if server.wait_for_lsp().await.is_err() {
+                return;
+            }
+            for client in server.diagnostics_servers() {
+                tokio::spawn(
+                    Arc::clone(&server.subscriptions)
+                        .forward(client.subscribe_events(), peer.clone()),
+                );
+            }
+        });
+    }
+
+    /// Lists the names of the tools this server advertises
+    pub fn tool_names() -> Vec<String> {
+        let mut names: Vec<String> = Self::tool_box()
+            .list()
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Records a tool call in the session state and schedules a save
+    fn record_tool(&self, tool: &str) {
+        if let Some(idle_monitor) = &self.idle_monitor {
+            idle_monitor.touch();
+        }
+        if let Some(session) = &self.session {
+            session.record_tool(tool);
+            session.schedule_save(Arc::clone(&self.lsp_client));
         }
     }
 
-    #[tool(description = "Get diagnostics for a file")]
-    async fn diagnostics(&self, #[tool(aggr)] request: DiagnosticsRequest) -> String {
-        let path = Path::new(&request.file_path).to_path_buf();
-        let context_lines = request.context_lines.unwrap_or(5);
-        let show_line_numbers = request.show_line_numbers.unwrap_or(true);
+    /// Parses the output style a tool call asked for
+    fn output_style(style: Option<&str>) -> Result<Option<OutputStyle>, String> {
+        style
+            .map(OutputStyle::parse)
+            .transpose()
+            .map_err(|e| format!("Error: {}", e))
+    }
 
-        match tools::get_diagnostics(&self.lsp_client, path, context_lines, show_line_numbers).await
-        {
-            Ok(result) => result,
-            Err(e) => format!("Error getting diagnostics: {}", e),
+    /// Parses the output format a tool call asked for
+    fn output_format(format: Option<&str>) -> Result<Option<OutputFormat>, String> {
+        format
+            .map(OutputFormat::parse)
+            .transpose()
+            .map_err(|e| format!("Error: {}", e))
+    }
+
+    /// Gets the language server that handles a file, starting it if needed
+    async fn client_for(&self, file_path: &Path) -> Result<Arc<lsp::Client>, String> {
+        if let Some(client_session) = &self.client_session {
+            client_session.use_file(file_path);
         }
+        self.router
+            .client_for(file_path)
+            .await
+            .map_err(|e| format!("Error: {}", e))
     }
 
-    #[tool(description = "Get hover information at a specific position")]
-    async fn hover(&self, #[tool(aggr)] request: HoverRequest) -> String {
-        let path = Path::new(&request.file_path).to_path_buf();
-        match tools::get_hover_info(&self.lsp_client, path, request.line, request.column).await {
+    /// Gets the servers to ask about a symbol: the one handling its file for a
+    /// path:line:column location, or every running server for a name
+    async fn clients_for_symbol(&self, symbol_name: &str) -> Result<Vec<Arc<lsp::Client>>, String> {
+        match tools::definition::parse_symbol_location(symbol_name) {
+            Ok((path, _, _)) => Ok(vec![self.client_for(&path).await?]),
+            Err(_) => Ok(self.router.clients()),
+        }
+    }
+
+    /// Runs a tool on each server and merges what they found. Servers that
+    /// fail are left out unless all of them do.
+    async fn on_each<F, Fut>(clients: Vec<Arc<lsp::Client>>, run: F) -> Result<String>
+    where
+        F: Fn(Arc<lsp::Client>) -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let results =
+            futures::future::join_all(clients.iter().map(|client| run(Arc::clone(client)))).await;
+
+        let mut outputs = Vec::new();
+        let mut first_error = None;
+        for (client, result) in clients.iter().zip(results) {
+            match result {
+                Ok(output) => outputs.push((client.name().to_string(), output)),
+                Err(e) => {
+                    log::debug!("[MCP] {} found nothing: {}", client.name(), e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        if outputs.is_empty() {
+            return Err(
+                first_error.unwrap_or_else(|| anyhow::anyhow!("No language server is running"))
+            );
+        }
+        Ok(tools::utils::merge_server_outputs(outputs))
+    }
+
+    /// Runs a tool declared in the config file on the server for the file it
+    /// names, or else on the first server offering what the tool needs
+    async fn call_custom_tool(
+        &self,
+        tool: &CustomToolConfig,
+        arguments: Option<&rmcp::model::JsonObject>,
+    ) -> String {
+        self.record_tool(&tool.name);
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        let client = match custom_tools::file_path(arguments) {
+            Some(path) => match self.client_for(&path).await {
+                Ok(client) => client,
+                Err(e) => return e,
+            },
+            None => match custom_tools::servers_for(tool, &self.router)
+                .into_iter()
+                .next()
+            {
+                Some(client) => client,
+                None => {
+                    return format!(
+                        "Error calling {}: no language server offers {}",
+                        tool.name,
+                        tool.requires.as_deref().unwrap_or(&tool.method)
+                    );
+                }
+            },
+        };
+        match custom_tools::call(tool, &client, arguments).await {
             Ok(result) => result,
-            Err(e) => format!("Error getting hover info: {}", e),
+            Err(e) => format!("Error calling {}: {}", tool.name, e),
         }
     }
 
-    #[tool(description = "Rename a symbol at a specific position")]
-    async fn rename_symbol(&self, #[tool(aggr)] request: RenameRequest) -> String {
-        let path = Path::new(&request.file_path).to_path_buf();
-        match tools::rename_symbol(
-            &self.lsp_client,
-            path,
-            request.line,
-            request.column,
-            request.new_name,
+    /// Holds a tool call until the LSP servers have finished initializing
+    async fn wait_for_lsp(&self) -> Result<(), String> {
+        // A server stopped while idle is started again first
+        if let Some(idle_monitor) = &self.idle_monitor {
+            idle_monitor
+                .wake()
+                .await
+                .map_err(|e| format!("Error: {}", e))?;
+        }
+
+        let clients = self.router.clients();
+        let results = futures::future::join_all(
+            clients
+                .iter()
+                .map(|client| client.wait_until_ready(self.ready_timeout)),
         )
-        .await
-        {
-            Ok(result) => result,
-            Err(e) => format!("Error renaming symbol: {}", e),
+        .await;
+        for (client, result) in clients.iter().zip(results) {
+            match result {
+                Ok(true) => {}
+                Ok(false) => log::warn!(
+                    "[MCP] {} still busy after {:?}; answering anyway",
+                    client.name(),
+                    self.ready_timeout
+                ),
+                // Calls for other languages still work without this server
+                Err(e) if !Arc::ptr_eq(client, &self.lsp_client) => {
+                    log::warn!("[MCP] {} is unavailable: {}", client.name(), e)
+                }
+                Err(e) => return Err(format!("Error: {}", e)),
+            }
         }
+        Ok(())
     }
 }
 
-// Implement the ServerHandler trait for MCP
+// Create a toolbox for our tools
 #[tool(tool_box)]
+impl McpLanguageServer {
+    #[tool(description = "Edit a file by applying text edits")]
+    async fn edit_file(&self, #[tool(aggr)] request: EditFileRequest) -> String {
+        self.record_tool("edit_file");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let format = match Self::output_format(request.output_format.as_deref()) {
+            Ok(format) => format,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        if let Some(expected) = &request.expected_hash {
+            let checked = match normalize_path(&path) {
+                Ok(path) => self.file_hashes.check(&path, expected).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = checked {
+                return format!("Error editing file: {}", e);
+            }
+        }
+
+        match render::with_format(
+            format,
+            tools::apply_text_edits(
+                &client,
+                path.clone(),
+                request.edits,
+                request.dry_run.unwrap_or(false),
+            ),
+        )
+        .await
+        {
+            Ok(result) => {
+                // The server already has the new content; spare the watcher a resync
+                if let Ok(path) = normalize_path(&path) {
+                    let _ = self.file_hashes.refresh(&path).await;
+                }
+                result
+            }
+            Err(e) => format!("Error editing file: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Edit a file by applying text edits, then wait for the language server to check it and return the new diagnostics"
+    )]
+    async fn edit_and_check(&self, #[tool(aggr)] request: EditAndCheckRequest) -> String {
+        self.record_tool("edit_and_check");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        if let Some(expected) = &request.expected_hash {
+            let checked = match normalize_path(&path) {
+                Ok(path) => self.file_hashes.check(&path, expected).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = checked {
+                return format!("Error editing file: {}", e);
+            }
+        }
+
+        let wait = Duration::from_millis(request.wait_ms.unwrap_or(5000));
+        let dry_run = request.dry_run.unwrap_or(false);
+        match tools::edit_and_check(
+            &client,
+            path.clone(),
+            request.edits,
+            request.context_lines.unwrap_or(5),
+            wait,
+            dry_run,
+        )
+        .await
+        {
+            Ok(result) => {
+                // The server already has the new content; spare the watcher a resync
+                if !dry_run && let Ok(path) = normalize_path(&path) {
+                    let _ = self.file_hashes.refresh(&path).await;
+                }
+                result
+            }
+            Err(e) => format!("Error editing file: {}", e),
+        }
+    }
+
+    #[tool(description = "Get content hashes of files, to tell whether they changed between calls")]
+    async fn file_hashes(&self, #[tool(aggr)] request: FileHashesRequest) -> String {
+        self.record_tool("file_hashes");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        let paths: Vec<PathBuf> = request.file_paths.iter().map(PathBuf::from).collect();
+        let client = match paths.first() {
+            Some(path) => match self.client_for(path).await {
+                Ok(client) => client,
+                Err(e) => return e,
+            },
+            None => Arc::clone(&self.lsp_client),
+        };
+        match tools::get_file_hashes(&client, &self.file_hashes, paths).await {
+            Ok(result) => result,
+            Err(e) => format!("Error hashing files: {}", e),
+        }
+    }
+
+    #[tool(description = "Find the definition of a symbol")]
+    async fn definition(&self, #[tool(aggr)] request: DefinitionRequest) -> String {
+        self.record_tool("definition");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+        let format = match Self::output_format(request.output_format.as_deref()) {
+            Ok(format) => format,
+            Err(e) => return e,
+        };
+
+        let client = match tools::definition::parse_symbol_location(&request.symbol_name) {
+            Ok((path, _, _)) => match self.client_for(&path).await {
+                Ok(client) => client,
+                Err(e) => return e,
+            },
+            Err(e) => return format!("Error finding definition: {}", e),
+        };
+
+        match render::with_style(
+            style,
+            render::with_format(
+                format,
+                tools::find_definition(
+                    &client,
+                    &request.symbol_name,
+                    request.context_lines.unwrap_or(0),
+                ),
+            ),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error finding definition: {}", e),
+        }
+    }
+
+    #[tool(description = "Find all references to a symbol")]
+    async fn references(&self, #[tool(aggr)] request: ReferencesRequest) -> String {
+        self.record_tool("references");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+        let format = match Self::output_format(request.output_format.as_deref()) {
+            Ok(format) => format,
+            Err(e) => return e,
+        };
+
+        let scope = match request.scope.as_deref() {
+            Some(scope) => match tools::references::ReferenceScope::parse(scope) {
+                Ok(scope) => scope,
+                Err(e) => return format!("Error finding references: {}", e),
+            },
+            None => tools::references::ReferenceScope::Workspace,
+        };
+        let page = tools::references::ReferencePage {
+            offset: request.offset.unwrap_or(0),
+            max_results: Some(
+                request
+                    .max_results
+                    .unwrap_or(tools::references::DEFAULT_MAX_REFERENCES),
+            ),
+            max_per_file: request.max_per_file,
+        };
+
+        let clients = match self.clients_for_symbol(&request.symbol_name).await {
+            Ok(clients) => clients,
+            Err(e) => return e,
+        };
+        let symbol_name = request.symbol_name.as_str();
+        let context_lines = request.context_lines.unwrap_or(0);
+        let page = &page;
+
+        match render::with_style(
+            style,
+            render::with_format(
+                format,
+                Self::on_each(clients, move |client| async move {
+                    tools::find_references(&client, symbol_name, scope, context_lines, page).await
+                }),
+            ),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error finding references: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "List the functions that call a function, grouped by file with a preview of each call"
+    )]
+    async fn incoming_calls(&self, #[tool(aggr)] request: CallHierarchyRequest) -> String {
+        self.record_tool("incoming_calls");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+        let format = match Self::output_format(request.output_format.as_deref()) {
+            Ok(format) => format,
+            Err(e) => return e,
+        };
+
+        let clients = match self.clients_for_symbol(&request.symbol_name).await {
+            Ok(clients) => clients,
+            Err(e) => return e,
+        };
+        let symbol_name = request.symbol_name.as_str();
+
+        match render::with_style(
+            style,
+            render::with_format(
+                format,
+                Self::on_each(clients, move |client| async move {
+                    tools::find_incoming_calls(&client, symbol_name).await
+                }),
+            ),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error finding incoming calls: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "List the functions a function calls, grouped by the file they're defined in with a preview of each call"
+    )]
+    async fn outgoing_calls(&self, #[tool(aggr)] request: CallHierarchyRequest) -> String {
+        self.record_tool("outgoing_calls");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+        let format = match Self::output_format(request.output_format.as_deref()) {
+            Ok(format) => format,
+            Err(e) => return e,
+        };
+
+        let clients = match self.clients_for_symbol(&request.symbol_name).await {
+            Ok(clients) => clients,
+            Err(e) => return e,
+        };
+        let symbol_name = request.symbol_name.as_str();
+
+        match render::with_style(
+            style,
+            render::with_format(
+                format,
+                Self::on_each(clients, move |client| async move {
+                    tools::find_outgoing_calls(&client, symbol_name).await
+                }),
+            ),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error finding outgoing calls: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "List the supertypes and subtypes of a type, such as the traits a struct implements or the classes extending a class, as indented trees"
+    )]
+    async fn type_hierarchy(&self, #[tool(aggr)] request: TypeHierarchyRequest) -> String {
+        self.record_tool("type_hierarchy");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let direction = match request.direction.as_deref() {
+            Some(direction) => match TypeHierarchyDirection::parse(direction) {
+                Ok(direction) => direction,
+                Err(e) => return format!("Error getting type hierarchy: {}", e),
+            },
+            None => TypeHierarchyDirection::default(),
+        };
+
+        let clients = match self.clients_for_symbol(&request.symbol_name).await {
+            Ok(clients) => clients,
+            Err(e) => return e,
+        };
+        let symbol_name = request.symbol_name.as_str();
+        let depth = request.depth.unwrap_or(type_hierarchy::DEFAULT_DEPTH);
+
+        match Self::on_each(clients, move |client| async move {
+            tools::get_type_hierarchy(&client, symbol_name, direction, depth).await
+        })
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error getting type hierarchy: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Describe the build metadata the language server loaded: workspace crates, dependencies and cargo targets from rust-analyzer, or modules and packages from gopls"
+    )]
+    async fn project_model(&self, #[tool(aggr)] request: ProjectModelRequest) -> String {
+        self.record_tool("project_model");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        let path = request
+            .file_path
+            .as_deref()
+            .map(|path| Path::new(path).to_path_buf());
+        let client = match &path {
+            Some(path) => match self.client_for(path).await {
+                Ok(client) => client,
+                Err(e) => return e,
+            },
+            None => Arc::clone(&self.lsp_client),
+        };
+        match tools::get_project_model(&client, path).await {
+            Ok(result) => result,
+            Err(e) => format!("Error getting project model: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "List all implementors of a named trait or interface with their locations"
+    )]
+    async fn find_impls_of_trait(&self, #[tool(aggr)] request: ImplsRequest) -> String {
+        self.record_tool("find_impls_of_trait");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        let trait_name = request.trait_name.as_str();
+        match render::with_style(
+            style,
+            Self::on_each(self.router.clients(), move |client| async move {
+                tools::find_impls_of_trait(&client, trait_name).await
+            }),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error finding implementations: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Suggest files related to a file: its tests, header/source counterpart, linked files and sibling modules"
+    )]
+    async fn related_files(&self, #[tool(aggr)] request: RelatedFilesRequest) -> String {
+        self.record_tool("related_files");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        match render::with_style(style, tools::find_related_files(&client, path)).await {
+            Ok(result) => result,
+            Err(e) => format!("Error finding related files: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Get a hierarchical outline of a file (types, impls, functions, fields) with line ranges"
+    )]
+    async fn document_symbols(&self, #[tool(aggr)] request: DocumentSymbolsRequest) -> String {
+        self.record_tool("document_symbols");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        match render::with_style(style, tools::get_document_symbols(&client, path)).await {
+            Ok(result) => result,
+            Err(e) => format!("Error getting document symbols: {}", e),
+        }
+    }
+
+    #[tool(description = "Get diagnostics for a file")]
+    async fn diagnostics(&self, #[tool(aggr)] request: DiagnosticsRequest) -> String {
+        self.record_tool("diagnostics");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+        let format = match Self::output_format(request.output_format.as_deref()) {
+            Ok(format) => format,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        let context_lines = request.context_lines.unwrap_or(5);
+        let show_line_numbers = request.show_line_numbers.unwrap_or(true);
+        let include_enclosing = request.include_enclosing.unwrap_or(false);
+        let wait = Duration::from_millis(request.wait_ms.unwrap_or(0));
+
+        match render::with_style(
+            style,
+            render::with_format(
+                format,
+                tools::get_diagnostics(
+                    &client,
+                    path,
+                    context_lines,
+                    show_line_numbers,
+                    include_enclosing,
+                    wait,
+                ),
+            ),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error getting diagnostics: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "List the diagnostics of every file in the workspace, for language servers that report project-wide diagnostics"
+    )]
+    async fn workspace_diagnostics(
+        &self,
+        #[tool(aggr)] request: WorkspaceDiagnosticsRequest,
+    ) -> String {
+        self.record_tool("workspace_diagnostics");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let format = match Self::output_format(request.output_format.as_deref()) {
+            Ok(format) => format,
+            Err(e) => return e,
+        };
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        match render::with_style(
+            style,
+            render::with_format(
+                format,
+                Self::on_each(self.router.clients(), |client| async move {
+                    tools::get_workspace_diagnostics(&client).await
+                }),
+            ),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error getting workspace diagnostics: {}", e),
+        }
+    }
+
+    #[tool(description = "Get hover information at a specific position")]
+    async fn hover(&self, #[tool(aggr)] request: HoverRequest) -> String {
+        self.record_tool("hover");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        let mut max_length = request.max_length.unwrap_or(2000) as usize;
+        if let Some(budget) = tools::budget::char_budget() {
+            max_length = max_length.min(budget);
+        }
+
+        match render::with_style(
+            style,
+            tools::get_hover_info(
+                &client,
+                path,
+                request.line,
+                request.column,
+                Some(max_length),
+            ),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error getting hover info: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "List code completions at a position with their kind, detail and documentation"
+    )]
+    async fn completion(&self, #[tool(aggr)] request: CompletionRequest) -> String {
+        self.record_tool("completion");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        let max_items = request.max_items.unwrap_or(20) as usize;
+        let resolve = request.resolve.unwrap_or(true);
+
+        match render::with_style(
+            style,
+            tools::get_completions(
+                &client,
+                path,
+                request.line,
+                request.column,
+                max_items,
+                request.prefix.as_deref(),
+                resolve,
+            ),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error getting completions: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Get stable, package-qualified identifiers (monikers) for the symbol at a position, for cross-repository linking"
+    )]
+    async fn moniker(&self, #[tool(aggr)] request: MonikerRequest) -> String {
+        self.record_tool("moniker");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        match render::with_style(
+            style,
+            tools::get_monikers(&client, path, request.line, request.column),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error getting monikers: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "List the occurrences of the symbol at a position within its file, marked as reads or writes; cheaper than references for local reasoning and shows where a variable is mutated"
+    )]
+    async fn document_highlights(
+        &self,
+        #[tool(aggr)] request: DocumentHighlightsRequest,
+    ) -> String {
+        self.record_tool("document_highlights");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        match render::with_style(
+            style,
+            tools::get_document_highlights(&client, path, request.line, request.column),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error getting document highlights: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "List the foldable regions of a file (blocks, comments, imports, regions) as a nested outline of line ranges, for a structural view of large files"
+    )]
+    async fn folding_ranges(&self, #[tool(aggr)] request: FoldingRangesRequest) -> String {
+        self.record_tool("folding_ranges");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        match render::with_style(
+            style,
+            tools::get_folding_ranges(&client, path, request.min_lines.unwrap_or(1)),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error getting folding ranges: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "List the ranges enclosing a position (expression, statement, block, function, ...) from the innermost out, with snippets; useful to find what to extract or replace"
+    )]
+    async fn selection_ranges(&self, #[tool(aggr)] request: SelectionRangesRequest) -> String {
+        self.record_tool("selection_ranges");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        match render::with_style(
+            style,
+            tools::get_selection_ranges(&client, path, request.line, request.column),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error getting selection ranges: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Classify the identifiers of a file or line range by the language server's semantic tokens (function, parameter, mutable, async, ...), to tell identifiers apart when hover is unavailable"
+    )]
+    async fn semantic_tokens(&self, #[tool(aggr)] request: SemanticTokensRequest) -> String {
+        self.record_tool("semantic_tokens");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        match render::with_style(
+            style,
+            tools::get_semantic_tokens(&client, path, request.start_line, request.end_line),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error getting semantic tokens: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "List the variables and expressions a debugger would show per line, useful for choosing where to add logging"
+    )]
+    async fn inline_values(&self, #[tool(aggr)] request: InlineValuesRequest) -> String {
+        self.record_tool("inline_values");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        match render::with_style(
+            style,
+            tools::get_inline_values(&client, path, request.start_line, request.end_line),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error getting inline values: {}", e),
+        }
+    }
+
+    #[tool(description = "List the color values in a file (such as CSS or SCSS) with their ranges")]
+    async fn document_colors(&self, #[tool(aggr)] request: DocumentColorsRequest) -> String {
+        self.record_tool("document_colors");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        match render::with_style(style, tools::get_document_colors(&client, path)).await {
+            Ok(result) => result,
+            Err(e) => format!("Error getting document colors: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "List the ways a color at a position can be written (hex, rgb(), hsl(), ...), optionally for a replacement color"
+    )]
+    async fn color_presentations(
+        &self,
+        #[tool(aggr)] request: ColorPresentationsRequest,
+    ) -> String {
+        self.record_tool("color_presentations");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        match tools::get_color_presentations(
+            &client,
+            path,
+            request.line,
+            request.column,
+            request.color.as_deref(),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error getting color presentations: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "List the documents open in the language server and whether each differs from disk"
+    )]
+    async fn open_documents(&self) -> String {
+        self.record_tool("open_documents");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        match tools::list_open_documents(&self.lsp_client).await {
+            Ok(result) => result,
+            Err(e) => format!("Error listing open documents: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "List the code lenses of a file, such as run test or references counts, numbered for run_code_lens"
+    )]
+    async fn code_lenses(&self, #[tool(aggr)] request: CodeLensesRequest) -> String {
+        self.record_tool("code_lenses");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        match render::with_style(style, tools::get_code_lenses(&client, path)).await {
+            Ok(result) => result,
+            Err(e) => format!("Error getting code lenses: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Run the command of a code lens listed by code_lenses, given the file and the lens's number"
+    )]
+    async fn run_code_lens(&self, #[tool(aggr)] request: RunCodeLensRequest) -> String {
+        self.record_tool("run_code_lens");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        match tools::run_code_lens(&client, path, request.index).await {
+            Ok(result) => result,
+            Err(e) => format!("Error running code lens: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Run a command the language server offers (e.g. gopls.tidy), applying the edits it makes"
+    )]
+    async fn execute_command(&self, #[tool(aggr)] request: ExecuteCommandRequest) -> String {
+        self.record_tool("execute_command");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        let client = match &request.file_path {
+            Some(path) => match self.client_for(Path::new(path)).await {
+                Ok(client) => client,
+                Err(e) => return e,
+            },
+            None => Arc::clone(&self.lsp_client),
+        };
+        match tools::execute_command(
+            &client,
+            &request.command,
+            request.arguments.unwrap_or_default(),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error executing command: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Send any request to the language server and get its raw JSON result, for server-specific extensions no other tool covers"
+    )]
+    async fn lsp_request(&self, #[tool(aggr)] request: LspRequestRequest) -> String {
+        self.record_tool("lsp_request");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        let file_path = request.file_path.as_ref().map(PathBuf::from);
+        let client = match &file_path {
+            Some(path) => match self.client_for(path).await {
+                Ok(client) => client,
+                Err(e) => return e,
+            },
+            None => Arc::clone(&self.lsp_client),
+        };
+        match tools::send_lsp_request(
+            &client,
+            &request.method,
+            request.params,
+            file_path.as_deref(),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error sending {}: {}", request.method, e),
+        }
+    }
+
+    #[tool(
+        description = "Report the health of each language server: whether its process is alive, uptime, open documents, cached diagnostics, pending requests, indexing progress and the last error"
+    )]
+    async fn status(&self, #[tool(aggr)] request: StatusRequest) -> String {
+        self.record_tool("status");
+        // No waiting for the server here: a hung or failed one is what this reports on
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        match render::with_style(style, tools::get_status(&self.router.clients())).await {
+            Ok(result) => result,
+            Err(e) => format!("Error reporting status: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Summarize what each language server supports (requests, document sync, position encoding, code action kinds), to tell why a tool returns nothing"
+    )]
+    async fn server_capabilities(
+        &self,
+        #[tool(aggr)] request: ServerCapabilitiesRequest,
+    ) -> String {
+        self.record_tool("server_capabilities");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        match render::with_style(
+            style,
+            tools::get_server_capabilities(&self.router.clients()),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error summarizing server capabilities: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Restart the language server and reopen its documents, to recover when it gets into a bad state"
+    )]
+    async fn restart_language_server(&self, #[tool(aggr)] request: RestartRequest) -> String {
+        self.record_tool("restart_language_server");
+        // No waiting for the server here: it may be the hung one being replaced
+        if let Some(idle_monitor) = &self.idle_monitor {
+            idle_monitor.touch();
+        }
+
+        let clients = match &request.file_path {
+            Some(path) => match self.client_for(Path::new(path)).await {
+                Ok(client) => vec![client],
+                Err(e) => return e,
+            },
+            None => self.router.clients(),
+        };
+
+        // Hold back file changes until the new servers have reopened their documents
+        let watcher = self.watcher();
+        if let Some(watcher) = watcher {
+            watcher.pause();
+        }
+        let mut results = Vec::new();
+        for client in clients {
+            match tools::restart_language_server(&client).await {
+                Ok(result) => results.push(result),
+                Err(e) => {
+                    results = vec![format!("Error restarting language server: {}", e)];
+                    break;
+                }
+            }
+        }
+        if let Some(watcher) = watcher {
+            watcher.resume();
+        }
+        results.join("\n")
+    }
+
+    #[tool(
+        description = "Add a folder to the workspace of the language servers, e.g. a sibling project they should index too"
+    )]
+    async fn add_workspace_folder(&self, #[tool(aggr)] request: WorkspaceFolderRequest) -> String {
+        self.record_tool("add_workspace_folder");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        let clients = self.router.clients();
+        let folder = Path::new(&request.folder);
+        let result = match tools::add_workspace_folder(&clients, folder).await {
+            Ok(result) => result,
+            Err(e) => return format!("Error adding workspace folder: {}", e),
+        };
+        if let Some(watcher) = self.watcher()
+            && let Ok(folder) = tools::workspace_folders::resolve_folder(&clients, folder)
+            && let Err(e) = watcher.add_folder(&folder).await
+        {
+            return format!(
+                "{}\nWarning: changes in the folder are not watched: {}",
+                result, e
+            );
+        }
+        result
+    }
+
+    #[tool(description = "Remove a folder added with add_workspace_folder from the workspace")]
+    async fn remove_workspace_folder(
+        &self,
+        #[tool(aggr)] request: WorkspaceFolderRequest,
+    ) -> String {
+        self.record_tool("remove_workspace_folder");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        let clients = self.router.clients();
+        let folder = Path::new(&request.folder);
+        let result = match tools::remove_workspace_folder(&clients, folder).await {
+            Ok(result) => result,
+            Err(e) => return format!("Error removing workspace folder: {}", e),
+        };
+        // The folder may never have been watched, e.g. when it wasn't added
+        if let Some(watcher) = self.watcher()
+            && let Ok(folder) = tools::workspace_folders::resolve_folder(&clients, folder)
+        {
+            let _ = watcher.remove_folder(&folder).await;
+        }
+        result
+    }
+
+    #[tool(
+        description = "List the code actions (quick fixes, imports, refactorings) available for a position or range, numbered for apply_code_action"
+    )]
+    async fn code_actions(&self, #[tool(aggr)] request: CodeActionsRequest) -> String {
+        self.record_tool("code_actions");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let style = match Self::output_style(request.output_style.as_deref()) {
+            Ok(style) => style,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        let range = tools::code_actions::action_range(
+            request.line,
+            request.column,
+            request.end_line,
+            request.end_column,
+        );
+        match render::with_style(
+            style,
+            tools::get_code_actions(&client, path, range, request.only.as_deref()),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error getting code actions: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Apply a code action listed by code_actions, given the same position and the action's number"
+    )]
+    async fn apply_code_action(&self, #[tool(aggr)] request: ApplyCodeActionRequest) -> String {
+        self.record_tool("apply_code_action");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        let range = tools::code_actions::action_range(
+            request.line,
+            request.column,
+            request.end_line,
+            request.end_column,
+        );
+        match tools::apply_code_action(&client, path, range, request.only.as_deref(), request.index)
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error applying code action: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Apply the quick fix of each diagnostic in a file, a directory or the workspace, where the server offers a single or preferred fix, and report which diagnostics were fixed or skipped"
+    )]
+    async fn fix_diagnostics(&self, #[tool(aggr)] request: FixDiagnosticsRequest) -> String {
+        self.record_tool("fix_diagnostics");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        let path = request.file_path.map(|path| Path::new(&path).to_path_buf());
+        // Without a file, every server fixes what it reported across the workspace
+        let clients = match &path {
+            Some(path) => match self.client_for(path).await {
+                Ok(client) => vec![client],
+                Err(e) => return e,
+            },
+            None => self.router.clients(),
+        };
+        let scope = match request.scope.as_deref() {
+            Some(scope) => match tools::scope::ActionScope::parse(scope) {
+                Ok(scope) => scope,
+                Err(e) => return format!("Error fixing diagnostics: {}", e),
+            },
+            None => tools::scope::ActionScope::File,
+        };
+        let options = ApplyOptions {
+            dry_run: request.dry_run.unwrap_or(false),
+            show_diff: request.dry_run.unwrap_or(false),
+        };
+        let max_fixes = request.max_fixes.unwrap_or(20);
+        let (path, options) = (&path, &options);
+        match Self::on_each(clients, move |client| async move {
+            tools::fix_diagnostics(&client, path.clone(), scope, max_fixes, options).await
+        })
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error fixing diagnostics: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Sort imports and remove unused ones in a file, a directory or the workspace, using the language server's organize imports action"
+    )]
+    async fn organize_imports(&self, #[tool(aggr)] request: OrganizeImportsRequest) -> String {
+        self.record_tool("organize_imports");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        let scope = match request.scope.as_deref() {
+            Some(scope) => match tools::scope::ActionScope::parse(scope) {
+                Ok(scope) => scope,
+                Err(e) => return format!("Error organizing imports: {}", e),
+            },
+            None => tools::scope::ActionScope::File,
+        };
+        let options = ApplyOptions {
+            dry_run: request.dry_run.unwrap_or(false),
+            show_diff: request.show_diff.unwrap_or(false),
+        };
+        match tools::organize_imports_in(&client, path, scope, &options).await {
+            Ok(result) => result,
+            Err(e) => format!("Error organizing imports: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Find where a test for a function belongs: its test module or file, created if missing, and the exact position to insert the test at"
+    )]
+    async fn test_anchor(&self, #[tool(aggr)] request: TestAnchorRequest) -> String {
+        self.record_tool("test_anchor");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+
+        match tools::find_test_anchor(
+            &self.lsp_client,
+            &request.symbol_name,
+            request.create.unwrap_or(true),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error finding test anchor: {}", e),
+        }
+    }
+
+    #[tool(description = "Rename a symbol at a specific position")]
+    async fn rename_symbol(&self, #[tool(aggr)] request: RenameRequest) -> String {
+        self.record_tool("rename_symbol");
+        if let Err(e) = self.wait_for_lsp().await {
+            return e;
+        }
+        let format = match Self::output_format(request.output_format.as_deref()) {
+            Ok(format) => format,
+            Err(e) => return e,
+        };
+
+        let path = Path::new(&request.file_path).to_path_buf();
+        let client = match self.client_for(&path).await {
+            Ok(client) => client,
+            Err(e) => return e,
+        };
+        let options = ApplyOptions {
+            dry_run: request.dry_run.unwrap_or(false),
+            show_diff: request.show_diff.unwrap_or(false),
+        };
+        match render::with_format(
+            format,
+            tools::rename_symbol(
+                &client,
+                path,
+                request.line,
+                request.column,
+                request.new_name,
+                request.allow_conflicts.unwrap_or(false),
+                &options,
+            ),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => format!("Error renaming symbol: {}", e),
+        }
+    }
+}
+
+// Implement the ServerHandler trait for MCP
 impl ServerHandler for McpLanguageServer {
+    async fn list_tools(
+        &self,
+        _request: PaginatedRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, rmcp::Error> {
+        // Tools no language server can answer are left out
+        let unsupported = availability::unsupported_tools(&self.router);
+        let mut tools: Vec<_> = Self::tool_box()
+            .list()
+            .into_iter()
+            .filter(|tool| !unsupported.iter().any(|(name, _)| tool.name == *name))
+            .map(|mut tool| {
+                tool.annotations = Some(annotations::tool_annotations(&tool.name));
+                tool
+            })
+            .collect();
+        // Custom tools are only offered once a server has what they need
+        tools.extend(
+            custom_tools::available(&self.custom_tools, &self.router)
+                .into_iter()
+                .map(custom_tools::tool),
+        );
+        Ok(ListToolsResult {
+            next_cursor: None,
+            tools,
+        })
+    }
+
+    /// Runs each tool call under its own correlation id, which tags the log
+    /// lines and LSP requests it causes and any error it returns. Progress the
+    /// language server reports meanwhile is forwarded under the same id.
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        if let Some((_, method)) = availability::unsupported_tools(&self.router)
+            .into_iter()
+            .find(|(name, _)| *name == request.name)
+        {
+            log::warn!(
+                "[MCP] Refusing {}: no server supports {}",
+                request.name,
+                method
+            );
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Error: {} is not available because the language server does not support {}",
+                request.name, method
+            ))]));
+        }
+
+        let correlation_id = CorrelationId::next(&request.name);
+        let progress = ProgressForwarder::new(correlation_id.to_string()).forward(
+            self.router
+                .clients()
+                .iter()
+                .map(|client| client.subscribe_events())
+                .collect(),
+            context.peer.clone(),
+        );
+        let custom_tool = self
+            .custom_tools
+            .iter()
+            .find(|tool| tool.name == request.name);
+        let call = correlation::with_correlation_id(correlation_id.clone(), async {
+            log::info!("[MCP] Calling tool {}", request.name);
+            if let Some(custom_tool) = custom_tool {
+                let text = self
+                    .call_custom_tool(custom_tool, request.arguments.as_ref())
+                    .await;
+                return Ok(CallToolResult::success(vec![Content::text(text)]));
+            }
+            let context = ToolCallContext::new(self, request, context);
+            Self::tool_box().call(context).await
+        });
+
+        // Progress stops with the call; if forwarding stops first, the call still finishes
+        tokio::pin!(call);
+        let result = tokio::select! {
+            result = &mut call => result,
+            () = progress => call.await,
+        };
+
+        result.map(|mut result| {
+            tag_errors(&mut result, &correlation_id);
+            let pending: Vec<String> = self
+                .router
+                .clients()
+                .iter()
+                .flat_map(|client| client.pending_work())
+                .collect();
+            annotate_pending_work(&mut result, &pending);
+            result
+        })
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: PaginatedRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, rmcp::Error> {
+        Ok(ListPromptsResult {
+            next_cursor: None,
+            prompts: prompts::list_prompts(),
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, rmcp::Error> {
+        prompts::get_prompt(&request.name, request.arguments.as_ref())
+            .map_err(|e| rmcp::Error::invalid_params(e.to_string(), None))
+    }
+
+    /// Lists the files of the workspace folders that the watcher doesn't
+    /// ignore, a page at a time; the cursor is the offset of the next page
+    async fn list_resources(
+        &self,
+        request: PaginatedRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, rmcp::Error> {
+        let folders = self.lsp_client.workspace_folders();
+
+        // The diagnostics of each file that has any come before the files
+        let mut diagnostics: Vec<PathBuf> = self
+            .diagnostics_servers()
+            .iter()
+            .flat_map(|client| client.diagnostic_uris())
+            .filter_map(|uri| tools::utils::to_path(&uri).ok())
+            .collect();
+        diagnostics.sort();
+        diagnostics.dedup();
+        let mut listed: Vec<_> = diagnostics
+            .iter()
+            .map(|path| {
+                let count = self.file_diagnostics(path).len();
+                resources::diagnostics_resource(path, &folders, count)
+            })
+            .collect();
+        listed.extend(
+            resources::workspace_files(&folders, self.ignore_check())
+                .iter()
+                .map(|path| resources::file_resource(path, &folders)),
+        );
+
+        let start = match request.and_then(|request| request.cursor) {
+            Some(cursor) => cursor.parse::<usize>().map_err(|_| {
+                rmcp::Error::invalid_params(format!("Invalid cursor: {}", cursor), None)
+            })?,
+            None => 0,
+        };
+        let end = (start + resources::PAGE_SIZE).min(listed.len());
+        Ok(ListResourcesResult {
+            resources: listed.get(start..end).unwrap_or_default().to_vec(),
+            next_cursor: (end < listed.len()).then(|| end.to_string()),
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, rmcp::Error> {
+        if let Some(path) = resources::diagnostics_path(&request.uri) {
+            let text = resources::format_diagnostics(&self.file_diagnostics(&path));
+            return Ok(ReadResourceResult {
+                contents: vec![ResourceContents::text(text, request.uri)],
+            });
+        }
+
+        let folders = self.lsp_client.workspace_folders();
+        match resources::read_file_resource(&request.uri, &folders, self.ignore_check()).await {
+            Ok((_, text)) => Ok(ReadResourceResult {
+                contents: vec![ResourceContents::text(text, request.uri)],
+            }),
+            Err(e) => Err(rmcp::Error::resource_not_found(e.to_string(), None)),
+        }
+    }
+
+    /// Subscribes to a resource. Diagnostics resources are updated whenever
+    /// a server publishes diagnostics for their file, files whenever the
+    /// watcher sees them change.
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), rmcp::Error> {
+        if resources::diagnostics_path(&request.uri).is_none()
+            && !request.uri.starts_with("file://")
+        {
+            return Err(rmcp::Error::invalid_params(
+                format!(
+                    "Only file and diagnostics resources can be subscribed to, not {}",
+                    request.uri
+                ),
+                None,
+            ));
+        }
+        self.subscriptions.subscribe(request.uri);
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), rmcp::Error> {
+        self.subscriptions.unsubscribe(&request.uri);
+        Ok(())
+    }
+
+    fn get_peer(&self) -> Option<Peer<RoleServer>> {
+        self.peer.clone()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleServer>) {
+        self.peer = Some(peer);
+    }
+
+    async fn on_initialized(&self) {
+        self.update_roots();
+        self.announce_ready();
+        self.announce_unsupported_tools();
+        self.forward_resource_notifications();
+    }
+
+    async fn on_roots_list_changed(&self) {
+        self.update_roots();
+    }
+
     fn get_info(&self) -> ServerInfo {
+        let mut instructions = "A Model Context Protocol server that proxies requests to Language Server Protocol servers, providing LLM-friendly access to language server features like code navigation, diagnostics, and refactoring.".to_string();
+
+        match self.lsp_client.init_state() {
+            lsp::InitState::Starting => instructions.push_str(
+                " The language server is still starting up; tool calls will wait until it is ready.",
+            ),
+            lsp::InitState::Failed(e) => instructions
+                .push_str(&format!(" The language server failed to initialize: {}", e)),
+            lsp::InitState::Stopped => instructions.push_str(
+                " The language server was stopped while idle and restarts on the next tool call.",
+            ),
+            lsp::InitState::Ready => {
+                let pending = self.lsp_client.pending_work();
+                if !pending.is_empty() {
+                    instructions.push_str(&format!(
+                        " The language server is still busy ({}); tool calls will wait for it to finish.",
+                        pending.join(", ")
+                    ));
+                }
+            }
+        }
+
+        if let Some(warning) = &self.workspace_warning {
+            instructions.push_str(&format!(" Warning: {}", warning));
+        }
+
         ServerInfo {
-            instructions: Some("A Model Context Protocol server that proxies requests to Language Server Protocol servers, providing LLM-friendly access to language server features like code navigation, diagnostics, and refactoring.".to_string()),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_prompts()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_resources_list_changed()
+                .build(),
+            instructions: Some(instructions),
             ..Default::default()
         }
     }
@@ -0,0 +1,94 @@
+use futures::stream::{self, StreamExt};
+use rmcp::model::ProgressNotificationParam;
+use rmcp::service::{Peer, RoleServer};
+use serde_json::json;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::lsp::ProxyEvent;
+
+/// Turns language server work done progress into MCP progress notifications
+/// for one tool call.
+///
+/// MCP progress must increase with every notification, so reports without a
+/// percentage count up by one and percentages never move the value back.
+#[derive(Debug)]
+pub struct ProgressForwarder {
+    token: String,
+    progress: u32,
+}
+
+impl ProgressForwarder {
+    /// Creates a forwarder whose notifications carry `token`
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            progress: 0,
+        }
+    }
+
+    /// Builds the notification for a proxy event, or `None` for events that
+    /// aren't progress
+    pub fn notification(&mut self, event: &ProxyEvent) -> Option<ProgressNotificationParam> {
+        let ProxyEvent::Progress {
+            title,
+            message,
+            percentage,
+            done,
+            ..
+        } = event
+        else {
+            return None;
+        };
+
+        self.progress = match percentage {
+            Some(percentage) => (*percentage).max(self.progress),
+            None => self.progress + 1,
+        };
+        let message = match (title.is_empty(), message) {
+            (false, Some(message)) => format!("{}: {}", title, message),
+            (false, None) if *done => format!("{}: done", title),
+            (false, None) => title.clone(),
+            (true, Some(message)) => message.clone(),
+            (true, None) => String::new(),
+        };
+
+        // Built from JSON so the message is kept where the protocol version has a field for it
+        serde_json::from_value(json!({
+            "progressToken": self.token,
+            "progress": self.progress,
+            "total": percentage.map(|_| 100),
+            "message": message,
+        }))
+        .ok()
+    }
+
+    /// Sends progress events from each server to the MCP client until every
+    /// event channel closes. Run it alongside a tool call and drop it when the
+    /// call returns.
+    pub async fn forward(
+        mut self,
+        events: Vec<broadcast::Receiver<ProxyEvent>>,
+        peer: Peer<RoleServer>,
+    ) {
+        let streams = events.into_iter().map(|events| {
+            Box::pin(stream::unfold(events, |mut events| async move {
+                loop {
+                    match events.recv().await {
+                        Ok(event) => return Some((event, events)),
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }))
+        });
+        let mut events = stream::select_all(streams);
+
+        while let Some(event) = events.next().await {
+            if let Some(notification) = self.notification(&event)
+                && let Err(e) = peer.notify_progress(notification).await
+            {
+                log::debug!("[MCP] Failed to send progress notification: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::{Result, anyhow};
+use rmcp::model::{
+    GetPromptResult, JsonObject, Prompt, PromptArgument, PromptMessage, PromptMessageRole,
+};
+
+/// An argument of a prompt: its name, description and whether it's required
+type ArgumentSpec = (&'static str, &'static str, bool);
+
+/// A prompt composing a sequence of tool calls for a common workflow
+struct PromptSpec {
+    name: &'static str,
+    description: &'static str,
+    arguments: &'static [ArgumentSpec],
+    /// Instructions with `{argument}` placeholders
+    template: &'static str,
+}
+
+const PROMPTS: &[PromptSpec] = &[
+    PromptSpec {
+        name: "investigate_diagnostic",
+        description: "Work out the cause of the diagnostics in a file and propose a fix",
+        arguments: &[
+            ("file_path", "Path to the file with the diagnostics", true),
+            (
+                "message",
+                "Part of the diagnostic's message, to focus on a single diagnostic",
+                false,
+            ),
+        ],
+        template: "Investigate the diagnostics in {file_path}{message_filter}.
+
+1. Call `diagnostics` with file_path \"{file_path}\", context_lines 3 and include_enclosing true to see each diagnostic in its function.
+2. For each symbol a diagnostic mentions, call `hover` on it for its type, and `definition` to read how it is declared.
+3. Call `code_actions` on the diagnostic's range to see the fixes the language server offers.
+4. Explain the cause, then propose the smallest fix. Prefer a code action the server offers; otherwise edit with `edit_and_check` so the file is checked again right away.",
+    },
+    PromptSpec {
+        name: "safe_rename_plan",
+        description: "Plan a rename by reviewing every use of the symbol before applying it",
+        arguments: &[
+            ("file_path", "Path to the file declaring the symbol", true),
+            ("line", "Line of the symbol", true),
+            ("column", "Column of the symbol", true),
+            ("new_name", "New name for the symbol", true),
+        ],
+        template: "Plan renaming the symbol at {file_path}:{line}:{column} to `{new_name}`.
+
+1. Call `hover` at the position to confirm which symbol it is.
+2. Call `references` with symbol_name \"{file_path}:{line}:{column}\" to list every use, and note uses in strings, comments, docs or configuration that a rename won't reach.
+3. Call `rename_symbol` with new_name \"{new_name}\" and dry_run true, and review the diff. Stop and report if the new name collides with an existing symbol.
+4. Once the plan is confirmed, call `rename_symbol` again without dry_run, then `workspace_diagnostics` to check nothing broke.",
+    },
+    PromptSpec {
+        name: "explore_symbol",
+        description: "Summarize what a symbol is, where it's defined and how it's used",
+        arguments: &[(
+            "symbol_name",
+            "The symbol to explore, such as Config or Config::load",
+            true,
+        )],
+        template: "Explore the symbol `{symbol_name}`.
+
+1. Call `definition` with symbol_name \"{symbol_name}\" to read its source.
+2. Call `hover` on the definition for its type and documentation.
+3. Call `references` with symbol_name \"{symbol_name}\" and output_style compact to see where it's used.
+4. If it's a function, call `incoming_calls` and `outgoing_calls`; if it's a type or trait, call `type_hierarchy` or `find_impls_of_trait`.
+5. Summarize its purpose, its important callers and dependencies, and anything surprising.",
+    },
+];
+
+/// Lists the prompts this server offers
+pub fn list_prompts() -> Vec<Prompt> {
+    PROMPTS
+        .iter()
+        .map(|spec| {
+            let arguments = spec
+                .arguments
+                .iter()
+                .map(|(name, description, required)| PromptArgument {
+                    name: name.to_string(),
+                    description: Some(description.to_string()),
+                    required: Some(*required),
+                })
+                .collect();
+            Prompt::new(spec.name, Some(spec.description), Some(arguments))
+        })
+        .collect()
+}
+
+/// Fills in a prompt's instructions with its arguments. Missing required
+/// arguments and unknown prompts are errors.
+pub fn get_prompt(name: &str, arguments: Option<&JsonObject>) -> Result<GetPromptResult> {
+    let spec = PROMPTS
+        .iter()
+        .find(|spec| spec.name == name)
+        .ok_or_else(|| anyhow!("Unknown prompt: {}", name))?;
+
+    let argument = |name: &str| -> Option<String> {
+        arguments
+            .and_then(|arguments| arguments.get(name))
+            .map(|value| match value {
+                serde_json::Value::String(text) => text.clone(),
+                value => value.to_string(),
+            })
+            .filter(|value| !value.is_empty())
+    };
+
+    let mut text = spec.template.to_string();
+    for (arg, _, required) in spec.arguments {
+        let value = match argument(arg) {
+            Some(value) => value,
+            None if *required => return Err(anyhow!("Missing required argument: {}", arg)),
+            None => String::new(),
+        };
+        text = text.replace(&format!("{{{}}}", arg), &value);
+    }
+    let message_filter = argument("message")
+        .map(|message| {
+            format!(
+                ", focusing on the one whose message mentions \"{}\"",
+                message
+            )
+        })
+        .unwrap_or_default();
+    text = text.replace("{message_filter}", &message_filter);
+
+    Ok(GetPromptResult {
+        description: Some(spec.description.to_string()),
+        messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+    })
+}
@@ -0,0 +1,69 @@
+use lsp_types::InitializeResult;
+use rmcp::model::{LoggingLevel, LoggingMessageNotificationParam};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Marks the ready line on stderr so scripts can find it among the logs
+pub const READY_PREFIX: &str = "MCP_LANGUAGE_SERVER_READY";
+
+/// Announcement that the LSP handshake has finished and tool calls will be served.
+///
+/// Written to stderr as a single line starting with [`READY_PREFIX`] followed by
+/// JSON, and sent to the MCP client as a logging notification.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReadySignal {
+    pub transport: String,
+    pub workspace: PathBuf,
+    pub server_name: String,
+    pub server_version: Option<String>,
+    pub tools: Vec<String>,
+}
+
+impl ReadySignal {
+    /// Describes a proxy whose language server answered the initialize request with
+    /// `result`. Servers that don't report their name are named after their command.
+    pub fn new(
+        transport: &str,
+        workspace: &Path,
+        command_name: &str,
+        result: &InitializeResult,
+        tools: Vec<String>,
+    ) -> Self {
+        let (server_name, server_version) = match &result.server_info {
+            Some(info) => (info.name.clone(), info.version.clone()),
+            None => (command_name.to_string(), None),
+        };
+
+        Self {
+            transport: transport.to_string(),
+            workspace: workspace.to_path_buf(),
+            server_name,
+            server_version,
+            tools,
+        }
+    }
+
+    /// Gets the signal as a JSON object with an `"event": "ready"` field
+    pub fn to_json(&self) -> Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Value::Object(fields) = &mut value {
+            fields.insert("event".to_string(), Value::from("ready"));
+        }
+        value
+    }
+
+    /// Formats the line written to stderr
+    pub fn stderr_line(&self) -> String {
+        format!("{} {}", READY_PREFIX, self.to_json())
+    }
+
+    /// Builds the MCP logging notification carrying the signal
+    pub fn logging_message(&self) -> LoggingMessageNotificationParam {
+        LoggingMessageNotificationParam {
+            level: LoggingLevel::Notice,
+            logger: Some("mcp-language-server".to_string()),
+            data: self.to_json(),
+        }
+    }
+}
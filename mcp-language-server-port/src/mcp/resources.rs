@@ -0,0 +1,168 @@
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use lsp_types::{Diagnostic, DiagnosticSeverity};
+use rmcp::model::{AnnotateAble, RawResource, Resource};
+use std::path::{Path, PathBuf};
+
+use crate::tools::diagnostics::format_workspace_diagnostic;
+use crate::tools::utils::{to_path, to_uri};
+use crate::workspace::paths::normalize_path;
+
+/// Resources listed per page of `resources/list`
+pub const PAGE_SIZE: usize = 500;
+
+/// Largest file served by `resources/read`
+pub const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Lists the files in the workspace folders, sorted, leaving out what
+/// `is_ignored` rejects. Symbolic links aren't followed, so a link can't
+/// lead out of the workspace or into a cycle.
+pub fn workspace_files(folders: &[PathBuf], is_ignored: impl Fn(&Path) -> bool) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending: Vec<PathBuf> = folders.to_vec();
+    while let Some(dir) = pending.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                debug!("[MCP] Failed to list {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_symlink() || is_ignored(&path) {
+                continue;
+            }
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Prefix of the resources holding the diagnostics of a file, followed by
+/// the file's absolute path
+pub const DIAGNOSTICS_SCHEME: &str = "diagnostics://";
+
+/// Gets the URI of the diagnostics resource of a file
+pub fn diagnostics_uri(path: &Path) -> String {
+    format!("{}{}", DIAGNOSTICS_SCHEME, path.display())
+}
+
+/// Gets the file a diagnostics resource URI is about, or `None` for other URIs
+pub fn diagnostics_path(uri: &str) -> Option<PathBuf> {
+    uri.strip_prefix(DIAGNOSTICS_SCHEME).map(PathBuf::from)
+}
+
+/// Gets the path of a file relative to the folder containing it
+fn relative_name(path: &Path, folders: &[PathBuf]) -> String {
+    folders
+        .iter()
+        .filter_map(|folder| path.strip_prefix(folder).ok())
+        .min_by_key(|relative| relative.components().count())
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// Describes the diagnostics of a file as a resource, with their count
+pub fn diagnostics_resource(path: &Path, folders: &[PathBuf], count: usize) -> Resource {
+    let mut resource = RawResource::new(
+        diagnostics_uri(path),
+        format!("Diagnostics: {}", relative_name(path, folders)),
+    );
+    resource.description = Some(format!("{} diagnostics", count));
+    resource.mime_type = Some("text/plain".to_string());
+    resource.no_annotation()
+}
+
+/// Formats the diagnostics of a file as a count by severity followed by one
+/// line per diagnostic
+pub fn format_diagnostics(diagnostics: &[Diagnostic]) -> String {
+    let count = |severity: DiagnosticSeverity| {
+        diagnostics
+            .iter()
+            .filter(|d| d.severity == Some(severity))
+            .count()
+    };
+    let (errors, warnings) = (
+        count(DiagnosticSeverity::ERROR),
+        count(DiagnosticSeverity::WARNING),
+    );
+
+    let mut result = format!(
+        "{} errors, {} warnings, {} other\n",
+        errors,
+        warnings,
+        diagnostics.len() - errors - warnings
+    );
+    for diagnostic in diagnostics {
+        result.push_str(&format_workspace_diagnostic(diagnostic));
+        result.push('\n');
+    }
+    result
+}
+
+/// Describes a workspace file as a resource, named by its path relative to
+/// the folder containing it
+pub fn file_resource(path: &Path, folders: &[PathBuf]) -> Resource {
+    let name = relative_name(path, folders);
+
+    let mut resource = RawResource::new(to_uri(path).to_string(), name);
+    resource.mime_type = Some("text/plain".to_string());
+    resource.size = std::fs::metadata(path)
+        .ok()
+        .and_then(|metadata| u32::try_from(metadata.len()).ok());
+    resource.no_annotation()
+}
+
+/// Reads the workspace file a `file://` resource URI names. Files outside
+/// the workspace folders, ignored files and files that aren't text are refused.
+pub async fn read_file_resource(
+    uri: &str,
+    folders: &[PathBuf],
+    is_ignored: impl Fn(&Path) -> bool,
+) -> Result<(PathBuf, String)> {
+    let url = lsp_types::Url::parse(uri).context(format!("Invalid resource URI: {}", uri))?;
+    if url.scheme() != "file" {
+        return Err(anyhow!("Only file:// resources are served, not {}", uri));
+    }
+    let path = normalize_path(&to_path(&url)?)?;
+
+    if !folders.iter().any(|folder| path.starts_with(folder)) {
+        return Err(anyhow!("{} is outside the workspace", path.display()));
+    }
+    if is_ignored(&path) {
+        return Err(anyhow!("{} is ignored", path.display()));
+    }
+
+    let metadata = tokio::fs::metadata(&path)
+        .await
+        .context(format!("File does not exist: {}", path.display()))?;
+    if !metadata.is_file() {
+        return Err(anyhow!("{} is not a file", path.display()));
+    }
+    if metadata.len() > MAX_FILE_SIZE {
+        return Err(anyhow!(
+            "{} is {} bytes, more than the {} served as a resource",
+            path.display(),
+            metadata.len(),
+            MAX_FILE_SIZE
+        ));
+    }
+
+    let bytes = tokio::fs::read(&path)
+        .await
+        .context(format!("Failed to read file: {}", path.display()))?;
+    let text =
+        String::from_utf8(bytes).map_err(|_| anyhow!("{} is not a text file", path.display()))?;
+    Ok((path, text))
+}
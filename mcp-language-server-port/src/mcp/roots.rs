@@ -0,0 +1,141 @@
+use anyhow::Result;
+use log::{error, info, warn};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::watch;
+
+use crate::lsp::Router;
+use crate::tools::utils::to_path;
+use crate::watcher::FileSystemWatcher;
+use crate::workspace::paths::normalize_path;
+
+/// The workspace and the roots MCP clients announce. Without a workspace on
+/// the command line, the first root a client lists becomes the workspace.
+/// Other roots are added as workspace folders, and removed again once no
+/// client lists them.
+pub struct WorkspaceRoots {
+    workspace: watch::Sender<Option<PathBuf>>,
+    watcher: OnceLock<FileSystemWatcher>,
+    // Folders added for the roots of each client, by client
+    folders: Mutex<HashMap<u64, Vec<PathBuf>>>,
+}
+
+impl WorkspaceRoots {
+    /// Starts with the workspace given on the command line, if any
+    pub fn new(workspace: Option<PathBuf>) -> Arc<Self> {
+        Arc::new(Self {
+            workspace: watch::Sender::new(workspace),
+            watcher: OnceLock::new(),
+            folders: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Gets the workspace, if it is known yet
+    pub fn workspace(&self) -> Option<PathBuf> {
+        self.workspace.borrow().clone()
+    }
+
+    /// Waits until the workspace is known
+    pub async fn wait_for_workspace(&self) -> Result<PathBuf> {
+        let mut workspace = self.workspace.subscribe();
+        let workspace = workspace.wait_for(Option::is_some).await?;
+        Ok(workspace.clone().unwrap_or_default())
+    }
+
+    /// Sets the watcher that follows the folders added for roots
+    pub fn set_watcher(&self, watcher: FileSystemWatcher) {
+        let _ = self.watcher.set(watcher);
+    }
+
+    /// Gets the watcher set with [`WorkspaceRoots::set_watcher`]
+    pub fn watcher(&self) -> Option<&FileSystemWatcher> {
+        self.watcher.get()
+    }
+
+    /// Applies the roots a client listed, as file URIs, to the workspace of
+    /// every running server
+    pub async fn update(&self, client: u64, uris: &[String], router: &Router) {
+        let mut roots = Vec::new();
+        for uri in uris {
+            let path = lsp_types::Url::parse(uri)
+                .map_err(anyhow::Error::from)
+                .and_then(|uri| to_path(&uri))
+                .and_then(|path| normalize_path(&path));
+            match path {
+                Ok(path) if path.is_dir() => roots.push(path),
+                Ok(path) => warn!("[MCP] Ignoring root {}: not a directory", path.display()),
+                Err(e) => warn!("[MCP] Ignoring root {}: {}", uri, e),
+            }
+        }
+
+        let workspace = self.workspace().or_else(|| roots.first().cloned());
+        let wanted: Vec<PathBuf> = roots
+            .into_iter()
+            .filter(|root| Some(root) != workspace.as_ref())
+            .collect();
+        let (previous, others) = {
+            let mut folders = self.folders.lock().unwrap();
+            let previous = folders.insert(client, wanted.clone()).unwrap_or_default();
+            let others: HashSet<PathBuf> = folders
+                .iter()
+                .filter(|(id, _)| **id != client)
+                .flat_map(|(_, folders)| folders.iter().cloned())
+                .collect();
+            (previous, others)
+        };
+
+        // Folders go in first, so servers initialized next start with them
+        let clients = router.clients();
+        for folder in wanted.iter().filter(|f| !previous.contains(f)) {
+            info!("[MCP] Adding root {} to the workspace", folder.display());
+            for server in &clients {
+                if let Err(e) = server.add_workspace_folder(folder).await {
+                    error!(
+                        "[MCP] Failed to add {} to {}: {}",
+                        folder.display(),
+                        server.name(),
+                        e
+                    );
+                }
+            }
+            if let Some(watcher) = self.watcher()
+                && let Err(e) = watcher.add_folder(folder).await
+            {
+                error!("[MCP] Failed to watch {}: {}", folder.display(), e);
+            }
+        }
+        for folder in previous
+            .iter()
+            .filter(|f| !wanted.contains(f) && !others.contains(*f))
+        {
+            info!(
+                "[MCP] Removing root {} from the workspace",
+                folder.display()
+            );
+            for server in &clients {
+                if let Err(e) = server.remove_workspace_folder(folder).await {
+                    error!(
+                        "[MCP] Failed to remove {} from {}: {}",
+                        folder.display(),
+                        server.name(),
+                        e
+                    );
+                }
+            }
+            if let Some(watcher) = self.watcher() {
+                let _ = watcher.remove_folder(folder).await;
+            }
+        }
+
+        if self.workspace().is_none()
+            && let Some(workspace) = workspace
+        {
+            info!(
+                "[MCP] Using the client's root {} as the workspace",
+                workspace.display()
+            );
+            self.workspace.send_replace(Some(workspace));
+        }
+    }
+}
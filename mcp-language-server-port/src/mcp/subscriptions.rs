@@ -0,0 +1,112 @@
+use lsp_types::FileChangeType;
+use rmcp::model::ResourceUpdatedNotificationParam;
+use rmcp::service::{Peer, RoleServer};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::lsp::ProxyEvent;
+use crate::tools::utils::to_path;
+
+use super::resources::diagnostics_uri;
+
+/// A notification about the resources sent to the MCP client
+#[derive(Debug, Clone)]
+pub enum ResourceNotification {
+    /// A subscribed resource changed
+    Updated(ResourceUpdatedNotificationParam),
+    /// Files appeared in or disappeared from the workspace
+    ListChanged,
+}
+
+/// The resources one MCP client subscribed to. Diagnostics resources are
+/// updated from the diagnostics the language servers publish, file resources
+/// from the changes the file watcher reports.
+#[derive(Debug, Default)]
+pub struct ResourceSubscriptions {
+    uris: Mutex<HashSet<String>>,
+}
+
+impl ResourceSubscriptions {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn subscribe(&self, uri: String) {
+        self.uris.lock().unwrap().insert(uri);
+    }
+
+    pub fn unsubscribe(&self, uri: &str) {
+        self.uris.lock().unwrap().remove(uri);
+    }
+
+    pub fn is_subscribed(&self, uri: &str) -> bool {
+        self.uris.lock().unwrap().contains(uri)
+    }
+
+    /// Builds the notifications a proxy event calls for: updates of the
+    /// subscribed resources it changes, and a list change if it created or
+    /// deleted files
+    pub fn notifications(&self, event: &ProxyEvent) -> Vec<ResourceNotification> {
+        let updated = |uri: String| {
+            self.is_subscribed(&uri)
+                .then_some(ResourceNotification::Updated(
+                    ResourceUpdatedNotificationParam { uri },
+                ))
+        };
+
+        match event {
+            ProxyEvent::DiagnosticsUpdated { uri, .. } => to_path(uri)
+                .ok()
+                .and_then(|path| updated(diagnostics_uri(&path)))
+                .into_iter()
+                .collect(),
+            ProxyEvent::FilesChanged { changes } => {
+                let mut notifications: Vec<ResourceNotification> = changes
+                    .iter()
+                    .filter(|change| change.typ == FileChangeType::CHANGED)
+                    .filter_map(|change| updated(change.uri.to_string()))
+                    .collect();
+                if changes
+                    .iter()
+                    .any(|change| change.typ != FileChangeType::CHANGED)
+                {
+                    notifications.push(ResourceNotification::ListChanged);
+                }
+                notifications
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Sends resource notifications to the MCP client until the event
+    /// channel closes or the client goes away
+    pub async fn forward(
+        self: Arc<Self>,
+        mut events: broadcast::Receiver<ProxyEvent>,
+        peer: Peer<RoleServer>,
+    ) {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            };
+            for notification in self.notifications(&event) {
+                let sent = match notification {
+                    ResourceNotification::Updated(params) => {
+                        peer.notify_resource_updated(params).await
+                    }
+                    ResourceNotification::ListChanged => peer.notify_resource_list_changed().await,
+                };
+                if let Err(e) = sent {
+                    log::debug!(
+                        "[MCP] Failed to send resource notification, stopping: {}",
+                        e
+                    );
+                    return;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use lsp_types::ServerCapabilities;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::lsp::Client;
+use crate::tools::utils::to_path;
+
+/// Name of the state file inside the workspace's state directory
+const STATE_FILE: &str = "session.json";
+
+/// How long tool calls are collected before the state is written, so a burst
+/// of calls costs a single write
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// How many of the most used tools are named when a session is restored
+const TOP_TOOLS: usize = 5;
+
+/// State that survives restarts of the proxy for a single workspace
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionState {
+    /// Files that were open in the language server
+    #[serde(default)]
+    pub open_files: Vec<PathBuf>,
+    /// Number of calls per tool, across all sessions
+    #[serde(default)]
+    pub tool_usage: BTreeMap<String, u64>,
+    /// Capabilities reported by the language server during the last handshake
+    #[serde(default)]
+    pub capabilities: Option<ServerCapabilities>,
+    /// When the state was last saved (RFC 3339)
+    #[serde(default)]
+    pub saved_at: Option<String>,
+}
+
+/// Persistent session state stored in a workspace-scoped state directory
+#[derive(Debug)]
+pub struct Session {
+    path: PathBuf,
+    state: Mutex<SessionState>,
+    /// Whether a save is already scheduled
+    save_pending: AtomicBool,
+}
+
+/// Returns the directory session state is kept in for a workspace
+pub fn state_dir(workspace: &Path) -> Option<PathBuf> {
+    let base = dirs::state_dir().or_else(dirs::data_local_dir)?;
+    Some(
+        base.join("mcp-language-server")
+            .join(workspace_key(workspace)),
+    )
+}
+
+/// Turns a workspace path into a directory name that is unique per workspace:
+/// the folder name for readability, then a hash of the full canonical path
+pub fn workspace_key(workspace: &Path) -> String {
+    let workspace = workspace
+        .canonicalize()
+        .unwrap_or_else(|_| workspace.to_path_buf());
+    let name: String = workspace
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let hash = xxhash_rust::xxh3::xxh3_64(workspace.as_os_str().as_encoded_bytes());
+    format!("{}-{:016x}", name.trim_matches('_'), hash)
+}
+
+impl Session {
+    /// Loads the session for a workspace from its default state directory
+    pub fn for_workspace(workspace: &Path) -> Option<Arc<Self>> {
+        let Some(dir) = state_dir(workspace) else {
+            warn!("[SESSION] No state directory available, session state will not be persisted");
+            return None;
+        };
+        Some(Self::load(dir.join(STATE_FILE)))
+    }
+
+    /// Loads the session from a state file, starting fresh if it is missing or unreadable
+    pub fn load(path: PathBuf) -> Arc<Self> {
+        let state = match std::fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(state) => {
+                    info!("[SESSION] Restored session state from {}", path.display());
+                    state
+                }
+                Err(e) => {
+                    warn!(
+                        "[SESSION] Ignoring invalid session state {}: {}",
+                        path.display(),
+                        e
+                    );
+                    SessionState::default()
+                }
+            },
+            Err(_) => {
+                debug!("[SESSION] No session state at {}", path.display());
+                SessionState::default()
+            }
+        };
+
+        Arc::new(Self {
+            path,
+            state: Mutex::new(state),
+            save_pending: AtomicBool::new(false),
+        })
+    }
+
+    /// Gets the path of the state file
+    #[allow(dead_code)]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets a snapshot of the current state
+    pub fn state(&self) -> SessionState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Counts a call to a tool
+    pub fn record_tool(&self, tool: &str) {
+        let mut state = self.state.lock().unwrap();
+        *state.tool_usage.entry(tool.to_string()).or_default() += 1;
+    }
+
+    /// Captures the open files and server capabilities from the client
+    pub fn capture(&self, client: &Client) {
+        let mut open_files: Vec<PathBuf> = client
+            .open_documents()
+            .iter()
+            .filter_map(|doc| to_path(&doc.uri).ok())
+            .collect();
+        open_files.sort();
+
+        let mut state = self.state.lock().unwrap();
+        state.open_files = open_files;
+        if let Some(capabilities) = client.server_capabilities() {
+            state.capabilities = Some(capabilities);
+        }
+    }
+
+    /// Reopens the files that were open in the previous session, and reports
+    /// what the server gained or lost since then and which tools were used most
+    pub async fn restore(&self, client: &Client) {
+        let SessionState {
+            open_files,
+            tool_usage,
+            capabilities,
+            ..
+        } = self.state();
+
+        if let Some(previous) = &capabilities
+            && let Some(current) = client.server_capabilities()
+        {
+            let (gained, lost) = capability_changes(previous, &current);
+            if !gained.is_empty() || !lost.is_empty() {
+                warn!(
+                    "[SESSION] {} changed since the last session: gained [{}], lost [{}]",
+                    client.name(),
+                    gained.join(", "),
+                    lost.join(", ")
+                );
+            }
+            if !lost.is_empty() {
+                let affected: Vec<&str> = most_used(&tool_usage)
+                    .into_iter()
+                    .filter(|tool| lost.iter().any(|lost| tool_needs(tool, lost)))
+                    .collect();
+                if !affected.is_empty() {
+                    warn!(
+                        "[SESSION] Tools used in earlier sessions may no longer work: {}",
+                        affected.join(", ")
+                    );
+                }
+            }
+        }
+
+        let top = most_used(&tool_usage);
+        if !top.is_empty() {
+            info!(
+                "[SESSION] Most used tools in this workspace: {}",
+                top.iter()
+                    .take(TOP_TOOLS)
+                    .map(|tool| format!("{} ({})", tool, tool_usage[*tool]))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+
+        let mut restored = 0;
+
+        for path in open_files.iter().filter(|path| path.exists()) {
+            match client.open_file(path).await {
+                Ok(()) => restored += 1,
+                Err(e) => debug!("[SESSION] Could not reopen {}: {}", path.display(), e),
+            }
+        }
+
+        if restored > 0 {
+            info!(
+                "[SESSION] Reopened {} files from the last session",
+                restored
+            );
+        }
+    }
+
+    /// Captures the client's state and saves it shortly, off the calling
+    /// task; calls made before the save runs share it
+    pub fn schedule_save(self: &Arc<Self>, client: Arc<Client>) {
+        if self.save_pending.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let session = Arc::clone(self);
+        tokio::spawn(async move {
+            tokio::time::sleep(SAVE_DELAY).await;
+            session.save_pending.store(false, Ordering::Release);
+            session.capture(&client);
+            match tokio::task::spawn_blocking(move || session.save()).await {
+                Ok(Err(e)) => warn!("[SESSION] {}", e),
+                Err(e) => warn!("[SESSION] Saving session state failed: {}", e),
+                Ok(Ok(())) => {}
+            }
+        });
+    }
+
+    /// Writes the current state to disk
+    pub fn save(&self) -> Result<()> {
+        let content = {
+            let mut state = self.state.lock().unwrap();
+            state.saved_at = Some(chrono::Utc::now().to_rfc3339());
+            serde_json::to_string_pretty(&*state)?
+        };
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).context(format!(
+                "Failed to create state directory: {}",
+                dir.display()
+            ))?;
+        }
+
+        // Write to a temporary file first so a crash never leaves a truncated state file
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, content).context(format!(
+            "Failed to write session state: {}",
+            tmp_path.display()
+        ))?;
+        std::fs::rename(&tmp_path, &self.path).context(format!(
+            "Failed to save session state: {}",
+            self.path.display()
+        ))?;
+
+        debug!("[SESSION] Saved session state to {}", self.path.display());
+        Ok(())
+    }
+}
+
+/// Lists the capabilities `current` offers that `previous` did not, and
+/// those it no longer offers, by their LSP names
+pub fn capability_changes(
+    previous: &ServerCapabilities,
+    current: &ServerCapabilities,
+) -> (Vec<String>, Vec<String>) {
+    let offered = |capabilities: &ServerCapabilities| -> Vec<String> {
+        match serde_json::to_value(capabilities) {
+            Ok(serde_json::Value::Object(map)) => map
+                .into_iter()
+                .filter(|(_, value)| {
+                    !matches!(
+                        value,
+                        serde_json::Value::Null | serde_json::Value::Bool(false)
+                    )
+                })
+                .map(|(name, _)| name)
+                .collect(),
+            _ => Vec::new(),
+        }
+    };
+    let previous = offered(previous);
+    let current = offered(current);
+
+    let gained = current
+        .iter()
+        .filter(|name| !previous.contains(name))
+        .cloned()
+        .collect();
+    let lost = previous
+        .iter()
+        .filter(|name| !current.contains(name))
+        .cloned()
+        .collect();
+    (gained, lost)
+}
+
+/// Gets the tools used so far, the most used first
+fn most_used(tool_usage: &BTreeMap<String, u64>) -> Vec<&str> {
+    let mut tools: Vec<(&str, u64)> = tool_usage
+        .iter()
+        .map(|(tool, count)| (tool.as_str(), *count))
+        .collect();
+    tools.sort_by(|a, b| b.1.cmp(&a.1));
+    tools.into_iter().map(|(tool, _)| tool).collect()
+}
+
+/// Whether a tool relies on a server capability, matching the tool name
+/// against the capability name (`hover` and `hoverProvider`)
+fn tool_needs(tool: &str, capability: &str) -> bool {
+    let capability = capability.trim_end_matches("Provider").to_ascii_lowercase();
+    let tool: String = tool.chars().filter(|c| *c != '_').collect();
+    !capability.is_empty() && (tool.starts_with(&capability) || capability.starts_with(&tool))
+}
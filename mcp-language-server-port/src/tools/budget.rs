@@ -0,0 +1,173 @@
+use std::sync::RwLock;
+
+use super::render;
+
+/// Default number of characters assumed to make up one token
+pub const DEFAULT_CHARS_PER_TOKEN: f64 = 4.0;
+
+/// Approximate size limit for tool responses
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResponseBudget {
+    pub max_tokens: usize,
+    pub chars_per_token: f64,
+}
+
+impl ResponseBudget {
+    /// Gets the budget in characters
+    pub fn max_chars(&self) -> usize {
+        (self.max_tokens as f64 * self.chars_per_token) as usize
+    }
+}
+
+/// Budget applied to every tool response, if any
+static RESPONSE_BUDGET: RwLock<Option<ResponseBudget>> = RwLock::new(None);
+
+/// Sets the budget tool responses are shaped to fit, or removes it
+pub fn set_response_budget(budget: Option<ResponseBudget>) {
+    *RESPONSE_BUDGET.write().unwrap() = budget;
+}
+
+/// Gets the configured response budget in characters
+pub fn char_budget() -> Option<usize> {
+    RESPONSE_BUDGET.read().unwrap().map(|b| b.max_chars())
+}
+
+/// One item of a tool response: a header that is always kept, context lines,
+/// and an optional code snippet, rendered in that order. The compact output
+/// style shows only the summary, or the first line of the header without one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Block {
+    pub header: String,
+    pub context: Vec<String>,
+    pub snippet: Option<String>,
+    pub summary: Option<String>,
+}
+
+impl Block {
+    /// Creates a block with just a header
+    pub fn new(header: impl Into<String>) -> Self {
+        Self {
+            header: header.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Renders a preamble followed by blocks joined with `separator`, trimming the
+/// output to the configured response budget.
+///
+/// Code snippets are dropped first, then context lines, then whole items,
+/// each starting from the last item. A note reports what was left out.
+pub fn fit(preamble: &str, blocks: Vec<Block>, separator: &str) -> String {
+    match char_budget() {
+        Some(max_chars) => fit_to(preamble, blocks, separator, max_chars),
+        None => render(preamble, &blocks, separator),
+    }
+}
+
+/// Trims plain text line by line to the configured response budget
+pub fn fit_lines(text: &str) -> String {
+    let blocks = text.split_inclusive('\n').map(Block::new).collect();
+    fit("", blocks, "")
+}
+
+/// Like [`fit`], with an explicit budget in characters
+pub fn fit_to(preamble: &str, mut blocks: Vec<Block>, separator: &str, max_chars: usize) -> String {
+    let renderer = render::renderer();
+    let separator_chars = renderer.separator(separator).chars().count();
+    let block_chars = |block: &Block| {
+        let mut out = String::new();
+        renderer.block(block, &mut out);
+        out.chars().count()
+    };
+
+    // Each block is measured once, and again only when it is trimmed
+    let mut sizes: Vec<usize> = blocks.iter().map(block_chars).collect();
+    let mut total = renderer.text(preamble).chars().count()
+        + sizes.iter().sum::<usize>()
+        + separator_chars * blocks.len().saturating_sub(1);
+    if total <= max_chars {
+        return render(preamble, &blocks, separator);
+    }
+
+    // Leave room for the note, at its longest
+    let all_context_lines = blocks.iter().map(|b| context_lines(&b.context)).sum();
+    let note_chars = trim_note(blocks.len(), all_context_lines, blocks.len())
+        .chars()
+        .count();
+    let max_chars = max_chars.saturating_sub(note_chars + 1);
+
+    let mut snippets = 0;
+    for i in (0..blocks.len()).rev() {
+        if total <= max_chars {
+            break;
+        }
+        if blocks[i].snippet.take().is_some() {
+            snippets += 1;
+            let size = block_chars(&blocks[i]);
+            total = total - sizes[i] + size;
+            sizes[i] = size;
+        }
+    }
+
+    let mut omitted_context = 0;
+    for i in (0..blocks.len()).rev() {
+        if total <= max_chars {
+            break;
+        }
+        omitted_context += context_lines(&blocks[i].context);
+        blocks[i].context.clear();
+        let size = block_chars(&blocks[i]);
+        total = total - sizes[i] + size;
+        sizes[i] = size;
+    }
+
+    let mut items = 0;
+    while total > max_chars
+        && let Some(size) = sizes.pop()
+    {
+        blocks.pop();
+        total -= size;
+        if !blocks.is_empty() {
+            total -= separator_chars;
+        }
+        items += 1;
+    }
+
+    let mut result = render(preamble, &blocks, separator);
+    if !result.ends_with('\n') {
+        result.push('\n');
+    }
+    result.push_str(&trim_note(snippets, omitted_context, items));
+    result
+}
+
+/// Counts the non-blank lines of a block's context
+fn context_lines(context: &[String]) -> usize {
+    context
+        .iter()
+        .map(|line| line.lines().filter(|l| !l.trim().is_empty()).count())
+        .sum()
+}
+
+/// Describes what was left out of a trimmed response
+fn trim_note(snippets: usize, context_lines: usize, items: usize) -> String {
+    format!(
+        "\n[Output trimmed to fit the response budget: omitted {} code snippets, {} context lines, {} items]",
+        snippets, context_lines, items
+    )
+}
+
+fn render(preamble: &str, blocks: &[Block], separator: &str) -> String {
+    let renderer = render::renderer();
+    let separator = renderer.separator(separator);
+
+    let mut out = renderer.text(preamble);
+    for (i, block) in blocks.iter().enumerate() {
+        if i > 0 {
+            out.push_str(separator);
+        }
+        renderer.block(block, &mut out);
+    }
+    out
+}
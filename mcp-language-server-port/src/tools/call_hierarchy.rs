@@ -0,0 +1,255 @@
+use crate::lsp::Client;
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
+    CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
+    Position, Range, TextDocumentPositionParams,
+};
+use std::collections::{BTreeMap, HashMap, hash_map::Entry};
+use std::path::PathBuf;
+use tokio::fs;
+
+use super::budget::{self, Block};
+use super::definition::parse_symbol_location;
+use super::document_symbols::symbol_kind_name;
+use super::references::resolve_symbol;
+use super::render::{self, OutputFormat};
+use super::structured::{CallResult, to_json};
+use super::utils::{display_path, to_path, to_text_document_identifier};
+
+/// A caller or callee with the ranges of the calls between it and the queried function
+#[derive(Debug, Clone, PartialEq)]
+pub struct Call {
+    pub item: CallHierarchyItem,
+    /// File the call ranges are in
+    pub call_file: PathBuf,
+    pub call_ranges: Vec<Range>,
+}
+
+/// Lists the functions that call a function, grouped by file
+pub async fn find_incoming_calls(client: &Client, symbol_name: &str) -> Result<String> {
+    debug!("[TOOL] Finding incoming calls for {}", symbol_name);
+
+    let (item, note) = prepare_call_hierarchy(client, symbol_name).await?;
+    let params = CallHierarchyIncomingCallsParams {
+        item: item.clone(),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+    let incoming: Option<Vec<CallHierarchyIncomingCall>> =
+        client.call("callHierarchy/incomingCalls", params).await?;
+
+    // Callers are listed where they make the calls
+    let mut calls = Vec::new();
+    for call in incoming.unwrap_or_default() {
+        calls.push(Call {
+            call_file: to_path(&call.from.uri)?,
+            item: call.from,
+            call_ranges: call.from_ranges,
+        });
+    }
+
+    if render::current_format() == OutputFormat::Json {
+        return calls_json(&calls);
+    }
+    if calls.is_empty() {
+        return Ok(format!("No calls to '{}' found{}", item.name, note));
+    }
+
+    let summary = format!(
+        "Found {} callers of '{}' in {} files:\n\n",
+        calls.len(),
+        item.name,
+        count_files(&calls)
+    );
+    let mut result = budget::fit(
+        &summary,
+        call_blocks(client, calls, "calling at").await?,
+        "\n",
+    );
+    result.push_str(&note);
+    Ok(result)
+}
+
+/// Lists the functions a function calls, grouped by the file they're defined in,
+/// with the lines of the function that call them
+pub async fn find_outgoing_calls(client: &Client, symbol_name: &str) -> Result<String> {
+    debug!("[TOOL] Finding outgoing calls for {}", symbol_name);
+
+    let (item, note) = prepare_call_hierarchy(client, symbol_name).await?;
+    let caller_file = to_path(&item.uri)?;
+    let params = CallHierarchyOutgoingCallsParams {
+        item: item.clone(),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+    let outgoing: Option<Vec<CallHierarchyOutgoingCall>> =
+        client.call("callHierarchy/outgoingCalls", params).await?;
+
+    // Call sites of outgoing calls are all in the queried function
+    let calls: Vec<Call> = outgoing
+        .unwrap_or_default()
+        .into_iter()
+        .map(|call| Call {
+            item: call.to,
+            call_file: caller_file.clone(),
+            call_ranges: call.from_ranges,
+        })
+        .collect();
+
+    if render::current_format() == OutputFormat::Json {
+        return calls_json(&calls);
+    }
+    if calls.is_empty() {
+        return Ok(format!("No calls from '{}' found{}", item.name, note));
+    }
+
+    let summary = format!(
+        "Found {} functions called by '{}' in {} files:\n\n",
+        calls.len(),
+        item.name,
+        count_files(&calls)
+    );
+    let mut result = budget::fit(
+        &summary,
+        call_blocks(client, calls, "called at").await?,
+        "\n",
+    );
+    result.push_str(&note);
+    Ok(result)
+}
+
+/// Resolves a symbol to the call hierarchy item for it, with a note when the
+/// name was ambiguous
+async fn prepare_call_hierarchy(
+    client: &Client,
+    symbol_name: &str,
+) -> Result<(CallHierarchyItem, String)> {
+    // Take a path:line:column location, or look a bare name up in the workspace
+    let mut note = String::new();
+    let (file_path, line, column) = match parse_symbol_location(symbol_name) {
+        Ok(location) => location,
+        Err(_) => {
+            let mut candidates = resolve_symbol(client, symbol_name).await?;
+            if candidates.len() > 1 {
+                note = format!(
+                    "\nNote: {} other symbols are named '{}'; pass path:line:column to pick another.\n",
+                    candidates.len() - 1,
+                    symbol_name
+                );
+            }
+            candidates.remove(0)
+        }
+    };
+
+    // Ensure the file is open
+    client.open_file(&file_path).await?;
+
+    let params = CallHierarchyPrepareParams {
+        text_document_position_params: TextDocumentPositionParams {
+            text_document: to_text_document_identifier(&file_path)?,
+            position: Position {
+                line,
+                character: column,
+            },
+        },
+        work_done_progress_params: Default::default(),
+    };
+    let items: Option<Vec<CallHierarchyItem>> = client
+        .call("textDocument/prepareCallHierarchy", params)
+        .await?;
+
+    let item = items
+        .unwrap_or_default()
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No function found at {}", symbol_name))?;
+    Ok((item, note))
+}
+
+/// Serializes calls for the JSON output format
+fn calls_json(calls: &[Call]) -> Result<String> {
+    let results = calls
+        .iter()
+        .map(CallResult::new)
+        .collect::<Result<Vec<_>>>()?;
+    to_json(&results)
+}
+
+fn count_files(calls: &[Call]) -> usize {
+    let mut files: Vec<&lsp_types::Url> = calls.iter().map(|call| &call.item.uri).collect();
+    files.sort();
+    files.dedup();
+    files.len()
+}
+
+/// Formats calls as one block per file the callers or callees are defined in,
+/// each call site with a preview of its line
+async fn call_blocks(client: &Client, calls: Vec<Call>, verb: &str) -> Result<Vec<Block>> {
+    let mut calls_by_file: BTreeMap<PathBuf, Vec<Call>> = BTreeMap::new();
+    for call in calls {
+        calls_by_file
+            .entry(to_path(&call.item.uri)?)
+            .or_default()
+            .push(call);
+    }
+
+    let mut contents: HashMap<PathBuf, String> = HashMap::new();
+    let mut blocks = Vec::new();
+    for (file_path, calls) in calls_by_file {
+        let mut block = Block::new(format!("File: {}\n", display_path(client, &file_path)));
+        let mut summary = String::new();
+        for call in calls {
+            if let Entry::Vacant(entry) = contents.entry(call.call_file.clone()) {
+                let content = fs::read_to_string(&call.call_file)
+                    .await
+                    .context(format!("Failed to read file: {}", call.call_file.display()))?;
+                entry.insert(content);
+            }
+            let lines: Vec<&str> = contents[&call.call_file].lines().collect();
+
+            block.context.push(format_call(&call, &lines, verb));
+
+            // Callers are summarized by where they call, callees by where they're defined
+            let line = match call.call_ranges.first() {
+                Some(range) if call.call_file == file_path => range.start.line,
+                _ => call.item.selection_range.start.line,
+            };
+            summary.push_str(&format!(
+                "{}:{}: {}\n",
+                display_path(client, &file_path),
+                line + 1,
+                call.item.name
+            ));
+        }
+        block.summary = Some(summary);
+        blocks.push(block);
+    }
+    Ok(blocks)
+}
+
+/// Formats a caller or callee followed by its call sites, previewing each
+/// line of `lines`, the content of the file the calls are in
+pub fn format_call(call: &Call, lines: &[&str], verb: &str) -> String {
+    let item = &call.item;
+    let mut text = format!(
+        "  {} {} (line {})",
+        symbol_kind_name(item.kind),
+        item.name,
+        item.selection_range.start.line + 1
+    );
+    if let Some(detail) = item.detail.as_ref().filter(|d| !d.is_empty()) {
+        text.push_str(&format!(": {}", detail));
+    }
+    text.push_str(&format!(", {}:\n", verb));
+
+    let mut call_lines: Vec<u32> = call.call_ranges.iter().map(|r| r.start.line).collect();
+    call_lines.sort();
+    call_lines.dedup();
+    for line in call_lines {
+        let preview = lines.get(line as usize).map_or("", |l| l.trim());
+        text.push_str(&format!("    Line {}: {}\n", line + 1, preview));
+    }
+    text
+}
@@ -0,0 +1,234 @@
+use crate::lsp::{Client, PositionEncoding};
+use anyhow::Result;
+use log::debug;
+use serde_json::Value;
+use std::sync::Arc;
+
+use super::budget;
+
+/// Requests a server supports when the capability under the key is present
+/// and not false
+const PROVIDERS: &[(&str, &str)] = &[
+    ("callHierarchyProvider", "textDocument/prepareCallHierarchy"),
+    ("codeActionProvider", "textDocument/codeAction"),
+    ("codeLensProvider", "textDocument/codeLens"),
+    ("colorProvider", "textDocument/documentColor"),
+    ("completionProvider", "textDocument/completion"),
+    ("declarationProvider", "textDocument/declaration"),
+    ("definitionProvider", "textDocument/definition"),
+    ("diagnosticProvider", "textDocument/diagnostic"),
+    ("documentFormattingProvider", "textDocument/formatting"),
+    (
+        "documentHighlightProvider",
+        "textDocument/documentHighlight",
+    ),
+    ("documentLinkProvider", "textDocument/documentLink"),
+    (
+        "documentOnTypeFormattingProvider",
+        "textDocument/onTypeFormatting",
+    ),
+    (
+        "documentRangeFormattingProvider",
+        "textDocument/rangeFormatting",
+    ),
+    ("documentSymbolProvider", "textDocument/documentSymbol"),
+    ("executeCommandProvider", "workspace/executeCommand"),
+    ("foldingRangeProvider", "textDocument/foldingRange"),
+    ("hoverProvider", "textDocument/hover"),
+    ("implementationProvider", "textDocument/implementation"),
+    ("inlayHintProvider", "textDocument/inlayHint"),
+    ("inlineValueProvider", "textDocument/inlineValue"),
+    (
+        "linkedEditingRangeProvider",
+        "textDocument/linkedEditingRange",
+    ),
+    ("monikerProvider", "textDocument/moniker"),
+    ("referencesProvider", "textDocument/references"),
+    ("renameProvider", "textDocument/rename"),
+    ("selectionRangeProvider", "textDocument/selectionRange"),
+    ("semanticTokensProvider", "textDocument/semanticTokens"),
+    ("signatureHelpProvider", "textDocument/signatureHelp"),
+    ("typeDefinitionProvider", "textDocument/typeDefinition"),
+    ("typeHierarchyProvider", "textDocument/prepareTypeHierarchy"),
+    ("workspaceSymbolProvider", "workspace/symbol"),
+];
+
+/// Checks whether a server supports a request, by its capabilities or a
+/// dynamic registration. Returns None until the server has initialized;
+/// requests without a capability of their own count as supported.
+pub fn supports(client: &Client, method: &str) -> Option<bool> {
+    let capabilities = serde_json::to_value(client.server_capabilities()?).ok()?;
+    let Some((key, _)) = PROVIDERS.iter().find(|(_, m)| *m == method) else {
+        return Some(true);
+    };
+    Some(
+        capabilities.get(key).is_some_and(is_enabled)
+            || client.registered_methods().iter().any(|m| m == method),
+    )
+}
+
+/// Summarizes what each server reported it can do during initialization, and
+/// what it registered since, to tell why a tool comes back empty
+pub async fn get_server_capabilities(clients: &[Arc<Client>]) -> Result<String> {
+    debug!("[TOOL] Summarizing server capabilities");
+
+    let mut servers = Vec::new();
+    for client in clients {
+        servers.push(Arc::clone(client));
+        servers.extend(client.attached_servers());
+    }
+
+    let sections: Vec<String> = servers
+        .iter()
+        .map(|client| summarize(client))
+        .collect::<Result<_>>()?;
+    Ok(budget::fit_lines(&sections.join("\n")))
+}
+
+/// Summarizes the capabilities of one server
+fn summarize(client: &Client) -> Result<String> {
+    let mut result = match client.server_info() {
+        Some(info) => match info.version {
+            Some(version) => format!("{} ({} {})\n", client.name(), info.name, version),
+            None => format!("{} ({})\n", client.name(), info.name),
+        },
+        None => format!("{}\n", client.name()),
+    };
+    let Some(capabilities) = client.server_capabilities() else {
+        result.push_str("  Not initialized yet\n");
+        return Ok(result);
+    };
+    let capabilities = serde_json::to_value(capabilities)?;
+
+    let encoding = match client.position_encoding() {
+        PositionEncoding::Utf8 => "utf-8",
+        PositionEncoding::Utf16 => "utf-16",
+    };
+    result.push_str(&format!("  Position encoding: {}\n", encoding));
+    result.push_str(&format!(
+        "  Text sync: {}\n",
+        text_sync(capabilities.get("textDocumentSync"))
+    ));
+
+    let registered = client.registered_methods();
+    let mut unsupported = Vec::new();
+    result.push_str("  Supported requests:\n");
+    for (key, method) in PROVIDERS {
+        match capabilities.get(key).filter(|value| is_enabled(value)) {
+            Some(value) => {
+                result.push_str(&format!("    {}{}\n", method, details(key, value)));
+            }
+            None if registered.iter().any(|m| m == method) => {
+                result.push_str(&format!("    {} (registered dynamically)\n", method));
+            }
+            None => unsupported.push(*method),
+        }
+    }
+    if !unsupported.is_empty() {
+        result.push_str(&format!("  Not supported: {}\n", unsupported.join(", ")));
+    }
+
+    let folder_option = |name: &str| {
+        capabilities
+            .pointer(&format!("/workspace/workspaceFolders/{}", name))
+            .is_some_and(is_enabled)
+    };
+    let folders = if !folder_option("supported") {
+        "not supported"
+    } else if folder_option("changeNotifications") {
+        "supported, follows changes"
+    } else {
+        "supported"
+    };
+    result.push_str(&format!("  Workspace folders: {}\n", folders));
+
+    if !registered.is_empty() {
+        result.push_str(&format!(
+            "  Registered dynamically: {}\n",
+            registered.join(", ")
+        ));
+    }
+    if let Some(experimental) = capabilities.get("experimental") {
+        result.push_str(&format!("  Experimental: {}\n", experimental));
+    }
+
+    Ok(result)
+}
+
+/// Checks whether a capability is present and not switched off
+fn is_enabled(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+/// Describes the options of a capability that change what tools get back
+fn details(key: &str, value: &Value) -> String {
+    let list = |name: &str| -> Option<String> {
+        let items: Vec<&str> = value
+            .get(name)?
+            .as_array()?
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        (!items.is_empty()).then(|| items.join(" "))
+    };
+    let flag = |name: &str| value.get(name).and_then(Value::as_bool).unwrap_or(false);
+
+    let mut details = Vec::new();
+    match key {
+        "codeActionProvider" => {
+            if let Some(kinds) = list("codeActionKinds") {
+                details.push(format!("kinds: {}", kinds));
+            }
+            if flag("resolveProvider") {
+                details.push("resolves edits lazily".to_string());
+            }
+        }
+        "completionProvider" => {
+            if let Some(triggers) = list("triggerCharacters") {
+                details.push(format!("triggered by: {}", triggers));
+            }
+        }
+        "diagnosticProvider" if flag("workspaceDiagnostics") => {
+            details.push("workspace diagnostics".to_string());
+        }
+        "executeCommandProvider" => {
+            if let Some(commands) = list("commands") {
+                details.push(format!("commands: {}", commands));
+            }
+        }
+        "renameProvider" if flag("prepareProvider") => {
+            details.push("prepareRename".to_string());
+        }
+        _ => {}
+    }
+
+    if details.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", details.join("; "))
+    }
+}
+
+/// Describes how documents are synchronized with the server
+fn text_sync(sync: Option<&Value>) -> String {
+    let kind_name = |kind: Option<i64>| match kind {
+        Some(1) => "full content on change",
+        Some(2) => "incremental changes",
+        _ => "no changes",
+    };
+
+    match sync {
+        Some(Value::Number(kind)) => kind_name(kind.as_i64()).to_string(),
+        Some(Value::Object(options)) => {
+            let mut parts = vec![kind_name(options.get("change").and_then(Value::as_i64))];
+            if options.get("openClose").and_then(Value::as_bool) == Some(true) {
+                parts.push("open/close");
+            }
+            if options.get("save").is_some_and(is_enabled) {
+                parts.push("save");
+            }
+            parts.join(", ")
+        }
+        _ => "none".to_string(),
+    }
+}
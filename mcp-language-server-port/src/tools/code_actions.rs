@@ -0,0 +1,541 @@
+use crate::lsp::Client;
+use crate::lsp::position::position_at;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Context, Result, anyhow};
+use futures::stream::{self, StreamExt};
+use log::debug;
+use lsp_types::{
+    CodeAction, CodeActionContext, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, CodeActionResponse, Diagnostic, Position, Range,
+};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::budget;
+use super::execute_command;
+use super::scope::{ActionScope, SCOPE_CONCURRENCY};
+use super::utils::{display_path, format_servers, to_text_document_identifier, to_uri};
+use super::workspace_edit::{self, ApplyOptions};
+
+/// Lists the code actions available for a range, numbered for `apply_code_action`
+pub async fn get_code_actions(
+    client: &Client,
+    file_path: PathBuf,
+    range: Range,
+    only: Option<&str>,
+) -> Result<String> {
+    debug!(
+        "[TOOL] Getting code actions for {}:{}:{}",
+        file_path.display(),
+        range.start.line,
+        range.start.character
+    );
+
+    let file_path = open_existing(client, file_path).await?;
+    let actions = all_code_actions(client, &file_path, range, only).await?;
+
+    if actions.is_empty() {
+        return Ok(format!(
+            "No code actions available at {}:{}",
+            display_path(client, &file_path),
+            range.start.line + 1
+        ));
+    }
+
+    let mut result = format!(
+        "Found {} code actions at {}:{}:\n",
+        actions.len(),
+        display_path(client, &file_path),
+        range.start.line + 1
+    );
+    // Only attribute actions when more than one server offered some
+    let attribute = actions.iter().any(|(server, _)| *server != actions[0].0);
+    for (i, (server, action)) in actions.iter().enumerate() {
+        let mut line = format_code_action(i + 1, action);
+        if attribute {
+            let end = line.find('\n').unwrap_or(line.len());
+            line.insert_str(end, &format_servers(std::slice::from_ref(server)));
+        }
+        result.push_str(&line);
+        result.push('\n');
+    }
+    result.push_str("\nApply one with apply_code_action and the same position and its number.\n");
+
+    Ok(budget::fit_lines(&result))
+}
+
+/// Applies the code action numbered `index` in the `code_actions` listing for
+/// the same range, editing files and running its command as needed
+pub async fn apply_code_action(
+    client: &Client,
+    file_path: PathBuf,
+    range: Range,
+    only: Option<&str>,
+    index: usize,
+) -> Result<String> {
+    debug!(
+        "[TOOL] Applying code action {} at {}:{}:{}",
+        index,
+        file_path.display(),
+        range.start.line,
+        range.start.character
+    );
+
+    let file_path = open_existing(client, file_path).await?;
+    let actions = all_code_actions(client, &file_path, range, only).await?;
+
+    // Actions are numbered from 1 in the listing
+    let (server, action) = index
+        .checked_sub(1)
+        .and_then(|i| actions.into_iter().nth(i))
+        .ok_or_else(|| {
+            anyhow!(
+                "No code action {} at this position; list them with code_actions first",
+                index
+            )
+        })?;
+    // The server that offered the action resolves and runs it
+    let server = server_named(client, &server);
+
+    let (title, edit, command) = match action {
+        CodeActionOrCommand::Command(command) => (command.title.clone(), None, Some(command)),
+        CodeActionOrCommand::CodeAction(action) => {
+            if let Some(disabled) = &action.disabled {
+                return Err(anyhow!(
+                    "Code action '{}' is disabled: {}",
+                    action.title,
+                    disabled.reason
+                ));
+            }
+            let action = resolve_code_action(&server, action).await;
+            (action.title, action.edit, action.command)
+        }
+    };
+
+    let mut result = format!("Applied code action '{}'", title);
+
+    // The edit comes first; the command runs on the edited files
+    if let Some(edit) = edit {
+        let report = workspace_edit::apply(&server, edit, &ApplyOptions::default()).await?;
+        result.push_str(&format!("\n{}", report));
+    }
+
+    if let Some(command) = command {
+        let arguments = command.arguments.unwrap_or_default();
+        let report = execute_command::run_command(&server, &command.command, arguments).await?;
+        result.push_str(&format!("\n{}", report));
+    }
+
+    Ok(budget::fit_lines(&result))
+}
+
+/// The code action kind servers use to sort and prune imports
+const ORGANIZE_IMPORTS: &str = "source.organizeImports";
+
+/// Organizes the imports of a file with the server's `source.organizeImports`
+/// action, applying its edit
+pub async fn organize_imports(
+    client: &Client,
+    file_path: PathBuf,
+    options: &ApplyOptions,
+) -> Result<String> {
+    debug!("[TOOL] Organizing imports in {}", file_path.display());
+
+    // Servers that list their action kinds say up front when they can't
+    if let Some(CodeActionProviderCapability::Options(provider)) = client
+        .server_capabilities()
+        .and_then(|capabilities| capabilities.code_action_provider)
+        && let Some(kinds) = provider.code_action_kinds
+        && !kinds.iter().any(|kind| is_kind(kind, ORGANIZE_IMPORTS))
+    {
+        return Err(anyhow!(
+            "{} does not offer {} actions",
+            client.name(),
+            ORGANIZE_IMPORTS
+        ));
+    }
+
+    let file_path = open_existing(client, file_path).await?;
+    let content = tokio::fs::read_to_string(&file_path)
+        .await
+        .context(format!("Failed to read file: {}", file_path.display()))?;
+    let range = Range {
+        start: Position::default(),
+        end: position_at(&content, content.len(), client.position_encoding()),
+    };
+
+    let actions = code_actions(client, &file_path, range, Some(ORGANIZE_IMPORTS)).await?;
+    let action = actions.into_iter().find_map(|action| match action {
+        CodeActionOrCommand::CodeAction(action)
+            if action.disabled.is_none()
+                && action
+                    .kind
+                    .as_ref()
+                    .is_none_or(|kind| is_kind(kind, ORGANIZE_IMPORTS)) =>
+        {
+            Some(action)
+        }
+        _ => None,
+    });
+    let Some(action) = action else {
+        return Ok(format!(
+            "Imports in {} are already organized",
+            display_path(client, &file_path)
+        ));
+    };
+    let action = resolve_code_action(client, action).await;
+
+    let mut result = format!("Organized imports in {}", display_path(client, &file_path));
+    match action.edit {
+        Some(edit) => {
+            let report = workspace_edit::apply(client, edit, options).await?;
+            if report.edits() == 0 && action.command.is_none() {
+                return Ok(format!(
+                    "Imports in {} are already organized",
+                    display_path(client, &file_path)
+                ));
+            }
+            result.push_str(&format!("\n{}", report));
+        }
+        None if action.command.is_none() => {
+            return Err(anyhow!(
+                "Action '{}' came without an edit or command",
+                action.title
+            ));
+        }
+        None => {}
+    }
+
+    // Commands can't be previewed, so a dry run only names it
+    if let Some(command) = action.command {
+        if options.dry_run {
+            result.push_str(&format!("\nWould run command '{}'", command.command));
+        } else {
+            let arguments = command.arguments.unwrap_or_default();
+            let report = execute_command::run_command(client, &command.command, arguments).await?;
+            result.push_str(&format!("\n{}", report));
+        }
+    }
+
+    Ok(budget::fit_lines(&result))
+}
+
+/// Organizes the imports of every file in `scope` around `path`, a few files
+/// at a time, and sums up which files changed
+pub async fn organize_imports_in(
+    client: &Client,
+    path: PathBuf,
+    scope: ActionScope,
+    options: &ApplyOptions,
+) -> Result<String> {
+    if scope == ActionScope::File {
+        return organize_imports(client, path, options).await;
+    }
+
+    let files = scope.files(client, &path)?;
+    debug!(
+        "[TOOL] Organizing imports in {} files under {}",
+        files.len(),
+        path.display()
+    );
+    if files.is_empty() {
+        return Ok(format!(
+            "No files to organize in {}",
+            display_path(client, &path)
+        ));
+    }
+
+    let results: Vec<(PathBuf, Result<String>)> = stream::iter(files)
+        .map(|file| async move {
+            let result = organize_imports(client, file.clone(), options).await;
+            (file, result)
+        })
+        .buffered(SCOPE_CONCURRENCY)
+        .collect()
+        .await;
+
+    let total = results.len();
+    let mut organized = Vec::new();
+    let mut failed = Vec::new();
+    let mut first_error = None;
+    for (file, result) in results {
+        match result {
+            Ok(report) if report.starts_with("Imports in") => {}
+            Ok(report) => organized.push(report),
+            Err(e) => {
+                failed.push(format!("{}: {}", display_path(client, &file), e));
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    // Failing on every file means the server can't do it at all
+    if let Some(e) = first_error
+        && failed.len() == total
+    {
+        return Err(e);
+    }
+
+    let mut result = format!(
+        "{} imports in {} of {} files\n",
+        if options.dry_run {
+            "Would organize"
+        } else {
+            "Organized"
+        },
+        organized.len(),
+        total
+    );
+    for report in &organized {
+        result.push_str(&format!("\n{}\n", report));
+    }
+    if !failed.is_empty() {
+        result.push_str("\nFailed:\n");
+        for line in &failed {
+            result.push_str(&format!("  {}\n", line));
+        }
+    }
+
+    Ok(budget::fit_lines(&result))
+}
+
+/// Checks whether a code action kind is `base` or one of its sub-kinds
+pub fn is_kind(kind: &CodeActionKind, base: &str) -> bool {
+    let kind = kind.as_str();
+    kind == base
+        || kind
+            .strip_prefix(base)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+async fn open_existing(client: &Client, file_path: PathBuf) -> Result<PathBuf> {
+    // Get the file's absolute path
+    let file_path = normalize_path(&file_path)?;
+
+    // Ensure the file exists
+    if !file_path.exists() {
+        return Err(anyhow!("File does not exist: {}", file_path.display()));
+    }
+
+    // Ensure the file is open in the LSP server
+    client.open_file(&file_path).await?;
+    Ok(file_path)
+}
+
+/// Requests the code actions for a range, passing along the diagnostics it overlaps
+pub async fn code_actions(
+    client: &Client,
+    file_path: &Path,
+    range: Range,
+    only: Option<&str>,
+) -> Result<Vec<CodeActionOrCommand>> {
+    // The server's own diagnostics, unaltered, so it recognizes the ones it can fix
+    let diagnostics = diagnostics_in_range(&client.get_diagnostics(&to_uri(file_path)), &range);
+
+    let params = CodeActionParams {
+        text_document: to_text_document_identifier(file_path)?,
+        range,
+        context: CodeActionContext {
+            diagnostics,
+            only: only.map(|kind| vec![CodeActionKind::from(kind.to_string())]),
+            trigger_kind: None,
+        },
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+
+    let actions: Option<CodeActionResponse> =
+        client.call("textDocument/codeAction", params).await?;
+    Ok(actions.unwrap_or_default())
+}
+
+/// A server answering for a file: the client itself or one attached to it
+enum Server<'a> {
+    Own(&'a Client),
+    Attached(Arc<Client>),
+}
+
+impl Deref for Server<'_> {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        match self {
+            Self::Own(client) => client,
+            Self::Attached(server) => server,
+        }
+    }
+}
+
+/// Gets the client or attached server going by `name`
+fn server_named<'a>(client: &'a Client, name: &str) -> Server<'a> {
+    if client.name() == name {
+        return Server::Own(client);
+    }
+    client
+        .attached_servers()
+        .into_iter()
+        .find(|server| server.name() == name)
+        .map_or(Server::Own(client), Server::Attached)
+}
+
+/// Requests the code actions for a range from every server that has the file
+/// open, each with the name of the server offering it
+pub async fn all_code_actions(
+    client: &Client,
+    file_path: &Path,
+    range: Range,
+    only: Option<&str>,
+) -> Result<Vec<(String, CodeActionOrCommand)>> {
+    let servers: Vec<Server> = std::iter::once(Server::Own(client))
+        .chain(client.attached_servers().into_iter().map(Server::Attached))
+        .filter(|server| server.is_file_open(file_path))
+        .collect();
+    let results = futures::future::join_all(
+        servers
+            .iter()
+            .map(|server| code_actions(server, file_path, range, only)),
+    )
+    .await;
+
+    let mut answers = Vec::new();
+    let mut first_error = None;
+    for (server, result) in servers.iter().zip(results) {
+        match result {
+            Ok(actions) => answers.push((server.name().to_string(), actions)),
+            Err(e) => {
+                debug!("[TOOL] {} offered no code actions: {}", server.name(), e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    if answers.is_empty()
+        && let Some(e) = first_error
+    {
+        return Err(e);
+    }
+    Ok(merge_code_actions(answers))
+}
+
+/// Merges the code actions several servers offered, keeping an action offered
+/// by more than one under the same title and kind once, from the first
+pub fn merge_code_actions(
+    results: Vec<(String, Vec<CodeActionOrCommand>)>,
+) -> Vec<(String, CodeActionOrCommand)> {
+    let mut merged: Vec<(String, CodeActionOrCommand)> = Vec::new();
+    for (server, actions) in results {
+        for action in actions {
+            if !merged.iter().any(|(_, known)| same_action(known, &action)) {
+                merged.push((server.clone(), action));
+            }
+        }
+    }
+    merged
+}
+
+/// Checks whether two code actions have the same title and kind
+fn same_action(a: &CodeActionOrCommand, b: &CodeActionOrCommand) -> bool {
+    match (a, b) {
+        (CodeActionOrCommand::Command(a), CodeActionOrCommand::Command(b)) => {
+            a.title == b.title && a.command == b.command
+        }
+        (CodeActionOrCommand::CodeAction(a), CodeActionOrCommand::CodeAction(b)) => {
+            a.title == b.title && a.kind == b.kind
+        }
+        _ => false,
+    }
+}
+
+/// Fetches the edit of an action that was listed without one, if the server supports it
+pub async fn resolve_code_action(client: &Client, action: CodeAction) -> CodeAction {
+    let can_resolve = matches!(
+        client
+            .server_capabilities()
+            .and_then(|capabilities| capabilities.code_action_provider),
+        Some(CodeActionProviderCapability::Options(options))
+            if options.resolve_provider == Some(true)
+    );
+    if action.edit.is_some() || !can_resolve {
+        return action;
+    }
+
+    match client
+        .call::<_, CodeAction>("codeAction/resolve", action.clone())
+        .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            debug!(
+                "[TOOL] Failed to resolve code action {}: {}",
+                action.title, e
+            );
+            action
+        }
+    }
+}
+
+/// Selects the diagnostics overlapping a range, which servers use to offer quick fixes
+pub fn diagnostics_in_range(diagnostics: &[Diagnostic], range: &Range) -> Vec<Diagnostic> {
+    diagnostics
+        .iter()
+        .filter(|d| d.range.start <= range.end && range.start <= d.range.end)
+        .cloned()
+        .collect()
+}
+
+/// Formats a numbered code action with its kind and the diagnostics it fixes
+pub fn format_code_action(index: usize, action: &CodeActionOrCommand) -> String {
+    match action {
+        CodeActionOrCommand::Command(command) => {
+            format!(
+                "{}. {} (command: {})",
+                index, command.title, command.command
+            )
+        }
+        CodeActionOrCommand::CodeAction(action) => {
+            let mut line = format!("{}.", index);
+            if let Some(kind) = &action.kind {
+                line.push_str(&format!(" [{}]", kind.as_str()));
+            }
+            line.push_str(&format!(" {}", action.title));
+            if action.is_preferred == Some(true) {
+                line.push_str(" (preferred)");
+            }
+            if let Some(disabled) = &action.disabled {
+                line.push_str(&format!(" (disabled: {})", disabled.reason));
+            }
+            for diagnostic in action.diagnostics.iter().flatten() {
+                line.push_str(&format!(
+                    "\n   fixes line {}: {}",
+                    diagnostic.range.start.line + 1,
+                    diagnostic.message
+                ));
+            }
+            line
+        }
+    }
+}
+
+/// Builds the range a code action request covers from 1-indexed positions.
+/// Without an end the range is the single position.
+pub fn action_range(
+    line: u32,
+    column: u32,
+    end_line: Option<u32>,
+    end_column: Option<u32>,
+) -> Range {
+    let start = Position {
+        line: line.saturating_sub(1),
+        character: column.saturating_sub(1),
+    };
+    let end = match end_line {
+        Some(end_line) => Position {
+            line: end_line.saturating_sub(1),
+            character: end_column.unwrap_or(1).saturating_sub(1),
+        },
+        None => Position {
+            line: start.line,
+            character: end_column.map_or(start.character, |c| c.saturating_sub(1)),
+        },
+    };
+    Range { start, end }
+}
@@ -0,0 +1,154 @@
+use crate::lsp::Client;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Result, anyhow};
+use log::debug;
+use lsp_types::{CodeLens, CodeLensParams};
+use std::path::{Path, PathBuf};
+
+use super::budget;
+use super::execute_command;
+use super::utils::{display_path, to_text_document_identifier};
+
+/// Lists the code lenses of a file, such as "run test" above a test function,
+/// numbered for `run_code_lens`
+pub async fn get_code_lenses(client: &Client, file_path: PathBuf) -> Result<String> {
+    debug!("[TOOL] Getting code lenses for {}", file_path.display());
+
+    let file_path = open_existing(client, file_path).await?;
+    let lenses = code_lenses(client, &file_path).await?;
+
+    if lenses.is_empty() {
+        return Ok(format!(
+            "No code lenses in {}",
+            display_path(client, &file_path)
+        ));
+    }
+
+    let mut result = format!(
+        "Found {} code lenses in {}:\n",
+        lenses.len(),
+        display_path(client, &file_path)
+    );
+    for (i, lens) in lenses.iter().enumerate() {
+        result.push_str(&format_code_lens(i + 1, lens));
+        result.push('\n');
+    }
+    result.push_str("\nRun one with run_code_lens and its number.\n");
+
+    Ok(budget::fit_lines(&result))
+}
+
+/// Runs the command of the code lens numbered `index` in the `code_lenses`
+/// listing for the file, applying the edits it makes
+pub async fn run_code_lens(client: &Client, file_path: PathBuf, index: usize) -> Result<String> {
+    debug!(
+        "[TOOL] Running code lens {} in {}",
+        index,
+        file_path.display()
+    );
+
+    let file_path = open_existing(client, file_path).await?;
+    let lenses = code_lenses(client, &file_path).await?;
+
+    // Lenses are numbered from 1 in the listing
+    let lens = index
+        .checked_sub(1)
+        .and_then(|i| lenses.into_iter().nth(i))
+        .ok_or_else(|| {
+            anyhow!(
+                "No code lens {} in this file; list them with code_lenses first",
+                index
+            )
+        })?;
+    let command = lens
+        .command
+        .ok_or_else(|| anyhow!("Code lens {} has no command to run", index))?;
+
+    // Some lenses run commands the editor implements, such as starting a debugger
+    let offered = client
+        .server_capabilities()
+        .and_then(|capabilities| capabilities.execute_command_provider)
+        .is_some_and(|provider| provider.commands.contains(&command.command));
+    if !offered {
+        return Err(anyhow!(
+            "Code lens '{}' runs '{}', which the editor implements rather than the language server; its arguments are: {}",
+            command.title,
+            command.command,
+            serde_json::to_string(&command.arguments.unwrap_or_default())?
+        ));
+    }
+
+    let arguments = command.arguments.unwrap_or_default();
+    let report = execute_command::run_command(client, &command.command, arguments).await?;
+    Ok(budget::fit_lines(&format!(
+        "Ran code lens '{}'\n{}",
+        command.title, report
+    )))
+}
+
+async fn open_existing(client: &Client, file_path: PathBuf) -> Result<PathBuf> {
+    // Get the file's absolute path
+    let file_path = normalize_path(&file_path)?;
+
+    // Ensure the file exists
+    if !file_path.exists() {
+        return Err(anyhow!("File does not exist: {}", file_path.display()));
+    }
+
+    // Ensure the file is open in the LSP server
+    client.open_file(&file_path).await?;
+    Ok(file_path)
+}
+
+/// Requests the code lenses of a file, resolving the commands of those the
+/// server leaves unresolved
+async fn code_lenses(client: &Client, file_path: &Path) -> Result<Vec<CodeLens>> {
+    let params = CodeLensParams {
+        text_document: to_text_document_identifier(file_path)?,
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+    let lenses: Option<Vec<CodeLens>> = client.call("textDocument/codeLens", params).await?;
+    let mut lenses = lenses.unwrap_or_default();
+    lenses.sort_by_key(|lens| (lens.range.start.line, lens.range.start.character));
+
+    let can_resolve = client
+        .server_capabilities()
+        .and_then(|capabilities| capabilities.code_lens_provider)
+        .and_then(|provider| provider.resolve_provider)
+        .unwrap_or(false);
+    if !can_resolve {
+        return Ok(lenses);
+    }
+
+    let mut resolved = Vec::with_capacity(lenses.len());
+    for lens in lenses {
+        if lens.command.is_some() {
+            resolved.push(lens);
+            continue;
+        }
+        match client
+            .call::<_, CodeLens>("codeLens/resolve", lens.clone())
+            .await
+        {
+            Ok(lens) => resolved.push(lens),
+            Err(e) => {
+                debug!("[TOOL] Failed to resolve code lens: {}", e);
+                resolved.push(lens);
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// Formats a numbered code lens with its line and command
+pub fn format_code_lens(index: usize, lens: &CodeLens) -> String {
+    let line = lens.range.start.line + 1;
+    match &lens.command {
+        Some(command) => format!(
+            "{}. line {}: {} (command: {})",
+            index, line, command.title, command.command
+        ),
+        None => format!("{}. line {}: (unresolved)", index, line),
+    }
+}
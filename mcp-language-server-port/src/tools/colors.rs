@@ -0,0 +1,203 @@
+use crate::lsp::Client;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Result, anyhow};
+use log::debug;
+use lsp_types::{
+    Color, ColorInformation, ColorPresentation, ColorPresentationParams, DocumentColorParams,
+    Position, Range,
+};
+use std::path::{Path, PathBuf};
+
+use super::budget;
+use super::utils::{display_path, range_contains, to_text_document_identifier};
+
+/// Lists the color values in a file with their ranges
+pub async fn get_document_colors(client: &Client, file_path: PathBuf) -> Result<String> {
+    debug!("[TOOL] Getting document colors for {}", file_path.display());
+
+    let file_path = open_existing(client, file_path).await?;
+    let colors = document_colors(client, &file_path).await?;
+
+    if colors.is_empty() {
+        return Ok(format!(
+            "No colors found in {}",
+            display_path(client, &file_path)
+        ));
+    }
+
+    let mut result = format!(
+        "Found {} colors in {}:\n\n",
+        colors.len(),
+        display_path(client, &file_path)
+    );
+    for info in &colors {
+        result.push_str(&format!(
+            "{}  {}\n",
+            format_range(&info.range),
+            format_color(&info.color)
+        ));
+    }
+
+    Ok(budget::fit_lines(&result))
+}
+
+/// Lists the ways the server can write the color at a position, optionally
+/// replacing it with a different color
+pub async fn get_color_presentations(
+    client: &Client,
+    file_path: PathBuf,
+    line: u32,
+    column: u32,
+    color: Option<&str>,
+) -> Result<String> {
+    debug!(
+        "[TOOL] Getting color presentations for {}:{}:{}",
+        file_path.display(),
+        line,
+        column
+    );
+
+    let file_path = open_existing(client, file_path).await?;
+
+    // Adjust from 1-indexed to 0-indexed
+    let position = Position {
+        line: line.saturating_sub(1),
+        character: column.saturating_sub(1),
+    };
+    let point = Range {
+        start: position,
+        end: position,
+    };
+
+    // Presentations are computed for an existing color usage
+    let colors = document_colors(client, &file_path).await?;
+    let info = colors
+        .into_iter()
+        .find(|info| range_contains(&info.range, &point))
+        .ok_or_else(|| anyhow!("No color at {}:{}", line, column))?;
+
+    let color = match color {
+        Some(color) => parse_hex_color(color)?,
+        None => info.color,
+    };
+
+    let params = ColorPresentationParams {
+        text_document: to_text_document_identifier(&file_path)?,
+        color,
+        range: info.range,
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+    let presentations: Vec<ColorPresentation> = client
+        .call("textDocument/colorPresentation", params)
+        .await?;
+
+    if presentations.is_empty() {
+        return Ok(format!(
+            "The server offered no presentations for {}",
+            format_color(&color)
+        ));
+    }
+
+    let mut result = format!(
+        "Presentations of {} at {}:\n\n",
+        format_color(&color),
+        format_range(&info.range)
+    );
+    for presentation in &presentations {
+        result.push_str(&format!("- {}", presentation.label));
+        // Some servers write a different text than the label shows
+        if let Some(edit) = presentation
+            .text_edit
+            .as_ref()
+            .filter(|edit| edit.new_text != presentation.label)
+        {
+            result.push_str(&format!(" (inserts `{}`)", edit.new_text));
+        }
+        result.push('\n');
+    }
+
+    Ok(result)
+}
+
+async fn open_existing(client: &Client, file_path: PathBuf) -> Result<PathBuf> {
+    // Get the file's absolute path
+    let file_path = normalize_path(&file_path)?;
+
+    // Ensure the file exists
+    if !file_path.exists() {
+        return Err(anyhow!("File does not exist: {}", file_path.display()));
+    }
+
+    // Ensure the file is open in the LSP server
+    client.open_file(&file_path).await?;
+    Ok(file_path)
+}
+
+async fn document_colors(client: &Client, file_path: &Path) -> Result<Vec<ColorInformation>> {
+    let params = DocumentColorParams {
+        text_document: to_text_document_identifier(file_path)?,
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+    let mut colors: Vec<ColorInformation> =
+        client.call("textDocument/documentColor", params).await?;
+    colors.sort_by_key(|info| info.range.start);
+    Ok(colors)
+}
+
+/// Formats a range as 1-indexed `line:column-line:column`
+fn format_range(range: &Range) -> String {
+    format!(
+        "{}:{}-{}:{}",
+        range.start.line + 1,
+        range.start.character + 1,
+        range.end.line + 1,
+        range.end.character + 1
+    )
+}
+
+fn channel(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Formats a color as `#rrggbb`, or `#rrggbbaa` when it isn't opaque
+pub fn format_color(color: &Color) -> String {
+    let mut hex = format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.red),
+        channel(color.green),
+        channel(color.blue)
+    );
+    if channel(color.alpha) != 255 {
+        hex.push_str(&format!("{:02x}", channel(color.alpha)));
+    }
+    hex
+}
+
+/// Parses a `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa` color
+pub fn parse_hex_color(text: &str) -> Result<Color> {
+    let digits = text.trim().trim_start_matches('#');
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow!("Invalid hex color: {}", text));
+    }
+
+    // Expand the short forms so every channel has two digits
+    let digits: String = match digits.len() {
+        3 | 4 => digits.chars().flat_map(|c| [c, c]).collect(),
+        6 | 8 => digits.to_string(),
+        _ => return Err(anyhow!("Invalid hex color: {}", text)),
+    };
+
+    let channels: Vec<f32> = (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap() as f32 / 255.0)
+        .collect();
+
+    Ok(Color {
+        red: channels[0],
+        green: channels[1],
+        blue: channels[2],
+        alpha: channels.get(3).copied().unwrap_or(1.0),
+    })
+}
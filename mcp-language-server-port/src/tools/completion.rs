@@ -0,0 +1,200 @@
+use crate::lsp::Client;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Result, anyhow};
+use log::debug;
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionParams, CompletionResponse, Documentation,
+    Position, TextDocumentPositionParams,
+};
+use std::path::PathBuf;
+
+use super::budget;
+use super::utils::to_text_document_identifier;
+
+/// Longest documentation excerpt shown per item
+const MAX_DOC_LENGTH: usize = 200;
+
+/// Lists completions at a position
+pub async fn get_completions(
+    client: &Client,
+    file_path: PathBuf,
+    line: u32,
+    column: u32,
+    max_items: usize,
+    prefix: Option<&str>,
+    resolve: bool,
+) -> Result<String> {
+    debug!(
+        "[TOOL] Getting completions for {}:{}:{}",
+        file_path.display(),
+        line,
+        column
+    );
+
+    // Get the file's absolute path
+    let file_path = normalize_path(&file_path)?;
+
+    // Ensure the file exists
+    if !file_path.exists() {
+        return Err(anyhow!("File does not exist: {}", file_path.display()));
+    }
+
+    // Ensure the file is open in the LSP server
+    client.open_file(&file_path).await?;
+
+    // Create position params (adjust from 1-indexed to 0-indexed)
+    let params = CompletionParams {
+        text_document_position: TextDocumentPositionParams {
+            text_document: to_text_document_identifier(&file_path)?,
+            position: Position {
+                line: line.saturating_sub(1),
+                character: column.saturating_sub(1),
+            },
+        },
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+        context: None,
+    };
+
+    let response: Option<CompletionResponse> =
+        client.call("textDocument/completion", params).await?;
+    let (items, incomplete) = match response {
+        Some(CompletionResponse::Array(items)) => (items, false),
+        Some(CompletionResponse::List(list)) => (list.items, list.is_incomplete),
+        None => (Vec::new(), false),
+    };
+
+    let total = items.len();
+    let mut items = select_items(items, prefix, max_items);
+    if items.is_empty() {
+        return Ok("No completions available at this position.".to_string());
+    }
+
+    // Servers often leave documentation out until an item is resolved
+    let can_resolve = client
+        .server_capabilities()
+        .and_then(|capabilities| capabilities.completion_provider)
+        .and_then(|provider| provider.resolve_provider)
+        .unwrap_or(false);
+    if resolve && can_resolve {
+        for item in items.iter_mut() {
+            if item.documentation.is_some() && item.detail.is_some() {
+                continue;
+            }
+            match client
+                .call::<_, CompletionItem>("completionItem/resolve", item.clone())
+                .await
+            {
+                Ok(resolved) => *item = resolved,
+                Err(e) => debug!("[TOOL] Failed to resolve completion {}: {}", item.label, e),
+            }
+        }
+    }
+
+    let mut result = format!("Showing {} of {} completions:\n", items.len(), total);
+    for item in &items {
+        result.push_str(&format_completion_item(item));
+        result.push('\n');
+    }
+    if incomplete {
+        result.push_str(
+            "(The server reported an incomplete list; type more of the name to narrow it)\n",
+        );
+    }
+
+    Ok(budget::fit_lines(&result))
+}
+
+/// Keeps the items whose filter text starts with `prefix` (case-insensitively),
+/// in the server's sort order, up to `max_items`
+pub fn select_items(
+    items: Vec<CompletionItem>,
+    prefix: Option<&str>,
+    max_items: usize,
+) -> Vec<CompletionItem> {
+    let prefix = prefix.map(str::to_lowercase);
+    let mut items: Vec<CompletionItem> = items
+        .into_iter()
+        .filter(|item| {
+            let text = item.filter_text.as_deref().unwrap_or(&item.label);
+            prefix
+                .as_deref()
+                .is_none_or(|prefix| text.to_lowercase().starts_with(prefix))
+        })
+        .collect();
+
+    // Servers sort by sort_text, falling back to the label
+    items.sort_by(|a, b| {
+        let a_key = a.sort_text.as_deref().unwrap_or(&a.label);
+        let b_key = b.sort_text.as_deref().unwrap_or(&b.label);
+        a_key.cmp(b_key)
+    });
+    items.truncate(max_items);
+    items
+}
+
+/// Formats a completion item on one line: label, kind, detail and the start
+/// of its documentation
+pub fn format_completion_item(item: &CompletionItem) -> String {
+    let mut line = item.label.clone();
+    if let Some(kind) = item.kind {
+        line.push_str(&format!(" ({})", completion_kind_name(kind)));
+    }
+    if let Some(detail) = item.detail.as_deref().filter(|d| !d.is_empty()) {
+        line.push_str(&format!(" — {}", detail.trim()));
+    }
+
+    let documentation = match &item.documentation {
+        Some(Documentation::String(text)) => text.as_str(),
+        Some(Documentation::MarkupContent(markup)) => markup.value.as_str(),
+        None => "",
+    };
+    // Only the first paragraph, flattened onto the same line
+    let first_paragraph = documentation
+        .split("\n\n")
+        .map(|p| p.split_whitespace().collect::<Vec<_>>().join(" "))
+        .find(|p| !p.is_empty() && !p.starts_with("```"));
+    if let Some(paragraph) = first_paragraph {
+        let excerpt: String = paragraph.chars().take(MAX_DOC_LENGTH).collect();
+        let ellipsis = if excerpt.len() < paragraph.len() {
+            "…"
+        } else {
+            ""
+        };
+        line.push_str(&format!(": {}{}", excerpt, ellipsis));
+    }
+
+    line
+}
+
+/// Gets a readable name for a completion item kind
+pub fn completion_kind_name(kind: CompletionItemKind) -> &'static str {
+    match kind {
+        CompletionItemKind::TEXT => "Text",
+        CompletionItemKind::METHOD => "Method",
+        CompletionItemKind::FUNCTION => "Function",
+        CompletionItemKind::CONSTRUCTOR => "Constructor",
+        CompletionItemKind::FIELD => "Field",
+        CompletionItemKind::VARIABLE => "Variable",
+        CompletionItemKind::CLASS => "Class",
+        CompletionItemKind::INTERFACE => "Interface",
+        CompletionItemKind::MODULE => "Module",
+        CompletionItemKind::PROPERTY => "Property",
+        CompletionItemKind::UNIT => "Unit",
+        CompletionItemKind::VALUE => "Value",
+        CompletionItemKind::ENUM => "Enum",
+        CompletionItemKind::KEYWORD => "Keyword",
+        CompletionItemKind::SNIPPET => "Snippet",
+        CompletionItemKind::COLOR => "Color",
+        CompletionItemKind::FILE => "File",
+        CompletionItemKind::REFERENCE => "Reference",
+        CompletionItemKind::FOLDER => "Folder",
+        CompletionItemKind::ENUM_MEMBER => "EnumMember",
+        CompletionItemKind::CONSTANT => "Constant",
+        CompletionItemKind::STRUCT => "Struct",
+        CompletionItemKind::EVENT => "Event",
+        CompletionItemKind::OPERATOR => "Operator",
+        CompletionItemKind::TYPE_PARAMETER => "TypeParameter",
+        _ => "Other",
+    }
+}
@@ -1,15 +1,26 @@
 use crate::lsp::Client;
 use anyhow::{Context, Result, anyhow};
 use log::{debug, error};
-use lsp_types::{Location, Position, TextDocumentPositionParams};
+use lsp_types::{Location, LocationLink, Position, TextDocumentPositionParams};
 use serde_json::Value;
 use std::path::PathBuf;
 use tokio::fs;
 
-use super::utils::{format_code, get_language_from_path, to_path, to_text_document_identifier};
-
-/// Finds the definition of a symbol in a file
-pub async fn find_definition(client: &Client, symbol_name: &str) -> Result<String> {
+use super::budget::{self, Block};
+use super::render::{self, OutputFormat};
+use super::structured::{LocationResult, to_json};
+use super::utils::{
+    context_window, display_path, format_code, format_numbered_line, format_servers,
+    get_language_from_path, merge_server_locations, to_path, to_text_document_identifier,
+};
+
+/// Finds the definition of a symbol in a file. With `context_lines`, that
+/// many lines around the definition are shown too, numbered.
+pub async fn find_definition(
+    client: &Client,
+    symbol_name: &str,
+    context_lines: u32,
+) -> Result<String> {
     debug!("[TOOL] Finding definition for symbol: {}", symbol_name);
 
     // We need to first find a file where the symbol is used
@@ -30,22 +41,45 @@ pub async fn find_definition(client: &Client, symbol_name: &str) -> Result<Strin
         },
     };
 
-    // Call the LSP definition request
-    let definition: Value = client
-        .call("textDocument/definition", position_params)
-        .await?;
+    // Ask every server that handles the file, so a definition in another
+    // language (a proto message used from Go, say) is found by the server for it
+    let results = client
+        .call_for_file::<_, Value>(&file_path, "textDocument/definition", position_params)
+        .await;
+
+    let mut answers = Vec::new();
+    let mut first_error = None;
+    for (server, result) in results {
+        // Parse the result (could be a Location or an array of Locations)
+        match result.and_then(parse_definition_result) {
+            Ok(locations) => answers.push((server, locations)),
+            Err(e) => {
+                debug!("[TOOL] {} found no definition: {}", server, e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    if answers.is_empty() {
+        return Err(first_error
+            .unwrap_or_else(|| anyhow!("No language server has {} open", file_path.display())));
+    }
 
-    // Parse the result (could be a Location or an array of Locations)
-    let locations = parse_definition_result(definition)?;
+    // Only attribute results when more than one server answered
+    let attribute = answers.len() > 1;
+    let locations = merge_server_locations(answers);
 
     if locations.is_empty() {
         return Err(anyhow!("Definition not found for symbol: {}", symbol_name));
     }
 
+    if render::current_format() == OutputFormat::Json {
+        return to_json(&location_results(&locations, attribute)?);
+    }
+
     // For each location, get the content
-    let mut result = String::new();
+    let mut blocks = Vec::new();
 
-    for location in &locations {
+    for (location, servers) in &locations {
         let file_path = to_path(&location.uri)?;
 
         // Read the file content
@@ -60,10 +94,16 @@ pub async fn find_definition(client: &Client, symbol_name: &str) -> Result<Strin
 
         // Get the code snippet
         let mut code_snippet = String::new();
-        for i in start_line..=end_line {
-            if i < lines.len() {
-                code_snippet.push_str(lines[i]);
-                code_snippet.push('\n');
+        if context_lines > 0 && !lines.is_empty() {
+            for i in context_window(lines.len(), start_line, end_line, context_lines) {
+                code_snippet.push_str(&format_numbered_line(&lines, i));
+            }
+        } else {
+            for i in start_line..=end_line {
+                if i < lines.len() {
+                    code_snippet.push_str(lines[i]);
+                    code_snippet.push('\n');
+                }
             }
         }
 
@@ -71,16 +111,40 @@ pub async fn find_definition(client: &Client, symbol_name: &str) -> Result<Strin
         let language = get_language_from_path(&file_path);
         let formatted_code = format_code(&code_snippet, language);
 
-        result.push_str(&format!(
-            "Definition found in {}:{}:{}\n\n{}\n\n",
-            file_path.display(),
+        let mut block = Block::new(format!(
+            "Definition found in {}:{}:{}{}\n\n",
+            display_path(client, &file_path),
             start_line + 1, // 1-indexed for display
             location.range.start.character + 1,
-            formatted_code
+            if attribute {
+                format_servers(servers)
+            } else {
+                String::new()
+            },
         ));
+        block.snippet = Some(format!("{}\n\n", formatted_code));
+        blocks.push(block);
     }
 
-    Ok(result)
+    Ok(budget::fit("", blocks, ""))
+}
+
+/// Converts merged locations to typed results, naming the servers behind
+/// each when `attribute` is set
+pub fn location_results(
+    locations: &[(Location, Vec<String>)],
+    attribute: bool,
+) -> Result<Vec<LocationResult>> {
+    locations
+        .iter()
+        .map(|(location, servers)| {
+            let mut result = LocationResult::from_location(location)?;
+            if attribute {
+                result.servers = servers.clone();
+            }
+            Ok(result)
+        })
+        .collect()
 }
 
 /// Parse a symbol location string in the format "path:line:column" or just "symbol"
@@ -111,14 +175,15 @@ pub fn parse_symbol_location(symbol_location: &str) -> Result<(PathBuf, u32, u32
     }
 }
 
-/// Parse the LSP definition result into a list of Locations
-fn parse_definition_result(value: Value) -> Result<Vec<Location>> {
+/// Parse the LSP definition result into a list of Locations. Servers answer
+/// with a Location, a list of Locations or a list of LocationLinks.
+pub fn parse_definition_result(value: Value) -> Result<Vec<Location>> {
     match value {
         Value::Array(array) => {
             let mut locations = Vec::new();
 
             for item in array {
-                match serde_json::from_value::<Location>(item) {
+                match parse_location(item) {
                     Ok(location) => locations.push(location),
                     Err(e) => error!("[TOOL] Failed to parse location: {}", e),
                 }
@@ -128,11 +193,35 @@ fn parse_definition_result(value: Value) -> Result<Vec<Location>> {
         }
         Value::Object(_) => {
             // Single location
-            match serde_json::from_value::<Location>(value) {
+            match parse_location(value) {
                 Ok(location) => Ok(vec![location]),
                 Err(e) => Err(anyhow!("Failed to parse location: {}", e)),
             }
         }
+        // No definition
+        Value::Null => Ok(Vec::new()),
         _ => Err(anyhow!("Unexpected definition result format")),
     }
 }
+
+/// Parses a Location or a LocationLink
+fn parse_location(item: Value) -> Result<Location> {
+    if item.get("targetUri").is_none() {
+        return Ok(serde_json::from_value::<Location>(item)?);
+    }
+
+    // The target range spans the whole definition, which is what gets shown;
+    // servers that send a target range not covering the name get the name only
+    let link = serde_json::from_value::<LocationLink>(item)?;
+    let selection = link.target_selection_range;
+    let range =
+        if link.target_range.start <= selection.start && selection.end <= link.target_range.end {
+            link.target_range
+        } else {
+            selection
+        };
+    Ok(Location {
+        uri: link.target_uri,
+        range,
+    })
+}
@@ -1,18 +1,31 @@
 use crate::lsp::Client;
+use crate::lsp::position::char_column;
+use crate::workspace::paths::normalize_path;
 use anyhow::{Context, Result, anyhow};
 use log::debug;
-use lsp_types::DiagnosticSeverity;
+use lsp_types::{Diagnostic, DiagnosticSeverity, DocumentSymbolParams, DocumentSymbolResponse};
 use std::path::PathBuf;
+use std::time::Duration;
 use tokio::fs;
 
-use super::utils::to_uri;
+use super::budget::{self, Block};
+use super::references::{ReferenceScope, enclosing_symbol_range};
+use super::render::{self, OutputFormat};
+use super::structured::{DiagnosticResult, to_json};
+use super::utils::{display_path, to_path, to_uri};
 
-/// Gets diagnostic information for a file
+/// Line prefixes treated as comments or attributes above a function signature
+const COMMENT_PREFIXES: &[&str] = &["//", "/*", "*", "#", "--", "@"];
+
+/// Gets diagnostic information for a file. With a `wait`, a file the server
+/// has not reported on yet gets up to that long to be analyzed.
 pub async fn get_diagnostics(
     client: &Client,
     file_path: PathBuf,
     context_lines: u32,
     show_line_numbers: bool,
+    include_enclosing: bool,
+    wait: Duration,
 ) -> Result<String> {
     debug!(
         "[TOOL] Getting diagnostics for file: {}",
@@ -20,63 +33,115 @@ pub async fn get_diagnostics(
     );
 
     // Get the file's absolute path
-    let file_path = file_path.canonicalize().context(format!(
-        "Failed to canonicalize path: {}",
-        file_path.display()
-    ))?;
+    let file_path = normalize_path(&file_path)?;
 
     // Ensure the file exists
     if !file_path.exists() {
         return Err(anyhow!("File does not exist: {}", file_path.display()));
     }
 
-    // Ensure the file is open in the LSP server
-    client.open_file(&file_path).await?;
-
     // Get the URI of the file
     let uri = to_uri(&file_path);
 
-    // Get diagnostics for the file
-    let diagnostics = client.get_diagnostics(&uri);
+    // Ensure the file is open in the LSP server, noting what it had reported
+    // before so a publication racing the open isn't missed
+    let was_open = client.is_file_open(&file_path);
+    let publication = client.diagnostics_publication(&uri);
+    client.open_file(&file_path).await?;
+
+    // Servers using the pull model report when asked rather than pushing
+    if let Err(e) = client.pull_diagnostics(&uri).await {
+        debug!("[TOOL] Could not pull diagnostics: {}", e);
+    }
+
+    // Servers pushing diagnostics report on a file just opened once they've analyzed it
+    let pushes = client.pull_diagnostics_support().is_none();
+    if pushes
+        && !wait.is_zero()
+        && (!was_open || publication.is_none())
+        && !client.wait_for_diagnostics(&uri, publication, wait).await
+    {
+        debug!(
+            "[TOOL] No diagnostics published for {} within {:?}",
+            file_path.display(),
+            wait
+        );
+    }
+
+    // Get diagnostics for the file from every server that checks it
+    let diagnostics = client.get_all_diagnostics(&uri);
+
+    if render::current_format() == OutputFormat::Json {
+        let results: Vec<DiagnosticResult> = diagnostics
+            .iter()
+            .map(|diagnostic| DiagnosticResult::new(&file_path, diagnostic))
+            .collect();
+        return to_json(&results);
+    }
 
     if diagnostics.is_empty() {
-        return Ok(format!("No diagnostics found for {}", file_path.display()));
+        return Ok(format!(
+            "No diagnostics found for {}",
+            display_path(client, &file_path)
+        ));
     }
 
-    // Read the file content
-    let content = fs::read_to_string(&file_path)
-        .await
-        .context(format!("Failed to read file: {}", file_path.display()))?;
+    // Read the content the server checked, which differs from disk under an overlay
+    let content = match client.overlay(&file_path) {
+        Some(content) => content,
+        None => fs::read_to_string(&file_path)
+            .await
+            .context(format!("Failed to read file: {}", file_path.display()))?,
+    };
 
     // Split the content into lines
     let lines: Vec<&str> = content.lines().collect();
 
-    // Format the diagnostics
-    let mut result = String::new();
+    // The outline lets each diagnostic show the function it sits in
+    let symbols = if include_enclosing {
+        document_symbols(client, &uri).await
+    } else {
+        None
+    };
 
-    result.push_str(&format!("Diagnostics for {}:\n\n", file_path.display()));
+    // Format the diagnostics
+    let mut preamble = format!("Diagnostics for {}:\n\n", display_path(client, &file_path));
 
-    for (i, diagnostic) in diagnostics.iter().enumerate() {
-        // Add a separator between diagnostics
-        if i > 0 {
-            result.push_str("\n---\n\n");
-        }
+    // Diagnostics computed for an earlier version may not match the content anymore
+    let open_version = client
+        .open_documents()
+        .into_iter()
+        .find(|document| document.uri == uri)
+        .map(|document| document.version);
+    if let (Some(version), Some(open_version)) = (client.diagnostics_version(&uri), open_version)
+        && version < open_version
+    {
+        preamble.push_str(&format!(
+            "Note: these diagnostics are for version {} of the file; the server has version {} and has not reported on it yet.\n\n",
+            version, open_version
+        ));
+    }
+    let encoding = client.position_encoding();
+    let mut blocks = Vec::new();
 
+    for diagnostic in &diagnostics {
         // Get the severity as a string
-        let severity_str = match diagnostic.severity {
-            Some(DiagnosticSeverity::ERROR) => "Error",
-            Some(DiagnosticSeverity::WARNING) => "Warning",
-            Some(DiagnosticSeverity::INFORMATION) => "Info",
-            Some(DiagnosticSeverity::HINT) => "Hint",
-            None => "Unknown",
-            _ => "Unknown", // Handle any other values
-        };
+        let severity_str = severity_name(diagnostic.severity);
 
         // Format the diagnostic
-        result.push_str(&format!("{}: {}\n", severity_str, diagnostic.message));
+        let mut block = Block::new(match &diagnostic.source {
+            Some(source) => format!("{} [{}]: {}\n", severity_str, source, diagnostic.message),
+            None => format!("{}: {}\n", severity_str, diagnostic.message),
+        });
 
         // Get the range of the diagnostic
         let range = &diagnostic.range;
+        block.summary = Some(format!(
+            "{}:{}: {}",
+            range.start.line + 1,
+            range.start.character + 1,
+            block.header.trim_end()
+        ));
         let start_line = range.start.line as usize;
         let end_line = range.end.line as usize;
 
@@ -85,30 +150,60 @@ pub async fn get_diagnostics(
         let context_end = std::cmp::min(end_line + context_lines as usize, lines.len() - 1);
 
         // Add code context
-        result.push_str("\nCode context:\n");
+        block.context.push("\nCode context:\n".to_string());
+
+        let format_line = |line_num: usize| {
+            if show_line_numbers {
+                format!("{:5} | {}\n", line_num + 1, lines[line_num])
+            } else {
+                format!("{}\n", lines[line_num])
+            }
+        };
+
+        // Extend the window up to the enclosing function's signature and doc comment
+        let mut context_start = context_start;
+        let enclosing = symbols.as_ref().and_then(|symbols| {
+            enclosing_symbol_range(
+                symbols,
+                range.start,
+                ReferenceScope::Function.symbol_kinds(),
+            )
+        });
+        if let Some(enclosing) = enclosing {
+            let (header_start, signature) = enclosing_header(&lines, enclosing.start.line as usize);
+            if signature + 1 >= context_start {
+                context_start = context_start.min(header_start);
+            } else {
+                for line_num in header_start..=signature {
+                    block.context.push(format_line(line_num));
+                }
+                let gap = if show_line_numbers {
+                    "      | ...\n"
+                } else {
+                    "...\n"
+                };
+                block.context.push(gap.to_string());
+            }
+        }
 
         for line_num in context_start..=context_end {
             if line_num < lines.len() {
                 let line_content = lines[line_num];
 
-                // Add line number if requested
-                if show_line_numbers {
-                    result.push_str(&format!("{:5} | {}\n", line_num + 1, line_content));
-                } else {
-                    result.push_str(&format!("{}\n", line_content));
-                }
+                // Add the line, numbered if requested
+                block.context.push(format_line(line_num));
 
                 // Add a pointer to the exact position if this is the error line
                 if line_num >= start_line && line_num <= end_line {
                     let start_char = if line_num == start_line {
-                        range.start.character as usize
+                        char_column(line_content, range.start.character, encoding)
                     } else {
                         0
                     };
                     let end_char = if line_num == end_line {
-                        range.end.character as usize
+                        char_column(line_content, range.end.character, encoding)
                     } else {
-                        line_content.len()
+                        line_content.chars().count()
                     };
 
                     // Create the pointer line
@@ -120,11 +215,147 @@ pub async fn get_diagnostics(
                         "^".repeat(end_char.saturating_sub(start_char).max(1))
                     );
 
-                    result.push_str(&pointer);
+                    block.context.push(pointer);
                 }
             }
         }
+
+        blocks.push(block);
     }
 
-    Ok(result)
+    Ok(budget::fit(&preamble, blocks, "\n---\n\n"))
+}
+
+/// Lists the diagnostics of every file in the workspace, for servers offering
+/// project-wide results through `workspace/diagnostic`
+pub async fn get_workspace_diagnostics(client: &Client) -> Result<String> {
+    debug!("[TOOL] Getting workspace diagnostics");
+
+    let mut uris = client.pull_workspace_diagnostics().await?.ok_or_else(|| {
+        anyhow!(
+            "{} does not report workspace diagnostics; get diagnostics for individual files instead",
+            client.name()
+        )
+    })?;
+    uris.sort();
+    uris.dedup();
+
+    if render::current_format() == OutputFormat::Json {
+        let mut results = Vec::new();
+        for uri in &uris {
+            let Ok(path) = to_path(uri) else {
+                continue;
+            };
+            results.extend(
+                client
+                    .get_all_diagnostics(uri)
+                    .iter()
+                    .map(|diagnostic| DiagnosticResult::new(&path, diagnostic)),
+            );
+        }
+        return to_json(&results);
+    }
+
+    let mut total = 0;
+    let mut blocks = Vec::new();
+    for uri in uris {
+        let diagnostics = client.get_all_diagnostics(&uri);
+        if diagnostics.is_empty() {
+            continue;
+        }
+        total += diagnostics.len();
+
+        let path = match to_path(&uri) {
+            Ok(path) => display_path(client, &path),
+            Err(_) => uri.to_string(),
+        };
+        let mut block = Block::new(format!("File: {} ({})\n", path, diagnostics.len()));
+        let mut summary = String::new();
+        for diagnostic in &diagnostics {
+            let line = format_workspace_diagnostic(diagnostic);
+            summary.push_str(&format!("{}:{}\n", path, line.trim_start()));
+            block.context.push(format!("{}\n", line));
+        }
+        block.summary = Some(summary);
+        blocks.push(block);
+    }
+
+    if blocks.is_empty() {
+        return Ok("No diagnostics found in the workspace".to_string());
+    }
+
+    let preamble = format!("Found {} diagnostics in {} files:\n\n", total, blocks.len());
+    Ok(budget::fit(&preamble, blocks, "\n"))
+}
+
+/// Formats a diagnostic as one indented `line:column: Severity [source]: message` line
+pub fn format_workspace_diagnostic(diagnostic: &Diagnostic) -> String {
+    let start = diagnostic.range.start;
+    let mut line = format!(
+        "  {}:{}: {}",
+        start.line + 1,
+        start.character + 1,
+        severity_name(diagnostic.severity)
+    );
+    if let Some(source) = &diagnostic.source {
+        line.push_str(&format!(" [{}]", source));
+    }
+    // Only the first line of multi-line messages fits on the line
+    let message = diagnostic.message.lines().next().unwrap_or_default();
+    line.push_str(&format!(": {}", message));
+    line
+}
+
+/// Names a diagnostic severity as shown in tool output
+pub fn severity_name(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "Error",
+        Some(DiagnosticSeverity::WARNING) => "Warning",
+        Some(DiagnosticSeverity::INFORMATION) => "Info",
+        Some(DiagnosticSeverity::HINT) => "Hint",
+        _ => "Unknown",
+    }
+}
+
+/// Gets the document outline, or `None` if the server can't provide one
+async fn document_symbols(client: &Client, uri: &lsp_types::Url) -> Option<DocumentSymbolResponse> {
+    let params = DocumentSymbolParams {
+        text_document: lsp_types::TextDocumentIdentifier { uri: uri.clone() },
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+
+    match client.call("textDocument/documentSymbol", params).await {
+        Ok(symbols) => symbols,
+        Err(e) => {
+            debug!("[TOOL] No enclosing context for diagnostics: {}", e);
+            None
+        }
+    }
+}
+
+/// Finds the lines that introduce a symbol whose range starts at `start`: the
+/// doc comment and attributes above it, and its signature line.
+///
+/// Returns the first line of the comment block and the signature line. Servers
+/// differ on whether a symbol's range includes its doc comment, so comments are
+/// skipped in both directions.
+pub fn enclosing_header(lines: &[&str], start: usize) -> (usize, usize) {
+    let is_comment = |line: &str| {
+        let line = line.trim_start();
+        COMMENT_PREFIXES
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+    };
+
+    let mut header_start = start.min(lines.len().saturating_sub(1));
+    while header_start > 0 && is_comment(lines[header_start - 1]) {
+        header_start -= 1;
+    }
+
+    let signature = (start..lines.len())
+        .find(|&line| !is_comment(lines[line]))
+        .unwrap_or(start);
+
+    (header_start, signature)
 }
@@ -0,0 +1,156 @@
+use crate::lsp::Client;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Result, anyhow};
+use log::debug;
+use lsp_types::{DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse, Range, SymbolKind};
+use std::path::PathBuf;
+
+use super::budget;
+use super::utils::{display_path, range_contains, to_text_document_identifier};
+
+/// Renders a hierarchical outline of the symbols in a file
+pub async fn get_document_symbols(client: &Client, file_path: PathBuf) -> Result<String> {
+    debug!(
+        "[TOOL] Getting document symbols for {}",
+        file_path.display()
+    );
+
+    // Get the file's absolute path
+    let file_path = normalize_path(&file_path)?;
+
+    // Ensure the file exists
+    if !file_path.exists() {
+        return Err(anyhow!("File does not exist: {}", file_path.display()));
+    }
+
+    // Ensure the file is open in the LSP server
+    client.open_file(&file_path).await?;
+
+    let params = DocumentSymbolParams {
+        text_document: to_text_document_identifier(&file_path)?,
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+    let symbols: Option<DocumentSymbolResponse> =
+        client.call("textDocument/documentSymbol", params).await?;
+
+    let outline = symbols.map(|symbols| render_outline(&symbols));
+    match outline {
+        Some(outline) if !outline.is_empty() => Ok(budget::fit_lines(&format!(
+            "Outline of {}:\n\n{}",
+            display_path(client, &file_path),
+            outline
+        ))),
+        _ => Ok(format!(
+            "No symbols found in {}",
+            display_path(client, &file_path)
+        )),
+    }
+}
+
+/// Renders symbols as an indented outline, one symbol per line
+pub fn render_outline(symbols: &DocumentSymbolResponse) -> String {
+    let mut outline = String::new();
+
+    match symbols {
+        DocumentSymbolResponse::Nested(symbols) => {
+            for symbol in symbols {
+                render_nested(symbol, 0, &mut outline);
+            }
+        }
+        DocumentSymbolResponse::Flat(symbols) => {
+            // Flat symbols carry no hierarchy, so nest them by their ranges
+            let mut symbols: Vec<_> = symbols.iter().collect();
+            symbols.sort_by_key(|symbol| symbol.location.range.start);
+            let mut parents: Vec<Range> = Vec::new();
+
+            for symbol in symbols {
+                let range = symbol.location.range;
+                while parents
+                    .last()
+                    .is_some_and(|parent| !range_contains(parent, &range))
+                {
+                    parents.pop();
+                }
+                outline.push_str(&format_symbol(
+                    symbol.kind,
+                    &symbol.name,
+                    None,
+                    &range,
+                    parents.len(),
+                ));
+                parents.push(range);
+            }
+        }
+    }
+
+    outline
+}
+
+fn render_nested(symbol: &DocumentSymbol, depth: usize, outline: &mut String) {
+    outline.push_str(&format_symbol(
+        symbol.kind,
+        &symbol.name,
+        symbol.detail.as_deref(),
+        &symbol.range,
+        depth,
+    ));
+    for child in symbol.children.iter().flatten() {
+        render_nested(child, depth + 1, outline);
+    }
+}
+
+fn format_symbol(
+    kind: SymbolKind,
+    name: &str,
+    detail: Option<&str>,
+    range: &Range,
+    depth: usize,
+) -> String {
+    let mut line = format!("{}{} {}", "  ".repeat(depth), symbol_kind_name(kind), name);
+    if let Some(detail) = detail.filter(|detail| !detail.is_empty()) {
+        line.push_str(&format!(": {}", detail));
+    }
+
+    // Line numbers are 1-indexed like the rest of the tool output
+    let (start, end) = (range.start.line + 1, range.end.line + 1);
+    if start == end {
+        line.push_str(&format!(" (line {})\n", start));
+    } else {
+        line.push_str(&format!(" (lines {}-{})\n", start, end));
+    }
+    line
+}
+
+/// Gets a readable name for a symbol kind
+pub fn symbol_kind_name(kind: SymbolKind) -> &'static str {
+    match kind {
+        SymbolKind::FILE => "File",
+        SymbolKind::MODULE => "Module",
+        SymbolKind::NAMESPACE => "Namespace",
+        SymbolKind::PACKAGE => "Package",
+        SymbolKind::CLASS => "Class",
+        SymbolKind::METHOD => "Method",
+        SymbolKind::PROPERTY => "Property",
+        SymbolKind::FIELD => "Field",
+        SymbolKind::CONSTRUCTOR => "Constructor",
+        SymbolKind::ENUM => "Enum",
+        SymbolKind::INTERFACE => "Interface",
+        SymbolKind::FUNCTION => "Function",
+        SymbolKind::VARIABLE => "Variable",
+        SymbolKind::CONSTANT => "Constant",
+        SymbolKind::STRING => "String",
+        SymbolKind::NUMBER => "Number",
+        SymbolKind::BOOLEAN => "Boolean",
+        SymbolKind::ARRAY => "Array",
+        SymbolKind::OBJECT => "Object",
+        SymbolKind::KEY => "Key",
+        SymbolKind::NULL => "Null",
+        SymbolKind::ENUM_MEMBER => "EnumMember",
+        SymbolKind::STRUCT => "Struct",
+        SymbolKind::EVENT => "Event",
+        SymbolKind::OPERATOR => "Operator",
+        SymbolKind::TYPE_PARAMETER => "TypeParameter",
+        _ => "Symbol",
+    }
+}
@@ -1,12 +1,21 @@
-use crate::lsp::Client;
+use crate::lsp::position::{PositionEncoding, column, offset_at};
+use crate::lsp::{Client, ProxyEvent};
+use crate::workspace::editorconfig::EditorConfig;
+use crate::workspace::paths::normalize_path;
 use anyhow::{Context, Result, anyhow};
 use log::debug;
 use lsp_types::TextEdit;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 
-/// Parameters for a text edit operation
+use super::diff::unified_diff;
+use super::render::{self, OutputFormat};
+use super::structured::{EditsApplied, FileEdits, to_json};
+use super::utils::{display_path, to_uri};
+
+/// Parameters for a text edit operation. Without columns the edit replaces
+/// whole lines; with them it replaces the text between the two positions.
 #[derive(Debug, Clone, Deserialize, Serialize, schemars::JsonSchema)]
 pub struct TextEditParams {
     #[schemars(description = "Start line to replace (1-indexed)")]
@@ -15,15 +24,29 @@ pub struct TextEditParams {
     #[schemars(description = "End line to replace (1-indexed)")]
     pub end_line: u32,
 
+    #[schemars(
+        description = "Column on the start line where the replacement begins (1-indexed); set together with end_column to replace part of a line"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_column: Option<u32>,
+
+    #[schemars(
+        description = "Column on the end line just past the replaced text (1-indexed); equal to start_column on the same line to insert"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_column: Option<u32>,
+
     #[schemars(description = "New text to insert")]
     pub new_text: String,
 }
 
-/// Applies a set of text edits to a file
+/// Applies a set of text edits to a file. With `dry_run` the result is
+/// returned as a diff and neither the file nor the server sees it.
 pub async fn apply_text_edits(
     client: &Client,
     file_path: PathBuf,
     edits: Vec<TextEditParams>,
+    dry_run: bool,
 ) -> Result<String> {
     debug!(
         "[TOOL] Applying {} text edits to {}",
@@ -31,11 +54,62 @@ pub async fn apply_text_edits(
         file_path.display()
     );
 
-    // Get the file's absolute path
-    let file_path = file_path.canonicalize().context(format!(
-        "Failed to canonicalize path: {}",
+    let (file_path, content, result) = edited_content(client, file_path, &edits).await?;
+    if dry_run {
+        let path = display_path(client, &file_path);
+        if render::current_format() == OutputFormat::Json {
+            let diff = unified_diff(&path, &content, &result);
+            return edits_json(&file_path, edits.len(), true, Some(diff));
+        }
+        return Ok(format!(
+            "Dry run: would apply {} edits to {}\n\n{}",
+            edits.len(),
+            path,
+            unified_diff(&path, &content, &result)
+        ));
+    }
+
+    // Ensure the file is open in the LSP server
+    client.open_file(&file_path).await?;
+
+    // Write the result back to the file
+    fs::write(&file_path, &result)
+        .await
+        .context(format!("Failed to write file: {}", file_path.display()))?;
+
+    // Notify the LSP server of the change
+    client.notify_change(&file_path).await?;
+    client.emit(ProxyEvent::EditApplied {
+        uri: to_uri(&file_path),
+        edits: edits.len(),
+    });
+
+    debug!(
+        "[TOOL] Successfully applied edits to {}",
         file_path.display()
-    ))?;
+    );
+
+    if render::current_format() == OutputFormat::Json {
+        return edits_json(&file_path, edits.len(), false, None);
+    }
+
+    Ok(format!(
+        "Successfully applied {} edits to {}",
+        edits.len(),
+        display_path(client, &file_path)
+    ))
+}
+
+/// Computes a file's content after text edits, matching the project's
+/// formatting conventions. Returns the file's absolute path, its content on
+/// disk and the edited content.
+pub async fn edited_content(
+    client: &Client,
+    file_path: PathBuf,
+    edits: &[TextEditParams],
+) -> Result<(PathBuf, String, String)> {
+    // Get the file's absolute path
+    let file_path = normalize_path(&file_path)?;
 
     // Ensure the file exists
     if !file_path.exists() {
@@ -50,10 +124,11 @@ pub async fn apply_text_edits(
     // Split the content into lines
     let lines: Vec<&str> = content.lines().collect();
 
-    // Ensure the file is open in the LSP server
-    client.open_file(&file_path).await?;
+    // Match the project's formatting conventions
+    let editorconfig = EditorConfig::for_file(&file_path);
 
     // Convert edits to LSP TextEdit format
+    let encoding = client.position_encoding();
     let lsp_edits: Vec<TextEdit> = edits
         .iter()
         .map(|edit| {
@@ -61,18 +136,41 @@ pub async fn apply_text_edits(
             let start_line = edit.start_line - 1;
             let end_line = edit.end_line - 1;
 
-            // Calculate the start character (start of the line)
-            let start_character = 0;
+            let (start_character, end_character) = match (edit.start_column, edit.end_column) {
+                // Replace part of the text, in the server's column units
+                (Some(start_column), Some(end_column)) => {
+                    (start_column.saturating_sub(1), end_column.saturating_sub(1))
+                }
+                // Replace from the start of the first line to the end of the last
+                (None, None) => {
+                    let end_character = if end_line < lines.len() as u32 {
+                        let line = lines[end_line as usize];
+                        column(line, line.len(), encoding)
+                    } else {
+                        0
+                    };
+                    (0, end_character)
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "start_column and end_column must be given together (edit at line {})",
+                        edit.start_line
+                    ));
+                }
+            };
 
-            // Calculate the end character (end of the line)
-            let end_character = if end_line < lines.len() as u32 {
-                lines[end_line as usize].len() as u32
-            } else {
-                0
+            // Text inserted mid-line keeps its first line as given; only the
+            // lines that start after a newline take the configured indentation
+            let new_text = match (edit.start_column, edit.new_text.split_once('\n')) {
+                (Some(_), Some((first, rest))) => {
+                    format!("{}\n{}", first, editorconfig.format_new_text(rest))
+                }
+                (Some(_), None) => edit.new_text.clone(),
+                (None, _) => editorconfig.format_new_text(&edit.new_text),
             };
 
             // Create the LSP TextEdit
-            TextEdit {
+            Ok(TextEdit {
                 range: lsp_types::Range {
                     start: lsp_types::Position {
                         line: start_line,
@@ -83,10 +181,10 @@ pub async fn apply_text_edits(
                         character: end_character,
                     },
                 },
-                new_text: edit.new_text.clone(),
-            }
+                new_text,
+            })
         })
-        .collect();
+        .collect::<Result<_>>()?;
 
     // Apply the edits to the in-memory content
     let mut result = content.clone();
@@ -94,8 +192,14 @@ pub async fn apply_text_edits(
     // Apply edits in reverse to avoid position changes
     for edit in lsp_edits.iter().rev() {
         // Convert the LSP positions to string indices
-        let start_index = position_to_index(&content, edit.range.start)?;
-        let end_index = position_to_index(&content, edit.range.end)?;
+        let start_index = position_to_index(&content, edit.range.start, encoding)?;
+        let end_index = position_to_index(&content, edit.range.end, encoding)?;
+        if end_index < start_index {
+            return Err(anyhow!(
+                "Edit at line {} ends before it starts",
+                edit.range.start.line + 1
+            ));
+        }
 
         // Apply the edit
         result = format!(
@@ -106,28 +210,35 @@ pub async fn apply_text_edits(
         );
     }
 
-    // Write the result back to the file
-    fs::write(&file_path, &result)
-        .await
-        .context(format!("Failed to write file: {}", file_path.display()))?;
-
-    // Notify the LSP server of the change
-    client.notify_change(&file_path).await?;
-
-    debug!(
-        "[TOOL] Successfully applied edits to {}",
-        file_path.display()
-    );
+    Ok((file_path, content, editorconfig.finish_file(result)))
+}
 
-    Ok(format!(
-        "Successfully applied {} edits to {}",
-        edits.len(),
-        file_path.display()
-    ))
+/// Reports the edits to a single file as JSON
+fn edits_json(
+    file_path: &Path,
+    edits: usize,
+    dry_run: bool,
+    diff: Option<String>,
+) -> Result<String> {
+    to_json(&EditsApplied {
+        dry_run,
+        edits,
+        files: vec![FileEdits {
+            path: file_path.display().to_string(),
+            edits,
+            diff,
+        }],
+        operations: Vec::new(),
+        warnings: Vec::new(),
+    })
 }
 
 /// Converts an LSP Position to a string index
-fn position_to_index(content: &str, position: lsp_types::Position) -> Result<usize> {
+fn position_to_index(
+    content: &str,
+    position: lsp_types::Position,
+    encoding: PositionEncoding,
+) -> Result<usize> {
     let lines: Vec<&str> = content.lines().collect();
 
     // Check if the position is valid
@@ -135,18 +246,6 @@ fn position_to_index(content: &str, position: lsp_types::Position) -> Result<usi
         return Err(anyhow!("Invalid line number: {}", position.line));
     }
 
-    // Calculate the index
-    let mut index = 0;
-
-    // Add the length of all lines before the position
-    for line in lines.iter().take(position.line as usize) {
-        index += line.len() + 1; // +1 for the newline character
-    }
-
-    // Add the character offset
-    let line_len = lines[position.line as usize].len();
-    let char_offset = std::cmp::min(position.character as usize, line_len);
-    index += char_offset;
-
-    Ok(index)
+    offset_at(content, position, encoding)
+        .ok_or_else(|| anyhow!("Invalid line number: {}", position.line))
 }
@@ -0,0 +1,116 @@
+use crate::lsp::Client;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Result, anyhow};
+use log::debug;
+use lsp_types::DocumentUri;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use super::diagnostics::get_diagnostics;
+use super::diff::unified_diff;
+use super::edit::{TextEditParams, apply_text_edits, edited_content};
+use super::utils::{display_path, to_uri};
+
+/// Applies text edits to a file, then waits up to `wait` for the server to
+/// report diagnostics on the edited content and returns both results. With
+/// `dry_run` the server checks the edited content as an overlay, which is
+/// dropped again afterwards, and the file on disk is left alone.
+pub async fn edit_and_check(
+    client: &Client,
+    file_path: PathBuf,
+    edits: Vec<TextEditParams>,
+    context_lines: u32,
+    wait: Duration,
+    dry_run: bool,
+) -> Result<String> {
+    debug!("[TOOL] Editing and checking {}", file_path.display());
+
+    // Get the file's absolute path
+    let file_path = normalize_path(&file_path)?;
+
+    // Ensure the file exists
+    if !file_path.exists() {
+        return Err(anyhow!("File does not exist: {}", file_path.display()));
+    }
+
+    // Open the file before taking the marker, so the publication for the
+    // opened content isn't taken for one about the edit
+    let uri = to_uri(&file_path);
+    client.open_file(&file_path).await?;
+    let publication = client.diagnostics_publication(&uri);
+
+    let edited = if dry_run {
+        let count = edits.len();
+        let (_, content, edited) = edited_content(client, file_path.clone(), &edits).await?;
+        client.set_overlay(&file_path, edited.clone()).await?;
+        let path = display_path(client, &file_path);
+        format!(
+            "Dry run: checked {} edits to {} without writing them\n\n{}",
+            count,
+            path,
+            unified_diff(&path, &content, &edited)
+        )
+    } else {
+        apply_text_edits(client, file_path.clone(), edits, false).await?
+    };
+
+    // Servers using the pull model are asked by get_diagnostics below
+    let mut result = format!("{}\n\n", edited);
+    let pushes = client.pull_diagnostics_support().is_none();
+    if pushes && !wait_for_fresh_diagnostics(client, &uri, publication, wait).await {
+        result.push_str(&format!(
+            "Note: the server did not report on the edited file within {}ms; these diagnostics may predate the edit.\n\n",
+            wait.as_millis()
+        ));
+    }
+
+    let diagnostics = get_diagnostics(
+        client,
+        file_path.clone(),
+        context_lines,
+        true,
+        false,
+        Duration::ZERO,
+    )
+    .await;
+    if dry_run {
+        client.discard_overlay(&file_path).await?;
+    }
+    result.push_str(&diagnostics?);
+    Ok(result)
+}
+
+/// Waits until the server publishes diagnostics for the open version of a
+/// file, skipping publications still about an earlier version. Servers that
+/// don't report versions count any publication after the `since` marker.
+pub async fn wait_for_fresh_diagnostics(
+    client: &Client,
+    uri: &DocumentUri,
+    mut since: Option<u64>,
+    wait: Duration,
+) -> bool {
+    let deadline = Instant::now() + wait;
+    let open_version = client
+        .open_documents()
+        .into_iter()
+        .find(|document| &document.uri == uri)
+        .map(|document| document.version);
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if !client.wait_for_diagnostics(uri, since, remaining).await {
+            return false;
+        }
+        match (client.diagnostics_version(uri), open_version) {
+            (Some(version), Some(open_version)) if version < open_version => {
+                debug!(
+                    "[TOOL] Diagnostics for version {} of {} are stale, waiting for version {}",
+                    version, uri, open_version
+                );
+                since = client.diagnostics_publication(uri);
+            }
+            _ => return true,
+        }
+    }
+}
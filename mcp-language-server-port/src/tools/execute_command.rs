@@ -0,0 +1,70 @@
+use crate::lsp::Client;
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use lsp_types::ExecuteCommandParams;
+use serde_json::Value;
+
+use super::budget;
+
+/// Runs a command the server offers, such as gopls' `gopls.tidy`, applying
+/// the edits it asks for meanwhile and reporting its result
+pub async fn execute_command(
+    client: &Client,
+    command: &str,
+    arguments: Vec<Value>,
+) -> Result<String> {
+    debug!("[TOOL] Executing command {} on {}", command, client.name());
+
+    let commands = client
+        .server_capabilities()
+        .and_then(|capabilities| capabilities.execute_command_provider)
+        .map(|provider| provider.commands);
+    if let Some(commands) = commands
+        && !commands.iter().any(|c| c == command)
+    {
+        return Err(anyhow!(
+            "{} has no command '{}'; it offers: {}",
+            client.name(),
+            command,
+            if commands.is_empty() {
+                "none".to_string()
+            } else {
+                commands.join(", ")
+            }
+        ));
+    }
+
+    Ok(budget::fit_lines(
+        &run_command(client, command, arguments).await?,
+    ))
+}
+
+/// Runs a command on the server, reporting the edits it had applied meanwhile
+pub async fn run_command(client: &Client, command: &str, arguments: Vec<Value>) -> Result<String> {
+    // Edits left over from earlier commands don't belong to this one
+    client.take_applied_edits();
+
+    let params = ExecuteCommandParams {
+        command: command.to_string(),
+        arguments,
+        work_done_progress_params: Default::default(),
+    };
+    let value: Option<Value> = client
+        .call("workspace/executeCommand", params)
+        .await
+        .context(format!("Failed to execute command '{}'", command))?;
+
+    let mut result = format!("Executed command '{}'", command);
+    if let Some(value) = value.filter(|value| !value.is_null()) {
+        result.push_str(&format!(
+            "\nResult: {}",
+            serde_json::to_string_pretty(&value)?
+        ));
+    }
+    // The client applies requested edits before answering the server, so
+    // they're done by the time the command finishes
+    for report in client.take_applied_edits() {
+        result.push_str(&format!("\n{}", report));
+    }
+    Ok(result)
+}
@@ -0,0 +1,33 @@
+use crate::lsp::Client;
+use crate::workspace::hashes::{FileHashes, format_hash};
+use crate::workspace::paths::normalize_path;
+use anyhow::Result;
+use log::debug;
+use std::path::PathBuf;
+
+use super::budget;
+use super::utils::display_path;
+
+/// Reports the content hash of each file, for detecting changes between calls
+pub async fn get_file_hashes(
+    client: &Client,
+    hashes: &FileHashes,
+    file_paths: Vec<PathBuf>,
+) -> Result<String> {
+    debug!("[TOOL] Hashing {} files", file_paths.len());
+
+    let mut result = String::new();
+
+    for file_path in file_paths {
+        let file_path = normalize_path(&file_path)?;
+        let hash = hashes.hash(&file_path).await?;
+
+        result.push_str(&format!(
+            "{}  {}\n",
+            format_hash(hash),
+            display_path(client, &file_path)
+        ));
+    }
+
+    Ok(budget::fit_lines(&result))
+}
@@ -0,0 +1,311 @@
+use crate::lsp::Client;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Result, anyhow};
+use log::debug;
+use lsp_types::{CodeAction, CodeActionOrCommand, Diagnostic};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::budget;
+use super::code_actions::{code_actions, is_kind, resolve_code_action};
+use super::edit_and_check::wait_for_fresh_diagnostics;
+use super::execute_command;
+use super::scope::ActionScope;
+use super::utils::{display_path, to_path, to_uri};
+use super::workspace_edit::{self, ApplyOptions};
+
+/// How long to wait for the server to report on a file after a fix, before
+/// leaving the rest of its diagnostics alone
+const RECHECK_WAIT: Duration = Duration::from_secs(5);
+
+/// Applies the quick fix of each diagnostic in a file, or in every file the
+/// server reported on within `scope` around it; without a file, in the whole
+/// workspace. Servers are asked for fixes where they offer a single or
+/// preferred one. At most `max_fixes` fixes are applied; the other
+/// diagnostics are reported as skipped along with those without an
+/// unambiguous fix. After each applied fix the file's diagnostics are fetched
+/// again, since the fix may have moved the ones left.
+pub async fn fix_diagnostics(
+    client: &Client,
+    file_path: Option<PathBuf>,
+    scope: ActionScope,
+    max_fixes: usize,
+    options: &ApplyOptions,
+) -> Result<String> {
+    let files = match (file_path, scope) {
+        (Some(file_path), ActionScope::File) => {
+            debug!("[TOOL] Fixing diagnostics in {}", file_path.display());
+            let file_path = normalize_path(&file_path)?;
+            if !file_path.exists() {
+                return Err(anyhow!("File does not exist: {}", file_path.display()));
+            }
+            client.open_file(&file_path).await?;
+
+            // Servers using the pull model report when asked rather than pushing
+            if let Err(e) = client.pull_diagnostics(&to_uri(&file_path)).await {
+                debug!("[TOOL] Could not pull diagnostics: {}", e);
+            }
+            vec![file_path]
+        }
+        (file_path, scope) => {
+            let root = match &file_path {
+                Some(file_path) => scope.root(client, file_path)?,
+                None => None,
+            };
+            match &root {
+                Some(root) => debug!("[TOOL] Fixing diagnostics under {}", root.display()),
+                None => debug!("[TOOL] Fixing diagnostics in the workspace"),
+            }
+            let uris = match client.pull_workspace_diagnostics().await {
+                Ok(Some(uris)) => uris,
+                Ok(None) => client.diagnostic_uris(),
+                Err(e) => {
+                    debug!("[TOOL] Could not pull workspace diagnostics: {}", e);
+                    client.diagnostic_uris()
+                }
+            };
+            let mut files: Vec<PathBuf> = uris
+                .iter()
+                .filter_map(|uri| to_path(uri).ok())
+                .filter(|file| root.as_ref().is_none_or(|root| file.starts_with(root)))
+                .collect();
+            files.sort();
+            files.dedup();
+            files
+        }
+    };
+
+    let mut fixed = Vec::new();
+    let mut skipped = Vec::new();
+    let mut reports = Vec::new();
+    let mut total = 0;
+    for file_path in files {
+        if !file_path.exists() {
+            continue;
+        }
+        client.open_file(&file_path).await?;
+        let uri = to_uri(&file_path);
+        let name = display_path(client, &file_path);
+        let location = |diagnostic: &Diagnostic| {
+            format!(
+                "{}:{}: {}",
+                name,
+                diagnostic.range.start.line + 1,
+                diagnostic.message
+            )
+        };
+
+        let mut diagnostics = client.get_diagnostics(&uri);
+        total += diagnostics.len();
+        // Diagnostics left alone, which aren't tried again once the file is rechecked
+        let mut passed: Vec<Diagnostic> = Vec::new();
+
+        loop {
+            // Fixing from the bottom up moves fewer of the diagnostics left
+            diagnostics
+                .sort_by_key(|d| std::cmp::Reverse((d.range.start.line, d.range.start.character)));
+            let Some(index) = diagnostics
+                .iter()
+                .position(|d| !passed.iter().any(|p| same_problem(p, d)))
+            else {
+                break;
+            };
+            let diagnostic = diagnostics.remove(index);
+            if fixed.len() >= max_fixes {
+                for diagnostic in std::iter::once(&diagnostic).chain(&diagnostics) {
+                    if !passed.iter().any(|p| same_problem(p, diagnostic)) {
+                        skipped.push(format!("{} (fix limit reached)", location(diagnostic)));
+                    }
+                }
+                break;
+            }
+
+            let actions =
+                code_actions(client, &file_path, diagnostic.range, Some("quickfix")).await?;
+            let action = match choose_fix(actions, &diagnostic) {
+                Ok(action) => action,
+                Err(reason) => {
+                    skipped.push(format!("{} ({})", location(&diagnostic), reason));
+                    passed.push(diagnostic);
+                    continue;
+                }
+            };
+            let action = resolve_code_action(client, action).await;
+            let publication = client.diagnostics_publication(&uri);
+
+            if let Some(edit) = action.edit {
+                let report = workspace_edit::apply(client, edit, options).await?;
+                if report.files.iter().any(|file| file.diff.is_some()) {
+                    reports.push(report.to_string());
+                }
+            } else if action.command.is_none() {
+                skipped.push(format!(
+                    "{} (fix '{}' has no edit)",
+                    location(&diagnostic),
+                    action.title
+                ));
+                passed.push(diagnostic);
+                continue;
+            }
+
+            // Commands can't be previewed, so a dry run only names them
+            if let Some(command) = action.command {
+                if options.dry_run {
+                    fixed.push(format!(
+                        "{} -> {} (would run command '{}')",
+                        location(&diagnostic),
+                        action.title,
+                        command.command
+                    ));
+                    continue;
+                }
+                let arguments = command.arguments.unwrap_or_default();
+                execute_command::run_command(client, &command.command, arguments).await?;
+            }
+            fixed.push(format!("{} -> {}", location(&diagnostic), action.title));
+
+            // A dry run leaves the file as it was, so the other ranges still hold
+            let more = diagnostics
+                .iter()
+                .any(|d| !passed.iter().any(|p| same_problem(p, d)));
+            if options.dry_run || !more {
+                continue;
+            }
+            let remaining = diagnostics
+                .iter()
+                .filter(|d| same_problem(d, &diagnostic))
+                .count();
+            match recheck(client, &file_path, publication).await {
+                Some(fresh) => diagnostics = fresh,
+                None => {
+                    for diagnostic in &diagnostics {
+                        if !passed.iter().any(|p| same_problem(p, diagnostic)) {
+                            skipped.push(format!(
+                                "{} (not rechecked after the previous fix)",
+                                location(diagnostic)
+                            ));
+                        }
+                    }
+                    break;
+                }
+            }
+            // A fix that leaves its diagnostic in place isn't applied again
+            if diagnostics
+                .iter()
+                .filter(|d| same_problem(d, &diagnostic))
+                .count()
+                > remaining
+            {
+                passed.push(diagnostic);
+            }
+        }
+    }
+
+    if total == 0 {
+        return Ok("No diagnostics to fix".to_string());
+    }
+
+    let mut result = format!(
+        "{} {} of {} diagnostics\n",
+        if options.dry_run {
+            "Would fix"
+        } else {
+            "Fixed"
+        },
+        fixed.len(),
+        total
+    );
+    if !fixed.is_empty() {
+        result.push_str("\nFixed:\n");
+        for line in &fixed {
+            result.push_str(&format!("  {}\n", line));
+        }
+    }
+    if !skipped.is_empty() {
+        result.push_str("\nSkipped:\n");
+        for line in &skipped {
+            result.push_str(&format!("  {}\n", line));
+        }
+    }
+    for report in &reports {
+        result.push_str(&format!("\n{}\n", report));
+    }
+
+    Ok(budget::fit_lines(&result))
+}
+
+/// Fetches a file's diagnostics again after a fix, once the server has
+/// reported on the fixed content. Gives `None` if it doesn't in time.
+async fn recheck(
+    client: &Client,
+    file_path: &Path,
+    publication: Option<u64>,
+) -> Option<Vec<Diagnostic>> {
+    let uri = to_uri(file_path);
+    if client.pull_diagnostics_support().is_some() {
+        if let Err(e) = client.pull_diagnostics(&uri).await {
+            debug!("[TOOL] Could not pull diagnostics: {}", e);
+            return None;
+        }
+    } else if !wait_for_fresh_diagnostics(client, &uri, publication, RECHECK_WAIT).await {
+        debug!(
+            "[TOOL] No diagnostics published for {} after a fix",
+            file_path.display()
+        );
+        return None;
+    }
+    Some(client.get_diagnostics(&uri))
+}
+
+/// Whether two diagnostics report the same problem, wherever an edit has
+/// moved it
+fn same_problem(a: &Diagnostic, b: &Diagnostic) -> bool {
+    a.message == b.message && a.code == b.code && a.source == b.source
+}
+
+/// Picks the quick fix to apply for a diagnostic: the preferred one, or the
+/// only one offered. Explains why none was picked otherwise.
+pub fn choose_fix(
+    actions: Vec<CodeActionOrCommand>,
+    diagnostic: &Diagnostic,
+) -> Result<CodeAction, String> {
+    let fixes: Vec<CodeAction> = actions
+        .into_iter()
+        .filter_map(|action| match action {
+            CodeActionOrCommand::CodeAction(action) => Some(action),
+            CodeActionOrCommand::Command(_) => None,
+        })
+        .filter(|action| {
+            action.disabled.is_none()
+                && action
+                    .kind
+                    .as_ref()
+                    .is_none_or(|kind| is_kind(kind, "quickfix"))
+                // Fixes for other diagnostics on the same range don't count
+                && action.diagnostics.as_ref().is_none_or(|fixed| {
+                    fixed.iter().any(|d| {
+                        d.range == diagnostic.range && d.message == diagnostic.message
+                    })
+                })
+        })
+        .collect();
+
+    let count = fixes.len();
+    let mut preferred: Vec<CodeAction> = Vec::new();
+    let mut others = Vec::new();
+    for fix in fixes {
+        if fix.is_preferred == Some(true) {
+            preferred.push(fix);
+        } else {
+            others.push(fix);
+        }
+    }
+
+    match (preferred.len(), count) {
+        (1, _) => Ok(preferred.remove(0)),
+        (0, 0) => Err("no fix available".to_string()),
+        (0, 1) => Ok(others.remove(0)),
+        (0, _) => Err(format!("{} fixes, none preferred", count)),
+        (n, _) => Err(format!("{} preferred fixes", n)),
+    }
+}
@@ -0,0 +1,106 @@
+use crate::lsp::Client;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use lsp_types::{FoldingRange, FoldingRangeKind, FoldingRangeParams};
+use std::path::PathBuf;
+
+use super::budget;
+use super::utils::{display_path, to_text_document_identifier};
+
+/// Longest preview of a range's first line
+const PREVIEW_CHARS: usize = 80;
+
+/// Lists the regions of a file that an editor could fold, nested as they
+/// are in the file. Ranges spanning fewer than `min_lines` lines are left out.
+pub async fn get_folding_ranges(
+    client: &Client,
+    file_path: PathBuf,
+    min_lines: u32,
+) -> Result<String> {
+    debug!("[TOOL] Getting folding ranges for {}", file_path.display());
+
+    // Get the file's absolute path
+    let file_path = normalize_path(&file_path)?;
+
+    // Ensure the file exists
+    if !file_path.exists() {
+        return Err(anyhow!("File does not exist: {}", file_path.display()));
+    }
+
+    // Ensure the file is open in the LSP server
+    client.open_file(&file_path).await?;
+
+    let params = FoldingRangeParams {
+        text_document: to_text_document_identifier(&file_path)?,
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+    let ranges: Option<Vec<FoldingRange>> =
+        client.call("textDocument/foldingRange", params).await?;
+    let mut ranges: Vec<FoldingRange> = ranges
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|range| range.end_line.saturating_sub(range.start_line) + 1 >= min_lines)
+        .collect();
+
+    if ranges.is_empty() {
+        return Ok(format!(
+            "No folding ranges in {}",
+            display_path(client, &file_path)
+        ));
+    }
+
+    let content = tokio::fs::read_to_string(&file_path)
+        .await
+        .context(format!("Failed to read file: {}", file_path.display()))?;
+    let lines: Vec<&str> = content.lines().collect();
+
+    // Outer ranges first, so each is listed before the ones it contains
+    ranges.sort_by_key(|range| (range.start_line, std::cmp::Reverse(range.end_line)));
+
+    let mut result = format!(
+        "Found {} folding ranges in {}:\n\n",
+        ranges.len(),
+        display_path(client, &file_path)
+    );
+    let mut enclosing: Vec<u32> = Vec::new();
+    for range in &ranges {
+        while enclosing.last().is_some_and(|end| *end < range.start_line) {
+            enclosing.pop();
+        }
+        result.push_str(&"  ".repeat(enclosing.len()));
+        result.push_str(&format_folding_range(range, &lines));
+        result.push('\n');
+        enclosing.push(range.end_line);
+    }
+
+    Ok(budget::fit_lines(&result))
+}
+
+/// Formats a range as `L<start>-<end> [kind] <first line>`
+pub fn format_folding_range(range: &FoldingRange, lines: &[&str]) -> String {
+    let mut result = format!("L{}-{}", range.start_line + 1, range.end_line + 1);
+    if let Some(kind) = &range.kind {
+        result.push_str(match kind {
+            FoldingRangeKind::Comment => " [comment]",
+            FoldingRangeKind::Imports => " [imports]",
+            FoldingRangeKind::Region => " [region]",
+        });
+    }
+
+    let preview = lines
+        .get(range.start_line as usize)
+        .map(|line| line.trim())
+        .unwrap_or_default();
+    if !preview.is_empty() {
+        result.push(' ');
+        if preview.chars().count() > PREVIEW_CHARS {
+            result.extend(preview.chars().take(PREVIEW_CHARS));
+            result.push_str("...");
+        } else {
+            result.push_str(preview);
+        }
+    }
+    result
+}
@@ -0,0 +1,146 @@
+use crate::lsp::Client;
+use crate::lsp::PositionEncoding;
+use crate::lsp::position::byte_column;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use lsp_types::{
+    DocumentHighlight, DocumentHighlightKind, DocumentHighlightParams, Position,
+    TextDocumentPositionParams,
+};
+use std::path::PathBuf;
+
+use super::budget;
+use super::utils::{display_path, to_text_document_identifier};
+
+/// Lists the occurrences of the symbol at a position within its file, marked
+/// as reads or writes where the server tells them apart
+pub async fn get_document_highlights(
+    client: &Client,
+    file_path: PathBuf,
+    line: u32,
+    column: u32,
+) -> Result<String> {
+    debug!(
+        "[TOOL] Getting document highlights for {}:{}:{}",
+        file_path.display(),
+        line,
+        column
+    );
+
+    // Get the file's absolute path
+    let file_path = normalize_path(&file_path)?;
+
+    // Ensure the file exists
+    if !file_path.exists() {
+        return Err(anyhow!("File does not exist: {}", file_path.display()));
+    }
+
+    // Ensure the file is open in the LSP server
+    client.open_file(&file_path).await?;
+
+    // Adjust from 1-indexed to 0-indexed
+    let params = DocumentHighlightParams {
+        text_document_position_params: TextDocumentPositionParams {
+            text_document: to_text_document_identifier(&file_path)?,
+            position: Position {
+                line: line.saturating_sub(1),
+                character: column.saturating_sub(1),
+            },
+        },
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+    let highlights: Option<Vec<DocumentHighlight>> = client
+        .call("textDocument/documentHighlight", params)
+        .await?;
+    let mut highlights = highlights.unwrap_or_default();
+
+    if highlights.is_empty() {
+        return Ok(format!(
+            "No occurrences found at {}:{}:{}",
+            display_path(client, &file_path),
+            line,
+            column
+        ));
+    }
+
+    let content = tokio::fs::read_to_string(&file_path)
+        .await
+        .context(format!("Failed to read file: {}", file_path.display()))?;
+    let lines: Vec<&str> = content.lines().collect();
+    let encoding = client.position_encoding();
+
+    highlights.sort_by_key(|highlight| highlight.range.start);
+    let count = |kind: DocumentHighlightKind| {
+        highlights
+            .iter()
+            .filter(|highlight| highlight.kind == Some(kind))
+            .count()
+    };
+    let (reads, writes) = (
+        count(DocumentHighlightKind::READ),
+        count(DocumentHighlightKind::WRITE),
+    );
+
+    let name = highlight_text(&highlights[0], &lines, encoding);
+    let mut result = format!(
+        "Found {} occurrences of `{}` in {}",
+        highlights.len(),
+        name,
+        display_path(client, &file_path)
+    );
+    if reads + writes > 0 {
+        result.push_str(&format!(" ({} reads, {} writes)", reads, writes));
+    }
+    result.push_str(":\n\n");
+    for highlight in &highlights {
+        result.push_str(&format_highlight(highlight, &lines));
+        result.push('\n');
+    }
+
+    Ok(budget::fit_lines(&result))
+}
+
+/// Gets the source text a highlight covers on its first line
+fn highlight_text(
+    highlight: &DocumentHighlight,
+    lines: &[&str],
+    encoding: PositionEncoding,
+) -> String {
+    let range = highlight.range;
+    lines
+        .get(range.start.line as usize)
+        .and_then(|line| {
+            let start = byte_column(line, range.start.character, encoding);
+            let end = if range.end.line == range.start.line {
+                byte_column(line, range.end.character, encoding)
+            } else {
+                line.len()
+            };
+            line.get(start..end)
+        })
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Formats a highlight as `L<line>:<column> <kind>  <line text>`
+pub fn format_highlight(highlight: &DocumentHighlight, lines: &[&str]) -> String {
+    let kind = match highlight.kind {
+        Some(DocumentHighlightKind::READ) => "read",
+        Some(DocumentHighlightKind::WRITE) => "write",
+        _ => "text",
+    };
+    let start = highlight.range.start;
+    let text = lines
+        .get(start.line as usize)
+        .map(|line| line.trim())
+        .unwrap_or_default();
+    format!(
+        "L{}:{} {:<5}  {}",
+        start.line + 1,
+        start.character + 1,
+        kind,
+        text
+    )
+}
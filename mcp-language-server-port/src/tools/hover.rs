@@ -1,9 +1,11 @@
 use crate::lsp::Client;
-use anyhow::{Context, Result, anyhow};
+use crate::workspace::paths::normalize_path;
+use anyhow::{Result, anyhow};
 use log::debug;
 use lsp_types::{Hover, HoverContents, MarkedString, Position, TextDocumentPositionParams};
 use std::path::PathBuf;
 
+use super::render;
 use super::utils::to_text_document_identifier;
 
 /// Gets hover information for a position in a file
@@ -12,6 +14,7 @@ pub async fn get_hover_info(
     file_path: PathBuf,
     line: u32,
     column: u32,
+    max_length: Option<usize>,
 ) -> Result<String> {
     debug!(
         "[TOOL] Getting hover info for {}:{}:{}",
@@ -21,10 +24,7 @@ pub async fn get_hover_info(
     );
 
     // Get the file's absolute path
-    let file_path = file_path.canonicalize().context(format!(
-        "Failed to canonicalize path: {}",
-        file_path.display()
-    ))?;
+    let file_path = normalize_path(&file_path)?;
 
     // Ensure the file exists
     if !file_path.exists() {
@@ -66,10 +66,15 @@ pub async fn get_hover_info(
             };
 
             if contents.is_empty() {
-                Ok("No hover information available at this position.".to_string())
-            } else {
-                Ok(contents)
+                return Ok("No hover information available at this position.".to_string());
             }
+
+            let contents = match max_length {
+                Some(max_length) => truncate_hover(&contents, max_length),
+                None => contents,
+            };
+            // Truncation relies on the code fences, so restyle afterwards
+            Ok(render::renderer().text(&contents))
         }
         None => Ok("No hover information available at this position.".to_string()),
     }
@@ -84,3 +89,82 @@ fn format_marked_string(marked_string: &MarkedString) -> String {
         }
     }
 }
+
+/// Shortens hover text to roughly `max_length` characters.
+///
+/// Oversized hovers are reduced to their signature (the first code block) and
+/// the first paragraph of documentation, then cut hard if that is still too
+/// long. A note at the end reports how much was dropped.
+pub fn truncate_hover(contents: &str, max_length: usize) -> String {
+    let total = contents.chars().count();
+    if total <= max_length {
+        return contents.to_string();
+    }
+
+    let blocks = split_markdown_blocks(contents);
+    let signature = blocks.iter().position(|b| b.starts_with("```"));
+    let paragraph = blocks
+        .iter()
+        .position(|b| !b.starts_with("```") && !is_horizontal_rule(b));
+
+    let mut result = blocks
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| Some(*i) == signature || Some(*i) == paragraph)
+        .map(|(_, b)| b.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    if result.chars().count() > max_length {
+        result = result.chars().take(max_length).collect();
+    }
+    let shown = result.chars().count();
+
+    // Keep an open code fence from swallowing the truncation note
+    if result.matches("```").count() % 2 == 1 {
+        result.push_str("\n```");
+    }
+
+    result.push_str(&format!(
+        "\n\n[Hover truncated: showing {} of {} characters]",
+        shown, total
+    ));
+    result
+}
+
+/// Splits markdown into blank-line separated blocks, keeping fenced code intact
+fn split_markdown_blocks(contents: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut in_fence = false;
+
+    for line in contents.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+
+        if !in_fence && line.trim().is_empty() {
+            if !current.is_empty() {
+                blocks.push(current.join("\n"));
+                current.clear();
+            }
+        } else {
+            current.push(line);
+        }
+    }
+
+    if !current.is_empty() {
+        blocks.push(current.join("\n"));
+    }
+
+    blocks
+}
+
+/// Returns true for markdown thematic breaks such as `---`
+fn is_horizontal_rule(block: &str) -> bool {
+    let block = block.trim();
+    block.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&c| block.chars().all(|ch| ch == c))
+}
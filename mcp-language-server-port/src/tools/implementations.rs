@@ -0,0 +1,230 @@
+use crate::lsp::Client;
+use anyhow::{Result, anyhow};
+use log::debug;
+use lsp_types::{
+    Location, OneOf, PartialResultParams, SymbolKind, TextDocumentPositionParams,
+    TypeHierarchyItem, TypeHierarchyPrepareParams, TypeHierarchySubtypesParams,
+    WorkDoneProgressParams, WorkspaceSymbolResponse,
+};
+use serde_json::Value;
+
+use super::budget;
+use super::references::workspace_symbols;
+use super::utils::{display_path, to_path};
+
+/// A type that implements a trait or interface
+#[derive(Debug, Clone, PartialEq)]
+pub struct Implementation {
+    pub name: String,
+    pub kind: Option<SymbolKind>,
+    pub location: Location,
+}
+
+/// Lists every implementor of a named trait or interface.
+///
+/// The trait is found through a workspace symbol search, then its subtypes are
+/// collected with the type hierarchy requests. Servers without type hierarchy
+/// support fall back to `textDocument/implementation`.
+pub async fn find_impls_of_trait(client: &Client, trait_name: &str) -> Result<String> {
+    debug!("[TOOL] Finding implementations of trait: {}", trait_name);
+
+    let symbols = workspace_symbols(client, trait_name).await?;
+
+    let traits = symbols
+        .map(|symbols| trait_locations(&symbols, trait_name))
+        .unwrap_or_default();
+
+    if traits.is_empty() {
+        return Err(anyhow!(
+            "No trait or interface named '{}' found",
+            trait_name
+        ));
+    }
+
+    let mut implementations = Vec::new();
+    for location in &traits {
+        let file_path = to_path(&location.uri)?;
+        client.open_file(&file_path).await?;
+
+        let position = TextDocumentPositionParams {
+            text_document: lsp_types::TextDocumentIdentifier {
+                uri: location.uri.clone(),
+            },
+            position: location.range.start,
+        };
+
+        match subtypes(client, position.clone()).await {
+            Ok(found) => implementations.extend(found),
+            Err(e) => {
+                debug!(
+                    "[TOOL] Type hierarchy unavailable, falling back to textDocument/implementation: {}",
+                    e
+                );
+                implementations.extend(implementation_locations(client, position).await?);
+            }
+        }
+    }
+
+    // Several trait matches (or both requests) can report the same implementor
+    let mut seen: Vec<Location> = Vec::new();
+    implementations.retain(|implementation| {
+        if seen.contains(&implementation.location) {
+            return false;
+        }
+        seen.push(implementation.location.clone());
+        true
+    });
+
+    if implementations.is_empty() {
+        return Ok(format!("No implementations of '{}' found", trait_name));
+    }
+
+    let mut result = format!(
+        "Found {} implementations of '{}':\n\n",
+        implementations.len(),
+        trait_name
+    );
+
+    for implementation in &implementations {
+        let file_path = to_path(&implementation.location.uri)?;
+        let kind = implementation
+            .kind
+            .map(|kind| format!("{:?} ", kind))
+            .unwrap_or_default();
+
+        result.push_str(&format!(
+            "  {}{} at {}:{}:{}\n",
+            kind,
+            implementation.name,
+            display_path(client, &file_path),
+            implementation.location.range.start.line + 1,
+            implementation.location.range.start.character + 1
+        ));
+    }
+
+    Ok(budget::fit_lines(&result))
+}
+
+/// Collects the subtypes of the type at a position via the type hierarchy requests
+async fn subtypes(
+    client: &Client,
+    position: TextDocumentPositionParams,
+) -> Result<Vec<Implementation>> {
+    let params = TypeHierarchyPrepareParams {
+        text_document_position_params: position,
+        work_done_progress_params: Default::default(),
+    };
+    let items: Option<Vec<TypeHierarchyItem>> = client
+        .call("textDocument/prepareTypeHierarchy", params)
+        .await?;
+
+    let mut implementations = Vec::new();
+    for item in items.unwrap_or_default() {
+        // Subtypes of a widely implemented trait can be many, so let the
+        // server stream them
+        let partial_result_token = client.new_progress_token();
+        let params = TypeHierarchySubtypesParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams {
+                work_done_token: Some(client.new_progress_token()),
+            },
+            partial_result_params: PartialResultParams {
+                partial_result_token: Some(partial_result_token.clone()),
+            },
+        };
+        let subtypes: Vec<TypeHierarchyItem> = client
+            .call_with_partial_results("typeHierarchy/subtypes", params, &partial_result_token)
+            .await?;
+
+        implementations.extend(subtypes.into_iter().map(|subtype| Implementation {
+            name: subtype.name,
+            kind: Some(subtype.kind),
+            location: Location {
+                uri: subtype.uri,
+                range: subtype.selection_range,
+            },
+        }));
+    }
+
+    Ok(implementations)
+}
+
+/// Collects implementation locations for the symbol at a position
+async fn implementation_locations(
+    client: &Client,
+    position: TextDocumentPositionParams,
+) -> Result<Vec<Implementation>> {
+    let response: Value = client.call("textDocument/implementation", position).await?;
+
+    // The result is a Location, a list of Locations, or a list of LocationLinks
+    let items = match response {
+        Value::Array(items) => items,
+        Value::Null => Vec::new(),
+        item => vec![item],
+    };
+
+    let mut implementations = Vec::new();
+    for item in items {
+        let location = match serde_json::from_value::<Location>(item.clone()) {
+            Ok(location) => location,
+            Err(_) => match serde_json::from_value::<lsp_types::LocationLink>(item) {
+                Ok(link) => Location {
+                    uri: link.target_uri,
+                    range: link.target_selection_range,
+                },
+                Err(e) => {
+                    debug!("[TOOL] Failed to parse implementation location: {}", e);
+                    continue;
+                }
+            },
+        };
+
+        implementations.push(Implementation {
+            name: implementation_name(&location).await,
+            kind: None,
+            location,
+        });
+    }
+
+    Ok(implementations)
+}
+
+/// Uses the source line an implementation starts on as its name, since
+/// `textDocument/implementation` only returns locations
+async fn implementation_name(location: &Location) -> String {
+    let line = location.range.start.line as usize;
+    let content = match to_path(&location.uri) {
+        Ok(path) => tokio::fs::read_to_string(path).await.unwrap_or_default(),
+        Err(_) => String::new(),
+    };
+
+    content
+        .lines()
+        .nth(line)
+        .map(|l| l.trim().trim_end_matches('{').trim().to_string())
+        .filter(|l| !l.is_empty())
+        .unwrap_or_else(|| "<implementation>".to_string())
+}
+
+/// Picks the locations of traits or interfaces named exactly `name` from a
+/// workspace symbol response. Symbols without a range are skipped.
+pub fn trait_locations(symbols: &WorkspaceSymbolResponse, name: &str) -> Vec<Location> {
+    let is_trait =
+        |symbol_name: &str, kind: SymbolKind| symbol_name == name && kind == SymbolKind::INTERFACE;
+
+    match symbols {
+        WorkspaceSymbolResponse::Flat(symbols) => symbols
+            .iter()
+            .filter(|s| is_trait(&s.name, s.kind))
+            .map(|s| s.location.clone())
+            .collect(),
+        WorkspaceSymbolResponse::Nested(symbols) => symbols
+            .iter()
+            .filter(|s| is_trait(&s.name, s.kind))
+            .filter_map(|s| match &s.location {
+                OneOf::Left(location) => Some(location.clone()),
+                OneOf::Right(_) => None,
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,161 @@
+use crate::lsp::Client;
+use crate::lsp::PositionEncoding;
+use crate::lsp::position::{byte_column, column};
+use crate::workspace::paths::normalize_path;
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use lsp_types::{InlineValue, InlineValueContext, InlineValueParams, Position, Range};
+use std::path::PathBuf;
+
+use super::budget;
+use super::utils::{display_path, to_text_document_identifier};
+
+/// Lists the expressions a debugger would show inline for a range of lines
+pub async fn get_inline_values(
+    client: &Client,
+    file_path: PathBuf,
+    start_line: Option<u32>,
+    end_line: Option<u32>,
+) -> Result<String> {
+    debug!("[TOOL] Getting inline values for {}", file_path.display());
+
+    // Get the file's absolute path
+    let file_path = normalize_path(&file_path)?;
+
+    // Ensure the file exists
+    if !file_path.exists() {
+        return Err(anyhow!("File does not exist: {}", file_path.display()));
+    }
+
+    let supported = client
+        .server_capabilities()
+        .is_some_and(|capabilities| capabilities.inline_value_provider.is_some());
+    if !supported {
+        return Err(anyhow!(
+            "The language server does not support inline values"
+        ));
+    }
+
+    // Ensure the file is open in the LSP server
+    client.open_file(&file_path).await?;
+
+    let content = tokio::fs::read_to_string(&file_path)
+        .await
+        .context(format!("Failed to read file: {}", file_path.display()))?;
+    let lines: Vec<&str> = content.lines().collect();
+    let encoding = client.position_encoding();
+
+    // Adjust from 1-indexed to 0-indexed; the whole file by default
+    let last_line = lines.len().saturating_sub(1) as u32;
+    let start = start_line.map_or(0, |line| line.saturating_sub(1));
+    let end = end_line.map_or(last_line, |line| line.saturating_sub(1).min(last_line));
+    if start > end {
+        return Err(anyhow!("Start line is after end line"));
+    }
+    let end_character = lines
+        .get(end as usize)
+        .map_or(0, |line| column(line, line.len(), encoding));
+    let range = Range {
+        start: Position {
+            line: start,
+            character: 0,
+        },
+        end: Position {
+            line: end,
+            character: end_character,
+        },
+    };
+
+    // There is no real debug session, so pretend execution stopped at the end of the range
+    let params = InlineValueParams {
+        work_done_progress_params: Default::default(),
+        text_document: to_text_document_identifier(&file_path)?,
+        range,
+        context: InlineValueContext {
+            frame_id: 0,
+            stopped_location: Range {
+                start: range.end,
+                end: range.end,
+            },
+        },
+    };
+    let values: Option<Vec<InlineValue>> = client.call("textDocument/inlineValue", params).await?;
+    let mut values = values.unwrap_or_default();
+
+    if values.is_empty() {
+        return Ok(format!(
+            "No inline values in {} lines {}-{}",
+            display_path(client, &file_path),
+            start + 1,
+            end + 1
+        ));
+    }
+
+    values.sort_by_key(|value| inline_value_range(value).start);
+    let mut result = format!(
+        "Inline values in {} lines {}-{}:\n\n",
+        display_path(client, &file_path),
+        start + 1,
+        end + 1
+    );
+    for value in &values {
+        result.push_str(&format!(
+            "L{}: {}\n",
+            inline_value_range(value).start.line + 1,
+            describe_inline_value(value, &lines, encoding)
+        ));
+    }
+
+    Ok(budget::fit_lines(&result))
+}
+
+fn inline_value_range(value: &InlineValue) -> Range {
+    match value {
+        InlineValue::Text(text) => text.range,
+        InlineValue::VariableLookup(lookup) => lookup.range,
+        InlineValue::EvaluatableExpression(expression) => expression.range,
+    }
+}
+
+/// Gets the source text covered by a single-line range
+fn text_at(lines: &[&str], range: &Range, encoding: PositionEncoding) -> String {
+    lines
+        .get(range.start.line as usize)
+        .and_then(|line| {
+            let start = byte_column(line, range.start.character, encoding);
+            let end = if range.end.line == range.start.line {
+                byte_column(line, range.end.character, encoding)
+            } else {
+                line.len()
+            };
+            line.get(start..end)
+        })
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Describes an inline value; names and expressions the server leaves out are
+/// taken from the source text at the value's range
+pub fn describe_inline_value(
+    value: &InlineValue,
+    lines: &[&str],
+    encoding: PositionEncoding,
+) -> String {
+    match value {
+        InlineValue::Text(text) => format!("text `{}`", text.text),
+        InlineValue::VariableLookup(lookup) => {
+            let name = lookup
+                .variable_name
+                .clone()
+                .unwrap_or_else(|| text_at(lines, &lookup.range, encoding));
+            format!("variable `{}`", name)
+        }
+        InlineValue::EvaluatableExpression(expression) => {
+            let text = expression
+                .expression
+                .clone()
+                .unwrap_or_else(|| text_at(lines, &expression.range, encoding));
+            format!("expression `{}`", text)
+        }
+    }
+}
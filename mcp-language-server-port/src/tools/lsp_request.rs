@@ -0,0 +1,40 @@
+use crate::lsp::Client;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Result, anyhow};
+use log::debug;
+use serde_json::Value;
+use std::path::Path;
+
+use super::budget;
+
+/// Sends any request to the server and returns its raw JSON result, to reach
+/// server-specific extensions such as `rust-analyzer/expandMacro`. With a
+/// file, the file is opened first so the server knows about it.
+pub async fn send_lsp_request(
+    client: &Client,
+    method: &str,
+    params: Option<Value>,
+    file_path: Option<&Path>,
+) -> Result<String> {
+    debug!("[TOOL] Sending raw request {} to {}", method, client.name());
+
+    // The proxy manages the server's lifecycle and documents itself
+    let last_segment = method.rsplit('/').next().unwrap_or(method);
+    if matches!(method, "initialize" | "shutdown" | "exit") || last_segment.starts_with("did") {
+        return Err(anyhow!(
+            "{} is managed by the proxy and can't be sent directly",
+            method
+        ));
+    }
+
+    if let Some(file_path) = file_path {
+        let file_path = normalize_path(file_path)?;
+        if !file_path.exists() {
+            return Err(anyhow!("File does not exist: {}", file_path.display()));
+        }
+        client.open_file(&file_path).await?;
+    }
+
+    let result: Value = client.call(method, params.unwrap_or(Value::Null)).await?;
+    Ok(budget::fit_lines(&serde_json::to_string_pretty(&result)?))
+}
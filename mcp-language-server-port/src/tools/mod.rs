@@ -1,15 +1,78 @@
+pub mod budget;
+pub mod call_hierarchy;
+pub mod capabilities;
+pub mod code_actions;
+pub mod code_lens;
+pub mod colors;
+pub mod completion;
 pub mod definition;
 pub mod diagnostics;
+pub mod diff;
+pub mod document_symbols;
 pub mod edit;
+pub mod edit_and_check;
+pub mod execute_command;
+pub mod file_hashes;
+pub mod fix_diagnostics;
+pub mod folding_ranges;
+pub mod highlights;
 pub mod hover;
+pub mod implementations;
+pub mod inline_values;
+pub mod lsp_request;
+pub mod moniker;
+pub mod open_documents;
+pub mod project_model;
 pub mod references;
+pub mod related_files;
 pub mod rename;
+pub mod render;
+pub mod resource_ops;
+pub mod restart;
+pub mod scope;
+pub mod selection_ranges;
+pub mod semantic_tokens;
+pub mod status;
+pub mod structured;
+pub mod test_anchor;
+pub mod type_hierarchy;
 pub mod utils;
+pub mod workspace_edit;
+pub mod workspace_folders;
 
 // Re-export tool functions for easy access
+pub use call_hierarchy::{find_incoming_calls, find_outgoing_calls};
+pub use capabilities::get_server_capabilities;
+pub use code_actions::{
+    apply_code_action, get_code_actions, organize_imports, organize_imports_in,
+};
+pub use code_lens::{get_code_lenses, run_code_lens};
+pub use colors::{get_color_presentations, get_document_colors};
+pub use completion::get_completions;
 pub use definition::find_definition;
-pub use diagnostics::get_diagnostics;
+pub use diagnostics::{get_diagnostics, get_workspace_diagnostics};
+pub use document_symbols::get_document_symbols;
 pub use edit::apply_text_edits;
+pub use edit_and_check::edit_and_check;
+pub use execute_command::execute_command;
+pub use file_hashes::get_file_hashes;
+pub use fix_diagnostics::fix_diagnostics;
+pub use folding_ranges::get_folding_ranges;
+pub use highlights::get_document_highlights;
 pub use hover::get_hover_info;
+pub use implementations::find_impls_of_trait;
+pub use inline_values::get_inline_values;
+pub use lsp_request::send_lsp_request;
+pub use moniker::get_monikers;
+pub use open_documents::list_open_documents;
+pub use project_model::get_project_model;
 pub use references::find_references;
+pub use related_files::find_related_files;
 pub use rename::rename_symbol;
+pub use restart::restart_language_server;
+pub use selection_ranges::get_selection_ranges;
+pub use semantic_tokens::get_semantic_tokens;
+pub use status::get_status;
+pub use test_anchor::find_test_anchor;
+pub use type_hierarchy::get_type_hierarchy;
+pub use workspace_folders::{add_workspace_folder, remove_workspace_folder};
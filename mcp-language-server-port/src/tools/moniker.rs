@@ -0,0 +1,86 @@
+use crate::lsp::Client;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Result, anyhow};
+use log::debug;
+use lsp_types::{Moniker, MonikerKind, MonikerParams, Position, TextDocumentPositionParams};
+use std::path::PathBuf;
+
+use super::budget;
+use super::utils::to_text_document_identifier;
+
+/// Gets the monikers (stable, package-qualified identifiers) of the symbol at a position
+pub async fn get_monikers(
+    client: &Client,
+    file_path: PathBuf,
+    line: u32,
+    column: u32,
+) -> Result<String> {
+    debug!(
+        "[TOOL] Getting monikers for {}:{}:{}",
+        file_path.display(),
+        line,
+        column
+    );
+
+    // Get the file's absolute path
+    let file_path = normalize_path(&file_path)?;
+
+    // Ensure the file exists
+    if !file_path.exists() {
+        return Err(anyhow!("File does not exist: {}", file_path.display()));
+    }
+
+    // Ensure the file is open in the LSP server
+    client.open_file(&file_path).await?;
+
+    // Create position params (adjust from 1-indexed to 0-indexed)
+    let params = MonikerParams {
+        text_document_position_params: TextDocumentPositionParams {
+            text_document: to_text_document_identifier(&file_path)?,
+            position: Position {
+                line: line.saturating_sub(1),
+                character: column.saturating_sub(1),
+            },
+        },
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+
+    let monikers: Option<Vec<Moniker>> = client.call("textDocument/moniker", params).await?;
+    let monikers = monikers.unwrap_or_default();
+
+    if monikers.is_empty() {
+        return Ok("No monikers available at this position.".to_string());
+    }
+
+    let mut result = String::new();
+    for moniker in &monikers {
+        result.push_str(&format_moniker(moniker));
+        result.push('\n');
+    }
+    Ok(budget::fit_lines(&result))
+}
+
+/// Formats a moniker as `scheme:identifier` followed by its uniqueness and kind
+pub fn format_moniker(moniker: &Moniker) -> String {
+    // The uniqueness level serializes to its protocol name, e.g. "project"
+    let unique = serde_json::to_value(&moniker.unique)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default();
+
+    let mut details = vec![format!("unique: {}", unique)];
+    match &moniker.kind {
+        Some(MonikerKind::Import) => details.push("kind: import".to_string()),
+        Some(MonikerKind::Export) => details.push("kind: export".to_string()),
+        Some(MonikerKind::Local) => details.push("kind: local".to_string()),
+        None => {}
+    }
+
+    format!(
+        "{}:{} ({})",
+        moniker.scheme,
+        moniker.identifier,
+        details.join(", ")
+    )
+}
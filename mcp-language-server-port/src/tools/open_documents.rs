@@ -0,0 +1,40 @@
+use crate::lsp::Client;
+use anyhow::Result;
+use log::debug;
+
+use super::budget;
+use super::utils::{display_path, to_path};
+
+/// Lists the documents the LSP server currently has open, and whether each
+/// one holds in-memory content that differs from disk
+pub async fn list_open_documents(client: &Client) -> Result<String> {
+    debug!("[TOOL] Listing open documents");
+
+    let mut documents = client.open_documents();
+
+    if documents.is_empty() {
+        return Ok("No documents are open in the language server.".to_string());
+    }
+
+    documents.sort_by(|a, b| a.uri.as_str().cmp(b.uri.as_str()));
+
+    let mut result = format!("{} open documents:\n\n", documents.len());
+
+    for document in documents {
+        let path = to_path(&document.uri)?;
+        let state = if document.dirty {
+            "dirty: server content differs from disk"
+        } else {
+            "in sync with disk"
+        };
+
+        result.push_str(&format!(
+            "  {} (version {}, {})\n",
+            display_path(client, &path),
+            document.version,
+            state
+        ));
+    }
+
+    Ok(budget::fit_lines(&result))
+}
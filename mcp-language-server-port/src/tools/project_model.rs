@@ -0,0 +1,262 @@
+use crate::lsp::Client;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use lsp_types::{ExecuteCommandParams, Url};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use super::budget;
+use super::utils::{display_path, to_path, to_text_document_identifier, to_uri};
+
+/// A crate in the dependency graph rust-analyzer loaded
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CrateInfo {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub path: Url,
+}
+
+/// Response to rust-analyzer's `rust-analyzer/fetchDependencyList`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DependencyList {
+    pub crates: Vec<CrateInfo>,
+}
+
+/// Cargo invocation of a rust-analyzer runnable
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunnableArgs {
+    #[serde(default)]
+    pub cargo_args: Vec<String>,
+    #[serde(default)]
+    pub executable_args: Vec<String>,
+}
+
+/// A target, test or binary rust-analyzer can run, from `experimental/runnables`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Runnable {
+    pub label: String,
+    #[serde(default)]
+    pub args: RunnableArgs,
+}
+
+/// A Go module from gopls' `gopls.packages` command
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GoModule {
+    pub path: String,
+    pub version: Option<String>,
+    #[serde(default)]
+    pub go_mod: Option<Url>,
+}
+
+/// A Go package from gopls' `gopls.packages` command
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GoPackage {
+    pub path: String,
+    #[serde(default)]
+    pub module_path: Option<String>,
+    #[serde(default)]
+    pub for_test: Option<String>,
+}
+
+/// Response to gopls' `gopls.packages` command
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GoPackages {
+    #[serde(default)]
+    pub module: Option<BTreeMap<String, GoModule>>,
+    #[serde(default)]
+    pub packages: Option<Vec<GoPackage>>,
+}
+
+/// Describes the build metadata a known language server has loaded: crates and
+/// cargo targets from rust-analyzer, modules and packages from gopls
+pub async fn get_project_model(client: &Client, file_path: Option<PathBuf>) -> Result<String> {
+    debug!("[TOOL] Getting project model");
+
+    let file_path = match file_path {
+        Some(file_path) => Some(normalize_path(&file_path)?),
+        None => None,
+    };
+
+    // The build model may come from an attached server as well as the primary one
+    let mut servers = vec![client];
+    let attached = client.attached_servers();
+    servers.extend(attached.iter().map(|server| server.as_ref()));
+
+    for server in servers {
+        match server.name() {
+            "rust-analyzer" => return rust_project_model(client, server, file_path).await,
+            "gopls" => return go_project_model(client, server).await,
+            _ => {}
+        }
+    }
+
+    Err(anyhow!(
+        "Project models are only available from rust-analyzer and gopls, not {}",
+        client.name()
+    ))
+}
+
+async fn rust_project_model(
+    client: &Client,
+    server: &Client,
+    file_path: Option<PathBuf>,
+) -> Result<String> {
+    let dependencies: DependencyList = server
+        .call("rust-analyzer/fetchDependencyList", json!({}))
+        .await
+        .context("rust-analyzer did not return its dependency list")?;
+
+    // Runnables are per file; they list the cargo targets and tests in it
+    let mut runnables = Vec::new();
+    if let Some(file_path) = &file_path {
+        server.open_file(file_path).await?;
+        let params = json!({ "textDocument": to_text_document_identifier(file_path)? });
+        runnables = server
+            .call("experimental/runnables", params)
+            .await
+            .context("rust-analyzer did not return runnables")?;
+    }
+
+    let mut result = format_rust_project(client, &dependencies);
+    if let Some(file_path) = &file_path {
+        result.push_str(&format!(
+            "\nRunnables in {}:\n",
+            display_path(client, file_path)
+        ));
+        result.push_str(&format_runnables(&runnables));
+    }
+    Ok(budget::fit_lines(&result))
+}
+
+async fn go_project_model(client: &Client, server: &Client) -> Result<String> {
+    let root = client
+        .workspace_root()
+        .ok_or_else(|| anyhow!("The language server has not been initialized"))?;
+
+    let params = ExecuteCommandParams {
+        command: "gopls.packages".to_string(),
+        arguments: vec![json!({
+            "Files": [to_uri(&root)],
+            "Recursive": true,
+            "Mode": 0,
+        })],
+        work_done_progress_params: Default::default(),
+    };
+    let packages: Option<GoPackages> = server
+        .call("workspace/executeCommand", params)
+        .await
+        .context("gopls did not list the workspace packages")?;
+
+    Ok(budget::fit_lines(&format_go_project(
+        client,
+        &packages.unwrap_or_default(),
+    )))
+}
+
+/// Lists the workspace crates and dependencies rust-analyzer loaded
+fn format_rust_project(client: &Client, dependencies: &DependencyList) -> String {
+    let workspace_root = client.workspace_root();
+    let (members, dependencies): (Vec<&CrateInfo>, Vec<&CrateInfo>) =
+        dependencies.crates.iter().partition(|info| {
+            let path = to_path(&info.path).ok();
+            matches!((&workspace_root, path), (Some(root), Some(path)) if path.starts_with(root))
+        });
+
+    let mut result = format!("Workspace crates ({}):\n", members.len());
+    for info in members {
+        let path = to_path(&info.path).unwrap_or_default();
+        result.push_str(&format!(
+            "  {} ({})\n",
+            format_crate(info),
+            display_path(client, &path)
+        ));
+    }
+    result.push_str(&format!("\nDependencies ({}):\n", dependencies.len()));
+    for info in dependencies {
+        result.push_str(&format!("  {}\n", format_crate(info)));
+    }
+    result
+}
+
+/// Formats a crate as `name version`
+pub fn format_crate(info: &CrateInfo) -> String {
+    let name = info.name.as_deref().unwrap_or("(unnamed)");
+    match &info.version {
+        Some(version) => format!("{} {}", name, version),
+        None => name.to_string(),
+    }
+}
+
+/// Formats runnables with the cargo command that runs each
+pub fn format_runnables(runnables: &[Runnable]) -> String {
+    if runnables.is_empty() {
+        return "  none\n".to_string();
+    }
+
+    let mut result = String::new();
+    for runnable in runnables {
+        let mut command = format!("cargo {}", runnable.args.cargo_args.join(" "));
+        if !runnable.args.executable_args.is_empty() {
+            command.push_str(&format!(" -- {}", runnable.args.executable_args.join(" ")));
+        }
+        result.push_str(&format!("  {}: {}\n", runnable.label, command.trim_end()));
+    }
+    result
+}
+
+/// Lists Go modules with their packages
+fn format_go_project(client: &Client, packages: &GoPackages) -> String {
+    format_go_packages(packages, |uri| {
+        to_path(uri)
+            .map(|path| display_path(client, &path))
+            .unwrap_or_else(|_| uri.to_string())
+    })
+}
+
+/// Lists Go modules with their packages, test variants left out
+pub fn format_go_packages(packages: &GoPackages, display: impl Fn(&Url) -> String) -> String {
+    let modules = packages.module.clone().unwrap_or_default();
+    let mut by_module: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for package in packages.packages.iter().flatten() {
+        if package.for_test.as_deref().is_some_and(|t| !t.is_empty()) {
+            continue;
+        }
+        by_module
+            .entry(package.module_path.clone().unwrap_or_default())
+            .or_default()
+            .push(&package.path);
+    }
+
+    let mut result = format!("Go modules ({}):\n", by_module.len());
+    for (module_path, package_paths) in &mut by_module {
+        let name = if module_path.is_empty() {
+            "(no module)"
+        } else {
+            module_path.as_str()
+        };
+        result.push_str(&format!("  {}", name));
+        if let Some(module) = modules.get(module_path) {
+            if let Some(version) = &module.version {
+                result.push_str(&format!(" {}", version));
+            }
+            if let Some(go_mod) = &module.go_mod {
+                result.push_str(&format!(" ({})", display(go_mod)));
+            }
+        }
+        result.push('\n');
+
+        package_paths.sort();
+        package_paths.dedup();
+        for package_path in package_paths.iter() {
+            result.push_str(&format!("    {}\n", package_path));
+        }
+    }
+    result
+}
@@ -1,23 +1,158 @@
 use crate::lsp::Client;
+use crate::lsp::PositionEncoding;
+use crate::lsp::position::{byte_column, char_column, column};
 use anyhow::{Context, Result, anyhow};
 use log::debug;
-use lsp_types::{Location, Position, ReferenceContext, ReferenceParams};
-use std::{collections::HashMap, path::PathBuf};
+use lsp_types::{
+    DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse, Location, OneOf,
+    PartialResultParams, Position, Range, ReferenceContext, ReferenceParams, SymbolKind, Url,
+    WorkDoneProgressParams, WorkspaceSymbolParams, WorkspaceSymbolResponse,
+};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
 use tokio::fs;
 
-use super::definition::parse_symbol_location;
-use super::utils::{to_path, to_text_document_identifier};
+use super::budget::{self, Block};
+use super::definition::{location_results, parse_symbol_location};
+use super::render::{self, OutputFormat};
+use super::structured::{ReferencesResult, to_json};
+use super::utils::{
+    context_window, display_path, format_count, format_numbered_line, format_servers,
+    merge_server_locations, range_contains, to_path, to_text_document_identifier,
+};
 
-/// Finds all references to a symbol
-pub async fn find_references(client: &Client, symbol_name: &str) -> Result<String> {
-    debug!("[TOOL] Finding references for symbol: {}", symbol_name);
+/// Limits which references are reported, relative to the queried position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceScope {
+    /// Every reference in the workspace
+    Workspace,
+    /// References in the queried file
+    File,
+    /// References inside the function or method enclosing the queried position
+    Function,
+    /// References inside the module or namespace enclosing the queried position
+    Module,
+}
+
+impl ReferenceScope {
+    /// Parses a scope name as accepted by the references tool
+    pub fn parse(scope: &str) -> Result<Self> {
+        match scope {
+            "workspace" => Ok(Self::Workspace),
+            "file" => Ok(Self::File),
+            "function" => Ok(Self::Function),
+            "module" => Ok(Self::Module),
+            _ => Err(anyhow!(
+                "Unknown scope '{}': expected workspace, file, function or module",
+                scope
+            )),
+        }
+    }
+
+    /// Symbol kinds that delimit this scope; empty for scopes that don't depend
+    /// on the document's symbols
+    pub fn symbol_kinds(self) -> &'static [SymbolKind] {
+        match self {
+            Self::Workspace | Self::File => &[],
+            Self::Function => &[
+                SymbolKind::FUNCTION,
+                SymbolKind::METHOD,
+                SymbolKind::CONSTRUCTOR,
+            ],
+            Self::Module => &[
+                SymbolKind::MODULE,
+                SymbolKind::NAMESPACE,
+                SymbolKind::PACKAGE,
+            ],
+        }
+    }
+}
+
+/// References reported by the references tool when the call doesn't say
+pub const DEFAULT_MAX_REFERENCES: usize = 200;
+
+/// Which references a call reports, after sorting them by path and position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReferencePage {
+    /// References skipped, counted after the per-file cap
+    pub offset: usize,
+    /// Most references reported, or all if `None`
+    pub max_results: Option<usize>,
+    /// Most references reported from any one file, or all if `None`
+    pub max_per_file: Option<usize>,
+}
+
+impl ReferencePage {
+    /// Sorts references by path and position, caps them per file and picks
+    /// the page. Also returns how many references the per-file cap left out.
+    pub fn apply(
+        &self,
+        mut locations: Vec<(Location, Vec<String>)>,
+    ) -> (Vec<(Location, Vec<String>)>, usize) {
+        locations.sort_by(|(a, _), (b, _)| {
+            (a.uri.path(), a.range.start).cmp(&(b.uri.path(), b.range.start))
+        });
+
+        let before = locations.len();
+        if let Some(max_per_file) = self.max_per_file {
+            let mut counts: HashMap<Url, usize> = HashMap::new();
+            locations.retain(|(location, _)| {
+                let count = counts.entry(location.uri.clone()).or_default();
+                *count += 1;
+                *count <= max_per_file
+            });
+        }
+        let capped = before - locations.len();
 
-    // Parse the symbol location
-    let (file_path, line, column) = parse_symbol_location(symbol_name)?;
+        let page = locations
+            .into_iter()
+            .skip(self.offset)
+            .take(self.max_results.unwrap_or(usize::MAX))
+            .collect();
+        (page, capped)
+    }
+}
+
+/// Finds all references to a symbol, sorted by path and position and limited
+/// to `page`. With `context_lines`, that many lines around each reference are
+/// shown too, numbered.
+pub async fn find_references(
+    client: &Client,
+    symbol_name: &str,
+    scope: ReferenceScope,
+    context_lines: u32,
+    page: &ReferencePage,
+) -> Result<String> {
+    debug!(
+        "[TOOL] Finding references for symbol: {} ({:?} scope)",
+        symbol_name, scope
+    );
+
+    // Take a path:line:column location, or look a bare name up in the workspace
+    let mut note = String::new();
+    let (file_path, line, column) = match parse_symbol_location(symbol_name) {
+        Ok(location) => location,
+        Err(_) => {
+            let mut candidates = resolve_symbol(client, symbol_name).await?;
+            if candidates.len() > 1 {
+                note = format!(
+                    "\nNote: {} other symbols are named '{}'; pass path:line:column to pick another.\n",
+                    candidates.len() - 1,
+                    symbol_name
+                );
+            }
+            candidates.remove(0)
+        }
+    };
 
     // Ensure the file is open
     client.open_file(&file_path).await?;
 
+    // References can take a while on large workspaces, so let the server
+    // report progress and stream partial results
+    let partial_result_token = client.new_progress_token();
+
     // Create reference params
     let reference_params = ReferenceParams {
         text_document_position: lsp_types::TextDocumentPositionParams {
@@ -30,47 +165,175 @@ pub async fn find_references(client: &Client, symbol_name: &str) -> Result<Strin
         context: ReferenceContext {
             include_declaration: true,
         },
-        work_done_progress_params: Default::default(),
-        partial_result_params: Default::default(),
+        work_done_progress_params: WorkDoneProgressParams {
+            work_done_token: Some(client.new_progress_token()),
+        },
+        partial_result_params: PartialResultParams {
+            partial_result_token: Some(partial_result_token.clone()),
+        },
     };
 
-    // Call the LSP references request
-    let locations: Vec<Location> = client
-        .call("textDocument/references", reference_params)
-        .await?;
+    // Call the LSP references request on every server that handles the file,
+    // so cross-language references are found whichever server owns the symbol
+    let mut answers = Vec::new();
+    let mut first_error = None;
+    if client.is_file_open(&file_path) {
+        match client
+            .call_with_partial_results(
+                "textDocument/references",
+                reference_params.clone(),
+                &partial_result_token,
+            )
+            .await
+        {
+            Ok(locations) => answers.push((client.name().to_string(), locations)),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    for server in client.attached_servers() {
+        if !server.is_file_open(&file_path) {
+            continue;
+        }
+        // Progress tokens belong to the primary server, so don't pass them on
+        let params = ReferenceParams {
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            ..reference_params.clone()
+        };
+        match server
+            .call::<_, Option<Vec<Location>>>("textDocument/references", params)
+            .await
+        {
+            Ok(Some(locations)) => answers.push((server.name().to_string(), locations)),
+            Ok(None) => answers.push((server.name().to_string(), Vec::new())),
+            Err(e) => {
+                debug!("[TOOL] {} found no references: {}", server.name(), e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    if answers.is_empty() {
+        return Err(first_error
+            .unwrap_or_else(|| anyhow!("No language server has {} open", file_path.display())));
+    }
+
+    // Only attribute results when more than one server answered
+    let attribute = answers.len() > 1;
+    let locations = merge_server_locations(answers);
 
     if locations.is_empty() {
         return Err(anyhow!("No references found for symbol: {}", symbol_name));
     }
 
+    // Narrow the results to the requested scope around the queried position
+    let query_uri = to_text_document_identifier(&file_path)?.uri;
+    let locations = match scope {
+        ReferenceScope::Workspace => locations,
+        ReferenceScope::File => locations
+            .into_iter()
+            .filter(|(location, _)| location.uri == query_uri)
+            .collect(),
+        ReferenceScope::Function | ReferenceScope::Module => {
+            let params = DocumentSymbolParams {
+                text_document: lsp_types::TextDocumentIdentifier {
+                    uri: query_uri.clone(),
+                },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            };
+            let symbols: Option<DocumentSymbolResponse> = client
+                .call("textDocument/documentSymbol", params)
+                .await
+                .context("Failed to resolve the enclosing scope")?;
+
+            let position = Position {
+                line,
+                character: column,
+            };
+            let enclosing = symbols.and_then(|symbols| {
+                enclosing_symbol_range(&symbols, position, scope.symbol_kinds())
+            });
+            let Some(enclosing) = enclosing else {
+                return Err(anyhow!(
+                    "No enclosing {:?} found at {}:{}:{}",
+                    scope,
+                    display_path(client, &file_path),
+                    line + 1,
+                    column + 1
+                ));
+            };
+
+            locations
+                .into_iter()
+                .filter(|(location, _)| {
+                    location.uri == query_uri && range_contains(&enclosing, &location.range)
+                })
+                .collect()
+        }
+    };
+
+    // Report the references in a stable order, a page at a time
+    let total = locations.len();
+    let total_files = locations
+        .iter()
+        .map(|(location, _)| &location.uri)
+        .collect::<HashSet<_>>()
+        .len();
+    let (locations, capped) = page.apply(locations);
+
+    if render::current_format() == OutputFormat::Json {
+        let shown = locations.len();
+        return to_json(&ReferencesResult {
+            references: location_results(&locations, attribute)?,
+            total,
+            capped,
+            next_offset: next_offset(page, shown, total, capped),
+        });
+    }
+
+    if total == 0 {
+        return Ok(format!(
+            "No references to '{}' found in the requested {:?} scope",
+            symbol_name, scope
+        ));
+    }
+    if locations.is_empty() {
+        return Ok(format!(
+            "Offset {} is past the {} references to '{}'",
+            page.offset,
+            format_count(total - capped),
+            symbol_name
+        ));
+    }
+    let shown = locations.len();
+
     // Group references by file
-    let mut references_by_file: HashMap<PathBuf, Vec<Location>> = HashMap::new();
+    let mut references_by_file: BTreeMap<PathBuf, Vec<(Location, Vec<String>)>> = BTreeMap::new();
 
-    for location in locations {
+    for (location, servers) in locations {
         let file_path = to_path(&location.uri)?;
         references_by_file
             .entry(file_path)
             .or_default()
-            .push(location);
+            .push((location, servers));
     }
 
-    // For each file, get the content and format the references
-    let mut result = String::new();
-
     // Add summary line
-    let reference_count = references_by_file
-        .values()
-        .map(|locs| locs.len())
-        .sum::<usize>();
-    result.push_str(&format!(
+    let summary = format!(
         "Found {} references to '{}' in {} files:\n\n",
-        reference_count,
+        format_count(total),
         symbol_name,
-        references_by_file.len()
-    ));
+        format_count(total_files)
+    );
+
+    // For each file, get the content and format the references
+    let encoding = client.position_encoding();
+    let mut blocks = Vec::new();
 
     for (file_path, locations) in references_by_file {
-        result.push_str(&format!("File: {}\n", file_path.display()));
+        let mut block = Block::new(format!("File: {}\n", display_path(client, &file_path)));
 
         // Read the file content
         let content = fs::read_to_string(&file_path)
@@ -80,24 +343,280 @@ pub async fn find_references(client: &Client, symbol_name: &str) -> Result<Strin
         let lines: Vec<&str> = content.lines().collect();
 
         // For each location, extract the line containing the reference
-        for location in locations {
+        let mut summary = String::new();
+        for (location, servers) in locations {
             let line_num = location.range.start.line as usize;
-            let col_num = location.range.start.character as usize;
+            let character = location.range.start.character;
 
             if line_num < lines.len() {
                 let line_content = lines[line_num];
 
-                // Format the line with the reference
-                result.push_str(&format!("  Line {}: {}\n", line_num + 1, line_content));
-
-                // Add a pointer to the exact position
-                let pointer = format!("  {}{}\n", " ".repeat(col_num + 7), "^");
-                result.push_str(&pointer);
+                // Format the line with the reference and a pointer to the exact position
+                summary.push_str(&format!(
+                    "{}:{}:{}: {}\n",
+                    display_path(client, &file_path),
+                    line_num + 1,
+                    character + 1,
+                    line_content.trim()
+                ));
+                let servers = if attribute {
+                    format_servers(&servers)
+                } else {
+                    String::new()
+                };
+                let pointer = " ".repeat(char_column(line_content, character, encoding));
+                if context_lines == 0 {
+                    block.context.push(format!(
+                        "  Line {}: {}\n         {}^{}\n",
+                        line_num + 1,
+                        line_content,
+                        pointer,
+                        servers
+                    ));
+                } else {
+                    // Show the surrounding lines, with the pointer under the reference
+                    let mut context = String::new();
+                    for i in context_window(lines.len(), line_num, line_num, context_lines) {
+                        context.push_str(&format_numbered_line(&lines, i));
+                        if i == line_num {
+                            context.push_str(&format!("      | {}^{}\n", pointer, servers));
+                        }
+                    }
+                    block.context.push(format!("{}\n", context));
+                }
             }
         }
 
-        result.push('\n');
+        block.summary = Some(summary);
+        blocks.push(block);
     }
 
+    let mut result = budget::fit(&summary, blocks, "\n");
+    if shown < total {
+        result.push_str(&format_page_summary(page, shown, total, capped));
+    }
+    result.push_str(&note);
     Ok(result)
 }
+
+/// Gets the offset of the page after one that showed `shown` references, if
+/// any references are left after it
+fn next_offset(page: &ReferencePage, shown: usize, total: usize, capped: usize) -> Option<usize> {
+    let next = page.offset + shown;
+    (next < total - capped).then_some(next)
+}
+
+/// Summarizes which references a page showed, such as
+/// `Showing 1-50 of 1,243 references; pass offset 50 for more.`
+fn format_page_summary(page: &ReferencePage, shown: usize, total: usize, capped: usize) -> String {
+    let mut result = format!(
+        "\nShowing {}-{} of {} references",
+        format_count(page.offset + 1),
+        format_count(page.offset + shown),
+        format_count(total)
+    );
+    if let Some(max_per_file) = page.max_per_file
+        && capped > 0
+    {
+        result.push_str(&format!(
+            ", leaving out {} beyond {} per file",
+            format_count(capped),
+            max_per_file
+        ));
+    }
+    if let Some(offset) = next_offset(page, shown, total, capped) {
+        result.push_str(&format!("; pass offset {} for more", offset));
+    }
+    result.push_str(".\n");
+    result
+}
+
+/// Searches the workspace symbols matching `query`. Searches can take a while
+/// on large workspaces, so the server may report progress and stream partial
+/// results, which are collected ahead of the final response.
+pub async fn workspace_symbols(
+    client: &Client,
+    query: &str,
+) -> Result<Option<WorkspaceSymbolResponse>> {
+    let partial_result_token = client.new_progress_token();
+    let params = WorkspaceSymbolParams {
+        query: query.to_string(),
+        work_done_progress_params: WorkDoneProgressParams {
+            work_done_token: Some(client.new_progress_token()),
+        },
+        partial_result_params: PartialResultParams {
+            partial_result_token: Some(partial_result_token.clone()),
+        },
+    };
+    let symbols: Vec<Value> = client
+        .call_with_partial_results("workspace/symbol", params, &partial_result_token)
+        .await?;
+    if symbols.is_empty() {
+        return Ok(None);
+    }
+
+    // Every batch holds the same kind of symbol, so the whole list parses as one response
+    Ok(Some(serde_json::from_value(Value::Array(symbols))?))
+}
+
+/// Finds the positions of the symbols named `symbol_name` through a workspace
+/// symbol search, most likely first
+pub async fn resolve_symbol(
+    client: &Client,
+    symbol_name: &str,
+) -> Result<Vec<(PathBuf, u32, u32)>> {
+    // Search for the unqualified name; qualifiers are matched against containers
+    let query = symbol_name.rsplit([':', '.']).next().unwrap_or(symbol_name);
+    let symbols = workspace_symbols(client, query)
+        .await
+        .context("Failed to search workspace symbols")?;
+
+    let locations = symbols
+        .map(|symbols| symbol_locations(&symbols, symbol_name))
+        .unwrap_or_default();
+    if locations.is_empty() {
+        return Err(anyhow!(
+            "No symbol named '{}' found; pass a location as path:line:column instead",
+            symbol_name
+        ));
+    }
+
+    let mut candidates = Vec::new();
+    for location in locations {
+        let file_path = to_path(&location.uri)?;
+
+        // Symbol ranges usually start at a keyword or doc comment, so point at the name itself
+        let content = fs::read_to_string(&file_path)
+            .await
+            .context(format!("Failed to read file: {}", file_path.display()))?;
+        let position = name_position(&content, &location.range, query, client.position_encoding());
+        candidates.push((file_path, position.line, position.character));
+    }
+    Ok(candidates)
+}
+
+/// Gets the locations of workspace symbols matching a name. Qualified names
+/// such as `Type::method` or `pkg.Func` also match a symbol whose container
+/// ends with the qualifier.
+pub fn symbol_locations(symbols: &WorkspaceSymbolResponse, symbol_name: &str) -> Vec<Location> {
+    let matches = |name: &str, container: Option<&str>| {
+        if name == symbol_name {
+            return true;
+        }
+        ["::", "."].iter().any(|separator| {
+            symbol_name
+                .rsplit_once(separator)
+                .is_some_and(|(qualifier, last)| {
+                    last == name
+                        && container.is_some_and(|container| {
+                            container == qualifier
+                                || container.ends_with(&format!("{}{}", separator, qualifier))
+                        })
+                })
+        })
+    };
+
+    match symbols {
+        WorkspaceSymbolResponse::Flat(symbols) => symbols
+            .iter()
+            .filter(|s| matches(&s.name, s.container_name.as_deref()))
+            .map(|s| s.location.clone())
+            .collect(),
+        WorkspaceSymbolResponse::Nested(symbols) => symbols
+            .iter()
+            .filter(|s| matches(&s.name, s.container_name.as_deref()))
+            .filter_map(|s| match &s.location {
+                OneOf::Left(location) => Some(location.clone()),
+                OneOf::Right(_) => None,
+            })
+            .collect(),
+    }
+}
+
+/// Finds the first whole-word occurrence of `name` within `range`, falling
+/// back to the start of the range
+pub fn name_position(
+    content: &str,
+    range: &Range,
+    name: &str,
+    encoding: PositionEncoding,
+) -> Position {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+
+    for (line_num, line) in content
+        .lines()
+        .enumerate()
+        .skip(range.start.line as usize)
+        .take(range.end.line.saturating_sub(range.start.line) as usize + 1)
+    {
+        let from = if line_num == range.start.line as usize {
+            byte_column(line, range.start.character, encoding)
+        } else {
+            0
+        };
+        let Some(rest) = line.get(from..) else {
+            continue;
+        };
+
+        for (offset, _) in rest.match_indices(name) {
+            let start = from + offset;
+            let before = line[..start].chars().next_back();
+            let after = line[start + name.len()..].chars().next();
+            if !before.is_some_and(is_ident) && !after.is_some_and(is_ident) {
+                return Position {
+                    line: line_num as u32,
+                    character: column(line, start, encoding),
+                };
+            }
+        }
+    }
+
+    range.start
+}
+
+/// Finds the range of the innermost symbol of one of `kinds` that encloses `position`
+pub fn enclosing_symbol_range(
+    symbols: &DocumentSymbolResponse,
+    position: Position,
+    kinds: &[SymbolKind],
+) -> Option<Range> {
+    let point = Range {
+        start: position,
+        end: position,
+    };
+
+    match symbols {
+        DocumentSymbolResponse::Flat(symbols) => symbols
+            .iter()
+            .filter(|s| kinds.contains(&s.kind) && range_contains(&s.location.range, &point))
+            .map(|s| s.location.range)
+            // Flat symbols carry no nesting; the latest-starting match is the innermost
+            .max_by_key(|range| range.start),
+        DocumentSymbolResponse::Nested(symbols) => innermost_nested_range(symbols, &point, kinds),
+    }
+}
+
+fn innermost_nested_range(
+    symbols: &[DocumentSymbol],
+    point: &Range,
+    kinds: &[SymbolKind],
+) -> Option<Range> {
+    for symbol in symbols {
+        if !range_contains(&symbol.range, point) {
+            continue;
+        }
+
+        let inner = symbol
+            .children
+            .as_deref()
+            .and_then(|children| innermost_nested_range(children, point, kinds));
+        if inner.is_some() {
+            return inner;
+        }
+        if kinds.contains(&symbol.kind) {
+            return Some(symbol.range);
+        }
+    }
+
+    None
+}
@@ -0,0 +1,260 @@
+use crate::lsp::Client;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Result, anyhow};
+use log::debug;
+use lsp_types::{DocumentLink, DocumentLinkParams};
+use std::path::{Path, PathBuf};
+
+use super::budget;
+use super::utils::{display_path, to_text_document_identifier};
+
+/// Most siblings listed for a file
+const MAX_SIBLINGS: usize = 15;
+
+/// Suggests files related to a file: its tests, its header/source counterpart,
+/// files it links to, and sibling modules
+pub async fn find_related_files(client: &Client, file_path: PathBuf) -> Result<String> {
+    debug!("[TOOL] Finding files related to {}", file_path.display());
+
+    // Get the file's absolute path
+    let file_path = normalize_path(&file_path)?;
+
+    // Ensure the file exists
+    if !file_path.exists() {
+        return Err(anyhow!("File does not exist: {}", file_path.display()));
+    }
+
+    let workspace_root = client.workspace_root();
+    let mut result = format!("Related files for {}:\n", display_path(client, &file_path));
+
+    // Tests, or the code under test when the file is itself a test
+    if is_test_file(&file_path) {
+        let sources = existing(source_file_candidates(&file_path));
+        push_section(client, &mut result, "Code under test", &sources);
+    } else {
+        let candidates = test_file_candidates(&file_path, workspace_root.as_deref());
+        let tests = existing(candidates.clone());
+        if tests.is_empty() {
+            result.push_str("\nTests:\n  none found");
+            if let Some(suggested) = candidates.first() {
+                result.push_str(&format!(
+                    "; suggested location: {}",
+                    display_path(client, suggested)
+                ));
+            }
+            result.push('\n');
+        } else {
+            push_section(client, &mut result, "Tests", &tests);
+        }
+    }
+
+    let counterparts = existing(counterpart_candidates(&file_path));
+    push_section(client, &mut result, "Counterparts", &counterparts);
+
+    // Files the server links to from this one, such as imports and includes
+    client.open_file(&file_path).await?;
+    let params = DocumentLinkParams {
+        text_document: to_text_document_identifier(&file_path)?,
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+    let links: Option<Vec<DocumentLink>> =
+        match client.call("textDocument/documentLink", params).await {
+            Ok(links) => links,
+            Err(e) => {
+                debug!("[TOOL] No document links for related files: {}", e);
+                None
+            }
+        };
+    let mut linked: Vec<PathBuf> = links
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|link| link.target?.to_file_path().ok())
+        .filter(|path| path.is_file() && *path != file_path)
+        .collect();
+    linked.sort();
+    linked.dedup();
+    push_section(client, &mut result, "Linked from this file", &linked);
+
+    let siblings = sibling_files(&file_path);
+    push_section(client, &mut result, "Siblings", &siblings);
+
+    Ok(budget::fit_lines(&result))
+}
+
+fn push_section(client: &Client, result: &mut String, title: &str, paths: &[PathBuf]) {
+    if paths.is_empty() {
+        return;
+    }
+
+    result.push_str(&format!("\n{}:\n", title));
+    for path in paths {
+        result.push_str(&format!("  {}\n", display_path(client, path)));
+    }
+}
+
+fn existing(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut found: Vec<PathBuf> = paths.into_iter().filter(|p| p.is_file()).collect();
+    found.dedup();
+    found
+}
+
+/// Splits a file name into its stem and extension
+fn stem_and_extension(path: &Path) -> (String, String) {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_default();
+    (stem, extension)
+}
+
+/// Checks whether a file looks like a test by the usual naming conventions
+pub fn is_test_file(path: &Path) -> bool {
+    let (stem, _) = stem_and_extension(path);
+    let in_test_dir = matches!(
+        path.parent()
+            .and_then(|dir| dir.file_name())
+            .and_then(|name| name.to_str()),
+        Some("tests") | Some("test") | Some("__tests__")
+    );
+
+    in_test_dir
+        || stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with(".test")
+        || stem.ends_with(".spec")
+        || (stem.ends_with("Test") && stem.len() > 4)
+}
+
+/// Lists the places a test for `path` would conventionally live, most likely first
+pub fn test_file_candidates(path: &Path, workspace_root: Option<&Path>) -> Vec<PathBuf> {
+    let (stem, extension) = stem_and_extension(path);
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+
+    let names: Vec<String> = match extension.as_str() {
+        "go" => vec![format!("{}_test.go", stem)],
+        "py" => vec![format!("test_{}.py", stem), format!("{}_test.py", stem)],
+        "ts" | "tsx" | "js" | "jsx" | "mjs" => vec![
+            format!("{}.test.{}", stem, extension),
+            format!("{}.spec.{}", stem, extension),
+        ],
+        "rs" => vec![format!("{}_test.rs", stem), format!("{}.rs", stem)],
+        "java" | "kt" => vec![format!("{}Test.{}", stem, extension)],
+        "c" | "cc" | "cpp" | "h" | "hpp" => vec![
+            format!("{}_test.{}", stem, extension),
+            format!("test_{}.{}", stem, extension),
+        ],
+        _ => vec![format!("{}_test.{}", stem, extension)],
+    };
+
+    // Rust integration tests and Python/JS test folders hold tests by convention
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    if extension != "rs" {
+        dirs.push(dir.to_path_buf());
+    }
+    dirs.push(dir.join("tests"));
+    dirs.push(dir.join("__tests__"));
+    if let Some(root) = workspace_root {
+        dirs.push(root.join("tests"));
+        dirs.push(root.join("test"));
+    }
+
+    let mut candidates = Vec::new();
+    for candidate in dirs
+        .iter()
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+    {
+        // A test named like the source file only makes sense in a test folder
+        if candidate != path && !candidates.contains(&candidate) {
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+/// Lists the files a test file is likely to be testing
+pub fn source_file_candidates(path: &Path) -> Vec<PathBuf> {
+    let (stem, extension) = stem_and_extension(path);
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+
+    let source_stem = stem
+        .strip_prefix("test_")
+        .or_else(|| stem.strip_suffix("_test"))
+        .or_else(|| stem.strip_suffix(".test"))
+        .or_else(|| stem.strip_suffix(".spec"))
+        .or_else(|| stem.strip_suffix("Test"))
+        .unwrap_or(&stem);
+    let name = format!("{}.{}", source_stem, extension);
+
+    let mut candidates = vec![dir.join(&name)];
+    // Tests kept in a test folder usually sit next to the code they test
+    if let Some(parent) = dir.parent() {
+        candidates.push(parent.join(&name));
+        candidates.push(parent.join("src").join(&name));
+    }
+    candidates.retain(|candidate| candidate != path);
+    candidates
+}
+
+/// Lists the header for a source file or the source files for a header
+pub fn counterpart_candidates(path: &Path) -> Vec<PathBuf> {
+    let (stem, extension) = stem_and_extension(path);
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+
+    let extensions: &[&str] = match extension.as_str() {
+        "h" => &["c", "cpp", "cc", "m", "mm"],
+        "hpp" | "hh" | "hxx" => &["cpp", "cc", "cxx"],
+        "c" | "m" => &["h"],
+        "cpp" | "cc" | "cxx" | "mm" => &["h", "hpp", "hh", "hxx"],
+        _ => &[],
+    };
+
+    // Headers and sources are often split into include/ and src/ folders
+    let mut dirs = vec![dir.to_path_buf()];
+    if let Some(parent) = dir.parent() {
+        dirs.push(parent.join("include"));
+        dirs.push(parent.join("src"));
+    }
+
+    let mut candidates = Vec::new();
+    for dir in &dirs {
+        for ext in extensions {
+            candidates.push(dir.join(format!("{}.{}", stem, ext)));
+        }
+    }
+    candidates
+}
+
+/// Lists other files of the same kind in the same directory
+fn sibling_files(path: &Path) -> Vec<PathBuf> {
+    let (_, extension) = stem_and_extension(path);
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut siblings: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|sibling| {
+            sibling != path
+                && sibling.is_file()
+                && stem_and_extension(sibling).1 == extension
+                && !is_test_file(sibling)
+        })
+        .collect();
+    siblings.sort();
+    siblings.truncate(MAX_SIBLINGS);
+    siblings
+}
@@ -1,19 +1,39 @@
 use crate::lsp::Client;
-use anyhow::{Context, Result, anyhow};
+use crate::workspace::paths::normalize_path;
+use anyhow::{Result, anyhow};
 use log::debug;
-use lsp_types::{OneOf, Position, RenameParams, WorkspaceEdit};
+use lsp_types::{
+    DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse, OneOf, Position, Range,
+    RenameParams, SymbolKind, Url, WorkspaceEdit,
+};
+use std::collections::HashMap;
 use std::path::PathBuf;
-use tokio::fs;
 
-use super::utils::{to_path, to_text_document_identifier};
+use super::budget;
+use super::render::{self, OutputFormat};
+use super::structured::{EditsApplied, to_json};
+use super::utils::{display_path, range_contains, to_path, to_text_document_identifier};
+use super::workspace_edit::{self, ApplyOptions, EditReport};
+
+/// An existing symbol that already uses the name a rename is about to introduce
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenameConflict {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub range: Range,
+}
 
-/// Renames a symbol across the workspace
+/// Renames a symbol across the workspace. With `dry_run` the edits are
+/// returned as diffs instead of applied, and with `show_diff` the applied
+/// changes are returned as diffs too.
 pub async fn rename_symbol(
     client: &Client,
     file_path: PathBuf,
     line: u32,
     column: u32,
     new_name: String,
+    allow_conflicts: bool,
+    options: &ApplyOptions,
 ) -> Result<String> {
     debug!(
         "[TOOL] Renaming symbol at {}:{}:{} to '{}'",
@@ -24,10 +44,7 @@ pub async fn rename_symbol(
     );
 
     // Get the file's absolute path
-    let file_path = file_path.canonicalize().context(format!(
-        "Failed to canonicalize path: {}",
-        file_path.display()
-    ))?;
+    let file_path = normalize_path(&file_path)?;
 
     // Ensure the file exists
     if !file_path.exists() {
@@ -49,175 +66,209 @@ pub async fn rename_symbol(
                 character: column,
             },
         },
-        new_name,
+        new_name: new_name.clone(),
         work_done_progress_params: Default::default(),
     };
 
     // Call the LSP rename request
     let edit: WorkspaceEdit = client.call("textDocument/rename", rename_params).await?;
 
+    // Look for existing symbols the new name would collide with
+    let conflicts = check_rename_conflicts(client, &edit, &new_name).await?;
+    let warning = format_conflicts(client, &new_name, &conflicts);
+
+    // A preview shows the edits whether or not they conflict
+    if options.dry_run {
+        let report = workspace_edit::apply(client, edit, options).await?;
+        return format_report(&report, &warning);
+    }
+
+    if !conflicts.is_empty() && !allow_conflicts {
+        return Err(anyhow!(
+            "{}Rename was not applied. Pass allow_conflicts to apply it anyway.",
+            warning
+        ));
+    }
+
     // Apply the edits
-    let result = apply_workspace_edit(client, edit).await?;
+    let report = workspace_edit::apply(client, edit, options).await?;
 
-    Ok(result)
+    format_report(&report, &warning)
 }
 
-/// Applies a workspace edit returned by the LSP server
-async fn apply_workspace_edit(client: &Client, edit: WorkspaceEdit) -> Result<String> {
-    let mut files_changed = 0;
-    let mut edits_applied = 0;
-
-    // Process changes
-    if let Some(changes) = edit.changes {
-        for (uri, edits) in changes {
-            let file_path = to_path(&uri)?;
-
-            // Read the file content
-            let content = fs::read_to_string(&file_path)
-                .await
-                .context(format!("Failed to read file: {}", file_path.display()))?;
-
-            // Apply the edits
-            let mut new_content = content.clone();
-
-            // Apply edits in reverse to avoid position changes
-            for text_edit in edits.iter().rev() {
-                // Convert the LSP positions to string indices
-                let start_line = text_edit.range.start.line as usize;
-                let start_char = text_edit.range.start.character as usize;
-                let end_line = text_edit.range.end.line as usize;
-                let end_char = text_edit.range.end.character as usize;
-
-                // Split into lines
-                let lines: Vec<&str> = new_content.lines().collect();
-
-                // Calculate start and end indices
-                let mut start_index = 0;
-                for i in 0..start_line {
-                    if i < lines.len() {
-                        start_index += lines[i].len() + 1; // +1 for the newline
-                    }
-                }
-                start_index += start_char;
+/// Formats the outcome of a rename after the conflict warning, if any
+fn format_report(report: &EditReport, warning: &str) -> Result<String> {
+    if render::current_format() == OutputFormat::Json {
+        let mut result = EditsApplied::from(report);
+        if !warning.is_empty() {
+            result.warnings.push(warning.trim_end().to_string());
+        }
+        return to_json(&result);
+    }
+    Ok(budget::fit_lines(&format!("{}{}", warning, report)))
+}
 
-                let mut end_index = 0;
-                for i in 0..end_line {
-                    if i < lines.len() {
-                        end_index += lines[i].len() + 1; // +1 for the newline
-                    }
-                }
-                end_index += end_char;
-
-                // Apply the edit
-                if start_index <= new_content.len() && end_index <= new_content.len() {
-                    new_content = format!(
-                        "{}{}{}",
-                        &new_content[..start_index],
-                        text_edit.new_text,
-                        &new_content[end_index..],
+/// Checks every file touched by a rename for symbols already named `new_name`
+async fn check_rename_conflicts(
+    client: &Client,
+    edit: &WorkspaceEdit,
+    new_name: &str,
+) -> Result<Vec<(PathBuf, RenameConflict)>> {
+    let mut conflicts = Vec::new();
+
+    for (uri, ranges) in edit_ranges_by_uri(edit) {
+        let file_path = to_path(&uri)?;
+        client.open_file(&file_path).await?;
+
+        let params = DocumentSymbolParams {
+            text_document: lsp_types::TextDocumentIdentifier { uri },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+
+        // Servers without documentSymbol support just skip the check
+        let symbols: Option<DocumentSymbolResponse> =
+            match client.call("textDocument/documentSymbol", params).await {
+                Ok(symbols) => symbols,
+                Err(e) => {
+                    debug!(
+                        "[TOOL] Skipping rename conflict check for {}: {}",
+                        file_path.display(),
+                        e
                     );
+                    continue;
                 }
+            };
 
-                edits_applied += 1;
+        if let Some(symbols) = symbols {
+            for conflict in find_rename_conflicts(&symbols, new_name, &ranges) {
+                conflicts.push((file_path.clone(), conflict));
             }
+        }
+    }
 
-            // Write the changes back to the file
-            fs::write(&file_path, &new_content)
-                .await
-                .context(format!("Failed to write file: {}", file_path.display()))?;
+    Ok(conflicts)
+}
+
+/// Collects the edited ranges of a workspace edit, grouped by document
+fn edit_ranges_by_uri(edit: &WorkspaceEdit) -> HashMap<Url, Vec<Range>> {
+    let mut ranges: HashMap<Url, Vec<Range>> = HashMap::new();
 
-            // Notify the LSP server of the change
-            client.notify_change(&file_path).await?;
+    if let Some(changes) = &edit.changes {
+        for (uri, edits) in changes {
+            ranges
+                .entry(uri.clone())
+                .or_default()
+                .extend(edits.iter().map(|e| e.range));
+        }
+    }
 
-            files_changed += 1;
+    if let Some(lsp_types::DocumentChanges::Edits(edits)) = &edit.document_changes {
+        for text_document_edit in edits {
+            ranges
+                .entry(text_document_edit.text_document.uri.clone())
+                .or_default()
+                .extend(text_document_edit.edits.iter().map(|e| match e {
+                    OneOf::Left(edit) => edit.range,
+                    OneOf::Right(annotated) => annotated.text_edit.range,
+                }));
         }
     }
 
-    // Process document changes
-    if let Some(document_changes) = edit.document_changes {
-        match document_changes {
-            lsp_types::DocumentChanges::Edits(edits) => {
-                for text_document_edit in edits {
-                    let uri = text_document_edit.text_document.uri;
-                    let file_path = to_path(&uri)?;
-
-                    // Read the file content
-                    let content = fs::read_to_string(&file_path)
-                        .await
-                        .context(format!("Failed to read file: {}", file_path.display()))?;
-
-                    // Apply the edits
-                    let mut new_content = content.clone();
-
-                    // Apply edits in reverse to avoid position changes
-                    for text_edit in text_document_edit.edits.iter().rev() {
-                        // Extract range and new_text based on the OneOf variant
-                        let (range, new_text) = match text_edit {
-                            OneOf::Left(edit) => (&edit.range, &edit.new_text),
-                            OneOf::Right(annotated) => {
-                                (&annotated.text_edit.range, &annotated.text_edit.new_text)
-                            }
-                        };
-
-                        // Convert the LSP positions to string indices
-                        let start_line = range.start.line as usize;
-                        let start_char = range.start.character as usize;
-                        let end_line = range.end.line as usize;
-                        let end_char = range.end.character as usize;
-
-                        // Split into lines
-                        let lines: Vec<&str> = new_content.lines().collect();
-
-                        // Calculate start and end indices
-                        let mut start_index = 0;
-                        for i in 0..start_line {
-                            if i < lines.len() {
-                                start_index += lines[i].len() + 1; // +1 for the newline
-                            }
-                        }
-                        start_index += start_char;
-
-                        let mut end_index = 0;
-                        for i in 0..end_line {
-                            if i < lines.len() {
-                                end_index += lines[i].len() + 1; // +1 for the newline
-                            }
-                        }
-                        end_index += end_char;
-
-                        // Apply the edit
-                        if start_index <= new_content.len() && end_index <= new_content.len() {
-                            new_content = format!(
-                                "{}{}{}",
-                                &new_content[..start_index],
-                                new_text,
-                                &new_content[end_index..],
-                            );
-                        }
-
-                        edits_applied += 1;
-                    }
-
-                    // Write the changes back to the file
-                    fs::write(&file_path, &new_content)
-                        .await
-                        .context(format!("Failed to write file: {}", file_path.display()))?;
-
-                    // Notify the LSP server of the change
-                    client.notify_change(&file_path).await?;
-
-                    files_changed += 1;
+    ranges
+}
+
+/// Finds symbols named `new_name` whose scope encloses one of the edited ranges.
+///
+/// A nested symbol's scope is the range of its parent; top-level symbols, and
+/// all symbols from a flat response, are scoped to the whole file.
+pub fn find_rename_conflicts(
+    symbols: &DocumentSymbolResponse,
+    new_name: &str,
+    edit_ranges: &[Range],
+) -> Vec<RenameConflict> {
+    let mut conflicts = Vec::new();
+
+    match symbols {
+        DocumentSymbolResponse::Flat(symbols) => {
+            if !edit_ranges.is_empty() {
+                for symbol in symbols.iter().filter(|s| s.name == new_name) {
+                    conflicts.push(RenameConflict {
+                        name: symbol.name.clone(),
+                        kind: symbol.kind,
+                        range: symbol.location.range,
+                    });
                 }
             }
-            lsp_types::DocumentChanges::Operations(_) => {
-                // We don't support document operations yet
-                return Err(anyhow!("Document operations are not supported"));
-            }
         }
+        DocumentSymbolResponse::Nested(symbols) => {
+            collect_nested_conflicts(symbols, None, new_name, edit_ranges, &mut conflicts);
+        }
+    }
+
+    conflicts
+}
+
+fn collect_nested_conflicts(
+    symbols: &[DocumentSymbol],
+    scope: Option<&Range>,
+    new_name: &str,
+    edit_ranges: &[Range],
+    conflicts: &mut Vec<RenameConflict>,
+) {
+    let in_scope = match scope {
+        Some(scope) => edit_ranges.iter().any(|r| range_contains(scope, r)),
+        None => !edit_ranges.is_empty(),
+    };
+
+    for symbol in symbols {
+        if in_scope && symbol.name == new_name {
+            conflicts.push(RenameConflict {
+                name: symbol.name.clone(),
+                kind: symbol.kind,
+                range: symbol.selection_range,
+            });
+        }
+
+        if let Some(children) = &symbol.children {
+            collect_nested_conflicts(
+                children,
+                Some(&symbol.range),
+                new_name,
+                edit_ranges,
+                conflicts,
+            );
+        }
+    }
+}
+
+/// Formats rename conflicts as a warning block, or an empty string if there are none
+fn format_conflicts(
+    client: &Client,
+    new_name: &str,
+    conflicts: &[(PathBuf, RenameConflict)],
+) -> String {
+    if conflicts.is_empty() {
+        return String::new();
+    }
+
+    let mut result = format!(
+        "Warning: '{}' already names {} symbol(s) in the affected scopes:\n",
+        new_name,
+        conflicts.len()
+    );
+
+    for (file_path, conflict) in conflicts {
+        result.push_str(&format!(
+            "  {:?} {} at {}:{}:{}\n",
+            conflict.kind,
+            conflict.name,
+            display_path(client, file_path),
+            conflict.range.start.line + 1,
+            conflict.range.start.character + 1
+        ));
     }
 
-    Ok(format!(
-        "Applied {} edits across {} files",
-        edits_applied, files_changed
-    ))
+    result.push('\n');
+    result
 }
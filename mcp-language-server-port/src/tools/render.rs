@@ -0,0 +1,224 @@
+use anyhow::{Result, anyhow};
+use std::future::Future;
+use std::sync::RwLock;
+
+use super::budget::Block;
+
+/// How tool output is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputStyle {
+    /// Markdown with fenced code blocks
+    #[default]
+    Markdown,
+    /// Plain text without Markdown markup
+    Plain,
+    /// One line per item, for very large result sets
+    Compact,
+}
+
+impl OutputStyle {
+    /// Parses a style name as accepted by the tools and the command line
+    pub fn parse(style: &str) -> Result<Self> {
+        match style {
+            "markdown" => Ok(Self::Markdown),
+            "plain" => Ok(Self::Plain),
+            "compact" => Ok(Self::Compact),
+            _ => Err(anyhow!(
+                "Unknown output style '{}': expected markdown, plain or compact",
+                style
+            )),
+        }
+    }
+
+    /// Gets the renderer for this style
+    pub fn renderer(self) -> &'static dyn Renderer {
+        match self {
+            Self::Markdown => &MarkdownRenderer,
+            Self::Plain => &PlainRenderer,
+            Self::Compact => &CompactRenderer,
+        }
+    }
+}
+
+/// Whether tool results are text for a model or JSON for programs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Text laid out in the output style
+    #[default]
+    Text,
+    /// Typed results serialized as JSON, for tools that have them
+    Json,
+}
+
+impl OutputFormat {
+    /// Parses a format name as accepted by the tools
+    pub fn parse(format: &str) -> Result<Self> {
+        match format {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow!(
+                "Unknown output format '{}': expected text or json",
+                format
+            )),
+        }
+    }
+}
+
+/// Style used when a request doesn't pick one
+static DEFAULT_STYLE: RwLock<OutputStyle> = RwLock::new(OutputStyle::Markdown);
+
+tokio::task_local! {
+    /// Style picked by the tool call being handled
+    static REQUEST_STYLE: OutputStyle;
+
+    /// Format picked by the tool call being handled
+    static REQUEST_FORMAT: OutputFormat;
+}
+
+/// Sets the style used when a request doesn't pick one
+pub fn set_default_style(style: OutputStyle) {
+    *DEFAULT_STYLE.write().unwrap() = style;
+}
+
+/// Gets the style for the tool call being handled
+pub fn current_style() -> OutputStyle {
+    REQUEST_STYLE
+        .try_with(|style| *style)
+        .unwrap_or_else(|_| *DEFAULT_STYLE.read().unwrap())
+}
+
+/// Gets the renderer for the tool call being handled
+pub fn renderer() -> &'static dyn Renderer {
+    current_style().renderer()
+}
+
+/// Runs a tool call with its own output style, or the default one if `None`
+pub async fn with_style<F: Future>(style: Option<OutputStyle>, f: F) -> F::Output {
+    match style {
+        Some(style) => REQUEST_STYLE.scope(style, f).await,
+        None => f.await,
+    }
+}
+
+/// Gets the format for the tool call being handled
+pub fn current_format() -> OutputFormat {
+    REQUEST_FORMAT
+        .try_with(|format| *format)
+        .unwrap_or_default()
+}
+
+/// Runs a tool call with its own output format, or text if `None`
+pub async fn with_format<F: Future>(format: Option<OutputFormat>, f: F) -> F::Output {
+    match format {
+        Some(format) => REQUEST_FORMAT.scope(format, f).await,
+        None => f.await,
+    }
+}
+
+/// Lays out the parts of tool output
+pub trait Renderer: Send + Sync {
+    /// Formats a code snippet
+    fn code(&self, code: &str, language: &str) -> String;
+
+    /// Appends a result item to the output
+    fn block(&self, block: &Block, out: &mut String);
+
+    /// Gets the text placed between result items
+    fn separator<'a>(&self, separator: &'a str) -> &'a str {
+        separator
+    }
+
+    /// Adjusts free-form text, such as hover documentation, to the style
+    fn text(&self, text: &str) -> String {
+        text.to_string()
+    }
+}
+
+/// Rich Markdown with code fences
+pub struct MarkdownRenderer;
+
+impl Renderer for MarkdownRenderer {
+    fn code(&self, code: &str, language: &str) -> String {
+        format!("```{}\n{}\n```", language, code)
+    }
+
+    fn block(&self, block: &Block, out: &mut String) {
+        out.push_str(&block.header);
+        for line in &block.context {
+            out.push_str(line);
+        }
+        if let Some(snippet) = &block.snippet {
+            out.push_str(snippet);
+        }
+    }
+}
+
+/// Plain text: the Markdown layout without code fences
+pub struct PlainRenderer;
+
+impl Renderer for PlainRenderer {
+    fn code(&self, code: &str, _language: &str) -> String {
+        code.to_string()
+    }
+
+    fn block(&self, block: &Block, out: &mut String) {
+        MarkdownRenderer.block(block, out);
+    }
+
+    fn text(&self, text: &str) -> String {
+        strip_fences(text)
+    }
+}
+
+/// A single line per item: block summaries, or their first header line
+pub struct CompactRenderer;
+
+impl Renderer for CompactRenderer {
+    fn code(&self, code: &str, _language: &str) -> String {
+        code.lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    }
+
+    fn block(&self, block: &Block, out: &mut String) {
+        let summary = match &block.summary {
+            Some(summary) => summary.clone(),
+            // Keep leading indentation, which carries nesting in outlines
+            None => block
+                .header
+                .lines()
+                .find(|line| !line.trim().is_empty())
+                .unwrap_or_default()
+                .trim_end()
+                .to_string(),
+        };
+        if !summary.is_empty() {
+            out.push_str(summary.trim_end());
+            out.push('\n');
+        }
+    }
+
+    fn separator<'a>(&self, _separator: &'a str) -> &'a str {
+        ""
+    }
+
+    fn text(&self, text: &str) -> String {
+        let mut compact = String::new();
+        for line in strip_fences(text).lines() {
+            if !line.trim().is_empty() {
+                compact.push_str(line);
+                compact.push('\n');
+            }
+        }
+        compact
+    }
+}
+
+/// Removes the fence lines of Markdown code blocks, keeping their content
+fn strip_fences(text: &str) -> String {
+    text.split_inclusive('\n')
+        .filter(|line| !line.trim_start().starts_with("```"))
+        .collect()
+}
@@ -0,0 +1,225 @@
+use crate::lsp::Client;
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use lsp_types::{
+    CreateFile, CreateFilesParams, DeleteFile, DeleteFilesParams, FileChangeType, FileCreate,
+    FileDelete, FileEvent, FileRename, RenameFile, RenameFilesParams, ResourceOp,
+    WorkspaceFileOperationsServerCapabilities,
+};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use super::utils::{display_path, to_path, to_uri};
+
+/// Describes a file operation of a workspace edit, as listed in tool output
+pub fn describe_resource_op(client: &Client, op: &ResourceOp) -> Result<String> {
+    Ok(match op {
+        ResourceOp::Create(create) => {
+            format!("Created {}", display_path(client, &to_path(&create.uri)?))
+        }
+        ResourceOp::Rename(rename) => format!(
+            "Renamed {} to {}",
+            display_path(client, &to_path(&rename.old_uri)?),
+            display_path(client, &to_path(&rename.new_uri)?)
+        ),
+        ResourceOp::Delete(delete) => {
+            format!("Deleted {}", display_path(client, &to_path(&delete.uri)?))
+        }
+    })
+}
+
+/// Creates, renames or deletes a file as a workspace edit asks, honoring its
+/// options, and tells the language servers about it. Returns a description
+/// of what was done.
+pub async fn apply_resource_op(client: &Client, op: &ResourceOp) -> Result<String> {
+    let applied = match op {
+        ResourceOp::Create(create) => create_file(client, create).await?,
+        ResourceOp::Rename(rename) => rename_file(client, rename).await?,
+        ResourceOp::Delete(delete) => delete_file(client, delete).await?,
+    };
+
+    let description = describe_resource_op(client, op)?;
+    if applied {
+        Ok(description)
+    } else {
+        Ok(format!("{} (skipped, nothing to do)", description))
+    }
+}
+
+async fn create_file(client: &Client, create: &CreateFile) -> Result<bool> {
+    let path = to_path(&create.uri)?;
+    let options = create.options.clone().unwrap_or_default();
+
+    // Overwrite wins over ignoreIfExists
+    if path.exists() && !options.overwrite.unwrap_or(false) {
+        if options.ignore_if_exists.unwrap_or(false) {
+            return Ok(false);
+        }
+        return Err(anyhow!(
+            "Cannot create {}: it already exists",
+            path.display()
+        ));
+    }
+
+    create_parent(&path).await?;
+    fs::write(&path, "")
+        .await
+        .context(format!("Failed to create file: {}", path.display()))?;
+    debug!("[TOOL] Created {}", path.display());
+
+    client
+        .notify_watched_files(vec![FileEvent::new(to_uri(&path), FileChangeType::CREATED)])
+        .await?;
+    if server_wants(client, |operations| operations.did_create.is_some()) {
+        let params = CreateFilesParams {
+            files: vec![FileCreate {
+                uri: to_uri(&path).to_string(),
+            }],
+        };
+        client.notify("workspace/didCreateFiles", params).await?;
+    }
+
+    Ok(true)
+}
+
+async fn rename_file(client: &Client, rename: &RenameFile) -> Result<bool> {
+    let old_path = to_path(&rename.old_uri)?;
+    let new_path = to_path(&rename.new_uri)?;
+    let options = rename.options.clone().unwrap_or_default();
+
+    if new_path.exists() && !options.overwrite.unwrap_or(false) {
+        if options.ignore_if_exists.unwrap_or(false) {
+            return Ok(false);
+        }
+        return Err(anyhow!(
+            "Cannot rename {} to {}: the target already exists",
+            old_path.display(),
+            new_path.display()
+        ));
+    }
+
+    // Open documents move with the file or directory
+    let moved: Vec<(PathBuf, PathBuf)> = open_documents_under(client, &old_path)
+        .into_iter()
+        .filter_map(|path| {
+            let relative = path.strip_prefix(&old_path).ok()?;
+            let moved_to = if relative.as_os_str().is_empty() {
+                new_path.clone()
+            } else {
+                new_path.join(relative)
+            };
+            Some((path, moved_to))
+        })
+        .collect();
+
+    create_parent(&new_path).await?;
+    fs::rename(&old_path, &new_path).await.context(format!(
+        "Failed to rename {} to {}",
+        old_path.display(),
+        new_path.display()
+    ))?;
+    debug!(
+        "[TOOL] Renamed {} to {}",
+        old_path.display(),
+        new_path.display()
+    );
+
+    for (old_document, new_document) in moved {
+        client.close_file(&old_document).await?;
+        if new_document.is_file() {
+            client.open_file(&new_document).await?;
+        }
+    }
+    client
+        .notify_watched_files(vec![
+            FileEvent::new(to_uri(&old_path), FileChangeType::DELETED),
+            FileEvent::new(to_uri(&new_path), FileChangeType::CREATED),
+        ])
+        .await?;
+    if server_wants(client, |operations| operations.did_rename.is_some()) {
+        let params = RenameFilesParams {
+            files: vec![FileRename {
+                old_uri: to_uri(&old_path).to_string(),
+                new_uri: to_uri(&new_path).to_string(),
+            }],
+        };
+        client.notify("workspace/didRenameFiles", params).await?;
+    }
+
+    Ok(true)
+}
+
+async fn delete_file(client: &Client, delete: &DeleteFile) -> Result<bool> {
+    let path = to_path(&delete.uri)?;
+    let options = delete.options.clone().unwrap_or_default();
+
+    if !path.exists() {
+        if options.ignore_if_not_exists.unwrap_or(false) {
+            return Ok(false);
+        }
+        return Err(anyhow!(
+            "Cannot delete {}: it does not exist",
+            path.display()
+        ));
+    }
+
+    for document in open_documents_under(client, &path) {
+        client.close_file(&document).await?;
+    }
+
+    let removed = if path.is_dir() {
+        if options.recursive.unwrap_or(false) {
+            fs::remove_dir_all(&path).await
+        } else {
+            fs::remove_dir(&path).await
+        }
+    } else {
+        fs::remove_file(&path).await
+    };
+    removed.context(format!("Failed to delete {}", path.display()))?;
+    debug!("[TOOL] Deleted {}", path.display());
+
+    client
+        .notify_watched_files(vec![FileEvent::new(to_uri(&path), FileChangeType::DELETED)])
+        .await?;
+    if server_wants(client, |operations| operations.did_delete.is_some()) {
+        let params = DeleteFilesParams {
+            files: vec![FileDelete {
+                uri: to_uri(&path).to_string(),
+            }],
+        };
+        client.notify("workspace/didDeleteFiles", params).await?;
+    }
+
+    Ok(true)
+}
+
+/// Gets the open documents at or below a path
+fn open_documents_under(client: &Client, path: &Path) -> Vec<PathBuf> {
+    client
+        .open_documents()
+        .into_iter()
+        .filter_map(|document| document.uri.to_file_path().ok())
+        .filter(|document| document.starts_with(path))
+        .collect()
+}
+
+async fn create_parent(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .context(format!("Failed to create directory: {}", parent.display()))?;
+    }
+    Ok(())
+}
+
+/// Checks whether the server asked to be told about a kind of file operation
+fn server_wants(
+    client: &Client,
+    registered: impl Fn(&WorkspaceFileOperationsServerCapabilities) -> bool,
+) -> bool {
+    client
+        .server_capabilities()
+        .and_then(|capabilities| capabilities.workspace?.file_operations)
+        .is_some_and(|operations| registered(&operations))
+}
@@ -0,0 +1,38 @@
+use crate::lsp::Client;
+use anyhow::Result;
+use log::debug;
+use std::sync::Arc;
+
+use super::utils::{display_path, to_path};
+
+/// Restarts the language server and reports which documents were reopened.
+/// Documents whose in-memory content differed from disk come back with the
+/// disk content.
+pub async fn restart_language_server(client: &Arc<Client>) -> Result<String> {
+    debug!("[TOOL] Restarting language server {}", client.name());
+
+    let mut documents = client.open_documents();
+    documents.sort_by(|a, b| a.uri.as_str().cmp(b.uri.as_str()));
+
+    client.relaunch().await?;
+
+    let mut result = format!("Restarted {}.", client.name());
+    if documents.is_empty() {
+        return Ok(result);
+    }
+
+    result.push_str(&format!("\n\nReopened {} documents:\n", documents.len()));
+    for document in documents {
+        let path = to_path(&document.uri)?;
+        let state = if !client.is_file_open(&path) {
+            " (failed to reopen)"
+        } else if document.dirty {
+            " (unsaved changes were dropped)"
+        } else {
+            ""
+        };
+        result.push_str(&format!("  {}{}\n", display_path(client, &path), state));
+    }
+
+    Ok(result)
+}
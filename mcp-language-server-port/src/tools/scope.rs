@@ -0,0 +1,82 @@
+use crate::lsp::Client;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Result, anyhow};
+use ignore::WalkBuilder;
+use std::path::{Path, PathBuf};
+
+/// How many files a tool working on a directory or the workspace handles at once
+pub const SCOPE_CONCURRENCY: usize = 4;
+
+/// The files a code action tool works on, relative to the path it was given
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ActionScope {
+    /// Only the given file
+    #[default]
+    File,
+    /// Every file in the given directory, or in the directory of the given file
+    Directory,
+    /// Every file in the workspace
+    Workspace,
+}
+
+impl ActionScope {
+    /// Parses a scope name as accepted by the code action tools
+    pub fn parse(scope: &str) -> Result<Self> {
+        match scope {
+            "file" => Ok(Self::File),
+            "directory" => Ok(Self::Directory),
+            "workspace" => Ok(Self::Workspace),
+            _ => Err(anyhow!(
+                "Unknown scope '{}': expected file, directory or workspace",
+                scope
+            )),
+        }
+    }
+
+    /// Gets the directory the scope covers around `path`, or `None` for a
+    /// single file
+    pub fn root(self, client: &Client, path: &Path) -> Result<Option<PathBuf>> {
+        let path = normalize_path(path)?;
+        match self {
+            Self::File => Ok(None),
+            Self::Directory if path.is_dir() => Ok(Some(path)),
+            Self::Directory => Ok(path.parent().map(Path::to_path_buf)),
+            Self::Workspace => client
+                .workspace_root()
+                .map(Some)
+                .ok_or_else(|| anyhow!("{} has no workspace", client.name())),
+        }
+    }
+
+    /// Lists the files in scope around `path`, sorted, leaving out ignored and
+    /// hidden files. Given a file, only files of its language are listed;
+    /// given a directory, every file of a language the client knows.
+    pub fn files(self, client: &Client, path: &Path) -> Result<Vec<PathBuf>> {
+        let path = normalize_path(path)?;
+        let Some(root) = self.root(client, &path)? else {
+            return Ok(vec![path]);
+        };
+        let language = path.is_file().then(|| client.language_id(&path));
+
+        let mut files: Vec<PathBuf> = WalkBuilder::new(&root)
+            .require_git(false)
+            .build()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_type()
+                    .is_some_and(|file_type| file_type.is_file())
+            })
+            .map(|entry| entry.into_path())
+            .filter(|file| {
+                let language_id = client.language_id(file);
+                match &language {
+                    Some(language) => language_id == *language,
+                    None => language_id != "plaintext",
+                }
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+}
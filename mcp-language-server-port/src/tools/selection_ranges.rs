@@ -0,0 +1,141 @@
+use crate::lsp::Client;
+use crate::lsp::PositionEncoding;
+use crate::lsp::position::byte_column;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use lsp_types::{Position, Range, SelectionRange, SelectionRangeParams};
+use std::path::PathBuf;
+
+use super::budget;
+use super::utils::{display_path, to_text_document_identifier};
+
+/// Longest snippet of a single-line range
+const SNIPPET_CHARS: usize = 80;
+
+/// Lists the syntactic ranges enclosing a position, from the innermost
+/// expression out to the whole file, each with a snippet of its text
+pub async fn get_selection_ranges(
+    client: &Client,
+    file_path: PathBuf,
+    line: u32,
+    column: u32,
+) -> Result<String> {
+    debug!(
+        "[TOOL] Getting selection ranges for {}:{}:{}",
+        file_path.display(),
+        line,
+        column
+    );
+
+    // Get the file's absolute path
+    let file_path = normalize_path(&file_path)?;
+
+    // Ensure the file exists
+    if !file_path.exists() {
+        return Err(anyhow!("File does not exist: {}", file_path.display()));
+    }
+
+    // Ensure the file is open in the LSP server
+    client.open_file(&file_path).await?;
+
+    // Adjust from 1-indexed to 0-indexed
+    let params = SelectionRangeParams {
+        text_document: to_text_document_identifier(&file_path)?,
+        positions: vec![Position {
+            line: line.saturating_sub(1),
+            character: column.saturating_sub(1),
+        }],
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+    let selections: Option<Vec<SelectionRange>> =
+        client.call("textDocument/selectionRange", params).await?;
+    let Some(selection) = selections.unwrap_or_default().into_iter().next() else {
+        return Ok(format!(
+            "No enclosing ranges at {}:{}:{}",
+            display_path(client, &file_path),
+            line,
+            column
+        ));
+    };
+
+    let content = tokio::fs::read_to_string(&file_path)
+        .await
+        .context(format!("Failed to read file: {}", file_path.display()))?;
+    let lines: Vec<&str> = content.lines().collect();
+    let encoding = client.position_encoding();
+
+    let ranges = selection_chain(selection);
+    let mut result = format!(
+        "{} enclosing ranges at {}:{}:{}, innermost first:\n\n",
+        ranges.len(),
+        display_path(client, &file_path),
+        line,
+        column
+    );
+    for (i, range) in ranges.iter().enumerate() {
+        result.push_str(&format!(
+            "{}. {}\n",
+            i + 1,
+            format_selection_range(range, &lines, encoding)
+        ));
+    }
+
+    Ok(budget::fit_lines(&result))
+}
+
+/// Flattens a selection range and its parents into a list, innermost first,
+/// leaving out parents that cover the same range as their child
+pub fn selection_chain(selection: SelectionRange) -> Vec<Range> {
+    let mut ranges: Vec<Range> = Vec::new();
+    let mut next = Some(selection);
+    while let Some(selection) = next {
+        if ranges.last() != Some(&selection.range) {
+            ranges.push(selection.range);
+        }
+        next = selection.parent.map(|parent| *parent);
+    }
+    ranges
+}
+
+/// Formats a range as `L<line>:<column>-L<line>:<column>` with its text, or
+/// its first and last lines when it spans several
+pub fn format_selection_range(range: &Range, lines: &[&str], encoding: PositionEncoding) -> String {
+    let location = format!(
+        "L{}:{}-L{}:{}",
+        range.start.line + 1,
+        range.start.character + 1,
+        range.end.line + 1,
+        range.end.character + 1
+    );
+
+    let line_at = |line: u32| lines.get(line as usize).copied().unwrap_or_default();
+    if range.start.line == range.end.line {
+        let line = line_at(range.start.line);
+        let start = byte_column(line, range.start.character, encoding);
+        let end = byte_column(line, range.end.character, encoding).max(start);
+        let text = &line[start..end];
+        let text = if text.chars().count() > SNIPPET_CHARS {
+            format!(
+                "{}...",
+                text.chars().take(SNIPPET_CHARS).collect::<String>()
+            )
+        } else {
+            text.to_string()
+        };
+        return format!("{} `{}`", location, text);
+    }
+
+    let first = line_at(range.start.line);
+    let first = &first[byte_column(first, range.start.character, encoding)..];
+    let last = line_at(range.end.line);
+    let last = &last[..byte_column(last, range.end.character, encoding)];
+    format!(
+        "{} ({} lines) `{}` ... `{}`",
+        location,
+        range.end.line - range.start.line + 1,
+        first.trim(),
+        last.trim()
+    )
+}
@@ -0,0 +1,231 @@
+use crate::lsp::Client;
+use crate::lsp::PositionEncoding;
+use crate::lsp::position::{byte_column, column};
+use crate::workspace::paths::normalize_path;
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use lsp_types::{Position, Range};
+use serde_json::{Value, json};
+use std::path::PathBuf;
+
+use super::budget;
+use super::utils::{display_path, to_text_document_identifier};
+
+/// A semantic token decoded from the server's relative encoding
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticToken {
+    /// 0-based line
+    pub line: u32,
+    /// Column the token starts at, in the server's position encoding
+    pub start: u32,
+    pub length: u32,
+    pub token_type: String,
+    pub modifiers: Vec<String>,
+}
+
+/// Lists the semantic tokens of a file, or of a range of its lines, with the
+/// kind and modifiers the server classified each one with
+pub async fn get_semantic_tokens(
+    client: &Client,
+    file_path: PathBuf,
+    start_line: Option<u32>,
+    end_line: Option<u32>,
+) -> Result<String> {
+    debug!("[TOOL] Getting semantic tokens for {}", file_path.display());
+
+    // Get the file's absolute path
+    let file_path = normalize_path(&file_path)?;
+
+    // Ensure the file exists
+    if !file_path.exists() {
+        return Err(anyhow!("File does not exist: {}", file_path.display()));
+    }
+
+    // Tokens only make sense with the legend the server announced
+    let provider = client
+        .server_capabilities()
+        .map(serde_json::to_value)
+        .transpose()?
+        .and_then(|capabilities| capabilities.get("semanticTokensProvider").cloned())
+        .filter(|provider| !provider.is_null())
+        .ok_or_else(|| anyhow!("The language server does not support semantic tokens"))?;
+    let legend = |name: &str| -> Vec<String> {
+        provider
+            .pointer(&format!("/legend/{}", name))
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let token_types = legend("tokenTypes");
+    let token_modifiers = legend("tokenModifiers");
+
+    // Ensure the file is open in the LSP server
+    client.open_file(&file_path).await?;
+
+    let content = tokio::fs::read_to_string(&file_path)
+        .await
+        .context(format!("Failed to read file: {}", file_path.display()))?;
+    let lines: Vec<&str> = content.lines().collect();
+    let encoding = client.position_encoding();
+
+    // Adjust from 1-indexed to 0-indexed; the whole file by default
+    let last_line = lines.len().saturating_sub(1) as u32;
+    let start = start_line.map_or(0, |line| line.saturating_sub(1));
+    let end = end_line.map_or(last_line, |line| line.saturating_sub(1).min(last_line));
+    if start > end {
+        return Err(anyhow!("Start line is after end line"));
+    }
+
+    // Servers that can tokenize a range spare the rest of a large file
+    let text_document = to_text_document_identifier(&file_path)?;
+    let ranged = start_line.is_some() || end_line.is_some();
+    let result: Value = if ranged && provider.get("range").is_some_and(is_enabled) {
+        let end_character = lines
+            .get(end as usize)
+            .map_or(0, |line| column(line, line.len(), encoding));
+        let range = Range {
+            start: Position {
+                line: start,
+                character: 0,
+            },
+            end: Position {
+                line: end,
+                character: end_character,
+            },
+        };
+        client
+            .call(
+                "textDocument/semanticTokens/range",
+                json!({ "textDocument": text_document, "range": range }),
+            )
+            .await?
+    } else {
+        client
+            .call(
+                "textDocument/semanticTokens/full",
+                json!({ "textDocument": text_document }),
+            )
+            .await?
+    };
+
+    let data: Vec<u32> = result
+        .get("data")
+        .and_then(Value::as_array)
+        .map(|data| {
+            data.iter()
+                .filter_map(Value::as_u64)
+                .map(|n| n as u32)
+                .collect()
+        })
+        .unwrap_or_default();
+    let tokens: Vec<SemanticToken> = decode_semantic_tokens(&data, &token_types, &token_modifiers)
+        .into_iter()
+        .filter(|token| token.line >= start && token.line <= end)
+        .collect();
+
+    if tokens.is_empty() {
+        return Ok(format!(
+            "No semantic tokens in {} lines {}-{}",
+            display_path(client, &file_path),
+            start + 1,
+            end + 1
+        ));
+    }
+
+    let mut result = format!(
+        "Semantic tokens in {} lines {}-{}:\n\n",
+        display_path(client, &file_path),
+        start + 1,
+        end + 1
+    );
+    for token in &tokens {
+        result.push_str(&format_semantic_token(token, &lines, encoding));
+        result.push('\n');
+    }
+
+    Ok(budget::fit_lines(&result))
+}
+
+/// Checks whether a capability is present and not switched off
+fn is_enabled(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+/// Decodes the server's flat token data, five numbers per token with lines
+/// and start columns relative to the previous token, naming types and
+/// modifiers by the legend. Indexes the legend lacks are kept as numbers.
+pub fn decode_semantic_tokens(
+    data: &[u32],
+    token_types: &[String],
+    token_modifiers: &[String],
+) -> Vec<SemanticToken> {
+    let mut tokens = Vec::with_capacity(data.len() / 5);
+    let (mut line, mut start) = (0, 0);
+    for chunk in data.chunks_exact(5) {
+        let [delta_line, delta_start, length, token_type, modifier_bits] = *chunk else {
+            continue;
+        };
+        if delta_line > 0 {
+            line += delta_line;
+            start = delta_start;
+        } else {
+            start += delta_start;
+        }
+
+        let token_type = token_types
+            .get(token_type as usize)
+            .cloned()
+            .unwrap_or_else(|| format!("type {}", token_type));
+        let modifiers = (0..32u32)
+            .filter(|bit| modifier_bits & (1u32 << bit) != 0)
+            .map(|bit| {
+                token_modifiers
+                    .get(bit as usize)
+                    .cloned()
+                    .unwrap_or_else(|| format!("modifier {}", bit))
+            })
+            .collect();
+        tokens.push(SemanticToken {
+            line,
+            start,
+            length,
+            token_type,
+            modifiers,
+        });
+    }
+    tokens
+}
+
+/// Formats a token as `line:column `text` type [modifiers]`
+pub fn format_semantic_token(
+    token: &SemanticToken,
+    lines: &[&str],
+    encoding: PositionEncoding,
+) -> String {
+    let text = lines
+        .get(token.line as usize)
+        .and_then(|line| {
+            let start = byte_column(line, token.start, encoding);
+            let end = byte_column(line, token.start + token.length, encoding);
+            line.get(start..end)
+        })
+        .unwrap_or_default();
+
+    let mut result = format!(
+        "{}:{} `{}` {}",
+        token.line + 1,
+        token.start + 1,
+        text,
+        token.token_type
+    );
+    if !token.modifiers.is_empty() {
+        result.push_str(&format!(" [{}]", token.modifiers.join(", ")));
+    }
+    result
+}
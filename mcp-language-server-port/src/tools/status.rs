@@ -0,0 +1,83 @@
+use crate::lsp::{Client, InitState};
+use anyhow::Result;
+use log::debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::budget;
+
+/// Reports the health of each server: its process, the documents and
+/// diagnostics it holds, the requests waiting on it, what it is working on
+/// and the last request that failed
+pub async fn get_status(clients: &[Arc<Client>]) -> Result<String> {
+    debug!("[TOOL] Reporting server status");
+
+    let mut servers = Vec::new();
+    for client in clients {
+        servers.push(Arc::clone(client));
+        servers.extend(client.attached_servers());
+    }
+
+    let sections: Vec<String> = servers.iter().map(|client| status(client)).collect();
+    Ok(budget::fit_lines(&sections.join("\n")))
+}
+
+/// Reports the health of one server
+fn status(client: &Client) -> String {
+    let state = match client.init_state() {
+        InitState::Starting => "starting".to_string(),
+        InitState::Ready => "ready".to_string(),
+        InitState::Failed(e) => format!("failed to initialize: {}", e),
+        InitState::Stopped => "stopped while idle".to_string(),
+    };
+    let process = match (client.pid(), client.address()) {
+        (Some(pid), _) => format!("process {}, up {}", pid, format_duration(client.uptime())),
+        (None, Some(address)) => format!(
+            "connected to {}, up {}",
+            address,
+            format_duration(client.uptime())
+        ),
+        (None, None) => "process not running".to_string(),
+    };
+
+    let mut result = format!("{}: {} ({})\n", client.name(), state, process);
+    result.push_str(&format!(
+        "  Open documents: {}\n",
+        client.open_documents().len()
+    ));
+    result.push_str(&format!(
+        "  Cached diagnostics: {}\n",
+        client.diagnostic_count()
+    ));
+    result.push_str(&format!(
+        "  Pending requests: {}\n",
+        client.pending_requests()
+    ));
+
+    let work = client.pending_work();
+    if work.is_empty() {
+        result.push_str("  Progress: idle\n");
+    } else {
+        result.push_str(&format!("  Progress: {}\n", work.join(", ")));
+    }
+
+    if let Some((ago, error)) = client.last_error() {
+        result.push_str(&format!(
+            "  Last error: {} ({} ago)\n",
+            error,
+            format_duration(ago)
+        ));
+    }
+
+    result
+}
+
+/// Formats a duration in its two largest units, e.g. `2h 5m` or `42s`
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..60 => format!("{}s", seconds),
+        60..3600 => format!("{}m {}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
@@ -0,0 +1,156 @@
+use anyhow::Result;
+use lsp_types::{Diagnostic, Location, NumberOrString, Range};
+use serde::Serialize;
+use std::path::Path;
+
+use super::call_hierarchy::Call;
+use super::diagnostics::severity_name;
+use super::document_symbols::symbol_kind_name;
+use super::utils::to_path;
+use super::workspace_edit::EditReport;
+
+/// A range in a file, with 1-based lines and columns like the text output
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LocationResult {
+    pub path: String,
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    /// The servers that reported the location, when several answered
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub servers: Vec<String>,
+}
+
+impl LocationResult {
+    pub fn new(path: &Path, range: &Range) -> Self {
+        Self {
+            path: path.display().to_string(),
+            line: range.start.line + 1,
+            column: range.start.character + 1,
+            end_line: range.end.line + 1,
+            end_column: range.end.character + 1,
+            servers: Vec::new(),
+        }
+    }
+
+    pub fn from_location(location: &Location) -> Result<Self> {
+        Ok(Self::new(&to_path(&location.uri)?, &location.range))
+    }
+}
+
+/// A page of references, with the counts needed to ask for the next one
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReferencesResult {
+    pub references: Vec<LocationResult>,
+    /// References found, including those the per-file cap left out
+    pub total: usize,
+    /// References the per-file cap left out
+    pub capped: usize,
+    /// The offset that gets the next page, if there are more references
+    pub next_offset: Option<usize>,
+}
+
+/// A diagnostic the language server reported for a file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiagnosticResult {
+    #[serde(flatten)]
+    pub location: LocationResult,
+    pub severity: String,
+    pub source: Option<String>,
+    pub code: Option<String>,
+    pub message: String,
+}
+
+impl DiagnosticResult {
+    pub fn new(path: &Path, diagnostic: &Diagnostic) -> Self {
+        Self {
+            location: LocationResult::new(path, &diagnostic.range),
+            severity: severity_name(diagnostic.severity).to_lowercase(),
+            source: diagnostic.source.clone(),
+            code: diagnostic.code.as_ref().map(|code| match code {
+                NumberOrString::Number(number) => number.to_string(),
+                NumberOrString::String(text) => text.clone(),
+            }),
+            message: diagnostic.message.clone(),
+        }
+    }
+}
+
+/// A caller or callee of the queried function, with the calls between them
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CallResult {
+    pub name: String,
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Where the caller or callee is defined
+    #[serde(flatten)]
+    pub location: LocationResult,
+    /// Where the calls are made
+    pub calls: Vec<LocationResult>,
+}
+
+impl CallResult {
+    pub fn new(call: &Call) -> Result<Self> {
+        let item = &call.item;
+        Ok(Self {
+            name: item.name.clone(),
+            kind: symbol_kind_name(item.kind).to_string(),
+            detail: item.detail.clone().filter(|detail| !detail.is_empty()),
+            location: LocationResult::new(&to_path(&item.uri)?, &item.selection_range),
+            calls: call
+                .call_ranges
+                .iter()
+                .map(|range| LocationResult::new(&call.call_file, range))
+                .collect(),
+        })
+    }
+}
+
+/// The edits applied to one file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileEdits {
+    pub path: String,
+    pub edits: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+}
+
+/// The outcome of an edit, whether applied or previewed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EditsApplied {
+    pub dry_run: bool,
+    pub edits: usize,
+    pub files: Vec<FileEdits>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub operations: Vec<String>,
+    /// Problems found that didn't stop the edit, such as rename conflicts
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl From<&EditReport> for EditsApplied {
+    fn from(report: &EditReport) -> Self {
+        Self {
+            dry_run: report.dry_run,
+            edits: report.edits(),
+            files: report
+                .files
+                .iter()
+                .map(|file| FileEdits {
+                    path: file.path.display().to_string(),
+                    edits: file.edits,
+                    diff: file.diff.clone(),
+                })
+                .collect(),
+            operations: report.operations.clone(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// Serializes a tool result for the JSON output format
+pub fn to_json<T: Serialize>(result: &T) -> Result<String> {
+    Ok(serde_json::to_string_pretty(result)?)
+}
@@ -0,0 +1,333 @@
+use crate::lsp::Client;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use lsp_types::{
+    CreateFilesParams, DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse, FileCreate,
+    SymbolKind, WorkspaceEdit,
+};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use super::definition::parse_symbol_location;
+use super::references::resolve_symbol;
+use super::related_files::{is_test_file, test_file_candidates};
+use super::utils::{display_path, to_text_document_identifier, to_uri};
+use super::workspace_edit::{self, ApplyOptions};
+
+/// Where a test for a symbol should be written
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestAnchor {
+    pub file: PathBuf,
+    /// 1-indexed line and column to insert the test at
+    pub line: u32,
+    pub column: u32,
+    pub created: bool,
+    pub inline_module: bool,
+}
+
+/// Finds the test file or module for a function and the position to insert a
+/// new test at, creating the test file with a stub when there is none
+pub async fn find_test_anchor(client: &Client, symbol_name: &str, create: bool) -> Result<String> {
+    debug!("[TOOL] Finding test anchor for {}", symbol_name);
+
+    // Take a path:line:column location, or look a bare name up in the workspace
+    let (source_path, line, column) = match parse_symbol_location(symbol_name) {
+        Ok(location) => location,
+        Err(_) => resolve_symbol(client, symbol_name).await?.remove(0),
+    };
+    let source_path = normalize_path(&source_path)?;
+
+    let source = fs::read_to_string(&source_path)
+        .await
+        .context(format!("Failed to read file: {}", source_path.display()))?;
+    let name = identifier_at(&source, line, column).unwrap_or_else(|| {
+        symbol_name
+            .rsplit([':', '.'])
+            .next()
+            .unwrap_or(symbol_name)
+            .to_string()
+    });
+
+    let extension = source_path
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let anchor = match inline_test_module(client, &source_path, &extension).await? {
+        Some(anchor) => anchor,
+        None => external_test_file(client, &source_path, &source, &extension, create).await?,
+    };
+
+    let mut result = format!(
+        "Test anchor for {} (defined at {}:{}):\n",
+        name,
+        display_path(client, &source_path),
+        line + 1
+    );
+    let origin = if anchor.inline_module {
+        "inline test module"
+    } else if anchor.created {
+        "created with a stub"
+    } else {
+        "existing"
+    };
+    result.push_str(&format!(
+        "File: {} ({})\n",
+        display_path(client, &anchor.file),
+        origin
+    ));
+    result.push_str(&format!(
+        "Insert at: {}:{}:{}\n",
+        display_path(client, &anchor.file),
+        anchor.line,
+        anchor.column
+    ));
+    result.push_str(&format!(
+        "Suggested test name: {}\n",
+        test_name(&name, &extension)
+    ));
+
+    Ok(result)
+}
+
+/// Finds a Rust `mod tests` in the source file itself
+async fn inline_test_module(
+    client: &Client,
+    source_path: &Path,
+    extension: &str,
+) -> Result<Option<TestAnchor>> {
+    if extension != "rs" {
+        return Ok(None);
+    }
+
+    client.open_file(source_path).await?;
+    let params = DocumentSymbolParams {
+        text_document: to_text_document_identifier(source_path)?,
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+    let symbols: Option<DocumentSymbolResponse> =
+        match client.call("textDocument/documentSymbol", params).await {
+            Ok(symbols) => symbols,
+            Err(e) => {
+                debug!("[TOOL] No document symbols for test anchor: {}", e);
+                None
+            }
+        };
+
+    let Some(DocumentSymbolResponse::Nested(symbols)) = symbols else {
+        return Ok(None);
+    };
+
+    // New tests go on the line of the module's closing brace, ahead of it
+    Ok(find_tests_module(&symbols).map(|module| TestAnchor {
+        file: source_path.to_path_buf(),
+        line: module.range.end.line + 1,
+        column: 1,
+        created: false,
+        inline_module: true,
+    }))
+}
+
+/// Finds a module named `tests` among nested document symbols
+pub fn find_tests_module(symbols: &[DocumentSymbol]) -> Option<&DocumentSymbol> {
+    symbols.iter().find_map(|symbol| {
+        if symbol.kind == SymbolKind::MODULE && symbol.name == "tests" {
+            return Some(symbol);
+        }
+        find_tests_module(symbol.children.as_deref().unwrap_or_default())
+    })
+}
+
+/// Finds the separate test file for a source file, creating it if allowed
+async fn external_test_file(
+    client: &Client,
+    source_path: &Path,
+    source: &str,
+    extension: &str,
+    create: bool,
+) -> Result<TestAnchor> {
+    if is_test_file(source_path) {
+        return Err(anyhow!(
+            "{} is already a test file",
+            display_path(client, source_path)
+        ));
+    }
+
+    let workspace_root = client.workspace_root();
+    let candidates = test_file_candidates(source_path, workspace_root.as_deref());
+
+    if let Some(existing) = candidates.iter().find(|candidate| candidate.is_file()) {
+        let content = fs::read_to_string(existing)
+            .await
+            .context(format!("Failed to read file: {}", existing.display()))?;
+        let (line, column) = insertion_point(&content, extension);
+        return Ok(TestAnchor {
+            file: existing.clone(),
+            line,
+            column,
+            created: false,
+            inline_module: false,
+        });
+    }
+
+    // Rust integration tests live in the workspace's tests directory
+    let preferred = match (extension, &workspace_root) {
+        ("rs", Some(root)) => candidates
+            .iter()
+            .find(|candidate| candidate.parent() == Some(root.join("tests").as_path())),
+        _ => candidates.first(),
+    };
+    let test_file = preferred
+        .cloned()
+        .ok_or_else(|| anyhow!("No test file location known for {}", source_path.display()))?;
+
+    if !create {
+        return Err(anyhow!(
+            "No test file found; it would be created at {}",
+            display_path(client, &test_file)
+        ));
+    }
+
+    let stub = test_stub(&test_file, source);
+    create_file(client, &test_file, &stub).await?;
+
+    let (line, column) = insertion_point(&stub, extension);
+    Ok(TestAnchor {
+        file: test_file,
+        line,
+        column,
+        created: true,
+        inline_module: false,
+    })
+}
+
+/// Creates a file, letting the server prepare for it and learn about it
+async fn create_file(client: &Client, path: &Path, content: &str) -> Result<()> {
+    let params = CreateFilesParams {
+        files: vec![FileCreate {
+            uri: to_uri(path).to_string(),
+        }],
+    };
+
+    // Servers may answer willCreateFiles with edits, such as a module declaration
+    let edit: Option<WorkspaceEdit> = match client
+        .call("workspace/willCreateFiles", params.clone())
+        .await
+    {
+        Ok(edit) => edit,
+        Err(e) => {
+            debug!("[TOOL] Server did not handle willCreateFiles: {}", e);
+            None
+        }
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .context(format!("Failed to create directory: {}", parent.display()))?;
+    }
+    fs::write(path, content)
+        .await
+        .context(format!("Failed to write file: {}", path.display()))?;
+
+    // The edits may target the new file, so they are applied once it exists
+    if let Some(edit) = edit {
+        workspace_edit::apply(client, edit, &ApplyOptions::default()).await?;
+    }
+
+    client.notify("workspace/didCreateFiles", params).await?;
+    client.open_file(path).await?;
+    Ok(())
+}
+
+/// Gets the identifier at a 0-indexed position
+pub fn identifier_at(content: &str, line: u32, column: u32) -> Option<String> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let chars: Vec<char> = content.lines().nth(line as usize)?.chars().collect();
+
+    let column = (column as usize).min(chars.len());
+    let start = chars[..column]
+        .iter()
+        .rposition(|c| !is_ident(*c))
+        .map_or(0, |i| i + 1);
+    let end = chars[column..]
+        .iter()
+        .position(|c| !is_ident(*c))
+        .map_or(chars.len(), |i| column + i);
+
+    let identifier: String = chars[start..end].iter().collect();
+    (!identifier.is_empty()).then_some(identifier)
+}
+
+/// Gets the 1-indexed position to add a test at: before the closing brace of
+/// a Java or Kotlin test class, and after the last line otherwise
+pub fn insertion_point(content: &str, extension: &str) -> (u32, u32) {
+    let lines: Vec<&str> = content.lines().collect();
+
+    if matches!(extension, "java" | "kt")
+        && let Some(index) = lines.iter().rposition(|line| line.trim() == "}")
+    {
+        return (index as u32 + 1, 1);
+    }
+
+    (lines.len() as u32 + 1, 1)
+}
+
+/// Gets the content of a new test file for the source it tests
+pub fn test_stub(test_file: &Path, source: &str) -> String {
+    let extension = test_file
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let stem = test_file
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "go" => {
+            // Go tests share the package of the code under test
+            let package = source
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("package "))
+                .unwrap_or("main")
+                .trim();
+            format!("package {}\n\nimport \"testing\"\n", package)
+        }
+        "java" => format!("class {} {{\n}}\n", stem),
+        "kt" => format!("class {} {{\n}}\n", stem),
+        _ => String::new(),
+    }
+}
+
+/// Suggests a test function name following the language's convention
+pub fn test_name(symbol: &str, extension: &str) -> String {
+    let mut chars = symbol.chars();
+    let capitalized = match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    };
+
+    match extension {
+        "go" => format!("Test{}", capitalized),
+        "java" | "kt" | "ts" | "tsx" | "js" | "jsx" | "mjs" => format!("test{}", capitalized),
+        _ => format!("test_{}", to_snake_case(symbol)),
+    }
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
@@ -0,0 +1,248 @@
+use crate::lsp::Client;
+use anyhow::{Result, anyhow};
+use log::debug;
+use lsp_types::{
+    Position, TextDocumentPositionParams, TypeHierarchyItem, TypeHierarchyPrepareParams,
+    TypeHierarchySubtypesParams, TypeHierarchySupertypesParams,
+};
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+
+use super::budget;
+use super::definition::parse_symbol_location;
+use super::document_symbols::symbol_kind_name;
+use super::references::resolve_symbol;
+use super::utils::{display_path, to_path, to_text_document_identifier};
+
+/// Levels of the hierarchy listed when no depth is given
+pub const DEFAULT_DEPTH: u32 = 3;
+
+/// Most levels of the hierarchy that are listed
+pub const MAX_DEPTH: u32 = 10;
+
+/// Which side of the hierarchy to list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TypeHierarchyDirection {
+    /// Types the queried type extends or implements
+    Supertypes,
+    /// Types that extend or implement the queried type
+    Subtypes,
+    #[default]
+    Both,
+}
+
+impl TypeHierarchyDirection {
+    /// Parses a direction name as accepted by the tool
+    pub fn parse(direction: &str) -> Result<Self> {
+        match direction {
+            "supertypes" => Ok(Self::Supertypes),
+            "subtypes" => Ok(Self::Subtypes),
+            "both" => Ok(Self::Both),
+            _ => Err(anyhow!(
+                "Unknown direction '{}': expected supertypes, subtypes or both",
+                direction
+            )),
+        }
+    }
+}
+
+/// A type with the supertypes or subtypes found below it in the tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeNode {
+    pub item: TypeHierarchyItem,
+    pub children: Vec<TypeNode>,
+}
+
+/// Lists the supertypes and subtypes of a type as indented trees
+pub async fn get_type_hierarchy(
+    client: &Client,
+    symbol_name: &str,
+    direction: TypeHierarchyDirection,
+    depth: u32,
+) -> Result<String> {
+    debug!("[TOOL] Getting type hierarchy for {}", symbol_name);
+
+    let depth = depth.clamp(1, MAX_DEPTH);
+    let (item, note) = prepare_type_hierarchy(client, symbol_name).await?;
+    let display = |item: &TypeHierarchyItem| item_location(client, item);
+
+    let mut result = format!("Type hierarchy of {}\n", format_type_item(&item, &display));
+    let sides = [
+        (TypeHierarchyDirection::Supertypes, "Supertypes"),
+        (TypeHierarchyDirection::Subtypes, "Subtypes"),
+    ];
+    for (side, heading) in sides {
+        if direction != side && direction != TypeHierarchyDirection::Both {
+            continue;
+        }
+
+        // Each side tracks its own visited types; a type may appear on both
+        let mut seen = HashSet::new();
+        seen.insert(item_key(&item));
+        let nodes = expand(client, &item, side, depth, &mut seen).await?;
+
+        result.push_str(&format!("\n{}:\n", heading));
+        if nodes.is_empty() {
+            result.push_str("  none\n");
+        } else {
+            result.push_str(&format_type_tree(&nodes, &display));
+        }
+    }
+
+    let mut result = budget::fit_lines(&result);
+    result.push_str(&note);
+    Ok(result)
+}
+
+/// Resolves a symbol to the type hierarchy item for it, with a note when the
+/// name was ambiguous
+async fn prepare_type_hierarchy(
+    client: &Client,
+    symbol_name: &str,
+) -> Result<(TypeHierarchyItem, String)> {
+    // Take a path:line:column location, or look a bare name up in the workspace
+    let mut note = String::new();
+    let (file_path, line, column) = match parse_symbol_location(symbol_name) {
+        Ok(location) => location,
+        Err(_) => {
+            let mut candidates = resolve_symbol(client, symbol_name).await?;
+            if candidates.len() > 1 {
+                note = format!(
+                    "\nNote: {} other symbols are named '{}'; pass path:line:column to pick another.\n",
+                    candidates.len() - 1,
+                    symbol_name
+                );
+            }
+            candidates.remove(0)
+        }
+    };
+
+    // Ensure the file is open
+    client.open_file(&file_path).await?;
+
+    let params = TypeHierarchyPrepareParams {
+        text_document_position_params: TextDocumentPositionParams {
+            text_document: to_text_document_identifier(&file_path)?,
+            position: Position {
+                line,
+                character: column,
+            },
+        },
+        work_done_progress_params: Default::default(),
+    };
+    let items: Option<Vec<TypeHierarchyItem>> = client
+        .call("textDocument/prepareTypeHierarchy", params)
+        .await?;
+
+    let item = items
+        .unwrap_or_default()
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No type found at {}", symbol_name))?;
+    Ok((item, note))
+}
+
+/// Requests the supertypes or subtypes of a type, and theirs in turn, down to
+/// `depth` levels. Types already in the tree aren't expanded again, so cycles
+/// and diamonds end.
+fn expand<'a>(
+    client: &'a Client,
+    item: &'a TypeHierarchyItem,
+    side: TypeHierarchyDirection,
+    depth: u32,
+    seen: &'a mut HashSet<(String, Position)>,
+) -> Pin<Box<dyn Future<Output = Result<Vec<TypeNode>>> + Send + 'a>> {
+    Box::pin(async move {
+        if depth == 0 {
+            return Ok(Vec::new());
+        }
+
+        let items: Option<Vec<TypeHierarchyItem>> = match side {
+            TypeHierarchyDirection::Subtypes => {
+                let params = TypeHierarchySubtypesParams {
+                    item: item.clone(),
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                };
+                client.call("typeHierarchy/subtypes", params).await?
+            }
+            _ => {
+                let params = TypeHierarchySupertypesParams {
+                    item: item.clone(),
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                };
+                client.call("typeHierarchy/supertypes", params).await?
+            }
+        };
+
+        let mut nodes = Vec::new();
+        for child in items.unwrap_or_default() {
+            let children = if seen.insert(item_key(&child)) {
+                expand(client, &child, side, depth - 1, seen).await?
+            } else {
+                Vec::new()
+            };
+            nodes.push(TypeNode {
+                item: child,
+                children,
+            });
+        }
+        Ok(nodes)
+    })
+}
+
+/// Identifies a type by where its name is
+fn item_key(item: &TypeHierarchyItem) -> (String, Position) {
+    (item.uri.to_string(), item.selection_range.start)
+}
+
+/// Formats where a type is defined as `path:line`
+fn item_location(client: &Client, item: &TypeHierarchyItem) -> String {
+    let line = item.selection_range.start.line + 1;
+    match to_path(&item.uri) {
+        Ok(path) => format!("{}:{}", display_path(client, &path), line),
+        Err(_) => format!("{}:{}", item.uri, line),
+    }
+}
+
+/// Formats a type as its kind and name, with its detail and the location
+/// `display` gives for it
+pub fn format_type_item(
+    item: &TypeHierarchyItem,
+    display: &impl Fn(&TypeHierarchyItem) -> String,
+) -> String {
+    let mut text = format!("{} {}", symbol_kind_name(item.kind), item.name);
+    if let Some(detail) = item.detail.as_ref().filter(|d| !d.is_empty()) {
+        text.push_str(&format!(": {}", detail));
+    }
+    text.push_str(&format!(" ({})", display(item)));
+    text
+}
+
+/// Formats types as an indented tree, one type per line
+pub fn format_type_tree(
+    nodes: &[TypeNode],
+    display: &impl Fn(&TypeHierarchyItem) -> String,
+) -> String {
+    let mut result = String::new();
+    push_nodes(&mut result, nodes, 1, display);
+    result
+}
+
+fn push_nodes(
+    result: &mut String,
+    nodes: &[TypeNode],
+    level: usize,
+    display: &impl Fn(&TypeHierarchyItem) -> String,
+) {
+    for node in nodes {
+        result.push_str(&format!(
+            "{}{}\n",
+            "  ".repeat(level),
+            format_type_item(&node.item, display)
+        ));
+        push_nodes(result, &node.children, level + 1, display);
+    }
+}
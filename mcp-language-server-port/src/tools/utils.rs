@@ -1,7 +1,29 @@
+use crate::lsp::Client;
 use anyhow::{Context, Result, anyhow};
-use lsp_types::{Position, Range, TextDocumentIdentifier, TextDocumentPositionParams};
+use lsp_types::{Location, Position, Range, TextDocumentIdentifier, TextDocumentPositionParams};
 use path_absolutize::Absolutize;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether tool output shows absolute paths instead of workspace-relative ones
+static ABSOLUTE_PATHS: AtomicBool = AtomicBool::new(false);
+
+/// Switches tool output between absolute and workspace-relative paths
+pub fn set_absolute_paths(enabled: bool) {
+    ABSOLUTE_PATHS.store(enabled, Ordering::Relaxed);
+}
+
+/// Formats a path for tool output, relative to the workspace root when possible
+pub fn display_path(client: &Client, path: &Path) -> String {
+    if !ABSOLUTE_PATHS.load(Ordering::Relaxed)
+        && let Some(root) = client.workspace_root()
+        && let Ok(relative) = path.strip_prefix(&root)
+    {
+        return relative.display().to_string();
+    }
+
+    path.display().to_string()
+}
 
 /// Converts a file path to an LSP URI
 pub fn to_uri(path: &Path) -> lsp_types::Url {
@@ -52,10 +74,98 @@ pub fn to_range(start_line: u32, start_char: u32, end_line: u32, end_char: u32)
     }
 }
 
-/// Formats code with syntax highlighting
+/// Returns true if `inner` lies entirely within `outer`
+pub fn range_contains(outer: &Range, inner: &Range) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end
+}
+
+/// Merges the locations returned by several servers, keeping each location once
+/// together with the names of the servers that reported it
+pub fn merge_server_locations(
+    results: Vec<(String, Vec<Location>)>,
+) -> Vec<(Location, Vec<String>)> {
+    let mut merged: Vec<(Location, Vec<String>)> = Vec::new();
+
+    for (server, locations) in results {
+        for location in locations {
+            match merged.iter_mut().find(|(known, _)| *known == location) {
+                Some((_, servers)) if !servers.contains(&server) => servers.push(server.clone()),
+                Some(_) => {}
+                None => merged.push((location, vec![server.clone()])),
+            }
+        }
+    }
+
+    merged
+}
+
+/// Merges the output a tool produced on several servers. JSON lists are joined
+/// into one list; other output gets a heading naming each server.
+pub fn merge_server_outputs(outputs: Vec<(String, String)>) -> String {
+    if outputs.len() == 1 {
+        return outputs.into_iter().next().unwrap().1;
+    }
+
+    let lists: Option<Vec<serde_json::Value>> = outputs
+        .iter()
+        .map(|(_, output)| match serde_json::from_str(output) {
+            Ok(serde_json::Value::Array(items)) => Some(items),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|lists| lists.into_iter().flatten().collect());
+    if let Some(list) = lists
+        && let Ok(json) = serde_json::to_string_pretty(&list)
+    {
+        return json;
+    }
+
+    outputs
+        .iter()
+        .map(|(server, output)| format!("From {}:\n{}", server, output.trim_end()))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Formats which servers reported a result, for output merged from several servers
+pub fn format_servers(servers: &[String]) -> String {
+    format!(" (via {})", servers.join(", "))
+}
+
+/// Formats code in the output style of the current tool call
 pub fn format_code(code: &str, language: &str) -> String {
-    // Simple formatting for now
-    format!("```{}\n{}\n```", language, code)
+    super::render::renderer().code(code, language)
+}
+
+/// Gets the lines from `context_lines` before `start` to `context_lines`
+/// after `end`, clamped to the file
+pub fn context_window(
+    line_count: usize,
+    start: usize,
+    end: usize,
+    context_lines: u32,
+) -> std::ops::RangeInclusive<usize> {
+    let last = line_count.saturating_sub(1);
+    start.saturating_sub(context_lines as usize).min(last)
+        ..=(end + context_lines as usize).min(last)
+}
+
+/// Formats a line of a file with its 1-based number, as in diagnostics context
+pub fn format_numbered_line(lines: &[&str], line_num: usize) -> String {
+    format!("{:5} | {}\n", line_num + 1, lines[line_num])
+}
+
+/// Formats a count with thousands separators, as in `1,243`
+pub fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut result = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push(',');
+        }
+        result.push(digit);
+    }
+    result
 }
 
 /// Extracts a language from a file path
@@ -0,0 +1,454 @@
+use crate::lsp::position::{PositionEncoding, offset_at};
+use crate::lsp::{Client, ProxyEvent};
+use crate::workspace::editorconfig::EditorConfig;
+use crate::workspace::transaction::FileTransaction;
+use anyhow::{Context, Result, anyhow};
+use lsp_types::{
+    DocumentChangeOperation, DocumentChanges, FileChangeType, FileEvent, OneOf, ResourceOp,
+    TextDocumentEdit, TextEdit, Url, WorkspaceEdit,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use tokio::fs;
+
+use super::diff::unified_diff;
+use super::resource_ops::{apply_resource_op, describe_resource_op};
+use super::utils::{display_path, to_path};
+
+/// Most lines of diff shown for a file written with `show_diff`; dry runs
+/// show whole diffs
+const MAX_DIFF_LINES: usize = 100;
+
+/// How a workspace edit is applied
+#[derive(Debug, Clone, Default)]
+pub struct ApplyOptions {
+    /// Computes the result as diffs without writing files or notifying the servers
+    pub dry_run: bool,
+    /// Reports a diff of each file written
+    pub show_diff: bool,
+}
+
+/// What a workspace edit changed in one file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileReport {
+    pub path: PathBuf,
+    /// The path as shown in tool output
+    pub name: String,
+    pub edits: usize,
+    /// Unified diff of the change, for dry runs
+    pub diff: Option<String>,
+}
+
+/// The outcome of applying a workspace edit
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EditReport {
+    pub dry_run: bool,
+    pub files: Vec<FileReport>,
+    /// The file operations carried out, in order
+    pub operations: Vec<String>,
+}
+
+impl EditReport {
+    /// Gets the number of text edits applied across all files
+    pub fn edits(&self) -> usize {
+        self.files.iter().map(|file| file.edits).sum()
+    }
+
+    fn add_file(&mut self, client: &Client, path: PathBuf, edits: usize) -> &mut FileReport {
+        let index = match self.files.iter().position(|file| file.path == path) {
+            Some(index) => index,
+            None => {
+                self.files.push(FileReport {
+                    name: display_path(client, &path),
+                    path,
+                    edits: 0,
+                    diff: None,
+                });
+                self.files.len() - 1
+            }
+        };
+        let file = &mut self.files[index];
+        file.edits += edits;
+        file
+    }
+}
+
+impl fmt::Display for EditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.dry_run {
+            write!(f, "Dry run: would apply")?;
+        } else {
+            write!(f, "Applied")?;
+        }
+        write!(
+            f,
+            " {} edits across {} files",
+            self.edits(),
+            self.files.len()
+        )?;
+
+        for file in &self.files {
+            write!(f, "\n  {} ({} edits)", file.name, file.edits)?;
+        }
+        for operation in &self.operations {
+            write!(f, "\n{}", operation)?;
+        }
+
+        let diffs: String = self
+            .files
+            .iter()
+            .filter_map(|file| file.diff.clone())
+            .collect();
+        if !diffs.is_empty() {
+            write!(f, "\n\n{}", diffs)?;
+        }
+        Ok(())
+    }
+}
+
+/// Applies a workspace edit returned by a language server.
+///
+/// Edits to a file may come in any order but must not overlap, and edits for
+/// a versioned document must match the version the server has; both are
+/// checked before anything is written. Text edits are written together, so
+/// a failure leaves those files unchanged, and file operations are carried
+/// out in order between them.
+pub async fn apply(
+    client: &Client,
+    edit: WorkspaceEdit,
+    options: &ApplyOptions,
+) -> Result<EditReport> {
+    let steps = edit_steps(edit);
+    check_steps(client, &steps)?;
+
+    let mut report = EditReport {
+        dry_run: options.dry_run,
+        ..Default::default()
+    };
+    if options.dry_run {
+        preview_steps(client, steps, &mut report).await?;
+    } else {
+        apply_steps(client, steps, options.show_diff, &mut report).await?;
+    }
+    Ok(report)
+}
+
+/// One step of a workspace edit, in the order the server gave them
+enum EditStep {
+    Text {
+        uri: Url,
+        version: Option<i32>,
+        edits: Vec<TextEdit>,
+    },
+    Resource(ResourceOp),
+}
+
+/// Collects the text edits and file operations of a workspace edit
+fn edit_steps(edit: WorkspaceEdit) -> Vec<EditStep> {
+    let mut steps: Vec<EditStep> = edit
+        .changes
+        .into_iter()
+        .flatten()
+        .map(|(uri, edits)| EditStep::Text {
+            uri,
+            version: None,
+            edits,
+        })
+        .collect();
+
+    let text_step = |edit: TextDocumentEdit| EditStep::Text {
+        uri: edit.text_document.uri,
+        version: edit.text_document.version,
+        // Annotated edits apply like plain ones
+        edits: edit
+            .edits
+            .into_iter()
+            .map(|edit| match edit {
+                OneOf::Left(edit) => edit,
+                OneOf::Right(annotated) => annotated.text_edit,
+            })
+            .collect(),
+    };
+
+    match edit.document_changes {
+        Some(DocumentChanges::Edits(edits)) => {
+            steps.extend(edits.into_iter().map(text_step));
+        }
+        Some(DocumentChanges::Operations(operations)) => {
+            steps.extend(operations.into_iter().map(|operation| match operation {
+                DocumentChangeOperation::Edit(edit) => text_step(edit),
+                DocumentChangeOperation::Op(op) => EditStep::Resource(op),
+            }));
+        }
+        None => {}
+    }
+
+    steps
+}
+
+/// Rejects edits for outdated document versions and overlapping edits
+fn check_steps(client: &Client, steps: &[EditStep]) -> Result<()> {
+    let open_documents = client.open_documents();
+
+    for step in steps {
+        let EditStep::Text {
+            uri,
+            version,
+            edits,
+        } = step
+        else {
+            continue;
+        };
+
+        if let Some(version) = version
+            && let Some(document) = open_documents.iter().find(|d| d.uri == *uri)
+            && document.version != *version
+        {
+            return Err(anyhow!(
+                "The edit for {} was computed for version {} of the document, but it is at version {}",
+                uri,
+                version,
+                document.version
+            ));
+        }
+
+        for pair in sorted_edits(edits).windows(2) {
+            if pair[0].range.end > pair[1].range.start {
+                return Err(anyhow!(
+                    "Overlapping edits for {} at {}:{} and {}:{}",
+                    uri,
+                    pair[0].range.start.line + 1,
+                    pair[0].range.start.character + 1,
+                    pair[1].range.start.line + 1,
+                    pair[1].range.start.character + 1
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Orders edits by where they start; edits at the same position keep their
+/// order
+fn sorted_edits(edits: &[TextEdit]) -> Vec<&TextEdit> {
+    let mut sorted: Vec<&TextEdit> = edits.iter().collect();
+    sorted.sort_by_key(|edit| edit.range.start);
+    sorted
+}
+
+/// Applies text edits to the content of a file. Positions refer to the
+/// content before any of the edits, so they are applied from the end; ones
+/// outside the file are skipped.
+fn apply_edits_to_content(content: &str, edits: &[TextEdit], encoding: PositionEncoding) -> String {
+    let mut new_content = content.to_string();
+
+    for text_edit in sorted_edits(edits).into_iter().rev() {
+        // Convert the LSP positions to string indices and apply the edit
+        if let Some(start_index) = offset_at(&new_content, text_edit.range.start, encoding)
+            && let Some(end_index) = offset_at(&new_content, text_edit.range.end, encoding)
+            && start_index <= end_index
+        {
+            new_content = format!(
+                "{}{}{}",
+                &new_content[..start_index],
+                text_edit.new_text,
+                &new_content[end_index..],
+            );
+        }
+    }
+
+    new_content
+}
+
+/// A file changed by a workspace edit that is yet to be written
+struct EditedFile {
+    uri: Url,
+    path: PathBuf,
+    original: String,
+    new_content: String,
+    edits: usize,
+}
+
+async fn apply_steps(
+    client: &Client,
+    steps: Vec<EditStep>,
+    show_diff: bool,
+    report: &mut EditReport,
+) -> Result<()> {
+    let encoding = client.position_encoding();
+
+    // Compute new contents until a file operation needs them on disk
+    let mut files: Vec<EditedFile> = Vec::new();
+    for step in steps {
+        match step {
+            EditStep::Text { uri, edits, .. } => {
+                stage_edits(&mut files, uri, &edits, encoding).await?;
+            }
+            EditStep::Resource(op) => {
+                write_edited_files(client, std::mem::take(&mut files), show_diff, report).await?;
+                report
+                    .operations
+                    .push(apply_resource_op(client, &op).await?);
+            }
+        }
+    }
+    write_edited_files(client, files, show_diff, report).await
+}
+
+/// Applies text edits to the pending content of a file; later edits to the
+/// same file build on the earlier ones
+async fn stage_edits(
+    files: &mut Vec<EditedFile>,
+    uri: Url,
+    edits: &[TextEdit],
+    encoding: PositionEncoding,
+) -> Result<()> {
+    let index = match files.iter().position(|file| file.uri == uri) {
+        Some(index) => index,
+        None => {
+            let file_path = to_path(&uri)?;
+            let content = fs::read_to_string(&file_path)
+                .await
+                .context(format!("Failed to read file: {}", file_path.display()))?;
+            files.push(EditedFile {
+                uri,
+                path: file_path,
+                new_content: content.clone(),
+                original: content,
+                edits: 0,
+            });
+            files.len() - 1
+        }
+    };
+
+    let file = &mut files[index];
+    file.new_content = apply_edits_to_content(&file.new_content, edits, encoding);
+    file.edits += edits.len();
+    Ok(())
+}
+
+/// Writes staged files together and tells the servers about them
+async fn write_edited_files(
+    client: &Client,
+    mut files: Vec<EditedFile>,
+    show_diff: bool,
+    report: &mut EditReport,
+) -> Result<()> {
+    // Write the changes back to the files
+    let mut transaction = FileTransaction::new();
+    for file in &mut files {
+        file.new_content = EditorConfig::for_file(&file.path).finish_file(file.new_content.clone());
+        transaction.write(
+            file.path.clone(),
+            file.original.clone(),
+            file.new_content.clone(),
+        );
+    }
+    transaction.commit().await?;
+
+    // Notify the LSP server of the changes; files it doesn't have open are
+    // reported like changes on disk
+    let mut unopened = Vec::new();
+    for file in files {
+        if client.is_file_open(&file.path) {
+            client.notify_change(&file.path).await?;
+        } else {
+            unopened.push(FileEvent::new(file.uri.clone(), FileChangeType::CHANGED));
+        }
+        client.emit(ProxyEvent::EditApplied {
+            uri: file.uri,
+            edits: file.edits,
+        });
+        let reported = report.add_file(client, file.path, file.edits);
+        if show_diff {
+            let diff = unified_diff(&reported.name, &file.original, &file.new_content);
+            reported
+                .diff
+                .get_or_insert_with(String::new)
+                .push_str(&cap_lines(&diff, MAX_DIFF_LINES));
+        }
+    }
+    client.notify_watched_files(unopened).await
+}
+
+/// Computes the result of the steps as diffs without touching any file
+async fn preview_steps(
+    client: &Client,
+    steps: Vec<EditStep>,
+    report: &mut EditReport,
+) -> Result<()> {
+    let encoding = client.position_encoding();
+
+    // Content of files as earlier steps left them, None once deleted
+    let mut contents: HashMap<PathBuf, Option<String>> = HashMap::new();
+    // Edited files with their content before the first edit
+    let mut before: HashMap<PathBuf, String> = HashMap::new();
+
+    for step in steps {
+        match step {
+            EditStep::Text { uri, edits, .. } => {
+                let file_path = to_path(&uri)?;
+                let content = match contents.get(&file_path) {
+                    Some(content) => content.clone().unwrap_or_default(),
+                    None => fs::read_to_string(&file_path)
+                        .await
+                        .context(format!("Failed to read file: {}", file_path.display()))?,
+                };
+                before
+                    .entry(file_path.clone())
+                    .or_insert_with(|| content.clone());
+
+                let new_content = apply_edits_to_content(&content, &edits, encoding);
+                contents.insert(file_path.clone(), Some(new_content));
+                report.add_file(client, file_path, edits.len());
+            }
+            EditStep::Resource(op) => {
+                report.operations.push(describe_resource_op(client, &op)?);
+                match op {
+                    ResourceOp::Create(create) => {
+                        contents.insert(to_path(&create.uri)?, Some(String::new()));
+                    }
+                    ResourceOp::Rename(rename) => {
+                        let old_path = to_path(&rename.old_uri)?;
+                        let content = match contents.remove(&old_path) {
+                            Some(content) => content,
+                            None => fs::read_to_string(&old_path).await.ok(),
+                        };
+                        contents.insert(old_path, None);
+                        contents.insert(to_path(&rename.new_uri)?, content);
+                    }
+                    ResourceOp::Delete(delete) => {
+                        contents.insert(to_path(&delete.uri)?, None);
+                    }
+                }
+            }
+        }
+    }
+
+    for file in &mut report.files {
+        let after = contents
+            .get(&file.path)
+            .cloned()
+            .flatten()
+            .unwrap_or_default();
+        let after = EditorConfig::for_file(&file.path).finish_file(after);
+        file.diff = Some(unified_diff(&file.name, &before[&file.path], &after));
+    }
+
+    Ok(())
+}
+
+/// Keeps the first `max_lines` lines of a diff, noting how many were left out
+fn cap_lines(diff: &str, max_lines: usize) -> String {
+    let lines: Vec<&str> = diff.split_inclusive('\n').collect();
+    if lines.len() <= max_lines {
+        return diff.to_string();
+    }
+    format!(
+        "{}... {} more diff lines\n",
+        lines[..max_lines].concat(),
+        lines.len() - max_lines
+    )
+}
@@ -0,0 +1,67 @@
+use crate::lsp::Client;
+use crate::workspace::paths::normalize_path;
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Adds a folder to the workspace of every server. Relative folders are taken
+/// from the workspace root.
+pub async fn add_workspace_folder(clients: &[Arc<Client>], folder: &Path) -> Result<String> {
+    let folder = resolve_folder(clients, folder)?;
+    if !folder.is_dir() {
+        return Err(anyhow!("{} is not a directory", folder.display()));
+    }
+    debug!("[TOOL] Adding workspace folder {}", folder.display());
+
+    let mut added = Vec::new();
+    for client in clients {
+        if client.add_workspace_folder(&folder).await? {
+            added.push(client.name());
+        }
+    }
+
+    if added.is_empty() {
+        return Ok(format!(
+            "{} is already a workspace folder",
+            folder.display()
+        ));
+    }
+    Ok(format!(
+        "Added {} to the workspace of {}",
+        folder.display(),
+        added.join(", ")
+    ))
+}
+
+/// Removes a folder added with [`add_workspace_folder`] from the workspace of
+/// every server. The workspace root can't be removed.
+pub async fn remove_workspace_folder(clients: &[Arc<Client>], folder: &Path) -> Result<String> {
+    let folder = resolve_folder(clients, folder)?;
+    debug!("[TOOL] Removing workspace folder {}", folder.display());
+
+    let mut removed = Vec::new();
+    for client in clients {
+        if client.remove_workspace_folder(&folder).await? {
+            removed.push(client.name());
+        }
+    }
+
+    if removed.is_empty() {
+        return Ok(format!("{} is not a workspace folder", folder.display()));
+    }
+    Ok(format!(
+        "Removed {} from the workspace of {}",
+        folder.display(),
+        removed.join(", ")
+    ))
+}
+
+/// Makes a folder absolute against the primary server's workspace root
+pub fn resolve_folder(clients: &[Arc<Client>], folder: &Path) -> Result<PathBuf> {
+    let folder = match clients.first().and_then(|client| client.workspace_root()) {
+        Some(root) if folder.is_relative() => root.join(folder),
+        _ => folder.to_path_buf(),
+    };
+    normalize_path(&folder).context(format!("Invalid folder: {}", folder.display()))
+}
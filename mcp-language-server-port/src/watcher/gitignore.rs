@@ -1,15 +1,57 @@
 use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{Match, WalkBuilder};
 use log::{debug, error};
 use std::path::{Path, PathBuf};
-
-/// GitignoreFilter handles testing whether paths match patterns from gitignore files
+use std::sync::{Arc, RwLock};
+
+use crate::workspace::transaction;
+
+/// Patterns ignored in every workspace on top of its gitignore files. An
+/// `--ignore` pattern like `!node_modules/` takes one back.
+pub const DEFAULT_IGNORES: &[&str] = &[
+    ".git",
+    "node_modules/",
+    ".venv/",
+    "__pycache__/",
+    "*~",
+    "*.bak",
+    "*.swp",
+    transaction::STAGED_FILES,
+];
+
+/// Workspace file with more patterns to ignore, in gitignore syntax
+pub const IGNORE_FILE: &str = ".mcp-language-server-ignore";
+
+/// GitignoreFilter handles testing whether paths match patterns from gitignore files.
+///
+/// Like git, it reads every `.gitignore` in the workspace, applying each one's
+/// patterns relative to its own directory, then `.git/info/exclude` and the
+/// user's global gitignore (`core.excludesFile`). A deeper file overrides a
+/// shallower one, so a nested `!pattern` can re-include what the root ignores.
+/// Patterns from [`DEFAULT_IGNORES`], [`IGNORE_FILE`] and `--ignore` apply
+/// before all of these and can't be re-included by them.
+/// Clones share the same rules, and [`GitignoreFilter::reload`] updates them all.
+#[derive(Clone)]
 pub struct GitignoreFilter {
-    gitignore: Option<Gitignore>,
+    rules: Arc<RwLock<Rules>>,
     workspace_root: PathBuf,
 }
 
+/// Gitignore rules in order of precedence
+#[derive(Default)]
+struct Rules {
+    // Default, ignore file and command line patterns, in that order
+    extra: Option<Gitignore>,
+    patterns: Vec<String>,
+    // .gitignore files, deepest directory first
+    nested: Vec<Gitignore>,
+    exclude: Option<Gitignore>,
+    global: Option<Gitignore>,
+}
+
 impl GitignoreFilter {
     /// Get a reference to the workspace root path
+    #[allow(dead_code)]
     pub fn workspace_root(&self) -> &PathBuf {
         &self.workspace_root
     }
@@ -18,88 +60,195 @@ impl GitignoreFilter {
 impl GitignoreFilter {
     /// Create a new GitignoreFilter for the given workspace
     pub fn new(workspace_root: PathBuf) -> Self {
-        let gitignore = Self::build_gitignore(&workspace_root);
+        let rules = Rules::load(&workspace_root, Vec::new());
 
-        if gitignore.is_none() {
+        if rules.nested.is_empty() {
             debug!("[WATCHER] No .gitignore file found in workspace");
         }
 
         Self {
-            gitignore,
+            rules: Arc::new(RwLock::new(rules)),
             workspace_root,
         }
     }
 
+    /// Ignores paths matching these gitignore-style patterns too, beyond the defaults
+    pub fn set_extra_patterns(&self, patterns: Vec<String>) {
+        let mut rules = self.rules.write().unwrap();
+        rules.extra = Rules::build_extra(&self.workspace_root, &patterns);
+        rules.patterns = patterns;
+    }
+
+    /// Gets the patterns set with [`GitignoreFilter::set_extra_patterns`]
+    pub fn extra_patterns(&self) -> Vec<String> {
+        self.rules.read().unwrap().patterns.clone()
+    }
+
+    /// Reads the gitignore files again, e.g. after one was edited
+    pub fn reload(&self) {
+        debug!("[WATCHER] Reloading gitignore rules");
+        let patterns = self.rules.read().unwrap().patterns.clone();
+        *self.rules.write().unwrap() = Rules::load(&self.workspace_root, patterns);
+    }
+
+    /// Checks whether a change to `path` may change the gitignore rules
+    pub fn is_rules_file(path: &Path) -> bool {
+        path.file_name()
+            .is_some_and(|name| name == ".gitignore" || name == IGNORE_FILE)
+            || path.ends_with(".git/info/exclude")
+    }
+
     /// Check if a path should be ignored
     pub fn is_ignored(&self, path: &Path) -> bool {
-        // Some paths should always be ignored
-        let always_ignored = Self::is_always_ignored(path);
-        if always_ignored {
-            return true;
+        // Rules only cover the workspace
+        if !path.starts_with(&self.workspace_root) {
+            return false;
         }
 
-        // Check gitignore rules
-        if let Some(gitignore) = &self.gitignore {
-            // Convert path to be relative to workspace root
-            let rel_path = path.strip_prefix(&self.workspace_root).unwrap_or(path);
+        // The path may be gone already, as for removals
+        let is_dir = path.is_dir();
+        let rules = self.rules.read().unwrap();
+
+        // Configured patterns can't be overridden by gitignore files
+        if let Some(extra) = &rules.extra
+            && extra.matched_path_or_any_parents(path, is_dir).is_ignore()
+        {
+            return true;
+        }
 
-            // Check if path matches any gitignore patterns
-            return matches!(gitignore.matched(rel_path, false), ignore::Match::Ignore(_));
+        let applicable = rules
+            .nested
+            .iter()
+            .filter(|gitignore| path.starts_with(gitignore.path()))
+            .chain(&rules.exclude)
+            .chain(&rules.global);
+
+        // The most specific file with an opinion decides
+        for gitignore in applicable {
+            match gitignore.matched_path_or_any_parents(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
         }
 
-        // If no gitignore, don't ignore
         false
     }
+}
 
-    /// Build gitignore from .gitignore files in the workspace
-    fn build_gitignore(workspace_root: &Path) -> Option<Gitignore> {
-        let gitignore_path = workspace_root.join(".gitignore");
+impl Rules {
+    /// Reads the gitignore files that apply to a workspace
+    fn load(workspace_root: &Path, patterns: Vec<String>) -> Self {
+        let extra = Self::build_extra(workspace_root, &patterns);
+        let mut nested: Vec<Gitignore> = Self::find_gitignores(workspace_root, extra.clone())
+            .into_iter()
+            .filter_map(|path| {
+                let (gitignore, error) = Gitignore::new(&path);
+                if let Some(e) = error {
+                    error!("[WATCHER] Error parsing {}: {}", path.display(), e);
+                }
+                (!gitignore.is_empty()).then_some(gitignore)
+            })
+            .collect();
+        nested.sort_by_key(|gitignore| std::cmp::Reverse(gitignore.path().components().count()));
+
+        let exclude = Self::build(workspace_root, &workspace_root.join(".git/info/exclude"));
+
+        // core.excludesFile, or git's default location for it
+        let (global, error) = GitignoreBuilder::new(workspace_root).build_global();
+        if let Some(e) = error {
+            debug!("[WATCHER] Error reading global gitignore: {}", e);
+        }
 
-        if !gitignore_path.exists() {
-            return None;
+        Self {
+            extra,
+            patterns,
+            nested,
+            exclude,
+            global: (!global.is_empty()).then_some(global),
         }
+    }
 
+    /// Builds the matcher for the default patterns, the ignore file and the
+    /// patterns given on the command line
+    fn build_extra(workspace_root: &Path, patterns: &[String]) -> Option<Gitignore> {
         let mut builder = GitignoreBuilder::new(workspace_root);
+        for pattern in DEFAULT_IGNORES {
+            if let Err(e) = builder.add_line(None, pattern) {
+                error!(
+                    "[WATCHER] Invalid default ignore pattern {}: {}",
+                    pattern, e
+                );
+            }
+        }
+
+        let ignore_file = workspace_root.join(IGNORE_FILE);
+        if ignore_file.is_file()
+            && let Some(e) = builder.add(&ignore_file)
+        {
+            error!("[WATCHER] Error parsing {}: {}", ignore_file.display(), e);
+        }
 
-        match builder.add(gitignore_path) {
-            None => {}
-            Some(e) => {
-                error!("[WATCHER] Error parsing .gitignore: {}", e);
-                return None;
+        for pattern in patterns {
+            if let Err(e) = builder.add_line(None, pattern) {
+                error!("[WATCHER] Invalid ignore pattern {}: {}", pattern, e);
             }
         }
 
         match builder.build() {
             Ok(gitignore) => Some(gitignore),
             Err(e) => {
-                error!("[WATCHER] Error building gitignore: {}", e);
+                error!("[WATCHER] Error building ignore patterns: {}", e);
                 None
             }
         }
     }
 
-    /// Check if a path should always be ignored (e.g., .git directory)
-    fn is_always_ignored(path: &Path) -> bool {
-        let path_str = path.to_string_lossy();
+    /// Finds the .gitignore files in a workspace, skipping directories that
+    /// are themselves ignored
+    fn find_gitignores(workspace_root: &Path, extra: Option<Gitignore>) -> Vec<PathBuf> {
+        WalkBuilder::new(workspace_root)
+            .hidden(false)
+            .require_git(false)
+            .git_global(false)
+            .filter_entry(move |entry| {
+                let is_dir = entry
+                    .file_type()
+                    .is_some_and(|file_type| file_type.is_dir());
+                !extra
+                    .as_ref()
+                    .is_some_and(|extra| extra.matched(entry.path(), is_dir).is_ignore())
+            })
+            .build()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry.file_name() == ".gitignore"
+                    && entry
+                        .file_type()
+                        .is_some_and(|file_type| file_type.is_file())
+            })
+            .map(|entry| entry.into_path())
+            .collect()
+    }
 
-        // Always ignore .git directory
-        if path_str.contains("/.git/") || path_str.ends_with("/.git") {
-            return true;
+    /// Builds a matcher rooted at the workspace from a single ignore file
+    fn build(workspace_root: &Path, path: &Path) -> Option<Gitignore> {
+        if !path.is_file() {
+            return None;
         }
 
-        // Always ignore other common directories
-        if path_str.contains("/node_modules/")
-            || path_str.contains("/.venv/")
-            || path_str.contains("/__pycache__/")
-        {
-            return true;
+        let mut builder = GitignoreBuilder::new(workspace_root);
+        if let Some(e) = builder.add(path) {
+            error!("[WATCHER] Error parsing {}: {}", path.display(), e);
+            return None;
         }
 
-        // Ignore backup files
-        if path_str.ends_with('~') || path_str.contains(".bak") || path_str.contains(".swp") {
-            return true;
+        match builder.build() {
+            Ok(gitignore) => (!gitignore.is_empty()).then_some(gitignore),
+            Err(e) => {
+                error!("[WATCHER] Error building gitignore: {}", e);
+                None
+            }
         }
-
-        false
     }
 }
@@ -1,17 +1,32 @@
 pub mod gitignore;
 
-use crate::lsp::Client;
+use crate::lsp::{Client, ProxyEvent, Router};
+use crate::tools::utils::to_uri;
+use crate::workspace::hashes::FileHashes;
+use crate::workspace::paths::normalize_path;
 
 use anyhow::{Context, Result, anyhow};
 use async_trait::async_trait;
 use log::{debug, error, info};
+use lsp_types::{FileChangeType, FileEvent};
+use notify::event::{ModifyKind, RenameMode};
 use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use path_absolutize::Absolutize;
-use std::{path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
 use tokio::sync::{broadcast, mpsc};
 
 use self::gitignore::GitignoreFilter;
 
+/// How long a removed open file may take to reappear before it is closed.
+/// Editors that save via rename briefly remove the file before recreating it.
+const RECREATE_GRACE_PERIOD: Duration = Duration::from_millis(500);
+
 /// Interface for a workspace watcher
 #[async_trait]
 pub trait WorkspaceWatcher: Send + Sync {
@@ -22,16 +37,25 @@ pub trait WorkspaceWatcher: Send + Sync {
     async fn stop(&self) -> Result<()>;
 }
 
-/// FileSystemWatcher watches a workspace for file changes and notifies the LSP client
+/// FileSystemWatcher watches a workspace for file changes and notifies the LSP clients
 pub struct FileSystemWatcher {
-    lsp_client: Arc<Client>,
+    router: Router,
     gitignore_filter: GitignoreFilter,
+    // Filters of the folders added with add_folder, each for its own root
+    folder_filters: Arc<RwLock<Vec<GitignoreFilter>>>,
+    file_hashes: Arc<FileHashes>,
     watcher_tx: broadcast::Sender<WatcherCommand>,
+    // Whether changes are held back until resume is called
+    paused: Arc<AtomicBool>,
 }
 
 #[derive(Clone)]
 enum WatcherCommand {
     Stop,
+    // Stops watching one folder added with add_folder
+    Unwatch(PathBuf),
+    // Passes on the changes held back while paused
+    Resume,
 }
 
 impl FileSystemWatcher {
@@ -41,61 +65,103 @@ impl FileSystemWatcher {
         let (watcher_tx, _) = broadcast::channel(10);
 
         Self {
-            lsp_client,
+            router: Router::new(lsp_client),
             gitignore_filter,
+            folder_filters: Arc::new(RwLock::new(Vec::new())),
+            file_hashes: FileHashes::new(),
             watcher_tx,
+            paused: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    /// Process a file change event
-    async fn process_event(&self, event: Event) -> Result<()> {
-        match event.kind {
-            EventKind::Create(_) | EventKind::Modify(_) => {
-                for path in event.paths {
-                    if self.gitignore_filter.is_ignored(&path) {
-                        continue;
-                    }
+    /// Notifies every server of the router instead of just one
+    pub fn with_router(mut self, router: Router) -> Self {
+        self.router = router;
+        self
+    }
 
-                    if path.is_file() {
-                        debug!("[WATCHER] File changed: {}", path.display());
+    /// Keeps the given content hashes up to date instead of a private set
+    pub fn with_file_hashes(mut self, file_hashes: Arc<FileHashes>) -> Self {
+        self.file_hashes = file_hashes;
+        self
+    }
 
-                        // If the file is already open, notify the LSP client of the change
-                        // Otherwise, just make sure the LSP server knows about it
-                        let absolute_path = path.absolutize()?;
-                        if self.lsp_client.is_file_open(&absolute_path) {
-                            self.lsp_client.notify_change(&absolute_path).await?;
-                        }
-                    }
-                }
+    /// Ignores paths matching these gitignore-style patterns as well as the
+    /// workspace's gitignore files
+    pub fn with_ignore_patterns(self, patterns: Vec<String>) -> Self {
+        self.gitignore_filter.set_extra_patterns(patterns);
+        self
+    }
+
+    /// Starts watching a folder added to the workspace, ignoring what its
+    /// own gitignore files and the workspace's ignore patterns ignore
+    pub async fn add_folder(&self, folder: &Path) -> Result<()> {
+        let folder = normalize_path(folder).context("Failed to normalize folder path")?;
+        {
+            let mut filters = self.folder_filters.write().unwrap();
+            if filters.iter().any(|f| *f.workspace_root() == folder) {
+                return Ok(());
             }
-            EventKind::Remove(_) => {
-                for path in event.paths {
-                    if self.gitignore_filter.is_ignored(&path) {
-                        continue;
-                    }
+            let filter = GitignoreFilter::new(folder.clone());
+            filter.set_extra_patterns(self.gitignore_filter.extra_patterns());
+            filters.push(filter);
+        }
 
-                    debug!("[WATCHER] File removed: {}", path.display());
+        self.watch(folder, false).await
+    }
 
-                    // If the file is open, close it
-                    let absolute_path = path.absolutize()?;
-                    if self.lsp_client.is_file_open(&absolute_path) {
-                        self.lsp_client.close_file(&absolute_path).await?;
-                    }
-                }
-            }
-            _ => {}
+    /// Stops watching a folder added with [`FileSystemWatcher::add_folder`]
+    pub async fn remove_folder(&self, folder: &Path) -> Result<()> {
+        let folder = normalize_path(folder).context("Failed to normalize folder path")?;
+        {
+            let mut filters = self.folder_filters.write().unwrap();
+            let Some(index) = filters.iter().position(|f| *f.workspace_root() == folder) else {
+                return Err(anyhow!("{} is not a watched folder", folder.display()));
+            };
+            filters.remove(index);
         }
 
+        self.watcher_tx
+            .send(WatcherCommand::Unwatch(folder))
+            .map_err(|e| anyhow!("Failed to send unwatch command to watcher: {}", e))?;
+
         Ok(())
     }
-}
 
-#[async_trait]
-impl WorkspaceWatcher for FileSystemWatcher {
-    async fn watch_workspace(&self, workspace_path: PathBuf) -> Result<()> {
-        let workspace_path = workspace_path
-            .absolutize()
-            .context("Failed to absolutize workspace path")?;
+    /// Holds back file changes until [`FileSystemWatcher::resume`], so a
+    /// server that is restarting doesn't miss them
+    pub fn pause(&self) {
+        if !self.paused.swap(true, Ordering::SeqCst) {
+            info!("[WATCHER] Pausing file watcher");
+            self.router.primary().emit(ProxyEvent::WatcherPaused);
+        }
+    }
+
+    /// Passes on file changes again, starting with those held back while paused
+    pub fn resume(&self) {
+        if self.paused.swap(false, Ordering::SeqCst) {
+            info!("[WATCHER] Resuming file watcher");
+            // Watch tasks that have nothing held back don't need to hear it
+            let _ = self.watcher_tx.send(WatcherCommand::Resume);
+            self.router.primary().emit(ProxyEvent::WatcherResumed);
+        }
+    }
+
+    /// Checks whether a path is ignored by the rules of the innermost
+    /// watched folder containing it
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let filters = self.folder_filters.read().unwrap();
+        filters
+            .iter()
+            .filter(|f| path.starts_with(f.workspace_root()))
+            .max_by_key(|f| f.workspace_root().components().count())
+            .unwrap_or(&self.gitignore_filter)
+            .is_ignored(path)
+    }
+
+    /// Watches a normalized folder until stopped, or until unwatched if it
+    /// isn't the workspace root
+    async fn watch(&self, workspace_path: PathBuf, is_root: bool) -> Result<()> {
         info!(
             "[WATCHER] Starting file watcher for workspace: {}",
             workspace_path.display()
@@ -129,13 +195,16 @@ impl WorkspaceWatcher for FileSystemWatcher {
         tokio::spawn(async move {
             // Create a channel for the watcher commands
             let mut watcher_rx = watcher_tx.subscribe();
+            // Events that arrived while the watcher was paused
+            let mut held = Vec::new();
 
             loop {
                 tokio::select! {
                     // Process file change events
                     Some(event) = rx.recv() => {
-                        if let Err(e) = self_clone.process_event(event).await {
-                            error!("[WATCHER] Error processing file event: {}", e);
+                        held.push(event);
+                        if !self_clone.paused.load(Ordering::SeqCst) {
+                            self_clone.process_events(&mut held).await;
                         }
                     }
 
@@ -146,6 +215,12 @@ impl WorkspaceWatcher for FileSystemWatcher {
                                 info!("[WATCHER] Stopping file watcher");
                                 break;
                             }
+                            WatcherCommand::Unwatch(path) if !is_root && path == workspace_path => {
+                                info!("[WATCHER] Unwatching {}", workspace_path.display());
+                                break;
+                            }
+                            WatcherCommand::Unwatch(_) => {}
+                            WatcherCommand::Resume => self_clone.process_events(&mut held).await,
                         }
                     }
 
@@ -156,12 +231,267 @@ impl WorkspaceWatcher for FileSystemWatcher {
 
             // Drop the watcher to stop watching
             drop(watcher);
-            info!("[WATCHER] File watcher stopped");
+            if is_root {
+                info!("[WATCHER] File watcher stopped");
+                self_clone.router.primary().emit(ProxyEvent::WatcherStopped);
+            }
         });
 
         Ok(())
     }
 
+    /// Processes the given file change events in order, leaving none behind
+    async fn process_events(&self, events: &mut Vec<Event>) {
+        for event in events.drain(..) {
+            if let Err(e) = self.process_event(event).await {
+                error!("[WATCHER] Error processing file event: {}", e);
+            }
+        }
+    }
+
+    /// Process a file change event
+    async fn process_event(&self, event: Event) -> Result<()> {
+        if event
+            .paths
+            .iter()
+            .any(|path| GitignoreFilter::is_rules_file(path))
+        {
+            self.gitignore_filter.reload();
+            for filter in self.folder_filters.read().unwrap().iter() {
+                filter.reload();
+            }
+        }
+
+        // Every non-ignored file is reported, open or not
+        let mut changes = Vec::new();
+
+        match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                self.process_rename(&event.paths[0], &event.paths[1], &mut changes)
+                    .await?;
+            }
+            EventKind::Create(_) | EventKind::Modify(_) => {
+                // A rename reports the old path, then the new one
+                let created = matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))
+                );
+                for path in event.paths {
+                    if self.is_ignored(&path) {
+                        continue;
+                    }
+
+                    if !path.exists() {
+                        if matches!(event.kind, EventKind::Modify(ModifyKind::Name(_))) {
+                            debug!("[WATCHER] File renamed away: {}", path.display());
+                            changes.push(FileEvent::new(
+                                to_uri(&normalize_path(&path)?),
+                                FileChangeType::DELETED,
+                            ));
+                        }
+                        continue;
+                    }
+
+                    if path.is_file() {
+                        debug!("[WATCHER] File changed: {}", path.display());
+
+                        // Writes that leave the content as it was need no resync
+                        let absolute_path = normalize_path(&path)?;
+                        let changed = match self.file_hashes.refresh(&absolute_path).await {
+                            Ok(changed) => changed,
+                            Err(e) => {
+                                debug!("[WATCHER] Could not hash {}: {}", path.display(), e);
+                                true
+                            }
+                        };
+
+                        if !changed {
+                            debug!("[WATCHER] Content unchanged: {}", path.display());
+                            continue;
+                        }
+
+                        // Servers with the file open get the new content; the
+                        // others just learn that it changed on disk
+                        changes.push(FileEvent::new(
+                            to_uri(&absolute_path),
+                            if created {
+                                FileChangeType::CREATED
+                            } else {
+                                FileChangeType::CHANGED
+                            },
+                        ));
+                        for client in self.router.clients() {
+                            if client.is_file_dirty(&absolute_path) {
+                                // Don't clobber in-memory content the server is working with
+                                debug!(
+                                    "[WATCHER] Skipping change for dirty file: {}",
+                                    path.display()
+                                );
+                            } else if client.is_file_open(&absolute_path) {
+                                client.notify_change(&absolute_path).await?;
+                            }
+                        }
+                    }
+                }
+            }
+            EventKind::Remove(_) => {
+                for path in event.paths {
+                    if self.is_ignored(&path) {
+                        continue;
+                    }
+
+                    debug!("[WATCHER] File removed: {}", path.display());
+
+                    // If the file is open, close it unless it is recreated shortly
+                    let absolute_path = normalize_path(&path)?;
+                    self.file_hashes.remove(&absolute_path);
+                    changes.push(FileEvent::new(
+                        to_uri(&absolute_path),
+                        FileChangeType::DELETED,
+                    ));
+                    for client in self.router.clients() {
+                        if !client.is_file_open(&absolute_path) {
+                            continue;
+                        }
+                        let absolute_path = absolute_path.clone();
+                        tokio::spawn(async move {
+                            tokio::time::sleep(RECREATE_GRACE_PERIOD).await;
+
+                            let result = if client.is_file_dirty(&absolute_path) {
+                                // The server works from in-memory content, not the file on disk
+                                Ok(())
+                            } else if absolute_path.is_file() {
+                                debug!(
+                                    "[WATCHER] File recreated, reloading: {}",
+                                    absolute_path.display()
+                                );
+                                client.notify_change(&absolute_path).await
+                            } else {
+                                client.close_file(&absolute_path).await
+                            };
+
+                            if let Err(e) = result {
+                                error!(
+                                    "[WATCHER] Error handling removal of {}: {}",
+                                    absolute_path.display(),
+                                    e
+                                );
+                            }
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        for client in self.router.clients() {
+            client.notify_watched_files(changes.clone()).await?;
+        }
+        if !changes.is_empty() {
+            self.router
+                .primary()
+                .emit(ProxyEvent::FilesChanged { changes });
+        }
+        Ok(())
+    }
+
+    /// Processes a file moved from `from` to `to`, which the server must see as
+    /// the old file going away rather than as two unrelated files
+    async fn process_rename(
+        &self,
+        from: &Path,
+        to: &Path,
+        changes: &mut Vec<FileEvent>,
+    ) -> Result<()> {
+        let from_ignored = self.is_ignored(from);
+        let to_ignored = self.is_ignored(to);
+        if from_ignored && to_ignored {
+            return Ok(());
+        }
+        debug!(
+            "[WATCHER] File renamed: {} -> {}",
+            from.display(),
+            to.display()
+        );
+
+        let old_path = normalize_path(from)?;
+        let new_path = normalize_path(to)?;
+        self.file_hashes.remove(&old_path);
+        if !from_ignored {
+            changes.push(FileEvent::new(to_uri(&old_path), FileChangeType::DELETED));
+        }
+        // A file moved over its target from an ignored path, like a staged
+        // transactional write, changes the target's content
+        let mut replaced = false;
+        if !to_ignored {
+            let changed =
+                !new_path.is_file() || self.file_hashes.refresh(&new_path).await.unwrap_or(true);
+            if !from_ignored {
+                changes.push(FileEvent::new(to_uri(&new_path), FileChangeType::CREATED));
+            } else if changed {
+                replaced = true;
+                changes.push(FileEvent::new(to_uri(&new_path), FileChangeType::CHANGED));
+            }
+        }
+
+        for client in self.router.clients() {
+            // Open documents move with the file or directory; in-memory edits made
+            // under the old name are dropped
+            let moved: Vec<(PathBuf, PathBuf)> = client
+                .open_documents()
+                .into_iter()
+                .filter_map(|document| document.uri.to_file_path().ok())
+                .filter_map(|path| {
+                    let relative = path.strip_prefix(&old_path).ok()?;
+                    let moved_to = if relative.as_os_str().is_empty() {
+                        new_path.clone()
+                    } else {
+                        new_path.join(relative)
+                    };
+                    Some((path, moved_to))
+                })
+                .collect();
+            for (old_document, new_document) in moved {
+                client.close_file(&old_document).await?;
+                if !to_ignored && new_document.is_file() {
+                    client.open_file(&new_document).await?;
+                }
+            }
+            if replaced && client.is_file_open(&new_path) && !client.is_file_dirty(&new_path) {
+                client.notify_change(&new_path).await?;
+            }
+
+            if !from_ignored && !to_ignored && Self::server_handles_renames(&client) {
+                let params = lsp_types::RenameFilesParams {
+                    files: vec![lsp_types::FileRename {
+                        old_uri: to_uri(&old_path).to_string(),
+                        new_uri: to_uri(&new_path).to_string(),
+                    }],
+                };
+                client.notify("workspace/didRenameFiles", params).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether a server asked to be told about renamed files
+    fn server_handles_renames(client: &Client) -> bool {
+        client
+            .server_capabilities()
+            .and_then(|capabilities| capabilities.workspace?.file_operations?.did_rename)
+            .is_some()
+    }
+}
+
+#[async_trait]
+impl WorkspaceWatcher for FileSystemWatcher {
+    async fn watch_workspace(&self, workspace_path: PathBuf) -> Result<()> {
+        let workspace_path =
+            normalize_path(&workspace_path).context("Failed to normalize workspace path")?;
+        self.watch(workspace_path, true).await
+    }
+
     async fn stop(&self) -> Result<()> {
         // Send stop command to the watcher task
         self.watcher_tx
@@ -176,9 +506,12 @@ impl WorkspaceWatcher for FileSystemWatcher {
 impl Clone for FileSystemWatcher {
     fn clone(&self) -> Self {
         Self {
-            lsp_client: Arc::clone(&self.lsp_client),
-            gitignore_filter: GitignoreFilter::new(self.gitignore_filter.workspace_root().clone()),
+            router: self.router.clone(),
+            gitignore_filter: self.gitignore_filter.clone(),
+            folder_filters: Arc::clone(&self.folder_filters),
+            file_hashes: Arc::clone(&self.file_hashes),
             watcher_tx: self.watcher_tx.clone(),
+            paused: Arc::clone(&self.paused),
         }
     }
 }
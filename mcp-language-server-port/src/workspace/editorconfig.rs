@@ -0,0 +1,221 @@
+use globset::GlobBuilder;
+use log::debug;
+use std::path::Path;
+
+/// Byte order mark written for `charset = utf-8-bom`
+const BOM: char = '\u{feff}';
+
+/// Indentation a project asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    Tab,
+    Space,
+}
+
+/// The `.editorconfig` properties that apply to one file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EditorConfig {
+    pub indent_style: Option<IndentStyle>,
+    pub indent_size: Option<usize>,
+    pub insert_final_newline: Option<bool>,
+    pub charset: Option<String>,
+}
+
+/// A `[glob]` section of an `.editorconfig` file with its raw properties
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub pattern: String,
+    pub properties: Vec<(String, String)>,
+}
+
+/// A parsed `.editorconfig` file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EditorConfigFile {
+    pub root: bool,
+    pub sections: Vec<Section>,
+}
+
+/// Parses the content of an `.editorconfig` file. Keys and values are lowercased.
+pub fn parse(content: &str) -> EditorConfigFile {
+    let mut file = EditorConfigFile::default();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(pattern) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            file.sections.push(Section {
+                pattern: pattern.to_string(),
+                properties: Vec::new(),
+            });
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim().to_lowercase();
+
+        match file.sections.last_mut() {
+            Some(section) => section.properties.push((key, value)),
+            // Only `root` may appear before the first section
+            None if key == "root" => file.root = value == "true",
+            None => {}
+        }
+    }
+
+    file
+}
+
+/// Checks whether a section pattern matches a path relative to the directory
+/// of the `.editorconfig` file
+fn section_matches(pattern: &str, relative: &Path) -> bool {
+    // Patterns without a slash match file names in any directory
+    let pattern = match pattern.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if pattern.contains('/') => pattern.to_string(),
+        None => format!("**/{}", pattern),
+    };
+
+    match GlobBuilder::new(&pattern).literal_separator(true).build() {
+        Ok(glob) => glob.compile_matcher().is_match(relative),
+        Err(e) => {
+            debug!(
+                "[WORKSPACE] Invalid .editorconfig pattern {}: {}",
+                pattern, e
+            );
+            false
+        }
+    }
+}
+
+impl EditorConfig {
+    /// Resolves the properties for a file from every `.editorconfig` between it
+    /// and the nearest one marked `root = true`
+    pub fn for_file(path: &Path) -> Self {
+        let mut files = Vec::new();
+
+        for dir in path.ancestors().skip(1) {
+            let Ok(content) = std::fs::read_to_string(dir.join(".editorconfig")) else {
+                continue;
+            };
+            let file = parse(&content);
+            let root = file.root;
+            files.push((dir.to_path_buf(), file));
+            if root {
+                break;
+            }
+        }
+
+        // Closer files take precedence, so apply them last
+        let mut config = Self::default();
+        for (dir, file) in files.iter().rev() {
+            let Ok(relative) = path.strip_prefix(dir) else {
+                continue;
+            };
+            for section in &file.sections {
+                if section_matches(&section.pattern, relative) {
+                    config.apply(&section.properties);
+                }
+            }
+        }
+
+        config
+    }
+
+    /// Applies the properties of a matching section
+    pub fn apply(&mut self, properties: &[(String, String)]) {
+        let mut tab_width = None;
+
+        for (key, value) in properties {
+            match key.as_str() {
+                "indent_style" => {
+                    self.indent_style = match value.as_str() {
+                        "tab" => Some(IndentStyle::Tab),
+                        "space" => Some(IndentStyle::Space),
+                        _ => None,
+                    }
+                }
+                "indent_size" => self.indent_size = value.parse().ok().or(self.indent_size),
+                "tab_width" => tab_width = value.parse().ok(),
+                "insert_final_newline" => {
+                    self.insert_final_newline = match value.as_str() {
+                        "true" => Some(true),
+                        "false" => Some(false),
+                        _ => None,
+                    }
+                }
+                "charset" => self.charset = Some(value.clone()),
+                _ => {}
+            }
+        }
+
+        // `indent_size = tab` means "use tab_width"
+        if properties
+            .iter()
+            .any(|(key, value)| key == "indent_size" && value == "tab")
+        {
+            self.indent_size = tab_width.or(self.indent_size);
+        }
+    }
+
+    /// Rewrites the leading indentation of text about to be inserted so it uses
+    /// the configured indent style
+    pub fn format_new_text(&self, text: &str) -> String {
+        let (Some(style), Some(size)) = (self.indent_style, self.indent_size) else {
+            return text.to_string();
+        };
+        if size == 0 {
+            return text.to_string();
+        }
+
+        text.split_inclusive('\n')
+            .map(|line| {
+                let rest = line.trim_start_matches([' ', '\t']);
+                let indent = &line[..line.len() - rest.len()];
+
+                // Measure the indentation in columns, then rebuild it
+                let columns = indent
+                    .chars()
+                    .map(|c| if c == '\t' { size } else { 1 })
+                    .sum::<usize>();
+                let indent = match style {
+                    IndentStyle::Tab => {
+                        format!(
+                            "{}{}",
+                            "\t".repeat(columns / size),
+                            " ".repeat(columns % size)
+                        )
+                    }
+                    IndentStyle::Space => " ".repeat(columns),
+                };
+
+                format!("{}{}", indent, rest)
+            })
+            .collect()
+    }
+
+    /// Applies the whole-file rules (final newline, byte order mark) to content
+    /// before it is written
+    pub fn finish_file(&self, mut content: String) -> String {
+        if self.insert_final_newline == Some(true)
+            && !content.is_empty()
+            && !content.ends_with('\n')
+        {
+            content.push('\n');
+        }
+
+        match self.charset.as_deref() {
+            Some("utf-8-bom") if !content.starts_with(BOM) => content.insert(0, BOM),
+            Some("utf-8") if content.starts_with(BOM) => {
+                content.remove(0);
+            }
+            _ => {}
+        }
+
+        content
+    }
+}
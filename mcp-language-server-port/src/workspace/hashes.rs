@@ -0,0 +1,118 @@
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Hashes file content with xxHash (XXH3, 64-bit)
+pub fn hash_content(content: &[u8]) -> u64 {
+    xxhash_rust::xxh3::xxh3_64(content)
+}
+
+/// Formats a content hash the way tools report it
+pub fn format_hash(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
+/// Content hashes of workspace files, kept current by the file watcher.
+///
+/// Hashes are computed the first time a file is asked for and refreshed on
+/// every change the watcher sees, so unchanged writes can be told apart from
+/// real edits.
+#[derive(Debug, Default)]
+pub struct FileHashes {
+    hashes: RwLock<HashMap<PathBuf, u64>>,
+}
+
+impl FileHashes {
+    /// Creates an empty hash store
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Gets the hash of a file, reading it from disk if it isn't known yet
+    pub async fn hash(&self, path: &Path) -> Result<u64> {
+        if let Some(hash) = self.hashes.read().unwrap().get(path) {
+            return Ok(*hash);
+        }
+        self.refresh(path).await?;
+        self.hashes
+            .read()
+            .unwrap()
+            .get(path)
+            .copied()
+            .ok_or_else(|| anyhow!("File was removed while hashing: {}", path.display()))
+    }
+
+    /// Rehashes a file from disk, returning true if its content changed since
+    /// the last time it was hashed (or it wasn't known before)
+    pub async fn refresh(&self, path: &Path) -> Result<bool> {
+        let content = tokio::fs::read(path)
+            .await
+            .context(format!("Failed to read file: {}", path.display()))?;
+        let hash = hash_content(&content);
+
+        let previous = self
+            .hashes
+            .write()
+            .unwrap()
+            .insert(path.to_path_buf(), hash);
+        if previous != Some(hash) {
+            debug!(
+                "[WORKSPACE] Content hash of {} is now {}",
+                path.display(),
+                format_hash(hash)
+            );
+        }
+        Ok(previous != Some(hash))
+    }
+
+    /// Forgets the hash of a removed file
+    pub fn remove(&self, path: &Path) {
+        self.hashes.write().unwrap().remove(path);
+    }
+
+    /// Fails if a file's current content doesn't match the hash a caller last saw
+    pub async fn check(&self, path: &Path, expected: &str) -> Result<()> {
+        // Compare against the disk, not the cache, so unseen writes are caught too
+        self.refresh(path).await?;
+        let actual = format_hash(self.hash(path).await?);
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(anyhow!(
+                "{} has changed (content hash {} instead of {}); read it again before editing",
+                path.display(),
+                actual,
+                expected.trim()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Gets a copy of every known hash
+    #[allow(dead_code)]
+    pub fn snapshot(&self) -> HashMap<PathBuf, u64> {
+        self.hashes.read().unwrap().clone()
+    }
+}
+
+/// Lists the files whose hash differs between two snapshots, including files
+/// that only appear in one of them
+#[allow(dead_code)]
+pub fn changed_files(
+    before: &HashMap<PathBuf, u64>,
+    after: &HashMap<PathBuf, u64>,
+) -> Vec<PathBuf> {
+    let mut changed: Vec<PathBuf> = after
+        .iter()
+        .filter(|(path, hash)| before.get(*path) != Some(*hash))
+        .map(|(path, _)| path.clone())
+        .chain(
+            before
+                .keys()
+                .filter(|path| !after.contains_key(*path))
+                .cloned(),
+        )
+        .collect();
+    changed.sort();
+    changed
+}
@@ -0,0 +1,96 @@
+pub mod editorconfig;
+pub mod hashes;
+pub mod paths;
+pub mod transaction;
+
+use log::{debug, info, warn};
+use std::path::{Path, PathBuf};
+
+/// Returns the files that mark a project root for a known language server
+pub fn project_markers(lsp_command: &str) -> &'static [&'static str] {
+    let name = Path::new(lsp_command)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(lsp_command);
+
+    match name {
+        "rust-analyzer" => &["Cargo.toml"],
+        "gopls" => &["go.mod", "go.work"],
+        "pyright-langserver" | "pyright" | "pylsp" | "ruff-lsp" => &[
+            "pyproject.toml",
+            "setup.py",
+            "setup.cfg",
+            "requirements.txt",
+        ],
+        "typescript-language-server" | "tsserver" => &["package.json", "tsconfig.json"],
+        "clangd" => &["compile_commands.json", "CMakeLists.txt", ".clangd"],
+        _ => &[],
+    }
+}
+
+/// Checks whether a directory contains any of the given project markers
+pub fn has_project_marker(dir: &Path, markers: &[&str]) -> bool {
+    markers.iter().any(|marker| dir.join(marker).exists())
+}
+
+/// Searches `start` and its ancestors for the nearest directory with a project marker
+pub fn find_project_root(start: &Path, markers: &[&str]) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| has_project_marker(dir, markers))
+        .map(Path::to_path_buf)
+}
+
+/// Result of checking the workspace against the language server's project markers
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceCheck {
+    /// The workspace to use, which may be an ancestor of the requested one
+    pub root: PathBuf,
+    /// Set when the workspace doesn't look like a project root for the server
+    pub warning: Option<String>,
+}
+
+/// Checks that `workspace` looks like a project root for `lsp_command`.
+///
+/// When the markers are missing and `search_upward` is set, the nearest
+/// ancestor with a marker becomes the root. Otherwise the workspace is kept
+/// and a warning describes what was expected.
+pub fn check_workspace(workspace: &Path, lsp_command: &str, search_upward: bool) -> WorkspaceCheck {
+    let markers = project_markers(lsp_command);
+
+    if markers.is_empty() || has_project_marker(workspace, markers) {
+        debug!("[WORKSPACE] Workspace looks like a project root");
+        return WorkspaceCheck {
+            root: workspace.to_path_buf(),
+            warning: None,
+        };
+    }
+
+    // Relative paths like "." have no ancestors to search
+    let absolute = paths::normalize_path(workspace).unwrap_or_else(|_| workspace.to_path_buf());
+
+    if search_upward && let Some(root) = find_project_root(&absolute, markers) {
+        info!(
+            "[WORKSPACE] Using project root {} instead of {}",
+            root.display(),
+            workspace.display()
+        );
+        return WorkspaceCheck {
+            root,
+            warning: None,
+        };
+    }
+
+    let warning = format!(
+        "The workspace {} has none of the project files {} expects ({}), so it may not be a project root and tools may return no results.",
+        workspace.display(),
+        lsp_command,
+        markers.join(", ")
+    );
+    warn!("[WORKSPACE] {}", warning);
+
+    WorkspaceCheck {
+        root: workspace.to_path_buf(),
+        warning: Some(warning),
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::{Context, Result, anyhow};
+use path_absolutize::Absolutize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How paths that pass through symbolic links are turned into the paths and
+/// URIs the proxy works with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymlinkPolicy {
+    /// Resolve symlinks to the real location of files
+    #[default]
+    Resolve,
+    /// Keep paths as given, only making them absolute, so files reached
+    /// through a symlinked directory stay inside the workspace
+    Preserve,
+}
+
+impl SymlinkPolicy {
+    /// Parses a policy name as accepted on the command line
+    pub fn parse(policy: &str) -> Result<Self> {
+        match policy {
+            "resolve" => Ok(Self::Resolve),
+            "preserve" => Ok(Self::Preserve),
+            _ => Err(anyhow!(
+                "Unknown symlink policy '{}': expected resolve or preserve",
+                policy
+            )),
+        }
+    }
+}
+
+/// Whether symlinks are preserved rather than resolved
+static PRESERVE_SYMLINKS: AtomicBool = AtomicBool::new(false);
+
+/// Sets how every path the proxy handles treats symlinks
+pub fn set_symlink_policy(policy: SymlinkPolicy) {
+    PRESERVE_SYMLINKS.store(policy == SymlinkPolicy::Preserve, Ordering::Relaxed);
+}
+
+/// Gets the configured symlink policy
+pub fn symlink_policy() -> SymlinkPolicy {
+    if PRESERVE_SYMLINKS.load(Ordering::Relaxed) {
+        SymlinkPolicy::Preserve
+    } else {
+        SymlinkPolicy::Resolve
+    }
+}
+
+/// Makes a path absolute following the configured symlink policy
+pub fn normalize_path(path: &Path) -> Result<PathBuf> {
+    normalize_path_with(path, symlink_policy())
+}
+
+/// Makes a path absolute following the given symlink policy.
+///
+/// Paths that don't exist, such as removed files, are resolved through their
+/// parent directory so they match the paths they had while they existed.
+pub fn normalize_path_with(path: &Path, policy: SymlinkPolicy) -> Result<PathBuf> {
+    let absolute = path
+        .absolutize()
+        .context(format!("Failed to make path absolute: {}", path.display()))?
+        .to_path_buf();
+
+    match policy {
+        SymlinkPolicy::Preserve => Ok(absolute),
+        SymlinkPolicy::Resolve => match absolute.canonicalize() {
+            Ok(resolved) => Ok(resolved),
+            Err(e) => {
+                let (Some(parent), Some(name)) = (absolute.parent(), absolute.file_name()) else {
+                    return Err(e).context(format!("Failed to resolve path: {}", path.display()));
+                };
+                let parent = parent
+                    .canonicalize()
+                    .context(format!("Failed to resolve path: {}", path.display()))?;
+                Ok(parent.join(name))
+            }
+        },
+    }
+}
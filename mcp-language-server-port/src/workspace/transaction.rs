@@ -0,0 +1,142 @@
+use anyhow::{Result, anyhow};
+use log::{debug, warn};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// A set of file writes that are applied together or not at all.
+///
+/// Every new content is first staged in a temporary file next to its target,
+/// so a failure while writing leaves the workspace untouched. The staged
+/// files then replace their targets by renaming; if one of those renames
+/// fails, the files already replaced get their original content back.
+#[derive(Debug, Default)]
+pub struct FileTransaction {
+    files: Vec<PendingWrite>,
+}
+
+#[derive(Debug)]
+struct PendingWrite {
+    path: PathBuf,
+    original: String,
+    content: String,
+}
+
+impl FileTransaction {
+    /// Creates an empty transaction
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a write of `content` to `path`, whose current content is
+    /// `original` and is restored if the transaction fails
+    pub fn write(&mut self, path: PathBuf, original: String, content: String) {
+        self.files.push(PendingWrite {
+            path,
+            original,
+            content,
+        });
+    }
+
+    /// Applies every write, returning the paths written
+    pub async fn commit(self) -> Result<Vec<PathBuf>> {
+        // Write through symlinks instead of replacing them
+        let mut targets = Vec::with_capacity(self.files.len());
+        for file in &self.files {
+            targets.push(
+                fs::canonicalize(&file.path)
+                    .await
+                    .unwrap_or_else(|_| file.path.clone()),
+            );
+        }
+
+        let mut staged = Vec::with_capacity(targets.len());
+        for (file, target) in self.files.iter().zip(&targets) {
+            match stage(target, &file.content).await {
+                Ok(temp) => staged.push(temp),
+                Err(e) => {
+                    remove_all(&staged).await;
+                    return Err(anyhow!(
+                        "Failed to write {}: {}; no files were changed",
+                        file.path.display(),
+                        e
+                    ));
+                }
+            }
+        }
+
+        for (index, (temp, target)) in staged.iter().zip(&targets).enumerate() {
+            if let Err(e) = fs::rename(temp, target).await {
+                remove_all(&staged[index..]).await;
+                let failed = self.rollback(&targets[..index]).await;
+                let mut message = format!(
+                    "Failed to write {}: {}; {} already written files were restored",
+                    self.files[index].path.display(),
+                    e,
+                    index - failed.len()
+                );
+                if !failed.is_empty() {
+                    message.push_str(&format!(
+                        ", but these could not be and need fixing by hand: {}",
+                        failed
+                            .iter()
+                            .map(|path| path.display().to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                }
+                return Err(anyhow!(message));
+            }
+        }
+
+        debug!("[WORKSPACE] Wrote {} files", self.files.len());
+        Ok(self.files.into_iter().map(|file| file.path).collect())
+    }
+
+    /// Restores the original content of the first files, returning the ones
+    /// that could not be restored
+    async fn rollback(&self, written: &[PathBuf]) -> Vec<PathBuf> {
+        let mut failed = Vec::new();
+        for (file, target) in self.files.iter().zip(written) {
+            if let Err(e) = fs::write(target, &file.original).await {
+                warn!(
+                    "[WORKSPACE] Failed to restore {}: {}",
+                    file.path.display(),
+                    e
+                );
+                failed.push(file.path.clone());
+            }
+        }
+        failed
+    }
+}
+
+/// Gitignore-style pattern matching the temporary files writes are staged in,
+/// `.<name>.<pid>.tmp`, which the watcher ignores
+pub const STAGED_FILES: &str = ".*.[0-9]*.tmp";
+
+/// Writes `content` to a temporary file next to `target`, with the same
+/// permissions
+async fn stage(target: &Path, content: &str) -> Result<PathBuf> {
+    let name = target
+        .file_name()
+        .ok_or_else(|| anyhow!("not a file path"))?
+        .to_string_lossy();
+    let temp = target.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+
+    fs::write(&temp, content).await?;
+    if let Ok(metadata) = fs::metadata(target).await
+        && let Err(e) = fs::set_permissions(&temp, metadata.permissions()).await
+    {
+        let _ = fs::remove_file(&temp).await;
+        return Err(e.into());
+    }
+
+    Ok(temp)
+}
+
+/// Removes staged files that were not used
+async fn remove_all(paths: &[PathBuf]) {
+    for path in paths {
+        let _ = fs::remove_file(path).await;
+    }
+}
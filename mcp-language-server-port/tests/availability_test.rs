@@ -0,0 +1,44 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use std::sync::Arc;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::{Client, Router};
+use mcp_language_server_rust::mcp::availability::unsupported_tools;
+
+/// A language server that only offers hover and definitions
+fn server() -> String {
+    scripted_server(&[(
+        "initialize",
+        r#"reply '{"capabilities":{"hoverProvider":true,"definitionProvider":true,"renameProvider":false}}'"#,
+    )])
+}
+
+#[test(tokio::test)]
+async fn test_tools_of_unsupported_requests_are_left_out() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let workspace = temp_dir.path().canonicalize()?;
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    let router = Router::new(Arc::clone(&client));
+
+    // Nothing is known before the server has initialized
+    assert!(unsupported_tools(&router).is_empty());
+
+    client.initialize(&workspace).await?;
+    let unsupported = unsupported_tools(&router);
+    let tools: Vec<&str> = unsupported.iter().map(|(tool, _)| *tool).collect();
+
+    assert!(unsupported.contains(&("rename_symbol", "textDocument/rename")));
+    assert!(tools.contains(&"references"));
+    assert!(tools.contains(&"incoming_calls"));
+    assert!(!tools.contains(&"hover"));
+    assert!(!tools.contains(&"definition"));
+    // Tools that need no particular request stay
+    assert!(!tools.contains(&"edit_file"));
+
+    Ok(())
+}
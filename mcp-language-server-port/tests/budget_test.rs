@@ -0,0 +1,58 @@
+use mcp_language_server_rust::tools::budget::{Block, ResponseBudget, fit_to};
+
+fn block(name: &str) -> Block {
+    Block {
+        header: format!("{}\n", name),
+        context: vec![
+            "  context line one\n".to_string(),
+            "  context line two\n".to_string(),
+        ],
+        snippet: Some(format!(
+            "```rust\nfn {}() {{\n{}}}\n```\n",
+            name,
+            "    todo!();\n".repeat(10)
+        )),
+        summary: None,
+    }
+}
+
+#[test]
+fn test_output_within_budget_is_unchanged() {
+    let result = fit_to("Results:\n", vec![block("first")], "\n", 10_000);
+
+    assert!(result.starts_with("Results:\nfirst\n  context line one\n"));
+    assert!(result.contains("fn first()"));
+    assert!(!result.contains("[Output trimmed"));
+}
+
+#[test]
+fn test_snippets_are_trimmed_before_context() {
+    let blocks = vec![block("first"), block("second")];
+    // Room for everything except the code snippets, and the note
+    let result = fit_to("", blocks, "", 200);
+
+    assert!(result.chars().count() <= 200);
+    assert!(!result.contains("```"));
+    assert!(result.contains("second\n  context line one"));
+    assert!(result.contains("omitted 2 code snippets, 0 context lines, 0 items"));
+}
+
+#[test]
+fn test_items_are_dropped_last() {
+    let blocks = vec![block("first"), block("second"), block("third")];
+    let result = fit_to("", blocks, "", 110);
+
+    assert!(result.chars().count() <= 110);
+    assert!(result.starts_with("first\nsecond\n"));
+    assert!(!result.contains("third"));
+    assert!(result.contains("omitted 3 code snippets, 6 context lines, 1 items"));
+}
+
+#[test]
+fn test_budget_in_chars() {
+    let budget = ResponseBudget {
+        max_tokens: 100,
+        chars_per_token: 3.5,
+    };
+    assert_eq!(budget.max_chars(), 350);
+}
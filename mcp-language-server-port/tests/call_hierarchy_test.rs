@@ -0,0 +1,67 @@
+use lsp_types::{CallHierarchyItem, Position, Range, SymbolKind, Url};
+use std::path::PathBuf;
+
+use mcp_language_server_rust::tools::call_hierarchy::{Call, format_call};
+
+fn range(line: u32, start: u32, end: u32) -> Range {
+    Range {
+        start: Position {
+            line,
+            character: start,
+        },
+        end: Position {
+            line,
+            character: end,
+        },
+    }
+}
+
+fn item(name: &str, detail: Option<&str>, line: u32) -> CallHierarchyItem {
+    CallHierarchyItem {
+        name: name.to_string(),
+        kind: SymbolKind::FUNCTION,
+        tags: None,
+        detail: detail.map(str::to_string),
+        uri: Url::parse("file:///ws/src/main.rs").unwrap(),
+        range: range(line, 0, 20),
+        selection_range: range(line, 3, 3 + name.len() as u32),
+        data: None,
+    }
+}
+
+#[test]
+fn test_format_call_previews_each_call_line() {
+    let lines = vec![
+        "fn main() {",
+        "    let config = load_config();",
+        "    run(config);",
+        "    let again = load_config();",
+        "}",
+    ];
+    let call = Call {
+        item: item("main", Some("fn main()"), 0),
+        call_file: PathBuf::from("/ws/src/main.rs"),
+        // Calls on the same line are only listed once
+        call_ranges: vec![range(3, 16, 27), range(1, 17, 28), range(1, 30, 41)],
+    };
+
+    assert_eq!(
+        format_call(&call, &lines, "calling at"),
+        "  Function main (line 1): fn main(), calling at:\n    Line 2: let config = load_config();\n    Line 4: let again = load_config();\n"
+    );
+}
+
+#[test]
+fn test_format_call_without_detail() {
+    let lines = vec!["fn main() {", "    run(config);", "}"];
+    let call = Call {
+        item: item("run", None, 10),
+        call_file: PathBuf::from("/ws/src/main.rs"),
+        call_ranges: vec![range(1, 4, 7)],
+    };
+
+    assert_eq!(
+        format_call(&call, &lines, "called at"),
+        "  Function run (line 11), called at:\n    Line 2: run(config);\n"
+    );
+}
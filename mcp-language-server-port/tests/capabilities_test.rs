@@ -0,0 +1,56 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+
+/// A language server that reports a few capabilities and its version
+fn server() -> String {
+    scripted_server(&[(
+        "initialize",
+        r#"reply '{"capabilities":{"textDocumentSync":2,"hoverProvider":true,"definitionProvider":false,"renameProvider":{"prepareProvider":true},"codeActionProvider":{"codeActionKinds":["quickfix","refactor.extract"]},"positionEncoding":"utf-8"},"serverInfo":{"name":"fakels","version":"1.2.3"}}'"#,
+    )])
+}
+
+#[test(tokio::test)]
+async fn test_server_capabilities_summary() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let workspace = temp_dir.path().canonicalize()?;
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(&workspace).await?;
+
+    let result = tools::get_server_capabilities(&[client]).await?;
+
+    assert!(result.starts_with("bash (fakels 1.2.3)\n"), "{}", result);
+    assert!(result.contains("  Position encoding: utf-8\n"));
+    assert!(result.contains("  Text sync: incremental changes\n"));
+    assert!(result.contains("    textDocument/hover\n"));
+    assert!(result.contains("    textDocument/rename (prepareRename)\n"));
+    assert!(result.contains("    textDocument/codeAction (kinds: quickfix refactor.extract)\n"));
+    // Providers switched off or left out are listed as unsupported
+    let unsupported = result
+        .lines()
+        .find(|line| line.starts_with("  Not supported: "))
+        .unwrap_or_default();
+    assert!(unsupported.contains("textDocument/definition"));
+    assert!(unsupported.contains("textDocument/references"));
+    assert!(result.contains("  Workspace folders: not supported\n"));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_server_capabilities_before_initialization() -> Result<()> {
+    let client = Client::new("cat", &[]).await?;
+
+    let result = tools::get_server_capabilities(&[client]).await?;
+
+    assert_eq!(result, "cat\n  Not initialized yet\n");
+
+    Ok(())
+}
@@ -0,0 +1,243 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use lsp_types::DidChangeTextDocumentParams;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use test_log::test;
+use tokio::time::{Duration, Instant, sleep, timeout};
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::position::offset_at;
+use mcp_language_server_rust::lsp::{Client, PositionEncoding};
+
+/// A language server that stalls before reading anything, then writes the version
+/// of every didChange it receives to the file given as its argument
+fn server() -> String {
+    let script = scripted_server(&[(
+        "textDocument/didChange",
+        r#"[[ $body =~ \"version\":([0-9]+) ]] && echo "${BASH_REMATCH[1]}" >> "$1""#,
+    )]);
+    format!("sleep 1\n{}", script)
+}
+
+/// A language server syncing documents incrementally that stalls on the first change,
+/// then writes every didChange it receives to the file given as its argument
+fn incremental_server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"textDocumentSync":2}}'"#,
+        ),
+        (
+            "textDocument/didChange",
+            r#"
+[[ -n $stalled ]] || { sleep 1; stalled=1; }
+printf '%s\n' "$body" >> "$1"
+"#,
+        ),
+    ])
+}
+
+/// A language server that answers every didChange with a large publishDiagnostics
+/// notification, and `fake/ping` with true
+fn chatty_server() -> String {
+    let script = scripted_server(&[
+        (
+            "textDocument/didChange",
+            r#"send "{\"jsonrpc\":\"2.0\",\"method\":\"textDocument/publishDiagnostics\",\"params\":{\"uri\":\"file:///ws/main.rs\",\"diagnostics\":[{\"range\":{\"start\":{\"line\":0,\"character\":0},\"end\":{\"line\":0,\"character\":1}},\"message\":\"$padding\"}]}}""#,
+        ),
+        ("fake/ping", "reply true"),
+    ]);
+    format!("padding=$(printf '%4000s' '')\n{}", script)
+}
+
+#[test(tokio::test)]
+async fn test_changes_do_not_block_when_server_stalls() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    let file_path = temp_dir.path().canonicalize()?.join("main.rs");
+
+    // `sleep` never reads its stdin, so the pipe and then the message channel fill up
+    let client = Client::new("sleep", &["30".to_string()]).await?;
+    client.open_file(&file_path).await?;
+
+    let content = "x".repeat(4096);
+    timeout(Duration::from_secs(5), async {
+        for _ in 0..500 {
+            client.set_overlay(&file_path, content.clone()).await?;
+        }
+        anyhow::Ok(())
+    })
+    .await??;
+
+    assert!(client.coalesced_changes() > 0);
+    assert_eq!(client.open_documents()[0].version, 501);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_parked_changes_reach_the_server_in_order() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    let file_path = temp_dir.path().canonicalize()?.join("main.rs");
+    let log = temp_dir.path().join("versions.log");
+
+    let client = Client::new(
+        "bash",
+        &[
+            "-c".to_string(),
+            server(),
+            "server".to_string(),
+            log.display().to_string(),
+        ],
+    )
+    .await?;
+    client.open_file(&file_path).await?;
+
+    // Fill the pipe and the message channel while the server stalls
+    let content = "x".repeat(4096);
+    for _ in 0..500 {
+        client.set_overlay(&file_path, content.clone()).await?;
+    }
+    assert!(client.coalesced_changes() > 0);
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let versions: Vec<i32> = loop {
+        let versions: Vec<i32> = std::fs::read_to_string(&log)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.parse().ok())
+            .collect();
+        if versions.last() == Some(&501) || Instant::now() > deadline {
+            break versions;
+        }
+        sleep(Duration::from_millis(50)).await;
+    };
+
+    assert_eq!(versions.last(), Some(&501));
+    assert!(
+        versions.windows(2).all(|pair| pair[0] < pair[1]),
+        "didChange versions out of order: {:?}",
+        versions
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_server_messages_are_read_while_changes_are_written() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    let file_path = temp_dir.path().canonicalize()?.join("main.rs");
+
+    let client = Client::new("bash", &["-c".to_string(), chatty_server()]).await?;
+    client.open_file(&file_path).await?;
+
+    // The server stops reading once its diagnostics pile up unread, so the changes
+    // and the request after them only get through if both directions keep moving
+    let content = "x".repeat(4096);
+    let answer: Value = timeout(Duration::from_secs(10), async {
+        for _ in 0..500 {
+            client.set_overlay(&file_path, content.clone()).await?;
+        }
+        client.call("fake/ping", json!({})).await
+    })
+    .await??;
+    assert_eq!(answer, json!(true));
+
+    Ok(())
+}
+
+#[test(tokio::test(flavor = "multi_thread", worker_threads = 2))]
+async fn test_interleaved_changes_keep_the_server_document_in_sync() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let file_path = workspace.join("main.rs");
+    let log = temp_dir.path().join("changes.log");
+
+    let client = Client::new(
+        "bash",
+        &[
+            "-c".to_string(),
+            incremental_server(),
+            "server".to_string(),
+            log.display().to_string(),
+        ],
+    )
+    .await?;
+    client.initialize(&workspace).await?;
+    client.open_file(&file_path).await?;
+
+    // Two writers race on the same file, like an edit and the watcher's resync
+    let writers: Vec<_> = ["a", "b"]
+        .into_iter()
+        .map(|fill| {
+            let client = Arc::clone(&client);
+            let file_path = file_path.clone();
+            tokio::spawn(async move {
+                for i in 0..200 {
+                    let content = format!("// {} {}\n{}\n", fill, i, fill.repeat(4096));
+                    client.set_overlay(&file_path, content).await?;
+                }
+                anyhow::Ok(())
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.await??;
+    }
+    client
+        .set_overlay(&file_path, "fn done() {}\n".to_string())
+        .await?;
+    let version = client.open_documents()[0].version;
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let changes: Vec<DidChangeTextDocumentParams> = loop {
+        let changes = std::fs::read_to_string(&log)
+            .unwrap_or_default()
+            .lines()
+            .map(|line| {
+                let message: Value = serde_json::from_str(line)?;
+                Ok(serde_json::from_value(message["params"].clone())?)
+            })
+            .collect::<Result<Vec<DidChangeTextDocumentParams>>>()?;
+        if changes.last().map(|change| change.text_document.version) == Some(version)
+            || Instant::now() > deadline
+        {
+            break changes;
+        }
+        sleep(Duration::from_millis(50)).await;
+    };
+
+    // Replaying the changes the server received gives the client's text
+    let mut text = "fn main() {}\n".to_string();
+    let mut last_version = 0;
+    for change in changes {
+        assert!(
+            change.text_document.version > last_version,
+            "didChange {} after {}",
+            change.text_document.version,
+            last_version
+        );
+        last_version = change.text_document.version;
+        for event in change.content_changes {
+            match event.range {
+                Some(range) => {
+                    let start = offset_at(&text, range.start, PositionEncoding::Utf16).unwrap();
+                    let end = offset_at(&text, range.end, PositionEncoding::Utf16).unwrap();
+                    text.replace_range(start..end, &event.text);
+                }
+                None => text = event.text,
+            }
+        }
+    }
+    assert_eq!(last_version, version);
+    assert_eq!(text, "fn done() {}\n");
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use std::sync::Arc;
+use std::time::Duration;
+use test_log::test;
+
+use mcp_language_server_rust::lsp::{Client, Router};
+use mcp_language_server_rust::mcp::clients::DocumentLeases;
+
+#[test(tokio::test)]
+async fn test_files_stay_open_while_any_client_uses_them() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("lib.rs").write_str("pub fn one() {}\n")?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let shared = workspace.join("lib.rs");
+    let own = workspace.join("main.rs");
+
+    let client = Client::new("cat", &[]).await?;
+    let router = Router::new(Arc::clone(&client));
+    let leases = DocumentLeases::new();
+
+    let first = leases.connect(router.clone());
+    let second = leases.connect(router.clone());
+    assert_ne!(first.id(), second.id());
+
+    first.use_file(&shared);
+    first.use_file(&own);
+    second.use_file(&shared);
+    client.open_file(&shared).await?;
+    client.open_file(&own).await?;
+    assert_eq!(leases.holders(&shared), 2);
+
+    // The file only the first client used is closed when it disconnects
+    drop(first);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(client.is_file_open(&shared));
+    assert!(!client.is_file_open(&own));
+    assert_eq!(leases.holders(&shared), 1);
+
+    drop(second);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!client.is_file_open(&shared));
+    assert_eq!(leases.holders(&shared), 0);
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use lsp_types::{
+    CodeAction, CodeActionDisabled, CodeActionKind, CodeActionOrCommand, Command, Diagnostic,
+    Position, Range,
+};
+
+use mcp_language_server_rust::tools::code_actions::{
+    action_range, diagnostics_in_range, format_code_action, merge_code_actions,
+};
+
+fn range(start_line: u32, start_char: u32, end_line: u32, end_char: u32) -> Range {
+    Range {
+        start: Position {
+            line: start_line,
+            character: start_char,
+        },
+        end: Position {
+            line: end_line,
+            character: end_char,
+        },
+    }
+}
+
+fn diagnostic(range: Range, message: &str) -> Diagnostic {
+    Diagnostic {
+        range,
+        message: message.to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_format_code_action() {
+    let action = CodeActionOrCommand::CodeAction(CodeAction {
+        title: "Import `HashMap`".to_string(),
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![diagnostic(
+            range(4, 8, 4, 15),
+            "cannot find type `HashMap`",
+        )]),
+        is_preferred: Some(true),
+        ..Default::default()
+    });
+    assert_eq!(
+        format_code_action(1, &action),
+        "1. [quickfix] Import `HashMap` (preferred)\n   fixes line 5: cannot find type `HashMap`"
+    );
+
+    let disabled = CodeActionOrCommand::CodeAction(CodeAction {
+        title: "Extract function".to_string(),
+        kind: Some(CodeActionKind::REFACTOR_EXTRACT),
+        disabled: Some(CodeActionDisabled {
+            reason: "Selection is not an expression".to_string(),
+        }),
+        ..Default::default()
+    });
+    assert_eq!(
+        format_code_action(2, &disabled),
+        "2. [refactor.extract] Extract function (disabled: Selection is not an expression)"
+    );
+
+    let command = CodeActionOrCommand::Command(Command {
+        title: "Run go mod tidy".to_string(),
+        command: "gopls.tidy".to_string(),
+        arguments: None,
+    });
+    assert_eq!(
+        format_code_action(3, &command),
+        "3. Run go mod tidy (command: gopls.tidy)"
+    );
+}
+
+#[test]
+fn test_diagnostics_in_range() {
+    let diagnostics = vec![
+        diagnostic(range(2, 4, 2, 10), "unused variable"),
+        diagnostic(range(5, 0, 7, 1), "missing return"),
+        diagnostic(range(9, 0, 9, 3), "unknown name"),
+    ];
+
+    // A cursor position inside a diagnostic
+    let found = diagnostics_in_range(&diagnostics, &range(2, 6, 2, 6));
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].message, "unused variable");
+
+    // A range spanning several diagnostics
+    let found = diagnostics_in_range(&diagnostics, &range(3, 0, 9, 0));
+    let messages: Vec<_> = found.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(messages, vec!["missing return", "unknown name"]);
+
+    assert!(diagnostics_in_range(&diagnostics, &range(0, 0, 1, 0)).is_empty());
+}
+
+#[test]
+fn test_action_range() {
+    // A single position converted from 1-indexed input
+    assert_eq!(action_range(3, 5, None, None), range(2, 4, 2, 4));
+
+    // An end column alone stays on the start line
+    assert_eq!(action_range(3, 5, None, Some(9)), range(2, 4, 2, 8));
+
+    // An end line without a column ends at its start
+    assert_eq!(action_range(3, 5, Some(6), None), range(2, 4, 5, 0));
+    assert_eq!(action_range(3, 5, Some(6), Some(2)), range(2, 4, 5, 1));
+}
+
+#[test]
+fn test_merge_code_actions_keeps_each_action_once() {
+    let action = |title: &str, kind: CodeActionKind| {
+        CodeActionOrCommand::CodeAction(CodeAction {
+            title: title.to_string(),
+            kind: Some(kind),
+            ..Default::default()
+        })
+    };
+
+    let merged = merge_code_actions(vec![
+        (
+            "pyright".to_string(),
+            vec![
+                action("Organize imports", CodeActionKind::SOURCE_ORGANIZE_IMPORTS),
+                action("Add import", CodeActionKind::QUICKFIX),
+            ],
+        ),
+        (
+            "ruff-lsp".to_string(),
+            vec![
+                action("Organize imports", CodeActionKind::SOURCE_ORGANIZE_IMPORTS),
+                action("Disable for this line", CodeActionKind::QUICKFIX),
+            ],
+        ),
+    ]);
+
+    let titles: Vec<(&str, &str)> = merged
+        .iter()
+        .map(|(server, action)| match action {
+            CodeActionOrCommand::CodeAction(action) => (server.as_str(), action.title.as_str()),
+            CodeActionOrCommand::Command(command) => (server.as_str(), command.title.as_str()),
+        })
+        .collect();
+    assert_eq!(
+        titles,
+        vec![
+            ("pyright", "Organize imports"),
+            ("pyright", "Add import"),
+            ("ruff-lsp", "Disable for this line"),
+        ]
+    );
+}
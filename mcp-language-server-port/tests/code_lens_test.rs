@@ -0,0 +1,95 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+
+/// A language server with two code lenses: one running its `fake.test`
+/// command, which rewrites the first line of the file given as the server's
+/// argument, and one running a command only an editor implements
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"codeLensProvider":{},"executeCommandProvider":{"commands":["fake.test"]}}}'"#,
+        ),
+        (
+            "textDocument/codeLens",
+            r#"reply '[{"range":{"start":{"line":2,"character":0},"end":{"line":2,"character":4}},"command":{"title":"debug test","command":"editor.debug","arguments":["TestMain"]}},{"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":4}},"command":{"title":"run test","command":"fake.test"}}]'"#,
+        ),
+        (
+            "workspace/executeCommand",
+            r#"
+command=$id
+send "{\"jsonrpc\":\"2.0\",\"id\":900,\"method\":\"workspace/applyEdit\",\"params\":{\"edit\":{\"changes\":{\"$1\":[{\"range\":{\"start\":{\"line\":0,\"character\":0},\"end\":{\"line\":0,\"character\":4}},\"newText\":\"ran\"}]}}}}"
+"#,
+        ),
+        (
+            "",
+            r#"
+# The command finishes once the client has applied its edit
+if [[ $body =~ \"applied\":true ]]; then
+    send "{\"jsonrpc\":\"2.0\",\"id\":$command,\"result\":null}"
+else
+    send "{\"jsonrpc\":\"2.0\",\"id\":$command,\"error\":{\"code\":-32603,\"message\":\"edit not applied\"}}"
+fi
+"#,
+        ),
+    ])
+}
+
+#[test(tokio::test)]
+async fn test_code_lenses_listed_and_run() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir
+        .child("main_test.go")
+        .write_str("test\n\nfunc TestMain() {}\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let file_path = workspace.join("main_test.go");
+    let uri = tools::utils::to_uri(&file_path).to_string();
+
+    let client = Client::new(
+        "bash",
+        &["-c".to_string(), server(), "server".to_string(), uri],
+    )
+    .await?;
+    client.initialize(&workspace).await?;
+
+    // Lenses are listed in file order
+    let listing = tools::get_code_lenses(&client, file_path.clone()).await?;
+    assert!(
+        listing.contains("1. line 1: run test (command: fake.test)"),
+        "{}",
+        listing
+    );
+    assert!(
+        listing.contains("2. line 3: debug test (command: editor.debug)"),
+        "{}",
+        listing
+    );
+
+    let result = tools::run_code_lens(&client, file_path.clone(), 1).await?;
+    assert!(result.starts_with("Ran code lens 'run test'"), "{}", result);
+    assert_eq!(
+        std::fs::read_to_string(&file_path)?,
+        "ran\n\nfunc TestMain() {}\n"
+    );
+
+    // Editor commands are described rather than sent to the server
+    let error = tools::run_code_lens(&client, file_path.clone(), 2)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("[\"TestMain\"]"), "{}", error);
+
+    let error = tools::run_code_lens(&client, file_path, 3)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("No code lens 3"), "{}", error);
+
+    Ok(())
+}
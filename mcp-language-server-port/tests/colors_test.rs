@@ -0,0 +1,34 @@
+use anyhow::Result;
+use lsp_types::Color;
+
+use mcp_language_server_rust::tools::colors::{format_color, parse_hex_color};
+
+#[test]
+fn test_format_color() {
+    let opaque = Color {
+        red: 1.0,
+        green: 0.5,
+        blue: 0.0,
+        alpha: 1.0,
+    };
+    assert_eq!(format_color(&opaque), "#ff8000");
+
+    let translucent = Color {
+        alpha: 0.5,
+        ..opaque
+    };
+    assert_eq!(format_color(&translucent), "#ff800080");
+}
+
+#[test]
+fn test_parse_hex_color() -> Result<()> {
+    assert_eq!(format_color(&parse_hex_color("#336699")?), "#336699");
+    assert_eq!(format_color(&parse_hex_color("#369")?), "#336699");
+    assert_eq!(format_color(&parse_hex_color("33669980")?), "#33669980");
+    assert_eq!(parse_hex_color("#fff")?.alpha, 1.0);
+
+    assert!(parse_hex_color("#12345").is_err());
+    assert!(parse_hex_color("#ggg").is_err());
+
+    Ok(())
+}
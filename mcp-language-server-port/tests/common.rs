@@ -1,3 +1,6 @@
+// Each test crate uses only some of these helpers
+#![allow(dead_code)]
+
 use anyhow::Result;
 use assert_fs::TempDir;
 use assert_fs::fixture::PathChild;
@@ -121,4 +124,61 @@ fn main() {
     }
 }
 "#
-}
\ No newline at end of file
+}
+
+/// Reads the framed messages sent to a scripted server and dispatches them by method
+const SCRIPT_PRELUDE: &str = r#"
+send() {
+    printf 'Content-Length: %d\r\n\r\n%s' "${#1}" "$1"
+}
+reply() {
+    send "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":$1}"
+}
+while IFS= read -r header; do
+    length=${header#Content-Length: }
+    length=${length%$'\r'}
+    IFS= read -r _
+    IFS= read -r -N "$length" body
+    id= method=
+    [[ $body =~ \"id\":([0-9]+) ]] && id=${BASH_REMATCH[1]}
+    [[ $body =~ \"method\":\"([^\"]+)\" ]] && method=${BASH_REMATCH[1]}
+    case $method in
+"#;
+
+/// Builds a fake language server for `bash -c` from a table of methods and the bash
+/// commands run when a message with that method arrives.
+///
+/// The commands see the message as `$body`, its id as `$id` and the script's arguments;
+/// `reply RESULT` answers the request and `send MESSAGE` writes any other message. The
+/// method `""` matches the client's responses to the server's own requests and `"*"`
+/// every message left over. Unless listed, initialize is answered with no capabilities
+/// and shutdown with null.
+pub fn scripted_server(handlers: &[(&str, &str)]) -> String {
+    let mut script = SCRIPT_PRELUDE.to_string();
+    let mut branch = |method: &str, commands: &str| {
+        let pattern = match method {
+            "" => "''".to_string(),
+            "*" => "*".to_string(),
+            method => format!("'{}'", method),
+        };
+        script.push_str(&format!("    {})\n{}\n    ;;\n", pattern, commands.trim()));
+    };
+
+    for &(method, commands) in handlers.iter().filter(|(method, _)| *method != "*") {
+        branch(method, commands);
+    }
+    for (method, commands) in [
+        ("initialize", r#"reply '{"capabilities":{}}'"#),
+        ("shutdown", "reply null"),
+    ] {
+        if !handlers.iter().any(|(listed, _)| *listed == method) {
+            branch(method, commands);
+        }
+    }
+    if let Some(&(_, commands)) = handlers.iter().find(|(method, _)| *method == "*") {
+        branch("*", commands);
+    }
+
+    script.push_str("    esac\ndone\n");
+    script
+}
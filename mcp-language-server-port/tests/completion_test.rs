@@ -0,0 +1,60 @@
+use lsp_types::{CompletionItem, CompletionItemKind, Documentation, MarkupContent, MarkupKind};
+
+use mcp_language_server_rust::tools::completion::{format_completion_item, select_items};
+
+fn item(label: &str, sort_text: Option<&str>) -> CompletionItem {
+    CompletionItem {
+        label: label.to_string(),
+        sort_text: sort_text.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_select_items_filters_sorts_and_limits() {
+    let items = vec![
+        item("push_str", Some("2")),
+        item("len", Some("0")),
+        item("push", Some("1")),
+        item("pop", Some("3")),
+    ];
+
+    let labels = |items: Vec<CompletionItem>| -> Vec<String> {
+        items.into_iter().map(|item| item.label).collect()
+    };
+
+    assert_eq!(
+        labels(select_items(items.clone(), Some("PU"), 10)),
+        vec!["push", "push_str"]
+    );
+    assert_eq!(labels(select_items(items, None, 2)), vec!["len", "push"]);
+}
+
+#[test]
+fn test_format_completion_item() {
+    let item = CompletionItem {
+        label: "push".to_string(),
+        kind: Some(CompletionItemKind::METHOD),
+        detail: Some("fn(&mut self, value: T)".to_string()),
+        documentation: Some(Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value:
+                "Appends an element to the back\nof a collection.\n\n# Panics\n\nPanics if full."
+                    .to_string(),
+        })),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        format_completion_item(&item),
+        "push (Method) — fn(&mut self, value: T): Appends an element to the back of a collection."
+    );
+    assert_eq!(format_completion_item(&item_without_details()), "len");
+}
+
+fn item_without_details() -> CompletionItem {
+    CompletionItem {
+        label: "len".to_string(),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use serde_json::json;
+use std::path::Path;
+use test_log::test;
+
+use mcp_language_server_rust::config::ConfigFile;
+
+#[test]
+fn test_config_file_lists_servers_and_defaults() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file = temp_dir.child("mcp-language-server.json");
+    file.write_str(
+        &json!({
+            "workspace": "project",
+            "servers": [
+                { "command": "rust-analyzer" },
+                {
+                    "command": "gopls",
+                    "args": ["serve"],
+                    "env": { "GOFLAGS": "-mod=mod" },
+                    "cwd": "project/go",
+                    "languages": ["go", "mod"],
+                    "initialization_options": { "staticcheck": true }
+                }
+            ],
+            "ignore": ["target/"],
+            "log_level": "info",
+            "language_ids": { "svelte": "svelte", "h": "cpp" },
+            "timeouts": { "lsp": 60, "methods": { "workspace/symbol": 120 } },
+            "tools": { "output_style": "compact", "absolute_paths": true },
+            "custom_tools": [{
+                "name": "expand_macro",
+                "description": "Expand the macro at a position",
+                "method": "rust-analyzer/expandMacro",
+                "requires": "expandMacro"
+            }]
+        })
+        .to_string(),
+    )?;
+
+    let config = ConfigFile::load(file.path())?;
+
+    // Relative paths are taken from the config file's directory
+    assert_eq!(config.workspace, Some(temp_dir.path().join("project")));
+    assert_eq!(config.servers.len(), 2);
+    assert_eq!(config.servers[0].command, "rust-analyzer");
+    assert!(config.servers[0].language_filter().is_none());
+
+    let gopls = &config.servers[1];
+    assert_eq!(gopls.args, vec!["serve".to_string()]);
+    assert_eq!(
+        gopls.env_pairs(),
+        vec![("GOFLAGS".to_string(), "-mod=mod".to_string())]
+    );
+    assert_eq!(gopls.cwd, Some(temp_dir.path().join("project/go")));
+    assert!(
+        gopls
+            .language_filter()
+            .is_some_and(|filter| filter.matches(Path::new("go.mod"), "plaintext"))
+    );
+    assert_eq!(
+        gopls.initialization_options,
+        Some(json!({ "staticcheck": true }))
+    );
+
+    assert_eq!(config.ignore, vec!["target/".to_string()]);
+    assert_eq!(config.log_level, Some(log::LevelFilter::Info));
+    assert_eq!(
+        config.language_ids.get("svelte").map(String::as_str),
+        Some("svelte")
+    );
+    assert_eq!(
+        config.language_ids.get("h").map(String::as_str),
+        Some("cpp")
+    );
+    assert_eq!(config.timeouts.lsp, Some(60));
+    assert_eq!(config.timeouts.ready, None);
+    assert_eq!(config.timeouts.methods.get("workspace/symbol"), Some(&120));
+    assert_eq!(config.tools.output_style.as_deref(), Some("compact"));
+    assert_eq!(config.tools.absolute_paths, Some(true));
+    assert_eq!(config.custom_tools.len(), 1);
+    assert_eq!(config.custom_tools[0].method, "rust-analyzer/expandMacro");
+    assert_eq!(
+        config.custom_tools[0].requires.as_deref(),
+        Some("expandMacro")
+    );
+    assert_eq!(config.custom_tools[0].params, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_config_file_rejects_unknown_options_and_missing_commands() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    let typo = temp_dir.child("typo.json");
+    typo.write_str(r#"{ "workspase": "." }"#)?;
+    let error = ConfigFile::load(typo.path()).unwrap_err();
+    assert!(format!("{:#}", error).contains("workspase"));
+
+    let no_command = temp_dir.child("no_command.json");
+    no_command.write_str(r#"{ "servers": [{ "args": ["serve"] }] }"#)?;
+    let error = ConfigFile::load(no_command.path()).unwrap_err();
+    assert!(error.to_string().contains("has no command"));
+
+    let no_method = temp_dir.child("no_method.json");
+    no_method.write_str(r#"{ "custom_tools": [{ "name": "expand_macro" }] }"#)?;
+    let error = ConfigFile::load(no_method.path()).unwrap_err();
+    assert!(error.to_string().contains("no name or method"));
+
+    assert!(ConfigFile::load(&temp_dir.path().join("missing.json")).is_err());
+
+    Ok(())
+}
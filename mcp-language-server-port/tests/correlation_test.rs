@@ -0,0 +1,55 @@
+use rmcp::model::{CallToolResult, Content};
+
+use mcp_language_server_rust::logging::correlation::{self, CorrelationId};
+use mcp_language_server_rust::mcp::tag_errors;
+
+#[test]
+fn test_ids_name_the_tool_and_are_unique() {
+    let first = CorrelationId::next("hover");
+    let second = CorrelationId::next("hover");
+
+    assert!(first.to_string().starts_with("hover-"));
+    assert_ne!(first, second);
+}
+
+#[tokio::test]
+async fn test_current_id_is_scoped_to_the_tool_call() {
+    assert_eq!(correlation::current(), None);
+
+    let id = CorrelationId::next("references");
+    let seen = correlation::with_correlation_id(id.clone(), async {
+        tokio::task::yield_now().await;
+        correlation::current()
+    })
+    .await;
+
+    assert_eq!(seen, Some(id));
+    assert_eq!(correlation::current(), None);
+}
+
+#[test]
+fn test_errors_are_tagged_with_the_id() {
+    let id = CorrelationId::next("hover");
+    let mut result = CallToolResult::success(vec![
+        Content::text("Error getting hover info: LSP error: no hover (code: -32603)"),
+        Content::text("fn main()"),
+    ]);
+
+    tag_errors(&mut result, &id);
+
+    let texts: Vec<String> = result
+        .content
+        .iter()
+        .filter_map(|content| content.as_text().map(|text| text.text.clone()))
+        .collect();
+    assert_eq!(
+        texts,
+        vec![
+            format!(
+                "Error getting hover info: LSP error: no hover (code: -32603) (correlation id: {})",
+                id
+            ),
+            "fn main()".to_string(),
+        ]
+    );
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use serde_json::json;
+use std::sync::Arc;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::config::CustomToolConfig;
+use mcp_language_server_rust::lsp::{Client, Router};
+use mcp_language_server_rust::mcp::custom_tools;
+
+/// A language server announcing the `expandMacro` experimental capability,
+/// which answers `rust-analyzer/expandMacro` with the params it was sent
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"experimental":{"expandMacro":true,"commands":{"run":false}}}}'"#,
+        ),
+        (
+            "rust-analyzer/expandMacro",
+            r#"
+[[ $body =~ \"params\":(\{[^}]*\}) ]]
+reply "{\"expansion\":${BASH_REMATCH[1]}}"
+"#,
+        ),
+    ])
+}
+
+fn custom_tool(name: &str, requires: Option<&str>) -> CustomToolConfig {
+    CustomToolConfig {
+        name: name.to_string(),
+        description: format!("Runs {}", name),
+        method: "rust-analyzer/expandMacro".to_string(),
+        requires: requires.map(str::to_string),
+        params: Some(json!({ "position": 1 })),
+    }
+}
+
+#[test(tokio::test)]
+async fn test_custom_tools_are_gated_on_experimental_capabilities() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let workspace = temp_dir.path().canonicalize()?;
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(&workspace).await?;
+    let router = Router::new(Arc::clone(&client));
+
+    assert!(custom_tools::has_experimental(&client, "expandMacro"));
+    assert!(!custom_tools::has_experimental(&client, "commands.run"));
+    assert!(!custom_tools::has_experimental(&client, "commands.missing"));
+
+    let tools = vec![
+        custom_tool("expand_macro", Some("expandMacro")),
+        custom_tool("run_command", Some("commands.run")),
+        custom_tool("plain_request", None),
+    ];
+    let available: Vec<&str> = custom_tools::available(&tools, &router)
+        .into_iter()
+        .map(|tool| tool.name.as_str())
+        .collect();
+    assert_eq!(available, vec!["expand_macro", "plain_request"]);
+
+    // Without params in the call, the configured ones are sent
+    let result = custom_tools::call(&tools[0], &client, None).await?;
+    let result: serde_json::Value = serde_json::from_str(&result)?;
+    assert_eq!(result, json!({ "expansion": { "position": 1 } }));
+
+    let arguments = json!({ "params": { "position": 7 } });
+    let result = custom_tools::call(&tools[0], &client, arguments.as_object()).await?;
+    let result: serde_json::Value = serde_json::from_str(&result)?;
+    assert_eq!(result, json!({ "expansion": { "position": 7 } }));
+
+    // A tool whose capability is missing is refused
+    assert!(custom_tools::call(&tools[1], &client, None).await.is_err());
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use lsp_types::{Location, Position, Range, Url};
+use serde_json::json;
+
+use mcp_language_server_rust::tools::definition::parse_definition_result;
+
+fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+    Range {
+        start: Position {
+            line: start.0,
+            character: start.1,
+        },
+        end: Position {
+            line: end.0,
+            character: end.1,
+        },
+    }
+}
+
+fn uri() -> Url {
+    Url::parse("file:///ws/src/lib.rs").unwrap()
+}
+
+#[test]
+fn test_parse_single_location() {
+    let locations = parse_definition_result(json!({
+        "uri": "file:///ws/src/lib.rs",
+        "range": { "start": { "line": 4, "character": 7 }, "end": { "line": 4, "character": 13 } },
+    }))
+    .unwrap();
+
+    assert_eq!(
+        locations,
+        vec![Location {
+            uri: uri(),
+            range: range((4, 7), (4, 13)),
+        }]
+    );
+}
+
+#[test]
+fn test_parse_location_links_use_the_whole_definition() {
+    let locations = parse_definition_result(json!([
+        {
+            "originSelectionRange": { "start": { "line": 20, "character": 4 }, "end": { "line": 20, "character": 10 } },
+            "targetUri": "file:///ws/src/lib.rs",
+            "targetRange": { "start": { "line": 3, "character": 0 }, "end": { "line": 8, "character": 1 } },
+            "targetSelectionRange": { "start": { "line": 4, "character": 7 }, "end": { "line": 4, "character": 13 } },
+        },
+        {
+            // A target range that doesn't contain the name is not trusted
+            "targetUri": "file:///ws/src/lib.rs",
+            "targetRange": { "start": { "line": 0, "character": 0 }, "end": { "line": 1, "character": 0 } },
+            "targetSelectionRange": { "start": { "line": 30, "character": 3 }, "end": { "line": 30, "character": 9 } },
+        }
+    ]))
+    .unwrap();
+
+    assert_eq!(
+        locations,
+        vec![
+            Location {
+                uri: uri(),
+                range: range((3, 0), (8, 1)),
+            },
+            Location {
+                uri: uri(),
+                range: range((30, 3), (30, 9)),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_parse_mixed_and_empty_results() {
+    let locations = parse_definition_result(json!([
+        {
+            "uri": "file:///ws/src/lib.rs",
+            "range": { "start": { "line": 1, "character": 0 }, "end": { "line": 1, "character": 5 } },
+        },
+        { "unexpected": true },
+    ]))
+    .unwrap();
+    assert_eq!(locations.len(), 1);
+
+    assert!(parse_definition_result(json!(null)).unwrap().is_empty());
+    assert!(parse_definition_result(json!(42)).is_err());
+}
@@ -53,7 +53,7 @@ async fn test_find_definition() -> Result<()> {
     let response_line = 5; // Line of the Person struct definition
     
     // Use the task function to find the definition
-    let result = tools::find_definition(&client, &symbol_location).await?;
+    let result = tools::find_definition(&client, &symbol_location, 0).await?;
     
     // Verify the result
     assert!(result.contains("struct Person"), "Definition should contain 'struct Person'");
@@ -74,7 +74,7 @@ async fn test_find_definition_invalid_location() -> Result<()> {
     let symbol_location = format!("{}:999:999", file_path); // Non-existent location
     
     // Try to find the definition
-    let result = tools::find_definition(&client, &symbol_location).await;
+    let result = tools::find_definition(&client, &symbol_location, 0).await;
     
     // Verify the result is an error
     assert!(result.is_err(), "Definition lookup at invalid location should fail");
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use lsp_types::{Diagnostic, Position, PublishDiagnosticsParams, Range, Url};
+use test_log::test;
+
+use mcp_language_server_rust::lsp::{Client, ProxyEvent};
+use mcp_language_server_rust::tools::utils::to_uri;
+
+fn diagnostic(message: &str) -> Diagnostic {
+    Diagnostic {
+        range: Range {
+            start: Position {
+                line: 0,
+                character: 0,
+            },
+            end: Position {
+                line: 0,
+                character: 2,
+            },
+        },
+        message: message.to_string(),
+        ..Default::default()
+    }
+}
+
+fn publish(uri: &Url, version: Option<i32>, messages: &[&str]) -> PublishDiagnosticsParams {
+    PublishDiagnosticsParams {
+        uri: uri.clone(),
+        diagnostics: messages.iter().map(|m| diagnostic(m)).collect(),
+        version,
+    }
+}
+
+fn messages(client: &Client, uri: &Url) -> Vec<String> {
+    client
+        .get_diagnostics(uri)
+        .into_iter()
+        .map(|d| d.message)
+        .collect()
+}
+
+#[test(tokio::test)]
+async fn test_published_diagnostics_are_cached_by_version() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    let uri = to_uri(&temp_dir.path().canonicalize()?.join("main.rs"));
+
+    let client = Client::new("cat", &[]).await?;
+    let mut events = client.subscribe_events();
+
+    client.store_diagnostics(publish(&uri, Some(2), &["unused variable"]));
+    assert_eq!(messages(&client, &uri), vec!["unused variable"]);
+    assert_eq!(client.diagnostics_version(&uri), Some(2));
+    assert_eq!(
+        events.recv().await?,
+        ProxyEvent::DiagnosticsUpdated {
+            uri: uri.clone(),
+            count: 1
+        }
+    );
+
+    // A late publication for an older version doesn't replace newer diagnostics
+    client.store_diagnostics(publish(&uri, Some(1), &["stale error"]));
+    assert_eq!(messages(&client, &uri), vec!["unused variable"]);
+
+    // Unversioned publications always replace the cache
+    client.store_diagnostics(publish(&uri, None, &[]));
+    assert!(messages(&client, &uri).is_empty());
+    assert_eq!(client.diagnostics_version(&uri), None);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_closing_a_file_evicts_its_diagnostics() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    let file_path = temp_dir.path().canonicalize()?.join("main.rs");
+    let uri = to_uri(&file_path);
+
+    let client = Client::new("cat", &[]).await?;
+    client.open_file(&file_path).await?;
+    client.store_diagnostics(publish(&uri, Some(1), &["missing semicolon"]));
+    assert_eq!(messages(&client, &uri), vec!["missing semicolon"]);
+
+    client.close_file(&file_path).await?;
+    assert!(messages(&client, &uri).is_empty());
+    assert_eq!(client.diagnostics_version(&uri), None);
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use mcp_language_server_rust::tools::diagnostics::enclosing_header;
+
+const SOURCE: &str = r#"use std::fmt;
+
+/// Formats a greeting.
+///
+/// The name is used as is.
+#[inline]
+pub fn greet(name: &str) -> String {
+    let greeting = format!("Hello, {}", name);
+    greeting
+}
+"#;
+
+#[test]
+fn test_range_starting_at_signature_includes_doc_comment() {
+    let lines: Vec<&str> = SOURCE.lines().collect();
+
+    // Servers like gopls start the symbol range at the signature
+    assert_eq!(enclosing_header(&lines, 6), (2, 6));
+}
+
+#[test]
+fn test_range_starting_at_doc_comment_finds_signature() {
+    let lines: Vec<&str> = SOURCE.lines().collect();
+
+    // rust-analyzer includes doc comments and attributes in the range
+    assert_eq!(enclosing_header(&lines, 2), (2, 6));
+}
+
+#[test]
+fn test_symbol_without_comment() {
+    let lines = vec!["x = 1", "", "def helper():", "    return x"];
+
+    assert_eq!(enclosing_header(&lines, 2), (2, 2));
+}
@@ -0,0 +1,37 @@
+use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+
+use mcp_language_server_rust::lsp::client::dedupe_diagnostics;
+
+fn diagnostic(line: u32, message: &str, source: &str, severity: DiagnosticSeverity) -> Diagnostic {
+    Diagnostic {
+        range: Range::new(Position::new(line, 0), Position::new(line, 5)),
+        severity: Some(severity),
+        source: Some(source.to_string()),
+        message: message.to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_duplicates_merge_sources() {
+    let merged = dedupe_diagnostics(vec![
+        diagnostic(1, "unused variable", "rustc", DiagnosticSeverity::WARNING),
+        diagnostic(1, "unused variable", "clippy", DiagnosticSeverity::ERROR),
+        diagnostic(1, "unused variable", "rustc", DiagnosticSeverity::WARNING),
+    ]);
+
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[0].source.as_deref(), Some("rustc, clippy"));
+    assert_eq!(merged[0].severity, Some(DiagnosticSeverity::ERROR));
+}
+
+#[test]
+fn test_distinct_diagnostics_are_kept() {
+    let merged = dedupe_diagnostics(vec![
+        diagnostic(1, "unused variable", "rustc", DiagnosticSeverity::WARNING),
+        diagnostic(2, "unused variable", "rustc", DiagnosticSeverity::WARNING),
+        diagnostic(1, "shadowed name", "clippy", DiagnosticSeverity::HINT),
+    ]);
+
+    assert_eq!(merged.len(), 3);
+}
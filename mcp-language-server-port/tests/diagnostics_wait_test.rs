@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use lsp_types::{PublishDiagnosticsParams, Url};
+use test_log::test;
+
+use mcp_language_server_rust::lsp::Client;
+
+fn publish(uri: &Url) -> PublishDiagnosticsParams {
+    PublishDiagnosticsParams {
+        uri: uri.clone(),
+        diagnostics: Vec::new(),
+        version: None,
+    }
+}
+
+#[test(tokio::test)]
+async fn test_wait_returns_once_diagnostics_are_published() -> Result<()> {
+    let client = Client::new("cat", &[]).await?;
+    let uri = Url::parse("file:///ws/src/main.rs")?;
+    assert_eq!(client.diagnostics_publication(&uri), None);
+
+    let publisher = client.clone();
+    let published_uri = uri.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        publisher.store_diagnostics(publish(&published_uri));
+    });
+
+    assert!(
+        client
+            .wait_for_diagnostics(&uri, None, Duration::from_secs(5))
+            .await
+    );
+    assert!(client.diagnostics_publication(&uri).is_some());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_wait_times_out_without_a_new_publication() -> Result<()> {
+    let client = Client::new("cat", &[]).await?;
+    let uri = Url::parse("file:///ws/src/main.rs")?;
+
+    assert!(
+        !client
+            .wait_for_diagnostics(&uri, None, Duration::from_millis(20))
+            .await
+    );
+
+    // Diagnostics published before the marker was taken don't count
+    client.store_diagnostics(publish(&uri));
+    let since = client.diagnostics_publication(&uri);
+    assert!(
+        !client
+            .wait_for_diagnostics(&uri, since, Duration::from_millis(20))
+            .await
+    );
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+
+/// A language server highlighting `count`: written on the first two lines and
+/// read on the third
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"documentHighlightProvider":true}}'"#,
+        ),
+        (
+            "textDocument/documentHighlight",
+            r#"reply '[{"range":{"start":{"line":2,"character":6},"end":{"line":2,"character":11}},"kind":2},{"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":5}},"kind":3},{"range":{"start":{"line":1,"character":0},"end":{"line":1,"character":5}},"kind":3}]'"#,
+        ),
+    ])
+}
+
+#[test(tokio::test)]
+async fn test_document_highlights_mark_reads_and_writes() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir
+        .child("main.py")
+        .write_str("count = 0\ncount += 1\nprint(count)\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(&workspace).await?;
+
+    let result = tools::get_document_highlights(&client, workspace.join("main.py"), 3, 8).await?;
+
+    assert!(
+        result.starts_with("Found 3 occurrences of `count` in main.py (1 reads, 2 writes)"),
+        "{}",
+        result
+    );
+    // Occurrences are listed in file order
+    assert!(
+        result
+            .contains("L1:1 write  count = 0\nL2:1 write  count += 1\nL3:7 read   print(count)\n"),
+        "{}",
+        result
+    );
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use lsp_types::{
+    DocumentSymbol, DocumentSymbolResponse, Location, Position, Range, SymbolInformation,
+    SymbolKind, Url,
+};
+
+use mcp_language_server_rust::tools::document_symbols::render_outline;
+
+fn range(start_line: u32, end_line: u32) -> Range {
+    Range {
+        start: Position {
+            line: start_line,
+            character: 0,
+        },
+        end: Position {
+            line: end_line,
+            character: 1,
+        },
+    }
+}
+
+#[allow(deprecated)]
+fn symbol(
+    name: &str,
+    kind: SymbolKind,
+    detail: Option<&str>,
+    range: Range,
+    children: Vec<DocumentSymbol>,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name: name.to_string(),
+        detail: detail.map(str::to_string),
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range: range,
+        children: if children.is_empty() {
+            None
+        } else {
+            Some(children)
+        },
+    }
+}
+
+#[allow(deprecated)]
+fn information(name: &str, kind: SymbolKind, range: Range) -> SymbolInformation {
+    SymbolInformation {
+        name: name.to_string(),
+        kind,
+        tags: None,
+        deprecated: None,
+        location: Location {
+            uri: Url::parse("file:///ws/src/shapes.rs").unwrap(),
+            range,
+        },
+        container_name: None,
+    }
+}
+
+#[test]
+fn test_nested_outline() {
+    let symbols = DocumentSymbolResponse::Nested(vec![
+        symbol(
+            "Circle",
+            SymbolKind::STRUCT,
+            None,
+            range(0, 3),
+            vec![symbol(
+                "radius",
+                SymbolKind::FIELD,
+                Some("f64"),
+                range(2, 2),
+                vec![],
+            )],
+        ),
+        symbol(
+            "main",
+            SymbolKind::FUNCTION,
+            Some("fn()"),
+            range(5, 9),
+            vec![],
+        ),
+    ]);
+
+    assert_eq!(
+        render_outline(&symbols),
+        "Struct Circle (lines 1-4)\n  Field radius: f64 (line 3)\nFunction main: fn() (lines 6-10)\n"
+    );
+}
+
+#[test]
+fn test_flat_outline_nests_by_range() {
+    let symbols = DocumentSymbolResponse::Flat(vec![
+        information("area", SymbolKind::METHOD, range(3, 8)),
+        information("Circle", SymbolKind::CLASS, range(2, 20)),
+        information("scale", SymbolKind::METHOD, range(10, 18)),
+        information("main", SymbolKind::FUNCTION, range(22, 30)),
+    ]);
+
+    assert_eq!(
+        render_outline(&symbols),
+        "Class Circle (lines 3-21)\n  Method area (lines 4-9)\n  Method scale (lines 11-19)\nFunction main (lines 23-31)\n"
+    );
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use lsp_types::{Position, Range, TextEdit, Url, WorkspaceEdit};
+use std::collections::HashMap;
+use test_log::test;
+
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools::{
+    self,
+    diff::unified_diff,
+    edit::TextEditParams,
+    workspace_edit::{self, ApplyOptions},
+};
+
+#[test]
+fn test_unified_diff_shows_changes_with_context() {
+    assert_eq!(unified_diff("main.rs", "a\n", "a\n"), "");
+
+    assert_eq!(
+        unified_diff("main.rs", "a\nb\nc\n", "a\nB\nc\n"),
+        "--- a/main.rs\n+++ b/main.rs\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n"
+    );
+
+    // Changes far apart get their own hunks
+    let old: String = (1..=10).map(|n| format!("{}\n", n)).collect();
+    let new = old.replace("1\n", "one\n").replace("10\n", "ten\n");
+    assert_eq!(
+        unified_diff("n.txt", &old, &new),
+        "--- a/n.txt\n+++ b/n.txt\n\
+         @@ -1,4 +1,4 @@\n-1\n+one\n 2\n 3\n 4\n\
+         @@ -7,4 +7,4 @@\n 7\n 8\n 9\n-10\n+ten\n"
+    );
+}
+
+#[test]
+fn test_unified_diff_marks_missing_final_newline() {
+    assert_eq!(
+        unified_diff("main.rs", "a", "b"),
+        "--- a/main.rs\n+++ b/main.rs\n@@ -1 +1 @@\n-a\n\\ No newline at end of file\n+b\n\\ No newline at end of file\n"
+    );
+}
+
+#[test(tokio::test)]
+async fn test_dry_run_edit_leaves_the_file_alone() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir
+        .child("main.rs")
+        .write_str("fn main() {\n    let x = 1;\n}\n")?;
+    let file_path = temp_dir.path().canonicalize()?.join("main.rs");
+    let client = Client::new("cat", &[]).await?;
+
+    let edits = vec![TextEditParams {
+        start_line: 2,
+        end_line: 2,
+        start_column: None,
+        end_column: None,
+        new_text: "    let x = 2;".to_string(),
+    }];
+    let result = tools::apply_text_edits(&client, file_path.clone(), edits, true).await?;
+
+    assert!(result.starts_with("Dry run: would apply 1 edits to "));
+    assert!(result.contains("-    let x = 1;\n+    let x = 2;\n"));
+    assert_eq!(
+        std::fs::read_to_string(&file_path)?,
+        "fn main() {\n    let x = 1;\n}\n"
+    );
+    assert!(!client.is_file_open(&file_path));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_workspace_edit_preview() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("lib.rs").write_str("fn old() {}\n")?;
+    let file_path = temp_dir.path().canonicalize()?.join("lib.rs");
+    let client = Client::new("cat", &[]).await?;
+
+    let edit = WorkspaceEdit {
+        changes: Some(HashMap::from([(
+            Url::from_file_path(&file_path).unwrap(),
+            vec![TextEdit {
+                range: Range::new(Position::new(0, 3), Position::new(0, 6)),
+                new_text: "new".to_string(),
+            }],
+        )])),
+        ..Default::default()
+    };
+    let result = workspace_edit::apply(
+        &client,
+        edit,
+        &ApplyOptions {
+            dry_run: true,
+            ..Default::default()
+        },
+    )
+    .await?
+    .to_string();
+
+    assert!(result.starts_with("Dry run: would apply 1 edits across 1 files\n"));
+    assert!(result.contains("lib.rs (1 edits)\n\n"));
+    assert!(result.contains("-fn old() {}\n+fn new() {}\n"));
+    assert_eq!(std::fs::read_to_string(&file_path)?, "fn old() {}\n");
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use std::time::Duration;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+use mcp_language_server_rust::tools::edit::TextEditParams;
+
+/// A language server that, a moment after each change, reports one error
+/// for the new version of the document
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"textDocumentSync":1}}'"#,
+        ),
+        (
+            "textDocument/didChange",
+            r#"
+[[ $body =~ \"uri\":\"([^\"]*)\" ]] && uri=${BASH_REMATCH[1]}
+[[ $body =~ \"version\":([0-9]+) ]] && version=${BASH_REMATCH[1]}
+sleep 0.2
+send "{\"jsonrpc\":\"2.0\",\"method\":\"textDocument/publishDiagnostics\",\"params\":{\"uri\":\"$uri\",\"version\":$version,\"diagnostics\":[{\"range\":{\"start\":{\"line\":0,\"character\":0},\"end\":{\"line\":0,\"character\":3}},\"severity\":1,\"message\":\"error in version $version\"}]}}"
+"#,
+        ),
+    ])
+}
+
+#[test(tokio::test)]
+async fn test_edit_and_check_waits_for_fresh_diagnostics() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.go").write_str("old\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let file_path = workspace.join("main.go");
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(&workspace).await?;
+
+    let edits = vec![TextEditParams {
+        start_line: 1,
+        end_line: 1,
+        start_column: None,
+        end_column: None,
+        new_text: "new".to_string(),
+    }];
+    let result = tools::edit_and_check(
+        &client,
+        file_path.clone(),
+        edits,
+        0,
+        Duration::from_secs(5),
+        false,
+    )
+    .await?;
+
+    assert!(
+        result.starts_with("Successfully applied 1 edits"),
+        "{}",
+        result
+    );
+    assert!(result.contains("error in version"), "{}", result);
+    assert!(!result.contains("may predate the edit"), "{}", result);
+    assert_eq!(std::fs::read_to_string(&file_path)?, "new\n");
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_edit_and_check_dry_run_checks_an_overlay() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.go").write_str("old\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let file_path = workspace.join("main.go");
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(&workspace).await?;
+
+    let edits = vec![TextEditParams {
+        start_line: 1,
+        end_line: 1,
+        start_column: None,
+        end_column: None,
+        new_text: "new".to_string(),
+    }];
+    let result = tools::edit_and_check(
+        &client,
+        file_path.clone(),
+        edits,
+        0,
+        Duration::from_secs(5),
+        true,
+    )
+    .await?;
+
+    // The server checked the proposed content, version 2 after the open
+    assert!(result.starts_with("Dry run: checked 1 edits"), "{}", result);
+    assert!(result.contains("+new"), "{}", result);
+    assert!(result.contains("error in version 2"), "{}", result);
+
+    // Neither the file nor the server keeps it
+    assert_eq!(std::fs::read_to_string(&file_path)?, "old\n");
+    assert!(!client.is_file_dirty(&file_path));
+    assert_eq!(client.overlay(&file_path), None);
+
+    Ok(())
+}
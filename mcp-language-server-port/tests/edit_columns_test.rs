@@ -0,0 +1,112 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use test_log::test;
+
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools::{self, edit::TextEditParams};
+
+fn column_edit(line: u32, start_column: u32, end_column: u32, new_text: &str) -> TextEditParams {
+    TextEditParams {
+        start_line: line,
+        end_line: line,
+        start_column: Some(start_column),
+        end_column: Some(end_column),
+        new_text: new_text.to_string(),
+    }
+}
+
+#[test(tokio::test)]
+async fn test_column_edits_replace_part_of_a_line() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir
+        .child("main.rs")
+        .write_str("fn main() {\n    let x = 1;\n}\n")?;
+    let file_path = temp_dir.path().join("main.rs");
+    let client = Client::new("cat", &[]).await?;
+
+    let edits = vec![
+        // An insertion, then a replacement
+        column_edit(1, 4, 4, "real_"),
+        column_edit(2, 13, 14, "42"),
+    ];
+    tools::apply_text_edits(&client, file_path.clone(), edits, false).await?;
+
+    assert_eq!(
+        std::fs::read_to_string(&file_path)?,
+        "fn real_main() {\n    let x = 42;\n}\n"
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_column_edits_count_utf16_code_units() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("let s = \"é😀\";\n")?;
+    let file_path = temp_dir.path().join("main.rs");
+    let client = Client::new("cat", &[]).await?;
+
+    // The emoji spans columns 11 and 12
+    tools::apply_text_edits(
+        &client,
+        file_path.clone(),
+        vec![column_edit(1, 11, 13, "!")],
+        false,
+    )
+    .await?;
+
+    assert_eq!(std::fs::read_to_string(&file_path)?, "let s = \"é!\";\n");
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_columns_must_be_given_together() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    let file_path = temp_dir.path().join("main.rs");
+    let client = Client::new("cat", &[]).await?;
+
+    let edit = TextEditParams {
+        end_column: None,
+        ..column_edit(1, 4, 4, "real_")
+    };
+    let error = tools::apply_text_edits(&client, file_path.clone(), vec![edit], false)
+        .await
+        .unwrap_err();
+
+    assert!(error.to_string().contains("must be given together"));
+    assert_eq!(std::fs::read_to_string(&file_path)?, "fn main() {}\n");
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_column_edits_keep_the_indentation_of_their_first_line() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir
+        .child(".editorconfig")
+        .write_str("root = true\n\n[*]\nindent_style = tab\nindent_size = 4\n")?;
+    temp_dir
+        .child("main.rs")
+        .write_str("fn main() {\n    let x = 1;\n}\n")?;
+    let file_path = temp_dir.path().join("main.rs");
+    let client = Client::new("cat", &[]).await?;
+
+    // The spaces before the comment are not indentation; the next line's are
+    tools::apply_text_edits(
+        &client,
+        file_path.clone(),
+        vec![column_edit(2, 15, 15, "    // one\n    let y = 2;")],
+        false,
+    )
+    .await?;
+
+    assert_eq!(
+        std::fs::read_to_string(&file_path)?,
+        "fn main() {\n    let x = 1;    // one\n\tlet y = 2;\n}\n"
+    );
+
+    Ok(())
+}
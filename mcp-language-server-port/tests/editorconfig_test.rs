@@ -0,0 +1,97 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+
+use mcp_language_server_rust::workspace::editorconfig::{EditorConfig, IndentStyle, parse};
+
+const EDITORCONFIG: &str = r#"
+# Top-most EditorConfig file
+root = true
+
+[*]
+insert_final_newline = true
+charset = utf-8
+
+[*.go]
+indent_style = tab
+indent_size = 4
+
+[src/legacy/*.py]
+indent_style = space
+indent_size = 2
+"#;
+
+#[test]
+fn test_parse_sections() {
+    let file = parse(EDITORCONFIG);
+
+    assert!(file.root);
+    assert_eq!(file.sections.len(), 3);
+    assert_eq!(file.sections[1].pattern, "*.go");
+    assert_eq!(
+        file.sections[1].properties[0],
+        ("indent_style".to_string(), "tab".to_string())
+    );
+}
+
+#[test]
+fn test_resolves_properties_for_file() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child(".editorconfig").write_str(EDITORCONFIG)?;
+    temp_dir.child("cmd/main.go").touch()?;
+    temp_dir.child("src/legacy/old.py").touch()?;
+
+    let go = EditorConfig::for_file(&temp_dir.path().join("cmd/main.go"));
+    assert_eq!(go.indent_style, Some(IndentStyle::Tab));
+    assert_eq!(go.indent_size, Some(4));
+    assert_eq!(go.insert_final_newline, Some(true));
+
+    let python = EditorConfig::for_file(&temp_dir.path().join("src/legacy/old.py"));
+    assert_eq!(python.indent_style, Some(IndentStyle::Space));
+    assert_eq!(python.indent_size, Some(2));
+
+    // Anchored patterns don't match elsewhere in the tree
+    let other = EditorConfig::for_file(&temp_dir.path().join("old.py"));
+    assert_eq!(other.indent_style, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_new_text_uses_configured_indentation() {
+    let tabs = EditorConfig {
+        indent_style: Some(IndentStyle::Tab),
+        indent_size: Some(4),
+        ..Default::default()
+    };
+    assert_eq!(
+        tabs.format_new_text("func main() {\n        x := 1\n}\n"),
+        "func main() {\n\t\tx := 1\n}\n"
+    );
+
+    let spaces = EditorConfig {
+        indent_style: Some(IndentStyle::Space),
+        indent_size: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(
+        spaces.format_new_text("\tif x:\n\t\tpass"),
+        "  if x:\n    pass"
+    );
+}
+
+#[test]
+fn test_finish_file() {
+    let config = EditorConfig {
+        insert_final_newline: Some(true),
+        charset: Some("utf-8-bom".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(config.finish_file("a = 1".to_string()), "\u{feff}a = 1\n");
+
+    // Without rules the content is written as is
+    assert_eq!(
+        EditorConfig::default().finish_file("a = 1".to_string()),
+        "a = 1"
+    );
+}
@@ -0,0 +1,84 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use std::sync::Arc;
+use std::time::Duration;
+use test_log::test;
+
+use mcp_language_server_rust::lsp::{Client, ProxyEvent};
+use mcp_language_server_rust::tools::utils::to_uri;
+use mcp_language_server_rust::watcher::{FileSystemWatcher, WorkspaceWatcher};
+
+#[test(tokio::test)]
+async fn test_document_events_are_broadcast() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    let file_path = temp_dir.path().canonicalize()?.join("main.rs");
+    let uri = to_uri(&file_path);
+
+    let client = Client::new("cat", &[]).await?;
+    let mut events = client.subscribe_events();
+
+    client.open_file(&file_path).await?;
+    client.close_file(&file_path).await?;
+
+    assert_eq!(
+        events.recv().await?,
+        ProxyEvent::DocumentOpened { uri: uri.clone() }
+    );
+    assert_eq!(events.recv().await?, ProxyEvent::DocumentClosed { uri });
+
+    Ok(())
+}
+
+#[test]
+fn test_events_serialize_with_type_tag() -> Result<()> {
+    let json = serde_json::to_value(ProxyEvent::WatcherStopped)?;
+    assert_eq!(json["type"], "watcher_stopped");
+
+    let json = serde_json::to_value(ProxyEvent::ServerRestarted {
+        server: "rust-analyzer".to_string(),
+        reopened: 3,
+    })?;
+    assert_eq!(json["type"], "server_restarted");
+    assert_eq!(json["reopened"], 3);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_paused_watcher_holds_back_changes() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let workspace = temp_dir.path().canonicalize()?;
+
+    let client = Client::new("cat", &[]).await?;
+    let mut events = client.subscribe_events();
+    let watcher = FileSystemWatcher::new(Arc::clone(&client), workspace.clone());
+    watcher.watch_workspace(workspace.clone()).await?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    watcher.pause();
+    assert_eq!(events.recv().await?, ProxyEvent::WatcherPaused);
+
+    std::fs::write(workspace.join("added.rs"), "fn added() {}\n")?;
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+    assert!(
+        events.try_recv().is_err(),
+        "changes wait for the watcher to resume"
+    );
+
+    watcher.resume();
+    assert_eq!(events.recv().await?, ProxyEvent::WatcherResumed);
+    let event = tokio::time::timeout(Duration::from_secs(5), events.recv()).await??;
+    let ProxyEvent::FilesChanged { changes } = event else {
+        panic!("expected the held back changes, got {:?}", event);
+    };
+    assert!(
+        changes
+            .iter()
+            .any(|change| change.uri == to_uri(&workspace.join("added.rs")))
+    );
+
+    watcher.stop().await?;
+    Ok(())
+}
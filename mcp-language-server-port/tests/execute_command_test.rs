@@ -0,0 +1,78 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use serde_json::json;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+
+/// A language server offering a `fake.fix` command, which asks the client to
+/// rewrite the first line of the file given as the server's argument
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"executeCommandProvider":{"commands":["fake.fix"]}}}'"#,
+        ),
+        (
+            "workspace/executeCommand",
+            r#"
+command=$id
+send "{\"jsonrpc\":\"2.0\",\"id\":900,\"method\":\"workspace/applyEdit\",\"params\":{\"edit\":{\"changes\":{\"$1\":[{\"range\":{\"start\":{\"line\":0,\"character\":0},\"end\":{\"line\":0,\"character\":5}},\"newText\":\"fixed\"}]}}}}"
+"#,
+        ),
+        (
+            "",
+            r#"
+# The command finishes once the client has applied its edit
+if [[ $body =~ \"applied\":true ]]; then
+    send "{\"jsonrpc\":\"2.0\",\"id\":$command,\"result\":{\"fixes\":1}}"
+else
+    send "{\"jsonrpc\":\"2.0\",\"id\":$command,\"error\":{\"code\":-32603,\"message\":\"edit not applied\"}}"
+fi
+"#,
+        ),
+    ])
+}
+
+#[test(tokio::test)]
+async fn test_execute_command_applies_requested_edits() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.go").write_str("broke\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let file_path = workspace.join("main.go");
+    let uri = tools::utils::to_uri(&file_path).to_string();
+
+    let client = Client::new(
+        "bash",
+        &["-c".to_string(), server(), "server".to_string(), uri],
+    )
+    .await?;
+    client.initialize(&workspace).await?;
+
+    let result = tools::execute_command(&client, "fake.fix", vec![json!("main.go")]).await?;
+
+    assert!(
+        result.starts_with("Executed command 'fake.fix'"),
+        "{}",
+        result
+    );
+    assert!(result.contains("\"fixes\": 1"));
+    assert_eq!(std::fs::read_to_string(&file_path)?, "fixed\n");
+
+    // Commands the server doesn't offer are refused with the ones it does
+    let error = tools::execute_command(&client, "fake.other", Vec::new())
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("it offers: fake.fix"),
+        "{}",
+        error
+    );
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use test_log::test;
+
+use mcp_language_server_rust::workspace::hashes::{
+    FileHashes, changed_files, format_hash, hash_content,
+};
+
+#[test(tokio::test)]
+async fn test_refresh_reports_content_changes() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    let file_path = temp_dir.path().join("main.rs");
+
+    let hashes = FileHashes::new();
+    assert!(hashes.refresh(&file_path).await?);
+
+    // Rewriting the same content is not a change
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    assert!(!hashes.refresh(&file_path).await?);
+
+    temp_dir
+        .child("main.rs")
+        .write_str("fn main() { todo!() }\n")?;
+    assert!(hashes.refresh(&file_path).await?);
+    assert_eq!(
+        hashes.hash(&file_path).await?,
+        hash_content(b"fn main() { todo!() }\n")
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_check_rejects_stale_hash() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    let file_path = temp_dir.path().join("main.rs");
+
+    let hashes = FileHashes::new();
+    let seen = format_hash(hashes.hash(&file_path).await?);
+    hashes.check(&file_path, &seen).await?;
+
+    // A write the watcher hasn't reported yet still fails the check
+    temp_dir.child("main.rs").write_str("fn other() {}\n")?;
+    let error = hashes
+        .check(&file_path, &seen)
+        .await
+        .expect_err("stale hash should be rejected");
+    assert!(error.to_string().contains("has changed"));
+
+    Ok(())
+}
+
+#[test]
+fn test_changed_files_between_snapshots() {
+    let before = HashMap::from([
+        (PathBuf::from("/ws/a.rs"), 1),
+        (PathBuf::from("/ws/b.rs"), 2),
+        (PathBuf::from("/ws/c.rs"), 3),
+    ]);
+    let after = HashMap::from([
+        (PathBuf::from("/ws/a.rs"), 1),
+        (PathBuf::from("/ws/b.rs"), 20),
+        (PathBuf::from("/ws/d.rs"), 4),
+    ]);
+
+    assert_eq!(
+        changed_files(&before, &after),
+        vec![
+            PathBuf::from("/ws/b.rs"),
+            PathBuf::from("/ws/c.rs"),
+            PathBuf::from("/ws/d.rs"),
+        ]
+    );
+}
@@ -0,0 +1,254 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use lsp_types::{Diagnostic, Position, PublishDiagnosticsParams, Range};
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+use mcp_language_server_rust::tools::scope::ActionScope;
+use mcp_language_server_rust::tools::workspace_edit::ApplyOptions;
+
+/// A language server offering one preferred quick fix, for the `missing
+/// semicolon` diagnostic on the first line of the file given as its argument
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"codeActionProvider":true}}'"#,
+        ),
+        (
+            "textDocument/codeAction",
+            r#"
+range='{"start":{"line":0,"character":5},"end":{"line":0,"character":5}}'
+reply "[{\"title\":\"Insert ';'\",\"kind\":\"quickfix\",\"isPreferred\":true,\"diagnostics\":[{\"range\":$range,\"message\":\"missing semicolon\"}],\"edit\":{\"changes\":{\"$1\":[{\"range\":$range,\"newText\":\";\"}]}}}]"
+"#,
+        ),
+    ])
+}
+
+/// A language server whose fix for an unknown type adds an include at the top
+/// of the file given as its argument. Once the file changes it reports the
+/// missing semicolon a line further down.
+fn include_server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"codeActionProvider":true}}'"#,
+        ),
+        (
+            "textDocument/didChange",
+            r#"
+range='{"start":{"line":1,"character":5},"end":{"line":1,"character":5}}'
+send "{\"jsonrpc\":\"2.0\",\"method\":\"textDocument/publishDiagnostics\",\"params\":{\"uri\":\"$1\",\"diagnostics\":[{\"range\":$range,\"message\":\"missing semicolon\"}]}}"
+"#,
+        ),
+        (
+            "textDocument/codeAction",
+            r##"
+[[ $body =~ \"start\":\{\"line\":([0-9]+) ]]
+line=${BASH_REMATCH[1]}
+if [[ $body =~ unknown\ type ]]; then
+    title="Include <stdint.h>"
+    range='{"start":{"line":0,"character":0},"end":{"line":0,"character":0}}'
+    text="#include <stdint.h>\\n"
+else
+    title="Insert ';'"
+    range="{\"start\":{\"line\":$line,\"character\":5},\"end\":{\"line\":$line,\"character\":5}}"
+    text=";"
+fi
+reply "[{\"title\":\"$title\",\"kind\":\"quickfix\",\"isPreferred\":true,\"edit\":{\"changes\":{\"$1\":[{\"range\":$range,\"newText\":\"$text\"}]}}}]"
+"##,
+        ),
+    ])
+}
+
+fn diagnostic(line: u32, character: u32, message: &str) -> Diagnostic {
+    let position = Position { line, character };
+    Diagnostic {
+        range: Range {
+            start: position,
+            end: position,
+        },
+        message: message.to_string(),
+        ..Default::default()
+    }
+}
+
+#[test(tokio::test)]
+async fn test_fix_diagnostics_applies_preferred_fixes() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.c").write_str("int a\n\nint b;\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let file_path = workspace.join("main.c");
+    let uri = tools::utils::to_uri(&file_path);
+
+    let client = Client::new(
+        "bash",
+        &[
+            "-c".to_string(),
+            server(),
+            "server".to_string(),
+            uri.to_string(),
+        ],
+    )
+    .await?;
+    client.initialize(&workspace).await?;
+    client.store_diagnostics(PublishDiagnosticsParams {
+        uri,
+        diagnostics: vec![
+            diagnostic(0, 5, "missing semicolon"),
+            diagnostic(2, 4, "unused variable 'b'"),
+        ],
+        version: None,
+    });
+
+    // A dry run previews the fix without writing it
+    let preview = tools::fix_diagnostics(
+        &client,
+        Some(file_path.clone()),
+        ActionScope::File,
+        20,
+        &ApplyOptions {
+            dry_run: true,
+            show_diff: true,
+        },
+    )
+    .await?;
+    assert!(preview.starts_with("Would fix 1 of 2"), "{}", preview);
+    assert!(preview.contains("+int a;"), "{}", preview);
+    assert_eq!(std::fs::read_to_string(&file_path)?, "int a\n\nint b;\n");
+
+    // The workspace covers every file with diagnostics; the fix the server
+    // offers for the first diagnostic doesn't count for the second
+    let result = tools::fix_diagnostics(
+        &client,
+        None,
+        ActionScope::File,
+        20,
+        &ApplyOptions::default(),
+    )
+    .await?;
+    assert!(result.starts_with("Fixed 1 of 2"), "{}", result);
+    assert!(
+        result.contains("main.c:1: missing semicolon -> Insert ';'"),
+        "{}",
+        result
+    );
+    assert!(
+        result.contains("main.c:3: unused variable 'b' (no fix available)"),
+        "{}",
+        result
+    );
+    assert_eq!(std::fs::read_to_string(&file_path)?, "int a;\n\nint b;\n");
+
+    // The limit leaves the remaining diagnostics alone
+    let limited = tools::fix_diagnostics(
+        &client,
+        Some(file_path.clone()),
+        ActionScope::File,
+        0,
+        &ApplyOptions::default(),
+    )
+    .await?;
+    assert!(limited.contains("(fix limit reached)"), "{}", limited);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_fix_diagnostics_in_directory_scope() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("src/main.c").write_str("int a\n")?;
+    temp_dir.child("other.c").write_str("int a\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let file_path = workspace.join("src/main.c");
+    let other_path = workspace.join("other.c");
+    let uri = tools::utils::to_uri(&file_path);
+
+    let client = Client::new(
+        "bash",
+        &[
+            "-c".to_string(),
+            server(),
+            "server".to_string(),
+            uri.to_string(),
+        ],
+    )
+    .await?;
+    client.initialize(&workspace).await?;
+    for path in [&file_path, &other_path] {
+        client.open_file(path).await?;
+        client.store_diagnostics(PublishDiagnosticsParams {
+            uri: tools::utils::to_uri(path),
+            diagnostics: vec![diagnostic(0, 5, "missing semicolon")],
+            version: None,
+        });
+    }
+
+    // Only the diagnostics in the file's directory are fixed
+    let result = tools::fix_diagnostics(
+        &client,
+        Some(file_path.clone()),
+        ActionScope::Directory,
+        20,
+        &ApplyOptions::default(),
+    )
+    .await?;
+    assert!(result.starts_with("Fixed 1 of 1"), "{}", result);
+    assert_eq!(std::fs::read_to_string(&file_path)?, "int a;\n");
+    assert_eq!(std::fs::read_to_string(&other_path)?, "int a\n");
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_fix_diagnostics_rechecks_after_each_fix() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.c").write_str("int a\nuint8_t b;\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let file_path = workspace.join("main.c");
+    let uri = tools::utils::to_uri(&file_path);
+
+    let client = Client::new(
+        "bash",
+        &[
+            "-c".to_string(),
+            include_server(),
+            "server".to_string(),
+            uri.to_string(),
+        ],
+    )
+    .await?;
+    client.initialize(&workspace).await?;
+    client.open_file(&file_path).await?;
+    client.store_diagnostics(PublishDiagnosticsParams {
+        uri,
+        diagnostics: vec![
+            diagnostic(0, 5, "missing semicolon"),
+            diagnostic(1, 0, "unknown type 'uint8_t'"),
+        ],
+        version: None,
+    });
+
+    // The include moves the missing semicolon down a line, where the second
+    // fix has to go
+    let result = tools::fix_diagnostics(
+        &client,
+        Some(file_path.clone()),
+        ActionScope::File,
+        20,
+        &ApplyOptions::default(),
+    )
+    .await?;
+    assert!(result.starts_with("Fixed 2 of 2"), "{}", result);
+    assert_eq!(
+        std::fs::read_to_string(&file_path)?,
+        "#include <stdint.h>\nint a;\nuint8_t b;\n"
+    );
+
+    Ok(())
+}
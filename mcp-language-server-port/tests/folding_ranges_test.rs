@@ -0,0 +1,52 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+
+/// A language server folding the imports, a function and the loop inside it
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"foldingRangeProvider":true}}'"#,
+        ),
+        (
+            "textDocument/foldingRange",
+            r#"reply '[{"startLine":4,"endLine":6},{"startLine":3,"endLine":7},{"startLine":0,"endLine":1,"kind":"imports"}]'"#,
+        ),
+    ])
+}
+
+#[test(tokio::test)]
+async fn test_folding_ranges_nested_outline() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.py").write_str(
+        "import os\nimport sys\n\ndef main():\n    for arg in sys.argv:\n        print(arg)\n        print(os.sep)\n    return 0\n",
+    )?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let file_path = workspace.join("main.py");
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(&workspace).await?;
+
+    let result = tools::get_folding_ranges(&client, file_path.clone(), 1).await?;
+    assert!(
+        result
+            .contains("L1-2 [imports] import os\nL4-8 def main():\n  L5-7 for arg in sys.argv:\n"),
+        "{}",
+        result
+    );
+
+    // Short ranges can be left out
+    let result = tools::get_folding_ranges(&client, file_path, 3).await?;
+    assert!(result.starts_with("Found 2 folding ranges"), "{}", result);
+    assert!(!result.contains("[imports]"), "{}", result);
+
+    Ok(())
+}
@@ -74,12 +74,14 @@ fn main() {
         tools::edit::TextEditParams {
             start_line: 4,
             end_line: 4,
+            start_column: None,
+            end_column: None,
             new_text: "    name: String,\n    email: String,\n    age: u32,\n".to_string(),
         }
     ];
     
     // Apply the edits
-    let edit_result = tools::apply_text_edits(&client_arc, file_path.clone(), edits).await?;
+    let edit_result = tools::apply_text_edits(&client_arc, file_path.clone(), edits, false).await?;
     assert!(edit_result.contains("Successfully"), "Edit should be successful");
     
     // Read the updated file
@@ -91,12 +93,14 @@ fn main() {
         tools::edit::TextEditParams {
             start_line: 12,
             end_line: 12,
+            start_column: None,
+            end_column: None,
             new_text: "            name: name.to_string(),\n            email: email.to_string(),\n            age: 0,\n".to_string(),
         }
     ];
     
     // Apply the edits
-    let edit_result = tools::apply_text_edits(&client_arc, file_path.clone(), edits).await?;
+    let edit_result = tools::apply_text_edits(&client_arc, file_path.clone(), edits, false).await?;
     assert!(edit_result.contains("Successfully"), "Edit should be successful");
     
     // Read the updated file again
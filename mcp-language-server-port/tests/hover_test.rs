@@ -50,7 +50,7 @@ async fn test_get_hover_info() -> Result<()> {
     let column = 9; // The 'n' in 'name'
     
     // Use the task function to get hover information
-    let result = tools::get_hover_info(&client, file_path.clone(), line, column).await?;
+    let result = tools::get_hover_info(&client, file_path.clone(), line, column, None).await?;
     
     // Verify the result
     assert!(!result.is_empty(), "Hover result should not be empty");
@@ -74,7 +74,7 @@ async fn test_get_hover_info_method() -> Result<()> {
     let column = 16; // The 'a' in 'add_attribute'
     
     // Use the task function to get hover information
-    let result = tools::get_hover_info(&client, file_path.clone(), line, column).await?;
+    let result = tools::get_hover_info(&client, file_path.clone(), line, column, None).await?;
     
     // Verify the result
     assert!(!result.is_empty(), "Hover result should not be empty");
@@ -98,7 +98,7 @@ async fn test_get_hover_info_no_info_available() -> Result<()> {
     let column = 1; // Beginning of line
     
     // Use the task function to get hover information
-    let result = tools::get_hover_info(&client, file_path.clone(), line, column).await?;
+    let result = tools::get_hover_info(&client, file_path.clone(), line, column, None).await?;
     
     // Verify the result indicates no information
     assert!(result.contains("No hover information available"), 
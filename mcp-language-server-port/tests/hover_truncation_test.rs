@@ -0,0 +1,40 @@
+use mcp_language_server_rust::tools::hover::truncate_hover;
+
+/// A hover in the shape rust-analyzer returns for a trait: signature, docs, examples
+fn long_hover() -> String {
+    let mut hover = String::from("```rust\npub trait Iterator\n```\n\n---\n\n");
+    hover.push_str("An interface for dealing with iterators.\n\n");
+    hover.push_str("This is the main iterator trait. For more about the concept of iterators\n");
+    hover.push_str("generally, please see the module-level documentation.\n\n");
+    hover.push_str("```rust\nlet v = vec![1, 2, 3];\n\nlet mut iter = v.iter();\n```\n\n");
+    hover.push_str(&"More documentation. ".repeat(200));
+    hover
+}
+
+#[test]
+fn test_short_hover_is_unchanged() {
+    let hover = "```rust\nfn main()\n```";
+    assert_eq!(truncate_hover(hover, 100), hover);
+}
+
+#[test]
+fn test_keeps_signature_and_first_paragraph() {
+    let result = truncate_hover(&long_hover(), 500);
+
+    assert!(result.starts_with("```rust\npub trait Iterator\n```"));
+    assert!(result.contains("An interface for dealing with iterators."));
+    assert!(!result.contains("This is the main iterator trait"));
+    assert!(!result.contains("let mut iter"));
+    assert!(result.ends_with("characters]"));
+    assert!(result.contains("[Hover truncated: showing"));
+}
+
+#[test]
+fn test_hard_cut_when_summary_is_too_long() {
+    let hover = format!("```rust\nfn {}()\n```", "a".repeat(300));
+    let result = truncate_hover(&hover, 50);
+
+    // The open code fence is closed before the truncation note
+    assert_eq!(result.matches("```").count(), 2);
+    assert!(result.contains("showing 50 of"));
+}
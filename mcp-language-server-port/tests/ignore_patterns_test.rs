@@ -0,0 +1,50 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+
+use mcp_language_server_rust::watcher::gitignore::{GitignoreFilter, IGNORE_FILE};
+
+#[test]
+fn test_default_patterns_are_ignored() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let filter = GitignoreFilter::new(temp_dir.path().to_path_buf());
+    let ignored = |relative: &str| filter.is_ignored(&temp_dir.path().join(relative));
+
+    assert!(ignored(".git/HEAD"));
+    assert!(ignored("web/node_modules/react/index.js"));
+    assert!(ignored("src/main.rs~"));
+    assert!(ignored("notes.swp"));
+    // Files that transactional writes are staged in
+    assert!(ignored("src/.main.rs.4242.tmp"));
+    assert!(!ignored("src/main.rs"));
+
+    Ok(())
+}
+
+#[test]
+fn test_extra_patterns_extend_and_override_defaults() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let filter = GitignoreFilter::new(temp_dir.path().to_path_buf());
+    filter.set_extra_patterns(vec!["target/".to_string(), "!node_modules/".to_string()]);
+    let ignored = |relative: &str| filter.is_ignored(&temp_dir.path().join(relative));
+
+    assert!(ignored("target/debug/build.rs"));
+    assert!(!ignored("node_modules/react/index.js"));
+
+    Ok(())
+}
+
+#[test]
+fn test_ignore_file_patterns_win_over_gitignore() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child(IGNORE_FILE).write_str("generated/\n")?;
+    temp_dir.child(".gitignore").write_str("!generated/\n")?;
+    let filter = GitignoreFilter::new(temp_dir.path().to_path_buf());
+
+    assert!(filter.is_ignored(&temp_dir.path().join("generated/schema.rs")));
+    assert!(GitignoreFilter::is_rules_file(
+        &temp_dir.path().join(IGNORE_FILE)
+    ));
+
+    Ok(())
+}
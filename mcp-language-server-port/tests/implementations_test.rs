@@ -0,0 +1,69 @@
+use lsp_types::{
+    Location, OneOf, Position, Range, SymbolInformation, SymbolKind, Url, WorkspaceLocation,
+    WorkspaceSymbol, WorkspaceSymbolResponse,
+};
+
+use mcp_language_server_rust::tools::implementations::trait_locations;
+
+fn location(line: u32) -> Location {
+    Location {
+        uri: Url::parse("file:///tmp/shapes.rs").unwrap(),
+        range: Range {
+            start: Position { line, character: 0 },
+            end: Position {
+                line,
+                character: 10,
+            },
+        },
+    }
+}
+
+#[allow(deprecated)]
+fn flat_symbol(name: &str, kind: SymbolKind, line: u32) -> SymbolInformation {
+    SymbolInformation {
+        name: name.to_string(),
+        kind,
+        tags: None,
+        deprecated: None,
+        location: location(line),
+        container_name: None,
+    }
+}
+
+#[test]
+fn test_only_exact_trait_matches() {
+    // Fuzzy workspace symbol search also returns similarly named items
+    let symbols = WorkspaceSymbolResponse::Flat(vec![
+        flat_symbol("Shape", SymbolKind::INTERFACE, 1),
+        flat_symbol("ShapeExt", SymbolKind::INTERFACE, 5),
+        flat_symbol("Shape", SymbolKind::STRUCT, 9),
+    ]);
+
+    assert_eq!(trait_locations(&symbols, "Shape"), vec![location(1)]);
+}
+
+#[test]
+fn test_nested_symbols_without_range_are_skipped() {
+    let symbols = WorkspaceSymbolResponse::Nested(vec![
+        WorkspaceSymbol {
+            name: "Shape".to_string(),
+            kind: SymbolKind::INTERFACE,
+            tags: None,
+            container_name: None,
+            location: OneOf::Left(location(3)),
+            data: None,
+        },
+        WorkspaceSymbol {
+            name: "Shape".to_string(),
+            kind: SymbolKind::INTERFACE,
+            tags: None,
+            container_name: None,
+            location: OneOf::Right(WorkspaceLocation {
+                uri: Url::parse("file:///tmp/other.rs").unwrap(),
+            }),
+            data: None,
+        },
+    ]);
+
+    assert_eq!(trait_locations(&symbols, "Shape"), vec![location(3)]);
+}
@@ -0,0 +1,74 @@
+use lsp_types::{Position, Range};
+
+use mcp_language_server_rust::lsp::PositionEncoding;
+use mcp_language_server_rust::lsp::sync::content_change;
+
+fn range(start: (u32, u32), end: (u32, u32)) -> Option<Range> {
+    Some(Range {
+        start: Position {
+            line: start.0,
+            character: start.1,
+        },
+        end: Position {
+            line: end.0,
+            character: end.1,
+        },
+    })
+}
+
+#[test]
+fn test_equal_content_has_no_change() {
+    assert!(content_change("fn main() {}\n", "fn main() {}\n", PositionEncoding::Utf16).is_none());
+}
+
+#[test]
+fn test_change_covers_only_the_differing_range() {
+    let change = content_change(
+        "fn main() {\n    let x = 1;\n}\n",
+        "fn main() {\n    let x = 42;\n}\n",
+        PositionEncoding::Utf16,
+    )
+    .unwrap();
+    assert_eq!(change.range, range((1, 12), (1, 13)));
+    assert_eq!(change.text, "42");
+
+    // Pure insertion and deletion
+    let change = content_change("a\nc\n", "a\nb\nc\n", PositionEncoding::Utf16).unwrap();
+    assert_eq!(change.range, range((1, 0), (1, 0)));
+    assert_eq!(change.text, "b\n");
+
+    let change = content_change("a\nb\nc\n", "a\nc\n", PositionEncoding::Utf16).unwrap();
+    assert_eq!(change.range, range((1, 0), (2, 0)));
+    assert_eq!(change.text, "");
+}
+
+#[test]
+fn test_repeated_text_does_not_overlap() {
+    let change = content_change("aaa", "aaaa", PositionEncoding::Utf16).unwrap();
+    assert_eq!(change.range, range((0, 3), (0, 3)));
+    assert_eq!(change.text, "a");
+}
+
+#[test]
+fn test_columns_count_utf16_code_units() {
+    // é is one UTF-16 unit, the emoji two
+    let change = content_change(
+        "let s = \"é😀a\";\n",
+        "let s = \"é😀b\";\n",
+        PositionEncoding::Utf16,
+    )
+    .unwrap();
+    assert_eq!(change.range, range((0, 12), (0, 13)));
+    assert_eq!(change.text, "b");
+
+    let change = content_change("😀", "😁", PositionEncoding::Utf16).unwrap();
+    assert_eq!(change.range, range((0, 0), (0, 2)));
+    assert_eq!(change.text, "😁");
+}
+
+#[test]
+fn test_crlf_line_breaks_are_not_split() {
+    let change = content_change("a\r\nb\r\n", "a\nb\r\n", PositionEncoding::Utf16).unwrap();
+    assert_eq!(change.text, "\n");
+    assert_eq!(change.range, range((0, 1), (1, 0)));
+}
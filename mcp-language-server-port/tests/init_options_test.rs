@@ -0,0 +1,40 @@
+use anyhow::Result;
+use serde_json::json;
+use test_log::test;
+
+use mcp_language_server_rust::lsp::{Client, presets};
+
+#[test]
+fn test_known_servers_have_presets() {
+    let gopls = presets::initialization_options("/usr/local/bin/gopls").unwrap();
+    assert_eq!(gopls["codelenses"]["test"], json!(true));
+
+    for command in [
+        "rust-analyzer",
+        "pyright-langserver",
+        "typescript-language-server",
+        "clangd",
+    ] {
+        assert!(
+            presets::initialization_options(command).is_some(),
+            "{command}"
+        );
+    }
+
+    // Other servers aren't sent gopls options they don't understand
+    assert_eq!(presets::initialization_options("my-server"), None);
+}
+
+#[test(tokio::test)]
+async fn test_configured_options_replace_the_preset() -> Result<()> {
+    let client = Client::new("cat", &[]).await?;
+    assert_eq!(client.initialization_options(), None);
+
+    client.set_initialization_options(json!({ "staticcheck": true }));
+    assert_eq!(
+        client.initialization_options(),
+        Some(json!({ "staticcheck": true }))
+    );
+
+    Ok(())
+}
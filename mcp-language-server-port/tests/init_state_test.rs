@@ -0,0 +1,33 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use test_log::test;
+use tokio::time::{Duration, timeout};
+
+use mcp_language_server_rust::lsp::{Client, InitState};
+
+#[test(tokio::test)]
+async fn test_tool_calls_wait_for_initialization() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    // `cat` echoes the initialize request back, which is not a valid response
+    let client = Client::new("cat", &[]).await?;
+    assert_eq!(client.init_state(), InitState::Starting);
+
+    let waiter = {
+        let client = client.clone();
+        tokio::spawn(async move { client.wait_for_initialization().await })
+    };
+
+    // The waiter stays blocked while the handshake hasn't finished
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!waiter.is_finished());
+
+    assert!(client.initialize(temp_dir.path()).await.is_err());
+
+    let result = timeout(Duration::from_secs(5), waiter).await??;
+    let error = result.expect_err("initialization should have failed");
+    assert!(error.to_string().contains("failed to initialize"));
+    assert!(matches!(client.init_state(), InitState::Failed(_)));
+
+    Ok(())
+}
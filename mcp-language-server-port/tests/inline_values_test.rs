@@ -0,0 +1,56 @@
+use lsp_types::{
+    InlineValue, InlineValueEvaluatableExpression, InlineValueText, InlineValueVariableLookup,
+    Position, Range,
+};
+
+use mcp_language_server_rust::lsp::PositionEncoding;
+use mcp_language_server_rust::tools::inline_values::describe_inline_value;
+
+const SOURCE: &str = "fn main() {\n    let total = items.len();\n    println!(\"{}\", total);\n}\n";
+
+fn range(line: u32, start: u32, end: u32) -> Range {
+    Range {
+        start: Position {
+            line,
+            character: start,
+        },
+        end: Position {
+            line,
+            character: end,
+        },
+    }
+}
+
+#[test]
+fn test_describe_inline_values() {
+    let lines: Vec<&str> = SOURCE.lines().collect();
+
+    let text = InlineValue::Text(InlineValueText {
+        range: range(1, 8, 13),
+        text: "total = 3".to_string(),
+    });
+    assert_eq!(
+        describe_inline_value(&text, &lines, PositionEncoding::Utf16),
+        "text `total = 3`"
+    );
+
+    // Without a name the variable is read from the source
+    let lookup = InlineValue::VariableLookup(InlineValueVariableLookup {
+        range: range(1, 8, 13),
+        variable_name: None,
+        case_sensitive_lookup: true,
+    });
+    assert_eq!(
+        describe_inline_value(&lookup, &lines, PositionEncoding::Utf16),
+        "variable `total`"
+    );
+
+    let expression = InlineValue::EvaluatableExpression(InlineValueEvaluatableExpression {
+        range: range(1, 16, 27),
+        expression: None,
+    });
+    assert_eq!(
+        describe_inline_value(&expression, &lines, PositionEncoding::Utf16),
+        "expression `items.len()`"
+    );
+}
@@ -0,0 +1,88 @@
+use anyhow::Result;
+use lsp_types::DocumentFilter;
+use std::path::Path;
+use test_log::test;
+
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::lsp::client::select_language_id;
+
+#[test(tokio::test)]
+async fn test_default_language_ids() -> Result<()> {
+    let client = Client::new("cat", &[]).await?;
+
+    assert_eq!(client.language_id(Path::new("src/main.rs")), "rust");
+    assert_eq!(client.language_id(Path::new("App.svelte")), "plaintext");
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_language_id_overrides() -> Result<()> {
+    let client = Client::new("cat", &[]).await?;
+
+    client.set_language_id("svelte", "svelte");
+    client.set_language_id(".h", "cpp");
+
+    assert_eq!(client.language_id(Path::new("App.svelte")), "svelte");
+    assert_eq!(client.language_id(Path::new("include/widget.h")), "cpp");
+    assert_eq!(client.language_id(Path::new("widget.c")), "c");
+
+    Ok(())
+}
+
+fn filter(language: Option<&str>, pattern: Option<&str>) -> DocumentFilter {
+    DocumentFilter {
+        language: language.map(str::to_string),
+        scheme: Some("file".to_string()),
+        pattern: pattern.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_selector_accepts_detected_language() {
+    let selector = vec![filter(Some("rust"), None)];
+
+    assert_eq!(
+        select_language_id(&selector, Path::new("/work/src/lib.rs"), "rust"),
+        Some("rust".to_string())
+    );
+    assert_eq!(
+        select_language_id(&selector, Path::new("/work/main.go"), "go"),
+        None
+    );
+}
+
+#[test]
+fn test_selector_pattern_chooses_language() {
+    let selector = vec![
+        filter(Some("typescript"), None),
+        filter(Some("svelte"), Some("**/*.svelte")),
+    ];
+
+    assert_eq!(
+        select_language_id(&selector, Path::new("/work/App.svelte"), "plaintext"),
+        Some("svelte".to_string())
+    );
+    assert_eq!(
+        select_language_id(&selector, Path::new("/work/index.ts"), "typescript"),
+        Some("typescript".to_string())
+    );
+    assert_eq!(
+        select_language_id(&selector, Path::new("/work/style.css"), "css"),
+        None
+    );
+}
+
+#[test]
+fn test_selector_ignores_other_schemes() {
+    let selector = vec![DocumentFilter {
+        language: Some("rust".to_string()),
+        scheme: Some("untitled".to_string()),
+        pattern: None,
+    }];
+
+    assert_eq!(
+        select_language_id(&selector, Path::new("/work/lib.rs"), "rust"),
+        None
+    );
+}
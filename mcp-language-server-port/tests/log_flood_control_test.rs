@@ -0,0 +1,121 @@
+use std::time::{Duration, Instant};
+
+use mcp_language_server_rust::logging::flood::{Admission, Decision, FloodConfig, FloodControl};
+
+const WATCHER: u64 = 1;
+const RETRY: u64 = 2;
+
+fn written(decision: Decision) -> Admission {
+    Admission {
+        decision,
+        suppressed: 0,
+    }
+}
+
+#[test]
+fn test_identical_records_collapse_into_repeat_counts() {
+    let flood_control = FloodControl::new(FloodConfig {
+        max_per_callsite: 0,
+        ..Default::default()
+    });
+    let now = Instant::now();
+
+    let decisions: Vec<Decision> = (0..20)
+        .map(|_| flood_control.admit(WATCHER, 42, now).decision)
+        .collect();
+
+    // Occurrences 1, 2, 4 and 16 are written
+    for (index, decision) in decisions.iter().enumerate() {
+        let expected = match index + 1 {
+            1 => Decision::Write,
+            occurrence @ (2 | 4 | 16) => Decision::WriteRepeated(occurrence as u64),
+            _ => Decision::Drop,
+        };
+        assert_eq!(*decision, expected, "occurrence {}", index + 1);
+    }
+
+    // A different record starts over
+    assert_eq!(
+        flood_control.admit(WATCHER, 43, now),
+        written(Decision::Write)
+    );
+    assert_eq!(
+        flood_control.admit(WATCHER, 43, now),
+        written(Decision::WriteRepeated(2))
+    );
+}
+
+#[test]
+fn test_callsite_rate_limit_summarizes_dropped_records() {
+    let flood_control = FloodControl::new(FloodConfig {
+        suppress_repeats: true,
+        max_per_callsite: 3,
+        window: Duration::from_secs(1),
+    });
+    let start = Instant::now();
+
+    // Distinct messages from one call site, e.g. one per changed file
+    let mut decisions = Vec::new();
+    for message in 0..10 {
+        decisions.push(flood_control.admit(WATCHER, message, start).decision);
+    }
+    assert_eq!(&decisions[..3], &[Decision::Write; 3]);
+    assert_eq!(&decisions[3..], &[Decision::Drop; 7]);
+
+    // Other call sites have their own limits
+    assert_eq!(
+        flood_control.admit(RETRY, 100, start),
+        written(Decision::Write)
+    );
+
+    // The next window reports what was dropped in the last
+    let later = start + Duration::from_millis(1500);
+    assert_eq!(
+        flood_control.admit(WATCHER, 11, later),
+        Admission {
+            decision: Decision::Write,
+            suppressed: 7,
+        }
+    );
+    assert_eq!(
+        flood_control.admit(WATCHER, 12, later),
+        written(Decision::Write)
+    );
+}
+
+#[test]
+fn test_collapsed_repeats_do_not_count_against_limit() {
+    let flood_control = FloodControl::new(FloodConfig {
+        suppress_repeats: true,
+        max_per_callsite: 2,
+        window: Duration::from_secs(1),
+    });
+    let now = Instant::now();
+
+    for _ in 0..3 {
+        flood_control.admit(WATCHER, 7, now);
+    }
+
+    // Only the first and second occurrences were written
+    assert_eq!(
+        flood_control.admit(WATCHER, 8, now).decision,
+        Decision::Drop
+    );
+}
+
+#[test]
+fn test_disabled_flood_control_writes_everything() {
+    let flood_control = FloodControl::new(FloodConfig {
+        suppress_repeats: false,
+        max_per_callsite: 0,
+        window: Duration::from_secs(1),
+    });
+    let now = Instant::now();
+
+    for _ in 0..100 {
+        assert_eq!(
+            flood_control.admit(WATCHER, 1, now),
+            written(Decision::Write)
+        );
+    }
+}
@@ -106,7 +106,7 @@ async fn test_diagnostics() -> Result<()> {
     sleep(Duration::from_millis(100)).await;
 
     // Get diagnostics using our tool
-    let diagnostics_result = tools::get_diagnostics(&client, file_path.clone(), 2, true).await?;
+    let diagnostics_result = tools::get_diagnostics(&client, file_path.clone(), 2, true, false, Duration::ZERO).await?;
     
     // Check that we got the expected diagnostics
     assert!(diagnostics_result.contains("Test error diagnostic"), 
@@ -171,7 +171,7 @@ async fn test_hover() -> Result<()> {
     // Get hover info using our tool
     let line = 8; // 1-indexed for our tool
     let column = 11; // 1-indexed for our tool
-    let hover_result = tools::get_hover_info(&client, file_path, line, column).await?;
+    let hover_result = tools::get_hover_info(&client, file_path, line, column, None).await?;
     
     // Verify we got some hover information
     assert!(!hover_result.is_empty(), "Hover result should not be empty");
@@ -208,11 +208,13 @@ async fn test_text_edits() -> Result<()> {
         tools::edit::TextEditParams {
             start_line: 2, // 1-indexed
             end_line: 2,   // 1-indexed
+            start_column: None,
+            end_column: None,
             new_text: "    println!(\"Hello, edited world!\");\n".to_string(),
         },
     ];
 
-    let edit_result = tools::apply_text_edits(&client, edit_file_path.clone(), edits).await?;
+    let edit_result = tools::apply_text_edits(&client, edit_file_path.clone(), edits, false).await?;
     
     // Verify the edit was successful
     assert!(edit_result.contains("Successfully applied"), "Edit result should indicate success");
@@ -0,0 +1,55 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use serde_json::json;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+
+/// A language server with an extension request that echoes its params
+fn server() -> String {
+    scripted_server(&[(
+        "fake/echo",
+        r#"
+[[ $body =~ \"params\":(\{[^}]*\}) ]] || continue
+reply "{\"echo\":${BASH_REMATCH[1]}}"
+"#,
+    )])
+}
+
+#[test(tokio::test)]
+async fn test_lsp_request_returns_raw_result() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let workspace = temp_dir.path().canonicalize()?;
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(&workspace).await?;
+
+    let result =
+        tools::send_lsp_request(&client, "fake/echo", Some(json!({ "answer": 42 })), None).await?;
+    let result: serde_json::Value = serde_json::from_str(&result)?;
+    assert_eq!(result, json!({ "echo": { "answer": 42 } }));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_lsp_request_refuses_methods_the_proxy_manages() -> Result<()> {
+    let client = Client::new("cat", &[]).await?;
+
+    for method in ["shutdown", "initialize", "textDocument/didOpen"] {
+        let error = tools::send_lsp_request(&client, method, None, None)
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("managed by the proxy"),
+            "{}",
+            error
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use anyhow::anyhow;
+use std::time::Duration;
+
+use mcp_language_server_rust::lsp::RetryPolicy;
+use mcp_language_server_rust::lsp::protocol::{CONTENT_MODIFIED, ResponseError, SERVER_CANCELLED};
+use mcp_language_server_rust::lsp::retry::is_transient;
+
+fn response_error(code: i32) -> anyhow::Error {
+    anyhow::Error::new(ResponseError {
+        code,
+        message: "request failed".to_string(),
+    })
+}
+
+#[test]
+fn test_transient_error_codes() {
+    assert!(is_transient(&response_error(CONTENT_MODIFIED)));
+    assert!(is_transient(&response_error(SERVER_CANCELLED)));
+
+    // Method not found and internal errors won't go away on their own
+    assert!(!is_transient(&response_error(-32601)));
+    assert!(!is_transient(&response_error(-32603)));
+    assert!(!is_transient(&anyhow!("LSP server is shutting down")));
+}
+
+#[test]
+fn test_transient_error_survives_context() {
+    let error = response_error(CONTENT_MODIFIED).context("Failed to get hover information");
+    assert!(is_transient(&error));
+}
+
+#[test]
+fn test_response_error_message() {
+    assert_eq!(
+        response_error(CONTENT_MODIFIED).to_string(),
+        "LSP error: request failed (code: -32801)"
+    );
+}
+
+#[test]
+fn test_backoff_doubles_up_to_limit() {
+    let policy = RetryPolicy {
+        max_retries: 6,
+        initial_backoff: Duration::from_millis(50),
+        max_backoff: Duration::from_millis(300),
+    };
+
+    assert_eq!(policy.backoff(1), Duration::from_millis(50));
+    assert_eq!(policy.backoff(2), Duration::from_millis(100));
+    assert_eq!(policy.backoff(3), Duration::from_millis(200));
+    assert_eq!(policy.backoff(4), Duration::from_millis(300));
+    assert_eq!(policy.backoff(40), Duration::from_millis(300));
+}
+
+#[test]
+fn test_no_retry_policy() {
+    assert_eq!(RetryPolicy::none().max_retries, 0);
+    assert_eq!(RetryPolicy::default().max_retries, 3);
+}
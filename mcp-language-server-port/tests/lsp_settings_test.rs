@@ -0,0 +1,152 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use serde_json::{Value, json};
+use std::path::Path;
+use test_log::test;
+use tokio::time::{Duration, Instant, sleep};
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::{Client, Settings};
+
+fn settings() -> Settings {
+    serde_json::from_value(json!({
+        "settings": {
+            "gopls": { "staticcheck": true, "buildFlags": [] },
+            "rust-analyzer": { "cargo": { "features": "all" } },
+        },
+        "scopes": {
+            "services": { "gopls": { "buildFlags": ["-tags=services"] } },
+            "services/api": { "gopls": { "buildFlags": ["-tags=api"] } },
+        },
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_sections_resolve_by_dotted_path() {
+    let settings = settings();
+
+    assert_eq!(
+        settings.resolve(Some("rust-analyzer.cargo.features"), None, None),
+        json!("all")
+    );
+    assert_eq!(
+        settings.resolve(Some("gopls"), None, None),
+        json!({ "staticcheck": true, "buildFlags": [] })
+    );
+    assert_eq!(settings.resolve(Some("pyright"), None, None), Value::Null);
+    assert_eq!(
+        settings.resolve(None, None, None)["gopls"]["staticcheck"],
+        json!(true)
+    );
+}
+
+#[test]
+fn test_innermost_scope_overrides_global_settings() {
+    let settings = settings();
+    let root = Path::new("/ws");
+
+    let resolve = |scope: &str| settings.resolve(Some("gopls"), Some(Path::new(scope)), Some(root));
+
+    assert_eq!(
+        resolve("/ws/services/api/main.go"),
+        json!({ "staticcheck": true, "buildFlags": ["-tags=api"] })
+    );
+    assert_eq!(
+        resolve("/ws/services/worker"),
+        json!({ "staticcheck": true, "buildFlags": ["-tags=services"] })
+    );
+    assert_eq!(
+        resolve("/ws/cmd/main.go"),
+        json!({ "staticcheck": true, "buildFlags": [] })
+    );
+}
+
+#[test]
+fn test_set_creates_nested_sections() -> Result<()> {
+    let mut settings = settings();
+    settings.set("rust-analyzer.check.command", json!("clippy"))?;
+    settings.set("gopls.staticcheck", json!(false))?;
+
+    assert_eq!(
+        settings.resolve(Some("rust-analyzer"), None, None),
+        json!({ "cargo": { "features": "all" }, "check": { "command": "clippy" } })
+    );
+    assert_eq!(
+        settings.resolve(Some("gopls.staticcheck"), None, None),
+        json!(false)
+    );
+    assert!(settings.set("gopls..staticcheck", json!(true)).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_load_settings_file() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file = temp_dir.child("settings.json");
+    file.write_str(r#"{ "settings": { "gopls": { "gofumpt": true } } }"#)?;
+
+    let settings = Settings::load(file.path())?;
+    assert_eq!(
+        settings.resolve(Some("gopls.gofumpt"), None, None),
+        json!(true)
+    );
+
+    file.write_str("gopls: true")?;
+    assert!(Settings::load(file.path()).is_err());
+
+    Ok(())
+}
+
+/// A language server that asks for its configuration once the client is
+/// initialized, and writes the client's answer to the file given as its argument
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialized",
+            r#"send '{"jsonrpc":"2.0","id":900,"method":"workspace/configuration","params":{"items":[{"section":"gopls.staticcheck"},{"section":"unknown"}]}}'"#,
+        ),
+        ("", r#"printf '%s' "$body" > "$1.tmp" && mv "$1.tmp" "$1""#),
+    ])
+}
+
+#[test(tokio::test)]
+async fn test_client_answers_configuration_requests() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let answer_path = workspace.join("answer.json");
+
+    let client = Client::new(
+        "bash",
+        &[
+            "-c".to_string(),
+            server(),
+            "server".to_string(),
+            answer_path.display().to_string(),
+        ],
+    )
+    .await?;
+    client.set_settings(settings());
+    client.initialize(&workspace).await?;
+
+    // The server asks through the message loop, and hears back from the settings
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let answer = loop {
+        if let Ok(text) = std::fs::read_to_string(&answer_path) {
+            break serde_json::from_str::<Value>(&text)?;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "The client never answered workspace/configuration"
+        );
+        sleep(Duration::from_millis(20)).await;
+    };
+    assert_eq!(answer["result"], json!([true, null]), "{}", answer);
+
+    client.shutdown().await?;
+    Ok(())
+}
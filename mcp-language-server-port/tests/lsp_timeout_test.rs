@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use serde_json::{Value, json};
+use test_log::test;
+
+use mcp_language_server_rust::lsp::timeout::{DEFAULT_TIMEOUT, RequestTimeout};
+use mcp_language_server_rust::lsp::{Client, TimeoutPolicy};
+
+#[test]
+fn test_method_timeouts_override_the_default() {
+    let policy = TimeoutPolicy::default()
+        .with_method("workspace/symbol", Duration::from_secs(120))
+        .with_method("textDocument/hover", Duration::ZERO);
+
+    assert_eq!(
+        policy.for_method("textDocument/definition"),
+        Some(DEFAULT_TIMEOUT)
+    );
+    assert_eq!(
+        policy.for_method("workspace/symbol"),
+        Some(Duration::from_secs(120))
+    );
+    // A zero timeout waits forever
+    assert_eq!(policy.for_method("textDocument/hover"), None);
+}
+
+#[test(tokio::test)]
+async fn test_unanswered_requests_time_out_and_are_cancelled() -> Result<()> {
+    // A server that records what it receives and never answers
+    let temp_dir = TempDir::new()?;
+    let received = temp_dir.path().join("received");
+    let client = Client::new(
+        "bash",
+        &["-c".to_string(), format!("cat > '{}'", received.display())],
+    )
+    .await?;
+    client.set_timeout_policy(TimeoutPolicy {
+        default: Duration::from_millis(100),
+        ..Default::default()
+    });
+
+    let error = client
+        .call::<_, Value>("workspace/symbol", json!({ "query": "main" }))
+        .await
+        .unwrap_err();
+    let timeout = error
+        .downcast_ref::<RequestTimeout>()
+        .expect("expected a timeout error");
+    assert_eq!(timeout.method, "workspace/symbol");
+    assert_eq!(timeout.timeout, Duration::from_millis(100));
+
+    // The cancellation is written right after the timeout
+    let cancelled = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let received = std::fs::read_to_string(&received).unwrap_or_default();
+            if received.contains("$/cancelRequest") {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await;
+    assert!(cancelled.is_ok(), "no $/cancelRequest was sent");
+
+    Ok(())
+}
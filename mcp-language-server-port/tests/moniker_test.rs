@@ -0,0 +1,28 @@
+use lsp_types::{Moniker, MonikerKind, UniquenessLevel};
+
+use mcp_language_server_rust::tools::moniker::format_moniker;
+
+#[test]
+fn test_format_moniker() {
+    let moniker = Moniker {
+        scheme: "npm".to_string(),
+        identifier: "lodash::debounce".to_string(),
+        unique: UniquenessLevel::Scheme,
+        kind: Some(MonikerKind::Import),
+    };
+    assert_eq!(
+        format_moniker(&moniker),
+        "npm:lodash::debounce (unique: scheme, kind: import)"
+    );
+
+    let local = Moniker {
+        scheme: "tsc".to_string(),
+        identifier: "src/app.ts:main".to_string(),
+        unique: UniquenessLevel::Document,
+        kind: None,
+    };
+    assert_eq!(
+        format_moniker(&local),
+        "tsc:src/app.ts:main (unique: document)"
+    );
+}
@@ -0,0 +1,88 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+
+use mcp_language_server_rust::watcher::gitignore::GitignoreFilter;
+
+fn workspace() -> Result<TempDir> {
+    let temp_dir = TempDir::new()?;
+    temp_dir
+        .child(".gitignore")
+        .write_str("*.log\nignored_dir/\n")?;
+    temp_dir
+        .child("sub/.gitignore")
+        .write_str("/build\n*.tmp\n!keep.log\n")?;
+    temp_dir
+        .child("sub/deeper/.gitignore")
+        .write_str("keep.log\n")?;
+    temp_dir
+        .child(".git/info/exclude")
+        .write_str("secret.txt\n")?;
+    temp_dir
+        .child("ignored_dir/.gitignore")
+        .write_str("!*.log\n")?;
+    Ok(temp_dir)
+}
+
+#[test]
+fn test_nested_patterns_apply_relative_to_their_directory() -> Result<()> {
+    let temp_dir = workspace()?;
+    let filter = GitignoreFilter::new(temp_dir.path().to_path_buf());
+    let ignored = |relative: &str| filter.is_ignored(&temp_dir.path().join(relative));
+
+    assert!(ignored("sub/scratch.tmp"));
+    assert!(!ignored("scratch.tmp"));
+
+    // Anchored patterns only match below their own directory
+    assert!(ignored("sub/build/output.rs"));
+    assert!(!ignored("build/output.rs"));
+
+    Ok(())
+}
+
+#[test]
+fn test_deeper_files_override_shallower_ones() -> Result<()> {
+    let temp_dir = workspace()?;
+    let filter = GitignoreFilter::new(temp_dir.path().to_path_buf());
+    let ignored = |relative: &str| filter.is_ignored(&temp_dir.path().join(relative));
+
+    assert!(ignored("debug.log"));
+    assert!(!ignored("sub/keep.log"));
+    assert!(ignored("sub/deeper/keep.log"));
+
+    // Rules inside an ignored directory are never read
+    assert!(ignored("ignored_dir/output.log"));
+
+    Ok(())
+}
+
+#[test]
+fn test_info_exclude_is_honored() -> Result<()> {
+    let temp_dir = workspace()?;
+    let filter = GitignoreFilter::new(temp_dir.path().to_path_buf());
+
+    assert!(filter.is_ignored(&temp_dir.path().join("secret.txt")));
+    assert!(filter.is_ignored(&temp_dir.path().join("sub/secret.txt")));
+
+    Ok(())
+}
+
+#[test]
+fn test_reload_picks_up_new_gitignore_files() -> Result<()> {
+    let temp_dir = workspace()?;
+    let filter = GitignoreFilter::new(temp_dir.path().to_path_buf());
+    let shared = filter.clone();
+    let generated = temp_dir.path().join("gen/schema.rs");
+    assert!(!filter.is_ignored(&generated));
+
+    temp_dir.child("gen/.gitignore").write_str("*.rs\n")?;
+    assert!(GitignoreFilter::is_rules_file(
+        &temp_dir.path().join("gen/.gitignore")
+    ));
+    filter.reload();
+
+    // Clones share the reloaded rules
+    assert!(shared.is_ignored(&generated));
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use test_log::test;
+
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+
+#[test(tokio::test)]
+async fn test_overlay_marks_file_dirty() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("lib.rs").write_str("pub fn one() {}\n")?;
+    let file_path = temp_dir.path().canonicalize()?.join("lib.rs");
+
+    let client = Client::new("cat", &[]).await?;
+    client.open_file(&file_path).await?;
+    assert!(!client.is_file_dirty(&file_path));
+
+    // Proposed content the server sees but the disk doesn't
+    client
+        .set_overlay(&file_path, "pub fn two() {}\n".to_string())
+        .await?;
+    assert!(client.is_file_dirty(&file_path));
+
+    let documents = client.open_documents();
+    assert_eq!(documents.len(), 1);
+    assert_eq!(documents[0].version, 2);
+    assert!(documents[0].dirty);
+
+    let listing = tools::list_open_documents(&client).await?;
+    assert!(listing.contains("lib.rs (version 2, dirty"));
+
+    // Going back to disk content clears the flag
+    client.discard_overlay(&file_path).await?;
+    assert!(!client.is_file_dirty(&file_path));
+    assert_eq!(client.open_documents()[0].version, 3);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_no_open_documents() -> Result<()> {
+    let client = Client::new("cat", &[]).await?;
+
+    let listing = tools::list_open_documents(&client).await?;
+    assert!(listing.contains("No documents are open"));
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+use mcp_language_server_rust::tools::scope::ActionScope;
+use mcp_language_server_rust::tools::workspace_edit::ApplyOptions;
+
+/// A language server whose organize imports action swaps the first two lines
+/// of the file it's asked about
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"codeActionProvider":{"codeActionKinds":["quickfix","source.organizeImports"]}}}'"#,
+        ),
+        (
+            "textDocument/codeAction",
+            r#"
+[[ $body =~ \"uri\":\"([^\"]+)\" ]]
+uri=${BASH_REMATCH[1]}
+reply "[{\"title\":\"Organize imports\",\"kind\":\"source.organizeImports\",\"edit\":{\"changes\":{\"$uri\":[{\"range\":{\"start\":{\"line\":0,\"character\":0},\"end\":{\"line\":2,\"character\":0}},\"newText\":\"import a\\nimport b\\n\"}]}}}]"
+"#,
+        ),
+    ])
+}
+
+#[test(tokio::test)]
+async fn test_organize_imports_applies_action() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir
+        .child("main.py")
+        .write_str("import b\nimport a\n\nprint(a, b)\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let file_path = workspace.join("main.py");
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(&workspace).await?;
+
+    // A dry run leaves the file alone
+    let preview = tools::organize_imports(
+        &client,
+        file_path.clone(),
+        &ApplyOptions {
+            dry_run: true,
+            show_diff: false,
+        },
+    )
+    .await?;
+    assert!(preview.contains("+import a"), "{}", preview);
+    assert_eq!(
+        std::fs::read_to_string(&file_path)?,
+        "import b\nimport a\n\nprint(a, b)\n"
+    );
+
+    let result =
+        tools::organize_imports(&client, file_path.clone(), &ApplyOptions::default()).await?;
+    assert!(result.starts_with("Organized imports in"), "{}", result);
+    assert_eq!(
+        std::fs::read_to_string(&file_path)?,
+        "import a\nimport b\n\nprint(a, b)\n"
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_organize_imports_in_directory() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("a.py").write_str("import b\nimport a\n")?;
+    temp_dir.child("b.py").write_str("import b\nimport a\n")?;
+    temp_dir
+        .child("README.md")
+        .write_str("import b\nimport a\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(&workspace).await?;
+
+    // Every Python file next to the given one is organized, nothing else
+    let result = tools::organize_imports_in(
+        &client,
+        workspace.join("a.py"),
+        ActionScope::Directory,
+        &ApplyOptions::default(),
+    )
+    .await?;
+    assert!(
+        result.starts_with("Organized imports in 2 of 2 files"),
+        "{}",
+        result
+    );
+    for file in ["a.py", "b.py"] {
+        assert_eq!(
+            std::fs::read_to_string(workspace.join(file))?,
+            "import a\nimport b\n"
+        );
+    }
+    assert_eq!(
+        std::fs::read_to_string(workspace.join("README.md"))?,
+        "import b\nimport a\n"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use lsp_types::Position;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::position::{
+    byte_column, char_column, column, offset_at, position_at,
+};
+use mcp_language_server_rust::lsp::{Client, PositionEncoding};
+
+use PositionEncoding::{Utf8, Utf16};
+
+/// A language server that counts columns in bytes
+fn server() -> String {
+    scripted_server(&[(
+        "initialize",
+        r#"reply '{"capabilities":{"positionEncoding":"utf-8"}}'"#,
+    )])
+}
+
+fn position(line: u32, character: u32) -> Position {
+    Position { line, character }
+}
+
+#[test]
+fn test_columns_are_utf16_code_units() {
+    // é takes two bytes and one UTF-16 unit, the emoji four bytes and two units
+    let line = "aé😀b";
+    assert_eq!(byte_column(line, 0, Utf16), 0);
+    assert_eq!(byte_column(line, 1, Utf16), 1);
+    assert_eq!(byte_column(line, 2, Utf16), 3);
+    assert_eq!(byte_column(line, 4, Utf16), 7);
+    assert_eq!(byte_column(line, 5, Utf16), 8);
+
+    assert_eq!(column(line, 3, Utf16), 2);
+    assert_eq!(column(line, 7, Utf16), 4);
+    assert_eq!(column(line, line.len(), Utf16), 5);
+
+    assert_eq!(char_column(line, 4, Utf16), 3);
+}
+
+#[test]
+fn test_columns_are_clamped() {
+    let line = "a😀";
+    // Inside the surrogate pair and past the end of the line
+    assert_eq!(byte_column(line, 2, Utf16), 1);
+    assert_eq!(byte_column(line, 10, Utf16), line.len());
+}
+
+#[test]
+fn test_offsets_of_positions() {
+    let content = "fn é() {}\r\nlet x = \"😀\";\n";
+    assert_eq!(offset_at(content, position(0, 3), Utf16), Some(3));
+    assert_eq!(offset_at(content, position(0, 4), Utf16), Some(5));
+    // Columns past the end stop before the line break
+    assert_eq!(offset_at(content, position(0, 100), Utf16), Some(10));
+    assert_eq!(offset_at(content, position(1, 11), Utf16), Some(12 + 13));
+    // The empty line after the final line break exists, the one after it doesn't
+    assert_eq!(
+        offset_at(content, position(2, 0), Utf16),
+        Some(content.len())
+    );
+    assert_eq!(offset_at(content, position(3, 0), Utf16), None);
+}
+
+#[test]
+fn test_positions_of_offsets() {
+    let content = "fn é() {}\nlet x = \"😀\";\n";
+    assert_eq!(position_at(content, 5, Utf16), position(0, 4));
+    assert_eq!(
+        position_at(content, content.find(';').unwrap(), Utf16),
+        position(1, 12)
+    );
+    assert_eq!(position_at(content, content.len(), Utf16), position(2, 0));
+
+    for offset in [0, 3, 5, 12, 20, 24, 25] {
+        assert_eq!(
+            offset_at(content, position_at(content, offset, Utf16), Utf16),
+            Some(offset)
+        );
+    }
+}
+
+#[test]
+fn test_utf8_columns_count_bytes() {
+    let line = "aé😀b";
+    assert_eq!(byte_column(line, 3, Utf8), 3);
+    // Inside a character
+    assert_eq!(byte_column(line, 5, Utf8), 3);
+    assert_eq!(column(line, 7, Utf8), 7);
+    assert_eq!(char_column(line, 7, Utf8), 3);
+
+    let content = "fn é() {}\nlet x = \"😀\";\n";
+    assert_eq!(offset_at(content, position(1, 13), Utf8), Some(11 + 13));
+    assert_eq!(position_at(content, 11 + 13, Utf8), position(1, 13));
+}
+
+#[test(tokio::test)]
+async fn test_position_encoding_is_negotiated() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    // Servers that don't choose an encoding use UTF-16
+    let client = Client::new("cat", &[]).await?;
+    assert_eq!(client.position_encoding(), Utf16);
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(temp_dir.path()).await?;
+    assert_eq!(client.position_encoding(), Utf8);
+
+    Ok(())
+}
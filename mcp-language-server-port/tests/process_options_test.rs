@@ -0,0 +1,35 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use std::time::Duration;
+use test_log::test;
+
+use mcp_language_server_rust::lsp::{Client, ProcessOptions};
+
+#[test(tokio::test)]
+async fn test_server_gets_environment_and_working_directory() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let options = ProcessOptions {
+        env: vec![("GREETING".to_string(), "hello".to_string())],
+        cwd: Some(workspace.clone()),
+    };
+
+    // The server records what it was started with, then idles
+    let script = r#"printf '%s %s' "$GREETING" "$PWD" > started.txt; exec cat"#;
+    let _client =
+        Client::with_options("bash", &["-c".to_string(), script.to_string()], &options).await?;
+
+    let started = workspace.join("started.txt");
+    for _ in 0..50 {
+        if started.is_file() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(
+        std::fs::read_to_string(&started)?,
+        format!("hello {}", workspace.display())
+    );
+
+    Ok(())
+}
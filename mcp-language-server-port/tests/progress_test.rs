@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use serde_json::{Value, json};
+use test_log::test;
+
+use mcp_language_server_rust::lsp::{Client, ProxyEvent};
+use mcp_language_server_rust::mcp::progress::ProgressForwarder;
+
+fn progress(title: &str, percentage: Option<u32>, done: bool) -> ProxyEvent {
+    ProxyEvent::Progress {
+        token: "rustAnalyzer/Indexing".to_string(),
+        title: title.to_string(),
+        message: None,
+        percentage,
+        done,
+    }
+}
+
+fn notify(forwarder: &mut ProgressForwarder, event: &ProxyEvent) -> Option<Value> {
+    let notification = forwarder.notification(event)?;
+    let value = serde_json::to_value(notification).unwrap();
+    Some(json!({
+        "progressToken": value["progressToken"],
+        "progress": value["progress"],
+        "total": value.get("total").cloned().unwrap_or(Value::Null),
+    }))
+}
+
+#[test]
+fn test_progress_is_forwarded_under_the_call_token() {
+    let mut forwarder = ProgressForwarder::new("references-7");
+
+    assert_eq!(
+        notify(&mut forwarder, &progress("Indexing", Some(40), false)),
+        Some(json!({ "progressToken": "references-7", "progress": 40, "total": 100 }))
+    );
+    // Progress never moves back, even if the server's percentage does
+    assert_eq!(
+        notify(&mut forwarder, &progress("Indexing", Some(10), false)),
+        Some(json!({ "progressToken": "references-7", "progress": 40, "total": 100 }))
+    );
+    // Reports without a percentage still count up
+    assert_eq!(
+        notify(&mut forwarder, &progress("Indexing", None, true)),
+        Some(json!({ "progressToken": "references-7", "progress": 41, "total": null }))
+    );
+
+    assert_eq!(notify(&mut forwarder, &ProxyEvent::WatcherStopped), None);
+}
+
+#[test(tokio::test)]
+async fn test_server_progress_is_emitted_as_events() -> Result<()> {
+    let frame = |value: Value| {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "method": "$/progress",
+            "params": { "token": "indexing", "value": value },
+        })
+        .to_string();
+        format!(
+            "printf 'Content-Length: {}\\r\\n\\r\\n%s' '{}'",
+            body.len(),
+            body
+        )
+    };
+    let script = format!(
+        "sleep 0.2; {}; {}; {}; cat",
+        frame(json!({ "kind": "begin", "title": "Indexing" })),
+        frame(json!({ "kind": "report", "message": "3/10 crates", "percentage": 30 })),
+        frame(json!({ "kind": "end" })),
+    );
+    let client = Client::new("bash", &["-c".to_string(), script]).await?;
+    let mut events = client.subscribe_events();
+
+    let mut received = Vec::new();
+    while received.len() < 3 {
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv()).await??;
+        received.push(event);
+    }
+
+    assert_eq!(
+        received,
+        vec![
+            ProxyEvent::Progress {
+                token: "indexing".to_string(),
+                title: "Indexing".to_string(),
+                message: None,
+                percentage: None,
+                done: false,
+            },
+            ProxyEvent::Progress {
+                token: "indexing".to_string(),
+                title: "Indexing".to_string(),
+                message: Some("3/10 crates".to_string()),
+                percentage: Some(30),
+                done: false,
+            },
+            ProxyEvent::Progress {
+                token: "indexing".to_string(),
+                title: "Indexing".to_string(),
+                message: None,
+                percentage: None,
+                done: true,
+            },
+        ]
+    );
+
+    Ok(())
+}
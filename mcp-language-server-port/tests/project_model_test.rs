@@ -0,0 +1,70 @@
+use serde_json::json;
+
+use mcp_language_server_rust::tools::project_model::{
+    DependencyList, GoPackages, Runnable, format_crate, format_go_packages, format_runnables,
+};
+
+#[test]
+fn test_parse_dependency_list() {
+    let dependencies: DependencyList = serde_json::from_value(json!({
+        "crates": [
+            { "name": "serde", "version": "1.0.197", "path": "file:///cargo/registry/serde-1.0.197" },
+            { "name": null, "version": null, "path": "file:///ws/crates/app" },
+        ]
+    }))
+    .unwrap();
+
+    assert_eq!(format_crate(&dependencies.crates[0]), "serde 1.0.197");
+    assert_eq!(format_crate(&dependencies.crates[1]), "(unnamed)");
+}
+
+#[test]
+fn test_format_runnables() {
+    let runnables: Vec<Runnable> = serde_json::from_value(json!([
+        {
+            "label": "test parser::tests::parses_empty",
+            "kind": "cargo",
+            "args": {
+                "workspaceRoot": "/ws",
+                "cargoArgs": ["test", "--package", "app", "--lib"],
+                "executableArgs": ["parser::tests::parses_empty", "--exact"],
+            }
+        },
+        {
+            "label": "run app",
+            "kind": "cargo",
+            "args": { "cargoArgs": ["run", "--bin", "app"] }
+        }
+    ]))
+    .unwrap();
+
+    assert_eq!(
+        format_runnables(&runnables),
+        "  test parser::tests::parses_empty: cargo test --package app --lib -- parser::tests::parses_empty --exact\n  run app: cargo run --bin app\n"
+    );
+    assert_eq!(format_runnables(&[]), "  none\n");
+}
+
+#[test]
+fn test_format_go_packages() {
+    let packages: GoPackages = serde_json::from_value(json!({
+        "Module": {
+            "example.com/app": {
+                "Path": "example.com/app",
+                "Version": null,
+                "GoMod": "file:///ws/go.mod",
+            }
+        },
+        "Packages": [
+            { "Path": "example.com/app/server", "ModulePath": "example.com/app" },
+            { "Path": "example.com/app", "ModulePath": "example.com/app" },
+            { "Path": "example.com/app/server", "ModulePath": "example.com/app", "ForTest": "example.com/app/server" },
+        ]
+    }))
+    .unwrap();
+
+    assert_eq!(
+        format_go_packages(&packages, |uri| uri.path().to_string()),
+        "Go modules (1):\n  example.com/app (/ws/go.mod)\n    example.com/app\n    example.com/app/server\n"
+    );
+}
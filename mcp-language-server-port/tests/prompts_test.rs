@@ -0,0 +1,34 @@
+use anyhow::Result;
+use serde_json::json;
+
+use mcp_language_server_rust::mcp::prompts::{get_prompt, list_prompts};
+
+#[test]
+fn test_prompts_compose_tool_calls() -> Result<()> {
+    let names: Vec<String> = list_prompts().into_iter().map(|p| p.name).collect();
+    assert_eq!(
+        names,
+        vec!["investigate_diagnostic", "safe_rename_plan", "explore_symbol"]
+    );
+
+    let arguments = json!({
+        "file_path": "src/main.rs",
+        "line": 3,
+        "column": "8",
+        "new_name": "settings",
+    });
+    let prompt = get_prompt("safe_rename_plan", arguments.as_object())?;
+    let text = serde_json::to_string(&prompt.messages)?;
+    assert!(text.contains("src/main.rs:3:8"), "{}", text);
+    assert!(text.contains("`settings`"), "{}", text);
+    assert!(!text.contains("{new_name}"), "{}", text);
+
+    // Optional arguments can be left out, required ones can't
+    let arguments = json!({ "file_path": "src/lib.rs" });
+    let prompt = get_prompt("investigate_diagnostic", arguments.as_object())?;
+    let text = serde_json::to_string(&prompt.messages)?;
+    assert!(!text.contains("{message_filter}"), "{}", text);
+    assert!(get_prompt("explore_symbol", None).is_err());
+    assert!(get_prompt("unknown", None).is_err());
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range, Url};
+use test_log::test;
+
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools::diagnostics::format_workspace_diagnostic;
+
+fn diagnostic(
+    severity: Option<DiagnosticSeverity>,
+    source: Option<&str>,
+    message: &str,
+) -> Diagnostic {
+    Diagnostic {
+        range: Range {
+            start: Position {
+                line: 11,
+                character: 4,
+            },
+            end: Position {
+                line: 11,
+                character: 9,
+            },
+        },
+        severity,
+        source: source.map(str::to_string),
+        message: message.to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_format_workspace_diagnostic() {
+    assert_eq!(
+        format_workspace_diagnostic(&diagnostic(
+            Some(DiagnosticSeverity::ERROR),
+            Some("rustc"),
+            "mismatched types\nexpected `u32`, found `&str`"
+        )),
+        "  12:5: Error [rustc]: mismatched types"
+    );
+    assert_eq!(
+        format_workspace_diagnostic(&diagnostic(None, None, "unused import")),
+        "  12:5: Unknown: unused import"
+    );
+}
+
+#[test(tokio::test)]
+async fn test_servers_without_pull_support_are_not_asked() -> Result<()> {
+    // cat never announces capabilities, so nothing is requested from it
+    let client = Client::new("cat", &[]).await?;
+    let uri = Url::parse("file:///ws/src/main.rs")?;
+
+    assert_eq!(client.pull_diagnostics_support(), None);
+    client.pull_diagnostics(&uri).await?;
+    assert!(client.get_diagnostics(&uri).is_empty());
+    assert_eq!(client.pull_workspace_diagnostics().await?, None);
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use rmcp::model::{CallToolResult, Content};
+use serde_json::json;
+use test_log::test;
+
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::mcp::annotate_pending_work;
+
+/// Frames a `$/progress` notification as a shell command that prints it
+fn print_progress(value: serde_json::Value) -> String {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "method": "$/progress",
+        "params": { "token": "indexing", "value": value },
+    })
+    .to_string();
+    format!(
+        "printf 'Content-Length: {}\\r\\n\\r\\n%s' '{}'",
+        body.len(),
+        body
+    )
+}
+
+#[test(tokio::test)]
+async fn test_work_done_progress_is_tracked_until_it_ends() -> Result<()> {
+    // A "server" that indexes for a moment and then echoes like cat
+    let script = format!(
+        "{}; sleep 0.5; {}; cat",
+        print_progress(json!({ "kind": "begin", "title": "Indexing" })),
+        print_progress(json!({ "kind": "end" })),
+    );
+    let client = Client::new("bash", &["-c".to_string(), script]).await?;
+
+    let started = tokio::time::timeout(Duration::from_secs(5), async {
+        while client.pending_work().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await;
+    assert!(started.is_ok(), "indexing progress was never reported");
+    assert_eq!(client.pending_work(), vec!["Indexing"]);
+
+    assert!(client.wait_for_work_done(Duration::from_secs(5)).await);
+    assert!(client.pending_work().is_empty());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_wait_times_out_while_work_is_in_flight() -> Result<()> {
+    let script = format!(
+        "{}; cat",
+        print_progress(json!({ "kind": "begin", "title": "Indexing" }))
+    );
+    let client = Client::new("bash", &["-c".to_string(), script]).await?;
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while client.pending_work().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await?;
+    assert!(!client.wait_for_work_done(Duration::from_millis(50)).await);
+
+    Ok(())
+}
+
+#[test]
+fn test_results_are_annotated_while_the_server_is_busy() {
+    let mut result = CallToolResult::success(vec![Content::text("No references found")]);
+    annotate_pending_work(&mut result, &[]);
+    assert_eq!(result.content.len(), 1);
+
+    annotate_pending_work(&mut result, &["Indexing".to_string()]);
+    assert_eq!(
+        result.content[1].as_text().map(|text| text.text.as_str()),
+        Some("Note: the language server is still busy (Indexing); results may be incomplete")
+    );
+}
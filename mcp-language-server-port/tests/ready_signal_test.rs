@@ -0,0 +1,131 @@
+use anyhow::Result;
+use lsp_types::{InitializeResult, ServerInfo};
+use serde_json::{Value, json};
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::watch;
+
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::mcp::McpLanguageServer;
+use mcp_language_server_rust::mcp::ready::{READY_PREFIX, ReadySignal};
+
+#[test]
+fn test_ready_signal_uses_server_info() {
+    let result = InitializeResult {
+        capabilities: Default::default(),
+        server_info: Some(ServerInfo {
+            name: "rust-analyzer".to_string(),
+            version: Some("0.3.2000".to_string()),
+        }),
+    };
+    let signal = ReadySignal::new(
+        "stdio",
+        Path::new("/work/project"),
+        "rust-analyzer",
+        &result,
+        vec!["definition".to_string(), "hover".to_string()],
+    );
+
+    assert_eq!(
+        signal.to_json(),
+        json!({
+            "event": "ready",
+            "transport": "stdio",
+            "workspace": "/work/project",
+            "server_name": "rust-analyzer",
+            "server_version": "0.3.2000",
+            "tools": ["definition", "hover"],
+        })
+    );
+}
+
+#[test]
+fn test_ready_signal_falls_back_to_command_name() {
+    let result = InitializeResult {
+        capabilities: Default::default(),
+        server_info: None,
+    };
+    let signal = ReadySignal::new("stdio", Path::new("/work"), "gopls", &result, Vec::new());
+
+    assert_eq!(signal.server_name, "gopls");
+    assert_eq!(signal.server_version, None);
+}
+
+#[test]
+fn test_ready_signal_stderr_line() {
+    let result = InitializeResult {
+        capabilities: Default::default(),
+        server_info: None,
+    };
+    let signal = ReadySignal::new("stdio", Path::new("/work"), "clangd", &result, Vec::new());
+    let line = signal.stderr_line();
+
+    // One line: the prefix, then JSON scripts can parse
+    assert!(!line.contains('\n'));
+    let json = line
+        .strip_prefix(READY_PREFIX)
+        .expect("ready line starts with the prefix")
+        .trim();
+    let value: serde_json::Value = serde_json::from_str(json).unwrap();
+    assert_eq!(value["event"], "ready");
+    assert_eq!(value["server_name"], "clangd");
+}
+
+#[tokio::test]
+async fn test_ready_signal_is_sent_to_each_client() -> Result<()> {
+    let client = Client::new("cat", &[]).await?;
+    let (ready_tx, ready_rx) = watch::channel(None);
+    let handler = McpLanguageServer::new(client, Path::new("/work").to_path_buf())
+        .with_ready_signal(ready_rx);
+
+    // Any transport works: the handler announces readiness, not the stdio setup
+    let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(async move {
+        if let Ok(server) = rmcp::serve_server(handler, tokio::io::split(server_io)).await {
+            let _ = server.waiting().await;
+        }
+    });
+
+    let (reader, mut writer) = tokio::io::split(client_io);
+    for message in [
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": { "name": "test", "version": "1.0" },
+            },
+        }),
+        json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+    ] {
+        writer
+            .write_all(format!("{}\n", message).as_bytes())
+            .await?;
+    }
+
+    let result = InitializeResult {
+        capabilities: Default::default(),
+        server_info: None,
+    };
+    let signal = ReadySignal::new("sse", Path::new("/work"), "clangd", &result, Vec::new());
+    ready_tx.send(Some(signal.clone()))?;
+
+    let mut lines = BufReader::new(reader).lines();
+    let notification = tokio::time::timeout(Duration::from_secs(5), async {
+        while let Some(line) = lines.next_line().await? {
+            let message: Value = serde_json::from_str(&line)?;
+            if message["method"] == "notifications/message" {
+                return Ok(message);
+            }
+        }
+        Err(anyhow::anyhow!("The server closed the connection"))
+    })
+    .await??;
+
+    assert_eq!(notification["params"]["data"], signal.to_json());
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use serde_json::{Value, json};
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+use mcp_language_server_rust::tools::references::{ReferencePage, ReferenceScope};
+use mcp_language_server_rust::tools::render::{OutputFormat, with_format};
+use mcp_language_server_rust::tools::utils::{context_window, to_uri};
+
+/// A language server finding `total` referenced on the fourth line of the
+/// file given as its first argument, and defined on the first
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"referencesProvider":true,"definitionProvider":true}}'"#,
+        ),
+        (
+            "textDocument/references",
+            r#"reply "[{\"uri\":\"$1\",\"range\":{\"start\":{\"line\":3,\"character\":4},\"end\":{\"line\":3,\"character\":9}}}]""#,
+        ),
+        (
+            "textDocument/definition",
+            r#"reply "{\"uri\":\"$1\",\"range\":{\"start\":{\"line\":0,\"character\":0},\"end\":{\"line\":0,\"character\":9}}}""#,
+        ),
+    ])
+}
+
+const SOURCE: &str = "total = 0\nfor n in items:\n    if n:\n    total += n\nprint(done)\nexit()\n";
+
+async fn start(temp_dir: &TempDir) -> Result<(Client, String)> {
+    temp_dir.child("main.py").write_str(SOURCE)?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let file = workspace.join("main.py");
+
+    let uri = to_uri(&file).to_string();
+    let client = Client::new(
+        "bash",
+        &["-c".to_string(), server(), "server".to_string(), uri],
+    )
+    .await?;
+    client.initialize(&workspace).await?;
+    Ok((client, file.display().to_string()))
+}
+
+#[test(tokio::test)]
+async fn test_references_show_numbered_context() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (client, file) = start(&temp_dir).await?;
+    let location = format!("{}:1:1", file);
+
+    let result = tools::find_references(
+        &client,
+        &location,
+        ReferenceScope::Workspace,
+        1,
+        &ReferencePage::default(),
+    )
+    .await?;
+    assert!(
+        result.contains(
+            "    3 |     if n:\n    4 |     total += n\n      |     ^\n    5 | print(done)\n"
+        ),
+        "{}",
+        result
+    );
+
+    // Without context only the line itself is shown
+    let result = tools::find_references(
+        &client,
+        &location,
+        ReferenceScope::Workspace,
+        0,
+        &ReferencePage::default(),
+    )
+    .await?;
+    assert!(result.contains("  Line 4:     total += n\n"), "{}", result);
+    assert!(!result.contains("print(done)"), "{}", result);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_definition_shows_numbered_context() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (client, file) = start(&temp_dir).await?;
+
+    let result = tools::find_definition(&client, &format!("{}:4:5", file), 2).await?;
+    assert!(
+        result.contains("    1 | total = 0\n    2 | for n in items:\n    3 |     if n:\n"),
+        "{}",
+        result
+    );
+    assert!(!result.contains("total += n"), "{}", result);
+
+    Ok(())
+}
+
+#[test]
+fn test_context_window_is_clamped_to_the_file() {
+    assert_eq!(context_window(10, 1, 1, 3), 0..=4);
+    assert_eq!(context_window(10, 8, 9, 3), 5..=9);
+    assert_eq!(context_window(10, 4, 4, 0), 4..=4);
+}
+
+#[test(tokio::test)]
+async fn test_json_references_report_the_page() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let (client, file) = start(&temp_dir).await?;
+    let location = format!("{}:1:1", file);
+    let find = |page: ReferencePage| {
+        let (client, location) = (&client, &location);
+        with_format(Some(OutputFormat::Json), async move {
+            tools::find_references(client, location, ReferenceScope::Workspace, 0, &page).await
+        })
+    };
+
+    let result: Value = serde_json::from_str(&find(ReferencePage::default()).await?)?;
+    assert_eq!(result["references"][0]["line"], 4, "{}", result);
+    assert_eq!(result["total"], 1);
+    assert_eq!(result["capped"], 0);
+    assert_eq!(result["next_offset"], Value::Null);
+
+    // An empty page points at the reference it left out
+    let page = ReferencePage {
+        max_results: Some(0),
+        ..Default::default()
+    };
+    let result: Value = serde_json::from_str(&find(page).await?)?;
+    assert_eq!(result["references"], json!([]), "{}", result);
+    assert_eq!(result["total"], 1);
+    assert_eq!(result["next_offset"], 0);
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use lsp_types::{
+    Location, Position, Range, SymbolInformation, SymbolKind, Url, WorkspaceSymbolResponse,
+};
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::{Client, PositionEncoding};
+use mcp_language_server_rust::tools::references::{
+    name_position, symbol_locations, workspace_symbols,
+};
+
+/// A language server that streams one workspace symbol as a partial result
+/// before answering with another
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"workspaceSymbolProvider":true}}'"#,
+        ),
+        (
+            "workspace/symbol",
+            r#"
+symbol() {
+    printf '{"name":"%s","kind":12,"location":{"uri":"file:///ws/src/lib.rs","range":{"start":{"line":%d,"character":0},"end":{"line":%d,"character":1}}}}' "$1" "$2" "$2"
+}
+[[ $body =~ \"partialResultToken\":(\"[^\"]*\") ]] || continue
+send "{\"jsonrpc\":\"2.0\",\"method\":\"\$/progress\",\"params\":{\"token\":${BASH_REMATCH[1]},\"value\":[$(symbol streamed 1)]}}"
+reply "[$(symbol answered 5)]"
+"#,
+        ),
+    ])
+}
+
+fn range(start_line: u32, start_char: u32, end_line: u32, end_char: u32) -> Range {
+    Range {
+        start: Position {
+            line: start_line,
+            character: start_char,
+        },
+        end: Position {
+            line: end_line,
+            character: end_char,
+        },
+    }
+}
+
+#[allow(deprecated)]
+fn symbol(name: &str, container: Option<&str>, line: u32) -> SymbolInformation {
+    SymbolInformation {
+        name: name.to_string(),
+        kind: SymbolKind::FUNCTION,
+        tags: None,
+        deprecated: None,
+        location: Location {
+            uri: Url::parse("file:///ws/src/config.rs").unwrap(),
+            range: range(line, 0, line + 3, 1),
+        },
+        container_name: container.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_symbol_locations_match_bare_and_qualified_names() {
+    let symbols = WorkspaceSymbolResponse::Flat(vec![
+        symbol("load", Some("Config"), 10),
+        symbol("load", Some("crate::cache::Cache"), 40),
+        symbol("load_all", Some("Config"), 20),
+    ]);
+
+    assert_eq!(symbol_locations(&symbols, "load").len(), 2);
+
+    let qualified = symbol_locations(&symbols, "Config::load");
+    assert_eq!(qualified.len(), 1);
+    assert_eq!(qualified[0].range.start.line, 10);
+
+    // A qualifier only has to match the end of the container
+    assert_eq!(symbol_locations(&symbols, "Cache::load").len(), 1);
+    assert!(symbol_locations(&symbols, "Other::load").is_empty());
+}
+
+#[test]
+fn test_name_position_skips_keywords_and_partial_matches() {
+    let content = "/// Loads the config\npub fn load_all() {}\npub fn load() -> Config {\n}\n";
+
+    // The symbol range starts at the doc comment
+    let position = name_position(content, &range(2, 0, 3, 1), "load", PositionEncoding::Utf16);
+    assert_eq!(
+        position,
+        Position {
+            line: 2,
+            character: 7
+        }
+    );
+
+    let position = name_position(content, &range(0, 0, 3, 1), "load", PositionEncoding::Utf16);
+    assert_eq!(position.line, 2);
+
+    // Names that don't appear leave the range start
+    let position = name_position(
+        content,
+        &range(1, 0, 1, 20),
+        "missing",
+        PositionEncoding::Utf16,
+    );
+    assert_eq!(position, Position::new(1, 0));
+}
+
+#[test(tokio::test)]
+async fn test_workspace_symbols_collect_partial_results() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(&temp_dir.path().canonicalize()?).await?;
+
+    let Some(WorkspaceSymbolResponse::Flat(symbols)) = workspace_symbols(&client, "any").await?
+    else {
+        panic!("expected a flat symbol list");
+    };
+    let names: Vec<&str> = symbols.iter().map(|symbol| symbol.name.as_str()).collect();
+    assert_eq!(names, vec!["streamed", "answered"]);
+
+    Ok(())
+}
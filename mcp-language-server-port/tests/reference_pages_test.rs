@@ -0,0 +1,76 @@
+use lsp_types::{Location, Position, Range, Url};
+
+use mcp_language_server_rust::tools::references::ReferencePage;
+use mcp_language_server_rust::tools::utils::format_count;
+
+fn location(file: &str, line: u32) -> (Location, Vec<String>) {
+    let uri = Url::parse(&format!("file:///workspace/{}", file)).unwrap();
+    let position = Position::new(line, 0);
+    (
+        Location::new(uri, Range::new(position, position)),
+        Vec::new(),
+    )
+}
+
+fn lines(locations: &[(Location, Vec<String>)]) -> Vec<(String, u32)> {
+    locations
+        .iter()
+        .map(|(location, _)| {
+            let path = location.uri.path().trim_start_matches("/workspace/");
+            (path.to_string(), location.range.start.line)
+        })
+        .collect()
+}
+
+#[test]
+fn test_references_are_sorted_and_paged() {
+    let locations = vec![
+        location("b.rs", 3),
+        location("a.rs", 9),
+        location("b.rs", 1),
+        location("a.rs", 2),
+    ];
+
+    let (all, capped) = ReferencePage::default().apply(locations.clone());
+    assert_eq!(capped, 0);
+    assert_eq!(
+        lines(&all),
+        vec![
+            ("a.rs".to_string(), 2),
+            ("a.rs".to_string(), 9),
+            ("b.rs".to_string(), 1),
+            ("b.rs".to_string(), 3),
+        ]
+    );
+
+    let page = ReferencePage {
+        offset: 1,
+        max_results: Some(2),
+        max_per_file: None,
+    };
+    let (second, _) = page.apply(locations.clone());
+    assert_eq!(
+        lines(&second),
+        vec![("a.rs".to_string(), 9), ("b.rs".to_string(), 1)]
+    );
+
+    // The per-file cap keeps the first references of each file
+    let page = ReferencePage {
+        max_per_file: Some(1),
+        ..Default::default()
+    };
+    let (capped_page, capped) = page.apply(locations);
+    assert_eq!(capped, 2);
+    assert_eq!(
+        lines(&capped_page),
+        vec![("a.rs".to_string(), 2), ("b.rs".to_string(), 1)]
+    );
+}
+
+#[test]
+fn test_counts_have_thousands_separators() {
+    assert_eq!(format_count(0), "0");
+    assert_eq!(format_count(999), "999");
+    assert_eq!(format_count(1243), "1,243");
+    assert_eq!(format_count(1_000_000), "1,000,000");
+}
@@ -0,0 +1,97 @@
+use lsp_types::{DocumentSymbol, DocumentSymbolResponse, Position, Range, SymbolKind};
+
+use mcp_language_server_rust::tools::references::{ReferenceScope, enclosing_symbol_range};
+
+fn range(start_line: u32, end_line: u32) -> Range {
+    Range {
+        start: Position {
+            line: start_line,
+            character: 0,
+        },
+        end: Position {
+            line: end_line,
+            character: 1,
+        },
+    }
+}
+
+#[allow(deprecated)]
+fn symbol(
+    name: &str,
+    kind: SymbolKind,
+    range: Range,
+    children: Vec<DocumentSymbol>,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name: name.to_string(),
+        detail: None,
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range: range,
+        children: if children.is_empty() {
+            None
+        } else {
+            Some(children)
+        },
+    }
+}
+
+/// A module holding an impl block with two methods
+fn outline() -> DocumentSymbolResponse {
+    DocumentSymbolResponse::Nested(vec![symbol(
+        "shapes",
+        SymbolKind::MODULE,
+        range(0, 30),
+        vec![symbol(
+            "Circle",
+            SymbolKind::OBJECT,
+            range(2, 20),
+            vec![
+                symbol("area", SymbolKind::METHOD, range(3, 8), vec![]),
+                symbol("scale", SymbolKind::METHOD, range(10, 18), vec![]),
+            ],
+        )],
+    )])
+}
+
+fn position(line: u32) -> Position {
+    Position { line, character: 4 }
+}
+
+#[test]
+fn test_innermost_function_encloses_position() {
+    let kinds = [SymbolKind::FUNCTION, SymbolKind::METHOD];
+
+    assert_eq!(
+        enclosing_symbol_range(&outline(), position(12), &kinds),
+        Some(range(10, 18))
+    );
+    // Between the two methods there is no enclosing function
+    assert_eq!(
+        enclosing_symbol_range(&outline(), position(9), &kinds),
+        None
+    );
+}
+
+#[test]
+fn test_module_scope_skips_inner_symbols() {
+    assert_eq!(
+        enclosing_symbol_range(&outline(), position(5), &[SymbolKind::MODULE]),
+        Some(range(0, 30))
+    );
+}
+
+#[test]
+fn test_parse_scope() {
+    assert_eq!(
+        ReferenceScope::parse("function").unwrap(),
+        ReferenceScope::Function
+    );
+    assert_eq!(
+        ReferenceScope::parse("workspace").unwrap(),
+        ReferenceScope::Workspace
+    );
+    assert!(ReferenceScope::parse("crate").is_err());
+}
@@ -13,6 +13,7 @@ use crate::common::{create_test_file, complex_rust_file};
 use crate::mock_lsp_server::MockLspServer;
 use mcp_language_server_rust::lsp::Client;
 use mcp_language_server_rust::tools;
+use mcp_language_server_rust::tools::references::{ReferencePage, ReferenceScope};
 
 /// Setup test environment for references tests
 async fn setup_test_env() -> Result<(TempDir, MockLspServer, Arc<Client>, String)> {
@@ -52,7 +53,7 @@ async fn test_find_references() -> Result<()> {
     // This is handled automatically by our improved mock server
     
     // Use the task function to find references
-    let result = tools::find_references(&client, &symbol_location).await?;
+    let result = tools::find_references(&client, &symbol_location, ReferenceScope::Workspace, 0, &ReferencePage::default()).await?;
     
     // Verify the result
     assert!(result.contains("Found"), "Result should contain 'Found'");
@@ -75,7 +76,7 @@ async fn test_find_references_method() -> Result<()> {
     let symbol_location = format!("{}:28:16", file_path); // line 28, column 16 (add_attribute method)
     
     // Use the task function to find references
-    let result = tools::find_references(&client, &symbol_location).await?;
+    let result = tools::find_references(&client, &symbol_location, ReferenceScope::Workspace, 0, &ReferencePage::default()).await?;
     
     // Verify the result
     assert!(result.contains("Found"), "Result should contain 'Found'");
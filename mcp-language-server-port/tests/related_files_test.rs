@@ -0,0 +1,62 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use std::path::PathBuf;
+
+use mcp_language_server_rust::tools::related_files::{
+    counterpart_candidates, is_test_file, source_file_candidates, test_file_candidates,
+};
+
+#[test]
+fn test_recognizes_test_files() {
+    assert!(is_test_file(&PathBuf::from("/ws/pkg/server_test.go")));
+    assert!(is_test_file(&PathBuf::from("/ws/app/test_models.py")));
+    assert!(is_test_file(&PathBuf::from("/ws/web/button.spec.ts")));
+    assert!(is_test_file(&PathBuf::from("/ws/tests/budget_test.rs")));
+    assert!(is_test_file(&PathBuf::from("/ws/src/ParserTest.java")));
+
+    assert!(!is_test_file(&PathBuf::from("/ws/pkg/server.go")));
+    assert!(!is_test_file(&PathBuf::from("/ws/src/Test.java")));
+}
+
+#[test]
+fn test_test_file_candidates_follow_language_conventions() {
+    let go = test_file_candidates(&PathBuf::from("/ws/pkg/server.go"), None);
+    assert_eq!(go[0], PathBuf::from("/ws/pkg/server_test.go"));
+
+    let python = test_file_candidates(&PathBuf::from("/ws/app/models.py"), None);
+    assert_eq!(python[0], PathBuf::from("/ws/app/test_models.py"));
+
+    // Rust integration tests live under the workspace tests folder
+    let rust = test_file_candidates(
+        &PathBuf::from("/ws/src/tools/budget.rs"),
+        Some(&PathBuf::from("/ws")),
+    );
+    assert!(rust.contains(&PathBuf::from("/ws/tests/budget_test.rs")));
+    assert!(!rust.contains(&PathBuf::from("/ws/src/tools/budget.rs")));
+}
+
+#[test]
+fn test_source_candidates_for_test_file() {
+    let candidates = source_file_candidates(&PathBuf::from("/ws/pkg/server_test.go"));
+    assert_eq!(candidates[0], PathBuf::from("/ws/pkg/server.go"));
+
+    let candidates = source_file_candidates(&PathBuf::from("/ws/web/button.test.tsx"));
+    assert_eq!(candidates[0], PathBuf::from("/ws/web/button.tsx"));
+}
+
+#[test]
+fn test_counterparts_across_include_and_src() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("include/parser.h").touch()?;
+    temp_dir.child("src/parser.cpp").touch()?;
+
+    let candidates = counterpart_candidates(&temp_dir.path().join("src/parser.cpp"));
+    let existing: Vec<PathBuf> = candidates.into_iter().filter(|p| p.is_file()).collect();
+    assert_eq!(existing, vec![temp_dir.path().join("include/parser.h")]);
+
+    // Languages without headers have no counterparts
+    assert!(counterpart_candidates(&PathBuf::from("/ws/main.go")).is_empty());
+
+    Ok(())
+}
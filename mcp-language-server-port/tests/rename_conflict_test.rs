@@ -0,0 +1,110 @@
+use lsp_types::{
+    DocumentSymbol, DocumentSymbolResponse, Location, Position, Range, SymbolInformation,
+    SymbolKind, Url,
+};
+
+use mcp_language_server_rust::tools::rename::find_rename_conflicts;
+
+fn range(start_line: u32, end_line: u32) -> Range {
+    Range {
+        start: Position {
+            line: start_line,
+            character: 0,
+        },
+        end: Position {
+            line: end_line,
+            character: 10,
+        },
+    }
+}
+
+#[allow(deprecated)]
+fn symbol(
+    name: &str,
+    kind: SymbolKind,
+    range: Range,
+    children: Vec<DocumentSymbol>,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name: name.to_string(),
+        detail: None,
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range: range,
+        children: if children.is_empty() {
+            None
+        } else {
+            Some(children)
+        },
+    }
+}
+
+/// Outline of a file with two functions, each declaring a local variable
+fn nested_symbols() -> DocumentSymbolResponse {
+    DocumentSymbolResponse::Nested(vec![
+        symbol(
+            "first",
+            SymbolKind::FUNCTION,
+            range(0, 10),
+            vec![symbol("total", SymbolKind::VARIABLE, range(2, 2), vec![])],
+        ),
+        symbol(
+            "second",
+            SymbolKind::FUNCTION,
+            range(12, 20),
+            vec![symbol("count", SymbolKind::VARIABLE, range(14, 14), vec![])],
+        ),
+    ])
+}
+
+#[test]
+fn test_conflict_in_same_scope() {
+    // Renaming something inside `first` to `total` shadows the existing local
+    let conflicts = find_rename_conflicts(&nested_symbols(), "total", &[range(5, 5)]);
+
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].name, "total");
+    assert_eq!(conflicts[0].kind, SymbolKind::VARIABLE);
+}
+
+#[test]
+fn test_no_conflict_in_other_scope() {
+    // `total` only lives in `first`, so renaming inside `second` is safe
+    let conflicts = find_rename_conflicts(&nested_symbols(), "total", &[range(16, 16)]);
+
+    assert!(conflicts.is_empty());
+}
+
+#[test]
+fn test_top_level_conflict() {
+    // Top-level symbols are visible to every edit in the file
+    let conflicts = find_rename_conflicts(&nested_symbols(), "second", &[range(5, 5)]);
+
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].kind, SymbolKind::FUNCTION);
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_flat_symbols_use_file_scope() {
+    let uri = Url::parse("file:///tmp/sample.rs").unwrap();
+    let symbols = DocumentSymbolResponse::Flat(vec![SymbolInformation {
+        name: "helper".to_string(),
+        kind: SymbolKind::FUNCTION,
+        tags: None,
+        deprecated: None,
+        location: Location {
+            uri,
+            range: range(30, 32),
+        },
+        container_name: None,
+    }]);
+
+    assert_eq!(
+        find_rename_conflicts(&symbols, "helper", &[range(1, 1)]).len(),
+        1
+    );
+    assert!(find_rename_conflicts(&symbols, "other", &[range(1, 1)]).is_empty());
+}
@@ -15,6 +15,7 @@ use crate::common::{create_test_file, read_file_content, complex_rust_file};
 use crate::mock_lsp_server::MockLspServer;
 use mcp_language_server_rust::lsp::Client;
 use mcp_language_server_rust::tools;
+use mcp_language_server_rust::tools::workspace_edit::ApplyOptions;
 
 /// Setup test environment for rename tests
 async fn setup_test_env() -> Result<(TempDir, MockLspServer, Arc<Client>, PathBuf)> {
@@ -52,7 +53,7 @@ async fn test_rename_symbol() -> Result<()> {
     let new_name = "fullName"; // New name for the field
     
     // Use the task function to rename the symbol
-    let result = tools::rename_symbol(&client, file_path.clone(), line - 1, column - 1, new_name.to_string()).await?;
+    let result = tools::rename_symbol(&client, file_path.clone(), line - 1, column - 1, new_name.to_string(), false, &ApplyOptions::default()).await?;
     
     // Verify the result
     assert!(result.contains("Applied"), "Result should contain 'Applied'");
@@ -81,7 +82,7 @@ async fn test_rename_method() -> Result<()> {
     let new_name = "setAttribute"; // New name for the method
     
     // Use the task function to rename the symbol
-    let result = tools::rename_symbol(&client, file_path.clone(), line - 1, column - 1, new_name.to_string()).await?;
+    let result = tools::rename_symbol(&client, file_path.clone(), line - 1, column - 1, new_name.to_string(), false, &ApplyOptions::default()).await?;
     
     // Verify the result
     assert!(result.contains("Applied"), "Result should contain 'Applied'");
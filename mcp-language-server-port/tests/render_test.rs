@@ -0,0 +1,55 @@
+use anyhow::Result;
+use test_log::test;
+
+use mcp_language_server_rust::tools::budget::{Block, fit_to};
+use mcp_language_server_rust::tools::render::{OutputStyle, current_style, with_style};
+use mcp_language_server_rust::tools::utils::format_code;
+
+fn blocks() -> Vec<Block> {
+    let mut first = Block::new("File: src/main.rs\n");
+    first.context.push("  Line 3: run();\n     ^\n".to_string());
+    first.summary = Some("src/main.rs:3:5: run();\n".to_string());
+
+    let mut second = Block::new("\nDefinition found in src/lib.rs:10:1\n\n");
+    second.snippet = Some(format!("{}\n", format_code("pub fn run() {}", "rust")));
+
+    vec![first, second]
+}
+
+#[test(tokio::test)]
+async fn test_markdown_is_the_default() {
+    assert_eq!(current_style(), OutputStyle::Markdown);
+    assert_eq!(format_code("x", "rust"), "```rust\nx\n```");
+}
+
+#[test(tokio::test)]
+async fn test_plain_drops_code_fences() {
+    let code = with_style(Some(OutputStyle::Plain), async {
+        format_code("pub fn run() {}", "rust")
+    })
+    .await;
+    assert_eq!(code, "pub fn run() {}");
+}
+
+#[test(tokio::test)]
+async fn test_compact_renders_one_line_per_item() {
+    let result = with_style(Some(OutputStyle::Compact), async {
+        fit_to("Results:\n\n", blocks(), "\n---\n", 10_000)
+    })
+    .await;
+
+    assert_eq!(
+        result,
+        "Results:\nsrc/main.rs:3:5: run();\nDefinition found in src/lib.rs:10:1\n"
+    );
+
+    // The style only applies inside the scope
+    assert_eq!(current_style(), OutputStyle::Markdown);
+}
+
+#[test]
+fn test_parse_style() -> Result<()> {
+    assert_eq!(OutputStyle::parse("compact")?, OutputStyle::Compact);
+    assert!(OutputStyle::parse("html").is_err());
+    Ok(())
+}
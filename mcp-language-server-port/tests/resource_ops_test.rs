@@ -0,0 +1,182 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use lsp_types::{
+    CreateFile, CreateFileOptions, DeleteFile, DocumentChangeOperation, DocumentChanges, OneOf,
+    OptionalVersionedTextDocumentIdentifier, Position, Range, RenameFile, ResourceOp,
+    TextDocumentEdit, TextEdit, Url, WorkspaceEdit,
+};
+use std::path::Path;
+use test_log::test;
+
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools::workspace_edit::{self, ApplyOptions, EditReport};
+
+async fn apply_workspace_edit(client: &Client, edit: WorkspaceEdit) -> Result<EditReport> {
+    workspace_edit::apply(client, edit, &ApplyOptions::default()).await
+}
+
+fn uri(path: &Path) -> Url {
+    Url::from_file_path(path).unwrap()
+}
+
+fn insert(path: &Path, text: &str) -> DocumentChangeOperation {
+    DocumentChangeOperation::Edit(TextDocumentEdit {
+        text_document: OptionalVersionedTextDocumentIdentifier {
+            uri: uri(path),
+            version: None,
+        },
+        edits: vec![OneOf::Left(TextEdit {
+            range: Range::new(Position::new(0, 0), Position::new(0, 0)),
+            new_text: text.to_string(),
+        })],
+    })
+}
+
+fn create(path: &Path, options: Option<CreateFileOptions>) -> DocumentChangeOperation {
+    DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+        uri: uri(path),
+        options,
+        annotation_id: None,
+    }))
+}
+
+fn rename(from: &Path, to: &Path) -> DocumentChangeOperation {
+    DocumentChangeOperation::Op(ResourceOp::Rename(RenameFile {
+        old_uri: uri(from),
+        new_uri: uri(to),
+        options: None,
+        annotation_id: None,
+    }))
+}
+
+fn delete(path: &Path) -> DocumentChangeOperation {
+    DocumentChangeOperation::Op(ResourceOp::Delete(DeleteFile {
+        uri: uri(path),
+        options: None,
+    }))
+}
+
+fn operations(operations: Vec<DocumentChangeOperation>) -> WorkspaceEdit {
+    WorkspaceEdit {
+        document_changes: Some(DocumentChanges::Operations(operations)),
+        ..Default::default()
+    }
+}
+
+#[test(tokio::test)]
+async fn test_file_operations_apply_in_order() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("old.rs").write_str("fn old() {}\n")?;
+    temp_dir.child("gone.rs").write_str("fn gone() {}\n")?;
+    let root = temp_dir.path().canonicalize()?;
+    let client = Client::new("cat", &[]).await?;
+    client.open_file(&root.join("old.rs")).await?;
+
+    let edit = operations(vec![
+        // A new module, filled in after it is created
+        create(&root.join("module/mod.rs"), None),
+        insert(&root.join("module/mod.rs"), "pub mod old;\n"),
+        // Edits follow a file to its new name
+        rename(&root.join("old.rs"), &root.join("module/old.rs")),
+        insert(&root.join("module/old.rs"), "// moved\n"),
+        delete(&root.join("gone.rs")),
+        create(
+            &root.join("module/mod.rs"),
+            Some(CreateFileOptions {
+                overwrite: None,
+                ignore_if_exists: Some(true),
+            }),
+        ),
+    ]);
+    let result = apply_workspace_edit(&client, edit).await?.to_string();
+
+    assert!(result.starts_with("Applied 2 edits across 2 files\n"));
+    assert!(result.contains("Renamed "));
+    assert!(result.ends_with("(skipped, nothing to do)"));
+    temp_dir.child("module/mod.rs").assert("pub mod old;\n");
+    temp_dir
+        .child("module/old.rs")
+        .assert("// moved\nfn old() {}\n");
+    assert!(!root.join("old.rs").exists());
+    assert!(!root.join("gone.rs").exists());
+
+    // The open document moved along
+    assert!(!client.is_file_open(&root.join("old.rs")));
+    assert!(client.is_file_open(&root.join("module/old.rs")));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_file_operations_respect_existing_files() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("a.rs").write_str("fn a() {}\n")?;
+    temp_dir.child("b.rs").write_str("fn b() {}\n")?;
+    let root = temp_dir.path().canonicalize()?;
+    let client = Client::new("cat", &[]).await?;
+
+    let error = apply_workspace_edit(&client, operations(vec![create(&root.join("a.rs"), None)]))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("already exists"));
+
+    let error = apply_workspace_edit(
+        &client,
+        operations(vec![rename(&root.join("a.rs"), &root.join("b.rs"))]),
+    )
+    .await
+    .unwrap_err();
+    assert!(error.to_string().contains("target already exists"));
+    temp_dir.child("a.rs").assert("fn a() {}\n");
+    temp_dir.child("b.rs").assert("fn b() {}\n");
+
+    // Overwriting empties the file
+    let overwrite = CreateFileOptions {
+        overwrite: Some(true),
+        ignore_if_exists: Some(true),
+    };
+    apply_workspace_edit(
+        &client,
+        operations(vec![create(&root.join("a.rs"), Some(overwrite))]),
+    )
+    .await?;
+    temp_dir.child("a.rs").assert("");
+
+    let error = apply_workspace_edit(&client, operations(vec![delete(&root.join("c.rs"))]))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("does not exist"));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_preview_follows_file_operations() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("old.rs").write_str("fn old() {}\n")?;
+    let root = temp_dir.path().canonicalize()?;
+    let client = Client::new("cat", &[]).await?;
+
+    let edit = operations(vec![
+        rename(&root.join("old.rs"), &root.join("new.rs")),
+        insert(&root.join("new.rs"), "// moved\n"),
+    ]);
+    let result = workspace_edit::apply(
+        &client,
+        edit,
+        &ApplyOptions {
+            dry_run: true,
+            ..Default::default()
+        },
+    )
+    .await?
+    .to_string();
+
+    assert!(result.contains("Renamed "));
+    assert!(result.contains("+// moved\n fn old() {}\n"));
+    temp_dir.child("old.rs").assert("fn old() {}\n");
+    assert!(!root.join("new.rs").exists());
+
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use lsp_types::{Diagnostic, DiagnosticSeverity, FileChangeType, FileEvent};
+use std::path::Path;
+
+use mcp_language_server_rust::lsp::ProxyEvent;
+use mcp_language_server_rust::mcp::resources::{
+    diagnostics_path, diagnostics_uri, format_diagnostics,
+};
+use mcp_language_server_rust::mcp::subscriptions::{ResourceNotification, ResourceSubscriptions};
+use mcp_language_server_rust::tools::utils::to_uri;
+
+#[test]
+fn test_diagnostics_updates_only_for_subscribed_files() {
+    let main = Path::new("/workspace/src/main.rs");
+    let lib = Path::new("/workspace/src/lib.rs");
+    let uri = diagnostics_uri(main);
+    assert_eq!(uri, "diagnostics:///workspace/src/main.rs");
+    assert_eq!(diagnostics_path(&uri).as_deref(), Some(main));
+    assert_eq!(diagnostics_path("file:///workspace/src/main.rs"), None);
+
+    let subscriptions = ResourceSubscriptions::new();
+    subscriptions.subscribe(uri.clone());
+    let published = |path: &Path| ProxyEvent::DiagnosticsUpdated {
+        uri: to_uri(path),
+        count: 1,
+    };
+
+    let notifications = subscriptions.notifications(&published(main));
+    assert!(
+        matches!(&notifications[..], [ResourceNotification::Updated(params)] if params.uri == uri),
+        "{:?}",
+        notifications
+    );
+    assert!(subscriptions.notifications(&published(lib)).is_empty());
+
+    subscriptions.unsubscribe(&uri);
+    assert!(subscriptions.notifications(&published(main)).is_empty());
+}
+
+#[test]
+fn test_file_changes_update_subscribed_files_and_list() {
+    let main = to_uri(Path::new("/workspace/src/main.rs"));
+    let lib = to_uri(Path::new("/workspace/src/lib.rs"));
+    let subscriptions = ResourceSubscriptions::new();
+    subscriptions.subscribe(main.to_string());
+
+    // Changed files only concern their subscribers
+    let changed = ProxyEvent::FilesChanged {
+        changes: vec![
+            FileEvent::new(main.clone(), FileChangeType::CHANGED),
+            FileEvent::new(lib.clone(), FileChangeType::CHANGED),
+        ],
+    };
+    let notifications = subscriptions.notifications(&changed);
+    assert!(
+        matches!(&notifications[..], [ResourceNotification::Updated(params)] if params.uri == main.to_string()),
+        "{:?}",
+        notifications
+    );
+
+    // New and deleted files change the list
+    let created = ProxyEvent::FilesChanged {
+        changes: vec![FileEvent::new(lib, FileChangeType::CREATED)],
+    };
+    let notifications = subscriptions.notifications(&created);
+    assert!(
+        matches!(&notifications[..], [ResourceNotification::ListChanged]),
+        "{:?}",
+        notifications
+    );
+}
+
+#[test]
+fn test_diagnostics_resource_counts_severities() {
+    let diagnostic = |severity, message: &str| Diagnostic {
+        severity: Some(severity),
+        message: message.to_string(),
+        ..Default::default()
+    };
+    let text = format_diagnostics(&[
+        diagnostic(DiagnosticSeverity::ERROR, "mismatched types"),
+        diagnostic(DiagnosticSeverity::WARNING, "unused variable"),
+        diagnostic(DiagnosticSeverity::HINT, "consider borrowing"),
+    ]);
+
+    assert!(
+        text.starts_with("1 errors, 1 warnings, 1 other\n"),
+        "{}",
+        text
+    );
+    assert!(text.contains("1:1: Error: mismatched types"), "{}", text);
+}
@@ -0,0 +1,51 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+
+use mcp_language_server_rust::mcp::resources::{
+    file_resource, read_file_resource, workspace_files,
+};
+use mcp_language_server_rust::tools::utils::to_uri;
+use mcp_language_server_rust::watcher::gitignore::GitignoreFilter;
+
+#[tokio::test]
+async fn test_workspace_files_served_as_resources() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child(".gitignore").write_str("build/\n")?;
+    temp_dir.child("src/main.rs").write_str("fn main() {}\n")?;
+    temp_dir.child("build/out.txt").write_str("generated\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let folders = vec![workspace.clone()];
+    let filter = GitignoreFilter::new(workspace.clone());
+    let is_ignored = |path: &std::path::Path| filter.is_ignored(path);
+
+    // Ignored directories are left out of the listing
+    let files = workspace_files(&folders, is_ignored);
+    assert_eq!(
+        files,
+        vec![workspace.join(".gitignore"), workspace.join("src/main.rs")]
+    );
+
+    let resource = file_resource(&files[1], &folders);
+    assert_eq!(resource.name, "src/main.rs");
+    assert_eq!(resource.uri, to_uri(&files[1]).to_string());
+
+    let (_, text) = read_file_resource(&resource.uri, &folders, is_ignored).await?;
+    assert_eq!(text, "fn main() {}\n");
+
+    // Ignored files and files outside the workspace are refused
+    let ignored = to_uri(&workspace.join("build/out.txt")).to_string();
+    assert!(
+        read_file_resource(&ignored, &folders, is_ignored)
+            .await
+            .is_err()
+    );
+    let outside = to_uri(&std::env::temp_dir()).to_string();
+    assert!(
+        read_file_resource(&outside, &folders, is_ignored)
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}
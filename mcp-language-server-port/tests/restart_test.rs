@@ -0,0 +1,66 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::{Client, InitState, ProxyEvent};
+use mcp_language_server_rust::tools;
+
+/// A minimal language server that answers initialize and shutdown and
+/// ignores everything else
+fn server() -> String {
+    scripted_server(&[])
+}
+
+#[test(tokio::test)]
+async fn test_restart_reopens_documents() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let file_path = workspace.join("main.rs");
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(&workspace).await?;
+    client.open_file(&file_path).await?;
+    let mut events = client.subscribe_events();
+
+    let result = tools::restart_language_server(&client).await?;
+
+    assert_eq!(
+        result,
+        "Restarted bash.\n\nReopened 1 documents:\n  main.rs\n"
+    );
+    assert_eq!(client.init_state(), InitState::Ready);
+    assert!(client.is_file_open(&file_path));
+
+    let mut received = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        received.push(event);
+    }
+    assert!(received.contains(&ProxyEvent::ServerRestarted {
+        server: "bash".to_string(),
+        reopened: 1,
+    }));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_restart_recovers_a_server_that_failed_to_initialize() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let workspace = temp_dir.path().canonicalize()?;
+
+    // cat echoes the initialize request back, which is not a valid answer
+    let client = Client::new("bash", &["-c".to_string(), "cat".to_string()]).await?;
+    assert!(client.initialize(&workspace).await.is_err());
+    assert!(matches!(client.init_state(), InitState::Failed(_)));
+
+    // Restarting repeats the handshake with a fresh process of the same command
+    assert!(client.relaunch().await.is_err());
+    assert!(matches!(client.init_state(), InitState::Failed(_)));
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use std::sync::Arc;
+use test_log::test;
+
+use mcp_language_server_rust::lsp::{Client, Router};
+use mcp_language_server_rust::mcp::roots::WorkspaceRoots;
+use mcp_language_server_rust::tools::utils::to_uri;
+
+#[test(tokio::test)]
+async fn test_first_root_becomes_the_workspace_and_others_folders() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("app").create_dir_all()?;
+    temp_dir.child("lib").create_dir_all()?;
+    let app = temp_dir.path().canonicalize()?.join("app");
+    let lib = temp_dir.path().canonicalize()?.join("lib");
+
+    let client = Client::new("cat", &[]).await?;
+    let router = Router::new(Arc::clone(&client));
+    let roots = WorkspaceRoots::new(None);
+    assert_eq!(roots.workspace(), None);
+
+    let uris = vec![to_uri(&app).to_string(), to_uri(&lib).to_string()];
+    roots.update(0, &uris, &router).await;
+    assert_eq!(roots.wait_for_workspace().await?, app);
+    assert_eq!(client.workspace_folders(), vec![lib.clone()]);
+
+    // A folder stays while another client still lists it
+    roots.update(1, &uris[1..], &router).await;
+    roots.update(0, &uris[..1], &router).await;
+    assert_eq!(client.workspace_folders(), vec![lib.clone()]);
+    roots.update(1, &[], &router).await;
+    assert!(client.workspace_folders().is_empty());
+
+    // The workspace stays once known, even if the roots change
+    roots.update(0, &uris[1..], &router).await;
+    assert_eq!(roots.workspace(), Some(app));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_roots_are_added_to_a_given_workspace() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("lib").create_dir_all()?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let lib = workspace.join("lib");
+
+    let client = Client::new("cat", &[]).await?;
+    let router = Router::new(Arc::clone(&client));
+    let roots = WorkspaceRoots::new(Some(workspace.clone()));
+
+    let uris = vec![
+        to_uri(&workspace).to_string(),
+        to_uri(&lib).to_string(),
+        "https://example.com/repo".to_string(),
+    ];
+    roots.update(0, &uris, &router).await;
+    assert_eq!(roots.workspace(), Some(workspace));
+    assert_eq!(client.workspace_folders(), vec![lib]);
+
+    Ok(())
+}
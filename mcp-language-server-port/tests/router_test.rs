@@ -0,0 +1,127 @@
+mod common;
+
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::{Client, LanguageFilter, ProcessOptions, Router};
+
+/// A minimal language server that answers initialize and shutdown and
+/// ignores everything else
+fn server() -> String {
+    scripted_server(&[])
+}
+
+#[test]
+fn test_language_filter_matches_extensions_and_language_ids() {
+    let filter = LanguageFilter::parse("go, .mod");
+    assert!(filter.matches(Path::new("main.go"), "go"));
+    assert!(filter.matches(Path::new("go.mod"), "plaintext"));
+    assert!(filter.matches(Path::new("main.gotmpl"), "go"));
+    assert!(!filter.matches(Path::new("main.rs"), "rust"));
+
+    assert!(!LanguageFilter::default().matches(Path::new("main.rs"), "rust"));
+    assert!(
+        LanguageFilter::for_command("/usr/bin/rust-analyzer").matches(Path::new("lib.rs"), "rust")
+    );
+    assert_eq!(
+        LanguageFilter::for_command("my-server"),
+        LanguageFilter::default()
+    );
+}
+
+#[test(tokio::test)]
+async fn test_router_picks_the_server_for_each_file() -> Result<()> {
+    let rust = Client::new("cat", &[]).await?;
+    let go = Client::new("cat", &[]).await?;
+    let router = Router::new(Arc::clone(&rust))
+        .with_primary_filter(LanguageFilter::for_command("rust-analyzer"))
+        .with_server(LanguageFilter::for_command("gopls"), Arc::clone(&go));
+
+    assert!(Arc::ptr_eq(
+        &router.client_for(Path::new("src/lib.rs")).await?,
+        &rust
+    ));
+    assert!(Arc::ptr_eq(
+        &router.client_for(Path::new("cmd/main.go")).await?,
+        &go
+    ));
+    assert!(Arc::ptr_eq(
+        &router.client_for(Path::new("go.mod")).await?,
+        &go
+    ));
+    // Files no server claims go to the primary one
+    assert!(Arc::ptr_eq(
+        &router.client_for(Path::new("README.md")).await?,
+        &rust
+    ));
+
+    assert!(Arc::ptr_eq(router.primary(), &rust));
+    assert_eq!(router.clients().len(), 2);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_language_id_overrides_route_files() -> Result<()> {
+    let primary = Client::new("cat", &[]).await?;
+    let svelte = Client::new("cat", &[]).await?;
+    // Overrides are set up on every server alike, so the primary's are used
+    primary.set_language_id("svx", "svelte");
+    let router = Router::new(Arc::clone(&primary))
+        .with_server(LanguageFilter::parse("svelte"), Arc::clone(&svelte));
+
+    assert!(Arc::ptr_eq(
+        &router.client_for(Path::new("App.svelte")).await?,
+        &svelte
+    ));
+    assert!(Arc::ptr_eq(
+        &router.client_for(Path::new("page.svx")).await?,
+        &svelte
+    ));
+    assert!(Arc::ptr_eq(
+        &router.client_for(Path::new("main.ts")).await?,
+        &primary
+    ));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_lazy_server_starts_on_first_use_and_stops_when_idle() -> Result<()> {
+    let temp_dir = assert_fs::TempDir::new()?;
+    let primary = Client::new("cat", &[]).await?;
+    let router = Router::new(Arc::clone(&primary))
+        .with_launcher(Some(temp_dir.path().to_path_buf()), |_| {})
+        .with_lazy_server(
+            LanguageFilter::parse("go"),
+            "bash",
+            &["-c".to_string(), server()],
+            &ProcessOptions::default(),
+        );
+
+    // Nothing is started for files of other languages
+    router.client_for(Path::new("main.rs")).await?;
+    assert_eq!(router.clients().len(), 1);
+
+    let go = router.client_for(Path::new("main.go")).await?;
+    assert_eq!(router.clients().len(), 2);
+    assert!(Arc::ptr_eq(
+        &router.client_for(Path::new("util.go")).await?,
+        &go
+    ));
+
+    router.stop_idle_servers(Duration::from_millis(100));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(router.clients().len(), 1);
+
+    // The next call starts it again
+    let restarted = router.client_for(Path::new("main.go")).await?;
+    assert!(!Arc::ptr_eq(&restarted, &go));
+    assert_eq!(router.clients().len(), 2);
+
+    Ok(())
+}
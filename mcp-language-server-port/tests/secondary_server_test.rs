@@ -0,0 +1,27 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use test_log::test;
+
+use mcp_language_server_rust::lsp::Client;
+
+#[test(tokio::test)]
+async fn test_attached_server_mirrors_documents() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.py").write_str("print('hi')\n")?;
+    let file_path = temp_dir.path().canonicalize()?.join("main.py");
+
+    let primary = Client::new("cat", &[]).await?;
+    let linter = Client::new("cat", &[]).await?;
+    primary.attach(linter.clone());
+    assert_eq!(primary.attached_servers().len(), 1);
+
+    primary.open_file(&file_path).await?;
+    assert!(primary.is_file_open(&file_path));
+    assert!(linter.is_file_open(&file_path));
+
+    primary.close_file(&file_path).await?;
+    assert!(!linter.is_file_open(&file_path));
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+
+/// A language server answering with `a + b`, the statement holding it (twice,
+/// as servers do for nodes of the same extent) and the function around it
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"selectionRangeProvider":true}}'"#,
+        ),
+        (
+            "textDocument/selectionRange",
+            r#"
+function='{"range":{"start":{"line":0,"character":0},"end":{"line":2,"character":1}}}'
+statement="{\"range\":{\"start\":{\"line\":1,\"character\":4},\"end\":{\"line\":1,\"character\":16}},\"parent\":$function}"
+statement="{\"range\":{\"start\":{\"line\":1,\"character\":4},\"end\":{\"line\":1,\"character\":16}},\"parent\":$statement}"
+reply "[{\"range\":{\"start\":{\"line\":1,\"character\":11},\"end\":{\"line\":1,\"character\":16}},\"parent\":$statement}]"
+"#,
+        ),
+    ])
+}
+
+#[test(tokio::test)]
+async fn test_selection_ranges_list_enclosing_chain() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir
+        .child("main.go")
+        .write_str("func add(a, b int) int {\n    return a + b\n}\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(&workspace).await?;
+
+    let result = tools::get_selection_ranges(&client, workspace.join("main.go"), 2, 14).await?;
+
+    assert!(
+        result.starts_with("3 enclosing ranges at main.go:2:14"),
+        "{}",
+        result
+    );
+    assert!(result.contains("1. L2:12-L2:17 `a + b`"), "{}", result);
+    assert!(
+        result.contains("2. L2:5-L2:17 `return a + b`"),
+        "{}",
+        result
+    );
+    assert!(
+        result.contains("3. L1:1-L3:2 (3 lines) `func add(a, b int) int {` ... `}`"),
+        "{}",
+        result
+    );
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+use mcp_language_server_rust::tools::semantic_tokens::decode_semantic_tokens;
+
+/// A language server tokenizing `fn main(mut x: u8)` on the second line
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"semanticTokensProvider":{"legend":{"tokenTypes":["function","parameter"],"tokenModifiers":["declaration","mutable"]},"full":true}}}'"#,
+        ),
+        (
+            "textDocument/semanticTokens/full",
+            r#"reply '{"data":[1,3,4,0,1,0,9,1,1,3]}'"#,
+        ),
+    ])
+}
+
+#[test]
+fn test_decode_semantic_tokens() {
+    let types = vec!["function".to_string()];
+    let modifiers = vec!["declaration".to_string()];
+
+    // The second token is on the same line, relative to the first's start
+    let tokens = decode_semantic_tokens(&[2, 4, 3, 0, 1, 0, 6, 2, 5, 4], &types, &modifiers);
+
+    assert_eq!(tokens.len(), 2);
+    assert_eq!((tokens[0].line, tokens[0].start), (2, 4));
+    assert_eq!(tokens[0].token_type, "function");
+    assert_eq!(tokens[0].modifiers, vec!["declaration"]);
+    assert_eq!((tokens[1].line, tokens[1].start), (2, 10));
+    assert_eq!(tokens[1].token_type, "type 5");
+    assert_eq!(tokens[1].modifiers, vec!["modifier 2"]);
+}
+
+#[test(tokio::test)]
+async fn test_semantic_tokens_classify_identifiers() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir
+        .child("main.rs")
+        .write_str("// entry\nfn main(mut x: u8) {}\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(&workspace).await?;
+
+    let result = tools::get_semantic_tokens(&client, workspace.join("main.rs"), None, None).await?;
+
+    assert!(
+        result.contains("2:4 `main` function [declaration]"),
+        "{}",
+        result
+    );
+    assert!(
+        result.contains("2:13 `x` parameter [declaration, mutable]"),
+        "{}",
+        result
+    );
+
+    // Lines outside the range are left out
+    let result =
+        tools::get_semantic_tokens(&client, workspace.join("main.rs"), Some(1), Some(1)).await?;
+    assert!(result.starts_with("No semantic tokens"), "{}", result);
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use lsp_types::{Location, Position, Range, Url};
+
+use mcp_language_server_rust::tools::utils::{
+    format_servers, merge_server_locations, merge_server_outputs,
+};
+
+fn location(path: &str, line: u32) -> Location {
+    Location {
+        uri: Url::parse(&format!("file:///ws/{}", path)).unwrap(),
+        range: Range {
+            start: Position { line, character: 4 },
+            end: Position {
+                line,
+                character: 10,
+            },
+        },
+    }
+}
+
+#[test]
+fn test_merge_keeps_each_location_once() {
+    let merged = merge_server_locations(vec![
+        (
+            "gopls".to_string(),
+            vec![location("main.go", 12), location("api/user.pb.go", 40)],
+        ),
+        (
+            "buf".to_string(),
+            vec![location("api/user.proto", 3), location("main.go", 12)],
+        ),
+    ]);
+
+    assert_eq!(
+        merged,
+        vec![
+            (
+                location("main.go", 12),
+                vec!["gopls".to_string(), "buf".to_string()]
+            ),
+            (location("api/user.pb.go", 40), vec!["gopls".to_string()]),
+            (location("api/user.proto", 3), vec!["buf".to_string()]),
+        ]
+    );
+}
+
+#[test]
+fn test_format_servers() {
+    assert_eq!(
+        format_servers(&["gopls".to_string(), "buf".to_string()]),
+        " (via gopls, buf)"
+    );
+}
+
+#[test]
+fn test_merge_outputs_from_one_server_unchanged() {
+    let output = "Incoming calls to parse:\n  main.go\n".to_string();
+    assert_eq!(
+        merge_server_outputs(vec![("gopls".to_string(), output.clone())]),
+        output
+    );
+}
+
+#[test]
+fn test_merge_outputs_names_each_server() {
+    let merged = merge_server_outputs(vec![
+        ("gopls".to_string(), "Found 2 implementations\n".to_string()),
+        ("buf".to_string(), "Found 1 implementation\n".to_string()),
+    ]);
+
+    assert_eq!(
+        merged,
+        "From gopls:\nFound 2 implementations\n\nFrom buf:\nFound 1 implementation"
+    );
+}
+
+#[test]
+fn test_merge_outputs_joins_json_lists() {
+    let merged = merge_server_outputs(vec![
+        ("gopls".to_string(), r#"[{"path":"main.go"}]"#.to_string()),
+        ("buf".to_string(), r#"[{"path":"user.proto"}]"#.to_string()),
+    ]);
+
+    let merged: serde_json::Value = serde_json::from_str(&merged).unwrap();
+    assert_eq!(
+        merged,
+        serde_json::json!([{"path": "main.go"}, {"path": "user.proto"}])
+    );
+}
@@ -0,0 +1,244 @@
+mod common;
+
+use anyhow::{Result, anyhow};
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use serde_json::{Value, json};
+use std::path::Path;
+use test_log::test;
+use tokio::time::{Duration, Instant, sleep};
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools::utils::to_uri;
+
+/// A language server that sends the requests given as its second argument, one
+/// per line, once the client is initialized, and writes the client's answer to
+/// the request with id 900 to the file given as its first
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialized",
+            r#"
+while IFS= read -r line; do
+    send "$line"
+done <<< "$2"
+"#,
+        ),
+        (
+            "",
+            r#"[[ $id == 900 ]] && printf '%s' "$body" > "$1.tmp" && mv "$1.tmp" "$1""#,
+        ),
+    ])
+}
+
+/// Starts a client on the server above, which makes `requests` in order
+async fn start(workspace: &Path, answer: &Path, requests: &[Value]) -> Result<Client> {
+    let requests: Vec<String> = requests.iter().map(Value::to_string).collect();
+    let client = Client::new(
+        "bash",
+        &[
+            "-c".to_string(),
+            server(),
+            "server".to_string(),
+            answer.display().to_string(),
+            requests.join("\n"),
+        ],
+    )
+    .await?;
+    client.initialize(workspace).await?;
+    Ok(client)
+}
+
+/// Waits for the client's answer to the server's request
+async fn answer(path: &Path) -> Result<Value> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if let Ok(text) = std::fs::read_to_string(path) {
+            return Ok(serde_json::from_str(&text)?);
+        }
+        sleep(Duration::from_millis(20)).await;
+    }
+    Err(anyhow!("The client never answered the server's request"))
+}
+
+#[test(tokio::test)]
+async fn test_apply_edit_is_answered_once_applied() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.go").write_str("broke\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let file = workspace.join("main.go");
+    let answer_path = workspace.join("answer.json");
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 900,
+        "method": "workspace/applyEdit",
+        "params": {
+            "label": "fix",
+            "edit": {
+                "changes": {
+                    to_uri(&file).to_string(): [{
+                        "range": {
+                            "start": { "line": 0, "character": 0 },
+                            "end": { "line": 0, "character": 5 },
+                        },
+                        "newText": "fixed",
+                    }],
+                },
+            },
+        },
+    });
+    let client = start(&workspace, &answer_path, &[request]).await?;
+
+    let answer = answer(&answer_path).await?;
+    assert_eq!(answer["result"]["applied"], json!(true), "{}", answer);
+    // The edit is written before the server hears it was applied
+    assert_eq!(std::fs::read_to_string(&file)?, "fixed\n");
+    assert_eq!(client.take_applied_edits().len(), 1);
+
+    client.shutdown().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_apply_edit_reports_failures() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let answer_path = workspace.join("answer.json");
+
+    // Edits to a file that doesn't exist can't be applied
+    let missing = to_uri(&workspace.join("missing.go")).to_string();
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 900,
+        "method": "workspace/applyEdit",
+        "params": {
+            "edit": {
+                "changes": {
+                    missing: [{
+                        "range": {
+                            "start": { "line": 3, "character": 0 },
+                            "end": { "line": 3, "character": 1 },
+                        },
+                        "newText": "x",
+                    }],
+                },
+            },
+        },
+    });
+    let client = start(&workspace, &answer_path, &[request]).await?;
+
+    let answer = answer(&answer_path).await?;
+    assert_eq!(answer["result"]["applied"], json!(false), "{}", answer);
+    assert!(answer["result"]["failureReason"].is_string(), "{}", answer);
+    assert!(client.take_applied_edits().is_empty());
+
+    client.shutdown().await?;
+    Ok(())
+}
+
+fn registration(id: u32, method: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "client/registerCapability",
+        "params": {
+            "registrations": [{
+                "id": "watch",
+                "method": method,
+                "registerOptions": { "watchers": [{ "globPattern": "**/*.go" }] },
+            }],
+        },
+    })
+}
+
+#[test(tokio::test)]
+async fn test_capability_registrations_are_answered_and_tracked() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let answer_path = workspace.join("answer.json");
+
+    let requests = [registration(900, "workspace/didChangeWatchedFiles")];
+    let client = start(&workspace, &answer_path, &requests).await?;
+
+    let answer = answer(&answer_path).await?;
+    assert_eq!(answer["result"], Value::Null, "{}", answer);
+    assert!(answer.get("error").is_none(), "{}", answer);
+    assert_eq!(
+        client.registered_methods(),
+        vec!["workspace/didChangeWatchedFiles".to_string()]
+    );
+
+    client.shutdown().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_capability_unregistrations_are_answered_and_tracked() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let answer_path = workspace.join("answer.json");
+
+    let requests = [
+        registration(901, "workspace/didChangeWatchedFiles"),
+        json!({
+            "jsonrpc": "2.0",
+            "id": 900,
+            "method": "client/unregisterCapability",
+            "params": {
+                "unregisterations": [{
+                    "id": "watch",
+                    "method": "workspace/didChangeWatchedFiles",
+                }],
+            },
+        }),
+    ];
+    let client = start(&workspace, &answer_path, &requests).await?;
+
+    let answer = answer(&answer_path).await?;
+    assert!(answer.get("error").is_none(), "{}", answer);
+    assert!(client.registered_methods().is_empty());
+
+    client.shutdown().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_progress_tokens_created_by_the_server_are_tracked() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let workspace = temp_dir.path().canonicalize()?;
+    let answer_path = workspace.join("answer.json");
+
+    // Servers such as rust-analyzer wait for the token before reporting on it
+    let requests = [
+        json!({
+            "jsonrpc": "2.0",
+            "id": 900,
+            "method": "window/workDoneProgress/create",
+            "params": { "token": "rustAnalyzer/Indexing" },
+        }),
+        json!({
+            "jsonrpc": "2.0",
+            "method": "$/progress",
+            "params": {
+                "token": "rustAnalyzer/Indexing",
+                "value": { "kind": "begin", "title": "Indexing" },
+            },
+        }),
+    ];
+    let client = start(&workspace, &answer_path, &requests).await?;
+
+    let answer = answer(&answer_path).await?;
+    assert!(answer.get("error").is_none(), "{}", answer);
+    assert_eq!(answer["result"], Value::Null, "{}", answer);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while client.pending_work().is_empty() && Instant::now() < deadline {
+        sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(client.pending_work(), vec!["Indexing".to_string()]);
+
+    client.shutdown().await?;
+    Ok(())
+}
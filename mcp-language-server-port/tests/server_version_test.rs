@@ -0,0 +1,50 @@
+use mcp_language_server_rust::lsp::versions::{
+    ServerVersion, Workaround, check_version, parse_version,
+};
+
+#[test]
+fn test_parse_version_output() {
+    assert_eq!(
+        parse_version("clangd version 17.0.6 (https://github.com/llvm/llvm-project 6009708b)"),
+        Some(ServerVersion::new(17, 0, 6))
+    );
+    assert_eq!(
+        parse_version("golang.org/x/tools/gopls v0.15.3\n    golang.org/x/tools/gopls@v0.15.3"),
+        Some(ServerVersion::new(0, 15, 3))
+    );
+    assert_eq!(
+        parse_version("rust-analyzer 0.3.1932-standalone (3d6d9ff 2024-04-21)"),
+        Some(ServerVersion::new(0, 3, 1932))
+    );
+    assert_eq!(
+        parse_version("pyright 1.1"),
+        Some(ServerVersion::new(1, 1, 0))
+    );
+    assert_eq!(parse_version("some-server (build 2024-04-21)"), None);
+}
+
+#[test]
+fn test_versions_compare_numerically() {
+    let older: ServerVersion = "0.9.10".parse().unwrap();
+    let newer: ServerVersion = "0.10.0".parse().unwrap();
+    assert!(older < newer);
+    assert_eq!(newer.to_string(), "0.10.0");
+    assert!("latest".parse::<ServerVersion>().is_err());
+}
+
+#[test]
+fn test_check_version_warns_and_works_around() {
+    let check = check_version("/usr/bin/clangd", ServerVersion::new(8, 0, 1));
+    assert_eq!(check.warnings.len(), 2);
+    assert!(check.warnings[0].contains("older than 9.0.0"));
+    assert_eq!(check.workarounds, vec![Workaround::FlatDocumentSymbols]);
+
+    // Releases with the issue fixed need nothing
+    let check = check_version("clangd", ServerVersion::new(17, 0, 6));
+    assert!(check.warnings.is_empty());
+    assert!(check.workarounds.is_empty());
+
+    // Servers without known requirements are always accepted
+    let check = check_version("my-server", ServerVersion::new(0, 0, 1));
+    assert!(check.warnings.is_empty());
+}
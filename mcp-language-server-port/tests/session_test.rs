@@ -0,0 +1,112 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use lsp_types::{HoverProviderCapability, OneOf, ServerCapabilities};
+use std::sync::Arc;
+use std::time::Duration;
+use test_log::test;
+
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::session::{Session, capability_changes, workspace_key};
+
+#[test(tokio::test)]
+async fn test_session_round_trip() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    let file_path = temp_dir.path().canonicalize()?.join("main.rs");
+    let state_path = temp_dir.path().join("state/session.json");
+
+    let client = Client::new("cat", &[]).await?;
+    client.open_file(&file_path).await?;
+
+    let session = Session::load(state_path.clone());
+    session.record_tool("hover");
+    session.record_tool("hover");
+    session.record_tool("diagnostics");
+    session.capture(&client);
+    session.save()?;
+
+    let restored = Session::load(state_path);
+    let state = restored.state();
+    assert_eq!(state.open_files, vec![file_path.clone()]);
+    assert_eq!(state.tool_usage.get("hover"), Some(&2));
+    assert_eq!(state.tool_usage.get("diagnostics"), Some(&1));
+    assert!(state.saved_at.is_some());
+
+    // A fresh server gets the previous session's files reopened
+    let new_client = Client::new("cat", &[]).await?;
+    restored.restore(&new_client).await;
+    assert!(new_client.is_file_open(&file_path));
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_state_starts_fresh() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("session.json").write_str("not json")?;
+
+    let session = Session::load(temp_dir.path().join("session.json"));
+    assert!(session.state().open_files.is_empty());
+    assert!(session.state().tool_usage.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_workspace_keys_do_not_collide() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("a-b").create_dir_all()?;
+    temp_dir.child("a_b").create_dir_all()?;
+    temp_dir.child("x/a-b").create_dir_all()?;
+
+    let keys = [
+        workspace_key(&temp_dir.path().join("a-b")),
+        workspace_key(&temp_dir.path().join("a_b")),
+        workspace_key(&temp_dir.path().join("x/a-b")),
+    ];
+    assert_ne!(keys[0], keys[1]);
+    assert_ne!(keys[0], keys[2]);
+    assert!(keys[0].starts_with("a_b-"));
+
+    // The same workspace reached through another path shares its key
+    assert_eq!(keys[0], workspace_key(&temp_dir.path().join("x/../a-b")));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_scheduled_saves_are_batched() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let state_path = temp_dir.path().join("session.json");
+    let client = Client::new("cat", &[]).await?;
+
+    let session = Session::load(state_path.clone());
+    for _ in 0..3 {
+        session.record_tool("hover");
+        session.schedule_save(Arc::clone(&client));
+    }
+    assert!(!state_path.exists(), "saving waits for more calls");
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    let restored = Session::load(state_path);
+    assert_eq!(restored.state().tool_usage.get("hover"), Some(&3));
+
+    Ok(())
+}
+
+#[test]
+fn test_capability_changes() {
+    let previous = ServerCapabilities {
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        ..Default::default()
+    };
+    let current = ServerCapabilities {
+        references_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+
+    let (gained, lost) = capability_changes(&previous, &current);
+    assert_eq!(gained, vec!["referencesProvider".to_string()]);
+    assert_eq!(lost, vec!["hoverProvider".to_string()]);
+}
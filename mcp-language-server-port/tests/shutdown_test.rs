@@ -0,0 +1,38 @@
+use anyhow::Result;
+use serde_json::Value;
+use test_log::test;
+use tokio::time::{Duration, sleep, timeout};
+
+use mcp_language_server_rust::lsp::Client;
+
+#[test(tokio::test)]
+async fn test_pending_requests_fail_on_shutdown() -> Result<()> {
+    // `sleep` never answers, so requests stay pending until the client shuts down
+    let client = Client::new("sleep", &["30".to_string()]).await?;
+
+    let pending = {
+        let client = client.clone();
+        tokio::spawn(async move {
+            client
+                .call::<_, Value>("workspace/symbol", serde_json::json!({ "query": "" }))
+                .await
+        })
+    };
+    sleep(Duration::from_millis(50)).await;
+
+    timeout(Duration::from_secs(10), client.shutdown()).await??;
+
+    let error = timeout(Duration::from_secs(1), pending)
+        .await??
+        .expect_err("pending request should fail");
+    assert!(error.to_string().contains("shutting down"));
+
+    // Requests after shutdown fail straight away
+    let error = client
+        .call::<_, Value>("workspace/symbol", Value::Null)
+        .await
+        .expect_err("request after shutdown should fail");
+    assert!(error.to_string().contains("shutting down"));
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use serde_json::json;
+use std::path::PathBuf;
+use test_log::test;
+use tokio::io::BufReader;
+use tokio::net::TcpListener;
+
+use mcp_language_server_rust::lsp::protocol::Message;
+use mcp_language_server_rust::lsp::transport::{read_message, write_message};
+use mcp_language_server_rust::lsp::{Client, InitState, ServerAddress};
+
+#[test]
+fn test_server_addresses_parse() -> Result<()> {
+    assert_eq!(
+        ServerAddress::parse("localhost:5036")?,
+        ServerAddress::Tcp("localhost:5036".to_string())
+    );
+    assert_eq!(
+        ServerAddress::parse("unix:/tmp/lsp.sock")?,
+        ServerAddress::Unix(PathBuf::from("/tmp/lsp.sock"))
+    );
+    assert_eq!(
+        ServerAddress::parse("/tmp/lsp.sock")?,
+        ServerAddress::Unix(PathBuf::from("/tmp/lsp.sock"))
+    );
+    assert!(ServerAddress::parse("localhost").is_err());
+    assert!(ServerAddress::parse("localhost:http").is_err());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_client_connects_to_a_server_over_tcp() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let address = ServerAddress::Tcp(listener.local_addr()?.to_string());
+
+    // A server that answers initialize and records the methods it is sent
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await?;
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut methods = Vec::new();
+        while let Ok(message) = read_message(&mut reader).await {
+            let method = message.method.clone().unwrap_or_default();
+            if method == "initialize"
+                && let Some(id) = message.id
+            {
+                let reply = Message::new_response(id, json!({ "capabilities": {} }))?;
+                write_message(&mut writer, &reply).await?;
+            }
+            methods.push(method);
+            if methods.iter().any(|method| method == "initialized") {
+                break;
+            }
+        }
+        anyhow::Ok(methods)
+    });
+
+    let client = Client::connect("my-server", &address).await?;
+    assert_eq!(client.name(), "my-server");
+    client.initialize(temp_dir.path()).await?;
+    assert_eq!(client.init_state(), InitState::Ready);
+
+    let methods = server.await??;
+    assert_eq!(methods, vec!["initialize", "initialized"]);
+
+    Ok(())
+}
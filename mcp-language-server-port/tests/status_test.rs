@@ -0,0 +1,76 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+use mcp_language_server_rust::tools::render::{OutputStyle, with_style};
+
+/// A language server that answers initialize and shutdown and fails
+/// every other request
+fn server() -> String {
+    scripted_server(&[(
+        "*",
+        r#"[[ -n $id ]] && send "{\"jsonrpc\":\"2.0\",\"id\":$id,\"error\":{\"code\":-32601,\"message\":\"no such method\"}}""#,
+    )])
+}
+
+#[test(tokio::test)]
+async fn test_status_reports_process_documents_and_last_error() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+    let workspace = temp_dir.path().canonicalize()?;
+
+    let client = Client::new("bash", &["-c".to_string(), server()]).await?;
+    client.initialize(&workspace).await?;
+    client.open_file(&workspace.join("main.rs")).await?;
+    assert!(
+        client
+            .call::<_, serde_json::Value>("textDocument/hover", serde_json::Value::Null)
+            .await
+            .is_err()
+    );
+
+    let result = tools::get_status(&[client.clone()]).await?;
+
+    let pid = client.pid().expect("the server process is running");
+    assert!(
+        result.starts_with(&format!("bash: ready (process {}, up ", pid)),
+        "{}",
+        result
+    );
+    assert!(result.contains("  Open documents: 1\n"));
+    assert!(result.contains("  Cached diagnostics: 0\n"));
+    assert!(result.contains("  Pending requests: 0\n"));
+    assert!(result.contains("  Progress: idle\n"));
+    assert!(result.contains("  Last error: textDocument/hover failed: "));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_status_follows_the_output_style() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let workspace = temp_dir.path().canonicalize()?;
+
+    let first = Client::new("bash", &["-c".to_string(), server()]).await?;
+    first.initialize(&workspace).await?;
+    let second = Client::new("bash", &["-c".to_string(), server()]).await?;
+    second.initialize(&workspace).await?;
+    let clients = [first, second];
+
+    let markdown = tools::get_status(&clients).await?;
+    assert!(markdown.contains("\n\nbash: ready"), "{}", markdown);
+
+    // Compact drops the blank line between servers
+    let compact = with_style(Some(OutputStyle::Compact), tools::get_status(&clients)).await?;
+    assert!(!compact.contains("\n\n"), "{}", compact);
+    assert_eq!(compact.matches("bash: ready").count(), 2, "{}", compact);
+    assert_eq!(compact.lines().count(), markdown.lines().count() - 1);
+
+    Ok(())
+}
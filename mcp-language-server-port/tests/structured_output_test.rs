@@ -0,0 +1,123 @@
+use anyhow::Result;
+use lsp_types::{
+    CallHierarchyItem, Diagnostic, DiagnosticSeverity, Location, NumberOrString, Position, Range,
+    SymbolKind,
+};
+use serde_json::json;
+use std::path::Path;
+use test_log::test;
+
+use mcp_language_server_rust::tools::call_hierarchy::Call;
+use mcp_language_server_rust::tools::render::{OutputFormat, current_format, with_format};
+use mcp_language_server_rust::tools::structured::{
+    CallResult, DiagnosticResult, EditsApplied, LocationResult, to_json,
+};
+use mcp_language_server_rust::tools::utils::to_uri;
+use mcp_language_server_rust::tools::workspace_edit::{EditReport, FileReport};
+
+fn range(line: u32, start: u32, end: u32) -> Range {
+    Range::new(Position::new(line, start), Position::new(line, end))
+}
+
+#[test(tokio::test)]
+async fn test_format_applies_within_scope() -> Result<()> {
+    assert_eq!(current_format(), OutputFormat::Text);
+    let format = with_format(Some(OutputFormat::Json), async { current_format() }).await;
+    assert_eq!(format, OutputFormat::Json);
+    assert_eq!(current_format(), OutputFormat::Text);
+
+    assert_eq!(OutputFormat::parse("json")?, OutputFormat::Json);
+    assert!(OutputFormat::parse("yaml").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_results_serialize_one_based() -> Result<()> {
+    let path = Path::new("/workspace/src/main.rs");
+    let location = Location::new(to_uri(path), range(2, 4, 7));
+    let result = LocationResult::from_location(&location)?;
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&to_json(&result)?)?,
+        json!({
+            "path": "/workspace/src/main.rs",
+            "line": 3,
+            "column": 5,
+            "end_line": 3,
+            "end_column": 8,
+        })
+    );
+
+    let diagnostic = Diagnostic {
+        severity: Some(DiagnosticSeverity::ERROR),
+        code: Some(NumberOrString::String("E0425".to_string())),
+        source: Some("rustc".to_string()),
+        ..Diagnostic::new_simple(range(0, 0, 3), "cannot find value".to_string())
+    };
+    let value = serde_json::to_value(DiagnosticResult::new(path, &diagnostic))?;
+    assert_eq!(value["line"], 1);
+    assert_eq!(value["severity"], "error");
+    assert_eq!(value["code"], "E0425");
+    assert_eq!(value["message"], "cannot find value");
+    Ok(())
+}
+
+#[test]
+fn test_edit_report_converts_to_edits_applied() -> Result<()> {
+    let report = EditReport {
+        dry_run: false,
+        files: vec![FileReport {
+            path: "/workspace/src/main.rs".into(),
+            name: "src/main.rs".to_string(),
+            edits: 2,
+            diff: None,
+        }],
+        operations: Vec::new(),
+    };
+    let value = serde_json::to_value(EditsApplied::from(&report))?;
+    assert_eq!(
+        value,
+        json!({
+            "dry_run": false,
+            "edits": 2,
+            "files": [{ "path": "/workspace/src/main.rs", "edits": 2 }],
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_call_result_lists_call_sites() -> Result<()> {
+    let call = Call {
+        item: CallHierarchyItem {
+            name: "load".to_string(),
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            detail: Some(String::new()),
+            uri: to_uri(Path::new("/ws/src/config.rs")),
+            range: range(9, 0, 20),
+            selection_range: range(9, 7, 11),
+            data: None,
+        },
+        call_file: Path::new("/ws/src/main.rs").to_path_buf(),
+        call_ranges: vec![range(2, 4, 8), range(5, 4, 8)],
+    };
+
+    let json = serde_json::to_value(CallResult::new(&call)?)?;
+    assert_eq!(
+        json,
+        json!({
+            "name": "load",
+            "kind": "Function",
+            "path": "/ws/src/config.rs",
+            "line": 10,
+            "column": 8,
+            "end_line": 10,
+            "end_column": 12,
+            "calls": [
+                { "path": "/ws/src/main.rs", "line": 3, "column": 5, "end_line": 3, "end_column": 9 },
+                { "path": "/ws/src/main.rs", "line": 6, "column": 5, "end_line": 6, "end_column": 9 },
+            ],
+        })
+    );
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use std::os::unix::fs::symlink;
+
+use mcp_language_server_rust::workspace::paths::{SymlinkPolicy, normalize_path_with};
+
+#[test]
+fn test_parse_symlink_policy() {
+    assert_eq!(
+        SymlinkPolicy::parse("resolve").unwrap(),
+        SymlinkPolicy::Resolve
+    );
+    assert_eq!(
+        SymlinkPolicy::parse("preserve").unwrap(),
+        SymlinkPolicy::Preserve
+    );
+    assert!(SymlinkPolicy::parse("follow").is_err());
+}
+
+#[test]
+fn test_symlinked_directory_by_policy() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path().canonicalize()?;
+    temp_dir.child("shared/lib.rs").touch()?;
+    temp_dir.child("workspace").create_dir_all()?;
+    symlink(root.join("shared"), root.join("workspace/shared"))?;
+
+    let linked = root.join("workspace/shared/lib.rs");
+
+    // Preserving keeps the file inside the workspace
+    let preserved = normalize_path_with(&linked, SymlinkPolicy::Preserve)?;
+    assert_eq!(preserved, linked);
+
+    // Resolving leads to the real file outside it
+    let resolved = normalize_path_with(&linked, SymlinkPolicy::Resolve)?;
+    assert_eq!(resolved, root.join("shared/lib.rs"));
+
+    Ok(())
+}
+
+#[test]
+fn test_preserve_normalizes_lexically() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path().canonicalize()?;
+
+    let path = root.join("workspace/src/../lib.rs");
+    let preserved = normalize_path_with(&path, SymlinkPolicy::Preserve)?;
+    assert_eq!(preserved, root.join("workspace/lib.rs"));
+
+    Ok(())
+}
+
+#[test]
+fn test_resolve_missing_file_through_parent() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path().canonicalize()?;
+    temp_dir.child("real").create_dir_all()?;
+    symlink(root.join("real"), root.join("link"))?;
+
+    // Removed files resolve like they did while they existed
+    let resolved = normalize_path_with(&root.join("link/removed.rs"), SymlinkPolicy::Resolve)?;
+    assert_eq!(resolved, root.join("real/removed.rs"));
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use lsp_types::{DocumentSymbol, Position, Range, SymbolKind};
+use std::path::PathBuf;
+
+use mcp_language_server_rust::tools::test_anchor::{
+    find_tests_module, identifier_at, insertion_point, test_name, test_stub,
+};
+
+fn range(start_line: u32, end_line: u32) -> Range {
+    Range {
+        start: Position {
+            line: start_line,
+            character: 0,
+        },
+        end: Position {
+            line: end_line,
+            character: 1,
+        },
+    }
+}
+
+#[allow(deprecated)]
+fn symbol(
+    name: &str,
+    kind: SymbolKind,
+    range: Range,
+    children: Vec<DocumentSymbol>,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name: name.to_string(),
+        detail: None,
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range: range,
+        children: Some(children),
+    }
+}
+
+#[test]
+fn test_find_tests_module() {
+    let symbols = vec![
+        symbol("parse", SymbolKind::FUNCTION, range(0, 10), Vec::new()),
+        symbol(
+            "inner",
+            SymbolKind::MODULE,
+            range(12, 40),
+            vec![symbol(
+                "tests",
+                SymbolKind::MODULE,
+                range(30, 39),
+                Vec::new(),
+            )],
+        ),
+    ];
+
+    let module = find_tests_module(&symbols).expect("nested tests module");
+    assert_eq!(module.range.end.line, 39);
+
+    // A function named tests is not a test module
+    let symbols = vec![symbol(
+        "tests",
+        SymbolKind::FUNCTION,
+        range(0, 3),
+        Vec::new(),
+    )];
+    assert!(find_tests_module(&symbols).is_none());
+}
+
+#[test]
+fn test_insertion_point() {
+    // After the last line for languages with free test functions
+    let go = "package parser\n\nimport \"testing\"\n";
+    assert_eq!(insertion_point(go, "go"), (4, 1));
+    assert_eq!(insertion_point("", "py"), (1, 1));
+
+    // Inside the test class for Java
+    let java = "class ParserTest {\n    void testParse() {\n    }\n}\n";
+    assert_eq!(insertion_point(java, "java"), (4, 1));
+}
+
+#[test]
+fn test_test_stub() {
+    let source = "// Package parser reads configs\npackage parser\n\nfunc Parse() {}\n";
+    assert_eq!(
+        test_stub(&PathBuf::from("/ws/parser/parse_test.go"), source),
+        "package parser\n\nimport \"testing\"\n"
+    );
+    assert_eq!(
+        test_stub(&PathBuf::from("/ws/src/ParserTest.java"), ""),
+        "class ParserTest {\n}\n"
+    );
+    assert_eq!(test_stub(&PathBuf::from("/ws/app/test_models.py"), ""), "");
+}
+
+#[test]
+fn test_test_name() {
+    assert_eq!(test_name("parseConfig", "go"), "TestParseConfig");
+    assert_eq!(test_name("parseConfig", "ts"), "testParseConfig");
+    assert_eq!(test_name("parseConfig", "py"), "test_parse_config");
+    assert_eq!(test_name("load_file", "rs"), "test_load_file");
+}
+
+#[test]
+fn test_identifier_at() {
+    let content = "fn main() {}\npub fn load_file(path: &Path) {}\n";
+    assert_eq!(identifier_at(content, 1, 9), Some("load_file".to_string()));
+    assert_eq!(identifier_at(content, 1, 7), Some("load_file".to_string()));
+    assert_eq!(identifier_at(content, 1, 16), Some("load_file".to_string()));
+    assert_eq!(identifier_at(content, 0, 10), None);
+    assert_eq!(identifier_at(content, 5, 0), None);
+}
@@ -0,0 +1,43 @@
+use mcp_language_server_rust::mcp::McpLanguageServer;
+use mcp_language_server_rust::mcp::annotations::tool_annotations;
+
+#[test]
+fn test_tools_are_annotated_by_behavior() {
+    for tool in ["definition", "references", "hover", "diagnostics"] {
+        let annotations = tool_annotations(tool);
+        assert_eq!(annotations.read_only_hint, Some(true), "{}", tool);
+        assert_eq!(annotations.destructive_hint, Some(false), "{}", tool);
+    }
+
+    for tool in ["edit_file", "rename_symbol"] {
+        let annotations = tool_annotations(tool);
+        assert_eq!(annotations.read_only_hint, Some(false), "{}", tool);
+        assert_eq!(annotations.destructive_hint, Some(true), "{}", tool);
+        assert_eq!(annotations.idempotent_hint, Some(false), "{}", tool);
+    }
+
+    // Changing the workspace folders overwrites nothing
+    let annotations = tool_annotations("add_workspace_folder");
+    assert_eq!(annotations.read_only_hint, Some(false));
+    assert_eq!(annotations.destructive_hint, Some(false));
+}
+
+#[test]
+fn test_tools_that_write_are_not_read_only() {
+    // Every tool whose name suggests it changes something is marked so
+    for tool in McpLanguageServer::tool_names() {
+        let writes = [
+            "edit", "apply", "rename", "fix", "organize", "run_", "execute",
+        ]
+        .iter()
+        .any(|verb| tool.contains(verb));
+        if writes {
+            assert_eq!(
+                tool_annotations(&tool).read_only_hint,
+                Some(false),
+                "{}",
+                tool
+            );
+        }
+    }
+}
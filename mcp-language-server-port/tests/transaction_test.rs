@@ -0,0 +1,122 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use std::path::Path;
+use test_log::test;
+
+use mcp_language_server_rust::workspace::transaction::FileTransaction;
+
+/// Lists the files in a directory, to check that no staged file is left over
+fn entries(dir: &Path) -> Result<Vec<String>> {
+    let mut names = std::fs::read_dir(dir)?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+        .collect::<Result<Vec<_>>>()?;
+    names.sort();
+    Ok(names)
+}
+
+#[test(tokio::test)]
+async fn test_commit_writes_every_file() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("a.rs").write_str("fn a() {}\n")?;
+    temp_dir.child("b.rs").write_str("fn b() {}\n")?;
+
+    let mut transaction = FileTransaction::new();
+    transaction.write(
+        temp_dir.path().join("a.rs"),
+        "fn a() {}\n".to_string(),
+        "fn alpha() {}\n".to_string(),
+    );
+    transaction.write(
+        temp_dir.path().join("b.rs"),
+        "fn b() {}\n".to_string(),
+        "fn beta() {}\n".to_string(),
+    );
+    let written = transaction.commit().await?;
+
+    assert_eq!(written.len(), 2);
+    temp_dir.child("a.rs").assert("fn alpha() {}\n");
+    temp_dir.child("b.rs").assert("fn beta() {}\n");
+    assert_eq!(entries(temp_dir.path())?, ["a.rs", "b.rs"]);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_failed_replace_restores_written_files() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("a.rs").write_str("fn a() {}\n")?;
+    // A file can't replace a directory, so the second write fails late
+    temp_dir.child("b.rs").create_dir_all()?;
+
+    let mut transaction = FileTransaction::new();
+    transaction.write(
+        temp_dir.path().join("a.rs"),
+        "fn a() {}\n".to_string(),
+        "fn alpha() {}\n".to_string(),
+    );
+    transaction.write(
+        temp_dir.path().join("b.rs"),
+        String::new(),
+        "fn beta() {}\n".to_string(),
+    );
+    let error = transaction.commit().await.unwrap_err();
+
+    assert!(
+        error
+            .to_string()
+            .contains("1 already written files were restored")
+    );
+    temp_dir.child("a.rs").assert("fn a() {}\n");
+    assert_eq!(entries(temp_dir.path())?, ["a.rs", "b.rs"]);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_failed_staging_changes_nothing() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("a.rs").write_str("fn a() {}\n")?;
+
+    let mut transaction = FileTransaction::new();
+    transaction.write(
+        temp_dir.path().join("a.rs"),
+        "fn a() {}\n".to_string(),
+        "fn alpha() {}\n".to_string(),
+    );
+    transaction.write(
+        temp_dir.path().join("missing").join("b.rs"),
+        String::new(),
+        "fn beta() {}\n".to_string(),
+    );
+    let error = transaction.commit().await.unwrap_err();
+
+    assert!(error.to_string().contains("no files were changed"));
+    temp_dir.child("a.rs").assert("fn a() {}\n");
+    assert_eq!(entries(temp_dir.path())?, ["a.rs"]);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_commit_writes_through_symlinks() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("real.rs").write_str("fn a() {}\n")?;
+    std::os::unix::fs::symlink(
+        temp_dir.path().join("real.rs"),
+        temp_dir.path().join("link.rs"),
+    )?;
+
+    let mut transaction = FileTransaction::new();
+    transaction.write(
+        temp_dir.path().join("link.rs"),
+        "fn a() {}\n".to_string(),
+        "fn alpha() {}\n".to_string(),
+    );
+    transaction.commit().await?;
+
+    assert!(temp_dir.path().join("link.rs").is_symlink());
+    temp_dir.child("real.rs").assert("fn alpha() {}\n");
+
+    Ok(())
+}
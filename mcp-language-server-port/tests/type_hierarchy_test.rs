@@ -0,0 +1,83 @@
+use lsp_types::TypeHierarchyItem;
+use serde_json::json;
+
+use mcp_language_server_rust::tools::type_hierarchy::{
+    TypeHierarchyDirection, TypeNode, format_type_item, format_type_tree,
+};
+
+fn item(name: &str, kind: u32, detail: Option<&str>, line: u32) -> TypeHierarchyItem {
+    let range = json!({
+        "start": { "line": line, "character": 0 },
+        "end": { "line": line, "character": 20 },
+    });
+    serde_json::from_value(json!({
+        "name": name,
+        "kind": kind,
+        "detail": detail,
+        "uri": "file:///ws/src/shapes.rs",
+        "range": range,
+        "selectionRange": range,
+    }))
+    .unwrap()
+}
+
+fn node(item: TypeHierarchyItem, children: Vec<TypeNode>) -> TypeNode {
+    TypeNode { item, children }
+}
+
+fn location(item: &TypeHierarchyItem) -> String {
+    format!("shapes.rs:{}", item.selection_range.start.line + 1)
+}
+
+#[test]
+fn test_parse_direction() {
+    assert_eq!(
+        TypeHierarchyDirection::parse("supertypes").unwrap(),
+        TypeHierarchyDirection::Supertypes
+    );
+    assert_eq!(
+        TypeHierarchyDirection::parse("subtypes").unwrap(),
+        TypeHierarchyDirection::Subtypes
+    );
+    assert_eq!(
+        TypeHierarchyDirection::parse("both").unwrap(),
+        TypeHierarchyDirection::Both
+    );
+    assert!(TypeHierarchyDirection::parse("parents").is_err());
+}
+
+#[test]
+fn test_format_type_item() {
+    // Kinds 11 and 23 are interface and struct
+    assert_eq!(
+        format_type_item(&item("Shape", 11, Some("trait Shape"), 2), &location),
+        "Interface Shape: trait Shape (shapes.rs:3)"
+    );
+    assert_eq!(
+        format_type_item(&item("Circle", 23, None, 10), &location),
+        "Struct Circle (shapes.rs:11)"
+    );
+}
+
+#[test]
+fn test_format_type_tree_indents_each_level() {
+    let tree = vec![
+        node(
+            item("Shape", 11, None, 0),
+            vec![
+                node(
+                    item("Polygon", 11, None, 5),
+                    vec![node(item("Square", 23, None, 9), vec![])],
+                ),
+                node(item("Circle", 23, None, 20), vec![]),
+            ],
+        ),
+        node(item("Debug", 11, None, 30), vec![]),
+    ];
+
+    assert_eq!(
+        format_type_tree(&tree, &location),
+        "  Interface Shape (shapes.rs:1)\n    Interface Polygon (shapes.rs:6)\n      Struct Square (shapes.rs:10)\n    Struct Circle (shapes.rs:21)\n  Interface Debug (shapes.rs:31)\n"
+    );
+    assert_eq!(format_type_tree(&[], &location), "");
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use std::path::Path;
+use std::sync::Arc;
+use test_log::test;
+use tokio::time::{Duration, sleep};
+
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools::utils::to_uri;
+use mcp_language_server_rust::watcher::{FileSystemWatcher, WorkspaceWatcher};
+
+/// Waits until the recorded messages contain `needle`
+async fn wait_for_message(received: &Path, needle: &str) -> bool {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let messages = std::fs::read_to_string(received).unwrap_or_default();
+            if messages.contains(needle) {
+                return;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .is_ok()
+}
+
+#[test(tokio::test)]
+async fn test_watcher_reports_created_and_deleted_files() -> Result<()> {
+    let workspace = TempDir::new()?;
+    workspace.child(".gitignore").write_str("*.log\n")?;
+    let workspace_path = workspace.path().canonicalize()?;
+
+    // A server that records what it receives, kept outside the watched workspace
+    let recorder = TempDir::new()?;
+    let received = recorder.path().join("received");
+    let client = Client::new(
+        "bash",
+        &["-c".to_string(), format!("cat > '{}'", received.display())],
+    )
+    .await?;
+
+    let watcher = FileSystemWatcher::new(Arc::clone(&client), workspace_path.clone());
+    watcher.watch_workspace(workspace_path.clone()).await?;
+    sleep(Duration::from_millis(200)).await;
+
+    // The file was never opened, but the server still hears about it
+    let file_path = workspace_path.join("added.rs");
+    tokio::fs::write(&file_path, "fn added() {}\n").await?;
+    tokio::fs::write(workspace_path.join("build.log"), "ignored\n").await?;
+
+    let uri = to_uri(&file_path);
+    assert!(
+        wait_for_message(&received, &format!(r#""uri":"{}","type":1"#, uri)).await,
+        "no Created event for {}",
+        uri
+    );
+
+    tokio::fs::remove_file(&file_path).await?;
+    assert!(
+        wait_for_message(&received, &format!(r#""uri":"{}","type":3"#, uri)).await,
+        "no Deleted event for {}",
+        uri
+    );
+
+    let messages = std::fs::read_to_string(&received)?;
+    assert!(messages.contains("workspace/didChangeWatchedFiles"));
+    assert!(!messages.contains("build.log"));
+
+    watcher.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_renamed_open_files_move_with_the_file() -> Result<()> {
+    let workspace = TempDir::new()?;
+    workspace.child("old.rs").write_str("fn moved() {}\n")?;
+    let workspace_path = workspace.path().canonicalize()?;
+    let old_path = workspace_path.join("old.rs");
+    let new_path = workspace_path.join("new.rs");
+
+    let recorder = TempDir::new()?;
+    let received = recorder.path().join("received");
+    let client = Client::new(
+        "bash",
+        &["-c".to_string(), format!("cat > '{}'", received.display())],
+    )
+    .await?;
+    client.open_file(&old_path).await?;
+
+    let watcher = FileSystemWatcher::new(Arc::clone(&client), workspace_path.clone());
+    watcher.watch_workspace(workspace_path.clone()).await?;
+    sleep(Duration::from_millis(200)).await;
+
+    tokio::fs::rename(&old_path, &new_path).await?;
+
+    let old_uri = to_uri(&old_path);
+    let new_uri = to_uri(&new_path);
+    assert!(wait_for_message(&received, &format!(r#""uri":"{}","type":3"#, old_uri)).await);
+    assert!(wait_for_message(&received, &format!(r#""uri":"{}","type":1"#, new_uri)).await);
+
+    let moved = tokio::time::timeout(Duration::from_secs(10), async {
+        while !client.is_file_open(&new_path) {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await;
+    assert!(moved.is_ok(), "renamed document was not reopened");
+    assert!(!client.is_file_open(&old_path));
+
+    watcher.stop().await?;
+    Ok(())
+}
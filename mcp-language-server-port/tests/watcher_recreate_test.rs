@@ -0,0 +1,60 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use serial_test::serial;
+use std::sync::Arc;
+use test_log::test;
+use tokio::time::{Duration, sleep};
+
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::watcher::{FileSystemWatcher, WorkspaceWatcher};
+
+/// Sets up a workspace with one open file and a running watcher. The client
+/// talks to `cat`, which is enough for notifications like didOpen/didClose.
+async fn setup_test_env() -> Result<(TempDir, Arc<Client>, FileSystemWatcher)> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("main.rs").write_str("fn main() {}\n")?;
+
+    let client = Client::new("cat", &[]).await?;
+    let workspace = temp_dir.path().canonicalize()?;
+    client.open_file(&workspace.join("main.rs")).await?;
+
+    let watcher = FileSystemWatcher::new(Arc::clone(&client), workspace.clone());
+    watcher.watch_workspace(workspace).await?;
+    sleep(Duration::from_millis(100)).await;
+
+    Ok((temp_dir, client, watcher))
+}
+
+#[test(tokio::test)]
+#[serial]
+async fn test_recreated_file_stays_open() -> Result<()> {
+    let (temp_dir, client, watcher) = setup_test_env().await?;
+    let file_path = temp_dir.path().canonicalize()?.join("main.rs");
+
+    // Save via remove + create, like editors that write a temp file and rename it
+    std::fs::remove_file(&file_path)?;
+    sleep(Duration::from_millis(50)).await;
+    std::fs::write(&file_path, "fn main() { println!(\"hi\"); }\n")?;
+
+    sleep(Duration::from_millis(1000)).await;
+    assert!(client.is_file_open(&file_path));
+
+    watcher.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+#[serial]
+async fn test_removed_file_is_closed() -> Result<()> {
+    let (temp_dir, client, watcher) = setup_test_env().await?;
+    let file_path = temp_dir.path().canonicalize()?.join("main.rs");
+
+    std::fs::remove_file(&file_path)?;
+
+    sleep(Duration::from_millis(1000)).await;
+    assert!(!client.is_file_open(&file_path));
+
+    watcher.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+
+use mcp_language_server_rust::workspace::{check_workspace, project_markers};
+
+#[test]
+fn test_project_markers_by_command() {
+    assert_eq!(project_markers("rust-analyzer"), &["Cargo.toml"]);
+    assert_eq!(
+        project_markers("/usr/local/bin/gopls"),
+        &["go.mod", "go.work"]
+    );
+    assert!(project_markers("some-custom-server").is_empty());
+}
+
+#[test]
+fn test_project_root_has_no_warning() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("Cargo.toml").touch()?;
+
+    let check = check_workspace(temp_dir.path(), "rust-analyzer", false);
+    assert_eq!(check.root, temp_dir.path());
+    assert!(check.warning.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_missing_markers_warn() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("src/lib.rs").touch()?;
+    let workspace = temp_dir.child("src");
+
+    let check = check_workspace(workspace.path(), "rust-analyzer", false);
+    assert_eq!(check.root, workspace.path());
+    assert!(check.warning.unwrap().contains("Cargo.toml"));
+
+    Ok(())
+}
+
+#[test]
+fn test_search_upward_for_project_root() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("go.mod").touch()?;
+    temp_dir.child("cmd/server/main.go").touch()?;
+
+    let check = check_workspace(&temp_dir.path().join("cmd/server"), "gopls", true);
+    assert_eq!(check.root, temp_dir.path().canonicalize()?);
+    assert!(check.warning.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_unknown_server_is_not_checked() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    let check = check_workspace(temp_dir.path(), "my-lsp", true);
+    assert_eq!(check.root, temp_dir.path());
+    assert!(check.warning.is_none());
+
+    Ok(())
+}
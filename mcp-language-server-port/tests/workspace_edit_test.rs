@@ -0,0 +1,203 @@
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use lsp_types::{
+    DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier, Position, Range,
+    TextDocumentEdit, TextEdit, Url, WorkspaceEdit,
+};
+use std::collections::HashMap;
+use std::path::Path;
+use test_log::test;
+
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools::workspace_edit::{self, ApplyOptions};
+
+fn edit(line: u32, start: u32, end: u32, text: &str) -> TextEdit {
+    TextEdit {
+        range: Range::new(Position::new(line, start), Position::new(line, end)),
+        new_text: text.to_string(),
+    }
+}
+
+fn changes(files: Vec<(&Path, Vec<TextEdit>)>) -> WorkspaceEdit {
+    WorkspaceEdit {
+        changes: Some(
+            files
+                .into_iter()
+                .map(|(path, edits)| (Url::from_file_path(path).unwrap(), edits))
+                .collect::<HashMap<_, _>>(),
+        ),
+        ..Default::default()
+    }
+}
+
+#[test(tokio::test)]
+async fn test_edits_apply_in_any_order() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("lib.rs").write_str("let a = b;\n")?;
+    let file_path = temp_dir.path().canonicalize()?.join("lib.rs");
+    let client = Client::new("cat", &[]).await?;
+
+    // Edits listed back to front, plus two inserts at the same position
+    let edits = vec![
+        edit(0, 8, 9, "c"),
+        edit(0, 4, 5, "x"),
+        edit(0, 0, 0, "// one\n"),
+        edit(0, 0, 0, "// two\n"),
+    ];
+    let report = workspace_edit::apply(
+        &client,
+        changes(vec![(&file_path, edits)]),
+        &ApplyOptions::default(),
+    )
+    .await?;
+
+    temp_dir
+        .child("lib.rs")
+        .assert("// one\n// two\nlet x = c;\n");
+    assert_eq!(report.edits(), 4);
+    assert_eq!(report.files.len(), 1);
+    assert_eq!(report.files[0].path, file_path);
+    assert_eq!(report.files[0].edits, 4);
+    assert!(report.files[0].diff.is_none());
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_overlapping_edits_change_nothing() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("a.rs").write_str("fn a() {}\n")?;
+    temp_dir.child("b.rs").write_str("fn b() {}\n")?;
+    let root = temp_dir.path().canonicalize()?;
+    let client = Client::new("cat", &[]).await?;
+
+    let edit = changes(vec![
+        (&root.join("a.rs"), vec![edit(0, 3, 4, "x")]),
+        (
+            &root.join("b.rs"),
+            vec![edit(0, 0, 5, "fn c()"), edit(0, 3, 4, "d")],
+        ),
+    ]);
+    let error = workspace_edit::apply(&client, edit, &ApplyOptions::default())
+        .await
+        .unwrap_err();
+
+    assert!(error.to_string().contains("Overlapping edits"));
+    temp_dir.child("a.rs").assert("fn a() {}\n");
+    temp_dir.child("b.rs").assert("fn b() {}\n");
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_edit_for_outdated_version_is_rejected() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("lib.rs").write_str("fn old() {}\n")?;
+    let file_path = temp_dir.path().canonicalize()?.join("lib.rs");
+    let client = Client::new("cat", &[]).await?;
+    client.open_file(&file_path).await?;
+
+    let versioned = |version| WorkspaceEdit {
+        document_changes: Some(DocumentChanges::Edits(vec![TextDocumentEdit {
+            text_document: OptionalVersionedTextDocumentIdentifier {
+                uri: Url::from_file_path(&file_path).unwrap(),
+                version: Some(version),
+            },
+            edits: vec![OneOf::Left(edit(0, 3, 6, "new"))],
+        }])),
+        ..Default::default()
+    };
+    let current = client.open_documents()[0].version;
+
+    let error = workspace_edit::apply(&client, versioned(current + 5), &ApplyOptions::default())
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("version"));
+    temp_dir.child("lib.rs").assert("fn old() {}\n");
+
+    workspace_edit::apply(&client, versioned(current), &ApplyOptions::default()).await?;
+    temp_dir.child("lib.rs").assert("fn new() {}\n");
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_report_lists_each_file() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("a.rs").write_str("fn a() {}\n")?;
+    temp_dir.child("b.rs").write_str("fn b() {}\n")?;
+    let root = temp_dir.path().canonicalize()?;
+    let client = Client::new("cat", &[]).await?;
+
+    let edit = changes(vec![
+        (&root.join("a.rs"), vec![edit(0, 3, 4, "x")]),
+        (
+            &root.join("b.rs"),
+            vec![edit(0, 0, 0, "// b\n"), edit(0, 3, 4, "y")],
+        ),
+    ]);
+    let report = workspace_edit::apply(
+        &client,
+        edit,
+        &ApplyOptions {
+            dry_run: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    assert!(report.dry_run);
+    assert_eq!(report.edits(), 3);
+    let mut files: Vec<(String, usize)> = report
+        .files
+        .iter()
+        .map(|file| (file.name.clone(), file.edits))
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        vec![
+            (root.join("a.rs").display().to_string(), 1),
+            (root.join("b.rs").display().to_string(), 2),
+        ]
+    );
+    assert!(report.files.iter().all(|file| file.diff.is_some()));
+
+    let text = report.to_string();
+    assert!(text.starts_with("Dry run: would apply 3 edits across 2 files\n"));
+    assert!(text.contains("b.rs (2 edits)\n"));
+    assert!(text.contains("+// b\n+fn y() {}\n"));
+    temp_dir.child("b.rs").assert("fn b() {}\n");
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_show_diff_reports_written_changes() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    temp_dir.child("lib.rs").write_str("fn old() {}\n")?;
+    let long: String = (0..200).map(|i| format!("let x{} = 0;\n", i)).collect();
+    temp_dir.child("long.rs").write_str(&long)?;
+    let root = temp_dir.path().canonicalize()?;
+    let client = Client::new("cat", &[]).await?;
+
+    let edit = changes(vec![
+        (&root.join("lib.rs"), vec![edit(0, 3, 6, "new")]),
+        (&root.join("long.rs"), vec![edit(0, 0, 0, &long)]),
+    ]);
+    let options = ApplyOptions {
+        show_diff: true,
+        ..Default::default()
+    };
+    let text = workspace_edit::apply(&client, edit, &options)
+        .await?
+        .to_string();
+
+    assert!(text.starts_with("Applied 2 edits across 2 files\n"));
+    assert!(text.contains("-fn old() {}\n+fn new() {}\n"));
+    assert!(text.contains("more diff lines\n"));
+    temp_dir.child("lib.rs").assert("fn new() {}\n");
+
+    Ok(())
+}
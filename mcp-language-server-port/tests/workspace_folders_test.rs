@@ -0,0 +1,107 @@
+mod common;
+
+use anyhow::Result;
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+use std::time::Duration;
+use test_log::test;
+
+use crate::common::scripted_server;
+use mcp_language_server_rust::lsp::Client;
+use mcp_language_server_rust::tools;
+
+/// A language server that follows workspace folder changes, writing each
+/// didChangeWorkspaceFolders notification to the file given as its argument
+fn server() -> String {
+    scripted_server(&[
+        (
+            "initialize",
+            r#"reply '{"capabilities":{"workspace":{"workspaceFolders":{"supported":true,"changeNotifications":true}}}}'"#,
+        ),
+        (
+            "workspace/didChangeWorkspaceFolders",
+            r#"printf '%s\n' "$body" >> "$1""#,
+        ),
+    ])
+}
+
+/// Waits until the server has written `count` notifications
+async fn notifications(log: &std::path::Path, count: usize) -> Vec<String> {
+    for _ in 0..50 {
+        let lines: Vec<String> = std::fs::read_to_string(log)
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        if lines.len() >= count {
+            return lines;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Expected {} workspace folder notifications", count);
+}
+
+#[test(tokio::test)]
+async fn test_workspace_folders_are_added_and_removed() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let workspace = temp_dir.child("app");
+    workspace.create_dir_all()?;
+    temp_dir.child("lib").create_dir_all()?;
+    let workspace = workspace.path().canonicalize()?;
+    let lib = temp_dir.path().canonicalize()?.join("lib");
+    let log = temp_dir.path().join("notifications.log");
+
+    let client = Client::new(
+        "bash",
+        &[
+            "-c".to_string(),
+            server(),
+            "server".to_string(),
+            log.display().to_string(),
+        ],
+    )
+    .await?;
+    client.initialize(&workspace).await?;
+    let clients = vec![client];
+
+    // Relative folders are taken from the workspace root
+    let result = tools::add_workspace_folder(&clients, std::path::Path::new("../lib")).await?;
+    assert_eq!(
+        result,
+        format!("Added {} to the workspace of bash", lib.display())
+    );
+    assert_eq!(
+        clients[0].workspace_folders(),
+        vec![workspace.clone(), lib.clone()]
+    );
+
+    let result = tools::add_workspace_folder(&clients, &lib).await?;
+    assert!(result.contains("already a workspace folder"));
+
+    let result = tools::remove_workspace_folder(&clients, &lib).await?;
+    assert_eq!(
+        result,
+        format!("Removed {} from the workspace of bash", lib.display())
+    );
+    assert_eq!(clients[0].workspace_folders(), vec![workspace.clone()]);
+
+    // Each change is sent once, the repeated add not at all
+    let sent = notifications(&log, 2).await;
+    assert_eq!(sent.len(), 2);
+    assert!(sent[0].contains(r#""added":[{"#) && sent[0].contains(r#""removed":[]"#));
+    assert!(sent[1].contains(r#""added":[]"#) && sent[1].contains(r#""removed":[{"#));
+
+    // The root stays, and folders that aren't directories are refused
+    assert!(
+        tools::remove_workspace_folder(&clients, &workspace)
+            .await
+            .is_err()
+    );
+    assert!(
+        tools::add_workspace_folder(&clients, &temp_dir.path().join("missing"))
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}